@@ -0,0 +1,308 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::animals::{spawn_animal, start_animal_hop, AnimalManager, Prey};
+use crate::aoe::{area_tiles, AreaShape};
+use crate::assets::TextureAtlases;
+use crate::combat::{spawn_hostile_projectile, trace_projectile_path, Health};
+use crate::components::{Animal, AnimalAnimation, AnimalNpc, Companion, GameTurn, Player, PlayerAnimation, Position};
+use crate::events::{EntityDamaged, PlayerMoved};
+use crate::faction::Hostile;
+use crate::level::move_player_to;
+use crate::map::TileMap;
+use crate::morale::Morale;
+use crate::rng::GameRng;
+use crate::status::{ApplyStatusEffect, StatusEffect, StatusKind};
+use crate::stealth::{AlertState, Awareness};
+use crate::ui::MessageLog;
+use crate::visibility::line_of_sight;
+
+/// Where monster abilities are defined, under assets/
+pub const ABILITIES_PATH: &str = "data/abilities.json";
+
+/// An effect to put on whatever an ability hits
+#[derive(Debug, Clone, Deserialize)]
+pub struct StatusSpec {
+    pub kind: StatusKind,
+    pub turns: u32,
+    pub potency: i32,
+}
+
+impl StatusSpec {
+    fn effect(&self) -> StatusEffect {
+        StatusEffect::new(self.kind, self.turns, self.potency)
+    }
+}
+
+/// When a monster may use an ability, measured against the player
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Targeting {
+    pub min_range: i32,
+    pub max_range: i32,
+    pub line_of_sight: bool,         // Needs a clear view of the player
+    pub straight_line: bool,         // Only along a row or column
+    pub below_health: Option<f32>,   // Only once hurt down to this share of its health
+    pub chance: f64,                 // Chance each turn it's used when everything else allows it
+    pub while_fleeing: bool,         // Still used once the monster's nerve has gone
+}
+
+impl Default for Targeting {
+    fn default() -> Self {
+        Self {
+            min_range: 1,
+            max_range: 1,
+            line_of_sight: true,
+            straight_line: false,
+            below_health: None,
+            chance: 1.0,
+            while_fleeing: false,
+        }
+    }
+}
+
+/// What an ability does, resolved through the usual combat and status systems
+#[derive(Debug, Clone, Deserialize)]
+pub enum AbilityEffect {
+    // Something flung at the player, which lands on whatever is in the way
+    Projectile { damage: i32, color: [f32; 3], status: Option<StatusSpec> },
+    // A rush along a straight line to the player's side, knocking them back
+    Charge { damage: i32, knockback: i32, status: Option<StatusSpec> },
+    // More monsters scurrying out around this one, up to `limit` of them nearby
+    Summon { monster: String, count: u32, radius: i32, limit: usize, hostile: bool },
+}
+
+/// One monster ability, as read from assets/data/abilities.json
+#[derive(Debug, Clone, Deserialize)]
+pub struct AbilityDef {
+    pub name: String,
+    pub monsters: Vec<String>, // Which monsters have it, by name
+    pub cooldown: u32,         // Turns before it can be used again
+    #[serde(default)]
+    pub targeting: Targeting,
+    pub message: String,       // What the log says, after "The <monster>"
+    pub effect: AbilityEffect,
+}
+
+/// Every monster ability in the game
+#[derive(Resource, Debug, Clone, Default, Deserialize)]
+pub struct MonsterAbilities {
+    pub abilities: Vec<AbilityDef>,
+}
+
+impl MonsterAbilities {
+    // Read the ability table, with no abilities if the file is missing or broken
+    pub fn load() -> Self {
+        let path = Path::new("assets").join(ABILITIES_PATH);
+        let parsed = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| serde_json::from_str::<MonsterAbilities>(&contents).map_err(|e| e.to_string()));
+        parsed.unwrap_or_else(|e| {
+            eprintln!("Could not load monster abilities {}: {}", path.display(), e);
+            Self::default()
+        })
+    }
+
+    pub fn for_monster<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a AbilityDef> {
+        self.abilities.iter().filter(move |ability| ability.monsters.iter().any(|monster| monster.eq_ignore_ascii_case(name)))
+    }
+}
+
+/// The turn each of a monster's abilities is next ready on, by ability name
+#[derive(Component, Debug, Default)]
+pub struct AbilityCooldowns {
+    ready_on: HashMap<String, u32>,
+}
+
+impl AbilityCooldowns {
+    fn ready(&self, ability: &AbilityDef, turn: u32) -> bool {
+        self.ready_on.get(&ability.name).map_or(true, |&ready_on| turn >= ready_on)
+    }
+
+    fn start(&mut self, ability: &AbilityDef, turn: u32) {
+        self.ready_on.insert(ability.name.clone(), turn + ability.cooldown);
+    }
+}
+
+// Whether the targeting rules allow an ability from `from` this turn
+fn in_reach(targeting: &Targeting, map: &TileMap, from: (i32, i32), player: (i32, i32), health: &Health) -> bool {
+    let (dx, dy) = (player.0 - from.0, player.1 - from.1);
+    let distance = dx.abs().max(dy.abs());
+    distance >= targeting.min_range
+        && distance <= targeting.max_range
+        && (!targeting.straight_line || dx == 0 || dy == 0)
+        && targeting.below_health.map_or(true, |share| health.current as f32 <= health.max as f32 * share)
+        && (!targeting.line_of_sight || line_of_sight(map, from, player))
+}
+
+// The tiles a charge crosses from `from` to the player's side, if they're all clear
+fn charge_path(map: &TileMap, from: (i32, i32), player: (i32, i32), occupied: &[(i32, i32)]) -> Option<Vec<(i32, i32)>> {
+    let run_up = (player.0 - from.0).abs().max((player.1 - from.1).abs()) - 1;
+    let path = area_tiles(map, from, AreaShape::Line { toward: player, length: run_up });
+    // Already alongside, cut short by a wall, or someone in the way
+    if path.len() < run_up.max(1) as usize || path.iter().any(|tile| !map.is_position_walkable(tile.0, tile.1) || occupied.contains(tile)) {
+        None
+    } else {
+        Some(path)
+    }
+}
+
+// System to let monsters use their abilities: each checks what it has off cooldown, in reach of the player
+// and passes its roll, then uses the first that does. Runs after the usual moves and attacks each turn
+pub fn use_monster_abilities(
+    mut commands: Commands,
+    abilities: Res<MonsterAbilities>,
+    mut monster_query: Query<(Entity, &Animal, &Position, &Health, Option<&Awareness>, Option<&Morale>, Option<&mut AbilityCooldowns>, &mut AnimalAnimation, &mut TextureAtlasSprite), (With<AnimalNpc>, Without<Companion>, Without<Player>)>,
+    mut player_query: Query<(Entity, &mut Position, &mut Transform, &mut PlayerAnimation, &mut Health), (With<Player>, Without<AnimalNpc>)>,
+    creature_query: Query<(Entity, &Position), (With<Health>, Without<Player>)>,
+    map: Res<TileMap>,
+    animal_manager: Res<AnimalManager>,
+    texture_atlases: Res<TextureAtlases>,
+    game_turn: Res<GameTurn>,
+    mut game_rng: ResMut<GameRng>,
+    mut message_log: ResMut<MessageLog>,
+    mut damage_events: EventWriter<EntityDamaged>,
+    mut status_events: EventWriter<ApplyStatusEffect>,
+    mut moved_events: EventWriter<PlayerMoved>,
+    mut local: Local<u32>,
+) {
+    // Only act once per turn
+    if game_turn.current_turn == 0 || game_turn.current_turn == *local {
+        return;
+    }
+    *local = game_turn.current_turn;
+    let turn = game_turn.current_turn;
+
+    let (player_entity, mut player_pos, mut player_transform, mut player_animation, mut player_health) =
+        if let Ok(player) = player_query.get_single_mut() { player } else { return; };
+    let mut occupied: Vec<(i32, i32)> = creature_query.iter().map(|(_, position)| (position.x, position.y)).collect();
+    // Every living monster by kind, so summoners don't flood the level
+    let mut monsters: Vec<(&'static str, (i32, i32))> = monster_query.iter()
+        .filter(|(_, _, _, health, ..)| !health.is_dead())
+        .map(|(_, animal, position, ..)| (animal.animal_type.get_name(), (position.x, position.y)))
+        .collect();
+
+    for (entity, animal, position, health, awareness, morale, cooldowns, mut animation, mut sprite) in monster_query.iter_mut() {
+        if health.is_dead() || player_health.is_dead() {
+            continue;
+        }
+        // Monsters that can be snuck past only use abilities once they're onto the player
+        if awareness.map_or(false, |awareness| awareness.state != AlertState::Alert) {
+            continue;
+        }
+        let fleeing = morale.map_or(false, |morale| morale.fleeing);
+        let name = animal.animal_type.get_name();
+        let here = (position.x, position.y);
+        let player_tile = (player_pos.x, player_pos.y);
+
+        let ability = abilities.for_monster(name).find(|ability| {
+            cooldowns.as_ref().map_or(true, |cooldowns| cooldowns.ready(ability, turn))
+                && (!fleeing || ability.targeting.while_fleeing)
+                && in_reach(&ability.targeting, &map, here, player_tile, health)
+        });
+        let ability = if let Some(ability) = ability { ability } else { continue; };
+        if !game_rng.combat.gen_bool(ability.targeting.chance.clamp(0.0, 1.0)) {
+            continue;
+        }
+
+        let used = match &ability.effect {
+            AbilityEffect::Projectile { damage, color, status } => {
+                let creatures: Vec<(Entity, (i32, i32))> = creature_query.iter()
+                    .filter(|(other, _)| *other != entity)
+                    .map(|(other, position)| (other, (position.x, position.y)))
+                    .chain(std::iter::once((player_entity, player_tile)))
+                    .collect();
+                let (path, hit) = trace_projectile_path(&map, here, player_tile, ability.targeting.max_range, &creatures);
+                if path.is_empty() {
+                    false
+                } else {
+                    let source = format!("the {}'s {}", name.to_lowercase(), ability.name);
+                    let color = Color::rgb(color[0], color[1], color[2]);
+                    spawn_hostile_projectile(&mut commands, here, path, *damage, hit, color, source, status.as_ref().map(StatusSpec::effect));
+                    true
+                }
+            }
+            AbilityEffect::Charge { damage, knockback, status } => {
+                if let Some(path) = charge_path(&map, here, player_tile, &occupied) {
+                    // The monster ends up beside the player
+                    let landing = path.last().copied().unwrap_or(here);
+                    occupied.retain(|&tile| tile != here);
+                    occupied.push(landing);
+                    let landing_pos = Position::new(landing.0, landing.1);
+                    start_animal_hop(&mut animation, &mut sprite, *position, landing_pos);
+                    commands.entity(entity).insert(landing_pos);
+
+                    player_health.take_damage(*damage);
+                    damage_events.send(EntityDamaged { target: player_entity, amount: *damage, source: format!("a {}", name) });
+                    if let Some(status) = status {
+                        status_events.send(ApplyStatusEffect { target: player_entity, effect: status.effect() });
+                    }
+
+                    // Then the player is thrown back along the line of the charge, as far as the floor allows
+                    let step = ((player_tile.0 - here.0).signum(), (player_tile.1 - here.1).signum());
+                    let mut thrown_to = player_tile;
+                    for _ in 0..*knockback {
+                        let next = (thrown_to.0 + step.0, thrown_to.1 + step.1);
+                        if !map.is_position_walkable(next.0, next.1) || occupied.contains(&next) {
+                            break;
+                        }
+                        thrown_to = next;
+                    }
+                    if thrown_to != player_tile {
+                        player_animation.is_moving = false;
+                        player_animation.move_buffer.clear();
+                        move_player_to(&mut player_transform, &mut player_pos, (thrown_to.0 as usize, thrown_to.1 as usize));
+                        // Overrides the Position a queued step may have just inserted
+                        commands.entity(player_entity).insert(*player_pos);
+                        moved_events.send(PlayerMoved { x: thrown_to.0, y: thrown_to.1 });
+                    }
+                    true
+                } else {
+                    false
+                }
+            }
+            AbilityEffect::Summon { monster, count, radius, limit, hostile } => {
+                let spawn_data = animal_manager.spawn_data_for(monster);
+                let nearby = monsters.iter()
+                    .filter(|(kind, tile)| kind.eq_ignore_ascii_case(monster) && (tile.0 - here.0).abs().max((tile.1 - here.1).abs()) <= *radius * 2)
+                    .count();
+                match spawn_data {
+                    Some(spawn_data) if nearby < *limit => {
+                        let mut spots: Vec<(i32, i32)> = area_tiles(&map, here, AreaShape::Circle { radius: *radius })
+                            .into_iter()
+                            .filter(|&tile| tile != player_tile && map.is_position_walkable(tile.0, tile.1) && !occupied.contains(&tile))
+                            .collect();
+                        let spawned = (*count as usize).min(*limit - nearby).min(spots.len());
+                        for _ in 0..spawned {
+                            let tile = spots.swap_remove(game_rng.spawns.gen_range(0..spots.len()));
+                            occupied.push(tile);
+                            monsters.push((spawn_data.animal_type.get_name(), tile));
+                            let summoned = spawn_animal(&mut commands, &map, &texture_atlases, &spawn_data, tile);
+                            if *hostile {
+                                commands.entity(summoned).insert(Hostile).remove::<Prey>();
+                            }
+                        }
+                        spawned > 0
+                    }
+                    _ => false,
+                }
+            }
+        };
+        if !used {
+            continue;
+        }
+
+        message_log.add_message(format!("The {} {}", name.to_lowercase(), ability.message));
+        if let Some(mut cooldowns) = cooldowns {
+            cooldowns.start(ability, turn);
+        } else {
+            let mut cooldowns = AbilityCooldowns::default();
+            cooldowns.start(ability, turn);
+            commands.entity(entity).insert(cooldowns);
+        }
+    }
+}
@@ -0,0 +1,289 @@
+use bevy::prelude::*;
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use crate::combat::Health;
+use crate::components::{GameTurn, Npc, Player};
+use crate::conversation::Conversation;
+use crate::events::{CreatureKilled, EntityDamaged, ItemPickedUp, LevelChanged, MovementBlocked, PlayerMoved};
+use crate::gold::GoldCollected;
+use crate::identify::ItemAppearances;
+use crate::interaction::InteractionMenu;
+use crate::inventory::Inventory;
+use crate::inventory_panel::InventoryMenu;
+use crate::map::{TileMap, TileType};
+use crate::ui::MessageLog;
+use crate::GameState;
+
+/// One thing that happened on screen, written as a line of JSON
+#[derive(Debug, Serialize)]
+struct Announcement<'a> {
+    turn: u32,
+    kind: &'a str,
+    text: &'a str,
+}
+
+/// Text mode for screen readers and other tools: everything that happens on screen is mirrored as
+/// one JSON object per line. Turned on with `--accessible`; `--accessible-log <path>` writes to a file instead of stdout
+#[derive(Resource, Default)]
+pub struct AccessibilityLog {
+    pub enabled: bool,
+    file: Option<File>,
+}
+
+impl AccessibilityLog {
+    pub fn from_args() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+        let log_path = args.iter().position(|arg| arg == "--accessible-log").and_then(|index| args.get(index + 1));
+        if !args.iter().any(|arg| arg == "--accessible") && log_path.is_none() {
+            return Self::default();
+        }
+
+        let file = log_path.and_then(|path| {
+            match OpenOptions::new().create(true).append(true).open(Path::new(path)) {
+                Ok(file) => Some(file),
+                Err(e) => {
+                    eprintln!("Could not open accessibility log {}, writing to stdout: {}", path, e);
+                    None
+                }
+            }
+        });
+        Self { enabled: true, file }
+    }
+
+    pub fn announce(&mut self, turn: u32, kind: &str, text: &str) {
+        let line = match serde_json::to_string(&Announcement { turn, kind, text }) {
+            Ok(line) => line,
+            Err(e) => {
+                eprintln!("Could not write announcement: {}", e);
+                return;
+            }
+        };
+        match self.file.as_mut() {
+            Some(file) => {
+                if let Err(e) = writeln!(file, "{}", line) {
+                    eprintln!("Could not write accessibility log: {}", e);
+                }
+            }
+            None => println!("{}", line),
+        }
+    }
+}
+
+// Run condition for the systems below
+pub fn accessibility_enabled(log: Res<AccessibilityLog>) -> bool {
+    log.enabled
+}
+
+// What a tile the player walked into is, for saying why they couldn't
+fn describe_obstacle(map: &TileMap, x: i32, y: i32) -> &'static str {
+    if !map.in_bounds(x, y) {
+        return "the edge of the map";
+    }
+    if map.prop_blocks(x, y) {
+        return "something heavy";
+    }
+    match map.tiles[y as usize][x as usize] {
+        TileType::Door => "a door (E to open)",
+        TileType::Wall | TileType::SecretDoor => "a wall",
+        _ => "something",
+    }
+}
+
+// System to announce movement, damage, pickups and the other gameplay events
+pub fn announce_game_events(
+    mut log: ResMut<AccessibilityLog>,
+    game_turn: Res<GameTurn>,
+    map: Res<TileMap>,
+    appearances: Res<ItemAppearances>,
+    names: Query<(Option<&Npc>, Option<&Player>, Option<&Health>)>,
+    mut moved_events: EventReader<PlayerMoved>,
+    mut blocked_events: EventReader<MovementBlocked>,
+    mut damage_events: EventReader<EntityDamaged>,
+    mut item_events: EventReader<ItemPickedUp>,
+    mut gold_events: EventReader<GoldCollected>,
+    mut level_events: EventReader<LevelChanged>,
+    mut kill_events: EventReader<CreatureKilled>,
+    mut last_blocked: Local<Option<(i32, i32)>>,
+) {
+    let turn = game_turn.current_turn;
+    for event in moved_events.read() {
+        *last_blocked = None;
+        log.announce(turn, "moved", &format!("({}, {})", event.x, event.y));
+    }
+    for event in blocked_events.read() {
+        // Holding a direction against a wall would otherwise repeat every frame
+        if *last_blocked == Some((event.x, event.y)) {
+            continue;
+        }
+        *last_blocked = Some((event.x, event.y));
+        log.announce(turn, "blocked", &format!("Blocked by {}", describe_obstacle(&map, event.x, event.y)));
+    }
+    for event in damage_events.read() {
+        let text = match names.get(event.target) {
+            Ok((_, Some(_), health)) => {
+                let left = health.map_or(String::new(), |health| format!(", {} of {} left", health.current.max(0), health.max));
+                format!("You take {} damage from {}{}", event.amount, event.source, left)
+            }
+            Ok((Some(npc), None, _)) => format!("{} takes {} damage from {}", npc.name, event.amount, event.source),
+            _ => format!("Something takes {} damage from {}", event.amount, event.source),
+        };
+        log.announce(turn, "damage", &text);
+    }
+    for event in item_events.read() {
+        log.announce(turn, "item", &format!("Picked up {}", appearances.display_name(event.item)));
+    }
+    for event in gold_events.read() {
+        log.announce(turn, "gold", &format!("Picked up {} gold", event.amount));
+    }
+    for event in level_events.read() {
+        log.announce(turn, "level", &format!("Depth {}", event.to + 1));
+    }
+    for event in kill_events.read() {
+        log.announce(turn, "killed", &format!("Killed {}", event.name));
+    }
+}
+
+// System to mirror the message log
+pub fn announce_messages(
+    mut log: ResMut<AccessibilityLog>,
+    game_turn: Res<GameTurn>,
+    message_log: Res<MessageLog>,
+    mut seen: Local<usize>,
+) {
+    let new = message_log.total().saturating_sub(*seen);
+    *seen = message_log.total();
+    for message in message_log.recent(new) {
+        log.announce(game_turn.current_turn, "message", message);
+    }
+}
+
+// System to read out NPC speech and the responses on offer
+pub fn announce_conversation(
+    mut log: ResMut<AccessibilityLog>,
+    game_turn: Res<GameTurn>,
+    conversation: Res<Conversation>,
+    mut spoken: Local<(Option<Entity>, usize)>,
+    mut offered: Local<Option<(usize, usize)>>, // (choices, selected)
+) {
+    if !conversation.is_changed() {
+        return;
+    }
+    let turn = game_turn.current_turn;
+
+    // A new conversation starts from the top of its history
+    if spoken.0 != conversation.speaker || conversation.history.len() < spoken.1 {
+        *spoken = (conversation.speaker, 0);
+        *offered = None;
+    }
+    for line in conversation.history.iter().skip(spoken.1) {
+        log.announce(turn, "speech", &format!("{}: {}", conversation.name, line));
+    }
+    spoken.1 = conversation.history.len();
+
+    if !conversation.awaiting_choice() {
+        *offered = None;
+        return;
+    }
+    let current = (conversation.choices.len(), conversation.selected);
+    match *offered {
+        Some((count, selected)) if count == current.0 => {
+            if selected != current.1 {
+                if let Some(choice) = conversation.choices.get(conversation.selected) {
+                    log.announce(turn, "selected", &choice.text);
+                }
+            }
+        }
+        _ => {
+            let choices: Vec<String> = conversation.choices.iter()
+                .enumerate()
+                .map(|(index, choice)| format!("{}. {}", index + 1, choice.text))
+                .collect();
+            log.announce(turn, "choices", &choices.join(" | "));
+        }
+    }
+    *offered = Some(current);
+}
+
+// System to read out the interaction and inventory menus as they open and the selection moves
+pub fn announce_menus(
+    mut log: ResMut<AccessibilityLog>,
+    game_turn: Res<GameTurn>,
+    interaction_menu: Res<InteractionMenu>,
+    inventory_menu: Res<InventoryMenu>,
+    appearances: Res<ItemAppearances>,
+    inventory_query: Query<&Inventory, With<Player>>,
+    mut last_interaction: Local<Option<(usize, usize)>>, // (options, selected)
+    mut last_inventory: Local<Option<usize>>,
+) {
+    let turn = game_turn.current_turn;
+
+    if interaction_menu.is_changed() {
+        let current = interaction_menu.is_open().then(|| (interaction_menu.options.len(), interaction_menu.selected));
+        match (*last_interaction, current) {
+            (_, None) => {}
+            (Some((count, selected)), Some((new_count, new_selected))) if count == new_count => {
+                if selected != new_selected {
+                    log.announce(turn, "selected", &interaction_menu.options[new_selected].1);
+                }
+            }
+            _ => {
+                let options: Vec<String> = interaction_menu.options.iter()
+                    .enumerate()
+                    .map(|(index, (_, label))| format!("{}. {}", index + 1, label))
+                    .collect();
+                log.announce(turn, "menu", &format!("Use what? {}", options.join(" | ")));
+            }
+        }
+        *last_interaction = current;
+    }
+
+    if inventory_menu.is_changed() {
+        let stacks = inventory_query.get_single().map(|inventory| inventory.stacks()).unwrap_or_default();
+        let describe = |index: usize| {
+            stacks.get(index).map_or("nothing".to_string(), |(item, count)| format!("{} x{}", appearances.display_name(*item), count))
+        };
+        let current = inventory_menu.open.then_some(inventory_menu.selected);
+        match (*last_inventory, current) {
+            (_, None) => {
+                if last_inventory.is_some() {
+                    log.announce(turn, "menu", "Inventory closed");
+                }
+            }
+            (None, Some(_)) => {
+                let items: Vec<String> = (0..stacks.len()).map(describe).collect();
+                let listing = if items.is_empty() { "empty".to_string() } else { items.join(" | ") };
+                log.announce(turn, "menu", &format!("Inventory: {} (E use, X throw, I close)", listing));
+            }
+            (Some(selected), Some(new_selected)) => {
+                if selected != new_selected {
+                    log.announce(turn, "selected", &describe(new_selected));
+                }
+            }
+        }
+        *last_inventory = current;
+    }
+}
+
+// System to read out the menu screens outside a run as their text appears
+pub fn announce_screens(
+    mut log: ResMut<AccessibilityLog>,
+    game_turn: Res<GameTurn>,
+    state: Res<State<GameState>>,
+    new_text: Query<&Text, (Added<Text>, With<Node>)>,
+) {
+    if state.is_changed() {
+        log.announce(game_turn.current_turn, "screen", &format!("{:?}", state.get()));
+    }
+    if *state.get() == GameState::InGame {
+        return;
+    }
+    for text in new_text.iter() {
+        let content: String = text.sections.iter().map(|section| section.value.as_str()).collect();
+        if !content.trim().is_empty() {
+            log.announce(game_turn.current_turn, "text", content.trim());
+        }
+    }
+}
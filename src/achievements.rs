@@ -0,0 +1,213 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+
+use crate::events::{AnimalTamed, LevelChanged, PlayerAttacked, SecretDoorFound};
+use crate::map::TileMap;
+use crate::run_summary::{RunEnded, RunOutcome};
+
+/// File unlocked achievements and progress toward them are kept in, across runs
+pub const ACHIEVEMENTS_PATH: &str = "achievements.json";
+// Zero-based level that counts as depth 10
+const DEEP_DIVER_LEVEL: usize = 9;
+const SECRET_DOORS_NEEDED: u32 = 5;
+// Seconds an unlock toast stays on screen
+const TOAST_SECONDS: f32 = 4.0;
+
+/// Everything that can be unlocked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Achievement {
+    DeepDiver,    // Reach depth 10
+    BeastFriend,  // Tame an animal
+    SecretSeeker, // Find 5 secret doors
+    Pacifist,     // Escape without attacking anything
+}
+
+impl Achievement {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Achievement::DeepDiver => "Deep Diver",
+            Achievement::BeastFriend => "Beast Friend",
+            Achievement::SecretSeeker => "Secret Seeker",
+            Achievement::Pacifist => "Pacifist",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Achievement::DeepDiver => "Reach depth 10",
+            Achievement::BeastFriend => "Tame an animal",
+            Achievement::SecretSeeker => "Find 5 secret doors",
+            Achievement::Pacifist => "Finish a run without attacking",
+        }
+    }
+}
+
+/// Unlocked achievements and the counters behind them
+#[derive(Resource, Debug, Default, Serialize, Deserialize)]
+pub struct Achievements {
+    pub unlocked: Vec<Achievement>,
+    pub secret_doors_found: u32,
+    #[serde(skip)]
+    attacked_this_run: bool,
+    #[serde(skip)]
+    doors_seen: HashSet<(u64, i32, i32)>, // Level seed and tile, so a door searched twice counts once
+}
+
+impl Achievements {
+    // Read saved progress, starting fresh if there's none or it can't be read
+    pub fn load() -> Self {
+        match fs::read_to_string(ACHIEVEMENTS_PATH) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                eprintln!("Ignoring unreadable {}: {}", ACHIEVEMENTS_PATH, e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let contents = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(ACHIEVEMENTS_PATH, contents).map_err(|e| format!("could not write {}: {}", ACHIEVEMENTS_PATH, e))
+    }
+
+    pub fn is_unlocked(&self, achievement: Achievement) -> bool {
+        self.unlocked.contains(&achievement)
+    }
+
+    // Returns true if this is a new unlock
+    fn unlock(&mut self, achievement: Achievement) -> bool {
+        if self.is_unlocked(achievement) {
+            return false;
+        }
+        self.unlocked.push(achievement);
+        true
+    }
+}
+
+/// Sent the moment an achievement is earned
+#[derive(Event, Debug, Clone, Copy)]
+pub struct AchievementUnlocked(pub Achievement);
+
+/// A pop-up announcing an unlock
+#[derive(Component)]
+pub struct AchievementToast {
+    timer: Timer,
+}
+
+// Start each run with a clean record of attacks
+pub fn reset_run_achievements(mut achievements: ResMut<Achievements>) {
+    achievements.attacked_this_run = false;
+    achievements.doors_seen.clear();
+}
+
+// System to check the gameplay events against every achievement
+pub fn evaluate_achievements_system(
+    mut level_events: EventReader<LevelChanged>,
+    mut tamed_events: EventReader<AnimalTamed>,
+    mut door_events: EventReader<SecretDoorFound>,
+    mut attack_events: EventReader<PlayerAttacked>,
+    mut run_ended: EventReader<RunEnded>,
+    map: Res<TileMap>,
+    mut achievements: ResMut<Achievements>,
+    mut unlocked_events: EventWriter<AchievementUnlocked>,
+) {
+    let mut earned = Vec::new();
+    let mut progressed = false;
+
+    if attack_events.read().count() > 0 {
+        achievements.attacked_this_run = true;
+    }
+    if level_events.read().any(|event| event.to >= DEEP_DIVER_LEVEL) {
+        earned.push(Achievement::DeepDiver);
+    }
+    if tamed_events.read().count() > 0 {
+        earned.push(Achievement::BeastFriend);
+    }
+    for door in door_events.read() {
+        if achievements.doors_seen.insert((map.seed, door.x, door.y)) {
+            achievements.secret_doors_found += 1;
+            progressed = true;
+        }
+    }
+    if achievements.secret_doors_found >= SECRET_DOORS_NEEDED {
+        earned.push(Achievement::SecretSeeker);
+    }
+    let escaped = run_ended.read().any(|event| event.outcome == RunOutcome::Escaped);
+    if escaped && !achievements.attacked_this_run {
+        earned.push(Achievement::Pacifist);
+    }
+
+    for achievement in earned {
+        if achievements.unlock(achievement) {
+            println!("Achievement unlocked: {}", achievement.get_name());
+            unlocked_events.send(AchievementUnlocked(achievement));
+            progressed = true;
+        }
+    }
+
+    if progressed {
+        if let Err(e) = achievements.save() {
+            eprintln!("Could not save achievements: {}", e);
+        }
+    }
+}
+
+// System to pop up a toast for each new unlock
+pub fn show_achievement_toasts(
+    mut commands: Commands,
+    mut unlocked_events: EventReader<AchievementUnlocked>,
+    asset_server: Res<AssetServer>,
+    toast_query: Query<(), With<AchievementToast>>,
+) {
+    let font = asset_server.load("fonts/FiraSans-Light.ttf");
+    // Stack new toasts under any still showing
+    let mut slot = toast_query.iter().count();
+
+    for AchievementUnlocked(achievement) in unlocked_events.read() {
+        commands.spawn((
+            TextBundle::from_sections([
+                TextSection::new(
+                    format!("Achievement unlocked: {}\n", achievement.get_name()),
+                    TextStyle { font: font.clone(), font_size: 20.0, color: Color::GOLD },
+                ),
+                TextSection::new(
+                    achievement.description(),
+                    TextStyle { font: font.clone(), font_size: 16.0, color: Color::WHITE },
+                ),
+            ])
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(48.0 + slot as f32 * 56.0),
+                right: Val::Px(16.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            })
+            .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.85)),
+            AchievementToast { timer: Timer::from_seconds(TOAST_SECONDS, TimerMode::Once) },
+        ))
+        .insert(ZIndex::Global(300));
+        slot += 1;
+    }
+}
+
+// System to fade toasts out and remove them once their time is up
+pub fn update_achievement_toasts(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut toast_query: Query<(Entity, &mut AchievementToast, &mut Text)>,
+) {
+    for (entity, mut toast, mut text) in toast_query.iter_mut() {
+        toast.timer.tick(time.delta());
+        if toast.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        // Fade over the last second
+        let alpha = (toast.timer.remaining_secs()).min(1.0);
+        for section in text.sections.iter_mut() {
+            section.style.color.set_a(alpha);
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+
+use crate::animals::{spawn_animal, AnimalManager, AnimalSpawnData};
+use crate::assets::{get_tile_sprite, SpriteAssets, TextureAtlases};
+use crate::biome::BiomeType;
+use crate::combat::Health;
+use crate::components::{AnimalType, GameTurn, Player, Position};
+use crate::input::TILE_SIZE;
+use crate::interaction::{Interactable, InteractedWith, InteractionKind};
+use crate::lighting::LightSource;
+use crate::map::{TileMap, TileType};
+use crate::rng::GameRng;
+use crate::status::{ApplyStatusEffect, StatusEffect, StatusKind};
+use crate::ui::MessageLog;
+
+// Altars sit with the props, above the floor and below creatures
+const ALTAR_Z: f32 = 2.0;
+// How far the deity's favor can swing either way
+const MAX_FAVOR: i32 = 5;
+const REGENERATION_TURNS: u32 = 5;
+const BLESSING_TURNS: u32 = 40;
+const BLESSING_DAMAGE: i32 = 2;
+const CURSE_TURNS: u32 = 30;
+const CURSE_DAMAGE: i32 = 1;
+
+/// How kindly whatever listens at the altars regards the player this run. Never
+/// shown; it tips the odds of what a prayer brings
+#[derive(Resource, Debug, Default)]
+pub struct DeityFavor(pub i32);
+
+impl DeityFavor {
+    fn shift(&mut self, amount: i32) {
+        self.0 = (self.0 + amount).clamp(-MAX_FAVOR, MAX_FAVOR);
+    }
+}
+
+/// An altar in a shrine; each answers one prayer
+#[derive(Component)]
+pub struct Altar {
+    pub answered: bool,
+}
+
+/// What can come of praying at an altar
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrayerOutcome {
+    Heal,
+    BlessWeapon,
+    Curse,
+    SummonGuardian,
+}
+
+impl PrayerOutcome {
+    const ALL: [PrayerOutcome; 4] = [PrayerOutcome::Heal, PrayerOutcome::BlessWeapon, PrayerOutcome::Curse, PrayerOutcome::SummonGuardian];
+
+    // How likely this outcome is at an altar in a biome, given the deity's favor
+    fn weight(&self, biome: BiomeType, favor: i32) -> i32 {
+        let weight = match self {
+            PrayerOutcome::Heal => 4 + favor + if biome == BiomeType::Groves { 2 } else { 0 },
+            PrayerOutcome::BlessWeapon => 3 + favor + if biome == BiomeType::Caves { 1 } else { 0 },
+            PrayerOutcome::Curse => 2 - favor + if biome == BiomeType::Catacombs { 2 } else { 0 },
+            PrayerOutcome::SummonGuardian => 1 - favor + match biome {
+                BiomeType::Labyrinth => 2,
+                BiomeType::Catacombs => 1,
+                _ => 0,
+            },
+        };
+        weight.max(0)
+    }
+
+    // Good fortune uses up favor; bad fortune pays some back
+    fn favor_change(&self) -> i32 {
+        match self {
+            PrayerOutcome::Heal => -1,
+            PrayerOutcome::BlessWeapon => -2,
+            PrayerOutcome::Curse => 2,
+            PrayerOutcome::SummonGuardian => 1,
+        }
+    }
+}
+
+// The creature that answers a prayer in anger, in each biome
+fn guardian_for(biome: BiomeType) -> AnimalType {
+    match biome {
+        BiomeType::Caves => AnimalType::BlackBear,
+        BiomeType::Groves => AnimalType::GrizzlyBear,
+        BiomeType::Labyrinth => AnimalType::Dog,
+        BiomeType::Catacombs => AnimalType::Honeybadger,
+    }
+}
+
+fn tile_center(tile: (usize, usize), z: f32) -> Vec3 {
+    Vec3::new(tile.0 as f32 * TILE_SIZE + TILE_SIZE / 2.0, tile.1 as f32 * TILE_SIZE + TILE_SIZE / 2.0, z)
+}
+
+// System to start each run with the deity indifferent
+pub fn reset_deity_favor(mut favor: ResMut<DeityFavor>) {
+    favor.0 = 0;
+}
+
+// System to put an altar in every shrine whenever a different level is loaded
+pub fn sync_altars(
+    mut commands: Commands,
+    map: Res<TileMap>,
+    texture_atlases: Res<TextureAtlases>,
+    sprite_assets: Res<SpriteAssets>,
+    altar_query: Query<Entity, With<Altar>>,
+) {
+    for entity in altar_query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    // There's no altar sprite, so a pale stone with a glow stands in for one
+    let index = get_tile_sprite(&sprite_assets, "large rock 1");
+    for &tile in &map.altar_positions {
+        commands.spawn((
+            SpriteSheetBundle {
+                texture_atlas: texture_atlases.tiles.clone(),
+                sprite: TextureAtlasSprite {
+                    index,
+                    color: Color::rgb(1.0, 0.95, 0.75),
+                    ..default()
+                },
+                transform: Transform::from_translation(tile_center(tile, ALTAR_Z)),
+                ..default()
+            },
+            Position::new(tile.0 as i32, tile.1 as i32),
+            LightSource { color: Color::rgb(1.0, 0.9, 0.6), radius: 2, intensity: 0.6 },
+            Interactable::new(InteractionKind::Pray, "Pray at the altar"),
+            Altar { answered: false },
+        ));
+    }
+}
+
+// System to answer a prayer at the altar E was used on
+pub fn pray_at_altar_system(
+    mut commands: Commands,
+    mut interactions: EventReader<InteractedWith>,
+    mut altar_query: Query<(&mut Altar, &Position, &mut TextureAtlasSprite, &mut LightSource)>,
+    mut player_query: Query<(Entity, &Position, &mut Health), With<Player>>,
+    map: Res<TileMap>,
+    texture_atlases: Res<TextureAtlases>,
+    animal_manager: Res<AnimalManager>,
+    mut favor: ResMut<DeityFavor>,
+    mut game_rng: ResMut<GameRng>,
+    mut game_turn: ResMut<GameTurn>,
+    mut message_log: ResMut<MessageLog>,
+    mut status_events: EventWriter<ApplyStatusEffect>,
+) {
+    let altar_entity = if let Some(interaction) = interactions.read().find(|interaction| interaction.kind == InteractionKind::Pray) {
+        interaction.target
+    } else {
+        return;
+    };
+    let (mut altar, altar_pos, mut sprite, mut light) = if let Ok(altar) = altar_query.get_mut(altar_entity) { altar } else { return; };
+    let (player, player_pos, mut health) = if let Ok(player) = player_query.get_single_mut() { player } else { return; };
+
+    // Praying takes a turn, answered or not
+    game_turn.increment();
+
+    if altar.answered {
+        // Asking twice is presumptuous
+        favor.shift(-1);
+        message_log.add_message("You pray, but the altar is cold and silent.".to_string());
+        return;
+    }
+    altar.answered = true;
+    sprite.color = Color::rgb(0.6, 0.6, 0.6);
+    light.intensity = 0.0;
+
+    let biome = map.get_biome_at(altar_pos.x as usize, altar_pos.y as usize);
+    let rng = &mut game_rng.combat;
+    let outcome = if let Ok(outcome) = PrayerOutcome::ALL.choose_weighted(rng, |outcome| outcome.weight(biome, favor.0)) {
+        *outcome
+    } else {
+        return;
+    };
+    favor.shift(outcome.favor_change());
+    message_log.add_message("You kneel at the altar and pray.".to_string());
+
+    match outcome {
+        PrayerOutcome::Heal => {
+            health.heal(health.max / 2);
+            status_events.send(ApplyStatusEffect { target: player, effect: StatusEffect::new(StatusKind::Regeneration, REGENERATION_TURNS, 1) });
+            message_log.add_message("Warmth spreads through you and your wounds close.".to_string());
+        }
+        PrayerOutcome::BlessWeapon => {
+            status_events.send(ApplyStatusEffect { target: player, effect: StatusEffect::new(StatusKind::Blessed, BLESSING_TURNS, BLESSING_DAMAGE) });
+            message_log.add_message("Light gathers in your hands. Your bolts will strike true.".to_string());
+        }
+        PrayerOutcome::Curse => {
+            status_events.send(ApplyStatusEffect { target: player, effect: StatusEffect::new(StatusKind::Cursed, CURSE_TURNS, CURSE_DAMAGE) });
+            message_log.add_message("Something answers, and it is not pleased.".to_string());
+        }
+        PrayerOutcome::SummonGuardian => {
+            // The guardian steps out next to the altar, on the side away from the player if it can
+            let mut spots: Vec<(i32, i32)> = [(0, 1), (1, 0), (0, -1), (-1, 0), (1, 1), (1, -1), (-1, 1), (-1, -1)].iter()
+                .map(|(dx, dy)| (altar_pos.x + dx, altar_pos.y + dy))
+                .filter(|&(x, y)| map.in_bounds(x, y) && map.tiles[y as usize][x as usize] == TileType::Floor && (x, y) != (player_pos.x, player_pos.y))
+                .collect();
+            spots.sort_by_key(|&(x, y)| -((x - player_pos.x).abs() + (y - player_pos.y).abs()));
+
+            let animal_type = guardian_for(biome);
+            let sprite_index = animal_manager.animal_sprites.get(&animal_type).copied();
+            if let (Some(&pos), Some(sprite_index)) = (spots.first(), sprite_index) {
+                let guardian = AnimalSpawnData { animal_type, spawn_rate: 0.0, sprite_index, flee_distance: 0 };
+                spawn_animal(&mut commands, &map, &texture_atlases, &guardian, pos);
+                message_log.add_message(format!("The altar's guardian, a {}, rises to drive you off!", animal_type.get_name().to_lowercase()));
+            } else {
+                message_log.add_message("The ground shudders, but nothing comes.".to_string());
+            }
+        }
+    }
+    println!("Prayer at {:?} in the {}: {:?} (favor now {})", (altar_pos.x, altar_pos.y), biome.get_name(), outcome, favor.0);
+}
@@ -0,0 +1,295 @@
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::animals::AnimalManager;
+use crate::assets::TextureAtlases;
+use crate::biome::BiomeType;
+use crate::components::{AnimalType, GameTurn, Player, Position};
+use crate::input::TILE_SIZE;
+use crate::lighting::LightSource;
+use crate::map::{TileMap, TileType};
+use crate::rng::GameRng;
+use crate::ui::MessageLog;
+use crate::camera::CameraControl;
+
+// Turns between chances of something happening
+const AMBIENT_INTERVAL_TURNS: u32 = 15;
+// Chance that anything happens when the interval comes round
+const AMBIENT_CHANCE: f64 = 0.4;
+// Lights within this many tiles of the player can flicker
+const FLICKER_RANGE: i32 = 8;
+const FLICKER_SECONDS: f32 = 1.2;
+const SHAKE_SECONDS: f32 = 0.6;
+const SHAKE_STRENGTH: f32 = 4.0;
+// Seconds between jolts, so a shake looks the same at any frame rate
+const SHAKE_STEP_SECONDS: f32 = 1.0 / 30.0;
+// How far to either side of the player a scurrying rat runs, in tiles
+const SCURRY_HALF_WIDTH: i32 = 5;
+const SCURRY_SECONDS: f32 = 0.8;
+
+/// The kinds of flavour event that can happen between encounters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AmbientEvent {
+    Rumble,  // Something shifts far off and the camera shakes
+    Flicker, // Nearby lights gutter
+    Scurry,  // A rat darts across the view
+    Whisper, // Voices in the log
+}
+
+impl AmbientEvent {
+    const ALL: [AmbientEvent; 4] = [AmbientEvent::Rumble, AmbientEvent::Flicker, AmbientEvent::Scurry, AmbientEvent::Whisper];
+
+    // How likely this event is in a biome at a (zero-based) level
+    fn weight(&self, biome: BiomeType, level: usize) -> u32 {
+        let depth = level as u32;
+        match self {
+            AmbientEvent::Rumble => 2 + depth / 3 + if biome == BiomeType::Caves { 2 } else { 0 },
+            AmbientEvent::Flicker => if biome == BiomeType::Labyrinth { 4 } else { 3 },
+            AmbientEvent::Scurry => match biome {
+                BiomeType::Caves | BiomeType::Catacombs => 3,
+                BiomeType::Labyrinth => 2,
+                BiomeType::Groves => 1,
+            },
+            AmbientEvent::Whisper => 1 + depth / 4 + if biome == BiomeType::Catacombs { 3 } else { 0 },
+        }
+    }
+}
+
+fn rumble_message(rng: &mut impl Rng) -> &'static str {
+    [
+        "A distant rumble shakes dust from the ceiling.",
+        "Somewhere below, stone grinds against stone.",
+        "The floor trembles for a moment, then is still.",
+    ].choose(rng).copied().unwrap_or("The ground shakes.")
+}
+
+fn flicker_message(rng: &mut impl Rng) -> &'static str {
+    [
+        "The lights gutter as if something passed by.",
+        "A cold draught sets the flames flickering.",
+    ].choose(rng).copied().unwrap_or("The lights flicker.")
+}
+
+// What the dark says, in the same spirit as the biome dialogue
+fn whisper_message(biome: BiomeType, rng: &mut impl Rng) -> &'static str {
+    let lines: &[&str] = match biome {
+        BiomeType::Caves => &[
+            "You hear dripping water... and something counting the drops.",
+            "A voice echoes from the rock: \"deeper...\"",
+        ],
+        BiomeType::Groves => &[
+            "The leaves rustle, though there is no wind.",
+            "Something hums softly among the roots.",
+        ],
+        BiomeType::Labyrinth => &[
+            "Footsteps echo down a passage that isn't there.",
+            "A whisper: \"left, always left...\"",
+        ],
+        BiomeType::Catacombs => &[
+            "The bones murmur a name you almost recognise.",
+            "A chorus of whispers rises and falls away.",
+            "Something scratches from inside a sealed niche.",
+        ],
+    };
+    lines.choose(rng).copied().unwrap_or("You hear whispering.")
+}
+
+/// Shakes the camera for a moment, through the camera's shake offset
+#[derive(Component)]
+pub struct CameraShake {
+    timer: Timer,
+    step: Timer,
+}
+
+/// A light that is guttering, and the brightness it goes back to
+#[derive(Component)]
+pub struct Flickering {
+    timer: Timer,
+    step: Timer,
+    base_intensity: f32,
+    rng: StdRng, // Seeded from the run, since how bright a light is changes what can be seen
+}
+
+/// A creature glimpsed crossing the view; purely for show, it isn't on the map
+#[derive(Component)]
+pub struct Scurrier {
+    from: Vec3,
+    to: Vec3,
+    timer: Timer,
+}
+
+// A row near the player with open floor all the way across, for a rat to run along
+fn scurry_path(map: &TileMap, player: &Position, rng: &mut impl Rng) -> Option<(i32, i32, i32)> {
+    let mut rows: Vec<i32> = (-3..=3).map(|dy| player.y + dy).collect();
+    rows.shuffle(rng);
+    rows.into_iter().find_map(|y| {
+        let (from, to) = (player.x - SCURRY_HALF_WIDTH, player.x + SCURRY_HALF_WIDTH);
+        let open = (from..=to).all(|x| map.in_bounds(x, y) && map.tiles[y as usize][x as usize] == TileType::Floor);
+        if open { Some((from, to, y)) } else { None }
+    })
+}
+
+fn tile_center(x: i32, y: i32, z: f32) -> Vec3 {
+    Vec3::new(x as f32 * TILE_SIZE + TILE_SIZE / 2.0, y as f32 * TILE_SIZE + TILE_SIZE / 2.0, z)
+}
+
+// System to roll for an ambient event every few turns
+pub fn ambient_events_system(
+    mut commands: Commands,
+    game_turn: Res<GameTurn>,
+    map: Res<TileMap>,
+    texture_atlases: Res<TextureAtlases>,
+    animal_manager: Res<AnimalManager>,
+    mut message_log: ResMut<MessageLog>,
+    player_query: Query<&Position, With<Player>>,
+    camera_query: Query<Entity, (With<CameraControl>, Without<CameraShake>)>,
+    light_query: Query<(Entity, &Position, &LightSource), Without<Flickering>>,
+    mut game_rng: ResMut<GameRng>,
+    mut local: Local<u32>, // The last window that was rolled for
+) {
+    let window = game_turn.current_turn / AMBIENT_INTERVAL_TURNS;
+    if window == *local {
+        return;
+    }
+    *local = window;
+
+    let rng = &mut game_rng.ambient;
+    if window == 0 || !rng.gen_bool(AMBIENT_CHANCE) {
+        return;
+    }
+
+    let player = if let Ok(pos) = player_query.get_single() { *pos } else { return; };
+    if !map.in_bounds(player.x, player.y) {
+        return;
+    }
+    let biome = map.get_biome_at(player.x as usize, player.y as usize);
+
+    let nearby_lights: Vec<(Entity, f32)> = light_query.iter()
+        .filter(|(_, pos, _)| (pos.x - player.x).abs() <= FLICKER_RANGE && (pos.y - player.y).abs() <= FLICKER_RANGE)
+        .map(|(entity, _, light)| (entity, light.intensity))
+        .collect();
+    let rat_sprite = animal_manager.animal_sprites.get(&AnimalType::Rat).copied();
+    let path = scurry_path(&map, &player, rng);
+
+    // Only pick from events that can actually play out here
+    let possible: Vec<AmbientEvent> = AmbientEvent::ALL.into_iter()
+        .filter(|event| match event {
+            AmbientEvent::Rumble => !camera_query.is_empty(),
+            AmbientEvent::Flicker => !nearby_lights.is_empty(),
+            AmbientEvent::Scurry => rat_sprite.is_some() && path.is_some(),
+            AmbientEvent::Whisper => true,
+        })
+        .collect();
+    let event = if let Ok(event) = possible.choose_weighted(rng, |event| event.weight(biome, map.current_level)) {
+        *event
+    } else {
+        return;
+    };
+
+    match event {
+        AmbientEvent::Rumble => {
+            for camera in camera_query.iter() {
+                commands.entity(camera).insert(CameraShake {
+                    timer: Timer::from_seconds(SHAKE_SECONDS, TimerMode::Once),
+                    step: Timer::from_seconds(SHAKE_STEP_SECONDS, TimerMode::Repeating),
+                });
+            }
+            message_log.add_message(rumble_message(rng).to_string());
+        }
+        AmbientEvent::Flicker => {
+            for (entity, intensity) in nearby_lights {
+                commands.entity(entity).insert(Flickering {
+                    timer: Timer::from_seconds(FLICKER_SECONDS, TimerMode::Once),
+                    step: Timer::from_seconds(0.08, TimerMode::Repeating),
+                    base_intensity: intensity,
+                    rng: StdRng::seed_from_u64(rng.gen()),
+                });
+            }
+            message_log.add_message(flicker_message(rng).to_string());
+        }
+        AmbientEvent::Scurry => {
+            let ((from, to, y), index) = if let (Some(path), Some(index)) = (path, rat_sprite) { (path, index) } else { return; };
+            // Run left or right at random
+            let (start, end) = if rng.gen_bool(0.5) { (from, to) } else { (to, from) };
+            commands.spawn((
+                SpriteSheetBundle {
+                    texture_atlas: texture_atlases.animals.clone(),
+                    sprite: TextureAtlasSprite { index, flip_x: end < start, ..default() },
+                    transform: Transform::from_translation(tile_center(start, y, 7.0)),
+                    ..default()
+                },
+                Scurrier {
+                    from: tile_center(start, y, 7.0),
+                    to: tile_center(end, y, 7.0),
+                    timer: Timer::from_seconds(SCURRY_SECONDS, TimerMode::Once),
+                },
+            ));
+        }
+        AmbientEvent::Whisper => {
+            message_log.add_message(whisper_message(biome, rng).to_string());
+        }
+    }
+    println!("Ambient event on turn {}: {:?}", game_turn.current_turn, event);
+}
+
+// System to jolt the camera around its followed position until the shake runs out
+pub fn camera_shake_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut camera_query: Query<(Entity, &mut CameraControl, &mut CameraShake)>,
+) {
+    // Purely cosmetic, so it doesn't draw from the run's seed
+    let mut rng = rand::thread_rng();
+    for (entity, mut control, mut shake) in camera_query.iter_mut() {
+        shake.timer.tick(time.delta());
+        if shake.timer.finished() {
+            control.shake_offset = Vec3::ZERO;
+            commands.entity(entity).remove::<CameraShake>();
+            continue;
+        }
+        if !shake.step.tick(time.delta()).just_finished() {
+            continue;
+        }
+        // Die down as the timer runs out
+        let strength = SHAKE_STRENGTH * shake.timer.percent_left();
+        control.shake_offset = Vec3::new(rng.gen_range(-1.0..=1.0) * strength, rng.gen_range(-1.0..=1.0) * strength, 0.0);
+    }
+}
+
+// System to gutter flickering lights, then put them back how they were
+pub fn flicker_lights_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut light_query: Query<(Entity, &mut LightSource, &mut Flickering)>,
+) {
+    for (entity, mut light, mut flicker) in light_query.iter_mut() {
+        flicker.timer.tick(time.delta());
+        if flicker.timer.finished() {
+            light.intensity = flicker.base_intensity;
+            commands.entity(entity).remove::<Flickering>();
+            continue;
+        }
+        // Only change every step, so the light map isn't rebuilt every frame
+        if flicker.step.tick(time.delta()).just_finished() {
+            light.intensity = flicker.base_intensity * flicker.rng.gen_range(0.2..=1.0);
+        }
+    }
+}
+
+// System to run glimpsed creatures across and clear them away
+pub fn move_scurriers_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut scurrier_query: Query<(Entity, &mut Transform, &mut Scurrier)>,
+) {
+    for (entity, mut transform, mut scurrier) in scurrier_query.iter_mut() {
+        scurrier.timer.tick(time.delta());
+        if scurrier.timer.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        transform.translation = scurrier.from.lerp(scurrier.to, scurrier.timer.percent());
+    }
+}
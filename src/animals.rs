@@ -4,20 +4,61 @@ use rand::seq::SliceRandom;
 use std::collections::HashMap;
 
 use crate::biome::BiomeType;
-use crate::components::{Animal, AnimalType, Position, AnimalTooltip, GameTurn, AnimalAnimation, MovementDirection, Npc, AnimalNpc};
+use crate::components::{Animal, AnimalType, Position, GameTurn, AnimalAnimation, MovementDirection, Npc, AnimalNpc, Companion, Player};
+use crate::combat::{Health, CombatStats};
+use crate::faction::Hostile;
+use crate::infighting::CreatureFaction;
+use crate::emotes::{EmoteKind, ShowEmote};
+use crate::events::{AnimalTamed, EntityDamaged, TileEntered};
+use crate::hearing::{Hearing, NoiseEvent, NoiseKind};
+use crate::rng::GameRng;
+use crate::pathmaps::PathMaps;
+use crate::scent::ScentMap;
+use crate::spawn_director::creature_budget;
+use crate::run_modifiers::RunModifiers;
+use crate::inventory::{Inventory, ItemKind};
+use crate::loot::CreatureDrop;
+use crate::status::{ApplyStatusEffect, StatusEffect, StatusEffects, StatusKind};
+use crate::ui::MessageLog;
 use crate::input::TILE_SIZE;
-use crate::map::{TileMap, TileType, MAP_WIDTH, MAP_HEIGHT};
-use crate::AnimationState;
+use crate::lighting::LightMap;
+use crate::map::{TileMap, TileType};
+use crate::visibility::{floor_darkness, in_field_of_view, Vision};
+use crate::stealth::{AlertState, Awareness, Facing, VISION_CONE_COS};
+use crate::morale::Morale;
 use crate::dialogue::CharacterType;
+use crate::interaction::{Interactable, InteractionKind};
 
-// Maximum number of animals that can spawn on a map
-pub const MAX_ANIMALS_PER_MAP: usize = 3;
+// How far a predator can see the player from (in steps), walls permitting
+const PREDATOR_SIGHT_RANGE: i32 = 10;
+
+// Chance each turn that a calm animal switches between idling and grazing
+const BEHAVIOR_SWITCH_CHANCE: f64 = 0.2;
+
+// Chance each turn that an animal standing idle hums a note
+const IDLE_NOTE_CHANCE: f64 = 0.05;
+
+/// What a prey animal is doing this turn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimalBehavior {
+    Idle,  // Standing still
+    Graze, // Wandering about
+    Flee,  // Running from the player or a predator
+}
+
+/// An animal that runs from threats instead of wandering into them
+#[derive(Component, Debug, Clone)]
+pub struct Prey {
+    pub behavior: AnimalBehavior,
+    pub flee_distance: i32,
+}
 
 // Structure to hold animal spawn data
 pub struct AnimalSpawnData {
     pub animal_type: AnimalType,
     pub spawn_rate: f32, // As a percentage (0-100)
     pub sprite_index: usize,
+    pub flee_distance: i32, // Prey runs from threats this close (in steps); 0 never runs
 }
 
 // Resource to manage animal spawning
@@ -124,51 +165,61 @@ impl AnimalManager {
             animal_type: AnimalType::Snake,
             spawn_rate: 6.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::Snake).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::Snake),
         });
         caves_animals.push(AnimalSpawnData {
             animal_type: AnimalType::Cobra,
             spawn_rate: 3.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::Cobra).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::Cobra),
         });
         caves_animals.push(AnimalSpawnData {
             animal_type: AnimalType::Kingsnake,
             spawn_rate: 3.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::Kingsnake).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::Kingsnake),
         });
         caves_animals.push(AnimalSpawnData {
             animal_type: AnimalType::BlackMamba,
             spawn_rate: 2.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::BlackMamba).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::BlackMamba),
         });
         caves_animals.push(AnimalSpawnData {
             animal_type: AnimalType::Rat,
             spawn_rate: 15.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::Rat).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::Rat),
         });
         caves_animals.push(AnimalSpawnData {
             animal_type: AnimalType::Honeybadger,
             spawn_rate: 3.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::Honeybadger).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::Honeybadger),
         });
         caves_animals.push(AnimalSpawnData {
             animal_type: AnimalType::GrizzlyBear,
             spawn_rate: 1.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::GrizzlyBear).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::GrizzlyBear),
         });
         caves_animals.push(AnimalSpawnData {
             animal_type: AnimalType::BlackBear,
             spawn_rate: 1.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::BlackBear).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::BlackBear),
         });
         caves_animals.push(AnimalSpawnData {
             animal_type: AnimalType::Pig,
             spawn_rate: 2.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::Pig).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::Pig),
         });
         caves_animals.push(AnimalSpawnData {
             animal_type: AnimalType::Boar,
             spawn_rate: 1.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::Boar).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::Boar),
         });
         self.biome_animals.insert(BiomeType::Caves, caves_animals);
         
@@ -178,36 +229,43 @@ impl AnimalManager {
             animal_type: AnimalType::Snake,
             spawn_rate: 6.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::Snake).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::Snake),
         });
         labyrinth_animals.push(AnimalSpawnData {
             animal_type: AnimalType::Cobra,
             spawn_rate: 3.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::Cobra).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::Cobra),
         });
         labyrinth_animals.push(AnimalSpawnData {
             animal_type: AnimalType::Kingsnake,
             spawn_rate: 3.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::Kingsnake).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::Kingsnake),
         });
         labyrinth_animals.push(AnimalSpawnData {
             animal_type: AnimalType::BlackMamba,
             spawn_rate: 2.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::BlackMamba).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::BlackMamba),
         });
         labyrinth_animals.push(AnimalSpawnData {
             animal_type: AnimalType::Rat,
             spawn_rate: 10.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::Rat).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::Rat),
         });
         labyrinth_animals.push(AnimalSpawnData {
             animal_type: AnimalType::Cat,
             spawn_rate: 5.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::Cat).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::Cat),
         });
         labyrinth_animals.push(AnimalSpawnData {
             animal_type: AnimalType::Dog,
             spawn_rate: 5.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::Dog).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::Dog),
         });
         self.biome_animals.insert(BiomeType::Labyrinth, labyrinth_animals);
         
@@ -217,16 +275,19 @@ impl AnimalManager {
             animal_type: AnimalType::Rat,
             spawn_rate: 20.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::Rat).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::Rat),
         });
         catacombs_animals.push(AnimalSpawnData {
             animal_type: AnimalType::Snake,
             spawn_rate: 6.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::Snake).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::Snake),
         });
         catacombs_animals.push(AnimalSpawnData {
             animal_type: AnimalType::Dog,
             spawn_rate: 5.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::Dog).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::Dog),
         });
         self.biome_animals.insert(BiomeType::Catacombs, catacombs_animals);
         
@@ -236,86 +297,103 @@ impl AnimalManager {
             animal_type: AnimalType::Snake,
             spawn_rate: 6.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::Snake).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::Snake),
         });
         groves_animals.push(AnimalSpawnData {
             animal_type: AnimalType::Cobra,
             spawn_rate: 3.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::Cobra).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::Cobra),
         });
         groves_animals.push(AnimalSpawnData {
             animal_type: AnimalType::Kingsnake,
             spawn_rate: 3.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::Kingsnake).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::Kingsnake),
         });
         groves_animals.push(AnimalSpawnData {
             animal_type: AnimalType::BlackMamba,
             spawn_rate: 2.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::BlackMamba).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::BlackMamba),
         });
         groves_animals.push(AnimalSpawnData {
             animal_type: AnimalType::Rat,
             spawn_rate: 15.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::Rat).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::Rat),
         });
         groves_animals.push(AnimalSpawnData {
             animal_type: AnimalType::Honeybadger,
             spawn_rate: 3.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::Honeybadger).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::Honeybadger),
         });
         groves_animals.push(AnimalSpawnData {
             animal_type: AnimalType::GrizzlyBear,
             spawn_rate: 1.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::GrizzlyBear).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::GrizzlyBear),
         });
         groves_animals.push(AnimalSpawnData {
             animal_type: AnimalType::BlackBear,
             spawn_rate: 1.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::BlackBear).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::BlackBear),
         });
         groves_animals.push(AnimalSpawnData {
             animal_type: AnimalType::Pig,
             spawn_rate: 2.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::Pig).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::Pig),
         });
         groves_animals.push(AnimalSpawnData {
             animal_type: AnimalType::Boar,
             spawn_rate: 1.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::Boar).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::Boar),
         });
         groves_animals.push(AnimalSpawnData {
             animal_type: AnimalType::Capybara,
             spawn_rate: 2.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::Capybara).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::Capybara),
         });
         groves_animals.push(AnimalSpawnData {
             animal_type: AnimalType::Beaver,
             spawn_rate: 5.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::Beaver).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::Beaver),
         });
         groves_animals.push(AnimalSpawnData {
             animal_type: AnimalType::WaterBuffalo,
             spawn_rate: 2.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::WaterBuffalo).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::WaterBuffalo),
         });
         groves_animals.push(AnimalSpawnData {
             animal_type: AnimalType::Yak,
             spawn_rate: 1.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::Yak).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::Yak),
         });
         groves_animals.push(AnimalSpawnData {
             animal_type: AnimalType::MallardDuck,
             spawn_rate: 4.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::MallardDuck).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::MallardDuck),
         });
         groves_animals.push(AnimalSpawnData {
             animal_type: AnimalType::SheepRam,
             spawn_rate: 1.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::SheepRam).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::SheepRam),
         });
         groves_animals.push(AnimalSpawnData {
             animal_type: AnimalType::SheepEwe,
             spawn_rate: 1.0,
             sprite_index: *self.animal_sprites.get(&AnimalType::SheepEwe).unwrap_or(&0),
+            flee_distance: animal_flee_distance(AnimalType::SheepEwe),
         });
         self.biome_animals.insert(BiomeType::Groves, groves_animals);
     }
@@ -347,6 +425,146 @@ impl AnimalManager {
         let index = rng.gen_range(0..biome_animals.len());
         Some(&biome_animals[index])
     }
+
+    // Spawn data for a kind of animal named in a data file, whatever the biome
+    pub fn spawn_data_for(&self, name: &str) -> Option<AnimalSpawnData> {
+        let (&animal_type, &sprite_index) = self.animal_sprites.iter()
+            .find(|(animal_type, _)| animal_type.get_name().eq_ignore_ascii_case(name))?;
+        Some(AnimalSpawnData {
+            animal_type,
+            spawn_rate: 0.0,
+            sprite_index,
+            flee_distance: animal_flee_distance(animal_type),
+        })
+    }
+}
+
+// Starting health for each kind of animal
+pub fn animal_health(animal_type: AnimalType) -> i32 {
+    match animal_type {
+        AnimalType::GrizzlyBear | AnimalType::WaterBuffalo | AnimalType::Yak => 20,
+        AnimalType::BlackBear | AnimalType::Boar => 15,
+        AnimalType::Dog | AnimalType::Honeybadger | AnimalType::Pig | AnimalType::SheepRam => 10,
+        AnimalType::Rat | AnimalType::MallardDuck => 3,
+        _ => 6,
+    }
+}
+
+// How hard each kind of animal hits
+pub fn animal_attack(animal_type: AnimalType) -> i32 {
+    match animal_type {
+        AnimalType::GrizzlyBear => 5,
+        AnimalType::BlackBear | AnimalType::BlackMamba | AnimalType::Honeybadger => 4,
+        AnimalType::Cobra | AnimalType::Boar | AnimalType::Dog => 3,
+        AnimalType::Rat | AnimalType::MallardDuck | AnimalType::SheepEwe | AnimalType::Capybara => 1,
+        _ => 2,
+    }
+}
+
+// Share of its health at which each kind of monster gives up and runs, when there's nothing about to steady it
+pub fn animal_courage(animal_type: AnimalType) -> f32 {
+    match animal_type {
+        // Honey badgers don't care
+        AnimalType::Honeybadger => 0.0,
+        AnimalType::GrizzlyBear => 0.15,
+        AnimalType::BlackBear | AnimalType::BlackMamba => 0.25,
+        AnimalType::Dog => 0.35,
+        _ => 0.3,
+    }
+}
+
+// What each kind of animal can leave on its body
+pub fn animal_drops(animal_type: AnimalType) -> Vec<CreatureDrop> {
+    match animal_type {
+        AnimalType::GrizzlyBear | AnimalType::BlackBear => vec![
+            CreatureDrop::new(ItemKind::Meat, 1.0, 3),
+            CreatureDrop::new(ItemKind::Hide, 0.8, 1),
+            CreatureDrop::new(ItemKind::Claw, 0.6, 2),
+        ],
+        AnimalType::WaterBuffalo | AnimalType::Yak | AnimalType::Boar | AnimalType::Pig => vec![
+            CreatureDrop::new(ItemKind::Meat, 1.0, 3),
+            CreatureDrop::new(ItemKind::Hide, 0.7, 1),
+        ],
+        AnimalType::SheepEwe | AnimalType::SheepRam | AnimalType::Beaver | AnimalType::Capybara => vec![
+            CreatureDrop::new(ItemKind::Meat, 0.9, 2),
+            CreatureDrop::new(ItemKind::Hide, 0.5, 1),
+        ],
+        AnimalType::Dog | AnimalType::Honeybadger | AnimalType::Cat => vec![
+            CreatureDrop::new(ItemKind::Meat, 0.6, 1),
+            CreatureDrop::new(ItemKind::Fang, 0.4, 1),
+            CreatureDrop::new(ItemKind::Claw, 0.3, 1),
+        ],
+        AnimalType::Snake | AnimalType::Cobra | AnimalType::BlackMamba | AnimalType::Kingsnake => vec![
+            CreatureDrop::new(ItemKind::Fang, 0.7, 2),
+            CreatureDrop::new(ItemKind::Hide, 0.3, 1),
+        ],
+        // Rats drag shiny things back to their nests
+        AnimalType::Rat => vec![
+            CreatureDrop::new(ItemKind::Meat, 0.3, 1),
+            CreatureDrop::new(ItemKind::Gold, 0.15, 3),
+        ],
+        _ => vec![CreatureDrop::new(ItemKind::Meat, 0.8, 1)],
+    }
+}
+
+// Chance (0.0 - 1.0) that feeding an animal tames it
+pub fn tame_chance(animal_type: AnimalType) -> f64 {
+    match animal_type {
+        // Domestic animals are happy to tag along
+        AnimalType::Dog | AnimalType::Cat => 0.8,
+        AnimalType::Pig | AnimalType::SheepEwe | AnimalType::SheepRam | AnimalType::MallardDuck | AnimalType::Capybara => 0.6,
+        AnimalType::Rat | AnimalType::Beaver | AnimalType::Yak | AnimalType::WaterBuffalo => 0.4,
+        AnimalType::Boar | AnimalType::Honeybadger | AnimalType::Kingsnake => 0.25,
+        // Dangerous animals are hard to win over
+        AnimalType::BlackBear | AnimalType::Snake => 0.15,
+        _ => 0.1,
+    }
+}
+
+// How close a threat can get before each kind of animal bolts
+pub fn animal_flee_distance(animal_type: AnimalType) -> i32 {
+    match animal_type {
+        // Skittish little things keep well away
+        AnimalType::Rat | AnimalType::MallardDuck | AnimalType::Cat => 5,
+        AnimalType::SheepEwe | AnimalType::SheepRam | AnimalType::Capybara | AnimalType::Beaver => 4,
+        AnimalType::Pig | AnimalType::Kingsnake => 3,
+        // Big animals only shy away when crowded
+        AnimalType::Yak | AnimalType::WaterBuffalo => 2,
+        // Predators, venomous snakes and boars stand their ground
+        _ => 0,
+    }
+}
+
+// Predators start out hostile
+fn is_predator(animal_type: AnimalType) -> bool {
+    matches!(animal_type, AnimalType::GrizzlyBear | AnimalType::BlackBear | AnimalType::Dog | AnimalType::Honeybadger)
+}
+
+// Venomous snakes bite anything that comes too close
+fn is_venomous(animal_type: AnimalType) -> bool {
+    matches!(animal_type, AnimalType::Snake | AnimalType::Cobra | AnimalType::BlackMamba)
+}
+
+// Which side each kind of animal takes when creatures fight among themselves
+pub fn creature_faction(animal_type: AnimalType) -> CreatureFaction {
+    if is_predator(animal_type) {
+        CreatureFaction::Predators
+    } else if is_venomous(animal_type) {
+        CreatureFaction::Venomous
+    } else {
+        CreatureFaction::Prey
+    }
+}
+
+// Lingering effect an animal's attack leaves behind, if any
+fn attack_status_effect(animal_type: AnimalType) -> Option<StatusEffect> {
+    match animal_type {
+        AnimalType::BlackMamba => Some(StatusEffect::new(StatusKind::Poison, 5, 2)),
+        AnimalType::Snake | AnimalType::Cobra => Some(StatusEffect::new(StatusKind::Poison, 4, 1)),
+        // A bear's mauling leaves you limping
+        AnimalType::GrizzlyBear | AnimalType::BlackBear => Some(StatusEffect::new(StatusKind::Slow, 3, 1)),
+        _ => None,
+    }
 }
 
 // Function to spawn animals on the map
@@ -355,23 +573,27 @@ pub fn spawn_animals(
     map: &TileMap,
     texture_atlases: &crate::assets::TextureAtlases,
     animal_manager: &AnimalManager,
+    modifiers: &RunModifiers,
+    rng: &mut impl Rng,
 ) {
-    let mut rng = rand::thread_rng();
     
     // Get the biome for this map
     let biome = map.get_biome_at(0, 0); // All maps currently use a single biome
     
     // Find valid floor tiles for animal spawning
     let mut valid_positions = Vec::new();
-    for y in 0..MAP_HEIGHT {
-        for x in 0..MAP_WIDTH {
+    for y in 0..map.height {
+        for x in 0..map.width {
             if map.tiles[y][x] == TileType::Floor {
                 // Check if this is a player spawn position or stairs
                 let is_player_pos = map.get_spawn_position().0 == x && map.get_spawn_position().1 == y;
                 let is_stairs = map.down_stairs_pos.map_or(false, |pos| pos.0 == x && pos.1 == y) ||
                                map.up_stairs_pos.map_or(false, |pos| pos.0 == x && pos.1 == y);
                 
-                if !is_player_pos && !is_stairs {
+                // Vaults only get the creatures their markers ask for
+                let in_vault = map.vaults.iter().any(|vault| vault.contains(x, y));
+                
+                if !is_player_pos && !is_stairs && !in_vault {
                     valid_positions.push((x as i32, y as i32));
                 }
             }
@@ -379,10 +601,15 @@ pub fn spawn_animals(
     }
     
     // Shuffle the valid positions
-    valid_positions.shuffle(&mut rng);
+    valid_positions.shuffle(rng);
+    
+    // Marked spawns (vault markers, nests) go last so they're popped, and filled, first
+    valid_positions.retain(|&(x, y)| !map.monster_spawns.contains(&(x as usize, y as usize)));
+    valid_positions.extend(map.monster_spawns.iter().map(|&(x, y)| (x as i32, y as i32)));
     
-    // Determine how many animals to spawn (up to MAX_ANIMALS_PER_MAP)
-    let num_animals = rng.gen_range(0..=MAX_ANIMALS_PER_MAP);
+    // Determine how many animals to spawn (from the level's budget, plus any the map demands)
+    let budget = creature_budget(map, modifiers);
+    let num_animals = rng.gen_range(budget / 2..=budget).max(map.monster_spawns.len());
     
     // Spawn the animals
     for _ in 0..num_animals {
@@ -394,136 +621,146 @@ pub fn spawn_animals(
         let pos = valid_positions.pop().unwrap();
         
         // Get a random animal for this biome
-        if let Some(animal_data) = animal_manager.get_random_animal(biome, &mut rng) {
-            let transform = Transform::from_xyz(
-                pos.0 as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
-                pos.1 as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
-                7.0  // Increased z-index to ensure animals render on top of all terrain and NPCs
-            ).with_scale(Vec3::splat(1.0));
-            
-            // Get animal name
-            let animal_name = animal_data.animal_type.get_name();
-            
-            // Spawn the animal entity as an NPC
-            commands.spawn((
-                SpriteSheetBundle {
-                    texture_atlas: texture_atlases.animals.clone(),
-                    sprite: TextureAtlasSprite {
-                        index: animal_data.sprite_index,
-                        ..default()
-                    },
-                    transform,
-                    ..default()
-                },
-                // Add both Animal and Npc components
-                Animal {
-                    animal_type: animal_data.animal_type,
-                    hover: false,
-                },
-                // Add Npc component with animal-specific settings
-                Npc {
-                    name: format!("{} ({})", animal_name, animal_data.animal_type.get_name()),
-                    dialog: vec![format!("A {} watches you cautiously.", animal_name)],
-                    speaking: false,
-                    dialog_text: format!("A {} watches you cautiously.", animal_name),
-                    current_dialog_index: 0,
-                    character_type: CharacterType::Generic,
-                    animation_timer: Timer::from_seconds(0.3, TimerMode::Once),
-                    original_scale: Vec3::splat(1.0),
-                    wiggle_direction: 1.0,
-                    wiggle_amount: 0.1,
-                    is_animal: true,
-                    animal_type: Some(animal_data.animal_type),
-                },
-                // Add marker component
-                AnimalNpc,
-                Position::new(pos.0, pos.1),
-                AnimalAnimation {
-                    start_pos: transform.translation,
-                    target_pos: transform.translation,
-                    ..default()
-                },
-            ));
-            
-            println!("Spawned {:?} at position: ({}, {})", animal_data.animal_type, pos.0, pos.1);
+        if let Some(animal_data) = animal_manager.get_random_animal(biome, rng) {
+            spawn_animal(commands, map, texture_atlases, animal_data, pos);
         }
     }
 }
 
-// System to handle mouse hover over animals
-pub fn handle_animal_hover(
-    mut commands: Commands,
-    windows: Query<&Window>,
-    camera_q: Query<(&Camera, &GlobalTransform)>,
-    mut animal_query: Query<(Entity, &mut Animal, &Transform, &Position)>,
-    tooltip_query: Query<Entity, With<AnimalTooltip>>,
-    asset_server: Res<AssetServer>,
-) {
-    // Get the window and camera
-    let window = windows.single();
-    let (camera, camera_transform) = camera_q.single();
-    
-    // Get the cursor position in the window
-    if let Some(cursor_position) = window.cursor_position() {
-        // Convert cursor position to world coordinates
-        if let Some(world_position) = camera.viewport_to_world(camera_transform, cursor_position) {
-            let world_pos = world_position.origin.truncate();
-            
-            // Check if the cursor is over any animal
-            let mut hovered_animal = None;
-            
-            for (entity, mut animal, transform, position) in animal_query.iter_mut() {
-                // Calculate the bounds of the animal sprite
-                let animal_pos = Vec2::new(
-                    position.x as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
-                    position.y as f32 * TILE_SIZE + (TILE_SIZE / 2.0)
-                );
-                
-                let half_size = TILE_SIZE / 2.0;
-                let min_x = animal_pos.x - half_size;
-                let max_x = animal_pos.x + half_size;
-                let min_y = animal_pos.y - half_size;
-                let max_y = animal_pos.y + half_size;
-                
-                // Check if the cursor is within the bounds
-                if world_pos.x >= min_x && world_pos.x <= max_x && 
-                   world_pos.y >= min_y && world_pos.y <= max_y {
-                    // Set hover state to true
-                    animal.hover = true;
-                    hovered_animal = Some((entity, animal.animal_type, transform.translation));
-                } else {
-                    // Set hover state to false
-                    animal.hover = false;
-                }
-            }
-            
-            // Remove any existing tooltips
-            for entity in tooltip_query.iter() {
-                commands.entity(entity).despawn();
-            }
-            
-            // Create a tooltip for the hovered animal
-            if let Some((_, animal_type, position)) = hovered_animal {
-                commands.spawn((
-                    Text2dBundle {
-                        text: Text::from_section(
-                            animal_type.get_name(),
-                            TextStyle {
-                                font: asset_server.load("fonts/FiraSans-Light.ttf"),
-                                font_size: 14.0,
-                                color: Color::WHITE,
-                            },
-                        )
-                        .with_alignment(TextAlignment::Center),
-                        transform: Transform::from_translation(
-                            position + Vec3::new(0.0, TILE_SIZE, 15.0) // Position above the animal
-                        ),
-                        ..default()
-                    },
-                    AnimalTooltip,
-                ));
+// Spawn one animal of the given kind on a tile
+pub fn spawn_animal(
+    commands: &mut Commands,
+    map: &TileMap,
+    texture_atlases: &crate::assets::TextureAtlases,
+    animal_data: &AnimalSpawnData,
+    pos: (i32, i32),
+) -> Entity {
+    let transform = Transform::from_xyz(
+        pos.0 as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+        pos.1 as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+        7.0  // Increased z-index to ensure animals render on top of all terrain and NPCs
+    ).with_scale(Vec3::splat(1.0));
+    
+    // Get animal name
+    let animal_name = animal_data.animal_type.get_name();
+    
+    // Spawn the animal entity as an NPC
+    let animal_entity = commands.spawn((
+        SpriteSheetBundle {
+            texture_atlas: texture_atlases.animals.clone(),
+            sprite: TextureAtlasSprite {
+                index: animal_data.sprite_index,
+                ..default()
+            },
+            transform,
+            ..default()
+        },
+        // Add both Animal and Npc components
+        Animal {
+            animal_type: animal_data.animal_type,
+            hover: false,
+        },
+        // Add Npc component with animal-specific settings
+        Npc {
+            name: format!("{} ({})", animal_name, animal_data.animal_type.get_name()),
+            dialog: vec![format!("A {} watches you cautiously.", animal_name)],
+            speaking: false,
+            dialog_text: format!("A {} watches you cautiously.", animal_name),
+            current_dialog_index: 0,
+            met: false,
+            lines_heard: 0,
+            character_type: CharacterType::Generic,
+            animation_timer: Timer::from_seconds(0.3, TimerMode::Once),
+            original_scale: Vec3::splat(1.0),
+            wiggle_direction: 1.0,
+            wiggle_amount: 0.1,
+            is_animal: true,
+            animal_type: Some(animal_data.animal_type),
+        },
+        Interactable::new(InteractionKind::Talk, format!("Look at the {}", animal_name)),
+        // Add marker component
+        AnimalNpc,
+        Position::new(pos.0, pos.1),
+        AnimalAnimation {
+            start_pos: transform.translation,
+            target_pos: transform.translation,
+            ..default()
+        },
+        // Deeper animals are tougher
+        Health::new(map.depth_tier.scale_monster_health(animal_health(animal_data.animal_type))),
+        CombatStats { attack: animal_attack(animal_data.animal_type) + map.depth_tier.monster_attack_bonus },
+        creature_faction(animal_data.animal_type),
+    )).id();
+    
+    if is_predator(animal_data.animal_type) || is_venomous(animal_data.animal_type) {
+        commands.entity(animal_entity).insert((Hostile, Morale::new(animal_courage(animal_data.animal_type))));
+    }
+    // Predators track the player by sound as well as by sight, and can be snuck past
+    if is_predator(animal_data.animal_type) {
+        commands.entity(animal_entity).insert((Hearing::default(), Facing::default(), Awareness::default(), Vision { radius: PREDATOR_SIGHT_RANGE as f32 }));
+    }
+    if animal_data.flee_distance > 0 {
+        commands.entity(animal_entity).insert(Prey {
+            behavior: AnimalBehavior::Graze,
+            flee_distance: animal_data.flee_distance,
+        });
+    }
+    
+    println!("Spawned {:?} at position: ({}, {})", animal_data.animal_type, pos.0, pos.1);
+    animal_entity
+}
+
+// One step in either x or y direction toward a tile, along whichever is further off
+fn step_toward(from: &Position, to: (i32, i32)) -> Position {
+    let dx = to.0 - from.x;
+    let dy = to.1 - from.y;
+    let mut target_pos = *from;
+    if dx.abs() > dy.abs() {
+        target_pos.x += dx.signum();
+    } else {
+        target_pos.y += dy.signum();
+    }
+    target_pos
+}
+
+// Pick a prey animal's behaviour for the turn and where it moves to
+fn prey_step(prey: &mut Prey, position: &Position, threats: &[(i32, i32)], player: (i32, i32), path_maps: &PathMaps, map: &TileMap, rng: &mut impl Rng) -> Position {
+    let distance_to = |x: i32, y: i32, threat: &(i32, i32)| (threat.0 - x).abs() + (threat.1 - y).abs();
+    let nearest = threats.iter()
+        .filter(|threat| **threat != (position.x, position.y))
+        .min_by_key(|threat| distance_to(position.x, position.y, threat));
+    
+    if let Some(threat) = nearest.filter(|threat| distance_to(position.x, position.y, threat) <= prey.flee_distance) {
+        prey.behavior = AnimalBehavior::Flee;
+        // Running from the player follows the shared flee map, which steers clear of dead ends
+        if *threat == player {
+            if let Some((x, y)) = path_maps.flee.downhill_from(position.x, position.y) {
+                return Position { x, y };
             }
         }
+        // Take whichever open step puts the most ground between us and the threat
+        return [(0, 1), (1, 0), (0, -1), (-1, 0)].iter()
+            .map(|(dx, dy)| Position { x: position.x + dx, y: position.y + dy })
+            .filter(|step| map.is_position_walkable(step.x, step.y))
+            .filter(|step| distance_to(step.x, step.y, threat) > distance_to(position.x, position.y, threat))
+            .max_by_key(|step| distance_to(step.x, step.y, threat))
+            .unwrap_or(*position);
+    }
+    
+    // Once safe, a fleeing animal calms down; calm ones drift between idling and grazing
+    prey.behavior = match prey.behavior {
+        AnimalBehavior::Flee => AnimalBehavior::Idle,
+        AnimalBehavior::Idle if rng.gen_bool(BEHAVIOR_SWITCH_CHANCE) => AnimalBehavior::Graze,
+        AnimalBehavior::Graze if rng.gen_bool(BEHAVIOR_SWITCH_CHANCE) => AnimalBehavior::Idle,
+        behavior => behavior,
+    };
+    
+    if prey.behavior == AnimalBehavior::Graze {
+        let directions = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+        let dir = directions[rng.gen_range(0..directions.len())];
+        Position { x: position.x + dir.0, y: position.y + dir.1 }
+    } else {
+        *position
     }
 }
 
@@ -531,11 +768,18 @@ pub fn handle_animal_hover(
 pub fn move_animals_system(
     mut commands: Commands,
     mut param_set: ParamSet<(
-        Query<(Entity, &Animal, &Npc, &Position, &mut Transform, &mut AnimalAnimation, &mut TextureAtlasSprite), With<AnimalNpc>>,
+        Query<(Entity, &Animal, &Npc, &Position, &mut Transform, &mut AnimalAnimation, &mut TextureAtlasSprite, Option<&StatusEffects>, Option<&mut Hearing>, Option<&mut Prey>, Option<&mut Facing>, Option<&mut Awareness>, Option<&Morale>, Option<&Vision>), (With<AnimalNpc>, Without<Companion>)>,
         Query<&Position, With<crate::components::Player>>
     )>,
     map: Res<TileMap>,
+    scent_map: Res<ScentMap>,
+    path_maps: Res<PathMaps>,
     game_turn: Res<GameTurn>,
+    mut game_rng: ResMut<GameRng>,
+    mut tile_events: EventWriter<TileEntered>,
+    mut emote_events: EventWriter<ShowEmote>,
+    mut message_log: ResMut<MessageLog>,
+    light_map: Option<Res<LightMap>>,
     mut local: Local<u32>, // Add a local resource to track the last turn animals moved
 ) {
     // Only move animals if this is a new turn
@@ -555,55 +799,144 @@ pub fn move_animals_system(
     
     // Process animal movements
     let mut animal_query = param_set.p0();
-    for (entity, animal, _npc, position, _transform, mut animation, mut sprite) in animal_query.iter_mut() {
+    let rng = &mut game_rng.combat;
+    
+    // Prey runs from the player and from any wild predator
+    let mut threats: Vec<(i32, i32)> = animal_query.iter()
+        .filter(|(_, animal, ..)| is_predator(animal.animal_type))
+        .map(|(_, _, _, position, ..)| (position.x, position.y))
+        .collect();
+    threats.push((player_pos.x, player_pos.y));
+    
+    // Predators hunt prey when they've nothing better to do
+    let quarry: Vec<(i32, i32)> = animal_query.iter()
+        .filter(|(_, animal, ..)| creature_faction(animal.animal_type) == CreatureFaction::Prey)
+        .map(|(_, _, _, position, ..)| (position.x, position.y))
+        .collect();
+    
+    let darkness = floor_darkness(&map);
+    for (entity, animal, _npc, position, _transform, mut animation, mut sprite, status, hearing, mut prey, mut facing, awareness, morale, vision) in animal_query.iter_mut() {
+        // Slowed animals sit out every other turn
+        if status.map_or(false, |status| status.skips_turn(game_turn.current_turn)) {
+            continue;
+        }
+        
         // Different movement behavior based on animal type
+        let fleeing = morale.map_or(false, |morale| morale.fleeing);
         let target_pos = match animal.animal_type {
+            // Monsters whose nerve has broken run for open ground, away from the player
+            _ if fleeing => path_maps.flee.downhill_from(position.x, position.y)
+                .map(|(x, y)| Position { x, y })
+                .unwrap_or(*position),
             // For predator-type animals
             AnimalType::GrizzlyBear | AnimalType::BlackBear | AnimalType::Dog | AnimalType::Honeybadger => {
-                // Predators move toward the player if within range
-                let dx = player_pos.x - position.x;
-                let dy = player_pos.y - position.y;
+                // Predators only see what's in front of them, walls permitting
+                let here = (position.x, position.y);
+                let player_tile = (player_pos.x, player_pos.y);
+                let looking = facing.as_deref().copied().unwrap_or_default().0;
+                // Dark floors hide the player unless they're standing in the light
+                let vision = vision.copied().unwrap_or(Vision { radius: PREDATOR_SIGHT_RANGE as f32 });
+                let sight = |tile: (i32, i32)| vision.radius_to(darkness, light_map.as_deref(), tile) as i32;
+                let sees_player = in_field_of_view(&map, here, looking, player_tile, sight(player_tile), VISION_CONE_COS);
                 
-                // Only chase if within 10 tiles
-                if dx.abs() + dy.abs() <= 10 {
-                    let mut target_pos = *position; // Now works because Position implements Copy
-                    
-                    // Move one step in either x or y direction toward player
-                    if dx.abs() > dy.abs() {
-                        // Move horizontally
-                        target_pos.x += if dx > 0 { 1 } else { -1 };
+                // Otherwise they go and look for the last thing they heard, or sniff out the trail
+                let mut heard = None;
+                if let Some(mut hearing) = hearing {
+                    if sees_player {
+                        hearing.heard = None;
                     } else {
-                        // Move vertically
-                        target_pos.y += if dy > 0 { 1 } else { -1 };
+                        hearing.tick(position);
+                        heard = hearing.heard;
+                    }
+                }
+                
+                // A glimpse makes them wary; only a second look sets them hunting
+                let state = if let Some(mut awareness) = awareness {
+                    let before = awareness.state;
+                    if awareness.observe(sees_player, heard.is_some()) {
+                        message_log.add_message(format!("The {} spots you!", animal.animal_type.get_name().to_lowercase()));
+                        emote_events.send(ShowEmote::icon(entity, EmoteKind::Alert));
+                    } else if before == AlertState::Unaware && awareness.state == AlertState::Suspicious {
+                        emote_events.send(ShowEmote::text(entity, "?"));
                     }
-                    
-                    target_pos
+                    awareness.state
                 } else {
-                    // Random movement if player is too far
+                    AlertState::Alert
+                };
+                
+                // Unaware ones wander; wary or hunting ones chase, stare, or follow sound and scent
+                let trail = scent_map.uphill_from(position.x, position.y);
+                let goal = match (state, heard, trail) {
+                    (AlertState::Unaware, _, _) => None,
+                    (AlertState::Alert, _, _) if sees_player => Some(
+                        path_maps.to_player.downhill_from(here.0, here.1)
+                            .map(|(x, y)| Position { x, y })
+                            .unwrap_or_else(|| step_toward(position, player_tile))
+                    ),
+                    (AlertState::Suspicious, _, _) if sees_player => {
+                        // Stops and stares, trying to make out what it saw
+                        if let Some(facing) = facing.as_deref_mut() {
+                            facing.turn_toward(here, player_tile);
+                        }
+                        Some(*position)
+                    }
+                    (_, Some(noise), _) => Some(step_toward(position, noise)),
+                    // Follow the player's trail towards where the scent is freshest
+                    (_, None, Some((x, y))) => Some(Position { x, y }),
+                    (_, None, None) => None,
+                };
+                
+                // Failing that, go after the nearest prey in view, stopping beside it to attack
+                let hunt = || {
+                    quarry.iter()
+                        .filter(|&&prey| in_field_of_view(&map, here, looking, prey, sight(prey), VISION_CONE_COS))
+                        .min_by_key(|&&(x, y)| (x - here.0).abs() + (y - here.1).abs())
+                        .map(|&prey| if (prey.0 - here.0).abs() + (prey.1 - here.1).abs() <= 1 { *position } else { step_toward(position, prey) })
+                };
+                
+                goal.or_else(hunt).unwrap_or_else(|| {
+                    // Random movement if there's nothing to go after
                     let directions = [(0, 1), (1, 0), (0, -1), (-1, 0)];
-                    let dir = directions[rand::random::<usize>() % 4];
+                    let dir = directions[rng.gen_range(0..directions.len())];
                     Position {
                         x: position.x + dir.0,
                         y: position.y + dir.1,
                     }
-                }
+                })
             },
-            // Other animals move randomly
+            // Prey idles, grazes or flees; anything else moves randomly
             _ => {
-                let directions = [(0, 1), (1, 0), (0, -1), (-1, 0)];
-                let dir = directions[rand::random::<usize>() % 4];
-                Position {
-                    x: position.x + dir.0,
-                    y: position.y + dir.1,
+                if let Some(prey) = prey.as_deref_mut() {
+                    prey_step(prey, position, &threats, (player_pos.x, player_pos.y), &path_maps, &map, rng)
+                } else {
+                    let directions = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+                    let dir = directions[rng.gen_range(0..directions.len())];
+                    Position {
+                        x: position.x + dir.0,
+                        y: position.y + dir.1,
+                    }
                 }
             }
         };
         
+        // Idling animals stay where they are, now and then humming to themselves
+        if target_pos == *position {
+            let idle = prey.as_deref().map_or(false, |prey| prey.behavior == AnimalBehavior::Idle);
+            if idle && rng.gen_bool(IDLE_NOTE_CHANCE) {
+                emote_events.send(ShowEmote::icon(entity, EmoteKind::Note));
+            }
+            continue;
+        }
+        
         // Check if the target position is valid (walkable)
         if map.is_position_walkable(target_pos.x, target_pos.y) {
             println!("Animal moving from ({}, {}) to ({}, {}) on turn {}", 
                      position.x, position.y, target_pos.x, target_pos.y, game_turn.current_turn);
             
+            if let Some(facing) = facing.as_deref_mut() {
+                facing.turn_toward((position.x, position.y), (target_pos.x, target_pos.y));
+            }
+            
             // Determine horizontal movement direction for sprite flipping
             let moving_right = target_pos.x > position.x;
             let moving_left = target_pos.x < position.x;
@@ -638,15 +971,282 @@ pub fn move_animals_system(
             
             // Update the position component
             commands.entity(entity).insert(target_pos);
+            tile_events.send(TileEntered { entity, x: target_pos.x, y: target_pos.y });
+        }
+    }
+}
+
+// System for hostile animals next to the player to attack
+pub fn animal_attack_system(
+    mut animal_query: Query<(&Animal, &Position, &CombatStats, Option<&StatusEffects>, Option<&Morale>), (With<Hostile>, Without<Companion>)>,
+    mut player_query: Query<(Entity, &Position, &mut Health), With<Player>>,
+    path_maps: Res<PathMaps>,
+    mut status_events: EventWriter<ApplyStatusEffect>,
+    mut message_log: ResMut<MessageLog>,
+    mut damage_events: EventWriter<EntityDamaged>,
+    mut noise_events: EventWriter<NoiseEvent>,
+    game_turn: Res<GameTurn>,
+    mut local: Local<u32>,
+) {
+    // Only attack once per turn
+    if game_turn.current_turn == 0 || game_turn.current_turn == *local {
+        return;
+    }
+    *local = game_turn.current_turn;
+    
+    let (player_entity, player_pos, mut health) = if let Ok(player) = player_query.get_single_mut() {
+        player
+    } else {
+        return;
+    };
+    
+    for (animal, position, stats, status, morale) in animal_query.iter_mut() {
+        if status.map_or(false, |status| status.skips_turn(game_turn.current_turn)) {
+            continue;
+        }
+        // A fleeing monster only fights back once it's cornered
+        let fleeing = morale.map_or(false, |morale| morale.fleeing);
+        if fleeing && path_maps.flee.downhill_from(position.x, position.y).is_some() {
+            continue;
+        }
+        
+        let distance = (position.x - player_pos.x).abs() + (position.y - player_pos.y).abs();
+        if distance > 1 {
+            continue;
+        }
+        
+        health.take_damage(stats.attack);
+        noise_events.send(NoiseEvent { x: position.x, y: position.y, kind: NoiseKind::Combat });
+        damage_events.send(EntityDamaged {
+            target: player_entity,
+            amount: stats.attack,
+            source: format!("a {}", animal.animal_type.get_name()),
+        });
+        message_log.add_message(format!("The {} attacks you for {} damage", animal.animal_type.get_name(), stats.attack));
+        
+        if let Some(effect) = attack_status_effect(animal.animal_type) {
+            status_events.send(ApplyStatusEffect { target: player_entity, effect });
+        }
+        
+        if health.is_dead() {
+            message_log.add_message("You have been slain...".to_string());
+            break;
+        }
+    }
+}
+
+// Start a one-tile hop animation from one tile to another
+pub fn start_animal_hop(
+    animation: &mut AnimalAnimation,
+    sprite: &mut TextureAtlasSprite,
+    from: Position,
+    to: Position,
+) {
+    // Animal sprites face left, so flip when moving right
+    if to.x != from.x {
+        animation.facing_right = to.x > from.x;
+        sprite.flip_x = animation.facing_right;
+    }
+    
+    animation.is_moving = true;
+    animation.start_pos = Vec3::new(
+        from.x as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+        from.y as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+        7.0
+    );
+    animation.target_pos = Vec3::new(
+        to.x as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+        to.y as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+        7.0
+    );
+    animation.animation_timer.reset();
+}
+
+// System to let the player feed an adjacent animal (T key) in the hope of taming it
+pub fn feed_animal_system(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut player_query: Query<(&Position, &mut Inventory), With<Player>>,
+    mut animal_query: Query<(Entity, &Animal, &Position, &mut Npc), Without<Companion>>,
+    mut game_turn: ResMut<GameTurn>,
+    mut game_rng: ResMut<GameRng>,
+    mut tamed_events: EventWriter<AnimalTamed>,
+    mut emote_events: EventWriter<ShowEmote>,
+) {
+    // SHIFT+T is reserved for the turn counter
+    if !keyboard_input.just_pressed(KeyCode::T) || keyboard_input.pressed(KeyCode::ShiftLeft) {
+        return;
+    }
+    
+    let (player_pos, mut inventory) = if let Ok(player) = player_query.get_single_mut() {
+        player
+    } else {
+        return;
+    };
+    
+    // Find an animal next to the player
+    let adjacent = animal_query.iter_mut().find(|(_, _, position, _)| {
+        (position.x - player_pos.x).abs() + (position.y - player_pos.y).abs() == 1
+    });
+    
+    let (entity, animal, _, mut npc) = if let Some(animal) = adjacent {
+        animal
+    } else {
+        println!("There is no animal close enough to feed");
+        return;
+    };
+    
+    let food = if let Some(food) = inventory.take_food() {
+        food
+    } else {
+        println!("You have nothing to feed the {}", animal.animal_type.get_name());
+        return;
+    };
+    
+    // Feeding takes a turn whether or not it works
+    game_turn.increment();
+    emote_events.send(ShowEmote::icon(entity, EmoteKind::Heart));
+    
+    if game_rng.combat.gen_bool(tame_chance(animal.animal_type)) {
+        commands.entity(entity).insert(Companion).remove::<Hostile>();
+        npc.dialog = vec![format!("The {} stays close by your side.", animal.animal_type.get_name())];
+        npc.dialog_text = npc.dialog[0].clone();
+        npc.current_dialog_index = 0;
+        tamed_events.send(AnimalTamed { entity });
+        println!("You feed the {} a {}. It decides to follow you!", animal.animal_type.get_name(), food.get_name());
+    } else {
+        println!("You feed the {} a {}, but it remains wary", animal.animal_type.get_name(), food.get_name());
+    }
+}
+
+// System to move tamed animals: they attack nearby hostiles, otherwise follow the player
+pub fn move_companions_system(
+    mut commands: Commands,
+    mut companion_query: Query<(Entity, &Animal, &Position, &CombatStats, &mut AnimalAnimation, &mut TextureAtlasSprite), With<Companion>>,
+    mut hostile_query: Query<(Entity, &Position, &mut Health, Option<&Npc>), (With<Hostile>, Without<Companion>)>,
+    player_query: Query<&Position, With<Player>>,
+    path_maps: Res<PathMaps>,
+    game_turn: Res<GameTurn>,
+    mut damage_events: EventWriter<EntityDamaged>,
+    mut local: Local<u32>,
+) {
+    // Only act once per turn
+    if game_turn.current_turn == 0 || game_turn.current_turn == *local {
+        return;
+    }
+    *local = game_turn.current_turn;
+    
+    let player_pos = if let Ok(pos) = player_query.get_single() {
+        *pos
+    } else {
+        return;
+    };
+    
+    // Tiles claimed by companions this turn so they don't stack up
+    let mut occupied: Vec<(i32, i32)> = companion_query.iter().map(|(_, _, position, ..)| (position.x, position.y)).collect();
+    
+    for (entity, animal, position, stats, mut animation, mut sprite) in companion_query.iter_mut() {
+        // Attack the first hostile standing next to us
+        let mut attacked = false;
+        for (hostile, hostile_pos, mut health, npc) in hostile_query.iter_mut() {
+            if health.is_dead() {
+                continue;
+            }
+            if (hostile_pos.x - position.x).abs() + (hostile_pos.y - position.y).abs() == 1 {
+                let killed = health.take_damage(stats.attack);
+                damage_events.send(EntityDamaged {
+                    target: hostile,
+                    amount: stats.attack,
+                    source: format!("your {}", animal.animal_type.get_name()),
+                });
+                let target_name = npc.map_or("enemy".to_string(), |npc| npc.name.clone());
+                println!("Your {} attacks {} for {} damage", animal.animal_type.get_name(), target_name, stats.attack);
+                if killed {
+                    println!("Your {} has slain {}", animal.animal_type.get_name(), target_name);
+                }
+                attacked = true;
+                break;
+            }
+        }
+        if attacked {
+            continue;
+        }
+        
+        // Already next to the player, nothing to do
+        if (player_pos.x - position.x).abs() + (player_pos.y - position.y).abs() <= 1 {
+            continue;
+        }
+        
+        // Follow the player, taking a single step down the distance map
+        if let Some(next) = path_maps.to_player.downhill_from(position.x, position.y) {
+            // Never step onto the player or another companion
+            if next == (player_pos.x, player_pos.y) || occupied.contains(&next) {
+                continue;
+            }
+            
+            occupied.retain(|&tile| tile != (position.x, position.y));
+            occupied.push(next);
+            
+            let target_pos = Position::new(next.0, next.1);
+            start_animal_hop(&mut animation, &mut sprite, *position, target_pos);
+            commands.entity(entity).insert(target_pos);
         }
     }
 }
 
+// Move companions onto free floor tiles around a target tile (used after changing levels)
+pub fn place_companions_near(
+    commands: &mut Commands,
+    companion_query: &mut Query<(Entity, &mut Transform, &mut AnimalAnimation), (With<Companion>, Without<Player>)>,
+    map: &TileMap,
+    target: (i32, i32),
+) {
+    // Collect walkable tiles around the target, nearest first
+    let mut spots = Vec::new();
+    for radius in 1..=3i32 {
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                if dx.abs().max(dy.abs()) != radius {
+                    continue;
+                }
+                let spot = (target.0 + dx, target.1 + dy);
+                if map.is_position_walkable(spot.0, spot.1) {
+                    spots.push(spot);
+                }
+            }
+        }
+    }
+    
+    if spots.is_empty() {
+        // Nowhere better to go, share the target tile
+        spots.push(target);
+    }
+    
+    for (i, (entity, mut transform, mut animation)) in companion_query.iter_mut().enumerate() {
+        let spot = spots[i % spots.len()];
+        let translation = Vec3::new(
+            spot.0 as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+            spot.1 as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+            7.0
+        );
+        
+        transform.translation = translation;
+        transform.rotation = Quat::IDENTITY;
+        animation.is_moving = false;
+        animation.start_pos = translation;
+        animation.target_pos = translation;
+        
+        commands.entity(entity).insert(Position::new(spot.0, spot.1));
+        println!("Companion follows you to ({}, {})", spot.0, spot.1);
+    }
+}
+
 // System to animate animal movement
 pub fn animate_animal_movement(
     time: Res<Time>,
-    mut animal_query: Query<(&Position, &mut Transform, &mut AnimalAnimation, &mut TextureAtlasSprite), With<Animal>>,
-    _animation_state: ResMut<crate::AnimationState>,
+    mut animal_query: Query<(&Position, &mut Transform, &mut AnimalAnimation, &mut TextureAtlasSprite), Or<(With<Animal>, With<crate::party::PartyMember>)>>,
+    _animation_state: ResMut<crate::player::AnimationState>,
+    animation_settings: Res<crate::animation_settings::AnimationSettings>,
 ) {
     // Track if any animal is currently moving (for debugging purposes)
     let mut _any_animal_moving = false;
@@ -667,8 +1267,8 @@ pub fn animate_animal_movement(
             // Use a sine curve for the hop (peaks at 0.5 progress)
             let hop_offset = (progress * std::f32::consts::PI).sin() * animation.hop_height;
             
-            // Interpolate between start and target positions
-            let current_pos = animation.start_pos.lerp(animation.target_pos, progress);
+            // Interpolate between start and target positions, along the configured curve
+            let current_pos = animation.start_pos.lerp(animation.target_pos, animation_settings.eased(progress));
             
             // Apply the hop offset to the y coordinate
             transform.translation = Vec3::new(
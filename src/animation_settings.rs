@@ -0,0 +1,129 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::components::{AnimalAnimation, PlayerAnimation};
+
+/// Where the movement animation tuning is read from, under assets/
+pub const ANIMATION_SETTINGS_PATH: &str = "data/animation.json";
+
+/// How a step eases from one tile to the next
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum Easing {
+    Linear,
+    #[default]
+    EaseOut, // Quick off the mark, settling into the tile
+    Bounce,  // Overshoots the landing and settles back
+}
+
+impl Easing {
+    // Map linear progress (0.0 to 1.0) onto the curve
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::Bounce => {
+                // easeOutBack: runs a little past the end before coming back
+                let overshoot = 1.70158;
+                let u = t - 1.0;
+                1.0 + (overshoot + 1.0) * u * u * u + overshoot * u * u
+            }
+        }
+    }
+}
+
+/// The hop-and-wobble of one kind of mover
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct MotionSettings {
+    pub duration: f32,      // Seconds per step
+    pub hop_height: f32,    // Pixels at the top of the hop
+    pub wobble_amount: f32, // Radians of tilt at the top of the hop
+}
+
+impl Default for MotionSettings {
+    fn default() -> Self {
+        Self { duration: 0.2, hop_height: 10.0, wobble_amount: 0.3 }
+    }
+}
+
+/// Tuning for how the player and animals move between tiles, from assets/data/animation.json.
+/// `--instant-movement` (or `instant_movement` in the file) skips the animation, for players who find it uncomfortable
+#[derive(Resource, Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AnimationSettings {
+    pub player: MotionSettings,
+    pub animal: MotionSettings,
+    pub easing: Easing,
+    pub instant_movement: bool,
+}
+
+impl Default for AnimationSettings {
+    fn default() -> Self {
+        Self {
+            player: MotionSettings::default(),
+            animal: MotionSettings { duration: 0.3, hop_height: 8.0, wobble_amount: 0.2 },
+            easing: Easing::default(),
+            instant_movement: false,
+        }
+    }
+}
+
+impl AnimationSettings {
+    // Read the settings, falling back to the defaults if the file is missing or broken
+    pub fn load() -> Self {
+        let path = Path::new("assets").join(ANIMATION_SETTINGS_PATH);
+        let mut settings = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                eprintln!("Could not parse animation settings {}: {}", path.display(), e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        };
+        if std::env::args().any(|arg| arg == "--instant-movement") {
+            settings.instant_movement = true;
+        }
+        settings
+    }
+
+    // The player's motion, flattened to nothing when movement is instant
+    pub fn player_motion(&self) -> MotionSettings {
+        if self.instant_movement { MotionSettings { duration: 0.0, hop_height: 0.0, wobble_amount: 0.0 } } else { self.player }
+    }
+
+    pub fn animal_motion(&self) -> MotionSettings {
+        if self.instant_movement { MotionSettings { duration: 0.0, hop_height: 0.0, wobble_amount: 0.0 } } else { self.animal }
+    }
+
+    // How far along a step is drawn, `progress` being the share of its time gone
+    pub fn eased(&self, progress: f32) -> f32 {
+        self.easing.apply(progress)
+    }
+}
+
+// System to give the player and animals the configured hop and wobble, as they appear and whenever the settings change
+pub fn apply_animation_settings(
+    settings: Res<AnimationSettings>,
+    mut player_query: Query<&mut PlayerAnimation>,
+    mut animal_query: Query<&mut AnimalAnimation>,
+) {
+    let player = settings.player_motion();
+    for mut animation in player_query.iter_mut() {
+        if settings.is_changed() || animation.is_added() {
+            animation.hop_height = player.hop_height;
+            animation.wobble_amount = player.wobble_amount;
+        }
+    }
+    // Animals reuse one timer, reset at every step
+    let animal = settings.animal_motion();
+    for mut animation in animal_query.iter_mut() {
+        if settings.is_changed() || animation.is_added() {
+            animation.hop_height = animal.hop_height;
+            animation.wobble_amount = animal.wobble_amount;
+            animation.animation_timer.set_duration(Duration::from_secs_f32(animal.duration));
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use bevy::prelude::*;
+
+use crate::input::TILE_SIZE;
+use crate::map::TileMap;
+use crate::visibility::{blocks_sight, bresenham_line, line_of_sight};
+
+// Cosine of the half-angle of a cone: a little over 45 degrees either side of where it points
+const CONE_COS: f32 = 0.68;
+
+/// The shape an area effect covers, from the tile it starts at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AreaShape {
+    Circle { radius: i32 },                     // Everything around the origin, the origin included
+    Cone { toward: (i32, i32), length: i32 },   // Fanning out from the origin toward a tile, the origin left out
+    Line { toward: (i32, i32), length: i32 },   // A straight run from the origin toward a tile, the origin left out
+    Cross { arm: i32 },                         // The origin and straight out along the row and column
+}
+
+// Whether a tile is inside a round area, rounded so radius 1 covers the whole 3x3 block
+fn within_radius(dx: i32, dy: i32, radius: i32) -> bool {
+    dx * dx + dy * dy <= radius * radius + radius
+}
+
+// Tiles along a run of points out from `origin`, stopping at the first wall or the edge of the map
+fn ray(map: &TileMap, origin: (i32, i32), points: impl Iterator<Item = (i32, i32)>) -> Vec<(i32, i32)> {
+    points
+        .filter(|&tile| tile != origin)
+        .take_while(|&(x, y)| !blocks_sight(x, y, map))
+        .collect()
+}
+
+// The tiles an area effect reaches: walls stop it (nothing behind one is touched) and it never
+// spills off the map. Shared by spells, thrown potions, traps and monster abilities
+pub fn area_tiles(map: &TileMap, origin: (i32, i32), shape: AreaShape) -> Vec<(i32, i32)> {
+    if blocks_sight(origin.0, origin.1, map) {
+        return Vec::new();
+    }
+    match shape {
+        AreaShape::Circle { radius } => {
+            let mut tiles = Vec::new();
+            for y in (origin.1 - radius)..=(origin.1 + radius) {
+                for x in (origin.0 - radius)..=(origin.0 + radius) {
+                    if within_radius(x - origin.0, y - origin.1, radius)
+                        && !blocks_sight(x, y, map)
+                        && line_of_sight(map, origin, (x, y))
+                    {
+                        tiles.push((x, y));
+                    }
+                }
+            }
+            tiles
+        }
+        AreaShape::Cone { toward, length } => {
+            let facing = ((toward.0 - origin.0) as f32, (toward.1 - origin.1) as f32);
+            let facing_length = (facing.0 * facing.0 + facing.1 * facing.1).sqrt();
+            if facing_length == 0.0 {
+                return Vec::new();
+            }
+            let mut tiles = Vec::new();
+            for y in (origin.1 - length)..=(origin.1 + length) {
+                for x in (origin.0 - length)..=(origin.0 + length) {
+                    let (dx, dy) = (x - origin.0, y - origin.1);
+                    if (dx, dy) == (0, 0) || !within_radius(dx, dy, length) {
+                        continue;
+                    }
+                    let cos = (dx as f32 * facing.0 + dy as f32 * facing.1) / (((dx * dx + dy * dy) as f32).sqrt() * facing_length);
+                    if cos >= CONE_COS && !blocks_sight(x, y, map) && line_of_sight(map, origin, (x, y)) {
+                        tiles.push((x, y));
+                    }
+                }
+            }
+            tiles
+        }
+        AreaShape::Line { toward, length } => {
+            if toward == origin {
+                return Vec::new();
+            }
+            // Carry on past the tile aimed at, out to the full length
+            let (dx, dy) = (toward.0 - origin.0, toward.1 - origin.1);
+            let scale = (length as f32 / dx.abs().max(dy.abs()) as f32).max(1.0);
+            let end = (origin.0 + (dx as f32 * scale).round() as i32, origin.1 + (dy as f32 * scale).round() as i32);
+            let points = bresenham_line(origin.0, origin.1, end.0, end.1).into_iter().take(length as usize + 1);
+            ray(map, origin, points)
+        }
+        AreaShape::Cross { arm } => {
+            let mut tiles = vec![origin];
+            for (step_x, step_y) in [(0, 1), (0, -1), (1, 0), (-1, 0)] {
+                let points = (1..=arm).map(|i| (origin.0 + step_x * i, origin.1 + step_y * i));
+                tiles.extend(ray(map, origin, points));
+            }
+            tiles
+        }
+    }
+}
+
+/// Tiles an area effect would reach, shown while aiming it. Empty when nothing is being aimed.
+/// Set it with `set_if_neq` so the overlay is only rebuilt when the area moves
+#[derive(Resource, Default, Debug, PartialEq)]
+pub struct AreaPreview {
+    pub tiles: Vec<(i32, i32)>,
+}
+
+/// One highlighted tile of the area preview
+#[derive(Component)]
+pub struct AreaPreviewTile;
+
+// System to redraw the area preview overlay whenever the previewed tiles change
+pub fn update_area_preview(
+    mut commands: Commands,
+    preview: Res<AreaPreview>,
+    tile_query: Query<Entity, With<AreaPreviewTile>>,
+) {
+    if !preview.is_changed() {
+        return;
+    }
+    for entity in tile_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    for &(x, y) in preview.tiles.iter() {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgba(1.0, 0.5, 0.2, 0.25),
+                    custom_size: Some(Vec2::splat(TILE_SIZE)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(x as f32 * TILE_SIZE + TILE_SIZE / 2.0, y as f32 * TILE_SIZE + TILE_SIZE / 2.0, 10.5),
+                ..default()
+            },
+            AreaPreviewTile,
+        ));
+    }
+}
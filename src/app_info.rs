@@ -0,0 +1,117 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy::winit::WinitWindows;
+use image::imageops;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+use winit::window::Icon;
+
+use crate::rng::GameRng;
+use crate::GameState;
+
+/// Where the window title and icon are read from, under assets/
+pub const APP_INFO_PATH: &str = "data/app.json";
+/// The version being run, from Cargo.toml
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// How the game presents itself to the desktop, from assets/data/app.json
+#[derive(Resource, Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AppInfo {
+    pub title: String,
+    pub icon: Option<String>,            // Image under assets/ for the window icon
+    pub icon_cell: Option<(u32, u32)>,   // Row and column of the icon in that image, if it's a sprite sheet
+    pub icon_tile_size: u32,             // Size of each cell in the sheet, in pixels
+}
+
+impl Default for AppInfo {
+    fn default() -> Self {
+        Self {
+            title: "Chasm".to_string(),
+            icon: Some("sprites/monsters.png".to_string()),
+            icon_cell: Some((5, 1)), // The reaper
+            icon_tile_size: 32,
+        }
+    }
+}
+
+impl AppInfo {
+    // Read the app info, falling back to the defaults if the file is missing or broken
+    pub fn load() -> Self {
+        let path = Path::new("assets").join(APP_INFO_PATH);
+        match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                eprintln!("Could not parse app info {}: {}", path.display(), e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    // "Chasm v0.1.0", with the run's seed once there is one
+    pub fn window_title(&self, seed: Option<u64>) -> String {
+        match seed {
+            Some(seed) => format!("{} v{} - seed {}", self.title, VERSION, seed),
+            None => format!("{} v{}", self.title, VERSION),
+        }
+    }
+
+    // The icon image, cut out of its sheet if it's on one
+    fn load_icon(&self) -> Result<Option<Icon>, String> {
+        let icon = if let Some(icon) = &self.icon { icon } else { return Ok(None); };
+        let path = Path::new("assets").join(icon);
+        let mut image = image::open(&path).map_err(|e| format!("could not open {}: {}", path.display(), e))?.into_rgba8();
+        if let Some((row, col)) = self.icon_cell {
+            let size = self.icon_tile_size;
+            if (col + 1) * size > image.width() || (row + 1) * size > image.height() {
+                return Err(format!("cell ({}, {}) is outside {}", row, col, path.display()));
+            }
+            image = imageops::crop_imm(&image, col * size, row * size, size, size).to_image();
+        }
+        let (width, height) = image.dimensions();
+        Icon::from_rgba(image.into_raw(), width, height).map(Some).map_err(|e| e.to_string())
+    }
+}
+
+// One line about this build, for the main menu
+pub fn build_info() -> String {
+    let profile = if cfg!(debug_assertions) { "debug" } else { "release" };
+    format!("v{} ({} build)", VERSION, profile)
+}
+
+// System to give the window its icon once it's open
+pub fn set_window_icon(app_info: Res<AppInfo>, winit_windows: NonSend<WinitWindows>) {
+    let icon = match app_info.load_icon() {
+        Ok(Some(icon)) => icon,
+        Ok(None) => return,
+        Err(e) => {
+            eprintln!("Could not set the window icon: {}", e);
+            return;
+        }
+    };
+    for window in winit_windows.windows.values() {
+        window.set_window_icon(Some(icon.clone()));
+    }
+}
+
+// System to keep the run's seed in the window title while a run is going, so it can be noted down and shared
+pub fn update_window_title(
+    app_info: Res<AppInfo>,
+    state: Res<State<GameState>>,
+    game_rng: Option<Res<GameRng>>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let rng_changed = game_rng.as_ref().map_or(false, |rng| rng.is_changed());
+    if !state.is_changed() && !rng_changed {
+        return;
+    }
+    let in_run = !matches!(state.get(), GameState::MainMenu | GameState::HallOfRecords | GameState::Codex);
+    let seed = game_rng.filter(|_| in_run).map(|rng| rng.seed());
+    let title = app_info.window_title(seed);
+    for mut window in window_query.iter_mut() {
+        if window.title != title {
+            window.title = title.clone();
+        }
+    }
+}
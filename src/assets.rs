@@ -1,10 +1,10 @@
 use bevy::prelude::*;
 use bevy::sprite::TextureAtlas;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{self, BufRead};
 use std::path::Path;
 
+use crate::manifest::{AssetManifest, AtlasManifest, ManifestError};
+
 /// Resource that holds all sprite mappings
 #[derive(Resource)]
 pub struct SpriteAssets {
@@ -13,6 +13,7 @@ pub struct SpriteAssets {
     pub monster_sprites: HashMap<String, usize>,
     pub item_sprites: HashMap<String, usize>,
     pub animal_sprites: HashMap<String, usize>,
+    pub tile_tags: HashMap<String, Vec<String>>,
 }
 
 /// Resource that holds all texture atlas handles
@@ -33,204 +34,80 @@ impl Default for SpriteAssets {
             monster_sprites: HashMap::new(),
             item_sprites: HashMap::new(),
             animal_sprites: HashMap::new(),
+            tile_tags: HashMap::new(),
         }
     }
 }
 
-/// Parse a sprite sheet metadata file and return a mapping of sprite names to indices
-fn parse_sprite_metadata(file_path: &str) -> io::Result<HashMap<String, usize>> {
-    let path = Path::new("assets").join(file_path);
-    
-    let file = match File::open(&path) {
-        Ok(f) => f,
-        Err(e) => {
-            // Create an empty map with fallbacks
-            let mut empty_map = HashMap::new();
-            // Add fallback sprites
-            empty_map.insert("wall".to_string(), 21);
-            empty_map.insert("floor".to_string(), 126);
-            empty_map.insert("door".to_string(), 338);
-            empty_map.insert("staircase down".to_string(), 343);
-            empty_map.insert("staircase up".to_string(), 344);
-            return Ok(empty_map);
-        }
-    };
-    
-    let reader = io::BufReader::new(file);
-    let mut sprite_map = HashMap::new();
-
-    // Determine columns per row based on the file
-    let columns_per_row = match file_path {
-        "sprites/tiles.txt" => 21,    // 672/32 = 21
-        "sprites/rogues.txt" => 6,    // 192/32 = 6
-        "sprites/monsters.txt" => 12, // 384/32 = 12
-        "sprites/items.txt" => 8,     // 256/32 = 8
-        "sprites/animals.txt" => 9,   // 288/32 = 9
-        _ => 16, // Default fallback
-    };
-
-    // Determine max index based on the file
-    let max_index = match file_path {
-        "sprites/tiles.txt" => 21 * 24,    // 21 columns × 24 rows = 504
-        "sprites/rogues.txt" => 6 * 7,     // 6 columns × 7 rows = 42
-        "sprites/monsters.txt" => 12 * 13, // 12 columns × 13 rows = 156
-        "sprites/items.txt" => 8 * 22,     // 8 columns × 22 rows = 176
-        "sprites/animals.txt" => 9 * 16,   // 9 columns × 16 rows = 144
-        _ => 256, // Default fallback
-    };
+impl SpriteAssets {
+    /// Names of every tile sprite carrying a tag (e.g. "wall", "floor", "door")
+    pub fn tiles_with_tag(&self, tag: &str) -> Vec<&str> {
+        self.tile_tags.iter()
+            .filter(|(_, tags)| tags.iter().any(|t| t == tag))
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+}
 
-    for line in reader.lines() {
-        let line = line?;
-        if line.trim().is_empty() {
-            continue;
-        }
+// The grid layout of one manifest atlas
+pub fn atlas_layout(atlas: &AtlasManifest, asset_server: &AssetServer) -> TextureAtlas {
+    TextureAtlas::from_grid(
+        asset_server.load(atlas.image.clone()),
+        Vec2::splat(atlas.tile_size),
+        atlas.columns, atlas.rows,
+        None, None
+    )
+}
 
-        // Parse lines like "1.a. dirt wall (top)"
-        if let Some(pos) = line.find('.') {
-            if let Some(second_pos) = line[pos+1..].find('.') {
-                let row_str = line[..pos].trim();
-                if let Ok(row) = row_str.parse::<usize>() {
-                    let col_char = line[pos+1..pos+1+second_pos].trim();
-                    let name = line[pos+1+second_pos+1..].trim().to_string();
-                    
-                    // Convert column letter to index (a=0, b=1, etc.)
-                    let col = match col_char {
-                        "a" => 0,
-                        "b" => 1,
-                        "c" => 2,
-                        "d" => 3,
-                        "e" => 4,
-                        "f" => 5,
-                        "g" => 6,
-                        "h" => 7,
-                        "i" => 8,
-                        "j" => 9,
-                        "k" => 10,
-                        "l" => 11,
-                        "m" => 12,
-                        "n" => 13,
-                        "o" => 14,
-                        "p" => 15,
-                        _ => 0,
-                    };
-                    
-                    // Calculate index in the sprite sheet (row * columns_per_row + column)
-                    // Ensure row is 0-indexed for calculation
-                    let index = ((row - 1) * columns_per_row) + col;
-                    
-                    // Only add if the index is within bounds
-                    if index < max_index {
-                        sprite_map.insert(name, index);
-                    }
-                }
-            }
+/// Build the sprite name mappings from a validated manifest
+pub fn sprite_assets_from_manifest(manifest: &AssetManifest) -> SpriteAssets {
+    // Validation guarantees all of these exist
+    let tiles = manifest.atlas("tiles").unwrap();
+
+    let mut tile_sprites = tiles.sprite_indices();
+
+    // Generic names the fallback helpers below look up
+    for (alias, sprite) in [
+        ("wall", "rough stone wall (top)"),
+        ("floor", "blank floor (dark grey)"),
+        ("door", "framed door 1 (shut)"),
+    ] {
+        if let Some(&index) = tile_sprites.get(sprite) {
+            tile_sprites.entry(alias.to_string()).or_insert(index);
         }
     }
 
-    // If the map is empty, add some fallback sprites
-    if sprite_map.is_empty() {
-        // Add some fallback sprites with correct indices
-        // Wall: 2.a rough stone wall (top) = ((2-1) * 21) + 0 = 21
-        sprite_map.insert("wall".to_string(), 21);
-        
-        // Floor: 7.a blank floor (dark grey) = ((7-1) * 21) + 0 = 126
-        sprite_map.insert("floor".to_string(), 126);
-        
-        // Door: 17.c framed door 1 (shut) = ((17-1) * 21) + 2 = 338
-        sprite_map.insert("door".to_string(), 338);
-        
-        // Stairs
-        sprite_map.insert("staircase down".to_string(), 343); // 17.h
-        sprite_map.insert("staircase up".to_string(), 344);   // 17.i
-    } else {
-        // Check if we have floor tiles
-        let has_floor = sprite_map.contains_key("blank floor (dark grey)") || 
-                        sprite_map.contains_key("floor") ||
-                        sprite_map.contains_key("floor stone 1");
-                        
-        if !has_floor {
-            sprite_map.insert("floor".to_string(), 126); // 7.a blank floor
-        }
+    SpriteAssets {
+        tile_sprites,
+        character_sprites: manifest.atlas("characters").unwrap().sprite_indices(),
+        monster_sprites: manifest.atlas("monsters").unwrap().sprite_indices(),
+        item_sprites: manifest.atlas("items").unwrap().sprite_indices(),
+        animal_sprites: manifest.atlas("animals").unwrap().sprite_indices(),
+        tile_tags: tiles.sprite_tags(),
     }
-
-    Ok(sprite_map)
 }
 
-/// Load all sprite assets
+/// Load all sprite assets described by the asset manifest
 pub fn load_sprite_assets(
     commands: &mut Commands,
     asset_server: Res<AssetServer>,
     mut texture_atlases: ResMut<Assets<TextureAtlas>>,
-) -> io::Result<()> {
-    // Load sprite metadata
-    let tile_sprites = parse_sprite_metadata("sprites/tiles.txt")?;
-    let character_sprites = parse_sprite_metadata("sprites/rogues.txt")?;
-    let monster_sprites = parse_sprite_metadata("sprites/monsters.txt")?;
-    let item_sprites = parse_sprite_metadata("sprites/items.txt")?;
-    let animal_sprites = parse_sprite_metadata("sprites/animals.txt")?;
-    
+) -> Result<(), ManifestError> {
+    let manifest = AssetManifest::load(Path::new("assets"))?;
+
     // Create sprite assets resource
-    commands.insert_resource(SpriteAssets {
-        tile_sprites,
-        character_sprites,
-        monster_sprites,
-        item_sprites,
-        animal_sprites,
-    });
-    
-    // Load texture atlases
-    let tiles_handle = asset_server.load("sprites/tiles.png");
-    let tiles_atlas = TextureAtlas::from_grid(
-        tiles_handle,
-        Vec2::new(32.0, 32.0),
-        21, 24,  // 672/32 = 21, 768/32 = 24
-        None, None
-    );
-    let tiles_atlas_handle = texture_atlases.add(tiles_atlas);
-    
-    let characters_handle = asset_server.load("sprites/rogues.png");
-    let characters_atlas = TextureAtlas::from_grid(
-        characters_handle,
-        Vec2::new(32.0, 32.0),
-        6, 7,  // 192/32 = 6, 224/32 = 7
-        None, None
-    );
-    let characters_atlas_handle = texture_atlases.add(characters_atlas);
-    
-    let monsters_handle = asset_server.load("sprites/monsters.png");
-    let monsters_atlas = TextureAtlas::from_grid(
-        monsters_handle,
-        Vec2::new(32.0, 32.0),
-        12, 13,  // 384/32 = 12, 416/32 = 13
-        None, None
-    );
-    let monsters_atlas_handle = texture_atlases.add(monsters_atlas);
-    
-    let items_handle = asset_server.load("sprites/items.png");
-    let items_atlas = TextureAtlas::from_grid(
-        items_handle,
-        Vec2::new(32.0, 32.0),
-        8, 22,  // 256/32 = 8, 704/32 = 22
-        None, None
-    );
-    let items_atlas_handle = texture_atlases.add(items_atlas);
-    
-    let animals_handle = asset_server.load("sprites/animals.png");
-    let animals_atlas = TextureAtlas::from_grid(
-        animals_handle,
-        Vec2::new(32.0, 32.0),
-        9, 16,  // 288/32 = 9, 512/32 = 16
-        None, None
-    );
-    let animals_atlas_handle = texture_atlases.add(animals_atlas);
-    
+    commands.insert_resource(sprite_assets_from_manifest(&manifest));
+
     // Create texture atlases resource
+    let mut add_atlas = |name: &str| {
+        texture_atlases.add(atlas_layout(manifest.atlas(name).unwrap(), &asset_server))
+    };
     commands.insert_resource(TextureAtlases {
-        tiles: tiles_atlas_handle,
-        characters: characters_atlas_handle,
-        monsters: monsters_atlas_handle,
-        items: items_atlas_handle,
-        animals: animals_atlas_handle,
+        tiles: add_atlas("tiles"),
+        characters: add_atlas("characters"),
+        monsters: add_atlas("monsters"),
+        items: add_atlas("items"),
+        animals: add_atlas("animals"),
     });
     
     Ok(())
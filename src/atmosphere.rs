@@ -0,0 +1,295 @@
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::biome::BiomeType;
+use crate::camera::CameraControl;
+use crate::components::{Player, Position};
+use crate::display_settings::{DisplaySettings, Palette};
+use crate::frame_timing::smoothing;
+use crate::input::TILE_SIZE;
+use crate::lighting::LightFixture;
+use crate::map::{TileMap, VIEWPORT_HEIGHT, VIEWPORT_WIDTH};
+use crate::GameState;
+
+// Above the map and creatures, below the UI
+const PARTICLE_Z: f32 = 20.0;
+// Heat rises from every lava vent in view, this many particles each
+const SHIMMER_PER_VENT: usize = 3;
+// Never keep more particles than this alive, however large the view
+const MAX_PARTICLES: usize = 80;
+// How quickly the screen tint moves toward a new biome's, per second
+const GRADE_BLEND_SPEED: f32 = 1.5;
+
+/// Wires up the per-biome particles that drift around the camera view, and the screen tint
+pub struct BiomeAmbiencePlugin;
+
+impl Plugin for BiomeAmbiencePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                maintain_particle_pool,
+                update_particles.after(maintain_particle_pool),
+                update_color_grade,
+            )
+                .run_if(in_state(GameState::InGame))
+                .run_if(resource_exists::<TileMap>())
+        )
+        .add_systems(OnEnter(GameState::InGame), spawn_color_grade)
+        .add_systems(OnExit(GameState::InGame), (
+            crate::menu::despawn_screen::<AmbientParticle>,
+            crate::menu::despawn_screen::<ColorGrade>,
+        ));
+    }
+}
+
+/// The look and motion of each kind of particle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParticleKind {
+    Spore,    // Groves: slow, swaying, drifting upward
+    Dust,     // Catacombs and Labyrinth: barely moving motes
+    Drip,     // Caves: falling water
+    Shimmer,  // Rising heat over lava
+}
+
+impl ParticleKind {
+    fn color(&self) -> Color {
+        match self {
+            ParticleKind::Spore => Color::rgba(0.75, 1.0, 0.5, 0.6),
+            ParticleKind::Dust => Color::rgba(0.85, 0.8, 0.7, 0.35),
+            ParticleKind::Drip => Color::rgba(0.5, 0.7, 1.0, 0.7),
+            ParticleKind::Shimmer => Color::rgba(1.0, 0.55, 0.2, 0.25),
+        }
+    }
+
+    fn size(&self) -> Vec2 {
+        match self {
+            ParticleKind::Spore => Vec2::splat(2.0),
+            ParticleKind::Dust => Vec2::splat(1.5),
+            ParticleKind::Drip => Vec2::new(1.0, 3.0),
+            ParticleKind::Shimmer => Vec2::new(4.0, 6.0),
+        }
+    }
+
+    // Lifetime in seconds, and starting velocity in pixels per second
+    fn roll(&self, rng: &mut impl Rng) -> (f32, Vec2) {
+        match self {
+            ParticleKind::Spore => (rng.gen_range(4.0..8.0), Vec2::new(rng.gen_range(-3.0..3.0), rng.gen_range(2.0..5.0))),
+            ParticleKind::Dust => (rng.gen_range(5.0..10.0), Vec2::new(rng.gen_range(-1.5..1.5), rng.gen_range(-1.5..1.5))),
+            ParticleKind::Drip => (rng.gen_range(0.6..1.2), Vec2::new(0.0, -rng.gen_range(30.0..50.0))),
+            ParticleKind::Shimmer => (rng.gen_range(1.0..2.0), Vec2::new(0.0, rng.gen_range(6.0..10.0))),
+        }
+    }
+
+    // How many of this kind a biome wants in a full view
+    fn density(biome: BiomeType) -> (ParticleKind, usize) {
+        match biome {
+            BiomeType::Groves => (ParticleKind::Spore, 40),
+            BiomeType::Catacombs => (ParticleKind::Dust, 50),
+            BiomeType::Caves => (ParticleKind::Drip, 12),
+            BiomeType::Labyrinth => (ParticleKind::Dust, 15),
+        }
+    }
+}
+
+/// One drifting speck; it's moved back into view and reused rather than despawned
+#[derive(Component)]
+pub struct AmbientParticle {
+    kind: ParticleKind,
+    velocity: Vec2,
+    life: Timer,
+    phase: f32, // Offsets the sway so particles don't move in step
+}
+
+// The part of the world the camera can see, as (min, max) corners
+fn view_rect(camera: &Transform, projection: &OrthographicProjection) -> (Vec2, Vec2) {
+    let half = Vec2::new(VIEWPORT_WIDTH as f32, VIEWPORT_HEIGHT as f32) * TILE_SIZE * projection.scale / 2.0;
+    let center = camera.translation.truncate();
+    (center - half, center + half)
+}
+
+// Pick what a particle should be and where it starts
+fn place_particle(
+    kind: ParticleKind,
+    vents: &[Vec2],
+    view: (Vec2, Vec2),
+    rng: &mut impl Rng,
+) -> (Vec3, AmbientParticle) {
+    let start = if kind == ParticleKind::Shimmer {
+        let vent = vents.choose(rng).copied().unwrap_or((view.0 + view.1) / 2.0);
+        vent + Vec2::new(rng.gen_range(-TILE_SIZE / 2.0..TILE_SIZE / 2.0), rng.gen_range(0.0..TILE_SIZE / 2.0))
+    } else {
+        Vec2::new(rng.gen_range(view.0.x..view.1.x), rng.gen_range(view.0.y..view.1.y))
+    };
+    let (seconds, velocity) = kind.roll(rng);
+    (
+        start.extend(PARTICLE_Z),
+        AmbientParticle {
+            kind,
+            velocity,
+            life: Timer::from_seconds(seconds, TimerMode::Once),
+            phase: rng.gen_range(0.0..std::f32::consts::TAU),
+        },
+    )
+}
+
+// System to keep the right number of particles around the camera for the biome in view
+fn maintain_particle_pool(
+    mut commands: Commands,
+    map: Res<TileMap>,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<CameraControl>>,
+    vent_query: Query<(&LightFixture, &Position)>,
+    particle_query: Query<(Entity, &AmbientParticle)>,
+) {
+    let (camera, projection) = if let Ok(camera) = camera_query.get_single() { camera } else { return; };
+    let view = view_rect(camera, projection);
+
+    // The biome under the middle of the screen sets the mood
+    let center_x = ((camera.translation.x / TILE_SIZE) as i32).clamp(0, map.width as i32 - 1) as usize;
+    let center_y = ((camera.translation.y / TILE_SIZE) as i32).clamp(0, map.height as i32 - 1) as usize;
+    let (kind, wanted) = ParticleKind::density(map.get_biome_at(center_x, center_y));
+    // Zoomed out views hold more, up to the cap
+    let wanted = ((wanted as f32 * projection.scale * projection.scale) as usize).min(MAX_PARTICLES);
+
+    let vents: Vec<Vec2> = vent_query.iter()
+        .filter(|(fixture, _)| **fixture == LightFixture::LavaVent)
+        .map(|(_, pos)| Vec2::new(pos.x as f32 * TILE_SIZE + TILE_SIZE / 2.0, pos.y as f32 * TILE_SIZE + TILE_SIZE / 2.0))
+        .filter(|vent| vent.x >= view.0.x && vent.x <= view.1.x && vent.y >= view.0.y && vent.y <= view.1.y)
+        .collect();
+    let wanted_shimmer = vents.len() * SHIMMER_PER_VENT;
+
+    // Particles are only for show, so they don't draw from the run's seed
+    let mut rng = rand::thread_rng();
+    for (target_kind, target) in [(kind, wanted), (ParticleKind::Shimmer, wanted_shimmer)] {
+        let existing: Vec<Entity> = particle_query.iter()
+            .filter(|(_, particle)| particle.kind == target_kind)
+            .map(|(entity, _)| entity)
+            .collect();
+        // Spawn a few a frame so they fade in rather than pop in together
+        for _ in existing.len()..target.min(existing.len() + 4) {
+            let (translation, particle) = place_particle(target_kind, &vents, view, &mut rng);
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite { color: target_kind.color(), custom_size: Some(target_kind.size()), ..default() },
+                    transform: Transform::from_translation(translation),
+                    ..default()
+                },
+                particle,
+            ));
+        }
+        for &entity in existing.iter().skip(target) {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    // Anything left over from a biome no longer in view goes
+    for (entity, particle) in particle_query.iter() {
+        if particle.kind != kind && particle.kind != ParticleKind::Shimmer {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+// System to drift, fade and recycle particles
+fn update_particles(
+    time: Res<Time>,
+    camera_query: Query<(&Transform, &OrthographicProjection), (With<CameraControl>, Without<AmbientParticle>)>,
+    vent_query: Query<(&LightFixture, &Position)>,
+    mut particle_query: Query<(&mut Transform, &mut Sprite, &mut AmbientParticle)>,
+) {
+    let (camera, projection) = if let Ok(camera) = camera_query.get_single() { camera } else { return; };
+    let view = view_rect(camera, projection);
+    let vents: Vec<Vec2> = vent_query.iter()
+        .filter(|(fixture, _)| **fixture == LightFixture::LavaVent)
+        .map(|(_, pos)| Vec2::new(pos.x as f32 * TILE_SIZE + TILE_SIZE / 2.0, pos.y as f32 * TILE_SIZE + TILE_SIZE / 2.0))
+        .collect();
+    // Only for show, like the spawning above
+    let mut rng = rand::thread_rng();
+    let elapsed = time.elapsed_seconds();
+
+    for (mut transform, mut sprite, mut particle) in particle_query.iter_mut() {
+        particle.life.tick(time.delta());
+        let position = transform.translation.truncate();
+        let out_of_view = position.x < view.0.x || position.x > view.1.x || position.y < view.0.y || position.y > view.1.y;
+
+        if particle.life.finished() || (out_of_view && particle.kind != ParticleKind::Shimmer) {
+            let (translation, fresh) = place_particle(particle.kind, &vents, view, &mut rng);
+            transform.translation = translation;
+            *particle = fresh;
+            continue;
+        }
+
+        // Spores and heat sway from side to side as they rise
+        let sway = match particle.kind {
+            ParticleKind::Spore => (elapsed * 1.5 + particle.phase).sin() * 4.0,
+            ParticleKind::Shimmer => (elapsed * 6.0 + particle.phase).sin() * 8.0,
+            _ => 0.0,
+        };
+        transform.translation.x += (particle.velocity.x + sway) * time.delta_seconds();
+        transform.translation.y += particle.velocity.y * time.delta_seconds();
+
+        // Fade in over the first half second and out over the last
+        let fade = (particle.life.elapsed_secs() * 2.0).min(particle.life.remaining_secs() * 2.0).min(1.0);
+        sprite.color.set_a(particle.kind.color().a() * fade);
+    }
+}
+
+// The tint laid over the whole view in each biome; the colorblind set keeps Groves and Labyrinth
+// apart by swapping their green and red for yellow and blue-grey
+fn biome_grade(biome: BiomeType, palette: Palette) -> Color {
+    match (palette, biome) {
+        (Palette::Standard, BiomeType::Caves) => Color::rgba(0.2, 0.4, 0.9, 0.12),
+        (Palette::Standard, BiomeType::Groves) => Color::rgba(0.45, 0.8, 0.2, 0.1),
+        (Palette::Standard, BiomeType::Labyrinth) => Color::rgba(0.9, 0.3, 0.15, 0.1),
+        (Palette::Standard, BiomeType::Catacombs) => Color::rgba(0.55, 0.5, 0.65, 0.12),
+        (Palette::Colorblind, BiomeType::Caves) => Color::rgba(0.0, 0.45, 0.7, 0.12),
+        (Palette::Colorblind, BiomeType::Groves) => Color::rgba(0.95, 0.9, 0.25, 0.1),
+        (Palette::Colorblind, BiomeType::Labyrinth) => Color::rgba(0.9, 0.6, 0.0, 0.1),
+        (Palette::Colorblind, BiomeType::Catacombs) => Color::rgba(0.4, 0.4, 0.45, 0.14),
+    }
+}
+
+/// A fullscreen overlay that tints the world by biome; it sits under the rest of the UI
+#[derive(Component)]
+pub struct ColorGrade;
+
+fn spawn_color_grade(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            background_color: Color::NONE.into(),
+            z_index: ZIndex::Global(-10),
+            ..default()
+        },
+        ColorGrade,
+    ));
+}
+
+// System to ease the tint toward the biome the player is standing in
+fn update_color_grade(
+    time: Res<Time>,
+    map: Res<TileMap>,
+    display_settings: Res<DisplaySettings>,
+    player_query: Query<&Position, With<Player>>,
+    mut grade_query: Query<&mut BackgroundColor, With<ColorGrade>>,
+) {
+    let pos = if let Ok(pos) = player_query.get_single() { pos } else { return; };
+    if !map.in_bounds(pos.x, pos.y) {
+        return;
+    }
+    let target = biome_grade(map.get_biome_at(pos.x as usize, pos.y as usize), display_settings.palette);
+    let blend = smoothing(GRADE_BLEND_SPEED, time.delta_seconds());
+
+    for mut background in grade_query.iter_mut() {
+        let current = background.0.as_rgba_f32();
+        let target = target.as_rgba_f32();
+        let mixed: Vec<f32> = current.iter().zip(target.iter()).map(|(from, to)| from + (to - from) * blend).collect();
+        background.0 = Color::rgba(mixed[0], mixed[1], mixed[2], mixed[3]);
+    }
+}
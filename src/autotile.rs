@@ -0,0 +1,162 @@
+use crate::biome::BiomeType;
+use crate::map::{TileMap, TileType};
+
+// Neighbor bits of a wall mask (y grows upward, so "south" is y - 1)
+pub const NORTH: u8 = 1;
+pub const EAST: u8 = 2;
+pub const SOUTH: u8 = 4;
+pub const WEST: u8 = 8;
+
+// Walls of one material stay together within a chunk this many tiles wide
+const MATERIAL_CHUNK_SIZE: usize = 12;
+
+/// Which kind of wall sprite a position needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WallVariant {
+    Top,     // Seen from above; the default
+    Face,    // Front face, open floor below and wall continuing to both sides
+    FaceEnd, // Front face at the end of a run or on a lone pillar
+    Inner,   // Surrounded by wall on all four sides
+}
+
+impl WallVariant {
+    // The variant to try when a biome has no sprite for this one
+    fn fallback(&self) -> Option<WallVariant> {
+        match self {
+            WallVariant::Top => None,
+            WallVariant::Face => Some(WallVariant::Top),
+            WallVariant::FaceEnd => Some(WallVariant::Face),
+            WallVariant::Inner => Some(WallVariant::Top),
+        }
+    }
+}
+
+/// One wall material with a sprite name for each variant it supports
+pub struct WallMaterial {
+    pub top: &'static str,
+    pub face: Option<&'static str>,
+    pub face_end: Option<&'static str>,
+    pub inner: Option<&'static str>,
+}
+
+impl WallMaterial {
+    fn sprite_for(&self, variant: WallVariant) -> Option<&'static str> {
+        match variant {
+            WallVariant::Top => Some(self.top),
+            WallVariant::Face => self.face,
+            WallVariant::FaceEnd => self.face_end,
+            WallVariant::Inner => self.inner,
+        }
+    }
+
+    // The sprite for a variant, walking the fallback chain past sprites that aren't available
+    pub fn resolve(&self, variant: WallVariant, is_available: impl Fn(&str) -> bool) -> Option<&'static str> {
+        let mut current = Some(variant);
+        while let Some(variant) = current {
+            if let Some(name) = self.sprite_for(variant).filter(|name| is_available(name)) {
+                return Some(name);
+            }
+            current = variant.fallback();
+        }
+        None
+    }
+}
+
+// The wall materials each biome is built from
+pub fn autotile_materials(biome: BiomeType) -> Vec<WallMaterial> {
+    match biome {
+        BiomeType::Caves | BiomeType::Groves => vec![
+            WallMaterial {
+                top: "dirt wall (top)",
+                face: Some("dirt wall (side)"),
+                face_end: None,
+                inner: Some("inner wall"),
+            },
+            WallMaterial {
+                top: "rough stone wall (top)",
+                face: Some("rough stone wall (side)"),
+                face_end: None,
+                inner: None,
+            },
+            WallMaterial {
+                top: "igneous wall (top)",
+                face: Some("igneous wall (side)"),
+                face_end: None,
+                inner: None,
+            },
+        ],
+        BiomeType::Labyrinth => vec![
+            WallMaterial {
+                top: "stone brick wall (top)",
+                face: Some("stone brick wall (side 1)"),
+                face_end: Some("stone brick wall (side 2)"),
+                inner: None,
+            },
+            WallMaterial {
+                top: "large stone wall (top)",
+                face: Some("large stone wall (side)"),
+                face_end: None,
+                inner: None,
+            },
+        ],
+        BiomeType::Catacombs => vec![
+            WallMaterial {
+                top: "catacombs / skull wall (top)",
+                face: Some("catacombs / skull walls (side)"),
+                face_end: None,
+                inner: None,
+            },
+            WallMaterial {
+                top: "stone brick wall (top)",
+                face: Some("stone brick wall (side 1)"),
+                face_end: Some("stone brick wall (side 2)"),
+                inner: None,
+            },
+        ],
+    }
+}
+
+// Walls and secret doors both look like wall; the map edge counts as wall too
+fn is_wall_like(map: &TileMap, x: i32, y: i32) -> bool {
+    if !map.in_bounds(x, y) {
+        return true;
+    }
+    matches!(map.tiles[y as usize][x as usize], TileType::Wall | TileType::SecretDoor)
+}
+
+/// 4-bit mask of which orthogonal neighbors are wall
+pub fn wall_mask(map: &TileMap, x: usize, y: usize) -> u8 {
+    let (x, y) = (x as i32, y as i32);
+    let mut mask = 0;
+    if is_wall_like(map, x, y + 1) { mask |= NORTH; }
+    if is_wall_like(map, x + 1, y) { mask |= EAST; }
+    if is_wall_like(map, x, y - 1) { mask |= SOUTH; }
+    if is_wall_like(map, x - 1, y) { mask |= WEST; }
+    mask
+}
+
+/// Map a neighbor mask to the wall variant drawn there
+pub fn variant_for_mask(mask: u8) -> WallVariant {
+    if mask & SOUTH == 0 {
+        // Open below: the viewer sees the wall's front face
+        if mask & (EAST | WEST) == (EAST | WEST) {
+            WallVariant::Face
+        } else {
+            WallVariant::FaceEnd
+        }
+    } else if mask == NORTH | EAST | SOUTH | WEST {
+        WallVariant::Inner
+    } else {
+        WallVariant::Top
+    }
+}
+
+/// Pick the material used around a position, stable across a chunk so walls connect
+pub fn material_index_at(x: usize, y: usize, material_count: usize) -> usize {
+    if material_count == 0 {
+        return 0;
+    }
+    let chunk_x = x / MATERIAL_CHUNK_SIZE;
+    let chunk_y = y / MATERIAL_CHUNK_SIZE;
+    ((chunk_x * 7919) ^ (chunk_y * 104729)) % material_count
+}
@@ -0,0 +1,166 @@
+use bevy::ecs::system::RunSystemOnce;
+use bevy::prelude::*;
+use criterion::{black_box, BatchSize, BenchmarkId, Criterion};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::path::Path;
+
+use crate::assets::{sprite_assets_from_manifest, SpriteAssets, TextureAtlases};
+use crate::biome::BiomeManager;
+use crate::manifest::AssetManifest;
+use crate::map::{is_boss_level, map_size_for_level, spawn_tiles, TileMap, MAP_HEIGHT, MAP_WIDTH, MAX_MAP_HEIGHT, MAX_MAP_WIDTH};
+use crate::sim::validate_map;
+use crate::visibility::{line_of_sight, visible_tiles_from};
+
+// Every benchmark draws from this, so runs compare like with like
+const BENCH_SEED: u64 = 0xC4A5;
+// Sizes the layout stages are timed at: the first floor, the deepest, and twice that to see how it scales
+const LAYOUT_SIZES: [(usize, usize); 3] = [(MAP_WIDTH, MAP_HEIGHT), (MAX_MAP_WIDTH, MAX_MAP_HEIGHT), (MAX_MAP_WIDTH * 2, MAX_MAP_HEIGHT * 2)];
+// Levels generated whole: the first, the first boss floor, and one deep enough to be full size
+const LEVELS: [usize; 3] = [0, 4, 15];
+// How far the player sees in the dark, and with a light
+const FOV_RADII: [f32; 2] = [6.0, 12.0];
+
+// Whole levels, as the game builds them when the stairs are taken
+fn bench_generate_level(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("generate_level");
+    for level in LEVELS {
+        let (width, height) = map_size_for_level(level);
+        let label = format!("depth {} ({}x{}{})", level + 1, width, height, if is_boss_level(level) { ", boss" } else { "" });
+        group.bench_function(BenchmarkId::from_parameter(label), |b| {
+            b.iter(|| TileMap::generate_level(black_box(level), black_box(BENCH_SEED)))
+        });
+    }
+    group.finish();
+}
+
+// The layout pass, and room placement and corridors on their own, as the map grows
+fn bench_layout(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("layout");
+    for (width, height) in LAYOUT_SIZES {
+        let size = format!("{}x{}", width, height);
+        group.bench_function(BenchmarkId::new("generate_map", &size), |b| {
+            let mut rng = StdRng::seed_from_u64(BENCH_SEED);
+            b.iter(|| TileMap::bench_layout(0, width, height, &mut rng))
+        });
+        group.bench_function(BenchmarkId::new("rooms", &size), |b| {
+            let mut rng = StdRng::seed_from_u64(BENCH_SEED);
+            b.iter(|| TileMap::bench_rooms(width, height, &mut rng))
+        });
+        group.bench_function(BenchmarkId::new("connect_rooms", &size), |b| {
+            let mut room_rng = StdRng::seed_from_u64(BENCH_SEED);
+            let mut corridor_rng = StdRng::seed_from_u64(BENCH_SEED);
+            b.iter_batched(
+                || TileMap::bench_rooms(width, height, &mut room_rng),
+                |(mut tiles, rooms)| TileMap::bench_connect_rooms(&mut tiles, &rooms, &mut corridor_rng),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+// The flood fill that checks everything on a level can be reached from the spawn
+fn bench_validation(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("validate_map");
+    for level in LEVELS {
+        let map = TileMap::generate_level(level, BENCH_SEED);
+        group.bench_function(BenchmarkId::from_parameter(format!("{}x{}", map.width, map.height)), |b| {
+            b.iter(|| validate_map(black_box(&map)))
+        });
+    }
+    group.finish();
+}
+
+// Field of view from the spawn point, and a sight line from corner to corner
+fn bench_fov(criterion: &mut Criterion) {
+    let map = TileMap::generate_level(LEVELS[LEVELS.len() - 1], BENCH_SEED);
+    let origin = (map.spawn_position.0 as i32, map.spawn_position.1 as i32);
+    let mut group = criterion.benchmark_group("fov");
+    for radius in FOV_RADII {
+        group.bench_function(BenchmarkId::new("visible_tiles_from", radius), |b| {
+            b.iter(|| visible_tiles_from(black_box(&map), origin, radius))
+        });
+    }
+    let far_corner = (map.width as i32 - 1, map.height as i32 - 1);
+    group.bench_function("line_of_sight", |b| {
+        b.iter(|| line_of_sight(black_box(&map), (0, 0), far_corner))
+    });
+    group.finish();
+}
+
+// A world with what spawning tiles reads: the real sprite names and biome tiles, and empty
+// texture handles, since nothing is drawn
+fn visuals_world() -> World {
+    let sprite_assets = AssetManifest::load(Path::new("assets"))
+        .map(|manifest| sprite_assets_from_manifest(&manifest))
+        .unwrap_or_default();
+    let mut biome_manager = BiomeManager::default();
+    biome_manager.initialize_default_tiles(&sprite_assets.tile_sprites);
+
+    let mut world = World::new();
+    world.insert_resource(sprite_assets);
+    world.insert_resource(biome_manager);
+    world.insert_resource(TextureAtlases {
+        tiles: Handle::default(),
+        characters: Handle::default(),
+        monsters: Handle::default(),
+        items: Handle::default(),
+        animals: Handle::default(),
+    });
+    world
+}
+
+// Spawn a level's tiles, rewriting the `reuse` entities first, and apply the commands to the world
+fn spawn_level_tiles(world: &mut World, map: &TileMap, reuse: Vec<Entity>) -> Vec<Entity> {
+    let map = map.clone();
+    world.run_system_once(move |mut commands: Commands, atlases: Res<TextureAtlases>, sprites: Res<SpriteAssets>, biomes: Res<BiomeManager>| {
+        spawn_tiles(&mut commands, &map, &atlases, &sprites, Some(&biomes), &reuse)
+    })
+}
+
+// The tile entities for a level: spawned fresh, as on the first floor, and written over the
+// last floor's, as the stairs do. This is most of what generate_map_visuals costs
+fn bench_map_visuals(criterion: &mut Criterion) {
+    let map = TileMap::generate_level(LEVELS[LEVELS.len() - 1], BENCH_SEED);
+    let previous = TileMap::generate_level(LEVELS[LEVELS.len() - 2], BENCH_SEED);
+    let mut group = criterion.benchmark_group("map_visuals");
+    group.bench_function(BenchmarkId::new("spawn", format!("{}x{}", map.width, map.height)), |b| {
+        b.iter_batched(
+            visuals_world,
+            |mut world| {
+                spawn_level_tiles(&mut world, &map, Vec::new());
+                world
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function(BenchmarkId::new("reuse", format!("{}x{}", map.width, map.height)), |b| {
+        b.iter_batched(
+            || {
+                let mut world = visuals_world();
+                let entities = spawn_level_tiles(&mut world, &previous, Vec::new());
+                (world, entities)
+            },
+            |(mut world, entities)| {
+                let reuse = entities[..entities.len().min(map.width * map.height)].to_vec();
+                spawn_level_tiles(&mut world, &map, reuse);
+                world
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+// Time the map generator with criterion: `cargo run --release --features bench -- --bench [filter]`.
+// Tile entities are spawned into a bare ECS world, so drawing them isn't covered
+pub fn run() {
+    let mut criterion = Criterion::default().configure_from_args();
+    bench_generate_level(&mut criterion);
+    bench_layout(&mut criterion);
+    bench_validation(&mut criterion);
+    bench_fov(&mut criterion);
+    bench_map_visuals(&mut criterion);
+    criterion.final_summary();
+}
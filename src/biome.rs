@@ -2,9 +2,12 @@ use bevy::prelude::*;
 use std::collections::HashMap;
 use rand::Rng;
 use rand::rngs::StdRng;
+use serde::Deserialize;
+
+use crate::path_noise::{on_ridge, PathNoise, PerlinPathNoise};
 
 /// Represents different biome types in the game
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
 pub enum BiomeType {
     Caves,      // Cave areas with dirt and stone walls
     Groves,     // Overgrown areas with grass and plants
@@ -12,6 +15,41 @@ pub enum BiomeType {
     Catacombs,  // Areas with skull walls and bone floors
 }
 
+impl BiomeType {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            BiomeType::Caves => "Caves",
+            BiomeType::Groves => "Groves",
+            BiomeType::Labyrinth => "Labyrinth",
+            BiomeType::Catacombs => "Catacombs",
+        }
+    }
+    // The colour a region is marked with on maps and overlays
+    pub fn color(&self) -> Color {
+        match self {
+            BiomeType::Caves => Color::rgb(0.6, 0.4, 0.2),
+            BiomeType::Groves => Color::rgb(0.2, 0.8, 0.3),
+            BiomeType::Labyrinth => Color::rgb(0.3, 0.5, 0.9),
+            BiomeType::Catacombs => Color::rgb(0.8, 0.8, 0.7),
+        }
+    }
+
+    // What the region is known for, shown under its name when it's first entered
+    pub fn epithet(&self) -> &'static str {
+        match self {
+            BiomeType::Caves => "the dark goes down forever",
+            BiomeType::Groves => "something grows here without the sun",
+            BiomeType::Labyrinth => "every wall was built to lose you",
+            BiomeType::Catacombs => "the dead rest uneasily",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        [BiomeType::Caves, BiomeType::Groves, BiomeType::Labyrinth, BiomeType::Catacombs]
+            .into_iter()
+            .find(|biome| biome.get_name().eq_ignore_ascii_case(name))
+    }
+}
+
 /// Represents the walkability status of a tile
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TileWalkability {
@@ -20,6 +58,16 @@ pub enum TileWalkability {
     Door,       // Special case - can be walked through but requires interaction
 }
 
+impl TileWalkability {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            TileWalkability::Walkable => "Walkable",
+            TileWalkability::Blocked => "Blocked",
+            TileWalkability::Door => "Passable (door)",
+        }
+    }
+}
+
 /// Stores information about a specific tile type
 #[derive(Debug, Clone)]
 pub struct TileInfo {
@@ -28,6 +76,14 @@ pub struct TileInfo {
     pub walkability: TileWalkability,
     pub biome: BiomeType,
     pub color: Color,
+    pub animation: Option<TileAnimationDef>,
+}
+
+/// Frames an animated tile cycles through
+#[derive(Debug, Clone)]
+pub struct TileAnimationDef {
+    pub frames: Vec<usize>,  // Atlas indices, starting with the tile's own sprite
+    pub frame_time: f32,     // Seconds each frame is shown
 }
 
 /// Resource that manages biome-specific tile information
@@ -37,6 +93,7 @@ pub struct BiomeManager {
     pub walkable_tiles: Vec<TileInfo>,
     pub wall_tiles: Vec<TileInfo>,
     pub door_tiles: Vec<TileInfo>,
+    pub path_noise: Box<dyn PathNoise>, // Traces the winding paths through floors
 }
 
 impl Default for BiomeManager {
@@ -46,6 +103,7 @@ impl Default for BiomeManager {
             walkable_tiles: Vec::new(),
             wall_tiles: Vec::new(),
             door_tiles: Vec::new(),
+            path_noise: Box::new(PerlinPathNoise::default()),
         }
     }
 }
@@ -53,6 +111,19 @@ impl Default for BiomeManager {
 impl BiomeManager {
     /// Register a tile with its properties
     pub fn register_tile(&mut self, name: &str, sprite_index: usize, walkability: TileWalkability, biome: BiomeType) {
+        self.add_tile(name, sprite_index, walkability, biome, None);
+    }
+
+    /// Register a tile that cycles through several sprites, e.g. shimmering water
+    pub fn register_animated_tile(&mut self, name: &str, frames: Vec<usize>, frame_time: f32, walkability: TileWalkability, biome: BiomeType) {
+        if frames.is_empty() {
+            return;
+        }
+        let sprite_index = frames[0];
+        self.add_tile(name, sprite_index, walkability, biome, Some(TileAnimationDef { frames, frame_time }));
+    }
+
+    fn add_tile(&mut self, name: &str, sprite_index: usize, walkability: TileWalkability, biome: BiomeType, animation: Option<TileAnimationDef>) {
         // Determine the appropriate color based on the biome or tile name
         let color = match biome {
             BiomeType::Caves => Color::rgb(0.5, 0.5, 0.5), // Grey for caves
@@ -67,6 +138,7 @@ impl BiomeManager {
             walkability,
             biome,
             color,
+            animation,
         };
         
         // Add to biome-specific collection
@@ -81,7 +153,29 @@ impl BiomeManager {
             TileWalkability::Door => self.door_tiles.push(tile_info),
         }
     }
-    
+
+    /// Get an animated floor tile (water, embers...) for a specific biome, if it has one
+    pub fn get_animated_floor_tile(&self, biome: BiomeType, x: usize, y: usize) -> Option<&TileInfo> {
+        let animated_tiles: Vec<&TileInfo> = self.biome_tiles.get(&biome)?
+            .iter()
+            .filter(|tile| tile.walkability == TileWalkability::Walkable && tile.animation.is_some())
+            .collect();
+
+        if animated_tiles.is_empty() {
+            return None;
+        }
+
+        Some(animated_tiles[(x * 31 + y * 17) % animated_tiles.len()])
+    }
+
+    /// Whether a floor position falls inside one of the biome's pools of animated tiles
+    pub fn is_in_animated_pool(&self, x: usize, y: usize) -> bool {
+        // Low frequency noise so the pools form small blobs rather than lone tiles
+        let fx = x as f32 * 0.31;
+        let fy = y as f32 * 0.27;
+        (fx.sin() + fy.cos() + (fx * 0.5 + fy * 0.7).sin()) > 2.4
+    }
+
     /// Get a random walkable tile for a specific biome
     pub fn get_random_floor_tile(&self, biome: BiomeType, rng: &mut impl Rng) -> Option<&TileInfo> {
         let biome_tiles = self.biome_tiles.get(&biome)?;
@@ -113,7 +207,7 @@ impl BiomeManager {
     }
     
     /// Get a wall tile based on its position in the map
-    /// This function selects between top and side wall tiles based on context
+    /// Autotiles from the 4-neighbor wall mask so faces, ends and wall interiors connect
     pub fn get_wall_tile_for_position(
         &self, 
         biome: BiomeType, 
@@ -123,79 +217,23 @@ impl BiomeManager {
         rng: &mut impl Rng
     ) -> Option<&TileInfo> {
         let biome_tiles = self.biome_tiles.get(&biome)?;
-        
-        // Filter wall tiles by type (top or side)
-        let top_wall_tiles: Vec<&TileInfo> = biome_tiles.iter()
-            .filter(|tile| 
-                tile.walkability == TileWalkability::Blocked && 
-                tile.name.contains("(top)")
-            )
-            .collect();
-            
-        let side_wall_tiles: Vec<&TileInfo> = biome_tiles.iter()
-            .filter(|tile| 
-                tile.walkability == TileWalkability::Blocked && 
-                tile.name.contains("(side)")
-            )
-            .collect();
-        
-        if top_wall_tiles.is_empty() && side_wall_tiles.is_empty() {
-            return None;
-        }
-        
-        // PRIMARY RULE: If this wall is directly above a floor tile, use a side wall tile
-        let is_above_floor = y > 0 && map.tiles[y-1][x] == crate::map::TileType::Floor;
-        
-        if is_above_floor && !side_wall_tiles.is_empty() {
-            // This wall is directly above a floor tile, so use a side wall tile
-            let index = rng.gen_range(0..side_wall_tiles.len());
-            return Some(side_wall_tiles[index]);
-        }
-        
-        // For walls not directly above floor tiles, use top wall tiles
-        // But add some variety by occasionally using side tiles for visual interest
-        let has_floor_left = x > 0 && map.tiles[y][x-1] == crate::map::TileType::Floor;
-        let has_floor_right = x < crate::map::MAP_WIDTH - 1 && map.tiles[y][x+1] == crate::map::TileType::Floor;
-        
-        // If there are adjacent floor tiles, consider using a side wall for visual interest
-        let should_use_side_tile = (has_floor_left || has_floor_right) && rng.gen_bool(0.3); // 30% chance
-        
-        if should_use_side_tile && !side_wall_tiles.is_empty() {
-            let index = rng.gen_range(0..side_wall_tiles.len());
-            Some(side_wall_tiles[index])
-        } else if !top_wall_tiles.is_empty() {
-            // Default to top wall tiles for most cases
-            let index = rng.gen_range(0..top_wall_tiles.len());
-            Some(top_wall_tiles[index])
-        } else if !side_wall_tiles.is_empty() {
-            // Fallback to side wall if no top walls are available
-            let index = rng.gen_range(0..side_wall_tiles.len());
-            Some(side_wall_tiles[index])
-        } else {
-            // Fallback to any wall tile
-            let wall_tiles: Vec<&TileInfo> = biome_tiles.iter()
-                .filter(|tile| tile.walkability == TileWalkability::Blocked)
-                .collect();
-                
-            if wall_tiles.is_empty() {
-                return None;
+        let find_wall = |name: &str| biome_tiles.iter()
+            .find(|tile| tile.walkability == TileWalkability::Blocked && tile.name == name);
+
+        let variant = crate::autotile::variant_for_mask(crate::autotile::wall_mask(map, x, y));
+        let materials = crate::autotile::autotile_materials(biome);
+
+        // Start with the material of this chunk, then try the others if its sprites aren't registered
+        let first = crate::autotile::material_index_at(x, y, materials.len());
+        for offset in 0..materials.len() {
+            let material = &materials[(first + offset) % materials.len()];
+            if let Some(name) = material.resolve(variant, |name| find_wall(name).is_some()) {
+                return find_wall(name);
             }
-            
-            let index = rng.gen_range(0..wall_tiles.len());
-            Some(wall_tiles[index])
         }
-    }
-    
-    /// Helper method to check if a position has a side wall tile
-    /// This is used to determine if a wall should be rendered as a side wall
-    fn is_side_wall_at(&self, _biome: BiomeType, x: usize, y: usize, map: &crate::map::TileMap) -> bool {
-        // If it's not a wall, it's definitely not a side wall
-        if map.tiles[y][x] != crate::map::TileType::Wall {
-            return false;
-        }
-        
-        // A wall is a side wall if it's directly above a floor tile
-        y > 0 && map.tiles[y-1][x] == crate::map::TileType::Floor
+
+        // Fallback to any wall tile
+        self.get_random_wall_tile(biome, rng)
     }
     
     /// Get a varied floor tile for a specific biome and position
@@ -206,6 +244,8 @@ impl BiomeManager {
         let floor_tiles: Vec<&TileInfo> = biome_tiles.iter()
             .filter(|tile| 
                 tile.walkability == TileWalkability::Walkable && 
+                tile.animation.is_none() &&
+                !Self::path_tile_names(biome).contains(&tile.name.as_str()) &&
                 !tile.name.contains("stair") && 
                 !tile.name.contains("staircase"))
             .collect();
@@ -243,199 +283,60 @@ impl BiomeManager {
     }
     
     /// Determine if a position should be part of a path
-    /// This uses noise functions to create winding paths
+    /// Paths follow the zero lines of the path noise, so they wind and branch naturally
     pub fn is_on_path(&self, x: usize, y: usize) -> bool {
-        // Convert coordinates to floating point for smoother calculations
-        let fx = x as f32 * 0.15;  // Adjust frequency for wider spacing between paths
-        let fy = y as f32 * 0.15;
-        
-        // Create primary winding path
-        let primary_path_value = (fx.sin() * 3.0 + fy.cos() * 3.0).abs();
-        let primary_path = primary_path_value < 0.6;  // Thinner primary path
-        
-        // Create secondary path with different frequency
-        let secondary_path_value = ((fx * 0.5).sin() * 4.0 + (fy * 0.5).cos() * 4.0).abs();
-        let secondary_path = secondary_path_value < 0.5;  // Even thinner secondary path
-        
-        // Create path branches/offshoots
-        let branch_seed = (x * 7 + y * 13) % 100;
-        let branch_path = branch_seed < 15 && (  // Only 15% chance of branch
-            ((fx * 0.3).sin() * 2.0 + (fy * 0.3).cos() * 2.0).abs() < 0.4
-        );
-        
-        // Create path width variation (1-2 tiles wide)
-        let width_variation = (x * 11 + y * 17) % 10;
-        let is_wider_path = width_variation < 4;  // 40% chance of wider path
+        let (fx, fy) = (x as f64 * 0.08, y as f64 * 0.08);
         
-        // Check if position is on any path
-        if primary_path || secondary_path || branch_path {
-            // For wider paths, include adjacent tiles
-            if is_wider_path {
-                // Check if this is an edge tile of a path
-                let edge_value = if primary_path {
-                    primary_path_value
-                } else if secondary_path {
-                    secondary_path_value
-                } else {
-                    0.3  // Default for branch paths
-                };
-                
-                // Edge tiles have values close to the threshold
-                return edge_value < 0.8;  // Wider threshold for edge tiles
-            }
-            return true;
-        }
-        
-        false
+        // A main path, plus fainter side paths from a second, offset slice of the noise
+        on_ridge(self.path_noise.as_ref(), fx, fy, 0.06)
+            || on_ridge(self.path_noise.as_ref(), fx * 1.7 + 100.0, fy * 1.7 + 100.0, 0.04)
     }
     
     /// Determine if a position should be part of a path for a specific biome
     /// This creates different path patterns for each biome
     pub fn is_on_biome_path(&self, biome: BiomeType, x: usize, y: usize) -> bool {
-        // Convert coordinates to floating point for smoother calculations
-        let fx = x as f32 * 0.15;
-        let fy = y as f32 * 0.15;
+        let noise = self.path_noise.as_ref();
         
         match biome {
+            // Caves: wide, meandering paths
             BiomeType::Caves => {
-                // Caves biome: More meandering paths with more branches
-                let primary_path_value = (fx.sin() * 2.5 + fy.cos() * 2.5).abs();
-                let primary_path = primary_path_value < 0.55;
-                
-                let secondary_path_value = ((fx * 0.4).sin() * 3.5 + (fy * 0.4).cos() * 3.5).abs();
-                let secondary_path = secondary_path_value < 0.45;
-                
-                // More branches in caves biome
-                let branch_seed = (x * 9 + y * 11) % 100;
-                let branch_path = branch_seed < 20 && (  // 20% chance of branch
-                    ((fx * 0.25).sin() * 1.8 + (fy * 0.25).cos() * 1.8).abs() < 0.5
-                );
-                
-                // Width variation - caves paths tend to be wider
-                let width_variation = (x * 13 + y * 19) % 10;
-                let is_wider_path = width_variation < 6;  // 60% chance of wider path
-                
-                if primary_path || secondary_path || branch_path {
-                    if is_wider_path {
-                        let edge_value = if primary_path {
-                            primary_path_value
-                        } else if secondary_path {
-                            secondary_path_value
-                        } else {
-                            0.35
-                        };
-                        return edge_value < 0.85;  // Wider threshold for caves paths
-                    }
-                    return true;
-                }
-            },
-            BiomeType::Groves => {
-                // Groves biome: Winding paths with occasional branches
-                let primary_path_value = (fx.sin() * 3.0 + fy.cos() * 3.0).abs();
-                let primary_path = primary_path_value < 0.6;
-                
-                let secondary_path_value = ((fx * 0.5).sin() * 4.0 + (fy * 0.5).cos() * 4.0).abs();
-                let secondary_path = secondary_path_value < 0.5;
-                
-                let branch_seed = (x * 7 + y * 13) % 100;
-                let branch_path = branch_seed < 15 && (
-                    ((fx * 0.3).sin() * 2.0 + (fy * 0.3).cos() * 2.0).abs() < 0.4
-                );
-                
-                // Width variation
-                let width_variation = (x * 11 + y * 17) % 10;
-                let is_wider_path = width_variation < 4;  // 40% chance of wider path
-                
-                if primary_path || secondary_path || branch_path {
-                    if is_wider_path {
-                        let edge_value = if primary_path {
-                            primary_path_value
-                        } else if secondary_path {
-                            secondary_path_value
-                        } else {
-                            0.3
-                        };
-                        return edge_value < 0.8;
-                    }
-                    return true;
-                }
-            },
-            BiomeType::Labyrinth => {
-                // Labyrinth biome: Straighter paths with sharp turns
-                // Use a different approach for labyrinth - more grid-like paths
-                
-                // Main horizontal paths
-                let h_path_value = (fy * 5.0).sin().abs();
-                let h_path = h_path_value < 0.3 && (x * 3 + y * 5) % 7 != 0;  // Occasional gaps
-                
-                // Main vertical paths
-                let v_path_value = (fx * 5.0).sin().abs();
-                let v_path = v_path_value < 0.3 && (x * 5 + y * 3) % 7 != 0;  // Occasional gaps
-                
-                // Diagonal connectors
-                let diag_seed = (x * 11 + y * 13) % 100;
-                let diag_path = diag_seed < 10 && (  // 10% chance of diagonal connector
-                    ((fx + fy) * 0.4).sin().abs() < 0.25
-                );
-                
-                // Width variation - labyrinth paths are mostly narrow
-                let width_variation = (x * 7 + y * 23) % 10;
-                let is_wider_path = width_variation < 3;  // 30% chance of wider path
-                
-                if h_path || v_path || diag_path {
-                    if is_wider_path {
-                        let edge_value = if h_path {
-                            h_path_value
-                        } else if v_path {
-                            v_path_value
-                        } else {
-                            0.2
-                        };
-                        return edge_value < 0.6;
-                    }
-                    return true;
-                }
-            },
-            BiomeType::Catacombs => {
-                // Catacombs biome: Straighter paths with sharp turns
-                // Use a different approach for catacombs - more grid-like paths
-                
-                // Main horizontal paths
-                let h_path_value = (fy * 5.0).sin().abs();
-                let h_path = h_path_value < 0.3 && (x * 3 + y * 5) % 7 != 0;  // Occasional gaps
-                
-                // Main vertical paths
-                let v_path_value = (fx * 5.0).sin().abs();
-                let v_path = v_path_value < 0.3 && (x * 5 + y * 3) % 7 != 0;  // Occasional gaps
-                
-                // Diagonal connectors
-                let diag_seed = (x * 11 + y * 13) % 100;
-                let diag_path = diag_seed < 10 && (  // 10% chance of diagonal connector
-                    ((fx + fy) * 0.4).sin().abs() < 0.25
-                );
-                
-                // Width variation - catacombs paths are mostly narrow
-                let width_variation = (x * 7 + y * 23) % 10;
-                let is_wider_path = width_variation < 3;  // 30% chance of wider path
-                
-                if h_path || v_path || diag_path {
-                    if is_wider_path {
-                        let edge_value = if h_path {
-                            h_path_value
-                        } else if v_path {
-                            v_path_value
-                        } else {
-                            0.2
-                        };
-                        return edge_value < 0.6;
-                    }
-                    return true;
-                }
-            },
-            _ => return self.is_on_path(x, y)  // Use default path logic for other biomes
+                let (fx, fy) = (x as f64 * 0.07, y as f64 * 0.07);
+                on_ridge(noise, fx, fy, 0.09) || on_ridge(noise, fx * 1.5 + 50.0, fy * 1.5 + 50.0, 0.05)
+            }
+            // Groves: narrower trails
+            BiomeType::Groves => self.is_on_path(x, y),
+            // Labyrinth and catacombs: blocky paths with sharp turns, sampled on a coarse grid
+            BiomeType::Labyrinth | BiomeType::Catacombs => {
+                let (cx, cy) = ((x / 3) as f64 * 0.25, (y / 3) as f64 * 0.25);
+                let offset = if biome == BiomeType::Labyrinth { 0.0 } else { 200.0 };
+                on_ridge(noise, cx + offset, cy + offset, 0.12)
+            }
+        }
+    }
+    
+    /// Floor tiles used for paths through a biome; they aren't used for ordinary floor
+    pub fn path_tile_names(biome: BiomeType) -> &'static [&'static str] {
+        match biome {
+            BiomeType::Caves => &["dirt 1", "dirt 2", "dirt 3"],
+            BiomeType::Groves => &["dirt 1 (green bg)", "dirt 2 (green bg)", "dirt 3 (green bg)"],
+            BiomeType::Labyrinth => &["red stone floor 1 (red bg)", "red stone floor 2 (red bg)", "red stone floor 3 (red bg)"],
+            BiomeType::Catacombs => &["bone 1", "bone 2", "bone 3"],
+        }
+    }
+    
+    /// Get a path tile for a specific biome, varied by position
+    pub fn get_path_tile(&self, biome: BiomeType, x: usize, y: usize) -> Option<&TileInfo> {
+        let names = Self::path_tile_names(biome);
+        let path_tiles: Vec<&TileInfo> = self.biome_tiles.get(&biome)?
+            .iter()
+            .filter(|tile| names.contains(&tile.name.as_str()))
+            .collect();
+        
+        if path_tiles.is_empty() {
+            return None;
         }
         
-        false
+        Some(path_tiles[(x * 7 + y * 13) % path_tiles.len()])
     }
     
     /// Initialize with default tile mappings
@@ -448,6 +349,9 @@ impl BiomeManager {
         if let Some(&index) = sprite_assets.get("dirt wall (side)") {
             self.register_tile("dirt wall (side)", index, TileWalkability::Blocked, BiomeType::Caves);
         }
+        if let Some(&index) = sprite_assets.get("inner wall") {
+            self.register_tile("inner wall", index, TileWalkability::Blocked, BiomeType::Caves);
+        }
         if let Some(&index) = sprite_assets.get("rough stone wall (top)") {
             self.register_tile("rough stone wall (top)", index, TileWalkability::Blocked, BiomeType::Caves);
         }
@@ -507,6 +411,9 @@ impl BiomeManager {
         if let Some(&index) = sprite_assets.get("dirt wall (side)") {
             self.register_tile("dirt wall (side)", index, TileWalkability::Blocked, BiomeType::Groves);
         }
+        if let Some(&index) = sprite_assets.get("inner wall") {
+            self.register_tile("inner wall", index, TileWalkability::Blocked, BiomeType::Groves);
+        }
         if let Some(&index) = sprite_assets.get("rough stone wall (top)") {
             self.register_tile("rough stone wall (top)", index, TileWalkability::Blocked, BiomeType::Groves);
         }
@@ -662,6 +569,28 @@ impl BiomeManager {
             self.register_tile("door 1", index, TileWalkability::Door, BiomeType::Catacombs);
         }
         
+        // Animated tiles: water shimmering in the caves, embers glowing in the labyrinth
+        let frames_for = |names: &[&str]| -> Vec<usize> {
+            names.iter().filter_map(|name| sprite_assets.get(*name).copied()).collect()
+        };
+        let water_frames = frames_for(&[
+            "blue stone floor 1 (blue bg)",
+            "blue stone floor 2 (blue bg)",
+            "blue stone floor 3 (blue bg)",
+            "blue stone floor 2 (blue bg)",
+        ]);
+        if water_frames.len() > 1 {
+            self.register_animated_tile("water", water_frames, 0.4, TileWalkability::Walkable, BiomeType::Caves);
+        }
+        let ember_frames = frames_for(&[
+            "red stone floor 1 (red bg)",
+            "blank red floor",
+            "red stone floor 3 (red bg)",
+        ]);
+        if ember_frames.len() > 1 {
+            self.register_animated_tile("embers", ember_frames, 0.25, TileWalkability::Walkable, BiomeType::Labyrinth);
+        }
+
         // Stair tiles for all biomes
         if let Some(&index) = sprite_assets.get("staircase down").or_else(|| sprite_assets.get("stairs down")) {
             self.register_tile("stairs down", index, TileWalkability::Walkable, BiomeType::Caves);
@@ -0,0 +1,233 @@
+use bevy::prelude::*;
+use bevy::window::ReceivedCharacter;
+use bevy::text::{Text2dBundle, TextAlignment};
+
+use crate::components::{Player, Position};
+use crate::conversation::Conversation;
+use crate::input::TILE_SIZE;
+use crate::inventory_panel::InventoryMenu;
+use crate::level::DungeonState;
+use crate::map::TileMap;
+use crate::pathmaps::PathMaps;
+use crate::run_log::RunReplay;
+use crate::throwing::ThrowTargeting;
+use crate::ui::MessageLog;
+use crate::virtual_cursor::VirtualCursor;
+
+const BOOKMARK_KEY: KeyCode = KeyCode::X;
+// What a new bookmark is called until the player names it
+const BOOKMARK_NAMES: [&str; 3] = [
+    "Marked spot",
+    "Chest I couldn't open",
+    "Sealed stairs",
+];
+const MAX_NAME_LEN: usize = 24;
+// Markers sit over tiles and props, under creatures
+const MARKER_Z: f32 = 3.5;
+const MARKER_COLOR: Color = Color::rgba(1.0, 0.85, 0.2, 0.9);
+const LABEL_FONT_SIZE: f32 = 9.0;
+
+/// A note the player has pinned to a tile
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bookmark {
+    pub tile: (i32, i32),
+    pub name: String,
+}
+
+/// The marker and label drawn for a bookmark on the current level
+#[derive(Component)]
+pub struct BookmarkMarker;
+
+/// A bookmark's name as it's being typed in
+#[derive(Debug, Clone)]
+pub struct NameEntry {
+    pub level: usize,
+    pub tile: (i32, i32),
+    pub text: String,
+}
+
+/// The bookmark being named, if any. While it is, typing goes into the name instead of the game
+#[derive(Resource, Debug, Default)]
+pub struct BookmarkNaming {
+    pub entry: Option<NameEntry>,
+}
+
+impl BookmarkNaming {
+    pub fn is_active(&self) -> bool {
+        self.entry.is_some()
+    }
+
+    fn begin(&mut self, level: usize, tile: (i32, i32)) {
+        self.entry = Some(NameEntry { level, tile, text: String::new() });
+    }
+}
+
+// The name a new bookmark starts with, from what's next to the tile
+fn suggested_name(map: &TileMap, tile: (i32, i32)) -> &'static str {
+    let near = |(x, y): (usize, usize)| (x as i32 - tile.0).abs() <= 1 && (y as i32 - tile.1).abs() <= 1;
+    if map.chest_positions.iter().any(|&chest| near(chest)) {
+        BOOKMARK_NAMES[1]
+    } else if map.down_stairs_locked && map.down_stairs_pos.map_or(false, near) {
+        BOOKMARK_NAMES[2]
+    } else {
+        BOOKMARK_NAMES[0]
+    }
+}
+
+// System to pin a bookmark with X on the player's tile, or the tile being looked at, and start naming it.
+// X again on a marked tile renames it, and Shift+X takes it away. Only ground already explored can be marked.
+// A replay adds the bookmark under its suggested name, since names never change what happens in a run
+pub fn place_bookmark_system(
+    keyboard: Res<Input<KeyCode>>,
+    mut naming: ResMut<BookmarkNaming>,
+    replay: Res<RunReplay>,
+    mut dungeon_state: ResMut<DungeonState>,
+    map: Res<TileMap>,
+    path_maps: Res<PathMaps>,
+    cursor: Res<VirtualCursor>,
+    conversation: Res<Conversation>,
+    inventory_menu: Res<InventoryMenu>,
+    throw_targeting: Res<ThrowTargeting>,
+    player_query: Query<&Position, With<Player>>,
+    mut message_log: ResMut<MessageLog>,
+) {
+    if !keyboard.just_pressed(BOOKMARK_KEY) || naming.is_active() || conversation.is_active() || inventory_menu.open || throw_targeting.is_aiming() {
+        return;
+    }
+    let player = if let Ok(pos) = player_query.get_single() { *pos } else { return; };
+    let tile = cursor.tile.unwrap_or((player.x, player.y));
+    if tile != (player.x, player.y) && !path_maps.is_explored(&map, tile.0, tile.1) {
+        message_log.add_message("You can only mark somewhere you've been.".to_string());
+        return;
+    }
+
+    let level = dungeon_state.current_level_index;
+    let bookmarks = dungeon_state.bookmarks.entry(level).or_default();
+    let existing = bookmarks.iter().position(|bookmark| bookmark.tile == tile);
+
+    if keyboard.pressed(KeyCode::ShiftLeft) || keyboard.pressed(KeyCode::ShiftRight) {
+        if let Some(index) = existing {
+            let removed = bookmarks.remove(index);
+            message_log.add_message(format!("Bookmark removed: {}", removed.name));
+        }
+        return;
+    }
+
+    match existing {
+        Some(index) => {
+            if !replay.active {
+                message_log.add_message(format!("Rename \"{}\" to what? (Enter to keep, Esc to cancel)", bookmarks[index].name));
+                naming.begin(level, tile);
+            }
+        }
+        None => {
+            let name = suggested_name(&map, tile).to_string();
+            if replay.active {
+                message_log.add_message(format!("Bookmark added: {}", name));
+            } else {
+                message_log.add_message(format!("Bookmark added. Name it, or Enter to keep \"{}\" (Shift+X removes it)", name));
+                naming.begin(level, tile);
+            }
+            bookmarks.push(Bookmark { tile, name });
+        }
+    }
+}
+
+// System to type in the name of the bookmark being named: Backspace takes a letter off, Enter keeps the name
+// and Esc leaves the old one. The keys are used up here so nothing in the game acts on them, and so they
+// never reach the run log
+pub fn bookmark_name_entry_system(
+    mut keyboard: ResMut<Input<KeyCode>>,
+    mut characters: EventReader<ReceivedCharacter>,
+    mut naming: ResMut<BookmarkNaming>,
+    mut dungeon_state: ResMut<DungeonState>,
+    mut message_log: ResMut<MessageLog>,
+) {
+    // Letters typed while not naming anything (including the X that starts it) are no part of a name
+    let entry = if let Some(entry) = naming.entry.as_mut() { entry } else {
+        characters.clear();
+        return;
+    };
+
+    for character in characters.read() {
+        if !character.char.is_control() && entry.text.chars().count() < MAX_NAME_LEN {
+            entry.text.push(character.char);
+        }
+    }
+    if keyboard.just_pressed(KeyCode::Back) {
+        entry.text.pop();
+    }
+
+    if keyboard.just_pressed(KeyCode::Return) {
+        let name = entry.text.trim().to_string();
+        let (level, tile) = (entry.level, entry.tile);
+        naming.entry = None;
+        let bookmark = dungeon_state.bookmarks.get_mut(&level)
+            .and_then(|bookmarks| bookmarks.iter_mut().find(|bookmark| bookmark.tile == tile));
+        if let Some(bookmark) = bookmark {
+            if !name.is_empty() {
+                bookmark.name = name;
+            }
+            message_log.add_message(format!("Bookmark named: {}", bookmark.name));
+        }
+    } else if keyboard.just_pressed(KeyCode::Escape) {
+        naming.entry = None;
+    }
+    keyboard.reset_all();
+}
+
+// System to redraw the markers for the current level's bookmarks whenever they change or the level does,
+// showing the name being typed in on the bookmark being named
+pub fn sync_bookmark_markers(
+    mut commands: Commands,
+    dungeon_state: Res<DungeonState>,
+    naming: Res<BookmarkNaming>,
+    asset_server: Res<AssetServer>,
+    marker_query: Query<Entity, With<BookmarkMarker>>,
+) {
+    if !dungeon_state.is_changed() && !naming.is_changed() {
+        return;
+    }
+    for entity in marker_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let font = asset_server.load("fonts/FiraSans-Light.ttf");
+    let level = dungeon_state.current_level_index;
+    for bookmark in dungeon_state.current_bookmarks() {
+        let label = match &naming.entry {
+            Some(entry) if entry.level == level && entry.tile == bookmark.tile => format!("{}_", entry.text),
+            _ => bookmark.name.clone(),
+        };
+        let center = Vec2::new(
+            bookmark.tile.0 as f32 * TILE_SIZE + TILE_SIZE / 2.0,
+            bookmark.tile.1 as f32 * TILE_SIZE + TILE_SIZE / 2.0,
+        );
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: MARKER_COLOR,
+                    custom_size: Some(Vec2::splat(TILE_SIZE / 4.0)),
+                    ..default()
+                },
+                // A small diamond in the tile's top corner
+                transform: Transform::from_xyz(center.x + TILE_SIZE / 3.0, center.y + TILE_SIZE / 3.0, MARKER_Z)
+                    .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4)),
+                ..default()
+            },
+            BookmarkMarker,
+        ));
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    label,
+                    TextStyle { font: font.clone(), font_size: LABEL_FONT_SIZE, color: MARKER_COLOR },
+                )
+                .with_alignment(TextAlignment::Center),
+                transform: Transform::from_xyz(center.x, center.y + TILE_SIZE * 0.7, MARKER_Z),
+                ..default()
+            },
+            BookmarkMarker,
+        ));
+    }
+}
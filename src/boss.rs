@@ -0,0 +1,410 @@
+use bevy::prelude::*;
+use std::path::Path;
+
+use crate::assets::{get_monster_sprite, SpriteAssets, TextureAtlases};
+use crate::biome::BiomeType;
+use crate::combat::{CombatStats, Health};
+use crate::components::{GameTurn, Npc, Player, Position};
+use crate::dialogue::CharacterType;
+use crate::events::EntityDamaged;
+use crate::faction::Hostile;
+use crate::input::TILE_SIZE;
+use crate::interaction::{Interactable, InteractionKind};
+use crate::inventory::ItemKind;
+use crate::loot::CreatureDrop;
+use crate::map::TileMap;
+use crate::pathmaps::PathMaps;
+use crate::status::{ApplyStatusEffect, StatusEffect, StatusKind};
+use crate::ui::MessageLog;
+use crate::level::DungeonState;
+
+// Music played on boss floors, if the track is present
+const BOSS_MUSIC_PATH: &str = "audio/boss_theme.ogg";
+
+/// The unique boss waiting at the bottom of each boss floor, one per biome
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BossKind {
+    Troll,        // Caves
+    Manticore,    // Groves
+    OrcWarchief,  // Labyrinth
+    Lich,         // Catacombs
+}
+
+impl BossKind {
+    pub fn for_biome(biome: BiomeType) -> Self {
+        match biome {
+            BiomeType::Caves => BossKind::Troll,
+            BiomeType::Groves => BossKind::Manticore,
+            BiomeType::Labyrinth => BossKind::OrcWarchief,
+            BiomeType::Catacombs => BossKind::Lich,
+        }
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            BossKind::Troll => "Grolm the Cave Troll",
+            BossKind::Manticore => "The Thornback Manticore",
+            BossKind::OrcWarchief => "Warchief Uzgar",
+            BossKind::Lich => "The Hollow Lich",
+        }
+    }
+
+    // Name of the sprite in the monsters atlas
+    fn sprite_name(&self) -> &'static str {
+        match self {
+            BossKind::Troll => "troll",
+            BossKind::Manticore => "manticore",
+            BossKind::OrcWarchief => "orc warchief",
+            BossKind::Lich => "lich",
+        }
+    }
+
+    // Bosses get tougher the deeper they are
+    fn max_health(&self, level: usize) -> i32 {
+        let base = match self {
+            BossKind::Troll => 45,
+            BossKind::Manticore => 35,
+            BossKind::OrcWarchief => 40,
+            BossKind::Lich => 30,
+        };
+        base + level as i32 * 3
+    }
+
+    fn attack(&self, level: usize) -> i32 {
+        let base = match self {
+            BossKind::Troll => 4,
+            BossKind::Manticore => 3,
+            BossKind::OrcWarchief => 4,
+            BossKind::Lich => 3,
+        };
+        base + level as i32 / 5
+    }
+
+    // Every boss carries its trophy and a hoard; what else depends on the beast
+    pub fn drops(&self) -> Vec<CreatureDrop> {
+        let mut drops = vec![
+            CreatureDrop::new(ItemKind::Trophy, 1.0, 1),
+            CreatureDrop::new(ItemKind::Gold, 1.0, 20),
+        ];
+        match self {
+            BossKind::Troll => drops.push(CreatureDrop::new(ItemKind::Hide, 1.0, 1)),
+            BossKind::Manticore => {
+                drops.push(CreatureDrop::new(ItemKind::Fang, 1.0, 2));
+                drops.push(CreatureDrop::new(ItemKind::Claw, 1.0, 2));
+            }
+            BossKind::OrcWarchief => drops.push(CreatureDrop::new(ItemKind::Dagger, 1.0, 2)),
+            BossKind::Lich => drops.push(CreatureDrop::new(ItemKind::ManaPotion, 1.0, 2)),
+        }
+        drops
+    }
+
+    fn taunts(&self) -> Vec<String> {
+        match self {
+            BossKind::Troll => vec![
+                "Grolm smash little wizard!".to_string(),
+                "Nobody leaves Grolm's cave.".to_string(),
+            ],
+            BossKind::Manticore => vec![
+                "The manticore's tail rattles with venom.".to_string(),
+                "Fresh meat wanders into my grove.".to_string(),
+            ],
+            BossKind::OrcWarchief => vec![
+                "The way down is mine to give, and I give nothing.".to_string(),
+                "Kneel, and I'll make it quick.".to_string(),
+            ],
+            BossKind::Lich => vec![
+                "Another soul for the Chasm.".to_string(),
+                "I have waited a thousand years. I can wait for you to die.".to_string(),
+            ],
+        }
+    }
+}
+
+/// How desperate a boss is, based on its remaining health
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BossPhase {
+    Stalking,  // Closes in one step at a time
+    Enraged,   // Moves twice a turn and hits harder
+    Desperate, // Moves twice a turn and its blows poison
+}
+
+impl BossPhase {
+    fn from_health(health: &Health) -> Self {
+        let fraction = health.current as f32 / health.max.max(1) as f32;
+        if fraction <= 0.25 {
+            BossPhase::Desperate
+        } else if fraction <= 0.6 {
+            BossPhase::Enraged
+        } else {
+            BossPhase::Stalking
+        }
+    }
+
+    fn steps_per_turn(&self) -> usize {
+        match self {
+            BossPhase::Stalking => 1,
+            BossPhase::Enraged | BossPhase::Desperate => 2,
+        }
+    }
+
+    fn attack_bonus(&self) -> i32 {
+        match self {
+            BossPhase::Stalking => 0,
+            BossPhase::Enraged | BossPhase::Desperate => 2,
+        }
+    }
+}
+
+/// A boss monster
+#[derive(Component, Debug)]
+pub struct Boss {
+    pub kind: BossKind,
+    pub phase: BossPhase,
+}
+
+/// Overlay marking the down stairs as sealed
+#[derive(Component)]
+pub struct StairsSeal {
+    pub tile: (usize, usize),
+}
+
+/// Marker for the looping boss floor music
+#[derive(Component)]
+pub struct BossMusic;
+
+// The looping boss floor music, if the track is present. It starts with the boss's entrance
+pub fn boss_music(asset_server: &AssetServer) -> Option<(AudioBundle, BossMusic)> {
+    if !Path::new("assets").join(BOSS_MUSIC_PATH).exists() {
+        return None;
+    }
+    Some((
+        AudioBundle {
+            source: asset_server.load(BOSS_MUSIC_PATH),
+            settings: PlaybackSettings::LOOP,
+        },
+        BossMusic,
+    ))
+}
+
+// System to set up a boss floor when it's entered: the ambience and the boss itself (its music waits
+// for the player to find it; see cutscene::queue_boss_intro)
+pub fn setup_boss_floor_system(
+    mut commands: Commands,
+    map: Res<TileMap>,
+    boss_query: Query<(), With<Boss>>,
+    music_query: Query<Entity, With<BossMusic>>,
+    mut clear_color: ResMut<ClearColor>,
+    texture_atlases: Res<TextureAtlases>,
+    sprite_assets: Res<SpriteAssets>,
+    mut message_log: ResMut<MessageLog>,
+) {
+    if !map.is_changed() {
+        return;
+    }
+
+    // Swap the ambience
+    if map.is_boss_level {
+        clear_color.0 = Color::rgb(0.12, 0.02, 0.02);
+    } else {
+        *clear_color = ClearColor::default();
+        for entity in music_query.iter() {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    // The boss only waits on floors where it hasn't been beaten yet
+    if !map.is_boss_level || !map.down_stairs_locked || !boss_query.is_empty() {
+        return;
+    }
+
+    let (x, y) = if let Some(pos) = map.boss_spawn_position() {
+        pos
+    } else {
+        return;
+    };
+
+    let kind = BossKind::for_biome(map.get_biome_at(x, y));
+    let taunts = kind.taunts();
+
+    commands.spawn((
+        SpriteSheetBundle {
+            texture_atlas: texture_atlases.monsters.clone(),
+            sprite: TextureAtlasSprite {
+                index: get_monster_sprite(&sprite_assets, kind.sprite_name()),
+                ..default()
+            },
+            transform: Transform::from_xyz(
+                x as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+                y as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+                8.0 // Above animals, below the player
+            ).with_scale(Vec3::splat(1.5)),
+            ..default()
+        },
+        Boss { kind, phase: BossPhase::Stalking },
+        Interactable::new(InteractionKind::Talk, format!("Talk to {}", kind.get_name())),
+        Npc {
+            name: kind.get_name().to_string(),
+            dialog_text: taunts[0].clone(),
+            dialog: taunts,
+            speaking: false,
+            current_dialog_index: 0,
+            met: false,
+            lines_heard: 0,
+            character_type: CharacterType::Generic,
+            animation_timer: Timer::from_seconds(0.3, TimerMode::Once),
+            original_scale: Vec3::splat(1.5),
+            wiggle_direction: 1.0,
+            wiggle_amount: 0.1,
+            is_animal: false,
+            animal_type: None,
+        },
+        Hostile,
+        Health::new(kind.max_health(map.current_level)),
+        CombatStats { attack: kind.attack(map.current_level) },
+        Position::new(x as i32, y as i32),
+    ));
+
+    message_log.add_message(format!("{} blocks the way down!", kind.get_name()));
+    println!("Spawned boss {:?} at ({}, {})", kind, x, y);
+}
+
+// Keep a seal over the down stairs while they're locked
+pub fn update_stairs_seal(
+    mut commands: Commands,
+    map: Res<TileMap>,
+    seal_query: Query<(Entity, &StairsSeal)>,
+) {
+    let sealed_tile = map.down_stairs_pos.filter(|_| map.down_stairs_locked);
+
+    let mut has_seal = false;
+    for (entity, seal) in seal_query.iter() {
+        if Some(seal.tile) == sealed_tile {
+            has_seal = true;
+        } else {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    if let Some((x, y)) = sealed_tile {
+        if !has_seal {
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::rgba(0.8, 0.1, 0.1, 0.6),
+                        custom_size: Some(Vec2::splat(TILE_SIZE)),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(
+                        x as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+                        y as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+                        2.0 // Just above the stairs tile
+                    ),
+                    ..default()
+                },
+                StairsSeal { tile: (x, y) },
+            ));
+        }
+    }
+}
+
+// System driving the boss: pick a phase from its health, then close in and attack
+pub fn boss_ai_system(
+    mut boss_query: Query<(&mut Boss, &mut Position, &mut Transform, &Health, &CombatStats), Without<Player>>,
+    mut player_query: Query<(Entity, &Position, &mut Health), (With<Player>, Without<Boss>)>,
+    path_maps: Res<PathMaps>,
+    mut status_events: EventWriter<ApplyStatusEffect>,
+    mut message_log: ResMut<MessageLog>,
+    mut damage_events: EventWriter<EntityDamaged>,
+    game_turn: Res<GameTurn>,
+    mut local: Local<u32>,
+) {
+    // Only act once per turn
+    if game_turn.current_turn == 0 || game_turn.current_turn == *local {
+        return;
+    }
+    *local = game_turn.current_turn;
+
+    let (player_entity, player_pos, mut player_health) = if let Ok(player) = player_query.get_single_mut() {
+        player
+    } else {
+        return;
+    };
+
+    for (mut boss, mut position, mut transform, health, stats) in boss_query.iter_mut() {
+        if health.is_dead() {
+            continue;
+        }
+
+        // Announce phase changes
+        let phase = BossPhase::from_health(health);
+        if phase != boss.phase {
+            boss.phase = phase;
+            let message = match phase {
+                BossPhase::Stalking => format!("{} steadies itself", boss.kind.get_name()),
+                BossPhase::Enraged => format!("{} flies into a rage!", boss.kind.get_name()),
+                BossPhase::Desperate => format!("{} fights with desperate fury!", boss.kind.get_name()),
+            };
+            message_log.add_message(message);
+        }
+
+        for _ in 0..phase.steps_per_turn() {
+            let distance = (position.x - player_pos.x).abs() + (position.y - player_pos.y).abs();
+
+            if distance <= 1 {
+                let damage = stats.attack + phase.attack_bonus();
+                player_health.take_damage(damage);
+                damage_events.send(EntityDamaged {
+                    target: player_entity,
+                    amount: damage,
+                    source: boss.kind.get_name().to_string(),
+                });
+                message_log.add_message(format!("{} hits you for {} damage", boss.kind.get_name(), damage));
+
+                if phase == BossPhase::Desperate {
+                    status_events.send(ApplyStatusEffect {
+                        target: player_entity,
+                        effect: StatusEffect::new(StatusKind::Poison, 3, 1),
+                    });
+                }
+                break;
+            }
+
+            // Close in along the shortest path, read off the shared distance map
+            let next = path_maps.to_player.downhill_from(position.x, position.y);
+
+            if let Some((x, y)) = next {
+                if (x, y) == (player_pos.x, player_pos.y) {
+                    break;
+                }
+                position.x = x;
+                position.y = y;
+                transform.translation.x = x as f32 * TILE_SIZE + (TILE_SIZE / 2.0);
+                transform.translation.y = y as f32 * TILE_SIZE + (TILE_SIZE / 2.0);
+            }
+        }
+    }
+}
+
+// System to unseal the down stairs once the boss falls
+pub fn boss_defeated_system(
+    boss_query: Query<(&Boss, &Health)>,
+    mut map: ResMut<TileMap>,
+    mut dungeon_state: ResMut<DungeonState>,
+    mut message_log: ResMut<MessageLog>,
+) {
+    for (boss, health) in boss_query.iter() {
+        if !health.is_dead() || !map.down_stairs_locked {
+            continue;
+        }
+
+        map.down_stairs_locked = false;
+
+        // Remember it for when the player comes back to this floor
+        let current_level = dungeon_state.current_level_index;
+        if let Some(level) = dungeon_state.levels.get_mut(current_level) {
+            level.down_stairs_locked = false;
+        }
+
+        message_log.add_message(format!("{} is defeated! The way down is open.", boss.kind.get_name()));
+    }
+}
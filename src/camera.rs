@@ -0,0 +1,615 @@
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::prelude::*;
+use bevy::render::camera::ScalingMode;
+use bevy::window::PrimaryWindow;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+
+use crate::components::{Player, Position};
+use crate::frame_timing::smoothing;
+use crate::input::TILE_SIZE;
+use crate::map::{TileMap, VIEWPORT_HEIGHT, VIEWPORT_WIDTH};
+use crate::{GameState, GameplaySet};
+
+/// File the player's camera preferences and bindings are kept in
+pub const CAMERA_PREFS_PATH: &str = "camera.json";
+
+// How close a conversation pulls the camera in, and how quickly
+const DIALOG_ZOOM: f32 = 0.2;
+const DIALOG_ZOOM_SPEED: f32 = 5.0;
+// How much a held zoom key changes the zoom each second
+const KEY_ZOOM_PER_SECOND: f32 = 1.2;
+
+// Inputs the camera can be bound to, with the names they're saved under
+const BINDABLE_INPUTS: [(CameraInput, &str); 16] = [
+    (CameraInput::Key(KeyCode::Plus), "Plus"),
+    (CameraInput::Key(KeyCode::Equals), "Equals"),
+    (CameraInput::Key(KeyCode::Minus), "Minus"),
+    (CameraInput::Key(KeyCode::NumpadAdd), "NumpadAdd"),
+    (CameraInput::Key(KeyCode::NumpadSubtract), "NumpadSubtract"),
+    (CameraInput::Key(KeyCode::Home), "Home"),
+    (CameraInput::Key(KeyCode::End), "End"),
+    (CameraInput::Key(KeyCode::PageUp), "PageUp"),
+    (CameraInput::Key(KeyCode::PageDown), "PageDown"),
+    (CameraInput::Key(KeyCode::Insert), "Insert"),
+    (CameraInput::Key(KeyCode::Key0), "0"),
+    (CameraInput::Key(KeyCode::Key9), "9"),
+    (CameraInput::Key(KeyCode::Space), "Space"),
+    (CameraInput::Mouse(MouseButton::Middle), "MouseMiddle"),
+    (CameraInput::Mouse(MouseButton::Right), "MouseRight"),
+    (CameraInput::Mouse(MouseButton::Left), "MouseLeft"),
+];
+
+/// The one game camera: following the player, zooming, and how it's tuned
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        let prefs = CameraPrefs::load();
+        app.insert_resource(prefs.settings())
+            .insert_resource(prefs.bindings())
+            .add_systems(Startup, spawn_camera)
+            .add_systems(
+                Update,
+                (
+                    update_camera_zoom.in_set(GameplaySet::Presentation),
+                    pan_camera.after(update_camera_zoom),
+                    follow_camera
+                        .after(pan_camera)
+                        .in_set(GameplaySet::Presentation),
+                )
+                .run_if(in_state(GameState::InGame))
+            );
+    }
+}
+
+/// Tuning for how the camera follows the player
+#[derive(Resource, Debug, Clone)]
+pub struct CameraSettings {
+    pub deadzone: Vec2,       // Half-size of the box, in tiles, the player can move in without the camera moving
+    pub lookahead: f32,       // Tiles the camera leads the player by in the direction they're walking
+    pub lookahead_speed: f32, // How fast the lead swings round when the player turns
+    pub follow_speed: f32,    // How fast the camera catches up once the player leaves the deadzone
+    pub pixel_snap: bool,     // Round the camera to whole screen pixels so sprites don't shimmer
+    pub wheel_zoom_step: f32, // Zoom change per notch of the mouse wheel
+    pub default_zoom: f32,    // Where each run starts and the reset-view key returns to
+    pub edge_pan: bool,       // Pan when the cursor rests at the window's edge while zoomed out
+    pub edge_pan_margin: f32, // How close to the edge, in screen pixels, the cursor has to be
+    pub pan_speed: f32,       // Tiles per second edge panning moves the view
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            deadzone: Vec2::new(1.5, 1.0),
+            lookahead: 1.5,
+            lookahead_speed: 3.0,
+            follow_speed: 6.0,
+            pixel_snap: true,
+            wheel_zoom_step: 0.1,
+            default_zoom: 0.6,
+            edge_pan: true,
+            edge_pan_margin: 12.0,
+            pan_speed: 12.0,
+        }
+    }
+}
+
+/// Everything the player can do with the camera, so each can be bound to any key or mouse button
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CameraAction {
+    ZoomIn,
+    ZoomOut,
+    ResetView,       // Back to the default zoom, centred on the player
+    SaveDefaultZoom, // Make the current zoom the default
+    DragPan,         // Held while moving the mouse to drag the view
+}
+
+impl CameraAction {
+    pub const ALL: [CameraAction; 5] = [
+        CameraAction::ZoomIn,
+        CameraAction::ZoomOut,
+        CameraAction::ResetView,
+        CameraAction::SaveDefaultZoom,
+        CameraAction::DragPan,
+    ];
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            CameraAction::ZoomIn => "Zoom in",
+            CameraAction::ZoomOut => "Zoom out",
+            CameraAction::ResetView => "Reset view",
+            CameraAction::SaveDefaultZoom => "Save zoom as default",
+            CameraAction::DragPan => "Drag to pan",
+        }
+    }
+}
+
+/// A key or mouse button a camera action is bound to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CameraInput {
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+
+impl CameraInput {
+    fn name(&self) -> Option<&'static str> {
+        BINDABLE_INPUTS.iter().find(|(input, _)| input == self).map(|(_, name)| *name)
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        BINDABLE_INPUTS.iter().find(|(_, input_name)| *input_name == name).map(|(input, _)| *input)
+    }
+}
+
+/// Which inputs trigger each camera action
+#[derive(Resource, Debug, Clone)]
+pub struct CameraBindings {
+    bindings: HashMap<CameraAction, Vec<CameraInput>>,
+}
+
+impl Default for CameraBindings {
+    fn default() -> Self {
+        let bindings = HashMap::from([
+            (CameraAction::ZoomIn, vec![
+                CameraInput::Key(KeyCode::Plus),
+                CameraInput::Key(KeyCode::NumpadAdd),
+                CameraInput::Key(KeyCode::Equals),
+            ]),
+            (CameraAction::ZoomOut, vec![
+                CameraInput::Key(KeyCode::Minus),
+                CameraInput::Key(KeyCode::NumpadSubtract),
+            ]),
+            (CameraAction::ResetView, vec![CameraInput::Key(KeyCode::Home)]),
+            (CameraAction::SaveDefaultZoom, vec![CameraInput::Key(KeyCode::End)]),
+            (CameraAction::DragPan, vec![CameraInput::Mouse(MouseButton::Middle)]),
+        ]);
+        Self { bindings }
+    }
+}
+
+impl CameraBindings {
+    // Replace whatever an action was bound to
+    pub fn rebind(&mut self, action: CameraAction, inputs: Vec<CameraInput>) {
+        self.bindings.insert(action, inputs);
+    }
+
+    fn inputs(&self, action: CameraAction) -> &[CameraInput] {
+        self.bindings.get(&action).map_or(&[][..], |inputs| inputs.as_slice())
+    }
+
+    // The inputs bound to an action, as shown to the player, e.g. "Plus / NumpadAdd"
+    pub fn describe(&self, action: CameraAction) -> String {
+        let names: Vec<&str> = self.inputs(action).iter().filter_map(|input| input.name()).collect();
+        if names.is_empty() { "unbound".to_string() } else { names.join(" / ") }
+    }
+
+    pub fn pressed(&self, action: CameraAction, keyboard: &Input<KeyCode>, mouse: &Input<MouseButton>) -> bool {
+        self.inputs(action).iter().any(|input| match input {
+            CameraInput::Key(key) => keyboard.pressed(*key),
+            CameraInput::Mouse(button) => mouse.pressed(*button),
+        })
+    }
+
+    pub fn just_pressed(&self, action: CameraAction, keyboard: &Input<KeyCode>, mouse: &Input<MouseButton>) -> bool {
+        self.inputs(action).iter().any(|input| match input {
+            CameraInput::Key(key) => keyboard.just_pressed(*key),
+            CameraInput::Mouse(button) => mouse.just_pressed(*button),
+        })
+    }
+}
+
+/// What's saved of the camera between sessions: the preferred zoom, edge panning and any rebound inputs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraPrefs {
+    pub default_zoom: f32,
+    pub edge_pan: bool,
+    #[serde(default)]
+    pub bindings: HashMap<CameraAction, Vec<String>>, // Only actions moved off their defaults
+}
+
+impl Default for CameraPrefs {
+    fn default() -> Self {
+        let settings = CameraSettings::default();
+        Self { default_zoom: settings.default_zoom, edge_pan: settings.edge_pan, bindings: HashMap::new() }
+    }
+}
+
+impl CameraPrefs {
+    // Read saved preferences, falling back to the defaults if there are none or they can't be read
+    pub fn load() -> Self {
+        match fs::read_to_string(CAMERA_PREFS_PATH) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                eprintln!("Ignoring unreadable {}: {}", CAMERA_PREFS_PATH, e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let contents = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(CAMERA_PREFS_PATH, contents).map_err(|e| format!("could not write {}: {}", CAMERA_PREFS_PATH, e))
+    }
+
+    fn settings(&self) -> CameraSettings {
+        CameraSettings { default_zoom: self.default_zoom, edge_pan: self.edge_pan, ..default() }
+    }
+
+    fn bindings(&self) -> CameraBindings {
+        let mut bindings = CameraBindings::default();
+        for (action, names) in &self.bindings {
+            let inputs: Vec<CameraInput> = names.iter()
+                .filter_map(|name| {
+                    let input = CameraInput::from_name(name);
+                    if input.is_none() {
+                        eprintln!("Unknown camera input '{}' for {:?} in {}", name, action, CAMERA_PREFS_PATH);
+                    }
+                    input
+                })
+                .collect();
+            bindings.rebind(*action, inputs);
+        }
+        bindings
+    }
+
+    // Capture the current settings and any bindings that differ from the defaults
+    fn from_current(settings: &CameraSettings, bindings: &CameraBindings) -> Self {
+        let defaults = CameraBindings::default();
+        let rebound = bindings.bindings.iter()
+            .filter(|(action, inputs)| defaults.inputs(**action) != inputs.as_slice())
+            .map(|(action, inputs)| (*action, inputs.iter().filter_map(|input| input.name()).map(str::to_string).collect()))
+            .collect();
+        Self { default_zoom: settings.default_zoom, edge_pan: settings.edge_pan, bindings: rebound }
+    }
+}
+
+/// Something the camera has been pulled away from the player to look at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusTarget {
+    Conversation(Entity), // The NPC being talked to
+    Cutscene,             // A scripted sequence is steering it
+}
+
+// A focus on the stack, with the view to go back to once it's popped
+#[derive(Debug, Clone, Copy)]
+struct FocusEntry {
+    target: FocusTarget,
+    return_zoom: f32,
+    return_position: Vec3,
+}
+
+// Camera control component
+#[derive(Component)]
+pub struct CameraControl {
+    pub current_zoom: f32,
+    pub target_zoom: f32,
+    pub zoom_speed: f32,
+    pub shake_offset: Vec3, // Added on top of the followed position, e.g. by a rumble
+    focus: Vec2,            // Where the camera is looking, before snapping and shake
+    lead: Vec2,             // Current lookahead, in world units
+    pan: Vec2,              // How far zooming toward the cursor has moved the view off the player
+    zoom_anchor: Option<Vec2>, // World point to hold still on screen while zooming
+    zoom_limits: (f32, f32),   // Closest in and furthest out, for the current map
+    focus_stack: Vec<FocusEntry>, // What the camera's been pulled in on, newest last; these own the zoom
+    last_output: Vec3,      // What was last written to the transform, to notice anyone else moving it
+}
+
+impl CameraControl {
+    // Pull the camera in on `target`, remembering the current view to go back to
+    pub fn push_focus(&mut self, target: FocusTarget, camera_transform: &mut Transform, position: Vec3, zoom: f32) {
+        self.focus_stack.push(FocusEntry {
+            target,
+            return_zoom: self.target_zoom,
+            return_position: camera_transform.translation,
+        });
+        self.target_zoom = zoom;
+        self.zoom_speed = DIALOG_ZOOM_SPEED;
+        self.zoom_anchor = None;
+        self.pan = Vec2::ZERO;
+        camera_transform.translation = position;
+    }
+
+    // Let go of `target` and anything pushed on top of it, going back to the view from before it.
+    // Does nothing if the camera wasn't focused on it, so a focus can't be popped twice
+    pub fn pop_focus(&mut self, target: FocusTarget, camera_transform: &mut Transform) {
+        let index = if let Some(index) = self.focus_stack.iter().position(|entry| entry.target == target) { index } else { return; };
+        let entry = self.focus_stack[index];
+        self.focus_stack.truncate(index);
+        self.target_zoom = entry.return_zoom;
+        camera_transform.translation = entry.return_position;
+        self.zoom_speed = 2.0; // Normal zoom speed
+    }
+
+    // The newest focus, if the camera is off the player
+    pub fn current_focus(&self) -> Option<FocusTarget> {
+        self.focus_stack.last().map(|entry| entry.target)
+    }
+
+    pub fn is_focused(&self) -> bool {
+        !self.focus_stack.is_empty()
+    }
+
+    // Close in on a conversation with `speaker`
+    pub fn begin_dialog(&mut self, speaker: Entity, camera_transform: &mut Transform, focus: Vec3) {
+        self.push_focus(FocusTarget::Conversation(speaker), camera_transform, focus, DIALOG_ZOOM);
+    }
+
+    // Zoom back out to where the camera was before the conversation with `speaker`
+    pub fn end_dialog(&mut self, speaker: Entity, camera_transform: &mut Transform) {
+        self.pop_focus(FocusTarget::Conversation(speaker), camera_transform);
+    }
+
+    // Manual zoom from the keyboard or wheel, toward `anchor` if given; ignored while focused elsewhere
+    pub fn zoom_by(&mut self, amount: f32, anchor: Option<Vec2>) {
+        if self.is_focused() {
+            return;
+        }
+        let (min_zoom, max_zoom) = self.zoom_limits;
+        self.target_zoom = (self.target_zoom + amount).clamp(min_zoom, max_zoom);
+        self.zoom_speed = 2.0;
+        self.zoom_anchor = anchor;
+    }
+
+    // Back to the given zoom with the view on the player; ignored while focused elsewhere
+    pub fn reset_view(&mut self, zoom: f32) {
+        if self.is_focused() {
+            return;
+        }
+        let (min_zoom, max_zoom) = self.zoom_limits;
+        self.target_zoom = zoom.clamp(min_zoom, max_zoom);
+        self.zoom_speed = 2.0;
+        self.zoom_anchor = None;
+        self.pan = Vec2::ZERO;
+    }
+
+    // Move the view off the player, e.g. by dragging; ignored while focused elsewhere
+    pub fn pan_by(&mut self, offset: Vec2) {
+        if self.is_focused() {
+            return;
+        }
+        self.pan += offset;
+    }
+}
+
+impl Default for CameraControl {
+    fn default() -> Self {
+        Self {
+            current_zoom: 1.0,
+            target_zoom: 0.6,
+            zoom_speed: 2.0,
+            shake_offset: Vec3::ZERO,
+            focus: Vec2::ZERO,
+            lead: Vec2::ZERO,
+            pan: Vec2::ZERO,
+            zoom_anchor: None,
+            zoom_limits: (0.0, 1.0),
+            focus_stack: Vec::new(),
+            last_output: Vec3::ZERO,
+        }
+    }
+}
+
+// Spawn the game camera
+pub fn spawn_camera(mut commands: Commands, settings: Res<CameraSettings>) {
+    // Starts wherever; the first frame in game puts it on the player
+    let mut camera = Camera2dBundle::default();
+    camera.transform.translation.z = 999.9;
+    camera.projection.scaling_mode = ScalingMode::Fixed {
+        width: VIEWPORT_WIDTH as f32 * TILE_SIZE,
+        height: VIEWPORT_HEIGHT as f32 * TILE_SIZE,
+    };
+    camera.projection.scale = 1.0;
+    commands.spawn((
+        camera,
+        CameraControl { target_zoom: settings.default_zoom, ..default() },
+    ));
+}
+
+// Keep one axis of the focus within `deadzone` of the target, moving as little as possible
+fn deadzone_axis(focus: f32, target: f32, deadzone: f32) -> f32 {
+    if target > focus + deadzone {
+        target - deadzone
+    } else if target < focus - deadzone {
+        target + deadzone
+    } else {
+        focus
+    }
+}
+
+// Clamp the camera within the map; center on any axis where the map is smaller than the view
+fn clamp_to_map(focus: Vec2, map: &TileMap, zoom: f32) -> Vec2 {
+    let map_size = Vec2::new(map.width as f32 * TILE_SIZE, map.height as f32 * TILE_SIZE);
+    let half_view = Vec2::new(VIEWPORT_WIDTH as f32, VIEWPORT_HEIGHT as f32) * TILE_SIZE * zoom / 2.0;
+    let axis = |value: f32, size: f32, half: f32| if size > half * 2.0 { value.clamp(half, size - half) } else { size / 2.0 };
+    Vec2::new(axis(focus.x, map_size.x, half_view.x), axis(focus.y, map_size.y, half_view.y))
+}
+
+// System to keep the player inside the deadzone, leading them a little in the way they're going
+pub fn follow_camera(
+    time: Res<Time>,
+    settings: Res<CameraSettings>,
+    map: Res<TileMap>,
+    mut camera_query: Query<(&mut CameraControl, &mut Transform), Without<Player>>,
+    player_query: Query<(&Transform, &Position), (With<Player>, Without<CameraControl>)>,
+    mut last_player_pos: Local<Option<Position>>,
+) {
+    let (mut control, mut camera_transform) = if let Ok(camera) = camera_query.get_single_mut() { camera } else { return; };
+
+    // Something else (a conversation, a restore) moved the camera; carry on from there
+    if camera_transform.translation != control.last_output {
+        control.focus = (camera_transform.translation - control.shake_offset).truncate();
+    }
+
+    if let Ok((player_transform, player_pos)) = player_query.get_single() {
+        // Nothing to follow from yet: start on the player rather than sweeping over from the origin
+        if last_player_pos.is_none() {
+            control.focus = player_transform.translation.truncate();
+        }
+
+        // Lead in the direction of the last step, and hold it while the player stands still
+        let step = last_player_pos.map_or(IVec2::ZERO, |last| IVec2::new(player_pos.x - last.x, player_pos.y - last.y));
+        *last_player_pos = Some(*player_pos);
+        if step != IVec2::ZERO {
+            // Walking brings the view back to the player after zooming off toward the cursor
+            control.pan = Vec2::ZERO;
+        }
+        let lead_target = if step != IVec2::ZERO && step.x.abs() <= 1 && step.y.abs() <= 1 {
+            step.as_vec2() * settings.lookahead * TILE_SIZE
+        } else if step == IVec2::ZERO {
+            control.lead
+        } else {
+            Vec2::ZERO // Teleported (stairs, a new level): don't swing across the map
+        };
+        control.lead = control.lead.lerp(lead_target, smoothing(settings.lookahead_speed, time.delta_seconds()));
+
+        let target = player_transform.translation.truncate() + control.lead + control.pan;
+        let deadzone = settings.deadzone * TILE_SIZE * control.current_zoom;
+        let wanted = Vec2::new(
+            deadzone_axis(control.focus.x, target.x, deadzone.x),
+            deadzone_axis(control.focus.y, target.y, deadzone.y),
+        );
+        control.focus = control.focus.lerp(wanted, smoothing(settings.follow_speed, time.delta_seconds()));
+    }
+
+    control.focus = clamp_to_map(control.focus, &map, control.current_zoom);
+
+    // One screen pixel is `zoom` world units
+    let mut position = control.focus;
+    if settings.pixel_snap && control.current_zoom > 0.0 {
+        position = (position / control.current_zoom).round() * control.current_zoom;
+    }
+
+    camera_transform.translation = position.extend(camera_transform.translation.z) + control.shake_offset;
+    control.last_output = camera_transform.translation;
+}
+
+// System to zoom with the bound keys or the mouse wheel, between fully zoomed in and the whole map in view
+pub fn update_camera_zoom(
+    keyboard: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    mut wheel_events: EventReader<MouseWheel>,
+    time: Res<Time>,
+    mut settings: ResMut<CameraSettings>,
+    bindings: Res<CameraBindings>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut camera_query: Query<(&mut CameraControl, &mut OrthographicProjection, &Camera, &GlobalTransform)>,
+    map: Res<TileMap>,
+) {
+    let map_size = Vec2::new(map.width as f32 * TILE_SIZE, map.height as f32 * TILE_SIZE);
+    let viewport_size = Vec2::new(VIEWPORT_WIDTH as f32 * TILE_SIZE, VIEWPORT_HEIGHT as f32 * TILE_SIZE);
+
+    let (mut control, mut projection, camera, camera_global) = if let Ok(camera) = camera_query.get_single_mut() { camera } else { return; };
+
+    // Calculate minimum zoom (closest in) from the viewport
+    let window_ratio = VIEWPORT_WIDTH as f32 / VIEWPORT_HEIGHT as f32;
+    let min_zoom = if window_ratio > 1.0 {
+        1.0 / VIEWPORT_WIDTH as f32
+    } else {
+        1.0 / VIEWPORT_HEIGHT as f32
+    } * 5.0;
+
+    // Zooming out stops once the whole map fits
+    let max_zoom = (map_size.x / viewport_size.x).max(map_size.y / viewport_size.y).max(1.0);
+    control.zoom_limits = (min_zoom, max_zoom);
+
+    // Handle zoom input
+    if bindings.pressed(CameraAction::ZoomIn, &keyboard, &mouse) {
+        control.zoom_by(-KEY_ZOOM_PER_SECOND * time.delta_seconds(), None);
+    }
+    if bindings.pressed(CameraAction::ZoomOut, &keyboard, &mouse) {
+        control.zoom_by(KEY_ZOOM_PER_SECOND * time.delta_seconds(), None);
+    }
+    if bindings.just_pressed(CameraAction::ResetView, &keyboard, &mouse) {
+        control.reset_view(settings.default_zoom);
+    }
+    if bindings.just_pressed(CameraAction::SaveDefaultZoom, &keyboard, &mouse) {
+        settings.default_zoom = control.target_zoom;
+        match CameraPrefs::from_current(&settings, &bindings).save() {
+            Ok(()) => println!("Default zoom set to {:.2}", settings.default_zoom),
+            Err(e) => eprintln!("Could not save camera preferences: {}", e),
+        }
+    }
+
+    // Scrolling up zooms in, toward whatever is under the cursor
+    let notches: f32 = wheel_events.read().map(|event| match event.unit {
+        MouseScrollUnit::Line => event.y,
+        MouseScrollUnit::Pixel => event.y / 100.0,
+    }).sum();
+    if notches != 0.0 {
+        let cursor = window_query.get_single().ok()
+            .and_then(|window| window.cursor_position())
+            .and_then(|cursor| camera.viewport_to_world_2d(camera_global, cursor));
+        let step = -notches * settings.wheel_zoom_step * control.target_zoom;
+        control.zoom_by(step, cursor);
+    }
+
+    // Smoothly interpolate current zoom to target
+    let zoom_delta = control.target_zoom - control.current_zoom;
+    if zoom_delta.abs() > 0.001 {
+        let old_zoom = control.current_zoom;
+        control.current_zoom += zoom_delta * smoothing(control.zoom_speed, time.delta_seconds());
+
+        // Keep the anchor at the same spot on screen by scaling the view about it
+        if let Some(anchor) = control.zoom_anchor {
+            let focus = anchor + (control.focus - anchor) * (control.current_zoom / old_zoom);
+            let shift = focus - control.focus;
+            control.pan += shift;
+            control.focus = focus;
+        }
+
+        // Update camera projection
+        projection.scale = control.current_zoom;
+    } else {
+        control.zoom_anchor = None;
+    }
+}
+
+// System to drag the view with the bound mouse button, or push it along by resting the cursor at
+// the window's edge while zoomed out past the default. Walking brings the view back to the player
+pub fn pan_camera(
+    keyboard: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    time: Res<Time>,
+    settings: Res<CameraSettings>,
+    bindings: Res<CameraBindings>,
+    map: Res<TileMap>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut camera_query: Query<&mut CameraControl>,
+    mut last_cursor: Local<Option<Vec2>>,
+) {
+    let window = if let Ok(window) = window_query.get_single() { window } else { return; };
+    let mut control = if let Ok(control) = camera_query.get_single_mut() { control } else { return; };
+    let cursor = window.cursor_position();
+
+    // World units per screen pixel; screen y runs down, world y up
+    let scale = VIEWPORT_WIDTH as f32 * TILE_SIZE * control.current_zoom / window.width().max(1.0);
+
+    if bindings.pressed(CameraAction::DragPan, &keyboard, &mouse) {
+        if let (Some(cursor), Some(last)) = (cursor, *last_cursor) {
+            let moved = cursor - last;
+            control.pan_by(Vec2::new(-moved.x, moved.y) * scale);
+        }
+    } else if settings.edge_pan && control.current_zoom > settings.default_zoom {
+        if let Some(cursor) = cursor {
+            let margin = settings.edge_pan_margin;
+            let edge = |position: f32, size: f32| {
+                if position < margin {
+                    -1.0
+                } else if position > size - margin {
+                    1.0
+                } else {
+                    0.0
+                }
+            };
+            let direction = Vec2::new(edge(cursor.x, window.width()), -edge(cursor.y, window.height()));
+            control.pan_by(direction * settings.pan_speed * TILE_SIZE * time.delta_seconds());
+        }
+    }
+    *last_cursor = cursor;
+
+    // Never pan further than the map reaches
+    let map_size = Vec2::new(map.width as f32, map.height as f32) * TILE_SIZE;
+    control.pan = control.pan.clamp(-map_size, map_size);
+}
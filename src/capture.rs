@@ -0,0 +1,145 @@
+use bevy::prelude::*;
+use bevy::render::view::screenshot::ScreenshotManager;
+use bevy::window::PrimaryWindow;
+use image::{imageops, Rgba, RgbaImage};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::assets::TextureAtlases;
+use crate::components::Tile;
+use crate::map::{TileIndex, TileMap};
+use crate::ui::MessageLog;
+
+/// Folder screenshots and map exports are written to
+pub const CAPTURE_DIR: &str = "screenshots";
+
+// Drawn where a tile has no sprite to copy
+const MISSING_TILE_COLOR: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+// A fresh path under CAPTURE_DIR, e.g. screenshots/map_level3_1700000000.png
+fn capture_path(prefix: &str) -> Result<PathBuf, String> {
+    fs::create_dir_all(CAPTURE_DIR).map_err(|e| format!("could not create {}: {}", CAPTURE_DIR, e))?;
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    Ok(Path::new(CAPTURE_DIR).join(format!("{}_{}.png", prefix, timestamp)))
+}
+
+// System to save what's on screen (F12)
+pub fn take_screenshot_system(
+    keyboard: Res<Input<KeyCode>>,
+    main_window: Query<Entity, With<PrimaryWindow>>,
+    mut screenshot_manager: ResMut<ScreenshotManager>,
+    mut message_log: ResMut<MessageLog>,
+) {
+    if !keyboard.just_pressed(KeyCode::F12) || keyboard.pressed(KeyCode::ShiftLeft) {
+        return;
+    }
+
+    let window = if let Ok(window) = main_window.get_single() {
+        window
+    } else {
+        return;
+    };
+
+    let result = capture_path("screenshot").and_then(|path| {
+        screenshot_manager
+            .save_screenshot_to_disk(window, &path)
+            .map(|_| path)
+            .map_err(|e| e.to_string())
+    });
+
+    match result {
+        Ok(path) => {
+            println!("Saving screenshot to {}", path.display());
+            message_log.add_message(format!("Screenshot saved to {}", path.display()));
+        }
+        Err(e) => eprintln!("Could not take screenshot: {}", e),
+    }
+}
+
+// Draw every tile of the map, not just the part in view, from the tile atlas
+pub fn render_map_image(
+    map: &TileMap,
+    tile_index: &TileIndex,
+    tile_sprites: &Query<&TextureAtlasSprite, With<Tile>>,
+    atlas: &TextureAtlas,
+    atlas_image: &Image,
+) -> Result<RgbaImage, String> {
+    let sheet = atlas_image
+        .clone()
+        .try_into_dynamic()
+        .map_err(|e| format!("tile atlas can't be read back: {:?}", e))?
+        .to_rgba8();
+
+    let tile_size = atlas.textures.first().map_or(32, |rect| rect.width() as u32);
+    let mut output = RgbaImage::from_pixel(map.width as u32 * tile_size, map.height as u32 * tile_size, MISSING_TILE_COLOR);
+
+    for y in 0..map.height {
+        for x in 0..map.width {
+            let sprite_index = tile_index
+                .get(x as i32, y as i32)
+                .and_then(|entity| tile_sprites.get(entity).ok())
+                .map(|sprite| sprite.index);
+            let rect = if let Some(rect) = sprite_index.and_then(|index| atlas.textures.get(index)) {
+                rect
+            } else {
+                continue;
+            };
+
+            let cell = imageops::crop_imm(&sheet, rect.min.x as u32, rect.min.y as u32, tile_size, tile_size).to_image();
+            // Image rows run top to bottom while map rows run bottom to top
+            let out_y = (map.height - 1 - y) as i64 * tile_size as i64;
+            imageops::replace(&mut output, &cell, x as i64 * tile_size as i64, out_y);
+        }
+    }
+
+    Ok(output)
+}
+
+// System to write the whole current level to a PNG (Shift+F12)
+pub fn export_map_image_system(
+    keyboard: Res<Input<KeyCode>>,
+    map: Res<TileMap>,
+    tile_index: Res<TileIndex>,
+    tile_sprites: Query<&TextureAtlasSprite, With<Tile>>,
+    texture_atlases: Res<TextureAtlases>,
+    atlas_assets: Res<Assets<TextureAtlas>>,
+    images: Res<Assets<Image>>,
+    mut message_log: ResMut<MessageLog>,
+) {
+    if !(keyboard.pressed(KeyCode::ShiftLeft) && keyboard.just_pressed(KeyCode::F12)) {
+        return;
+    }
+
+    let atlas = if let Some(atlas) = atlas_assets.get(&texture_atlases.tiles) {
+        atlas
+    } else {
+        eprintln!("Can't export the map before the tile atlas has loaded");
+        return;
+    };
+    let atlas_image = if let Some(image) = images.get(&atlas.texture) {
+        image
+    } else {
+        eprintln!("Can't export the map before the tile atlas image has loaded");
+        return;
+    };
+
+    let result = render_map_image(&map, &tile_index, &tile_sprites, atlas, atlas_image).and_then(|image| {
+        let path = capture_path(&format!("map_level{}", map.current_level + 1))?;
+        image.save(&path).map_err(|e| format!("could not write {}: {}", path.display(), e))?;
+        Ok(path)
+    });
+
+    match result {
+        Ok(path) => {
+            println!("Exported {}x{} map to {}", map.width, map.height, path.display());
+            message_log.add_message(format!("Map saved to {}", path.display()));
+        }
+        Err(e) => {
+            eprintln!("Could not export map: {}", e);
+            message_log.add_message("Could not save the map image.".to_string());
+        }
+    }
+}
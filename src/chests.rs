@@ -0,0 +1,179 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::assets::{get_tile_sprite, SpriteAssets, TextureAtlases};
+use crate::components::{GameTurn, Player, Position, Skills};
+use crate::events::ItemPickedUp;
+use crate::gold::{roll_gold, GoldCollected};
+use crate::hearing::{NoiseEvent, NoiseKind};
+use crate::identify::ItemAppearances;
+use crate::input::TILE_SIZE;
+use crate::interaction::{Interactable, InteractedWith, InteractionKind};
+use crate::inventory::{Inventory, ItemKind};
+use crate::loot::roll_loot;
+use crate::map::TileMap;
+use crate::rng::{GameRng, RngStream};
+use crate::ui::MessageLog;
+use crate::level::DungeonState;
+
+// Base chance a chest is locked, plus a bit more per level
+const LOCKED_CHANCE: f64 = 0.25;
+const LOCKED_CHANCE_PER_LEVEL: f64 = 0.03;
+// Bonus a lockpick gives to the lockpicking check
+const LOCKPICK_BONUS: i32 = 3;
+// Chance a chest also holds coin
+const CHEST_GOLD_CHANCE: f64 = 0.6;
+// Mixed into the level seed so a chest's contents don't follow whatever else is rolled for its tile
+const CHEST_SALT: u64 = 0x6368_6573_7400;
+
+/// A container holding loot
+#[derive(Component, Debug)]
+pub struct Chest {
+    pub contents: Vec<ItemKind>,
+    pub gold: u32,
+    pub locked: bool,
+    pub lock_difficulty: i32, // Target for a d20 + lockpicking roll
+    pub opened: bool,
+    pub tile: (usize, usize),
+}
+
+// Spawn a chest entity for every unopened chest on the map. What's in each one is rolled from the
+// level's seed and the chest's tile, so leaving and coming back finds the same loot and the same lock
+pub fn spawn_chests(
+    commands: &mut Commands,
+    map: &TileMap,
+    texture_atlases: &TextureAtlases,
+    sprite_assets: &SpriteAssets,
+) {
+    let depth = map.current_level;
+    let locked_chance = (LOCKED_CHANCE + LOCKED_CHANCE_PER_LEVEL * depth as f64).min(0.75);
+
+    for &(x, y) in &map.chest_positions {
+        let mut rng = RngStream::Spawns.seeded(map.seed ^ CHEST_SALT ^ ((x as u64) << 32 | y as u64));
+        let biome = map.get_biome_at(x, y);
+        let locked = rng.gen_bool(locked_chance);
+
+        commands.spawn((
+            SpriteSheetBundle {
+                texture_atlas: texture_atlases.tiles.clone(),
+                sprite: TextureAtlasSprite {
+                    index: get_tile_sprite(sprite_assets, "chest (closed)"),
+                    ..default()
+                },
+                transform: Transform::from_xyz(
+                    x as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+                    y as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+                    3.0 // Above tiles, below creatures
+                ),
+                ..default()
+            },
+            Chest {
+                contents: roll_loot(biome, depth, &mut rng),
+                gold: if rng.gen_bool(CHEST_GOLD_CHANCE) { roll_gold(depth, &mut rng) } else { 0 },
+                locked,
+                lock_difficulty: 10 + depth as i32 / 2,
+                opened: false,
+                tile: (x, y),
+            },
+            Interactable::new(InteractionKind::Open, "Open the chest"),
+            Position::new(x as i32, y as i32),
+        ));
+    }
+}
+
+// System to open the chest E was used on
+pub fn open_chest_system(
+    mut commands: Commands,
+    mut interactions: EventReader<InteractedWith>,
+    mut player_query: Query<(&Position, &mut Inventory, &Skills), With<Player>>,
+    mut chest_query: Query<(&mut Chest, &Position, &mut TextureAtlasSprite)>,
+    sprite_assets: Res<SpriteAssets>,
+    mut map: ResMut<TileMap>,
+    mut dungeon_state: ResMut<DungeonState>,
+    mut message_log: ResMut<MessageLog>,
+    mut game_turn: ResMut<GameTurn>,
+    mut noise_events: EventWriter<NoiseEvent>,
+    mut pickup_events: EventWriter<ItemPickedUp>,
+    mut gold_events: EventWriter<GoldCollected>,
+    appearances: Res<ItemAppearances>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    let chest_entity = if let Some(interaction) = interactions.read().find(|interaction| interaction.kind == InteractionKind::Open) {
+        interaction.target
+    } else {
+        return;
+    };
+
+    let (player_pos, mut inventory, skills) = if let Ok(player) = player_query.get_single_mut() {
+        player
+    } else {
+        return;
+    };
+
+    let (mut chest, _, mut sprite) = if let Ok(chest) = chest_query.get_mut(chest_entity) {
+        chest
+    } else {
+        return;
+    };
+    if chest.opened {
+        return;
+    }
+
+    // Opening (or trying to) takes a turn, and isn't quiet
+    game_turn.increment();
+    noise_events.send(NoiseEvent { x: player_pos.x, y: player_pos.y, kind: NoiseKind::Chest });
+
+    if chest.locked {
+        if inventory.remove(ItemKind::Key) {
+            message_log.add_message("You unlock the chest with a key".to_string());
+        } else {
+            let has_lockpick = inventory.count(ItemKind::Lockpick) > 0;
+            let bonus = if has_lockpick { LOCKPICK_BONUS } else { 0 };
+            let roll = game_rng.combat.gen_range(1..=20);
+
+            if roll + skills.lockpicking + bonus >= chest.lock_difficulty {
+                message_log.add_message("You pick the lock".to_string());
+            } else {
+                // A failed attempt with a lockpick snaps it
+                if has_lockpick && roll <= 5 {
+                    inventory.remove(ItemKind::Lockpick);
+                    message_log.add_message("Your lockpick snaps in the lock".to_string());
+                } else {
+                    message_log.add_message("The chest is locked and you fail to pick it".to_string());
+                }
+                return;
+            }
+        }
+        chest.locked = false;
+    }
+
+    chest.opened = true;
+    commands.entity(chest_entity).remove::<Interactable>();
+    sprite.index = get_tile_sprite(&sprite_assets, "chest (open)");
+
+    let gold = std::mem::take(&mut chest.gold);
+    if gold > 0 {
+        message_log.add_message(format!("You find {} gold", gold));
+        gold_events.send(GoldCollected { amount: gold });
+    }
+    if chest.contents.is_empty() {
+        if gold == 0 {
+            message_log.add_message("The chest is empty".to_string());
+        }
+    } else {
+        let names: Vec<String> = chest.contents.iter().map(|&item| appearances.display_name(item)).collect();
+        message_log.add_message(format!("You find: {}", names.join(", ")));
+        for item in chest.contents.drain(..) {
+            inventory.add(item);
+            pickup_events.send(ItemPickedUp { item });
+        }
+    }
+
+    // Don't respawn this chest when the level is revisited
+    let tile = chest.tile;
+    map.chest_positions.retain(|&pos| pos != tile);
+    let current_level = dungeon_state.current_level_index;
+    if let Some(level) = dungeon_state.levels.get_mut(current_level) {
+        level.chest_positions.retain(|&pos| pos != tile);
+    }
+}
@@ -0,0 +1,337 @@
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+
+use crate::assets::TextureAtlases;
+use crate::biome::BiomeType;
+use crate::boss::{Boss, BossKind};
+use crate::combat::{CombatStats, Health};
+use crate::components::{GameTurn, Npc, Player, Position};
+use crate::dialogue::{generate_biome_cryptic_dialogue, CharacterType};
+use crate::faction::Faction;
+use crate::lighting::LightMap;
+use crate::map::TileMap;
+use crate::rng::GameRng;
+use crate::ui::MessageLog;
+use crate::visibility::{floor_darkness, line_of_sight, Vision};
+use crate::GameState;
+
+/// File the codex is kept in, across runs
+pub const CODEX_PATH: &str = "codex.json";
+// Encounters needed before an entry's stats are shown
+const STATS_REVEAL_ENCOUNTERS: u32 = 3;
+const CODEX_SPRITE_SIZE: f32 = 32.0;
+
+/// The sections of the codex
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CodexCategory {
+    Animal,
+    Monster,
+    Character,
+    Biome,
+    Lore,
+}
+
+impl CodexCategory {
+    pub const ALL: [CodexCategory; 5] = [CodexCategory::Animal, CodexCategory::Monster, CodexCategory::Character, CodexCategory::Biome, CodexCategory::Lore];
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            CodexCategory::Animal => "Bestiary",
+            CodexCategory::Monster => "Monsters",
+            CodexCategory::Character => "Folk of the Chasm",
+            CodexCategory::Biome => "Regions",
+            CodexCategory::Lore => "Writings",
+        }
+    }
+}
+
+/// Which atlas an entry's sprite comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CodexAtlas {
+    Characters,
+    Monsters,
+    Animals,
+}
+
+/// One thing the player has come across
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodexEntry {
+    pub category: CodexCategory,
+    pub name: String,
+    pub encounters: u32,
+    pub flavor: String,         // Written on the first encounter
+    pub stats: Option<String>,  // Kept up to date, but only shown after a few encounters
+    pub sprite: Option<(CodexAtlas, usize)>,
+}
+
+impl CodexEntry {
+    pub fn stats_revealed(&self) -> bool {
+        self.encounters >= STATS_REVEAL_ENCOUNTERS
+    }
+}
+
+/// Everything encountered so far, over every run
+#[derive(Resource, Debug, Default, Serialize, Deserialize)]
+pub struct Codex {
+    pub entries: Vec<CodexEntry>,
+}
+
+impl Codex {
+    // Read the saved codex, starting empty if there's none or it can't be read
+    pub fn load() -> Self {
+        match fs::read_to_string(CODEX_PATH) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                eprintln!("Ignoring unreadable {}: {}", CODEX_PATH, e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let contents = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(CODEX_PATH, contents).map_err(|e| format!("could not write {}: {}", CODEX_PATH, e))
+    }
+
+    // Count an encounter; returns true the first time something is met
+    fn record(
+        &mut self,
+        category: CodexCategory,
+        name: &str,
+        stats: Option<String>,
+        sprite: Option<(CodexAtlas, usize)>,
+        flavor: impl FnOnce() -> String,
+    ) -> bool {
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.category == category && entry.name == name) {
+            entry.encounters += 1;
+            entry.stats = stats.or(entry.stats.take());
+            return false;
+        }
+        self.entries.push(CodexEntry {
+            category,
+            name: name.to_string(),
+            encounters: 1,
+            flavor: flavor(),
+            stats,
+            sprite,
+        });
+        true
+    }
+
+    /// Note down a region the player has walked into, saving straight away;
+    /// returns true the first time it's found
+    pub fn record_biome(&mut self, biome: BiomeType, rng: &mut impl rand::Rng) -> bool {
+        let stats = Some(format!("Guardian: {}", BossKind::for_biome(biome).get_name()));
+        let added = self.record(CodexCategory::Biome, biome.get_name(), stats, None, || generate_biome_cryptic_dialogue(&biome, rng));
+        if let Err(e) = self.save() {
+            eprintln!("Could not save the codex: {}", e);
+        }
+        added
+    }
+
+    /// Note down a gravestone, tablet or sign that was read, saving straight away;
+    /// returns true the first time it's read
+    pub fn record_reading(&mut self, title: &str, text: &str) -> bool {
+        let added = self.record(CodexCategory::Lore, title, None, None, || text.to_string());
+        if let Err(e) = self.save() {
+            eprintln!("Could not save the codex: {}", e);
+        }
+        added
+    }
+}
+
+// Flavour for a creature, in the same spirit as the NPC dialogue generators
+fn generate_animal_flavor(name: &str, rng: &mut impl rand::Rng) -> String {
+    let lines = [
+        "Something about the way the {} moves suggests it has never seen daylight.",
+        "Dwellers say a {} down here is a sign that water is near.",
+        "The {} has grown bold in the dark, and wary of torchlight.",
+        "Scratches on the walls mark where a {} made its den.",
+        "Even the cultists leave the {} well alone.",
+    ];
+    let line = lines.choose(rng).unwrap_or(&lines[0]);
+    line.replace("{}", &name.to_lowercase())
+}
+
+// "FemaleKnight" reads as "Female Knight"
+fn character_type_name(character_type: &CharacterType) -> String {
+    let mut name = String::new();
+    for (i, c) in format!("{:?}", character_type).chars().enumerate() {
+        if i > 0 && c.is_uppercase() {
+            name.push(' ');
+        }
+        name.push(c);
+    }
+    name
+}
+
+// System to note every creature the player can see, counting each once per level. Regions are
+// noted as they're discovered, by cutscene::discover_biomes_system
+pub fn record_encounters_system(
+    game_turn: Res<GameTurn>,
+    map: Res<TileMap>,
+    player_query: Query<(&Position, Option<&Vision>), With<Player>>,
+    light_map: Option<Res<LightMap>>,
+    npc_query: Query<(Entity, &Npc, &Position, Option<&Health>, Option<&CombatStats>, Option<&Boss>, Option<&Faction>, Option<&TextureAtlasSprite>)>,
+    mut codex: ResMut<Codex>,
+    mut message_log: ResMut<MessageLog>,
+    mut game_rng: ResMut<GameRng>,
+    mut seen: Local<(u64, u32, HashSet<Entity>)>, // Level seed, last turn, creatures seen on it
+) {
+    let (seed, last_turn, seen_entities) = &mut *seen;
+    if *seed != map.seed {
+        *seed = map.seed;
+        seen_entities.clear();
+    } else if *last_turn == game_turn.current_turn {
+        return;
+    }
+    *last_turn = game_turn.current_turn;
+
+    let (player_pos, vision) = if let Ok(player) = player_query.get_single() { player } else { return; };
+    let vision = vision.copied().unwrap_or_default();
+    let darkness = floor_darkness(&map);
+    let mut changed = false;
+
+    for (entity, npc, position, health, combat, boss, faction, sprite) in npc_query.iter() {
+        if seen_entities.contains(&entity) {
+            continue;
+        }
+        let dx = (position.x - player_pos.x) as f32;
+        let dy = (position.y - player_pos.y) as f32;
+        let range = vision.radius_to(darkness, light_map.as_deref(), (position.x, position.y));
+        if (dx * dx + dy * dy).sqrt() > range || !line_of_sight(&map, (player_pos.x, player_pos.y), (position.x, position.y)) {
+            continue;
+        }
+        seen_entities.insert(entity);
+
+        let fighting_stats = health.zip(combat).map(|(health, combat)| format!("Health {}, Attack {}", health.max, combat.attack));
+        let first_line = npc.dialog.first().map(|line| format!("\"{}\"", line)).unwrap_or_default();
+        let (category, name, stats, atlas, flavor): (_, String, _, _, Box<dyn FnOnce() -> String + '_>) = if let Some(boss) = boss {
+            (CodexCategory::Monster, boss.kind.get_name().to_string(), fighting_stats, CodexAtlas::Monsters, Box::new(move || first_line))
+        } else if let Some(animal_type) = npc.animal_type.filter(|_| npc.is_animal) {
+            let name = animal_type.get_name().to_string();
+            let flavor_name = name.clone();
+            let rng = &mut game_rng.dialogue;
+            (CodexCategory::Animal, name, fighting_stats, CodexAtlas::Animals, Box::new(move || generate_animal_flavor(&flavor_name, rng)))
+        } else {
+            let stats = faction.map(|faction| format!("Faction: {}", faction.get_name()));
+            (CodexCategory::Character, character_type_name(&npc.character_type), stats, CodexAtlas::Characters, Box::new(move || first_line))
+        };
+
+        if codex.record(category, &name, stats, sprite.map(|sprite| (atlas, sprite.index)), flavor) {
+            message_log.add_message(format!("Codex: {} added", name));
+        }
+        changed = true;
+    }
+
+    if changed {
+        if let Err(e) = codex.save() {
+            eprintln!("Could not save the codex: {}", e);
+        }
+    }
+}
+
+/// Marker for everything on the codex screen
+#[derive(Component)]
+pub struct CodexScreen;
+
+pub fn setup_codex_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    codex: Res<Codex>,
+    texture_atlases: Res<TextureAtlases>,
+) {
+    let font = asset_server.load("fonts/FiraSans-Light.ttf");
+    let style = |size: f32, color: Color| TextStyle { font: font.clone(), font_size: size, color };
+
+    commands.spawn((crate::menu::screen_root(), CodexScreen)).with_children(|parent| {
+        parent.spawn(TextBundle::from_section("Codex", style(40.0, Color::WHITE)));
+
+        // One column per section
+        parent.spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                column_gap: Val::Px(24.0),
+                align_items: AlignItems::FlexStart,
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|columns| {
+            for category in CodexCategory::ALL {
+                columns.spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(8.0),
+                        width: Val::Px(260.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|column| {
+                    column.spawn(TextBundle::from_section(category.get_name(), style(22.0, Color::GOLD)));
+
+                    let mut any = false;
+                    for entry in codex.entries.iter().filter(|entry| entry.category == category) {
+                        any = true;
+                        column.spawn(NodeBundle {
+                            style: Style { flex_direction: FlexDirection::Row, column_gap: Val::Px(8.0), ..default() },
+                            ..default()
+                        })
+                        .with_children(|row| {
+                            if let Some((atlas, index)) = entry.sprite {
+                                let texture_atlas = match atlas {
+                                    CodexAtlas::Characters => texture_atlases.characters.clone(),
+                                    CodexAtlas::Monsters => texture_atlases.monsters.clone(),
+                                    CodexAtlas::Animals => texture_atlases.animals.clone(),
+                                };
+                                row.spawn(AtlasImageBundle {
+                                    style: Style {
+                                        width: Val::Px(CODEX_SPRITE_SIZE),
+                                        height: Val::Px(CODEX_SPRITE_SIZE),
+                                        flex_shrink: 0.0,
+                                        ..default()
+                                    },
+                                    texture_atlas,
+                                    texture_atlas_image: UiTextureAtlasImage { index, ..default() },
+                                    ..default()
+                                });
+                            }
+
+                            let stats = if entry.category == CodexCategory::Lore {
+                                String::new() // Nothing more to learn from reading it again
+                            } else if entry.stats_revealed() {
+                                entry.stats.clone().unwrap_or_default()
+                            } else {
+                                format!("Meet it {} more times to learn more", STATS_REVEAL_ENCOUNTERS - entry.encounters)
+                            };
+                            row.spawn(TextBundle::from_sections([
+                                TextSection::new(format!("{} (seen {})\n", entry.name, entry.encounters), style(16.0, Color::WHITE)),
+                                TextSection::new(format!("{}\n", entry.flavor), style(13.0, Color::rgb(0.75, 0.75, 0.75))),
+                                TextSection::new(stats, style(13.0, Color::rgb(0.6, 0.8, 1.0))),
+                            ]));
+                        });
+                    }
+                    if !any {
+                        column.spawn(TextBundle::from_section("Nothing found yet.", style(14.0, Color::GRAY)));
+                    }
+                });
+            }
+        });
+
+        parent.spawn(TextBundle::from_section("Backspace - Back", style(16.0, Color::GRAY)));
+    });
+}
+
+// System to go back to the main menu
+pub fn codex_screen_system(
+    keyboard: Res<Input<KeyCode>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard.any_just_pressed([KeyCode::Back, KeyCode::C, KeyCode::Return]) {
+        next_state.set(GameState::MainMenu);
+    }
+}
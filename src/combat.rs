@@ -0,0 +1,367 @@
+use bevy::prelude::*;
+
+use crate::components::{Companion, Npc, Player, Position, GameTurn};
+use crate::faction::{Faction, ReputationChange};
+use crate::events::{CreatureKilled, EntityDamaged, PlayerAttacked};
+use crate::hearing::{NoiseEvent, NoiseKind};
+use crate::infighting::SlainByCreature;
+use crate::input::{InputState, TileClick, TILE_SIZE};
+use crate::map::TileMap;
+use crate::run_summary::RunStats;
+use crate::status::{ApplyStatusEffect, StatusEffect, StatusEffects};
+use crate::ui::MessageLog;
+use crate::visibility::{bresenham_line, blocks_sight, line_of_sight, VisibilityMap};
+use crate::warden::Warden;
+
+// Seconds a projectile spends crossing each tile
+const PROJECTILE_STEP_TIME: f32 = 0.04;
+/// Reputation lost when shooting (or throwing something at) a faction member
+pub const ATTACK_REPUTATION_PENALTY: i32 = -15;
+
+/// Hit points for anything that can be hurt
+#[derive(Component, Debug, Clone)]
+pub struct Health {
+    pub current: i32,
+    pub max: i32,
+}
+
+impl Health {
+    pub fn new(max: i32) -> Self {
+        Self { current: max, max }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0
+    }
+
+    // Apply damage and return true if this killed the entity
+    pub fn take_damage(&mut self, amount: i32) -> bool {
+        let was_alive = !self.is_dead();
+        self.current = (self.current - amount.max(0)).max(0);
+        was_alive && self.is_dead()
+    }
+
+    pub fn heal(&mut self, amount: i32) {
+        self.current = (self.current + amount.max(0)).min(self.max);
+    }
+}
+
+/// Basic offensive stats
+#[derive(Component, Debug, Clone)]
+pub struct CombatStats {
+    pub attack: i32,
+}
+
+impl Default for CombatStats {
+    fn default() -> Self {
+        Self { attack: 1 }
+    }
+}
+
+/// Lets an entity attack from a distance
+#[derive(Component, Debug, Clone)]
+pub struct RangedAttack {
+    pub damage: i32,
+    pub range: i32,
+}
+
+/// A projectile in flight along a precomputed line of tiles
+#[derive(Component, Debug)]
+pub struct Projectile {
+    pub start: (i32, i32),
+    pub path: Vec<(i32, i32)>,
+    pub step: usize,
+    pub timer: Timer,
+    pub damage: i32,
+    pub target: Option<Entity>,
+    pub source: String,                // Named in the damage report, e.g. "your bolt"
+    pub status: Option<StatusEffect>,  // Put on whatever it hits
+    pub from_player: bool,             // Hitting a faction member with it costs reputation
+}
+
+// System to fire a ranged attack: F, then a direction key or a mouse click on the target
+pub fn fire_ranged_attack(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    tile_click: Res<TileClick>,
+    mut input_state: ResMut<InputState>,
+    player_query: Query<(&Position, &RangedAttack, Option<&StatusEffects>), With<Player>>,
+    creature_query: Query<(Entity, &Position), (With<Health>, Without<Player>)>,
+    map: Res<TileMap>,
+    visibility_map: Option<Res<VisibilityMap>>,
+    mut game_turn: ResMut<GameTurn>,
+    mut noise_events: EventWriter<NoiseEvent>,
+    mut attack_events: EventWriter<PlayerAttacked>,
+) {
+    if !input_state.aiming {
+        return;
+    }
+    
+    let (player_pos, ranged, status) = if let Ok(player) = player_query.get_single() {
+        player
+    } else {
+        // Only ranged attackers can aim
+        input_state.aiming = false;
+        return;
+    };
+    
+    // Work out the tile being aimed at
+    let direction = if keyboard_input.just_pressed(KeyCode::W) || keyboard_input.just_pressed(KeyCode::Up) {
+        Some((0, 1))
+    } else if keyboard_input.just_pressed(KeyCode::S) || keyboard_input.just_pressed(KeyCode::Down) {
+        Some((0, -1))
+    } else if keyboard_input.just_pressed(KeyCode::A) || keyboard_input.just_pressed(KeyCode::Left) {
+        Some((-1, 0))
+    } else if keyboard_input.just_pressed(KeyCode::D) || keyboard_input.just_pressed(KeyCode::Right) {
+        Some((1, 0))
+    } else {
+        None
+    };
+    
+    let target_tile = if let Some((dx, dy)) = direction {
+        (player_pos.x + dx * ranged.range, player_pos.y + dy * ranged.range)
+    } else if let Some(tile) = tile_click.tile {
+        tile
+    } else {
+        return;
+    };
+    
+    input_state.aiming = false;
+    
+    if target_tile == (player_pos.x, player_pos.y) {
+        return;
+    }
+    
+    // Mouse targets must be in view
+    if direction.is_none() {
+        let can_see = if let Some(visibility_map) = &visibility_map {
+            let in_bounds = target_tile.0 >= 0 && target_tile.1 >= 0
+                && (target_tile.1 as usize) < visibility_map.visible_tiles.len()
+                && (target_tile.0 as usize) < visibility_map.visible_tiles[0].len();
+            in_bounds && visibility_map.visible_tiles[target_tile.1 as usize][target_tile.0 as usize]
+        } else {
+            // No visibility map yet, fall back to a direct line of sight check
+            line_of_sight(&map, (player_pos.x, player_pos.y), target_tile)
+        };
+        if !can_see {
+            println!("You can't see that spot");
+            return;
+        }
+    }
+    
+    // Trace the flight path, stopping at the first wall or creature
+    let creatures: Vec<(Entity, (i32, i32))> = creature_query.iter().map(|(entity, position)| (entity, (position.x, position.y))).collect();
+    let (path, target) = trace_projectile_path(&map, (player_pos.x, player_pos.y), target_tile, ranged.range, &creatures);
+    
+    // Firing takes a turn even if the shot goes nowhere
+    game_turn.increment();
+    noise_events.send(NoiseEvent { x: player_pos.x, y: player_pos.y, kind: NoiseKind::Combat });
+    attack_events.send(PlayerAttacked);
+    
+    if path.is_empty() {
+        println!("Your bolt fizzles against the wall");
+        return;
+    }
+    
+    println!("You fire a bolt toward ({}, {})", target_tile.0, target_tile.1);
+    
+    // Blessings and curses from the altars change how hard it hits
+    let damage = (ranged.damage + status.map_or(0, |status| status.damage_modifier())).max(1);
+    spawn_projectile(
+        &mut commands,
+        (player_pos.x, player_pos.y),
+        path,
+        damage,
+        target,
+        Color::rgb(0.6, 0.8, 1.0),
+    );
+}
+
+// Work out which tiles a projectile crosses before hitting a wall, a creature or running out of range
+pub fn trace_projectile_path(
+    map: &TileMap,
+    start: (i32, i32),
+    target_tile: (i32, i32),
+    range: i32,
+    creatures: &[(Entity, (i32, i32))],
+) -> (Vec<(i32, i32)>, Option<Entity>) {
+    let mut path = Vec::new();
+    for (x, y) in bresenham_line(start.0, start.1, target_tile.0, target_tile.1).into_iter().skip(1) {
+        if (x - start.0).abs().max((y - start.1).abs()) > range || blocks_sight(x, y, map) {
+            break;
+        }
+        path.push((x, y));
+        if let Some((entity, _)) = creatures.iter().find(|(_, tile)| *tile == (x, y)) {
+            return (path, Some(*entity));
+        }
+    }
+    (path, None)
+}
+
+// Spawn one of the player's projectiles that flies from `start` along `path` and damages `target` on arrival
+pub fn spawn_projectile(
+    commands: &mut Commands,
+    start: (i32, i32),
+    path: Vec<(i32, i32)>,
+    damage: i32,
+    target: Option<Entity>,
+    color: Color,
+) {
+    spawn_projectile_from(commands, start, path, color, Projectile {
+        start,
+        path: Vec::new(),
+        step: 0,
+        timer: Timer::from_seconds(PROJECTILE_STEP_TIME, TimerMode::Repeating),
+        damage,
+        target,
+        source: "your bolt".to_string(),
+        status: None,
+        from_player: true,
+    });
+}
+
+// Spawn a monster's projectile, named for the damage report and carrying an effect for whatever it hits
+pub fn spawn_hostile_projectile(
+    commands: &mut Commands,
+    start: (i32, i32),
+    path: Vec<(i32, i32)>,
+    damage: i32,
+    target: Option<Entity>,
+    color: Color,
+    source: String,
+    status: Option<StatusEffect>,
+) {
+    spawn_projectile_from(commands, start, path, color, Projectile {
+        start,
+        path: Vec::new(),
+        step: 0,
+        timer: Timer::from_seconds(PROJECTILE_STEP_TIME, TimerMode::Repeating),
+        damage,
+        target,
+        source,
+        status,
+        from_player: false,
+    });
+}
+
+fn spawn_projectile_from(commands: &mut Commands, start: (i32, i32), path: Vec<(i32, i32)>, color: Color, projectile: Projectile) {
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color,
+                custom_size: Some(Vec2::splat(TILE_SIZE / 4.0)),
+                ..default()
+            },
+            transform: Transform::from_xyz(
+                start.0 as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+                start.1 as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+                12.0 // Above the player
+            ),
+            ..default()
+        },
+        Projectile { path, ..projectile },
+    ));
+}
+
+// "your bolt" -> "Your bolt", for the start of a sentence
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    chars.next().map_or(String::new(), |first| first.to_uppercase().chain(chars).collect())
+}
+
+// System to move projectiles tile by tile and apply damage when they land
+pub fn animate_projectiles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut projectile_query: Query<(Entity, &mut Projectile, &mut Transform)>,
+    mut target_query: Query<(&mut Health, Option<&Npc>, Option<&Faction>, Option<&Player>), Without<Projectile>>,
+    mut reputation_events: EventWriter<ReputationChange>,
+    mut damage_events: EventWriter<EntityDamaged>,
+    mut status_events: EventWriter<ApplyStatusEffect>,
+    mut message_log: ResMut<MessageLog>,
+) {
+    for (entity, mut projectile, mut transform) in projectile_query.iter_mut() {
+        projectile.timer.tick(time.delta());
+        
+        // Glide from the previous tile toward the current one
+        let from = if projectile.step == 0 { projectile.start } else { projectile.path[projectile.step - 1] };
+        let to = projectile.path[projectile.step];
+        let origin = Vec3::new(
+            from.0 as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+            from.1 as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+            transform.translation.z
+        );
+        let destination = Vec3::new(
+            to.0 as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+            to.1 as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+            transform.translation.z
+        );
+        transform.translation = origin.lerp(destination, projectile.timer.percent());
+        
+        if !projectile.timer.just_finished() {
+            continue;
+        }
+        
+        transform.translation = destination;
+        projectile.step += 1;
+        if projectile.step < projectile.path.len() {
+            continue;
+        }
+        
+        // Reached the end of the path
+        if let Some(target) = projectile.target {
+            if let Ok((mut health, npc, faction, player)) = target_query.get_mut(target) {
+                let target_name = npc.map_or("the creature".to_string(), |npc| npc.name.clone());
+                let killed = health.take_damage(projectile.damage);
+                damage_events.send(EntityDamaged { target, amount: projectile.damage, source: projectile.source.clone() });
+                if player.is_some() {
+                    message_log.add_message(format!("{} hits you for {} damage", capitalize(&projectile.source), projectile.damage));
+                } else {
+                    println!("{} hits {} for {} damage", capitalize(&projectile.source), target_name, projectile.damage);
+                }
+                if killed {
+                    println!("{} is slain", target_name);
+                }
+                if let Some(effect) = projectile.status.clone() {
+                    status_events.send(ApplyStatusEffect { target, effect });
+                }
+                
+                // Shooting a faction member doesn't go unnoticed
+                if let Some(faction) = faction.filter(|_| projectile.from_player) {
+                    reputation_events.send(ReputationChange {
+                        faction: *faction,
+                        amount: ATTACK_REPUTATION_PENALTY,
+                        reason: format!("attacked {}", target_name),
+                    });
+                }
+            }
+        }
+        
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// Remove anything that has run out of health (the player is handled separately)
+pub fn despawn_dead_entities(
+    mut commands: Commands,
+    query: Query<(Entity, &Health, Option<&Npc>, Option<&Companion>, Option<&SlainByCreature>), (Without<Player>, Without<Warden>)>,
+    mut run_stats: ResMut<RunStats>,
+    mut kill_events: EventWriter<CreatureKilled>,
+) {
+    for (entity, health, npc, companion, slain_by_creature) in query.iter() {
+        if health.is_dead() {
+            if let Some(npc) = npc {
+                println!("{} has died", npc.name);
+            }
+            // Losing a companion is no victory, and neither is watching creatures kill each other
+            if companion.is_none() && slain_by_creature.is_none() {
+                run_stats.kills += 1;
+                if let Some(npc) = npc {
+                    let name = npc.animal_type.as_ref().map_or_else(|| npc.name.clone(), |animal_type| animal_type.get_name().to_string());
+                    kill_events.send(CreatureKilled { name });
+                }
+            }
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
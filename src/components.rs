@@ -1,9 +1,10 @@
 use bevy::prelude::*;
+use std::collections::VecDeque;
 use crate::biome::{BiomeType, TileWalkability};
 use crate::map::TileType;
 use crate::dialogue::CharacterType;
 
-#[derive(Component, Debug, Default)]
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct Position {
     pub x: i32,
     pub y: i32,
@@ -30,7 +31,7 @@ pub struct PlayerAnimation {
     pub rapid_press_count: u8,
     pub continuous_movement_timer: Timer,
     pub last_movement_direction: Option<MovementDirection>,
-    pub queued_direction: Option<MovementDirection>,
+    pub move_buffer: MoveBuffer,
 }
 
 impl Default for PlayerAnimation {
@@ -46,7 +47,7 @@ impl Default for PlayerAnimation {
             rapid_press_count: 0,
             continuous_movement_timer: Timer::from_seconds(0.5, TimerMode::Once),
             last_movement_direction: None,
-            queued_direction: None,
+            move_buffer: MoveBuffer::default(),
         }
     }
 }
@@ -59,6 +60,45 @@ pub enum MovementDirection {
     Right,
 }
 
+// Moves pressed during a step that are still waiting to be taken; older presses are dropped past this
+const MOVE_BUFFER_SIZE: usize = 3;
+// Seconds a buffered move is kept before it's too old to still mean anything
+const MOVE_BUFFER_EXPIRY: f64 = 0.5;
+
+/// Moves tapped while the player is mid-step, taken in order as each step lands,
+/// so quick taps and changes of direction aren't lost during the animation
+#[derive(Debug, Default, Clone)]
+pub struct MoveBuffer {
+    moves: VecDeque<(MovementDirection, f64)>, // Direction, and when it was pressed (seconds since startup)
+}
+
+impl MoveBuffer {
+    pub fn push(&mut self, direction: MovementDirection, now: f64) {
+        self.moves.push_back((direction, now));
+        while self.moves.len() > MOVE_BUFFER_SIZE {
+            self.moves.pop_front();
+        }
+    }
+
+    // The oldest move that was pressed recently enough to still take
+    pub fn pop(&mut self, now: f64) -> Option<MovementDirection> {
+        while let Some((direction, pressed)) = self.moves.pop_front() {
+            if now - pressed <= MOVE_BUFFER_EXPIRY {
+                return Some(direction);
+            }
+        }
+        None
+    }
+
+    pub fn clear(&mut self) {
+        self.moves.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+}
+
 #[derive(Component, Debug)]
 pub struct Tile {
     pub tile_type: TileType,
@@ -76,13 +116,15 @@ impl Default for Tile {
     }
 }
 
-#[derive(Component, Debug)]
+#[derive(Component, Debug, Clone)]
 pub struct Npc {
     pub speaking: bool,
     pub dialog_text: String,
     pub name: String,
     pub dialog: Vec<String>,
     pub current_dialog_index: usize,
+    pub met: bool,           // Has talked to the player before, so skips the greeting
+    pub lines_heard: usize,  // How much of `dialog` the player has heard; once it's all been said, only idle remarks are left
     pub character_type: CharacterType,
     pub animation_timer: Timer,
     pub original_scale: Vec3,
@@ -98,6 +140,8 @@ impl Default for Npc {
             name: "NPC".to_string(),
             dialog: vec!["Hello!".to_string()],
             current_dialog_index: 0,
+            met: false,
+            lines_heard: 0,
             character_type: CharacterType::Generic,
             animation_timer: Timer::from_seconds(0.2, TimerMode::Repeating),
             original_scale: Vec3::splat(1.0),
@@ -111,6 +155,8 @@ impl Default for Npc {
 pub struct DialogBox {
     pub text: String,
     pub visible: bool,
+    pub speaker: Option<Entity>, // The NPC this box floats above
+    pub revealed: f32,           // How many characters of the text the typewriter has shown
 }
 
 impl Default for DialogBox {
@@ -118,6 +164,24 @@ impl Default for DialogBox {
         Self {
             text: String::new(),
             visible: false,
+            speaker: None,
+            revealed: 0.0,
         }
     }
 }
+
+// Marks an animal that has been tamed, or an NPC who has joined the party, and now follows the player
+#[derive(Component, Debug)]
+pub struct Companion;
+
+// Skill levels used for skill checks (added to a d20 roll)
+#[derive(Component, Debug)]
+pub struct Skills {
+    pub lockpicking: i32,
+}
+
+impl Default for Skills {
+    fn default() -> Self {
+        Self { lockpicking: 2 }
+    }
+}
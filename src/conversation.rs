@@ -0,0 +1,312 @@
+use bevy::prelude::*;
+
+use crate::components::Npc;
+use crate::dialogue::DialogueResponse;
+
+// Lines of history visible in the panel at once
+const VISIBLE_HISTORY: usize = 4;
+const PORTRAIT_SIZE: f32 = 64.0;
+
+/// The conversation the player is having, if any, and everything said in it so far
+#[derive(Resource, Default)]
+pub struct Conversation {
+    pub speaker: Option<Entity>,
+    pub name: String,
+    pub portrait: Option<(Handle<TextureAtlas>, usize)>, // The speaker's atlas sprite
+    pub history: Vec<String>,                           // Oldest first; the last entry is the current line
+    pub scroll: usize,                                  // Lines scrolled back from the newest
+    pub line_finished: bool,                            // Whether the current line has finished typing out
+    pub choices: Vec<DialogueResponse>,                 // Responses on offer for the current line
+    pub selected: usize,
+}
+
+/// Sent when the player picks one of the responses to an NPC's line
+#[derive(Event, Debug, Clone)]
+pub struct DialogueChoiceMade {
+    pub speaker: Entity,
+    pub index: usize,
+    pub response: DialogueResponse,
+}
+
+impl Conversation {
+    pub fn begin(&mut self, speaker: Entity, name: String, portrait: Option<(Handle<TextureAtlas>, usize)>, first_line: String) {
+        *self = Self {
+            speaker: Some(speaker),
+            name,
+            portrait,
+            history: vec![first_line],
+            scroll: 0,
+            line_finished: false,
+            choices: Vec::new(),
+            selected: 0,
+        };
+    }
+
+    pub fn say(&mut self, line: String) {
+        self.history.push(line);
+        self.scroll = 0;
+        self.line_finished = false;
+        self.choices.clear();
+        self.selected = 0;
+    }
+
+    // Responses are shown once the current line has typed out
+    pub fn offer(&mut self, choices: Vec<DialogueResponse>) {
+        self.choices = choices;
+        self.selected = 0;
+    }
+
+    // Movement and number keys pick a response instead of their usual action
+    pub fn awaiting_choice(&self) -> bool {
+        self.is_active() && !self.choices.is_empty()
+    }
+
+    pub fn end(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.speaker.is_some()
+    }
+}
+
+// Markers for the parts of the conversation panel
+#[derive(Component)]
+pub struct ConversationPanel;
+
+#[derive(Component)]
+pub struct ConversationName;
+
+#[derive(Component)]
+pub struct ConversationPortrait;
+
+#[derive(Component)]
+pub struct ConversationHistory;
+
+#[derive(Component)]
+pub struct ConversationChoices;
+
+pub fn setup_conversation_panel(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("fonts/FiraSans-Light.ttf");
+
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Px(640.0),
+                left: Val::Px(80.0),
+                bottom: Val::Px(140.0), // Sits just above the message log
+                padding: UiRect::all(Val::Px(8.0)),
+                column_gap: Val::Px(12.0),
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::FlexStart,
+                ..default()
+            },
+            background_color: BackgroundColor(Color::rgba(0.0, 0.0, 0.0, 0.85)),
+            visibility: Visibility::Hidden,
+            z_index: ZIndex::Global(110),
+            ..default()
+        },
+        ConversationPanel,
+    ))
+    .with_children(|parent| {
+        parent.spawn((
+            AtlasImageBundle {
+                style: Style {
+                    width: Val::Px(PORTRAIT_SIZE),
+                    height: Val::Px(PORTRAIT_SIZE),
+                    ..default()
+                },
+                ..default()
+            },
+            ConversationPortrait,
+        ));
+
+        parent.spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Column,
+                flex_grow: 1.0,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 18.0,
+                        color: Color::GOLD,
+                    },
+                ),
+                ConversationName,
+            ));
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font,
+                        font_size: 15.0,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_style(Style {
+                    max_width: Val::Px(640.0 - PORTRAIT_SIZE - 40.0),
+                    ..default()
+                }),
+                ConversationHistory,
+            ));
+            parent.spawn((
+                TextBundle::from_section("", TextStyle::default()),
+                ConversationChoices,
+            ));
+        });
+    });
+}
+
+// Pick a response with the arrow keys or W/S and confirm with E, Space or Enter, or press its number
+pub fn choose_dialogue_response(
+    mut keyboard: ResMut<Input<KeyCode>>,
+    mut conversation: ResMut<Conversation>,
+    mut choice_events: EventWriter<DialogueChoiceMade>,
+) {
+    // Wait for the line to finish so the key that skipped it doesn't also answer it
+    if !conversation.awaiting_choice() || !conversation.line_finished {
+        return;
+    }
+
+    let count = conversation.choices.len();
+    if keyboard.just_pressed(KeyCode::Up) || keyboard.just_pressed(KeyCode::W) {
+        conversation.selected = (conversation.selected + count - 1) % count;
+    }
+    if keyboard.just_pressed(KeyCode::Down) || keyboard.just_pressed(KeyCode::S) {
+        conversation.selected = (conversation.selected + 1) % count;
+    }
+
+    let number_keys = [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4];
+    let confirm_keys = [KeyCode::E, KeyCode::Space, KeyCode::Return];
+    let picked = if let Some(index) = number_keys.iter().take(count).position(|key| keyboard.just_pressed(*key)) {
+        keyboard.clear_just_pressed(number_keys[index]);
+        Some(index)
+    } else if let Some(&key) = confirm_keys.iter().find(|key| keyboard.just_pressed(**key)) {
+        keyboard.clear_just_pressed(key);
+        Some(conversation.selected)
+    } else {
+        None
+    };
+
+    // The key is used up here so spell and interaction systems don't act on it too
+    if let (Some(index), Some(speaker)) = (picked, conversation.speaker) {
+        let response = conversation.choices[index].clone();
+        println!("Chose response {}: {}", index + 1, response.text);
+        conversation.choices.clear();
+        choice_events.send(DialogueChoiceMade { speaker, index, response });
+    }
+}
+
+// Scroll back through the conversation with Page Up / Page Down
+pub fn scroll_conversation_history(
+    keyboard: Res<Input<KeyCode>>,
+    mut conversation: ResMut<Conversation>,
+) {
+    if !conversation.is_active() {
+        return;
+    }
+
+    let max_scroll = conversation.history.len().saturating_sub(VISIBLE_HISTORY);
+    if keyboard.just_pressed(KeyCode::PageUp) && conversation.scroll < max_scroll {
+        conversation.scroll += 1;
+    }
+    if keyboard.just_pressed(KeyCode::PageDown) && conversation.scroll > 0 {
+        conversation.scroll -= 1;
+    }
+}
+
+// Keep the panel in step with the conversation, closing it when the speaker stops talking
+pub fn update_conversation_panel(
+    mut conversation: ResMut<Conversation>,
+    npc_query: Query<&Npc>,
+    asset_server: Res<AssetServer>,
+    mut panel_query: Query<&mut Visibility, With<ConversationPanel>>,
+    mut name_query: Query<&mut Text, (With<ConversationName>, Without<ConversationHistory>, Without<ConversationChoices>)>,
+    mut history_query: Query<&mut Text, (With<ConversationHistory>, Without<ConversationName>, Without<ConversationChoices>)>,
+    mut choices_query: Query<&mut Text, (With<ConversationChoices>, Without<ConversationName>, Without<ConversationHistory>)>,
+    mut portrait_query: Query<(&mut Handle<TextureAtlas>, &mut UiTextureAtlasImage), With<ConversationPortrait>>,
+) {
+    // Walking away or the speaker disappearing ends the conversation
+    if let Some(speaker) = conversation.speaker {
+        if !npc_query.get(speaker).map_or(false, |npc| npc.speaking) {
+            conversation.end();
+        }
+    }
+
+    if !conversation.is_changed() {
+        return;
+    }
+
+    for mut visibility in panel_query.iter_mut() {
+        *visibility = if conversation.is_active() { Visibility::Visible } else { Visibility::Hidden };
+    }
+    if !conversation.is_active() {
+        return;
+    }
+
+    for mut text in name_query.iter_mut() {
+        text.sections[0].value = conversation.name.clone();
+    }
+
+    if let Some((atlas, index)) = &conversation.portrait {
+        for (mut handle, mut image) in portrait_query.iter_mut() {
+            *handle = atlas.clone();
+            image.index = *index;
+        }
+    }
+
+    // Older lines are dimmed; the newest visible line is the one being spoken unless scrolled back
+    let end = conversation.history.len() - conversation.scroll;
+    let start = end.saturating_sub(VISIBLE_HISTORY);
+    let font = asset_server.load("fonts/FiraSans-Light.ttf");
+    for mut text in history_query.iter_mut() {
+        text.sections = conversation.history[start..end]
+            .iter()
+            .enumerate()
+            .map(|(offset, line)| {
+                let is_current = conversation.scroll == 0 && start + offset == conversation.history.len() - 1;
+                TextSection::new(
+                    format!("{}\n", line),
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 15.0,
+                        color: if is_current { Color::WHITE } else { Color::GRAY },
+                    },
+                )
+            })
+            .collect();
+    }
+
+    // Numbered responses, with the selected one highlighted
+    let choices_visible = conversation.line_finished && conversation.scroll == 0;
+    for mut text in choices_query.iter_mut() {
+        text.sections = if choices_visible {
+            conversation.choices
+                .iter()
+                .enumerate()
+                .map(|(index, choice)| {
+                    let selected = index == conversation.selected;
+                    TextSection::new(
+                        format!("{} {}. {}\n", if selected { ">" } else { " " }, index + 1, choice.text),
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 15.0,
+                            color: if selected { Color::GOLD } else { Color::rgb(0.8, 0.8, 0.8) },
+                        },
+                    )
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+    }
+}
@@ -0,0 +1,120 @@
+use bevy::prelude::*;
+
+use crate::animals::animal_drops;
+use crate::boss::Boss;
+use crate::combat::Health;
+use crate::components::{Animal, Companion, GameTurn, Npc, Player, Position};
+use crate::assets::{SpriteAssets, TextureAtlases};
+use crate::events::ItemPickedUp;
+use crate::gold::spawn_treasure_pile;
+use crate::identify::ItemAppearances;
+use crate::interaction::{Interactable, InteractedWith, InteractionKind};
+use crate::inventory::{Inventory, ItemKind};
+use crate::loot::{roll_drops, CreatureDrop};
+use crate::props::Prop;
+use crate::rng::GameRng;
+use crate::ui::MessageLog;
+
+// Corpses lie with the props, above the floor and below the living
+const CORPSE_Z: f32 = 2.0;
+const CORPSE_TINT: Color = Color::rgb(0.45, 0.4, 0.4);
+
+/// What's left of a creature, and whatever it was carrying
+#[derive(Component, Debug)]
+pub struct Corpse {
+    pub name: String, // As it reads after "search", e.g. "the rat" or "Warchief Uzgar"
+    pub items: Vec<ItemKind>,
+}
+
+// System to leave a body where each creature falls, rolling its drops. Runs
+// before the dead are despawned so their sprite can be copied
+pub fn spawn_corpses_system(
+    mut commands: Commands,
+    dead_query: Query<(&Health, &Position, &Transform, &Handle<TextureAtlas>, &TextureAtlasSprite, Option<&Animal>, Option<&Boss>, Option<&Npc>, Option<&Companion>), Without<Player>>,
+    texture_atlases: Res<TextureAtlases>,
+    sprite_assets: Res<SpriteAssets>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    for (health, pos, transform, atlas, sprite, animal, boss, npc, companion) in dead_query.iter() {
+        if !health.is_dead() {
+            continue;
+        }
+
+        let (name, drops): (String, Vec<CreatureDrop>) = if let Some(boss) = boss {
+            (boss.kind.get_name().to_string(), boss.kind.drops())
+        } else if let Some(animal) = animal {
+            (format!("the {}", animal.animal_type.get_name().to_lowercase()), animal_drops(animal.animal_type))
+        } else if let Some(npc) = npc {
+            // Folk met in the dungeon carry a little coin
+            (npc.name.clone(), vec![CreatureDrop::new(ItemKind::Gold, 0.5, 5)])
+        } else {
+            continue;
+        };
+
+        // Nobody picks over a fallen companion
+        let mut items = if companion.is_some() { Vec::new() } else { roll_drops(&drops, &mut game_rng.combat) };
+
+        // Coins spill onto the floor to be picked up in passing
+        let coins = items.iter().filter(|&&item| item == ItemKind::Gold).count() as u32;
+        items.retain(|&item| item != ItemKind::Gold);
+        if coins > 0 {
+            spawn_treasure_pile(&mut commands, &texture_atlases, &sprite_assets, (pos.x, pos.y), coins, true);
+        }
+
+        // The creature's own sprite, laid on its side and greyed
+        let translation = Vec3::new(transform.translation.x, transform.translation.y, CORPSE_Z);
+        let mut corpse = commands.spawn((
+            SpriteSheetBundle {
+                texture_atlas: atlas.clone(),
+                sprite: TextureAtlasSprite {
+                    index: sprite.index,
+                    color: CORPSE_TINT,
+                    ..default()
+                },
+                transform: Transform::from_translation(translation).with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2)),
+                ..default()
+            },
+            Position::new(pos.x, pos.y),
+            Prop { blocking: false },
+        ));
+        if !items.is_empty() {
+            corpse.insert(Interactable::new(InteractionKind::Loot, format!("Search {}", name)));
+        }
+        corpse.insert(Corpse { name, items });
+    }
+}
+
+// System to take whatever a corpse E was used on still holds
+pub fn loot_corpses_system(
+    mut commands: Commands,
+    mut interactions: EventReader<InteractedWith>,
+    mut corpse_query: Query<&mut Corpse>,
+    mut player_query: Query<&mut Inventory, With<Player>>,
+    appearances: Res<ItemAppearances>,
+    mut game_turn: ResMut<GameTurn>,
+    mut message_log: ResMut<MessageLog>,
+    mut pickup_events: EventWriter<ItemPickedUp>,
+) {
+    let corpse_entity = if let Some(interaction) = interactions.read().find(|interaction| interaction.kind == InteractionKind::Loot) {
+        interaction.target
+    } else {
+        return;
+    };
+    let mut corpse = if let Ok(corpse) = corpse_query.get_mut(corpse_entity) { corpse } else { return; };
+    let mut inventory = if let Ok(inventory) = player_query.get_single_mut() { inventory } else { return; };
+
+    // Searching a body takes a turn
+    game_turn.increment();
+    commands.entity(corpse_entity).remove::<Interactable>();
+
+    if corpse.items.is_empty() {
+        message_log.add_message(format!("There is nothing worth taking on {}", corpse.name));
+        return;
+    }
+    let names: Vec<String> = corpse.items.iter().map(|&item| appearances.display_name(item)).collect();
+    message_log.add_message(format!("You search {} and find: {}", corpse.name, names.join(", ")));
+    for item in corpse.items.drain(..) {
+        inventory.add(item);
+        pickup_events.send(ItemPickedUp { item });
+    }
+}
@@ -0,0 +1,390 @@
+use bevy::prelude::*;
+use std::collections::{HashSet, VecDeque};
+
+use crate::biome::BiomeType;
+use crate::boss::{boss_music, Boss, BossMusic};
+use crate::camera::{CameraControl, CameraSettings, FocusTarget};
+use crate::codex::Codex;
+use crate::components::{GameTurn, Npc, Player, Position};
+use crate::dialogue::generate_biome_cryptic_dialogue;
+use crate::input::TILE_SIZE;
+use crate::loading_screen::LoadingScreen;
+use crate::map::TileMap;
+use crate::rng::GameRng;
+use crate::ui::MessageLog;
+use crate::visibility::{line_of_sight, Vision};
+use crate::GameState;
+
+// Share of the screen taken by each of the black bars at the top and bottom
+const LETTERBOX_PERCENT: f32 = 14.0;
+// How close in the run intro starts on the player before pulling back
+const INTRO_ZOOM: f32 = 0.3;
+// How close in the camera goes on a boss being introduced
+const BOSS_INTRO_ZOOM: f32 = 0.35;
+// How long a new region's splash is up, and how long of that it takes to fade in and again to fade out
+const BIOME_SPLASH_SECONDS: f32 = 3.0;
+const SPLASH_FADE_SECONDS: f32 = 0.6;
+
+/// One step of a cutscene, played in order
+pub enum CutsceneStep {
+    MoveCamera { to: (i32, i32), zoom: f32, seconds: f32 }, // Glide to a tile and zoom; 0 seconds cuts straight there
+    ShowText { text: String, seconds: f32 },                // Caption the letterbox, then hold it; it stays up until replaced
+    Spawn(Box<dyn FnOnce(&mut Commands) + Send + Sync>),    // Put something into the world; still happens if skipped
+    Splash { title: String, subtitle: String, seconds: f32 }, // A card over the whole screen, faded in and out
+    Wait { seconds: f32 },
+}
+
+/// The scripted sequence waiting to play or playing now. Queue steps with `play`; the game switches to
+/// `GameState::Cutscene` for them (no input, no turns) and back once they've run out or been skipped
+#[derive(Resource, Default)]
+pub struct Cutscene {
+    steps: VecDeque<CutsceneStep>,
+    step_time: f32,                   // Seconds into the current step
+    camera_from: Option<(Vec3, f32)>, // Where the current camera move started, and at what zoom
+    intro_pending: bool,              // A new run hasn't had its intro yet
+    discovered: HashSet<BiomeType>,   // Regions already announced this run
+}
+
+impl CutsceneStep {
+    pub fn spawn(spawn: impl FnOnce(&mut Commands) + Send + Sync + 'static) -> Self {
+        CutsceneStep::Spawn(Box::new(spawn))
+    }
+}
+
+impl Cutscene {
+    pub fn play(&mut self, steps: Vec<CutsceneStep>) {
+        self.steps.extend(steps);
+    }
+}
+
+/// Marks a boss that has already been introduced, so it only gets the one entrance
+#[derive(Component)]
+pub struct BossIntroduced;
+
+/// Marker for the letterbox and caption shown while a cutscene plays
+#[derive(Component)]
+pub struct CutsceneScreen;
+
+#[derive(Component)]
+pub struct CutsceneCaption;
+
+/// The full-screen card behind a splash step, and its text
+#[derive(Component)]
+pub struct CutsceneSplash;
+
+#[derive(Component)]
+pub struct CutsceneSplashText;
+
+// Centre of a tile in world space
+fn tile_center(tile: (i32, i32)) -> Vec2 {
+    Vec2::new(tile.0 as f32 * TILE_SIZE + TILE_SIZE / 2.0, tile.1 as f32 * TILE_SIZE + TILE_SIZE / 2.0)
+}
+
+// System to drop anything left over from the last run and line up the intro for this one
+pub fn reset_cutscene(mut cutscene: ResMut<Cutscene>) {
+    *cutscene = Cutscene { intro_pending: true, ..default() };
+}
+
+// System to play the run intro once the first floor and the player are in place:
+// a close look at where they've landed, the depth and biome, then pulling back to the usual view
+pub fn queue_intro_cutscene(
+    mut cutscene: ResMut<Cutscene>,
+    map: Res<TileMap>,
+    settings: Res<CameraSettings>,
+    player_query: Query<&Position, With<Player>>,
+) {
+    if !cutscene.intro_pending {
+        return;
+    }
+    let player = if let Ok(pos) = player_query.get_single() { (pos.x, pos.y) } else { return; };
+    cutscene.intro_pending = false;
+
+    cutscene.play(vec![
+        CutsceneStep::MoveCamera { to: player, zoom: INTRO_ZOOM, seconds: 0.0 },
+        CutsceneStep::ShowText { text: "You climb down into the Chasm.".to_string(), seconds: 2.0 },
+        CutsceneStep::ShowText { text: format!("Depth {}", map.current_level + 1), seconds: 1.5 },
+        CutsceneStep::ShowText { text: "Find the way down. Don't linger.".to_string(), seconds: 0.5 },
+        CutsceneStep::MoveCamera { to: player, zoom: settings.default_zoom, seconds: 1.5 },
+    ]);
+}
+
+// System to notice the player walking into a region: it goes in the codex (counted once per level),
+// and the first time this run it gets a splash naming it, with a line from the deep
+pub fn discover_biomes_system(
+    mut cutscene: ResMut<Cutscene>,
+    mut codex: ResMut<Codex>,
+    map: Res<TileMap>,
+    player_query: Query<&Position, With<Player>>,
+    game_turn: Res<GameTurn>,
+    mut game_rng: ResMut<GameRng>,
+    mut message_log: ResMut<MessageLog>,
+    mut seen: Local<(u64, u32, HashSet<BiomeType>)>, // Level seed, last turn, regions entered on it
+) {
+    let (seed, last_turn, seen_biomes) = &mut *seen;
+    if *seed != map.seed {
+        *seed = map.seed;
+        seen_biomes.clear();
+    } else if *last_turn == game_turn.current_turn {
+        return;
+    }
+    *last_turn = game_turn.current_turn;
+
+    let player = if let Ok(pos) = player_query.get_single() { *pos } else { return; };
+    if !map.in_bounds(player.x, player.y) {
+        return;
+    }
+    let biome = map.get_biome_at(player.x as usize, player.y as usize);
+    if !seen_biomes.insert(biome) {
+        return;
+    }
+    if codex.record_biome(biome, &mut game_rng.dialogue) {
+        message_log.add_message(format!("Codex: {} added", biome.get_name()));
+    }
+    if cutscene.discovered.insert(biome) {
+        cutscene.play(vec![CutsceneStep::Splash {
+            title: format!("The {} \u{2014} {}", biome.get_name(), biome.epithet()),
+            subtitle: generate_biome_cryptic_dialogue(&biome, &mut game_rng.dialogue),
+            seconds: BIOME_SPLASH_SECONDS,
+        }]);
+    }
+}
+
+// System to give a boss its entrance the first time the player lays eyes on it:
+// the camera goes over to it, its music starts, and it names itself before the fight
+pub fn queue_boss_intro(
+    mut commands: Commands,
+    mut cutscene: ResMut<Cutscene>,
+    asset_server: Res<AssetServer>,
+    map: Res<TileMap>,
+    boss_query: Query<(Entity, &Boss, &Npc, &Position), Without<BossIntroduced>>,
+    player_query: Query<(&Position, &Vision), With<Player>>,
+    music_query: Query<(), With<BossMusic>>,
+) {
+    let (player, vision) = if let Ok((pos, vision)) = player_query.get_single() { ((pos.x, pos.y), vision) } else { return; };
+    for (entity, boss, npc, position) in boss_query.iter() {
+        let boss_tile = (position.x, position.y);
+        let distance = (((boss_tile.0 - player.0).pow(2) + (boss_tile.1 - player.1).pow(2)) as f32).sqrt();
+        if distance > vision.radius || !line_of_sight(&map, player, boss_tile) {
+            continue;
+        }
+        commands.entity(entity).insert(BossIntroduced);
+
+        let mut steps = vec![CutsceneStep::MoveCamera { to: boss_tile, zoom: BOSS_INTRO_ZOOM, seconds: 1.2 }];
+        if let Some(music) = boss_music(&asset_server).filter(|_| music_query.is_empty()) {
+            steps.push(CutsceneStep::spawn(move |commands| {
+                commands.spawn(music);
+            }));
+        }
+        steps.push(CutsceneStep::ShowText { text: boss.kind.get_name().to_string(), seconds: 1.5 });
+        if let Some(taunt) = npc.dialog.first() {
+            steps.push(CutsceneStep::ShowText { text: format!("\"{}\"", taunt), seconds: 2.5 });
+        }
+        steps.push(CutsceneStep::MoveCamera { to: player, zoom: BOSS_INTRO_ZOOM, seconds: 0.8 });
+        cutscene.play(steps);
+    }
+}
+
+// System to switch over to the cutscene once one is queued, unless the game is already on its way
+// somewhere else (down the stairs, the run ending) or the camera is busy with a conversation
+pub fn start_cutscene(
+    cutscene: Res<Cutscene>,
+    camera_query: Query<&CameraControl>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if cutscene.steps.is_empty() || next_state.0.is_some() {
+        return;
+    }
+    if camera_query.get_single().map_or(false, |control| control.is_focused()) {
+        return;
+    }
+    next_state.set(GameState::Cutscene);
+}
+
+// Put up the letterbox and take the camera off the player
+pub fn setup_cutscene_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut camera_query: Query<(&mut CameraControl, &mut Transform)>,
+) {
+    if let Ok((mut control, mut transform)) = camera_query.get_single_mut() {
+        let (position, zoom) = (transform.translation, control.current_zoom);
+        control.push_focus(FocusTarget::Cutscene, &mut transform, position, zoom);
+    }
+
+    let font = asset_server.load("fonts/FiraSans-Light.ttf");
+    let bar = || NodeBundle {
+        style: Style {
+            width: Val::Percent(100.0),
+            height: Val::Percent(LETTERBOX_PERCENT),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        background_color: BackgroundColor(Color::BLACK),
+        ..default()
+    };
+
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::SpaceBetween,
+                ..default()
+            },
+            z_index: ZIndex::Global(150),
+            ..default()
+        },
+        CutsceneScreen,
+    )).with_children(|parent| {
+        parent.spawn(bar());
+        parent.spawn(bar()).with_children(|bottom| {
+            bottom.spawn((
+                TextBundle::from_section("", TextStyle { font: font.clone(), font_size: 26.0, color: Color::WHITE }),
+                CutsceneCaption,
+            ));
+            bottom.spawn(TextBundle::from_section(
+                "Space to skip",
+                TextStyle { font: font.clone(), font_size: 14.0, color: Color::rgb(0.5, 0.5, 0.55) },
+            ));
+        });
+    });
+
+    // Hidden until a splash step fades it in
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: BackgroundColor(Color::NONE),
+            z_index: ZIndex::Global(160),
+            ..default()
+        },
+        CutsceneScreen,
+        CutsceneSplash,
+    )).with_children(|parent| {
+        parent.spawn((
+            TextBundle::from_sections([
+                TextSection::new("", TextStyle { font: font.clone(), font_size: 44.0, color: Color::GOLD.with_a(0.0) }),
+                TextSection::new("", TextStyle { font: font.clone(), font_size: 22.0, color: Color::rgba(0.6, 0.6, 0.7, 0.0) }),
+            ]).with_text_alignment(TextAlignment::Center),
+            CutsceneSplashText,
+        ));
+    });
+}
+
+// How far faded in a splash is, `elapsed` seconds into showing it for `seconds`
+fn splash_alpha(elapsed: f32, seconds: f32) -> f32 {
+    (elapsed / SPLASH_FADE_SECONDS).min((seconds - elapsed) / SPLASH_FADE_SECONDS).clamp(0.0, 1.0)
+}
+
+// System to run the steps in order, and go back to the game when they're done. Space, Enter or a click skips
+// to the end, though anything the rest of the cutscene would have spawned still turns up
+pub fn play_cutscene(
+    mut commands: Commands,
+    time: Res<Time>,
+    keyboard: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    mut cutscene: ResMut<Cutscene>,
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection, &mut CameraControl)>,
+    mut caption_query: Query<&mut Text, (With<CutsceneCaption>, Without<CutsceneSplashText>)>,
+    mut splash_query: Query<&mut BackgroundColor, With<CutsceneSplash>>,
+    mut splash_text_query: Query<&mut Text, (With<CutsceneSplashText>, Without<CutsceneCaption>)>,
+    mut loading_screen: ResMut<LoadingScreen>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let cutscene = &mut *cutscene;
+    if keyboard.any_just_pressed([KeyCode::Space, KeyCode::Return]) || mouse.just_pressed(MouseButton::Left) {
+        for step in cutscene.steps.drain(..) {
+            if let CutsceneStep::Spawn(spawn) = step {
+                spawn(&mut commands);
+            }
+        }
+    }
+    cutscene.step_time += time.delta_seconds();
+
+    let mut splash = 0.0;
+    while let Some(step) = cutscene.steps.front() {
+        let finished = match step {
+            CutsceneStep::MoveCamera { to, zoom, seconds } => {
+                if let Ok((mut transform, mut projection, mut control)) = camera_query.get_single_mut() {
+                    let (from, from_zoom) = *cutscene.camera_from.get_or_insert((transform.translation, projection.scale));
+                    let t = if *seconds > 0.0 { (cutscene.step_time / seconds).min(1.0) } else { 1.0 };
+                    let eased = t * t * (3.0 - 2.0 * t);
+                    transform.translation = from.lerp(tile_center(*to).extend(from.z), eased);
+                    projection.scale = from_zoom + (zoom - from_zoom) * eased;
+                    control.current_zoom = projection.scale;
+                    t >= 1.0
+                } else {
+                    true
+                }
+            }
+            CutsceneStep::ShowText { text, seconds } => {
+                for mut caption in caption_query.iter_mut() {
+                    if caption.sections[0].value != *text {
+                        caption.sections[0].value = text.clone();
+                    }
+                }
+                cutscene.step_time >= *seconds
+            }
+            CutsceneStep::Spawn(_) => true,
+            CutsceneStep::Splash { title, subtitle, seconds } => {
+                for mut text in splash_text_query.iter_mut() {
+                    if text.sections[0].value != *title {
+                        text.sections[0].value = title.clone();
+                        text.sections[1].value = format!("\n\n{}", subtitle);
+                    }
+                }
+                splash = splash_alpha(cutscene.step_time, *seconds);
+                cutscene.step_time >= *seconds
+            }
+            CutsceneStep::Wait { seconds } => cutscene.step_time >= *seconds,
+        };
+        if !finished {
+            break;
+        }
+        if let Some(CutsceneStep::Spawn(spawn)) = cutscene.steps.pop_front() {
+            spawn(&mut commands);
+        }
+        cutscene.step_time = 0.0;
+        cutscene.camera_from = None;
+        splash = 0.0;
+    }
+
+    for mut background in splash_query.iter_mut() {
+        background.0 = Color::rgba(0.02, 0.02, 0.03, splash);
+    }
+    for mut text in splash_text_query.iter_mut() {
+        for section in text.sections.iter_mut() {
+            section.style.color.set_a(splash);
+        }
+    }
+    if !cutscene.steps.is_empty() {
+        return;
+    }
+
+    // The run picks up where it was, not from the start
+    loading_screen.resume_run();
+    next_state.set(GameState::InGame);
+}
+
+// Take the letterbox down and hand the camera back to the player
+pub fn finish_cutscene(
+    mut commands: Commands,
+    screen_query: Query<Entity, With<CutsceneScreen>>,
+    mut camera_query: Query<(&mut CameraControl, &mut Transform)>,
+) {
+    for entity in screen_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    if let Ok((mut control, mut transform)) = camera_query.get_single_mut() {
+        control.pop_focus(FocusTarget::Cutscene, &mut transform);
+    }
+}
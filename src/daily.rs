@@ -0,0 +1,112 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::level::DungeonState;
+use crate::map::TileMap;
+use crate::rng::GameRng;
+use crate::run_summary::RunRecord;
+
+/// File daily runs are recorded in, kept apart from the regular history so their scores compare like for like
+pub const DAILY_HISTORY_PATH: &str = "daily_runs.json";
+// Mixed into the day number so the daily seed isn't just a small integer
+const DAILY_SALT: u64 = 0x6461_696c_7963_6873;
+
+/// The Daily Chasm: everyone playing on the same (UTC) day gets the same dungeon.
+/// Chosen from the main menu or with `--daily`
+#[derive(Resource, Debug, Clone)]
+pub struct DailyChallenge {
+    pub active: bool,
+    pub date: String, // YYYY-MM-DD
+    pub seed: u64,
+}
+
+impl Default for DailyChallenge {
+    fn default() -> Self {
+        let days = days_since_epoch();
+        Self { active: false, date: format_date(days), seed: daily_seed(days) }
+    }
+}
+
+impl DailyChallenge {
+    pub fn from_args() -> Self {
+        Self { active: std::env::args().any(|arg| arg == "--daily"), ..default() }
+    }
+}
+
+/// One finished daily run and the day it was played
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyRecord {
+    pub date: String,
+    pub record: RunRecord,
+}
+
+fn days_since_epoch() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() / 86_400
+}
+
+// A well-mixed seed from the day number (splitmix64)
+fn daily_seed(days: u64) -> u64 {
+    let mut z = (days ^ DAILY_SALT).wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+// The calendar date of a day number, from Howard Hinnant's civil_from_days
+fn format_date(days: u64) -> String {
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// Read every recorded daily run, treating a missing or unreadable file as empty
+pub fn load_daily_history() -> Vec<DailyRecord> {
+    match fs::read_to_string(DAILY_HISTORY_PATH) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+            eprintln!("Ignoring unreadable {}: {}", DAILY_HISTORY_PATH, e);
+            Vec::new()
+        }),
+        Err(_) => Vec::new(),
+    }
+}
+
+pub fn save_daily_history(history: &[DailyRecord]) -> Result<(), String> {
+    let contents = serde_json::to_string_pretty(history).map_err(|e| e.to_string())?;
+    fs::write(DAILY_HISTORY_PATH, contents).map_err(|e| format!("could not write {}: {}", DAILY_HISTORY_PATH, e))
+}
+
+// The runs played on one day, oldest first
+pub fn runs_on(history: &[DailyRecord], date: &str) -> Vec<RunRecord> {
+    history.iter().filter(|daily| daily.date == date).map(|daily| daily.record.clone()).collect()
+}
+
+// Run condition for setting up a daily run
+pub fn daily_active(daily: Res<DailyChallenge>) -> bool {
+    daily.active
+}
+
+// System to swap the first level for the day's on the way out of the main menu, before the run is built
+pub fn start_daily_run(
+    daily: Res<DailyChallenge>,
+    mut map: ResMut<TileMap>,
+    mut dungeon_state: ResMut<DungeonState>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    if map.seed == daily.seed && map.current_level == 0 {
+        return;
+    }
+    let first = TileMap::generate_level(0, daily.seed);
+    *map = first.clone();
+    *dungeon_state = DungeonState { levels: vec![first], ..default() };
+    *game_rng = GameRng::new(daily.seed);
+    println!("Daily Chasm for {} (seed {})", daily.date, daily.seed);
+}
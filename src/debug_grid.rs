@@ -0,0 +1,150 @@
+use bevy::prelude::*;
+use bevy::text::{Text2dBundle, TextAlignment};
+
+use crate::biome::TileWalkability;
+use crate::components::Tile;
+use crate::input::TILE_SIZE;
+use crate::map::{GridLine, TilePos};
+
+// Overlays sit over tiles and props but under creatures; labels go on top of them
+const OVERLAY_Z: f32 = 2.8;
+const LABEL_Z: f32 = 2.9;
+const OVERLAY_ALPHA: f32 = 0.35;
+const LABEL_FONT_SIZE: f32 = 9.0;
+
+/// What each tile is tinted by in the debug overlay
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TileOverlay {
+    #[default]
+    Off,
+    Walkability,
+    Biome,
+}
+
+impl TileOverlay {
+    fn next(self) -> Self {
+        match self {
+            TileOverlay::Off => TileOverlay::Walkability,
+            TileOverlay::Walkability => TileOverlay::Biome,
+            TileOverlay::Biome => TileOverlay::Off,
+        }
+    }
+
+    // The tint for a tile, if this overlay shows one
+    fn color(self, tile: &Tile) -> Option<Color> {
+        let color = match self {
+            TileOverlay::Off => return None,
+            TileOverlay::Walkability => match tile.walkability {
+                TileWalkability::Walkable => Color::GREEN,
+                TileWalkability::Blocked => Color::RED,
+                TileWalkability::Door => Color::YELLOW,
+            },
+            TileOverlay::Biome => tile.biome.color(),
+        };
+        Some(color.with_a(OVERLAY_ALPHA))
+    }
+}
+
+/// Map debugging layers: G cycles grid lines and tile coordinates, Shift+G the tile overlays
+#[derive(Resource, Debug, Default)]
+pub struct DebugGrid {
+    pub lines: bool,
+    pub labels: bool,
+    pub overlay: TileOverlay,
+}
+
+/// Marker for the coordinate labels and overlay tints, rebuilt whenever they change
+#[derive(Component)]
+pub struct DebugGridLayer;
+
+// System to cycle the debug layers and show or hide the grid lines
+pub fn toggle_grid_visibility(
+    keyboard: Res<Input<KeyCode>>,
+    mut debug_grid: ResMut<DebugGrid>,
+    mut grid_query: Query<&mut Visibility, With<GridLine>>,
+    new_lines: Query<(), Added<GridLine>>,
+) {
+    if keyboard.just_pressed(KeyCode::G) {
+        if keyboard.pressed(KeyCode::ShiftLeft) {
+            debug_grid.overlay = debug_grid.overlay.next();
+            println!("Tile overlay: {:?}", debug_grid.overlay);
+        } else {
+            // Off, then lines, then lines with coordinates
+            let (lines, labels) = match (debug_grid.lines, debug_grid.labels) {
+                (false, _) => (true, false),
+                (true, false) => (true, true),
+                (true, true) => (false, false),
+            };
+            debug_grid.lines = lines;
+            debug_grid.labels = labels;
+            println!("Grid lines: {}, coordinates: {}", debug_grid.lines, debug_grid.labels);
+        }
+    }
+
+    // Grid lines are spawned hidden, so newly laid ones need telling too
+    if !debug_grid.is_changed() && new_lines.is_empty() {
+        return;
+    }
+    let visibility = if debug_grid.lines { Visibility::Visible } else { Visibility::Hidden };
+    for mut line_visibility in grid_query.iter_mut() {
+        *line_visibility = visibility;
+    }
+}
+
+// System to lay out coordinate labels and overlay tints when they're toggled or the tiles change
+pub fn sync_debug_grid_layers(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    debug_grid: Res<DebugGrid>,
+    tile_query: Query<(&TilePos, &Tile)>,
+    changed_tiles: Query<(), Changed<Tile>>,
+    layer_query: Query<Entity, With<DebugGridLayer>>,
+) {
+    let tiles_changed = !changed_tiles.is_empty() && (debug_grid.labels || debug_grid.overlay != TileOverlay::Off);
+    if !debug_grid.is_changed() && !tiles_changed {
+        return;
+    }
+
+    for entity in layer_query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let font = asset_server.load("fonts/FiraSans-Light.ttf");
+    for (pos, tile) in tile_query.iter() {
+        let center = (pos.x as f32 * TILE_SIZE + TILE_SIZE / 2.0, pos.y as f32 * TILE_SIZE + TILE_SIZE / 2.0);
+
+        if let Some(color) = debug_grid.overlay.color(tile) {
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color,
+                        custom_size: Some(Vec2::splat(TILE_SIZE)),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(center.0, center.1, OVERLAY_Z),
+                    ..default()
+                },
+                DebugGridLayer,
+            ));
+        }
+
+        if debug_grid.labels {
+            commands.spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        format!("{},{}", pos.x, pos.y),
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: LABEL_FONT_SIZE,
+                            color: Color::WHITE,
+                        },
+                    )
+                    .with_alignment(TextAlignment::Center),
+                    transform: Transform::from_xyz(center.0, center.1, LABEL_Z),
+                    ..default()
+                },
+                DebugGridLayer,
+            ));
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+use crate::components::{Animal, GameTurn, Npc, Player, Position, Tile};
+use crate::events::{AnimalTamed, EntityDamaged, ItemPickedUp, LevelChanged, SecretDoorFound};
+use crate::gold::GoldCollected;
+use crate::input::cursor_tile;
+use crate::map::{TileIndex, TileMap};
+use crate::props::Prop;
+use crate::rng::GameRng;
+
+// How many of the latest gameplay events the overlay lists
+const RECENT_EVENTS: usize = 6;
+// Seconds of spawns and despawns averaged into the churn figures
+const CHURN_WINDOW: f32 = 1.0;
+
+/// The F3 debug overlay. Events are recorded even while it's closed, so opening it shows what just happened
+#[derive(Resource, Debug, Default)]
+pub struct DebugOverlay {
+    pub open: bool,
+    recent: VecDeque<String>,
+    to_inspect: Vec<Entity>, // Clicked this frame, for the exclusive system to print
+    churn: EntityChurn,
+}
+
+/// Entities spawned and despawned per second, to spot systems that rebuild what they could reuse
+#[derive(Debug, Default)]
+struct EntityChurn {
+    spawned: usize,
+    despawned: usize,
+    elapsed: f32,
+    spawned_per_second: f32,
+    despawned_per_second: f32,
+}
+
+impl DebugOverlay {
+    fn record(&mut self, turn: u32, line: String) {
+        if self.recent.len() == RECENT_EVENTS {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(format!("[{}] {}", turn, line));
+    }
+}
+
+#[derive(Component)]
+pub struct DebugOverlayText;
+
+// System to keep a short history of gameplay events for the overlay
+pub fn record_debug_events(
+    mut overlay: ResMut<DebugOverlay>,
+    game_turn: Res<GameTurn>,
+    mut level_events: EventReader<LevelChanged>,
+    mut damage_events: EventReader<EntityDamaged>,
+    mut item_events: EventReader<ItemPickedUp>,
+    mut gold_events: EventReader<GoldCollected>,
+    mut tamed_events: EventReader<AnimalTamed>,
+    mut door_events: EventReader<SecretDoorFound>,
+) {
+    let turn = game_turn.current_turn;
+    for event in level_events.read() {
+        overlay.record(turn, format!("LevelChanged {} -> {}", event.from, event.to));
+    }
+    for event in damage_events.read() {
+        overlay.record(turn, format!("EntityDamaged {:?} -{} by {}", event.target, event.amount, event.source));
+    }
+    for event in item_events.read() {
+        overlay.record(turn, format!("ItemPickedUp {}", event.item.get_name()));
+    }
+    for event in gold_events.read() {
+        overlay.record(turn, format!("GoldCollected {}", event.amount));
+    }
+    for event in tamed_events.read() {
+        overlay.record(turn, format!("AnimalTamed {:?}", event.entity));
+    }
+    for event in door_events.read() {
+        overlay.record(turn, format!("SecretDoorFound ({}, {})", event.x, event.y));
+    }
+}
+
+// System to count entities coming and going while the overlay is open. Everything drawn has a
+// Transform, so its arrival and removal stand in for spawns and despawns
+pub fn record_entity_churn(
+    time: Res<Time>,
+    mut overlay: ResMut<DebugOverlay>,
+    added: Query<(), Added<Transform>>,
+    mut removed: RemovedComponents<Transform>,
+) {
+    let despawned = removed.read().count();
+    if !overlay.open {
+        return;
+    }
+    let churn = &mut overlay.churn;
+    churn.spawned += added.iter().count();
+    churn.despawned += despawned;
+    churn.elapsed += time.delta_seconds();
+    if churn.elapsed >= CHURN_WINDOW {
+        churn.spawned_per_second = churn.spawned as f32 / churn.elapsed;
+        churn.despawned_per_second = churn.despawned as f32 / churn.elapsed;
+        churn.spawned = 0;
+        churn.despawned = 0;
+        churn.elapsed = 0.0;
+    }
+}
+
+// System to open and close the overlay with F3
+pub fn toggle_debug_overlay(
+    mut commands: Commands,
+    keyboard: Res<Input<KeyCode>>,
+    asset_server: Res<AssetServer>,
+    mut overlay: ResMut<DebugOverlay>,
+    text_query: Query<Entity, With<DebugOverlayText>>,
+) {
+    if !keyboard.just_pressed(KeyCode::F3) {
+        return;
+    }
+    overlay.open = !overlay.open;
+    for entity in text_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    if !overlay.open {
+        return;
+    }
+
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/FiraSans-Light.ttf"),
+                font_size: 14.0,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            right: Val::Px(10.0),
+            padding: UiRect::all(Val::Px(6.0)),
+            ..default()
+        })
+        .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.7)),
+        DebugOverlayText,
+    ));
+}
+
+// System to refresh the overlay's numbers while it's open
+pub fn update_debug_overlay(
+    overlay: Res<DebugOverlay>,
+    diagnostics: Res<DiagnosticsStore>,
+    map: Res<TileMap>,
+    game_rng: Res<GameRng>,
+    player_query: Query<&Position, With<Player>>,
+    entities: Query<Entity>,
+    tiles: Query<(), With<Tile>>,
+    npcs: Query<(), (With<Npc>, Without<Animal>)>,
+    animals: Query<(), With<Animal>>,
+    props: Query<(), With<Prop>>,
+    mut text_query: Query<&mut Text, With<DebugOverlayText>>,
+) {
+    if !overlay.open {
+        return;
+    }
+    let fps = diagnostics
+        .get(FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed())
+        .unwrap_or(0.0);
+    let player = player_query.get_single().map_or("none".to_string(), |pos| format!("({}, {})", pos.x, pos.y));
+
+    let mut lines = vec![
+        format!("FPS {:.0}", fps),
+        format!("Entities {}", entities.iter().count()),
+        format!("  tiles {}  npcs {}  animals {}  props {}", tiles.iter().count(), npcs.iter().count(), animals.iter().count(), props.iter().count()),
+        format!("  spawned {:.0}/s  despawned {:.0}/s", overlay.churn.spawned_per_second, overlay.churn.despawned_per_second),
+        format!("Run seed {}  level seed {}", game_rng.seed(), map.seed),
+        format!("Level {}  player {}", map.current_level, player),
+        "Recent events:".to_string(),
+    ];
+    lines.extend(overlay.recent.iter().cloned());
+    lines.push("Click an entity to print its components".to_string());
+
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = lines.join("\n");
+    }
+}
+
+// System to pick out whatever is on the clicked tile while the overlay is open
+pub fn debug_click_system(
+    mouse_input: Res<Input<MouseButton>>,
+    windows: Query<&Window>,
+    camera_q: Query<(&Camera, &GlobalTransform)>,
+    tile_index: Res<TileIndex>,
+    position_query: Query<(Entity, &Position)>,
+    mut overlay: ResMut<DebugOverlay>,
+) {
+    if !overlay.open || !mouse_input.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let (window, (camera, camera_transform)) = if let (Ok(window), Ok(camera)) = (windows.get_single(), camera_q.get_single()) {
+        (window, camera)
+    } else {
+        return;
+    };
+    let (x, y) = if let Some(tile) = cursor_tile(window, camera, camera_transform) { tile } else { return; };
+
+    let mut clicked: Vec<Entity> = position_query.iter()
+        .filter(|(_, pos)| (pos.x, pos.y) == (x, y))
+        .map(|(entity, _)| entity)
+        .collect();
+    clicked.extend(tile_index.get(x, y));
+    overlay.to_inspect = clicked;
+}
+
+// Exclusive system to print the components of clicked entities to the console
+pub fn print_inspected_components(world: &mut World) {
+    let to_inspect = std::mem::take(&mut world.resource_mut::<DebugOverlay>().to_inspect);
+    for entity in to_inspect {
+        if world.get_entity(entity).is_none() {
+            continue;
+        }
+        let names: Vec<&str> = world.inspect_entity(entity).iter().map(|info| info.name()).collect();
+        println!("{:?}: {}", entity, names.join(", "));
+    }
+}
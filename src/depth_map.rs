@@ -0,0 +1,143 @@
+use bevy::prelude::*;
+
+use crate::bookmarks::Bookmark;
+use crate::level::DungeonState;
+use crate::map::{RoomPurpose, TileMap};
+use crate::npc_registry::{NpcRegistry, RosterEntry};
+use crate::tutorial::TutorialTip;
+
+// Size of the biome swatch beside each depth
+const SWATCH_SIZE: f32 = 14.0;
+// Depths listed before the chain starts a new column
+const DEPTHS_PER_COLUMN: usize = 12;
+
+/// The depth map, while it's open
+#[derive(Component)]
+pub struct DepthMapOverlay;
+
+// What's worth remembering about a level the player has been to, their own bookmarks last
+fn level_notes(level_index: usize, map: &TileMap, roster: &[RosterEntry], bookmarks: &[Bookmark]) -> Vec<String> {
+    let mut notes = Vec::new();
+    if map.is_boss_level {
+        notes.push(if map.down_stairs_locked { "Guardian waits" } else { "Guardian defeated" }.to_string());
+    }
+    if map.rooms.iter().any(|room| room.purpose == Some(RoomPurpose::Shop)) {
+        notes.push("Shop".to_string());
+    }
+    for entry in roster.iter().filter(|entry| entry.level == Some(level_index)) {
+        notes.push(format!("{} waits here", entry.name));
+    }
+    notes.extend(bookmarks.iter().map(|bookmark| format!("\"{}\"", bookmark.name)));
+    notes
+}
+
+// One row of the chain: the biome swatch, the depth and region, and the notes for it
+fn spawn_depth_row(parent: &mut ChildBuilder, font: &Handle<Font>, level_index: usize, map: &TileMap, notes: Vec<String>, current: bool) {
+    let (x, y) = map.up_stairs_pos.unwrap_or(map.spawn_position);
+    let biome = map.get_biome_at(x, y);
+    let color = if current { Color::GOLD } else { Color::WHITE };
+
+    parent.spawn(NodeBundle {
+        style: Style { align_items: AlignItems::Center, column_gap: Val::Px(8.0), ..default() },
+        ..default()
+    }).with_children(|row| {
+        row.spawn(NodeBundle {
+            style: Style { width: Val::Px(SWATCH_SIZE), height: Val::Px(SWATCH_SIZE), ..default() },
+            background_color: BackgroundColor(biome.color()),
+            ..default()
+        });
+        let mut sections = vec![TextSection::new(
+            format!("Depth {:<3} {}", level_index + 1, biome.get_name()),
+            TextStyle { font: font.clone(), font_size: 18.0, color },
+        )];
+        if current {
+            sections.push(TextSection::new("  < you are here", TextStyle { font: font.clone(), font_size: 16.0, color: Color::GOLD }));
+        }
+        if !notes.is_empty() {
+            sections.push(TextSection::new(
+                format!("  ({})", notes.join(", ")),
+                TextStyle { font: font.clone(), font_size: 16.0, color: Color::rgb(0.6, 0.6, 0.7) },
+            ));
+        }
+        row.spawn(TextBundle::from_sections(sections));
+    });
+}
+
+// System to open and close the depth map with Tab: every level visited this run as a chain running down,
+// with its region, what's been found there and where the player is now. Tab dismisses a tutorial tip first
+pub fn toggle_depth_map(
+    mut commands: Commands,
+    keyboard: Res<Input<KeyCode>>,
+    asset_server: Res<AssetServer>,
+    dungeon_state: Res<DungeonState>,
+    registry: Option<Res<NpcRegistry>>,
+    overlay_query: Query<Entity, With<DepthMapOverlay>>,
+    tip_query: Query<(), With<TutorialTip>>,
+) {
+    let toggled = keyboard.just_pressed(KeyCode::Tab) && tip_query.is_empty();
+    let closed = !overlay_query.is_empty() && keyboard.just_pressed(KeyCode::Back);
+    if !toggled && !closed {
+        return;
+    }
+    if !overlay_query.is_empty() {
+        for entity in overlay_query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+
+    let roster = registry.as_deref().map_or(&[][..], |registry| registry.roster.as_slice());
+    let font = asset_server.load("fonts/FiraSans-Light.ttf");
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(8.0),
+                left: Val::Percent(8.0),
+                width: Val::Percent(84.0),
+                padding: UiRect::all(Val::Px(16.0)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(12.0),
+                ..default()
+            },
+            background_color: BackgroundColor(Color::rgba(0.0, 0.0, 0.0, 0.88)),
+            z_index: ZIndex::Global(200),
+            ..default()
+        },
+        DepthMapOverlay,
+    ))
+    .with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            "The Chasm",
+            TextStyle { font: font.clone(), font_size: 24.0, color: Color::GOLD },
+        ));
+        parent.spawn(NodeBundle {
+            style: Style { column_gap: Val::Px(40.0), ..default() },
+            ..default()
+        }).with_children(|columns| {
+            let levels: Vec<(usize, &TileMap)> = dungeon_state.levels.iter().enumerate().collect();
+            for column in levels.chunks(DEPTHS_PER_COLUMN) {
+                columns.spawn(NodeBundle {
+                    style: Style { flex_direction: FlexDirection::Column, row_gap: Val::Px(2.0), ..default() },
+                    ..default()
+                }).with_children(|chain| {
+                    for (i, &(level_index, map)) in column.iter().enumerate() {
+                        if i > 0 {
+                            chain.spawn(TextBundle::from_section(
+                                "  |",
+                                TextStyle { font: font.clone(), font_size: 14.0, color: Color::rgb(0.4, 0.4, 0.45) },
+                            ));
+                        }
+                        let bookmarks = dungeon_state.bookmarks.get(&level_index).map_or(&[][..], |bookmarks| bookmarks.as_slice());
+                        let notes = level_notes(level_index, map, roster, bookmarks);
+                        spawn_depth_row(chain, &font, level_index, map, notes, level_index == dungeon_state.current_level_index);
+                    }
+                });
+            }
+        });
+        parent.spawn(TextBundle::from_section(
+            "Tab - Close",
+            TextStyle { font: font.clone(), font_size: 14.0, color: Color::GRAY },
+        ));
+    });
+}
@@ -0,0 +1,209 @@
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use bevy::text::{Text2dBundle, TextAlignment};
+
+use crate::components::{DialogBox, Npc};
+use crate::conversation::Conversation;
+use crate::display_settings::DisplaySettings;
+use crate::input::TILE_SIZE;
+
+// Layout of the speech box, in world units
+const FONT_SIZE: f32 = 10.0;
+const CHAR_WIDTH: f32 = 5.5;   // Approximate width per character at FONT_SIZE
+const LINE_HEIGHT: f32 = 12.0;
+const PADDING: f32 = 6.0;
+const MAX_LINE_CHARS: usize = 32;
+const MIN_WIDTH: f32 = 3.0 * TILE_SIZE;
+const ANCHOR_OFFSET: f32 = TILE_SIZE / 2.0 + 4.0; // Gap between the NPC's center and the box's bottom edge
+
+// Typewriter speed, in characters per second
+const REVEAL_SPEED: f32 = 40.0;
+
+/// Marker for the text inside a dialog box
+#[derive(Component)]
+pub struct DialogText;
+
+// Break text into lines of at most max_chars, splitting on spaces where possible
+pub fn wrap_text(text: &str, max_chars: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+
+    for word in text.split_whitespace() {
+        let mut word = word.to_string();
+
+        // Words longer than a whole line get hard-broken
+        while word.chars().count() > max_chars {
+            if !current.is_empty() {
+                lines.push(std::mem::take(&mut current));
+            }
+            let split_at = word.char_indices().nth(max_chars).map_or(word.len(), |(index, _)| index);
+            let rest = word.split_off(split_at);
+            lines.push(word);
+            word = rest;
+        }
+
+        let needed = if current.is_empty() { word.chars().count() } else { current.chars().count() + 1 + word.chars().count() };
+        if needed > max_chars && !current.is_empty() {
+            lines.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(&word);
+    }
+
+    if !current.is_empty() || lines.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+// Size of a box that fits the given wrapped lines
+fn box_size(lines: &[String]) -> Vec2 {
+    let longest = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+    let width = (longest as f32 * CHAR_WIDTH + PADDING * 2.0).max(MIN_WIDTH);
+    let height = lines.len() as f32 * LINE_HEIGHT + PADDING * 2.0;
+    Vec2::new(width, height)
+}
+
+// System to show a word-wrapped speech box above each speaking NPC, typing its text out.
+// Boxes are pooled: when an NPC stops talking its box is hidden and handed to the next speaker
+pub fn render_dialog_boxes(
+    mut commands: Commands,
+    time: Res<Time>,
+    keyboard: Res<Input<KeyCode>>,
+    npc_query: Query<(Entity, &Transform, &Npc), Without<DialogBox>>,
+    mut dialog_query: Query<(Entity, &mut DialogBox, &mut Sprite, &mut Transform, &mut Visibility, &Children)>,
+    mut text_query: Query<(&mut Text, &mut Transform), (With<DialogText>, Without<DialogBox>, Without<Npc>)>,
+    mut conversation: ResMut<Conversation>,
+    display: Res<DisplaySettings>,
+    asset_server: Res<AssetServer>,
+) {
+    // With screen-space text on, the boxes are kept up to date but the UI copies are what's drawn
+    let shown = if display.screen_space_text { Visibility::Hidden } else { Visibility::Inherited };
+
+    // E, Space or Enter finishes the line being typed
+    let skip = keyboard.just_pressed(KeyCode::E) || keyboard.just_pressed(KeyCode::Space) || keyboard.just_pressed(KeyCode::Return);
+
+    let mut has_box = Vec::new();
+    let mut free_boxes = Vec::new();
+    for (box_entity, mut dialog, mut sprite, mut transform, mut visibility, children) in dialog_query.iter_mut() {
+        let speaker = dialog.speaker.and_then(|speaker| npc_query.get(speaker).ok());
+        let (npc_entity, npc_transform, npc) = if let Some(speaker) = speaker.filter(|(_, _, npc)| npc.speaking) {
+            speaker
+        } else {
+            // The NPC stopped talking or is gone; keep the box for whoever speaks next
+            if dialog.speaker.is_some() {
+                dialog.speaker = None;
+                dialog.visible = false;
+            }
+            if *visibility != Visibility::Hidden {
+                *visibility = Visibility::Hidden;
+            }
+            free_boxes.push(box_entity);
+            continue;
+        };
+        has_box.push(npc_entity);
+        if *visibility != shown {
+            *visibility = shown;
+        }
+
+        // A new line starts typing from the beginning; the key that asked for it doesn't skip it
+        let new_line = dialog.text != npc.dialog_text;
+        if new_line {
+            dialog.text = npc.dialog_text.clone();
+            dialog.revealed = 0.0;
+        }
+
+        // Wrap the full line up front so words don't jump between lines as they appear
+        let lines = wrap_text(&dialog.text, MAX_LINE_CHARS);
+        let size = box_size(&lines);
+        let wrapped = lines.join("\n");
+        let total_chars = wrapped.chars().count() as f32;
+        dialog.revealed = if skip && !new_line { total_chars } else { (dialog.revealed + REVEAL_SPEED * time.delta_seconds()).min(total_chars) };
+
+        // Let the conversation know when it can move on
+        let finished = dialog.revealed >= total_chars;
+        if conversation.speaker == Some(npc_entity) && conversation.line_finished != finished {
+            conversation.line_finished = finished;
+        }
+
+        if sprite.custom_size != Some(size) {
+            sprite.custom_size = Some(size);
+        }
+
+        // Follow the NPC, ignoring its speaking wiggle
+        transform.translation = npc_transform.translation + Vec3::new(0.0, ANCHOR_OFFSET, 5.0);
+
+        // Only re-lay out the text when another character has appeared
+        let typed: String = wrapped.chars().take(dialog.revealed as usize).collect();
+        for &child in children.iter() {
+            if let Ok((mut text, mut text_transform)) = text_query.get_mut(child) {
+                if text.sections[0].value != typed {
+                    text.sections[0].value = typed.clone();
+                }
+                // Text starts at the box's top-left corner and grows down and right
+                let corner = Vec3::new(-size.x / 2.0 + PADDING, size.y - PADDING, 5.0);
+                if text_transform.translation != corner {
+                    text_transform.translation = corner;
+                }
+            }
+        }
+    }
+
+    // Give every NPC that just started speaking a box, from the pool if there's one spare
+    for (npc_entity, npc_transform, npc) in npc_query.iter() {
+        if !npc.speaking || has_box.contains(&npc_entity) {
+            continue;
+        }
+
+        if let Some(box_entity) = free_boxes.pop() {
+            // Stays hidden until the loop above lays it out for its new speaker next frame
+            if let Ok((_, mut dialog, ..)) = dialog_query.get_mut(box_entity) {
+                *dialog = DialogBox { visible: true, speaker: Some(npc_entity), ..default() };
+            }
+            continue;
+        }
+
+        let size = box_size(&wrap_text(&npc.dialog_text, MAX_LINE_CHARS));
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgba(0.2, 0.2, 0.2, 0.85), // Dark gray with transparency
+                    custom_size: Some(size),
+                    anchor: Anchor::BottomCenter, // Taller boxes grow upward, away from the NPC
+                    ..default()
+                },
+                transform: Transform::from_translation(
+                    npc_transform.translation + Vec3::new(0.0, ANCHOR_OFFSET, 5.0)
+                ),
+                visibility: shown,
+                ..default()
+            },
+            DialogBox {
+                text: npc.dialog_text.clone(),
+                visible: true,
+                speaker: Some(npc_entity),
+                revealed: 0.0,
+            },
+        )).with_children(|parent| {
+            parent.spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        String::new(),
+                        TextStyle {
+                            font: asset_server.load("fonts/FiraSans-Light.ttf"),
+                            font_size: FONT_SIZE,
+                            color: Color::WHITE,
+                        },
+                    )
+                    .with_alignment(TextAlignment::Left),
+                    text_anchor: Anchor::TopLeft,
+                    transform: Transform::from_translation(Vec3::new(-size.x / 2.0 + PADDING, size.y - PADDING, 5.0)),
+                    ..default()
+                },
+                DialogText,
+            ));
+        });
+    }
+}
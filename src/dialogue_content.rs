@@ -0,0 +1,205 @@
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+use std::sync::RwLock;
+use std::time::SystemTime;
+
+use crate::biome::BiomeType;
+use crate::dialogue::CharacterType;
+use crate::faction::{Faction, Standing};
+use crate::world_facts::{Condition, WorldFacts};
+
+/// Where NPC lines live, relative to the assets folder
+pub const DIALOGUE_PATH: &str = "data/dialogue.json";
+
+// How often the dialogue file is checked for edits
+const POLL_INTERVAL: f32 = 1.0;
+
+/// What kind of line something is, so each generator can pick from the right ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineTag {
+    Greeting,
+    Farewell,
+    Idle,      // Said once someone has nothing left to tell
+    Cryptic,
+    Friendly,
+    Hostile,
+    QuestHint, // Points the player at something worth finding
+}
+
+/// One line an NPC can say and who may say it. Empty lists mean anyone, anywhere
+#[derive(Debug, Clone, Deserialize)]
+pub struct DialogueLine {
+    pub text: String,
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+    #[serde(default)]
+    pub tags: Vec<LineTag>,
+    #[serde(default)]
+    pub characters: Vec<CharacterType>,
+    #[serde(default)]
+    pub biomes: Vec<BiomeType>,
+    #[serde(default)]
+    pub faction: Option<Faction>,   // Only said by this faction...
+    #[serde(default)]
+    pub standing: Option<Standing>, // ...when it feels this way about the player
+    #[serde(default)]
+    pub when: Option<Condition>,    // Only said while this holds
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+impl DialogueLine {
+    pub fn has_tag(&self, tag: LineTag) -> bool {
+        self.tags.contains(&tag)
+    }
+
+    // Whether the line is written for this character type in particular
+    pub fn for_character(&self, character_type: &CharacterType) -> bool {
+        self.characters.contains(character_type)
+    }
+
+    // Whether the line is written for this biome in particular
+    pub fn for_biome(&self, biome: BiomeType) -> bool {
+        self.biomes.contains(&biome)
+    }
+
+    // Whether the line's condition holds; conditional lines are never said without facts to check them against
+    fn is_available(&self, facts: Option<&WorldFacts>) -> bool {
+        match (&self.when, facts) {
+            (None, _) => true,
+            (Some(condition), Some(facts)) => condition.holds(facts),
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// Every NPC line in the game
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DialogueContent {
+    pub lines: Vec<DialogueLine>,
+}
+
+impl DialogueContent {
+    pub fn load(assets_dir: &Path) -> Result<Self, String> {
+        let path = assets_dir.join(DIALOGUE_PATH);
+        let contents = fs::read_to_string(&path).map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+        serde_json::from_str(&contents).map_err(|e| format!("could not parse {}: {}", path.display(), e))
+    }
+}
+
+// Lines are drawn deep inside spawning and lore helpers that can't reach the ECS world,
+// so the loaded content lives here and the watcher below swaps it out
+static DIALOGUE_CONTENT: RwLock<Option<DialogueContent>> = RwLock::new(None);
+
+// Load the dialogue file the first time any line is needed
+fn ensure_loaded() {
+    if DIALOGUE_CONTENT.read().map_or(false, |content| content.is_some()) {
+        return;
+    }
+    let content = DialogueContent::load(Path::new("assets")).unwrap_or_else(|e| {
+        eprintln!("No NPC dialogue: {}", e);
+        DialogueContent::default()
+    });
+    if let Ok(mut slot) = DIALOGUE_CONTENT.write() {
+        slot.get_or_insert(content);
+    }
+}
+
+/// Pick a line matching the filter, favouring heavier weights
+pub fn pick_line(facts: Option<&WorldFacts>, filter: impl Fn(&DialogueLine) -> bool, rng: &mut impl Rng) -> Option<String> {
+    ensure_loaded();
+    let content = DIALOGUE_CONTENT.read().ok()?;
+    let candidates: Vec<&DialogueLine> = available_lines(&content, facts, filter).collect();
+    candidates
+        .choose_weighted(rng, |line| line.weight)
+        .ok()
+        .map(|line| line.text.clone())
+}
+
+/// Pick up to `count` different lines matching the filter
+pub fn pick_lines(facts: Option<&WorldFacts>, filter: impl Fn(&DialogueLine) -> bool, count: usize, rng: &mut impl Rng) -> Vec<String> {
+    ensure_loaded();
+    let content = if let Ok(content) = DIALOGUE_CONTENT.read() { content } else { return Vec::new(); };
+    let candidates: Vec<&DialogueLine> = available_lines(&content, facts, filter).collect();
+    candidates
+        .choose_multiple_weighted(rng, count, |line| line.weight)
+        .map(|chosen| chosen.map(|line| line.text.clone()).collect())
+        .unwrap_or_default()
+}
+
+/// Whether any line matches the filter
+pub fn has_lines(facts: Option<&WorldFacts>, filter: impl Fn(&DialogueLine) -> bool) -> bool {
+    ensure_loaded();
+    DIALOGUE_CONTENT
+        .read()
+        .map_or(false, |content| available_lines(&content, facts, filter).next().is_some())
+}
+
+// The lines that match a filter and can be said right now
+fn available_lines<'a>(
+    content: &'a Option<DialogueContent>,
+    facts: Option<&'a WorldFacts>,
+    filter: impl Fn(&DialogueLine) -> bool + 'a,
+) -> impl Iterator<Item = &'a DialogueLine> + 'a {
+    content
+        .iter()
+        .flat_map(|content| content.lines.iter())
+        .filter(move |line| line.is_available(facts) && filter(line))
+}
+
+/// State for polling the dialogue file for changes
+pub struct DialogueWatch {
+    timer: Timer,
+    last_modified: Option<SystemTime>,
+}
+
+impl Default for DialogueWatch {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(POLL_INTERVAL, TimerMode::Repeating),
+            last_modified: None,
+        }
+    }
+}
+
+fn dialogue_modified() -> Option<SystemTime> {
+    fs::metadata(Path::new("assets").join(DIALOGUE_PATH))
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+// System to reload NPC lines when the dialogue file is edited, so writers see them without recompiling
+pub fn hot_reload_dialogue(time: Res<Time>, mut watch: Local<DialogueWatch>) {
+    if !watch.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let modified = dialogue_modified();
+    if watch.last_modified.is_none() {
+        // First check just records the starting point
+        watch.last_modified = modified;
+        return;
+    }
+    if modified == watch.last_modified {
+        return;
+    }
+    watch.last_modified = modified;
+
+    // Keep the current lines if the edit broke something
+    match DialogueContent::load(Path::new("assets")) {
+        Ok(content) => {
+            println!("Dialogue changed, reloaded {} lines", content.lines.len());
+            if let Ok(mut slot) = DIALOGUE_CONTENT.write() {
+                *slot = Some(content);
+            }
+        }
+        Err(e) => eprintln!("Not reloading dialogue: {}", e),
+    }
+}
@@ -0,0 +1,148 @@
+use bevy::prelude::*;
+
+use crate::components::{Npc, Player, Tile};
+use crate::input::TILE_SIZE;
+use crate::map::TileType;
+use crate::visibility::TileVisibility;
+
+// How thick the high-contrast frame is, in pixels
+const OUTLINE_WIDTH: f32 = 2.0;
+// Drawn just above whatever it frames
+const OUTLINE_Z: f32 = 0.5;
+// Frame colors: the player stands out most, stairs are marked apart from creatures
+const PLAYER_OUTLINE: Color = Color::rgb(1.0, 1.0, 0.0);
+const NPC_OUTLINE: Color = Color::rgb(1.0, 1.0, 1.0);
+const STAIRS_OUTLINE: Color = Color::rgb(0.0, 1.0, 1.0);
+
+/// Which set of colors biome tints and status effects are drawn in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Palette {
+    #[default]
+    Standard,
+    Colorblind, // Okabe-Ito hues, told apart by brightness as well as hue
+}
+
+/// How the game is drawn for players who need it. Set with `--colorblind`, `--high-contrast` and `--screen-text`,
+/// and toggled in a run with F7 (palette), F8 (outlines) and F6 (screen-space text)
+#[derive(Resource, Debug, Default)]
+pub struct DisplaySettings {
+    pub palette: Palette,
+    pub high_contrast: bool,     // Frame the player, NPCs and stairs
+    pub screen_space_text: bool, // Draw speech and tooltips as UI at a fixed size, whatever the zoom
+}
+
+impl DisplaySettings {
+    pub fn from_args() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+        let palette = if args.iter().any(|arg| arg == "--colorblind") { Palette::Colorblind } else { Palette::Standard };
+        let high_contrast = args.iter().any(|arg| arg == "--high-contrast");
+        let screen_space_text = args.iter().any(|arg| arg == "--screen-text");
+        Self { palette, high_contrast, screen_space_text }
+    }
+}
+
+/// A frame drawn around something so it stands out; removed when high contrast is turned off
+#[derive(Component)]
+pub struct ContrastOutline;
+
+// System to switch the palette, outlines and screen-space text during a run
+pub fn toggle_display_settings(keyboard: Res<Input<KeyCode>>, mut settings: ResMut<DisplaySettings>) {
+    if keyboard.just_pressed(KeyCode::F7) {
+        settings.palette = match settings.palette {
+            Palette::Standard => Palette::Colorblind,
+            Palette::Colorblind => Palette::Standard,
+        };
+        println!("Palette: {:?}", settings.palette);
+    }
+    if keyboard.just_pressed(KeyCode::F8) {
+        settings.high_contrast = !settings.high_contrast;
+        println!("High contrast: {}", if settings.high_contrast { "on" } else { "off" });
+    }
+    if keyboard.just_pressed(KeyCode::F6) {
+        settings.screen_space_text = !settings.screen_space_text;
+        println!("Screen-space text: {}", if settings.screen_space_text { "on" } else { "off" });
+    }
+}
+
+// Spawn four bars as children of an entity, framing its tile
+fn spawn_outline(commands: &mut Commands, entity: Entity, color: Color) {
+    let edge = TILE_SIZE / 2.0 - OUTLINE_WIDTH / 2.0;
+    let bars = [
+        (Vec2::new(0.0, edge), Vec2::new(TILE_SIZE, OUTLINE_WIDTH)),
+        (Vec2::new(0.0, -edge), Vec2::new(TILE_SIZE, OUTLINE_WIDTH)),
+        (Vec2::new(-edge, 0.0), Vec2::new(OUTLINE_WIDTH, TILE_SIZE)),
+        (Vec2::new(edge, 0.0), Vec2::new(OUTLINE_WIDTH, TILE_SIZE)),
+    ];
+    commands.entity(entity).with_children(|parent| {
+        for (offset, size) in bars {
+            parent.spawn((
+                SpriteBundle {
+                    sprite: Sprite { color, custom_size: Some(size), ..default() },
+                    transform: Transform::from_xyz(offset.x, offset.y, OUTLINE_Z),
+                    ..default()
+                },
+                ContrastOutline,
+            ));
+        }
+    });
+}
+
+// Whether a tile gets a frame
+fn is_stairs(tile: &Tile) -> bool {
+    matches!(tile.tile_type, TileType::StairsDown | TileType::StairsUp)
+}
+
+// System to frame the player, NPCs and stairs while high contrast is on, and clear the frames when it's off
+pub fn sync_contrast_outlines(
+    mut commands: Commands,
+    settings: Res<DisplaySettings>,
+    creatures: Query<(Entity, Option<&Children>, Option<&Player>), Or<(With<Player>, With<Npc>)>>,
+    tiles: Query<(Entity, Option<&Children>, &Tile)>,
+    outlines: Query<(Entity, &Parent), With<ContrastOutline>>,
+) {
+    // Tile entities are reused across levels, and some despawns leave children behind,
+    // so drop frames whose owner is gone or is no longer stairs
+    for (outline, parent) in outlines.iter() {
+        let stale = match tiles.get(parent.get()) {
+            Ok((_, _, tile)) => !is_stairs(tile),
+            Err(_) => !creatures.contains(parent.get()),
+        };
+        if stale || !settings.high_contrast {
+            commands.entity(outline).despawn_recursive();
+        }
+    }
+    if !settings.high_contrast {
+        return;
+    }
+
+    let framed = |children: Option<&Children>| {
+        children.map_or(false, |children| children.iter().any(|child| outlines.contains(*child)))
+    };
+    for (entity, children, player) in creatures.iter() {
+        if !framed(children) {
+            spawn_outline(&mut commands, entity, if player.is_some() { PLAYER_OUTLINE } else { NPC_OUTLINE });
+        }
+    }
+    for (entity, children, tile) in tiles.iter() {
+        if is_stairs(tile) && !framed(children) {
+            spawn_outline(&mut commands, entity, STAIRS_OUTLINE);
+        }
+    }
+}
+
+// System to keep stairs frames hidden until the stairs themselves have been seen
+pub fn hide_unseen_stairs_outlines(
+    stairs: Query<(&TileVisibility, &Children), With<Tile>>,
+    mut outlines: Query<&mut Visibility, With<ContrastOutline>>,
+) {
+    for (tile_visibility, children) in stairs.iter() {
+        let visibility = if tile_visibility.previously_seen { Visibility::Inherited } else { Visibility::Hidden };
+        for &child in children.iter() {
+            if let Ok(mut outline) = outlines.get_mut(child) {
+                if *outline != visibility {
+                    *outline = visibility;
+                }
+            }
+        }
+    }
+}
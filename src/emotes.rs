@@ -0,0 +1,223 @@
+use bevy::prelude::*;
+use bevy::text::{Text2dBundle, TextAlignment};
+use std::collections::{HashMap, VecDeque};
+
+use crate::input::TILE_SIZE;
+
+// How long each emote stays up, fading in at the start and out at the end
+const EMOTE_SECONDS: f32 = 1.6;
+const EMOTE_FADE_IN_SECONDS: f32 = 0.15;
+const EMOTE_FADE_OUT_SECONDS: f32 = 0.5;
+// Emotes waiting behind the one on show; older ones are dropped past this
+const MAX_QUEUED: usize = 3;
+// Where the bubble sits relative to the entity's centre, and how far it drifts up while shown
+const EMOTE_OFFSET: Vec2 = Vec2::new(TILE_SIZE * 0.35, TILE_SIZE * 0.7);
+const EMOTE_RISE: f32 = 6.0;
+// Relative to the entity; dialog boxes sit at most 10 above their speaker, so this clears them
+const EMOTE_Z: f32 = 30.0;
+const BUBBLE_SIZE: f32 = 14.0;
+const BUBBLE_COLOR: Color = Color::rgba(0.05, 0.05, 0.08, 0.85);
+// Text emotes: font size, and roughly how wide each character comes out
+const TEXT_SIZE: f32 = 11.0;
+const TEXT_CHAR_WIDTH: f32 = 6.0;
+const TEXT_COLOR: Color = Color::WHITE;
+
+/// An icon shown in a bubble over an entity's head
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmoteKind {
+    Alert, // Red exclamation mark: it has noticed the player, or turned on them
+    Note,  // Yellow musical note: it's idling, content
+    Heart, // Pink heart: it has just been fed
+}
+
+impl EmoteKind {
+    fn color(&self) -> Color {
+        match self {
+            EmoteKind::Alert => Color::rgb(1.0, 0.25, 0.2),
+            EmoteKind::Note => Color::rgb(1.0, 0.85, 0.3),
+            EmoteKind::Heart => Color::rgb(1.0, 0.45, 0.65),
+        }
+    }
+
+    // The icon as rectangles: (centre, size, turned 45 degrees), in pixels from the bubble's centre.
+    // The font has no glyphs for these, so they're built like the zoom markers
+    fn shape(&self) -> Vec<(Vec2, Vec2, bool)> {
+        match self {
+            EmoteKind::Alert => vec![
+                (Vec2::new(0.0, 1.5), Vec2::new(2.5, 6.0), false),
+                (Vec2::new(0.0, -4.0), Vec2::new(2.5, 2.5), false),
+            ],
+            EmoteKind::Note => vec![
+                (Vec2::new(-1.5, -3.5), Vec2::new(4.0, 3.0), false), // Head
+                (Vec2::new(0.0, 0.5), Vec2::new(1.2, 8.0), false),   // Stem
+                (Vec2::new(1.5, 4.0), Vec2::new(3.0, 1.5), false),   // Flag
+            ],
+            EmoteKind::Heart => vec![
+                (Vec2::new(0.0, -1.0), Vec2::new(6.0, 6.0), true),
+                (Vec2::new(-2.0, 1.5), Vec2::new(4.0, 4.0), true),
+                (Vec2::new(2.0, 1.5), Vec2::new(4.0, 4.0), true),
+            ],
+        }
+    }
+}
+
+/// What an emote shows: one of the icons, or a few characters of text ("?", "z")
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmoteContent {
+    Icon(EmoteKind),
+    Text(String),
+}
+
+/// Sent to pop an emote up over any entity: the player, an NPC, an animal or a monster
+#[derive(Event, Debug, Clone)]
+pub struct ShowEmote {
+    pub entity: Entity,
+    pub content: EmoteContent,
+}
+
+impl ShowEmote {
+    pub fn icon(entity: Entity, kind: EmoteKind) -> Self {
+        Self { entity, content: EmoteContent::Icon(kind) }
+    }
+
+    pub fn text(entity: Entity, text: &str) -> Self {
+        Self { entity, content: EmoteContent::Text(text.to_string()) }
+    }
+}
+
+/// The emotes an entity has to show, one after another
+#[derive(Component, Debug, Default)]
+pub struct Emote {
+    queue: VecDeque<EmoteContent>,
+    showing: Option<(Entity, EmoteContent, Timer)>, // The bubble on screen, what's in it, and how long it's been up
+}
+
+impl Emote {
+    // Queue an emote, unless it's already showing or next in line
+    pub fn push(&mut self, content: EmoteContent) {
+        let showing = self.showing.as_ref().map(|(_, showing, _)| showing);
+        if showing == Some(&content) || self.queue.back() == Some(&content) {
+            return;
+        }
+        self.queue.push_back(content);
+        while self.queue.len() > MAX_QUEUED {
+            self.queue.pop_front();
+        }
+    }
+}
+
+/// The bubble on show, a child of the entity it's over
+#[derive(Component, Debug)]
+pub struct EmoteBubble;
+
+// System to queue the emotes asked for this frame on their entities
+pub fn queue_emotes(
+    mut commands: Commands,
+    mut emote_events: EventReader<ShowEmote>,
+    mut emote_query: Query<&mut Emote>,
+) {
+    let mut new_emotes: HashMap<Entity, Emote> = HashMap::new();
+    for event in emote_events.read() {
+        if let Ok(mut emote) = emote_query.get_mut(event.entity) {
+            emote.push(event.content.clone());
+        } else if commands.get_entity(event.entity).is_some() {
+            new_emotes.entry(event.entity).or_default().push(event.content.clone());
+        }
+    }
+    for (entity, emote) in new_emotes {
+        commands.entity(entity).insert(emote);
+    }
+}
+
+fn spawn_bubble(commands: &mut Commands, owner: Entity, content: &EmoteContent, font: Handle<Font>) -> Entity {
+    let width = match content {
+        EmoteContent::Icon(_) => BUBBLE_SIZE,
+        EmoteContent::Text(text) => (text.chars().count() as f32 * TEXT_CHAR_WIDTH + 6.0).max(BUBBLE_SIZE),
+    };
+    let bubble = commands
+        .spawn((
+            SpriteBundle {
+                sprite: Sprite { color: BUBBLE_COLOR.with_a(0.0), custom_size: Some(Vec2::new(width, BUBBLE_SIZE)), ..default() },
+                transform: Transform::from_translation(EMOTE_OFFSET.extend(EMOTE_Z)),
+                ..default()
+            },
+            EmoteBubble,
+        ))
+        .with_children(|bubble| match content {
+            EmoteContent::Icon(kind) => {
+                for (offset, size, diamond) in kind.shape() {
+                    let rotation = if diamond { Quat::from_rotation_z(std::f32::consts::FRAC_PI_4) } else { Quat::IDENTITY };
+                    bubble.spawn(SpriteBundle {
+                        sprite: Sprite { color: kind.color().with_a(0.0), custom_size: Some(size), ..default() },
+                        transform: Transform::from_translation(offset.extend(0.1)).with_rotation(rotation),
+                        ..default()
+                    });
+                }
+            }
+            EmoteContent::Text(text) => {
+                bubble.spawn(Text2dBundle {
+                    text: Text::from_section(
+                        text.clone(),
+                        TextStyle { font, font_size: TEXT_SIZE, color: TEXT_COLOR.with_a(0.0) },
+                    )
+                    .with_alignment(TextAlignment::Center),
+                    transform: Transform::from_xyz(0.0, 0.0, 0.1),
+                    ..default()
+                });
+            }
+        })
+        .id();
+    commands.entity(owner).add_child(bubble);
+    bubble
+}
+
+// System to show each entity's emotes in turn: fading one in, floating it up, fading it out, then starting the next
+pub fn update_emotes(
+    mut commands: Commands,
+    time: Res<Time>,
+    asset_server: Res<AssetServer>,
+    mut emote_query: Query<(Entity, &mut Emote)>,
+    mut bubble_query: Query<(&mut Transform, &mut Sprite, &Children), With<EmoteBubble>>,
+    mut icon_query: Query<&mut Sprite, Without<EmoteBubble>>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (entity, mut emote) in emote_query.iter_mut() {
+        if emote.showing.is_none() {
+            let content = if let Some(content) = emote.queue.pop_front() { content } else {
+                commands.entity(entity).remove::<Emote>();
+                continue;
+            };
+            let bubble = spawn_bubble(&mut commands, entity, &content, asset_server.load("fonts/FiraSans-Bold.ttf"));
+            emote.showing = Some((bubble, content, Timer::from_seconds(EMOTE_SECONDS, TimerMode::Once)));
+            continue;
+        }
+
+        let (bubble, _, timer) = emote.showing.as_mut().unwrap();
+        timer.tick(time.delta());
+        if timer.finished() {
+            commands.entity(*bubble).despawn_recursive();
+            emote.showing = None;
+            continue;
+        }
+        let opacity = (timer.elapsed_secs() / EMOTE_FADE_IN_SECONDS).min(timer.remaining_secs() / EMOTE_FADE_OUT_SECONDS).min(1.0);
+        let rise = EMOTE_RISE * timer.percent();
+
+        // The bubble can be despawned from under us along with a child-clearing owner
+        let (mut transform, mut sprite, children) = if let Ok(bubble) = bubble_query.get_mut(*bubble) { bubble } else {
+            emote.showing = None;
+            continue;
+        };
+        transform.translation.y = EMOTE_OFFSET.y + rise;
+        sprite.color.set_a(BUBBLE_COLOR.a() * opacity);
+        for &child in children.iter() {
+            if let Ok(mut icon) = icon_query.get_mut(child) {
+                icon.color.set_a(opacity);
+            }
+            if let Ok(mut text) = text_query.get_mut(child) {
+                for section in text.sections.iter_mut() {
+                    section.style.color.set_a(opacity);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+use crate::animals::{spawn_animal, AnimalManager};
+use crate::assets::TextureAtlases;
+use crate::components::{GameTurn, Player, Position};
+use crate::events::LevelChanged;
+use crate::faction::Hostile;
+use crate::map::TileMap;
+use crate::rng::GameRng;
+use crate::spawn_director::respawn_candidates;
+use crate::stealth::{AlertState, Awareness};
+use crate::ui::MessageLog;
+use crate::visibility::VisibilityMap;
+
+/// Where the escalation pacing is read from, under assets/
+pub const ESCALATION_PATH: &str = "data/escalation.json";
+
+// How long a hunter sent by the Chasm stays on the player's trail without seeing them
+const HUNTER_ALERT_TURNS: u32 = 60;
+
+/// How the Chasm turns up the pressure on a player who lingers, from assets/data/escalation.json
+#[derive(Resource, Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct EscalationSettings {
+    pub calm_turns: u32,                // Turns on a floor before the Chasm stirs
+    pub stage_turns: u32,               // Turns between each stage after that
+    pub max_stage: u32,
+    pub extra_creatures_per_stage: usize,
+    pub respawn_speedup_per_stage: u32, // Divides the wait between creatures wandering in, plus one per stage
+    pub dim_per_stage: f32,             // Share of the light taken away at each stage
+    pub hunter_stage: u32,              // Stage at which something comes looking for the player
+    pub hunter: String,                 // Which monster, by name
+    pub messages: Vec<String>,          // Logged at each stage in turn; the last repeats
+}
+
+impl Default for EscalationSettings {
+    fn default() -> Self {
+        Self {
+            calm_turns: 300,
+            stage_turns: 100,
+            max_stage: 4,
+            extra_creatures_per_stage: 1,
+            respawn_speedup_per_stage: 1,
+            dim_per_stage: 0.12,
+            hunter_stage: 3,
+            hunter: "Grizzly Bear".to_string(),
+            messages: vec![
+                "The Chasm stirs. Somewhere below, things begin to move.".to_string(),
+                "The lights gutter, and the dark presses closer.".to_string(),
+                "Something has caught your scent.".to_string(),
+                "The Chasm grows restless. It's time to go deeper.".to_string(),
+            ],
+        }
+    }
+}
+
+impl EscalationSettings {
+    // Read the pacing, falling back to the defaults if the file is missing or broken
+    pub fn load() -> Self {
+        let path = Path::new("assets").join(ESCALATION_PATH);
+        match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                eprintln!("Could not parse escalation settings {}: {}", path.display(), e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    // The stage reached after this many turns on a floor
+    fn stage_after(&self, turns: u32) -> u32 {
+        if turns < self.calm_turns {
+            return 0;
+        }
+        (1 + (turns - self.calm_turns) / self.stage_turns.max(1)).min(self.max_stage)
+    }
+}
+
+/// How stirred up the Chasm is on the current floor. Only changes when a new stage is reached
+/// or the player moves to another floor, so systems can react to `is_changed`
+#[derive(Resource, Debug, Default)]
+pub struct Escalation {
+    pub stage: u32,
+    pub floor_entered: u32, // The turn the player arrived on this floor
+}
+
+impl Escalation {
+    pub fn extra_creatures(&self, settings: &EscalationSettings) -> usize {
+        self.stage as usize * settings.extra_creatures_per_stage
+    }
+
+    // Turns between creatures wandering in, shortened as the Chasm stirs
+    pub fn respawn_interval(&self, settings: &EscalationSettings, base: u32) -> u32 {
+        (base / (1 + self.stage * settings.respawn_speedup_per_stage)).max(1)
+    }
+
+    // How much of the usual light is left
+    pub fn light_factor(&self, settings: &EscalationSettings) -> f32 {
+        (1.0 - self.stage as f32 * settings.dim_per_stage).max(0.0)
+    }
+}
+
+// System to calm the Chasm when a run starts
+pub fn reset_escalation(mut escalation: ResMut<Escalation>) {
+    *escalation = Escalation::default();
+}
+
+// System to count turns on the current floor and raise the stakes for a player who lingers there
+pub fn escalation_system(
+    mut commands: Commands,
+    settings: Res<EscalationSettings>,
+    mut escalation: ResMut<Escalation>,
+    mut level_events: EventReader<LevelChanged>,
+    map: Res<TileMap>,
+    texture_atlases: Res<TextureAtlases>,
+    animal_manager: Res<AnimalManager>,
+    visibility_map: Option<Res<VisibilityMap>>,
+    player_query: Query<&Position, With<Player>>,
+    game_turn: Res<GameTurn>,
+    mut game_rng: ResMut<GameRng>,
+    mut message_log: ResMut<MessageLog>,
+    mut local: Local<u32>,
+) {
+    // A new floor settles things down again
+    if level_events.read().last().is_some() {
+        *escalation = Escalation { stage: 0, floor_entered: game_turn.current_turn };
+    }
+
+    // Only check once per turn
+    if game_turn.current_turn == 0 || game_turn.current_turn == *local {
+        return;
+    }
+    *local = game_turn.current_turn;
+
+    // Boss floors keep to their set piece
+    if map.is_boss_level {
+        return;
+    }
+    let stage = settings.stage_after(game_turn.current_turn.saturating_sub(escalation.floor_entered));
+    if stage <= escalation.stage {
+        return;
+    }
+    escalation.stage = stage;
+
+    if let Some(message) = settings.messages.get(stage as usize - 1).or(settings.messages.last()) {
+        message_log.add_message(message.clone());
+    }
+    println!("Escalation stage {} on level {} (turn {})", stage, map.current_level, game_turn.current_turn);
+
+    // Something is sent out after the player, out of sight, already knowing where they are
+    if stage == settings.hunter_stage {
+        let player = if let Ok(pos) = player_query.get_single() { *pos } else { return; };
+        let candidates = respawn_candidates(&map, &player, visibility_map.as_deref());
+        let spawn_data = animal_manager.spawn_data_for(&settings.hunter);
+        if let (Some(&pos), Some(spawn_data)) = (candidates.choose(&mut game_rng.spawns), spawn_data) {
+            let hunter = spawn_animal(&mut commands, &map, &texture_atlases, &spawn_data, pos);
+            commands.entity(hunter).insert((Hostile, Awareness { state: AlertState::Alert, turns_left: HUNTER_ALERT_TURNS }));
+        }
+    }
+}
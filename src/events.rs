@@ -0,0 +1,73 @@
+use bevy::prelude::*;
+
+use crate::inventory::ItemKind;
+
+// The core gameplay events. Systems announce what happened through these rather
+// than reaching into each other's resources, so anything that cares (run stats,
+// achievements, sound, logging) can listen in without the sender knowing about it.
+
+/// Sent when the player finishes stepping onto a new tile
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PlayerMoved {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// Sent when the player tries to step somewhere they can't go
+#[derive(Event, Debug, Clone, Copy)]
+pub struct MovementBlocked {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// Sent when any creature, the player included, arrives on a tile
+#[derive(Event, Debug, Clone, Copy)]
+pub struct TileEntered {
+    pub entity: Entity,
+    pub x: i32,
+    pub y: i32,
+}
+
+/// Sent when the player takes the stairs to another floor; levels are zero-based
+#[derive(Event, Debug, Clone, Copy)]
+pub struct LevelChanged {
+    pub from: usize,
+    pub to: usize,
+}
+
+/// Sent whenever something loses health
+#[derive(Event, Debug, Clone)]
+pub struct EntityDamaged {
+    pub target: Entity,
+    pub amount: i32,
+    pub source: String, // What did it, e.g. "a Wolf" or "poison"
+}
+
+/// Sent when the player kills a creature, named as its kind (e.g. "Grizzly Bear") where it has one
+#[derive(Event, Debug, Clone)]
+pub struct CreatureKilled {
+    pub name: String,
+}
+
+/// Sent for every item the player picks up
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ItemPickedUp {
+    pub item: ItemKind,
+}
+
+/// Sent when the player makes an attack of any kind, hit or miss
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PlayerAttacked;
+
+/// Sent when feeding wins an animal over as a companion
+#[derive(Event, Debug, Clone, Copy)]
+pub struct AnimalTamed {
+    pub entity: Entity,
+}
+
+/// Sent when a search turns up a secret door
+#[derive(Event, Debug, Clone, Copy)]
+pub struct SecretDoorFound {
+    pub x: i32,
+    pub y: i32,
+}
@@ -0,0 +1,237 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+
+use crate::combat::{CombatStats, Health};
+use crate::components::{GameTurn, Npc, Player, Position};
+use crate::dialogue::CharacterType;
+use crate::emotes::{EmoteKind, ShowEmote};
+use crate::events::EntityDamaged;
+use crate::hearing::{NoiseEvent, NoiseKind};
+use crate::input::TILE_SIZE;
+use crate::map::TileMap;
+use crate::pathmaps::PathMaps;
+use crate::status::StatusEffects;
+use crate::ui::MessageLog;
+use crate::visibility::line_of_sight;
+
+// Reputation thresholds used to classify how a faction feels about the player
+pub const HOSTILE_THRESHOLD: i32 = -25;
+pub const FRIENDLY_THRESHOLD: i32 = 25;
+pub const MIN_REPUTATION: i32 = -100;
+pub const MAX_REPUTATION: i32 = 100;
+// How hard an NPC that has turned on the player hits, unless it already fights with something harder
+const HOSTILE_NPC_ATTACK: i32 = 2;
+// How far a hostile NPC spots the player from, in tiles
+const HOSTILE_NPC_SIGHT_RANGE: i32 = 8;
+
+/// The groups NPCs belong to
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum Faction {
+    Dwellers,   // Ordinary folk scraping a living in the depths
+    Cultists,   // Followers of whatever waits at the bottom of the Chasm
+    Merchants,  // Traders, smiths and shopkeepers
+}
+
+impl Faction {
+    // Pick a faction for an NPC based on its character type
+    pub fn from_character_type(character_type: &CharacterType) -> Self {
+        match character_type {
+            CharacterType::Warlock
+            | CharacterType::Rogue
+            | CharacterType::Bandit
+            | CharacterType::Sage => Faction::Cultists,
+            CharacterType::Shopkeeper
+            | CharacterType::Blacksmith
+            | CharacterType::Baker => Faction::Merchants,
+            _ => Faction::Dwellers,
+        }
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Faction::Dwellers => "Dwellers",
+            Faction::Cultists => "Cultists",
+            Faction::Merchants => "Merchants",
+        }
+    }
+
+    // Reputation every new run starts with
+    fn starting_reputation(&self) -> i32 {
+        match self {
+            Faction::Dwellers => 10,
+            Faction::Cultists => -10,
+            Faction::Merchants => 0,
+        }
+    }
+}
+
+/// How a faction currently regards the player
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Standing {
+    Hostile,
+    Neutral,
+    Friendly,
+}
+
+/// Marker for NPCs that have turned against the player
+#[derive(Component, Debug)]
+pub struct Hostile;
+
+/// Event sent by gameplay systems whenever the player does something a faction cares about
+#[derive(Event, Debug)]
+pub struct ReputationChange {
+    pub faction: Faction,
+    pub amount: i32,
+    pub reason: String,
+}
+
+/// Resource tracking the player's reputation with each faction
+#[derive(Resource)]
+pub struct Reputation {
+    pub values: HashMap<Faction, i32>,
+}
+
+impl Default for Reputation {
+    fn default() -> Self {
+        let mut values = HashMap::new();
+        for faction in [Faction::Dwellers, Faction::Cultists, Faction::Merchants] {
+            values.insert(faction, faction.starting_reputation());
+        }
+        Self { values }
+    }
+}
+
+impl Reputation {
+    pub fn get(&self, faction: Faction) -> i32 {
+        *self.values.get(&faction).unwrap_or(&0)
+    }
+
+    pub fn adjust(&mut self, faction: Faction, amount: i32) {
+        let value = self.values.entry(faction).or_insert(0);
+        *value = (*value + amount).clamp(MIN_REPUTATION, MAX_REPUTATION);
+    }
+
+    pub fn standing(&self, faction: Faction) -> Standing {
+        let value = self.get(faction);
+        if value <= HOSTILE_THRESHOLD {
+            Standing::Hostile
+        } else if value >= FRIENDLY_THRESHOLD {
+            Standing::Friendly
+        } else {
+            Standing::Neutral
+        }
+    }
+
+    // Multiplier shops apply to their prices: friends get a discount, enemies pay extra
+    pub fn price_multiplier(&self, faction: Faction) -> f32 {
+        // Linear from 1.5x at -100 down to 0.75x at +100
+        let value = self.get(faction) as f32;
+        1.125 - value * 0.00375
+    }
+}
+
+// Apply queued reputation changes
+pub fn apply_reputation_changes(
+    mut reputation: ResMut<Reputation>,
+    mut events: EventReader<ReputationChange>,
+) {
+    for event in events.read() {
+        let before = reputation.standing(event.faction);
+        reputation.adjust(event.faction, event.amount);
+        let after = reputation.standing(event.faction);
+
+        println!("Reputation with {} changed by {} ({}), now {}",
+                 event.faction.get_name(), event.amount, event.reason, reputation.get(event.faction));
+
+        if before != after {
+            println!("The {} now regard you as {:?}", event.faction.get_name(), after);
+        }
+    }
+}
+
+// Flip NPCs between friendly and hostile as their faction's opinion changes; party members stay loyal
+pub fn update_npc_hostility(
+    mut commands: Commands,
+    reputation: Res<Reputation>,
+    mut npc_query: Query<(Entity, &Faction, &mut Npc, Option<&Hostile>), Without<crate::party::PartyMember>>,
+    mut emote_events: EventWriter<ShowEmote>,
+) {
+    if !reputation.is_changed() {
+        return;
+    }
+
+    for (entity, faction, mut npc, hostile) in npc_query.iter_mut() {
+        let should_be_hostile = reputation.standing(*faction) == Standing::Hostile;
+
+        if should_be_hostile && hostile.is_none() {
+            commands.entity(entity).insert(Hostile);
+            // Hostile NPCs stop whatever conversation they were having
+            npc.speaking = false;
+            emote_events.send(ShowEmote::icon(entity, EmoteKind::Alert));
+            println!("{} of the {} turns hostile!", npc.name, faction.get_name());
+        } else if !should_be_hostile && hostile.is_some() {
+            commands.entity(entity).remove::<Hostile>();
+            println!("{} of the {} is no longer hostile", npc.name, faction.get_name());
+        }
+    }
+}
+
+// System for NPCs whose faction has turned on the player: they close in once they can see them
+// and strike when next to them, once a turn
+pub fn hostile_npc_system(
+    mut npc_query: Query<(&Npc, &mut Position, &mut Transform, &Health, Option<&CombatStats>, Option<&StatusEffects>), (With<Hostile>, With<Faction>, Without<Player>, Without<crate::party::PartyMember>)>,
+    mut player_query: Query<(Entity, &Position, &mut Health), (With<Player>, Without<Npc>)>,
+    path_maps: Res<PathMaps>,
+    map: Res<TileMap>,
+    game_turn: Res<GameTurn>,
+    mut damage_events: EventWriter<EntityDamaged>,
+    mut noise_events: EventWriter<NoiseEvent>,
+    mut message_log: ResMut<MessageLog>,
+    mut local: Local<u32>,
+) {
+    // Only act once per turn
+    if game_turn.current_turn == 0 || game_turn.current_turn == *local {
+        return;
+    }
+    *local = game_turn.current_turn;
+
+    let (player_entity, player_pos, mut player_health) = if let Ok(player) = player_query.get_single_mut() { player } else { return; };
+    let player_tile = (player_pos.x, player_pos.y);
+    // Where each of them stands, so two don't step onto the same tile
+    let mut taken: HashSet<(i32, i32)> = npc_query.iter().map(|(_, position, ..)| (position.x, position.y)).collect();
+
+    for (npc, mut position, mut transform, health, stats, status) in npc_query.iter_mut() {
+        if health.is_dead() || status.map_or(false, |status| status.skips_turn(game_turn.current_turn)) {
+            continue;
+        }
+        let here = (position.x, position.y);
+        if (here.0 - player_tile.0).abs() + (here.1 - player_tile.1).abs() <= 1 {
+            let attack = stats.map_or(HOSTILE_NPC_ATTACK, |stats| stats.attack);
+            player_health.take_damage(attack);
+            noise_events.send(NoiseEvent { x: here.0, y: here.1, kind: NoiseKind::Combat });
+            damage_events.send(EntityDamaged { target: player_entity, amount: attack, source: npc.name.clone() });
+            message_log.add_message(format!("{} attacks you for {} damage", npc.name, attack));
+            if player_health.is_dead() {
+                message_log.add_message("You have been slain...".to_string());
+                break;
+            }
+            continue;
+        }
+
+        let sees_player = (here.0 - player_tile.0).abs().max((here.1 - player_tile.1).abs()) <= HOSTILE_NPC_SIGHT_RANGE
+            && line_of_sight(&map, here, player_tile);
+        if !sees_player {
+            continue;
+        }
+        let next = path_maps.to_player.downhill_from(here.0, here.1);
+        if let Some((x, y)) = next.filter(|&tile| tile != player_tile && !taken.contains(&tile)) {
+            taken.remove(&here);
+            taken.insert((x, y));
+            position.x = x;
+            position.y = y;
+            transform.translation.x = x as f32 * TILE_SIZE + (TILE_SIZE / 2.0);
+            transform.translation.y = y as f32 * TILE_SIZE + (TILE_SIZE / 2.0);
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use bevy::core::FrameCount;
+use bevy::prelude::*;
+use bevy::window::PresentMode;
+use bevy::winit::{UpdateMode, WinitSettings};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// Where the frame pacing settings are read from, under assets/
+pub const FRAME_SETTINGS_PATH: &str = "data/frame_timing.json";
+
+/// How often the simulation ticks and how frames are paced, from assets/data/frame_timing.json.
+/// `--no-vsync` and `--fps-limit N` override the file. Rest pacing is the only turn logic on the fixed tick.
+/// Everything else that resolves a turn (movement, creature AI, combat, status ticks) stays in Update on
+/// purpose: it runs once per `GameTurn`, not per frame or per second, so it already plays out the same at any
+/// frame rate, and the events it trades with input and UI systems would be dropped on frames with no tick
+#[derive(Resource, Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct FrameSettings {
+    pub simulation_hz: f64,     // Fixed ticks per second for rest pacing
+    pub vsync: bool,
+    pub fps_limit: Option<f64>, // Frames per second to cap at; see `winit_settings`
+}
+
+impl Default for FrameSettings {
+    fn default() -> Self {
+        Self { simulation_hz: 20.0, vsync: true, fps_limit: None }
+    }
+}
+
+impl FrameSettings {
+    // Read the settings, falling back to the defaults if the file is missing or broken
+    pub fn load() -> Self {
+        let path = Path::new("assets").join(FRAME_SETTINGS_PATH);
+        let mut settings: Self = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                eprintln!("Could not parse frame settings {}: {}", path.display(), e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        };
+
+        let args: Vec<String> = std::env::args().collect();
+        if args.iter().any(|arg| arg == "--no-vsync") {
+            settings.vsync = false;
+        }
+        if let Some(i) = args.iter().position(|arg| arg == "--fps-limit") {
+            match args.get(i + 1).and_then(|value| value.parse::<f64>().ok()) {
+                Some(limit) => settings.fps_limit = Some(limit),
+                None => eprintln!("--fps-limit needs a number of frames per second"),
+            }
+        }
+
+        if settings.simulation_hz.is_nan() || settings.simulation_hz <= 0.0 {
+            eprintln!("simulation_hz must be above zero, using {}", Self::default().simulation_hz);
+            settings.simulation_hz = Self::default().simulation_hz;
+        }
+        settings.fps_limit = settings.fps_limit.filter(|&limit| limit > 0.0);
+        settings
+    }
+
+    pub fn present_mode(&self) -> PresentMode {
+        if self.vsync { PresentMode::AutoVsync } else { PresentMode::AutoNoVsync }
+    }
+
+    // How winit schedules frames. With a limit the app waits in the event loop for up to a frame's budget
+    // instead of running flat out; input arriving in the meantime still wakes it at once
+    pub fn winit_settings(&self) -> WinitSettings {
+        let mut settings = WinitSettings::game();
+        if let Some(limit) = self.fps_limit {
+            let wait = Duration::from_secs_f64(1.0 / limit);
+            settings.focused_mode = UpdateMode::ReactiveLowPower { wait };
+            settings.unfocused_mode = UpdateMode::ReactiveLowPower { wait };
+        }
+        settings
+    }
+
+    // The fixed clock rest pacing ticks on
+    pub fn fixed_time(&self) -> Time<Fixed> {
+        Time::<Fixed>::from_hz(self.simulation_hz)
+    }
+}
+
+// How far to move toward a target this frame when closing `rate` of the gap per second,
+// so smoothing comes out the same however long the frame took
+pub fn smoothing(rate: f32, delta_seconds: f32) -> f32 {
+    1.0 - (-rate * delta_seconds).exp()
+}
+
+// Whether a fixed tick is the first one this frame. When frames run long FixedUpdate catches up with
+// several ticks in a row; turn logic takes only one of them, so creatures get a frame to answer each turn
+pub fn first_tick_this_frame(frame: &FrameCount, last_frame: &mut Option<u32>) -> bool {
+    if *last_frame == Some(frame.0) {
+        return false;
+    }
+    *last_frame = Some(frame.0);
+    true
+}
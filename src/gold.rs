@@ -0,0 +1,232 @@
+use bevy::prelude::*;
+use rand::Rng;
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::assets::{get_item_sprite, SpriteAssets, TextureAtlases};
+use crate::components::{Player, Position};
+use crate::events::TileEntered;
+use crate::input::TILE_SIZE;
+use crate::map::{TileMap, TileType};
+use crate::rng::RngStream;
+use crate::run_summary::RunStats;
+use crate::ui::MessageLog;
+
+const COIN_SOUND_PATH: &str = "audio/coins.ogg";
+// Piles lie with the props, above the floor and below creatures
+const TREASURE_Z: f32 = 2.5;
+// Chance a room holds a pile, growing with depth up to a cap
+const TREASURE_CHANCE: f64 = 0.2;
+const TREASURE_CHANCE_PER_LEVEL: f64 = 0.02;
+const MAX_TREASURE_CHANCE: f64 = 0.5;
+// Mixed into the level seed so piles don't land where the props do
+const TREASURE_SALT: u64 = 0x676f_6c64;
+
+/// The gold the player is carrying
+#[derive(Resource, Debug, Default)]
+pub struct Purse {
+    pub gold: u32,
+}
+
+impl Purse {
+    pub fn add(&mut self, amount: u32) {
+        self.gold += amount;
+    }
+
+    // Pay out if there's enough; returns whether it was paid
+    pub fn spend(&mut self, amount: u32) -> bool {
+        if self.gold < amount {
+            return false;
+        }
+        self.gold -= amount;
+        true
+    }
+}
+
+/// Piles already picked up, so revisiting a level doesn't refill them
+#[derive(Resource, Debug, Default)]
+pub struct CollectedTreasure(pub HashSet<(usize, (usize, usize))>);
+
+/// Coins lying on the floor, taken by walking over them
+#[derive(Component, Debug)]
+pub struct TreasurePile {
+    pub amount: u32,
+    pub dropped: bool, // Spilled by a creature rather than placed with the level
+}
+
+/// Sent when gold is found, wherever it came from
+#[derive(Event, Debug, Clone, Copy)]
+pub struct GoldCollected {
+    pub amount: u32,
+}
+
+#[derive(Component)]
+pub struct GoldHudText;
+
+// How much gold a find holds at a given depth
+pub fn roll_gold(depth: usize, rng: &mut impl Rng) -> u32 {
+    rng.gen_range(3..=8) + depth as u32 * 2
+}
+
+// Spawn a pile of coins on a tile
+pub fn spawn_treasure_pile(
+    commands: &mut Commands,
+    texture_atlases: &TextureAtlases,
+    sprite_assets: &SpriteAssets,
+    tile: (i32, i32),
+    amount: u32,
+    dropped: bool,
+) {
+    // There's no coin sprite; a gold ring glints in its place
+    commands.spawn((
+        SpriteSheetBundle {
+            texture_atlas: texture_atlases.items.clone(),
+            sprite: TextureAtlasSprite {
+                index: get_item_sprite(sprite_assets, "gold signet ring"),
+                ..default()
+            },
+            transform: Transform::from_xyz(
+                tile.0 as f32 * TILE_SIZE + TILE_SIZE / 2.0,
+                tile.1 as f32 * TILE_SIZE + TILE_SIZE / 2.0,
+                TREASURE_Z,
+            ),
+            ..default()
+        },
+        Position::new(tile.0, tile.1),
+        TreasurePile { amount, dropped },
+    ));
+}
+
+// Where this level's piles go, and how much each holds; the same level always gives the same piles
+fn place_treasure(map: &TileMap) -> Vec<((usize, usize), u32)> {
+    let mut rng = RngStream::Spawns.seeded(map.seed ^ TREASURE_SALT);
+    let depth = map.current_level;
+    let chance = (TREASURE_CHANCE + TREASURE_CHANCE_PER_LEVEL * depth as f64).min(MAX_TREASURE_CHANCE);
+
+    let mut piles = Vec::new();
+    for room in &map.rooms {
+        if room.width < 3 || room.height < 3 || !rng.gen_bool(chance) {
+            continue;
+        }
+        let tile = (rng.gen_range(room.x + 1..room.x + room.width - 1), rng.gen_range(room.y + 1..room.y + room.height - 1));
+        let amount = roll_gold(depth, &mut rng);
+        let taken = tile == map.spawn_position
+            || map.down_stairs_pos == Some(tile)
+            || map.up_stairs_pos == Some(tile)
+            || map.chest_positions.contains(&tile)
+            || map.altar_positions.contains(&tile);
+        if map.tiles[tile.1][tile.0] == TileType::Floor && !taken {
+            piles.push((tile, amount));
+        }
+    }
+    piles
+}
+
+// System to start each run with an empty purse
+pub fn reset_purse(mut purse: ResMut<Purse>, mut collected: ResMut<CollectedTreasure>) {
+    purse.gold = 0;
+    collected.0.clear();
+}
+
+// System to lay out the treasure piles whenever a different level is loaded
+pub fn sync_treasure_piles(
+    mut commands: Commands,
+    map: Res<TileMap>,
+    texture_atlases: Res<TextureAtlases>,
+    sprite_assets: Res<SpriteAssets>,
+    collected: Res<CollectedTreasure>,
+    pile_query: Query<Entity, With<TreasurePile>>,
+) {
+    for entity in pile_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    for (tile, amount) in place_treasure(&map) {
+        if !collected.0.contains(&(map.current_level, tile)) {
+            spawn_treasure_pile(&mut commands, &texture_atlases, &sprite_assets, (tile.0 as i32, tile.1 as i32), amount, false);
+        }
+    }
+}
+
+// System to scoop up any pile the player steps onto
+pub fn pick_up_treasure_system(
+    mut commands: Commands,
+    mut tile_events: EventReader<TileEntered>,
+    map: Res<TileMap>,
+    player_query: Query<Entity, With<Player>>,
+    pile_query: Query<(Entity, &Position, &TreasurePile)>,
+    mut collected: ResMut<CollectedTreasure>,
+    mut message_log: ResMut<MessageLog>,
+    mut gold_events: EventWriter<GoldCollected>,
+) {
+    let player = if let Ok(player) = player_query.get_single() { player } else { return; };
+    for entered in tile_events.read().filter(|entered| entered.entity == player) {
+        for (entity, pos, pile) in pile_query.iter() {
+            if (pos.x, pos.y) != (entered.x, entered.y) {
+                continue;
+            }
+            commands.entity(entity).despawn();
+            if !pile.dropped {
+                collected.0.insert((map.current_level, (pos.x as usize, pos.y as usize)));
+            }
+            message_log.add_message(format!("You pick up {} gold", pile.amount));
+            gold_events.send(GoldCollected { amount: pile.amount });
+        }
+    }
+}
+
+// System to put found gold in the purse, with a chime
+pub fn collect_gold_system(
+    mut commands: Commands,
+    mut gold_events: EventReader<GoldCollected>,
+    asset_server: Res<AssetServer>,
+    mut purse: ResMut<Purse>,
+    mut run_stats: ResMut<RunStats>,
+) {
+    let mut found = 0;
+    for event in gold_events.read() {
+        found += event.amount;
+    }
+    if found == 0 {
+        return;
+    }
+    purse.add(found);
+    // Everything found counts toward the score, even if it's spent later
+    run_stats.gold += found;
+
+    if Path::new("assets").join(COIN_SOUND_PATH).exists() {
+        commands.spawn(AudioBundle {
+            source: asset_server.load(COIN_SOUND_PATH),
+            settings: PlaybackSettings::DESPAWN,
+        });
+    }
+}
+
+// Show the purse under the spell bar
+pub fn setup_gold_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/FiraSans-Light.ttf"),
+                font_size: 18.0,
+                color: Color::GOLD,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(34.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+        GoldHudText,
+    ));
+}
+
+pub fn update_gold_hud(purse: Res<Purse>, mut text_query: Query<&mut Text, With<GoldHudText>>) {
+    if !purse.is_changed() {
+        return;
+    }
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = format!("Gold: {}", purse.gold);
+    }
+}
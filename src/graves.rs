@@ -0,0 +1,170 @@
+use bevy::prelude::*;
+
+use crate::animals::place_companions_near;
+use crate::assets::{get_tile_sprite, SpriteAssets, TextureAtlases};
+use crate::combat::Health;
+use crate::components::{AnimalAnimation, Companion, Player, PlayerAnimation, Position};
+use crate::corpses::Corpse;
+use crate::events::PlayerMoved;
+use crate::input::TILE_SIZE;
+use crate::interaction::{Interactable, InteractionKind};
+use crate::inventory::{Inventory, ItemKind};
+use crate::level::move_player_to;
+use crate::map::TileMap;
+use crate::status::StatusEffects;
+use crate::ui::MessageLog;
+
+// Graves lie with the props, above the floor and below the living
+const GRAVE_Z: f32 = 2.0;
+const GRAVE_TINT: Color = Color::rgb(0.75, 0.85, 1.0);
+const GRAVE_SPRITE: &str = "corpse (bones) 1";
+
+/// What happens when the player's health runs out
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeathMode {
+    #[default]
+    Permadeath, // The run ends
+    SoftDeath,  // Wake at the level's up stairs; everything carried is left in a grave where you fell
+}
+
+impl DeathMode {
+    // `--soft-death` turns off permadeath
+    pub fn from_args() -> Self {
+        if std::env::args().any(|arg| arg == "--soft-death") {
+            DeathMode::SoftDeath
+        } else {
+            DeathMode::Permadeath
+        }
+    }
+}
+
+/// Everything the player dropped on one death, waiting to be recovered
+#[derive(Debug, Clone)]
+pub struct GraveRecord {
+    pub level: usize,
+    pub tile: (i32, i32),
+    pub items: Vec<ItemKind>,
+}
+
+/// The player's graves this run, on every level
+#[derive(Resource, Debug, Default)]
+pub struct Graves {
+    pub graves: Vec<GraveRecord>,
+}
+
+/// Marks the grave entity for the record at this index
+#[derive(Component, Debug)]
+pub struct Grave {
+    pub index: usize,
+}
+
+// Start each run with no graves
+pub fn reset_graves(mut graves: ResMut<Graves>) {
+    graves.graves.clear();
+}
+
+fn spawn_grave(
+    commands: &mut Commands,
+    texture_atlases: &TextureAtlases,
+    sprite_assets: &SpriteAssets,
+    index: usize,
+    record: &GraveRecord,
+) {
+    let translation = Vec3::new(
+        record.tile.0 as f32 * TILE_SIZE + TILE_SIZE / 2.0,
+        record.tile.1 as f32 * TILE_SIZE + TILE_SIZE / 2.0,
+        GRAVE_Z,
+    );
+    // A grave is searched like any body, so looting goes through the corpse system
+    commands.spawn((
+        SpriteSheetBundle {
+            texture_atlas: texture_atlases.tiles.clone(),
+            sprite: TextureAtlasSprite {
+                index: get_tile_sprite(sprite_assets, GRAVE_SPRITE),
+                color: GRAVE_TINT,
+                ..default()
+            },
+            transform: Transform::from_translation(translation),
+            ..default()
+        },
+        Position::new(record.tile.0, record.tile.1),
+        Interactable::new(InteractionKind::Loot, "Search your grave".to_string()),
+        Corpse { name: "your grave".to_string(), items: record.items.clone() },
+        Grave { index },
+    ));
+}
+
+// System to catch the player as they die in soft death mode: bury what they carried and wake them at the up stairs.
+// Runs before the death check so the run never sees them dead
+pub fn soft_death_system(
+    mut commands: Commands,
+    death_mode: Res<DeathMode>,
+    map: Res<TileMap>,
+    mut graves: ResMut<Graves>,
+    texture_atlases: Res<TextureAtlases>,
+    sprite_assets: Res<SpriteAssets>,
+    mut player_query: Query<(Entity, &mut Health, &mut Inventory, &mut Transform, &mut Position, &mut PlayerAnimation), With<Player>>,
+    mut companion_query: Query<(Entity, &mut Transform, &mut AnimalAnimation), (With<Companion>, Without<Player>)>,
+    mut moved_events: EventWriter<PlayerMoved>,
+    mut message_log: ResMut<MessageLog>,
+) {
+    if *death_mode != DeathMode::SoftDeath {
+        return;
+    }
+    let (entity, mut health, mut inventory, mut transform, mut position, mut animation) =
+        if let Ok(player) = player_query.get_single_mut() { player } else { return; };
+    if !health.is_dead() {
+        return;
+    }
+
+    if !inventory.items.is_empty() {
+        let record = GraveRecord {
+            level: map.current_level,
+            tile: (position.x, position.y),
+            items: std::mem::take(&mut inventory.items),
+        };
+        spawn_grave(&mut commands, &texture_atlases, &sprite_assets, graves.graves.len(), &record);
+        graves.graves.push(record);
+    }
+
+    let stairs = map.up_stairs_pos.unwrap_or(map.spawn_position);
+    animation.is_moving = false;
+    animation.move_buffer.clear();
+    move_player_to(&mut transform, &mut position, stairs);
+    // Overrides the Position a queued step may have just inserted
+    commands.entity(entity).insert(*position).remove::<StatusEffects>();
+    health.current = health.max;
+    place_companions_near(&mut commands, &mut companion_query, &map, (position.x, position.y));
+
+    moved_events.send(PlayerMoved { x: position.x, y: position.y });
+    message_log.add_message("Everything goes dark... You wake by the stairs, your pack gone. It lies in a grave where you fell.".to_string());
+    println!("Player died on level {} and woke at {:?}", map.current_level, stairs);
+}
+
+// System to put the graves back whenever a different level is loaded
+pub fn sync_graves(
+    mut commands: Commands,
+    map: Res<TileMap>,
+    graves: Res<Graves>,
+    texture_atlases: Res<TextureAtlases>,
+    sprite_assets: Res<SpriteAssets>,
+    grave_query: Query<Entity, With<Grave>>,
+) {
+    for entity in grave_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    for (index, record) in graves.graves.iter().enumerate() {
+        if record.level == map.current_level && !record.items.is_empty() {
+            spawn_grave(&mut commands, &texture_atlases, &sprite_assets, index, record);
+        }
+    }
+}
+
+// System to empty a grave's record once it has been searched, so it isn't laid out again
+pub fn record_looted_graves(mut graves: ResMut<Graves>, grave_query: Query<(&Grave, &Corpse), Changed<Corpse>>) {
+    for (grave, corpse) in grave_query.iter() {
+        if let Some(record) = graves.graves.get_mut(grave.index) {
+            record.items = corpse.items.clone();
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use bevy::prelude::*;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::components::{Companion, Player, Position};
+use crate::map::{TileMap, TileType};
+use crate::stealth::Sneaking;
+
+// Cost for sound to cross a tile; walls and doors muffle it
+const OPEN_STEP_COST: i32 = 1;
+const DOOR_STEP_COST: i32 = 3;
+const WALL_STEP_COST: i32 = 6;
+// How many turns a creature keeps heading for a noise before giving up on it
+const HEARING_MEMORY_TURNS: u32 = 8;
+
+/// What made a noise; louder noises carry further
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoiseKind {
+    Footsteps,
+    Sneaking, // Footsteps taken carefully
+    Chest,  // Prying a chest open
+    Combat, // Attacks, bolts and spells
+}
+
+impl NoiseKind {
+    pub fn volume(&self) -> i32 {
+        match self {
+            NoiseKind::Footsteps => 4,
+            NoiseKind::Sneaking => 2,
+            NoiseKind::Chest => 8,
+            NoiseKind::Combat => 12,
+        }
+    }
+}
+
+/// A noise made somewhere on the map this turn
+#[derive(Event, Debug, Clone, Copy)]
+pub struct NoiseEvent {
+    pub x: i32,
+    pub y: i32,
+    pub kind: NoiseKind,
+}
+
+/// A creature that can hear, and the noise it's currently investigating
+#[derive(Component, Debug, Clone, Default)]
+pub struct Hearing {
+    pub heard: Option<(i32, i32)>,
+    pub turns_left: u32,
+}
+
+impl Hearing {
+    // Count down a turn spent investigating, and forget the noise once there or out of patience
+    pub fn tick(&mut self, position: &Position) {
+        if self.heard == Some((position.x, position.y)) || self.turns_left == 0 {
+            self.heard = None;
+        }
+        self.turns_left = self.turns_left.saturating_sub(1);
+    }
+}
+
+fn step_cost(map: &TileMap, x: i32, y: i32) -> i32 {
+    match map.tiles[y as usize][x as usize] {
+        TileType::Wall | TileType::SecretDoor => WALL_STEP_COST,
+        TileType::Door => DOOR_STEP_COST,
+        TileType::Floor | TileType::StairsDown | TileType::StairsUp => OPEN_STEP_COST,
+    }
+}
+
+// How loud a noise is on every tile it reaches, spreading around corners and
+// losing volume as it goes; tiles it never reaches are missing
+pub fn loudness_map(map: &TileMap, x: i32, y: i32, volume: i32) -> HashMap<(i32, i32), i32> {
+    let mut loudness = HashMap::new();
+    if !map.in_bounds(x, y) {
+        return loudness;
+    }
+
+    // Dijkstra outwards from the source, highest loudness first
+    let mut frontier = BinaryHeap::new();
+    loudness.insert((x, y), volume);
+    frontier.push((volume, Reverse((x, y))));
+
+    while let Some((level, Reverse((cx, cy)))) = frontier.pop() {
+        if loudness.get(&(cx, cy)).map_or(false, |&best| best > level) {
+            continue;
+        }
+        for (dx, dy) in [(0, 1), (1, 0), (0, -1), (-1, 0)] {
+            let (nx, ny) = (cx + dx, cy + dy);
+            if !map.in_bounds(nx, ny) {
+                continue;
+            }
+            let next = level - step_cost(map, nx, ny);
+            if next <= 0 || loudness.get(&(nx, ny)).map_or(false, |&best| best >= next) {
+                continue;
+            }
+            loudness.insert((nx, ny), next);
+            frontier.push((next, Reverse((nx, ny))));
+        }
+    }
+
+    loudness
+}
+
+// System to make a little noise every time the player steps somewhere, less when sneaking
+pub fn footstep_noise_system(
+    player_query: Query<&Position, (With<Player>, Changed<Position>)>,
+    sneaking: Res<Sneaking>,
+    mut noise_events: EventWriter<NoiseEvent>,
+) {
+    if let Ok(position) = player_query.get_single() {
+        let kind = if sneaking.0 { NoiseKind::Sneaking } else { NoiseKind::Footsteps };
+        noise_events.send(NoiseEvent { x: position.x, y: position.y, kind });
+    }
+}
+
+// System to send creatures that hear a noise off to investigate it
+pub fn hear_noises_system(
+    mut noise_events: EventReader<NoiseEvent>,
+    map: Res<TileMap>,
+    mut listener_query: Query<(&Position, &mut Hearing), Without<Companion>>,
+) {
+    for noise in noise_events.read() {
+        let loudness = loudness_map(&map, noise.x, noise.y, noise.kind.volume());
+        for (position, mut hearing) in listener_query.iter_mut() {
+            if loudness.contains_key(&(position.x, position.y)) {
+                hearing.heard = Some((noise.x, noise.y));
+                hearing.turns_left = HEARING_MEMORY_TURNS;
+            }
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use bevy::prelude::*;
+
+use crate::camera::{CameraAction, CameraBindings};
+
+// Lines of text per column before the overlay starts another
+const ROWS_PER_COLUMN: usize = 22;
+
+/// Groups the controls are listed under on the help overlay
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindingCategory {
+    Movement,
+    Interaction,
+    Camera,
+    Debug,
+}
+
+impl BindingCategory {
+    pub const ALL: [BindingCategory; 4] = [
+        BindingCategory::Movement,
+        BindingCategory::Interaction,
+        BindingCategory::Camera,
+        BindingCategory::Debug,
+    ];
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            BindingCategory::Movement => "Movement",
+            BindingCategory::Interaction => "Interaction",
+            BindingCategory::Camera => "Camera",
+            BindingCategory::Debug => "Debug",
+        }
+    }
+}
+
+/// One control: the keys that trigger it and what it does
+#[derive(Debug, Clone)]
+pub struct KeyBinding {
+    pub category: BindingCategory,
+    pub keys: String,
+    pub action: &'static str,
+}
+
+impl KeyBinding {
+    fn new(category: BindingCategory, keys: &str, action: &'static str) -> Self {
+        Self { category, keys: keys.to_string(), action }
+    }
+}
+
+/// Every control in the game, for the help overlay. Camera rows follow `CameraBindings`, so rebinding shows up here
+#[derive(Resource, Debug, Clone)]
+pub struct KeyBindings {
+    pub bindings: Vec<KeyBinding>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        use BindingCategory::*;
+        let mut bindings = vec![
+            KeyBinding::new(Movement, "WASD / Arrows", "Move, or attack what's in the way"),
+            KeyBinding::new(Movement, "Shift + direction", "Run until something interesting happens"),
+            KeyBinding::new(Movement, "O", "Explore automatically"),
+            KeyBinding::new(Movement, "Ctrl+S / Ctrl+W", "Take the stairs down / up"),
+            KeyBinding::new(Movement, "Q", "Sneak"),
+            KeyBinding::new(Movement, ".", "Wait a turn"),
+            KeyBinding::new(Movement, "R", "Rest until healed"),
+            KeyBinding::new(Movement, "Z", "Search for secret doors"),
+            KeyBinding::new(Movement, "B", "Travel to a bookmark on this floor (again for the next)"),
+            KeyBinding::new(Interaction, "E", "Talk, open, loot, read or use what's next to you"),
+            KeyBinding::new(Interaction, "1-9", "Pick a reply or a target from a menu"),
+            KeyBinding::new(Interaction, "I", "Inventory"),
+            KeyBinding::new(Interaction, "X", "Throw the selected item (from the inventory)"),
+            KeyBinding::new(Interaction, "F", "Aim a ranged attack"),
+            KeyBinding::new(Interaction, "1-5, C", "Choose and cast a spell"),
+            KeyBinding::new(Interaction, "T", "Tame an animal"),
+            KeyBinding::new(Interaction, "V / Right stick", "Look around with a cursor"),
+            KeyBinding::new(Interaction, "X / Shift+X", "Bookmark and name a tile, or rename it / remove it"),
+            KeyBinding::new(Interaction, "Backspace", "Back out of a menu, aim or look"),
+            KeyBinding::new(Interaction, "Tab", "Depth map, or dismiss a tutorial tip"),
+            KeyBinding::new(Interaction, "F1 / ?", "This help"),
+        ];
+        bindings.extend(camera_rows(&CameraBindings::default()));
+        bindings.extend([
+            KeyBinding::new(Debug, "F3", "Debug overlay (click an entity to inspect it)"),
+            KeyBinding::new(Debug, "G / Shift+G", "Grid lines / tile overlays"),
+            KeyBinding::new(Debug, "Shift+T", "Turn counter"),
+            KeyBinding::new(Debug, "Shift+R", "Regenerate the map"),
+            KeyBinding::new(Debug, "F6", "Screen-space speech and tooltips"),
+            KeyBinding::new(Debug, "F7 / F8", "Colorblind palette / high-contrast outlines"),
+            KeyBinding::new(Debug, "F9", "Export the map as text"),
+            KeyBinding::new(Debug, "F10", "Load the hand-authored map"),
+            KeyBinding::new(Debug, "F12 / Shift+F12", "Screenshot / whole-level image"),
+            KeyBinding::new(Debug, "Ctrl+L", "Export the run log"),
+        ]);
+        Self { bindings }
+    }
+}
+
+impl KeyBindings {
+    // Swap in the camera's current bindings, keeping the rows where they were
+    pub fn refresh_camera(&mut self, camera: &CameraBindings) {
+        let position = self.bindings.iter().position(|binding| binding.category == BindingCategory::Camera);
+        self.bindings.retain(|binding| binding.category != BindingCategory::Camera);
+        let index = position.unwrap_or(self.bindings.len());
+        self.bindings.splice(index..index, camera_rows(camera));
+    }
+}
+
+fn camera_rows(camera: &CameraBindings) -> Vec<KeyBinding> {
+    let mut rows: Vec<KeyBinding> = CameraAction::ALL
+        .iter()
+        .map(|action| KeyBinding { category: BindingCategory::Camera, keys: camera.describe(*action), action: action.get_name() })
+        .collect();
+    rows.push(KeyBinding::new(BindingCategory::Camera, "Mouse wheel", "Zoom"));
+    rows
+}
+
+/// The help overlay, while it's open
+#[derive(Component)]
+pub struct HelpOverlay;
+
+// System to keep the camera rows in step with any rebinding
+pub fn sync_key_bindings(camera: Res<CameraBindings>, mut key_bindings: ResMut<KeyBindings>) {
+    if camera.is_changed() {
+        key_bindings.refresh_camera(&camera);
+    }
+}
+
+// The overlay's text, one column per run of ROWS_PER_COLUMN lines, categories in order
+fn help_columns(key_bindings: &KeyBindings) -> Vec<Vec<(String, bool)>> {
+    let mut lines = Vec::new();
+    for category in BindingCategory::ALL {
+        lines.push((category.get_name().to_string(), true));
+        for binding in key_bindings.bindings.iter().filter(|binding| binding.category == category) {
+            lines.push((format!("{:<18} {}", binding.keys, binding.action), false));
+        }
+        lines.push((String::new(), false));
+    }
+    lines.chunks(ROWS_PER_COLUMN).map(|column| column.to_vec()).collect()
+}
+
+// System to open and close the help overlay with F1 or ?
+pub fn toggle_help_overlay(
+    mut commands: Commands,
+    keyboard: Res<Input<KeyCode>>,
+    asset_server: Res<AssetServer>,
+    key_bindings: Res<KeyBindings>,
+    overlay_query: Query<Entity, With<HelpOverlay>>,
+) {
+    let question_mark = keyboard.pressed(KeyCode::ShiftLeft) && keyboard.just_pressed(KeyCode::Slash);
+    let toggled = keyboard.just_pressed(KeyCode::F1) || question_mark;
+    let closed = !overlay_query.is_empty() && keyboard.just_pressed(KeyCode::Back);
+    if !toggled && !closed {
+        return;
+    }
+    if !overlay_query.is_empty() {
+        for entity in overlay_query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+
+    let font = asset_server.load("fonts/FiraSans-Light.ttf");
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(8.0),
+                left: Val::Percent(8.0),
+                width: Val::Percent(84.0),
+                padding: UiRect::all(Val::Px(16.0)),
+                column_gap: Val::Px(32.0),
+                ..default()
+            },
+            background_color: BackgroundColor(Color::rgba(0.0, 0.0, 0.0, 0.88)),
+            z_index: ZIndex::Global(200),
+            ..default()
+        },
+        HelpOverlay,
+    ))
+    .with_children(|parent| {
+        for column in help_columns(&key_bindings) {
+            let sections: Vec<TextSection> = column
+                .into_iter()
+                .map(|(line, heading)| {
+                    TextSection::new(
+                        format!("{}\n", line),
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: if heading { 20.0 } else { 16.0 },
+                            color: if heading { Color::GOLD } else { Color::WHITE },
+                        },
+                    )
+                })
+                .collect();
+            parent.spawn(TextBundle::from_sections(sections));
+        }
+    });
+}
@@ -0,0 +1,170 @@
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::assets::{atlas_layout, sprite_assets_from_manifest, SpriteAssets, TextureAtlases};
+use crate::biome::{BiomeManager, TileWalkability};
+use crate::components::Tile;
+use crate::manifest::{AssetManifest, MANIFEST_PATH};
+use crate::map::{TileMap, TilePos, TileType};
+use crate::tile_animation::TileAnimation;
+
+// How often the manifest is checked for edits
+const POLL_INTERVAL: f32 = 1.0;
+
+/// State for polling the asset manifest for changes
+pub struct ManifestWatch {
+    timer: Timer,
+    last_modified: Option<SystemTime>,
+}
+
+impl Default for ManifestWatch {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(POLL_INTERVAL, TimerMode::Repeating),
+            last_modified: None,
+        }
+    }
+}
+
+fn manifest_modified() -> Option<SystemTime> {
+    fs::metadata(Path::new("assets").join(MANIFEST_PATH))
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+// Invert a name -> index map so existing sprites can be looked up by what they showed
+fn names_by_index(sprites: &HashMap<String, usize>) -> HashMap<usize, String> {
+    // Skip the generic aliases so a tile maps back to its real sprite name
+    sprites.iter()
+        .filter(|(name, _)| !matches!(name.as_str(), "wall" | "floor" | "door"))
+        .map(|(name, &index)| (index, name.clone()))
+        .collect()
+}
+
+// Where a sprite's index lands in the new manifest, if its name still exists
+fn remap(index: usize, old_names: &HashMap<usize, String>, new_sprites: &HashMap<String, usize>) -> Option<usize> {
+    old_names.get(&index).and_then(|name| new_sprites.get(name)).copied()
+}
+
+// Pick a fresh sprite for a tile whose old sprite is gone from the manifest
+fn repick_tile_sprite(biome_manager: &BiomeManager, map: &TileMap, tile: &Tile, pos: &TilePos) -> Option<usize> {
+    let (x, y) = (pos.x as usize, pos.y as usize);
+    // Which variant a tile is drawn with doesn't affect play, so this doesn't draw from the run's seed
+    let mut rng = rand::thread_rng();
+    let tile_info = match tile.tile_type {
+        TileType::Wall | TileType::SecretDoor => biome_manager.get_wall_tile_for_position(tile.biome, x, y, map, &mut rng),
+        TileType::Floor => biome_manager.get_varied_floor_tile(tile.biome, x, y, &mut rng),
+        TileType::Door => biome_manager.get_door_tile(tile.biome),
+        TileType::StairsDown => biome_manager.get_stairs_down_tile(tile.biome),
+        TileType::StairsUp => biome_manager.get_stairs_up_tile(tile.biome),
+    }?;
+
+    // Never swap in a sprite that reads as a different kind of tile
+    if tile_info.walkability == tile.walkability || tile.tile_type == TileType::SecretDoor && tile_info.walkability == TileWalkability::Blocked {
+        Some(tile_info.sprite_index)
+    } else {
+        None
+    }
+}
+
+// System to reload the sprite manifest and biome tile tables when the manifest is edited
+pub fn hot_reload_sprite_manifest(
+    time: Res<Time>,
+    mut watch: Local<ManifestWatch>,
+    mut sprite_assets: ResMut<SpriteAssets>,
+    mut biome_manager: ResMut<BiomeManager>,
+    texture_atlases: Res<TextureAtlases>,
+    mut atlas_assets: ResMut<Assets<TextureAtlas>>,
+    asset_server: Res<AssetServer>,
+    map: Res<TileMap>,
+    mut sprite_query: Query<(&Handle<TextureAtlas>, &mut TextureAtlasSprite, Option<&Tile>, Option<&TilePos>, Option<&mut TileAnimation>)>,
+) {
+    if !watch.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let modified = manifest_modified();
+    if watch.last_modified.is_none() {
+        // First check just records the starting point
+        watch.last_modified = modified;
+        return;
+    }
+    if modified == watch.last_modified {
+        return;
+    }
+    watch.last_modified = modified;
+
+    // Keep the current assets if the edit broke something
+    let manifest = match AssetManifest::load(Path::new("assets")) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            eprintln!("Not reloading sprites: {}", e);
+            return;
+        }
+    };
+    println!("Asset manifest changed, reloading sprites");
+
+    // Grid layouts may have changed too
+    for (name, handle) in [
+        ("tiles", &texture_atlases.tiles),
+        ("characters", &texture_atlases.characters),
+        ("monsters", &texture_atlases.monsters),
+        ("items", &texture_atlases.items),
+        ("animals", &texture_atlases.animals),
+    ] {
+        if let (Some(atlas), Some(existing)) = (manifest.atlas(name), atlas_assets.get_mut(handle)) {
+            *existing = atlas_layout(atlas, &asset_server);
+        }
+    }
+
+    let new_assets = sprite_assets_from_manifest(&manifest);
+
+    // Rebuild the biome tile tables from the new names
+    *biome_manager = BiomeManager::default();
+    biome_manager.initialize_default_tiles(&new_assets.tile_sprites);
+
+    // Point every existing sprite at the same named sprite in the new manifest
+    let atlas_maps = [
+        (&texture_atlases.tiles, names_by_index(&sprite_assets.tile_sprites), &new_assets.tile_sprites),
+        (&texture_atlases.characters, names_by_index(&sprite_assets.character_sprites), &new_assets.character_sprites),
+        (&texture_atlases.monsters, names_by_index(&sprite_assets.monster_sprites), &new_assets.monster_sprites),
+        (&texture_atlases.items, names_by_index(&sprite_assets.item_sprites), &new_assets.item_sprites),
+        (&texture_atlases.animals, names_by_index(&sprite_assets.animal_sprites), &new_assets.animal_sprites),
+    ];
+
+    let mut refreshed = 0;
+    for (handle, mut sprite, tile, tile_pos, animation) in sprite_query.iter_mut() {
+        let (old_names, new_sprites) = if let Some((_, old_names, new_sprites)) = atlas_maps.iter().find(|(atlas, _, _)| *atlas == handle) {
+            (old_names, *new_sprites)
+        } else {
+            continue;
+        };
+
+        if let Some(mut animation) = animation {
+            animation.frames = animation.frames.iter()
+                .map(|&frame| remap(frame, old_names, new_sprites).unwrap_or(frame))
+                .collect();
+            sprite.index = animation.current_index();
+            refreshed += 1;
+            continue;
+        }
+
+        let new_index = remap(sprite.index, old_names, new_sprites).or_else(|| match (tile, tile_pos) {
+            (Some(tile), Some(pos)) => repick_tile_sprite(&biome_manager, &map, tile, pos),
+            _ => None,
+        });
+
+        if let Some(index) = new_index {
+            if index != sprite.index {
+                sprite.index = index;
+                refreshed += 1;
+            }
+        }
+    }
+
+    *sprite_assets = new_assets;
+    println!("Reloaded sprites, refreshed {} entities", refreshed);
+}
@@ -0,0 +1,71 @@
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use std::collections::{HashMap, HashSet};
+
+use crate::inventory::{ItemClass, ItemKind};
+use crate::rng::{GameRng, RngStream};
+
+const POTION_KINDS: [ItemKind; 4] = [ItemKind::HealingPotion, ItemKind::ManaPotion, ItemKind::Antidote, ItemKind::PoisonPotion];
+const SCROLL_KINDS: [ItemKind; 3] = [ItemKind::ScrollOfIdentify, ItemKind::ScrollOfBlessing, ItemKind::ScrollOfCursing];
+
+// Looks dealt out to the potions and scrolls; there are spares so a run can't be solved by elimination
+const POTION_LOOKS: [&str; 8] = ["murky", "fizzing", "amber", "violet", "smoky", "glowing", "bubbling", "milky"];
+const SCROLL_LABELS: [&str; 6] = ["ORN VASH", "KEL DORU", "IMMA RETH", "SOL TAVIN", "ULGAR MIST", "PRAE NOX"];
+
+// "an antidote", "a mana potion"
+pub fn with_article(name: &str) -> String {
+    let article = if name.starts_with(['a', 'e', 'i', 'o', 'u']) { "an" } else { "a" };
+    format!("{} {}", article, name)
+}
+
+/// What each potion and scroll looks like this run, and which the player has learned
+#[derive(Resource, Debug, Default)]
+pub struct ItemAppearances {
+    looks: HashMap<ItemKind, String>,
+    known: HashSet<ItemKind>,
+}
+
+impl ItemAppearances {
+    // Deal out the looks from the run seed, so the same seed hides the same things
+    pub fn new(seed: u64) -> Self {
+        let mut rng = RngStream::Spawns.seeded(seed);
+        let mut potion_looks = POTION_LOOKS.to_vec();
+        let mut scroll_labels = SCROLL_LABELS.to_vec();
+        potion_looks.shuffle(&mut rng);
+        scroll_labels.shuffle(&mut rng);
+
+        let mut looks = HashMap::new();
+        for (kind, look) in POTION_KINDS.iter().zip(potion_looks) {
+            looks.insert(*kind, format!("{} potion", look));
+        }
+        for (kind, label) in SCROLL_KINDS.iter().zip(scroll_labels) {
+            looks.insert(*kind, format!("scroll labelled {}", label));
+        }
+        Self { looks, known: HashSet::new() }
+    }
+
+    pub fn is_known(&self, item: ItemKind) -> bool {
+        item.class().is_none() || self.known.contains(&item)
+    }
+
+    // The item's real name if it has been learned, otherwise what it looks like
+    pub fn display_name(&self, item: ItemKind) -> String {
+        if self.is_known(item) {
+            return item.get_name().to_string();
+        }
+        self.looks.get(&item).cloned().unwrap_or_else(|| match item.class() {
+            Some(ItemClass::Potion) => "strange potion".to_string(),
+            _ => "strange scroll".to_string(),
+        })
+    }
+
+    // Learn what an item is; returns true if it wasn't known before
+    pub fn identify(&mut self, item: ItemKind) -> bool {
+        item.class().is_some() && self.known.insert(item)
+    }
+}
+
+// System to deal out this run's potion and scroll looks
+pub fn setup_item_appearances(mut commands: Commands, game_rng: Res<GameRng>) {
+    commands.insert_resource(ItemAppearances::new(game_rng.seed()));
+}
@@ -0,0 +1,124 @@
+use bevy::prelude::*;
+
+use crate::combat::{CombatStats, Health};
+use crate::components::{Animal, Companion, GameTurn, Npc, Player, Position};
+use crate::events::EntityDamaged;
+use crate::hearing::{NoiseEvent, NoiseKind};
+use crate::map::TileMap;
+use crate::status::StatusEffects;
+use crate::ui::MessageLog;
+use crate::visibility::line_of_sight;
+
+// Fights further off than this go unseen, however clear the view
+const WITNESS_RANGE: i32 = 8;
+
+/// Which side a creature takes in fights that don't involve the player
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CreatureFaction {
+    Predators,
+    Prey,
+    Venomous,
+    Cultists,
+}
+
+// Who goes for whom: each row attacks the columns marked true.
+// Order is Predators, Prey, Venomous, Cultists
+const HOSTILITY: [[bool; 4]; 4] = [
+    [false, true, false, true],  // Predators hunt prey and fight off cultists
+    [false, false, false, false], // Prey only ever runs
+    [true, true, false, true],   // Snakes bite whatever comes too close
+    [true, true, true, false],   // Cultists turn on every living thing but their own
+];
+
+impl CreatureFaction {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            CreatureFaction::Predators => "Predators",
+            CreatureFaction::Prey => "Prey",
+            CreatureFaction::Venomous => "Venomous",
+            CreatureFaction::Cultists => "Cultists",
+        }
+    }
+
+    // Whether creatures of this faction attack creatures of another
+    pub fn attacks(&self, other: CreatureFaction) -> bool {
+        HOSTILITY[*self as usize][other as usize]
+    }
+}
+
+/// Marks a creature killed by another creature, so it isn't counted as the player's kill
+#[derive(Component, Debug)]
+pub struct SlainByCreature;
+
+fn creature_name(npc: Option<&Npc>, animal: Option<&Animal>) -> String {
+    match (animal, npc) {
+        (Some(animal), _) => format!("the {}", animal.animal_type.get_name().to_lowercase()),
+        (None, Some(npc)) => npc.name.clone(),
+        (None, None) => "something".to_string(),
+    }
+}
+
+// System for creatures to attack hostile creatures next to them, once a turn,
+// through the same damage events as any other fight
+pub fn creature_infighting_system(
+    mut commands: Commands,
+    mut creature_query: Query<(Entity, &Position, &CreatureFaction, &mut Health, Option<&CombatStats>, Option<&Npc>, Option<&Animal>, Option<&StatusEffects>), (Without<Player>, Without<Companion>)>,
+    player_query: Query<&Position, With<Player>>,
+    map: Res<TileMap>,
+    game_turn: Res<GameTurn>,
+    mut damage_events: EventWriter<EntityDamaged>,
+    mut noise_events: EventWriter<NoiseEvent>,
+    mut message_log: ResMut<MessageLog>,
+    mut local: Local<u32>,
+) {
+    // Only fight once per turn
+    if game_turn.current_turn == 0 || game_turn.current_turn == *local {
+        return;
+    }
+    *local = game_turn.current_turn;
+
+    let player_pos = player_query.get_single().ok().copied();
+
+    // Everyone still standing at the start of the turn, and what they hit for
+    let creatures: Vec<(Entity, (i32, i32), CreatureFaction, Option<i32>, String)> = creature_query.iter()
+        .filter(|(_, _, _, health, ..)| !health.is_dead())
+        .map(|(entity, position, faction, _, stats, npc, animal, status)| {
+            let attack = stats.map(|stats| stats.attack).filter(|_| !status.map_or(false, |status| status.skips_turn(game_turn.current_turn)));
+            (entity, (position.x, position.y), *faction, attack, creature_name(npc, animal))
+        })
+        .collect();
+
+    for (attacker, (ax, ay), faction, attack, name) in &creatures {
+        let attack = if let Some(attack) = attack { *attack } else { continue; };
+        let target = creatures.iter().find(|(other, (tx, ty), other_faction, ..)| {
+            other != attacker && (tx - ax).abs() + (ty - ay).abs() == 1 && faction.attacks(*other_faction)
+        });
+        let (target, (tx, ty), _, _, target_name) = if let Some(target) = target { target } else { continue; };
+
+        // The attacker may have been killed earlier this turn, or the target already finished off
+        let attacker_alive = creature_query.get(*attacker).map_or(false, |(_, _, _, health, ..)| !health.is_dead());
+        let mut health = if let Ok((_, _, _, health, ..)) = creature_query.get_mut(*target) { health } else { continue; };
+        if !attacker_alive || health.is_dead() {
+            continue;
+        }
+
+        let killed = health.take_damage(attack);
+        damage_events.send(EntityDamaged { target: *target, amount: attack, source: name.clone() });
+        noise_events.send(NoiseEvent { x: *tx, y: *ty, kind: NoiseKind::Combat });
+        if killed {
+            commands.entity(*target).insert(SlainByCreature);
+        }
+
+        // Only fights the player can actually see make it into the log
+        let witnessed = player_pos.map_or(false, |player| {
+            (player.x - ax).abs() + (player.y - ay).abs() <= WITNESS_RANGE && line_of_sight(&map, (player.x, player.y), (*ax, *ay))
+        });
+        if witnessed {
+            let verb = if killed { "kills" } else { "attacks" };
+            let mut line = format!("{} {} {}", name, verb, target_name);
+            line[..1].make_ascii_uppercase();
+            message_log.add_message(line);
+        }
+        println!("{} ({}) attacks {} for {} damage{}", name, faction.get_name(), target_name, attack, if killed { ", killing it" } else { "" });
+    }
+}
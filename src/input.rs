@@ -1,8 +1,15 @@
 use bevy::prelude::*;
-use crate::map::{TileMap, TileType, MAP_WIDTH, MAP_HEIGHT};
+use crate::map::{TileMap, TileType};
 use crate::components::{Position, Player, Tile, MovementDirection, PlayerAnimation};
 use crate::biome::TileWalkability;
-use crate::AnimationState;
+use crate::player::AnimationState;
+use crate::conversation::Conversation;
+use crate::interaction::InteractionMenu;
+use crate::inventory_panel::InventoryMenu;
+use crate::throwing::ThrowTargeting;
+use crate::virtual_cursor::VirtualCursor;
+use crate::level_generation::LevelGeneration;
+use crate::events::MovementBlocked;
 
 #[derive(Resource, Default)]
 pub struct InputState {
@@ -18,6 +25,8 @@ pub struct InputState {
     pub continuous_movement: bool,
     pub use_stairs_down: bool,
     pub use_stairs_up: bool,
+    pub aiming: bool, // Waiting for a direction or mouse target for a ranged attack
+    pub load_custom_map: bool, // Debug: replace the level with the hand-authored map file
 }
 
 pub fn handle_input(
@@ -25,6 +34,12 @@ pub fn handle_input(
     time: Res<Time>,
     mut input_state: ResMut<InputState>,
     animation_state: Res<AnimationState>,
+    conversation: Res<Conversation>,
+    interaction_menu: Res<InteractionMenu>,
+    inventory_menu: Res<InventoryMenu>,
+    throw_targeting: Res<ThrowTargeting>,
+    virtual_cursor: Res<VirtualCursor>,
+    level_generation: Res<LevelGeneration>,
 ) {
     // Reset movement flags
     input_state.up = false;
@@ -34,6 +49,26 @@ pub fn handle_input(
     input_state.interact = false;
     input_state.attack = false;
     input_state.regenerate_map = false;
+    input_state.load_custom_map = false;
+    
+    // While a dialogue response, interaction target, item, throw or tile to look at is being picked, the movement keys belong to that;
+    // and nothing moves while the next level is still being generated
+    if conversation.awaiting_choice() || interaction_menu.is_open() || inventory_menu.open || throw_targeting.is_aiming() || virtual_cursor.is_looking() || level_generation.is_generating() {
+        input_state.continuous_movement = false;
+        input_state.use_stairs_down = false;
+        input_state.use_stairs_up = false;
+        return;
+    }
+    
+    // F toggles aiming a ranged attack; while aiming, movement keys pick a direction instead
+    if keyboard.just_pressed(KeyCode::F) {
+        input_state.aiming = !input_state.aiming;
+        println!("Aiming: {}", input_state.aiming);
+    }
+    if input_state.aiming {
+        input_state.continuous_movement = false;
+        return;
+    }
     
     // Check for movement keys - only set flags if no animation is in progress
     // or if we're handling continuous movement
@@ -112,6 +147,11 @@ pub fn handle_input(
         input_state.regenerate_map = true;
     }
     
+    // Load the hand-authored map (F10)
+    if keyboard.just_pressed(KeyCode::F10) {
+        input_state.load_custom_map = true;
+    }
+    
     // Check for stair navigation
     input_state.use_stairs_down = keyboard.pressed(KeyCode::ControlLeft) && keyboard.just_pressed(KeyCode::S);
     input_state.use_stairs_up = keyboard.pressed(KeyCode::ControlLeft) && keyboard.just_pressed(KeyCode::W);
@@ -119,12 +159,49 @@ pub fn handle_input(
 
 pub const TILE_SIZE: f32 = 32.0;
 
+/// The tile left-clicked this frame, if any. Targeting reads this rather than the mouse,
+/// so a replay can click the same tile whatever the camera is doing
+#[derive(Resource, Debug, Default)]
+pub struct TileClick {
+    pub tile: Option<(i32, i32)>,
+}
+
+// Turn a world position into the tile coordinate it falls on
+pub fn world_to_tile(world_pos: Vec2) -> (i32, i32) {
+    ((world_pos.x / TILE_SIZE).floor() as i32, (world_pos.y / TILE_SIZE).floor() as i32)
+}
+
+// The tile under the mouse cursor, if the cursor is over the window
+pub fn cursor_tile(window: &Window, camera: &Camera, camera_transform: &GlobalTransform) -> Option<(i32, i32)> {
+    window.cursor_position()
+        .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor))
+        .map(|ray| world_to_tile(ray.origin.truncate()))
+}
+
+// System to turn a left click into the tile under the cursor, before anything in Update looks at it
+pub fn read_tile_click(
+    mouse: Res<Input<MouseButton>>,
+    windows: Query<&Window>,
+    camera_q: Query<(&Camera, &GlobalTransform)>,
+    mut click: ResMut<TileClick>,
+) {
+    click.tile = None;
+    if !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    if let (Ok(window), Ok((camera, camera_transform))) = (windows.get_single(), camera_q.get_single()) {
+        click.tile = cursor_tile(window, camera, camera_transform);
+    }
+}
+
 pub fn move_player(
     mut query: Query<&mut Position, With<Player>>,
     input: Res<InputState>,
     tilemap: Res<TileMap>,
-    tile_query: Query<(&crate::map::TilePos, &Tile), Without<Player>>,
+    tile_index: Res<crate::map::TileIndex>,
+    tile_query: Query<&Tile, Without<Player>>,
     animation_state: Res<AnimationState>,
+    mut blocked_events: EventWriter<MovementBlocked>,
 ) {
     // Skip movement if an animation is in progress
     if animation_state.animation_in_progress {
@@ -145,31 +222,27 @@ pub fn move_player(
         }
 
         // Check if the new position is within bounds
-        if new_pos.x >= 0 && new_pos.x < MAP_WIDTH as i32 &&
-        new_pos.y >= 0 && new_pos.y < MAP_HEIGHT as i32 {
+        if tilemap.in_bounds(new_pos.x, new_pos.y) {
             // Default to not allowing movement unless we find a tile entity that says otherwise
             let mut can_move = false;
             
             // Check for walkability information from tile entities
             let mut found_tile = false;
-            for (tile_pos, tile) in tile_query.iter() {
-                if tile_pos.x == new_pos.x && tile_pos.y == new_pos.y {
-                    found_tile = true;
-                    // Use the tile's walkability property
-                    can_move = match tile.walkability {
-                        TileWalkability::Walkable => true,
-                        TileWalkability::Blocked => false,
-                        TileWalkability::Door => {
-                            // Doors can be walked through if the player presses the interact key
-                            if input.interact {
-                                true
-                            } else {
-                                false
-                            }
+            if let Some(tile) = tile_index.get(new_pos.x, new_pos.y).and_then(|entity| tile_query.get(entity).ok()) {
+                found_tile = true;
+                // Use the tile's walkability property
+                can_move = match tile.walkability {
+                    TileWalkability::Walkable => true,
+                    TileWalkability::Blocked => false,
+                    TileWalkability::Door => {
+                        // Doors can be walked through if the player presses the interact key
+                        if input.interact {
+                            true
+                        } else {
+                            false
                         }
-                    };
-                    break;
-                }
+                    }
+                };
             }
             
             // If no tile entity was found, fall back to the tilemap data
@@ -187,17 +260,27 @@ pub fn move_player(
                 println!("Warning: No tile entity found at ({}, {}), using tilemap data", new_pos.x, new_pos.y);
             }
             
+            // Heavy props like barrels and boulders are in the way too
+            if tilemap.prop_blocks(new_pos.x, new_pos.y) {
+                can_move = false;
+            }
+            
             // Apply the movement only if valid
             if can_move {
                 pos.x = new_pos.x;
                 pos.y = new_pos.y;
+            } else if new_pos != *pos {
+                blocked_events.send(MovementBlocked { x: new_pos.x, y: new_pos.y });
             }
+        } else if new_pos != *pos {
+            blocked_events.send(MovementBlocked { x: new_pos.x, y: new_pos.y });
         }
     }
 }
 
-// Add a new system to queue up the next movement direction
+// System to buffer movement keys pressed during a step, to be taken in order as each step lands
 pub fn queue_next_movement(
+    time: Res<Time>,
     keyboard: Res<Input<KeyCode>>,
     animation_state: Res<AnimationState>,
     mut player_query: Query<&mut PlayerAnimation, With<Player>>,
@@ -209,19 +292,19 @@ pub fn queue_next_movement(
     
     // Check for a player animation component
     if let Ok(mut animation) = player_query.get_single_mut() {
-        // Check for movement keys and queue the direction
-        if keyboard.just_pressed(KeyCode::W) || keyboard.just_pressed(KeyCode::Up) {
-            animation.queued_direction = Some(MovementDirection::Up);
-            println!("Queued UP movement");
-        } else if keyboard.just_pressed(KeyCode::S) || keyboard.just_pressed(KeyCode::Down) {
-            animation.queued_direction = Some(MovementDirection::Down);
-            println!("Queued DOWN movement");
-        } else if keyboard.just_pressed(KeyCode::A) || keyboard.just_pressed(KeyCode::Left) {
-            animation.queued_direction = Some(MovementDirection::Left);
-            println!("Queued LEFT movement");
-        } else if keyboard.just_pressed(KeyCode::D) || keyboard.just_pressed(KeyCode::Right) {
-            animation.queued_direction = Some(MovementDirection::Right);
-            println!("Queued RIGHT movement");
+        let now = time.elapsed_seconds_f64();
+        // Every key pressed this frame is buffered, so two taps landing together aren't lost
+        let keys = [
+            (KeyCode::W, KeyCode::Up, MovementDirection::Up),
+            (KeyCode::S, KeyCode::Down, MovementDirection::Down),
+            (KeyCode::A, KeyCode::Left, MovementDirection::Left),
+            (KeyCode::D, KeyCode::Right, MovementDirection::Right),
+        ];
+        for (key, arrow, direction) in keys {
+            if keyboard.just_pressed(key) || keyboard.just_pressed(arrow) {
+                animation.move_buffer.push(direction, now);
+                println!("Queued {:?} movement ({} buffered)", direction, animation.move_buffer.len());
+            }
         }
     }
 }
@@ -0,0 +1,171 @@
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use bevy::text::{Text2dBundle, TextAlignment};
+
+use crate::chests::Chest;
+use crate::components::{Animal, Companion, Npc, Player, Position, Tile};
+use crate::display_settings::DisplaySettings;
+use crate::faction::{Faction, Hostile, Reputation, Standing};
+use crate::input::{cursor_tile, TILE_SIZE};
+use crate::map::{TileIndex, TileMap};
+use crate::virtual_cursor::VirtualCursor;
+use crate::visibility::VisibilityMap;
+
+/// The floating text describing whatever is under the cursor. There's only ever one;
+/// it's moved and re-texted rather than respawned, and hidden when there's nothing to say
+#[derive(Component)]
+pub struct InspectTooltip {
+    pub shown: bool, // Has something to say; drawn in the world unless screen-space text is on
+}
+
+// How a creature regards the player, for its tooltip line
+fn disposition(faction: Option<&Faction>, hostile: bool, reputation: &Reputation) -> String {
+    match (faction, hostile) {
+        (Some(faction), true) => format!("{} - Hostile", faction.get_name()),
+        (Some(faction), false) => {
+            let standing = match reputation.standing(*faction) {
+                Standing::Hostile => "Hostile",
+                Standing::Neutral => "Neutral",
+                Standing::Friendly => "Friendly",
+            };
+            format!("{} - {}", faction.get_name(), standing)
+        }
+        (None, true) => "Hostile".to_string(),
+        (None, false) => "Neutral".to_string(),
+    }
+}
+
+// System to describe the tile, creature or NPC under the mouse cursor, or under the virtual cursor while looking
+pub fn inspect_hover_system(
+    mut commands: Commands,
+    windows: Query<&Window>,
+    camera_q: Query<(&Camera, &GlobalTransform)>,
+    map: Res<TileMap>,
+    tile_index: Res<TileIndex>,
+    tile_query: Query<&Tile>,
+    mut occupant_query: Query<(&Position, Option<&Player>, Option<&Npc>, Option<&mut Animal>, Option<&Faction>, Option<&Hostile>, Option<&Companion>, Option<&Chest>)>,
+    mut tooltip_query: Query<(&mut InspectTooltip, &mut Text, &mut Transform, &mut Visibility)>,
+    reputation: Res<Reputation>,
+    visibility_map: Res<VisibilityMap>,
+    virtual_cursor: Res<VirtualCursor>,
+    display: Res<DisplaySettings>,
+    asset_server: Res<AssetServer>,
+) {
+    let window = windows.single();
+    let (camera, camera_transform) = camera_q.single();
+    let hovered = virtual_cursor.tile
+        .or_else(|| cursor_tile(window, camera, camera_transform))
+        .filter(|&(x, y)| map.in_bounds(x, y));
+
+    // Tiles the player hasn't seen stay a mystery, and only creatures in view are named
+    let (seen, visible) = match hovered {
+        Some((x, y)) => {
+            let (x, y) = (x as usize, y as usize);
+            let lookup = |grid: &Vec<Vec<bool>>| grid.get(y).and_then(|row| row.get(x)).copied().unwrap_or(false);
+            let visible = lookup(&visibility_map.visible_tiles);
+            (visible || lookup(&visibility_map.previously_seen), visible)
+        }
+        None => (false, false),
+    };
+
+    let mut occupant_lines = Vec::new();
+    let mut creature_hovered = false;
+    for (position, player, npc, animal, faction, hostile, companion, chest) in occupant_query.iter_mut() {
+        let here = hovered == Some((position.x, position.y));
+
+        // Animals animate while hovered
+        if let Some(mut animal) = animal {
+            animal.hover = here && visible;
+            if animal.hover {
+                let role = if companion.is_some() { "Companion" } else { "Wild" };
+                occupant_lines.push(format!("{} ({})", animal.animal_type.get_name(), role));
+                creature_hovered = true;
+            }
+            continue;
+        }
+        if !here || !visible {
+            continue;
+        }
+
+        if player.is_some() {
+            occupant_lines.push("You".to_string());
+        } else if let Some(npc) = npc {
+            occupant_lines.push(npc.name.clone());
+            occupant_lines.push(disposition(faction, hostile.is_some(), &reputation));
+            creature_hovered = true;
+        } else if let Some(chest) = chest {
+            let state = if chest.opened { "opened" } else if chest.locked { "locked" } else { "closed" };
+            occupant_lines.push(format!("Chest ({})", state));
+        }
+    }
+
+    let (x, y) = if let Some(tile) = hovered.filter(|_| seen) { tile } else {
+        for (mut tooltip, _, _, mut visibility) in tooltip_query.iter_mut() {
+            if tooltip.shown {
+                tooltip.shown = false;
+            }
+            if *visibility != Visibility::Hidden {
+                *visibility = Visibility::Hidden;
+            }
+        }
+        return;
+    };
+
+    // Creatures get their own tooltip; otherwise describe the tile and whatever is on it
+    let mut lines = Vec::new();
+    if !creature_hovered {
+        let tile = tile_index.get(x, y).and_then(|entity| tile_query.get(entity).ok());
+        let tile_type = map.tiles[y as usize][x as usize];
+        let biome = map.biomes[y as usize][x as usize];
+        lines.push(format!("{} - {}", tile_type.get_name(), biome.get_name()));
+        if let Some(tile) = tile {
+            lines.push(tile.walkability.get_name().to_string());
+        }
+    }
+    lines.extend(occupant_lines);
+    let contents = lines.join("\n");
+    let translation = Vec3::new(
+        x as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+        (y + 1) as f32 * TILE_SIZE + 4.0, // Just above the hovered tile
+        15.0,
+    );
+
+    // Only touch the text when it changes, so it isn't laid out again every frame
+    // With screen-space text on, the UI copy is drawn instead
+    let wanted = if display.screen_space_text { Visibility::Hidden } else { Visibility::Visible };
+    if let Ok((mut tooltip, mut text, mut transform, mut visibility)) = tooltip_query.get_single_mut() {
+        if !tooltip.shown {
+            tooltip.shown = true;
+        }
+        if text.sections[0].value != contents {
+            text.sections[0].value = contents;
+        }
+        if transform.translation != translation {
+            transform.translation = translation;
+        }
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+        return;
+    }
+
+    // The first hover, or the level change took the old one with it
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                contents,
+                TextStyle {
+                    font: asset_server.load("fonts/FiraSans-Light.ttf"),
+                    font_size: 14.0,
+                    color: Color::WHITE,
+                },
+            )
+            .with_alignment(TextAlignment::Center),
+            transform: Transform::from_translation(translation),
+            text_anchor: Anchor::BottomCenter, // Extra lines grow upward, away from the tile
+            visibility: wanted,
+            ..default()
+        },
+        InspectTooltip { shown: true },
+    ));
+}
@@ -0,0 +1,215 @@
+use bevy::prelude::*;
+
+use crate::components::{Player, Position};
+use crate::conversation::Conversation;
+
+/// What using an interactable does; each kind is handled by its own system
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InteractionKind {
+    Talk,
+    Open,
+    Loot,
+    UseStairs,
+    Read,
+    Pray,
+}
+
+impl InteractionKind {
+    // How close the player has to be: stairs are used standing on them, everything else from next door
+    pub fn reach(&self) -> i32 {
+        match self {
+            InteractionKind::UseStairs => 0,
+            _ => 1,
+        }
+    }
+}
+
+/// Something the player can use with E; the label is how the interaction menu
+/// lists it when there's more than one thing in reach
+#[derive(Component, Debug, Clone)]
+pub struct Interactable {
+    pub kind: InteractionKind,
+    pub label: String,
+}
+
+impl Interactable {
+    pub fn new(kind: InteractionKind, label: impl Into<String>) -> Self {
+        Self { kind, label: label.into() }
+    }
+}
+
+/// Sent when the player uses an interactable with E
+#[derive(Event, Debug, Clone, Copy)]
+pub struct InteractedWith {
+    pub target: Entity,
+    pub kind: InteractionKind,
+}
+
+/// The choice offered when E is pressed with several interactables in reach
+#[derive(Resource, Default)]
+pub struct InteractionMenu {
+    pub options: Vec<(Entity, String)>,
+    pub selected: usize,
+}
+
+impl InteractionMenu {
+    // Movement and number keys pick a target instead of their usual action
+    pub fn is_open(&self) -> bool {
+        !self.options.is_empty()
+    }
+
+    fn close(&mut self) {
+        self.options.clear();
+        self.selected = 0;
+    }
+}
+
+#[derive(Component)]
+pub struct InteractionMenuPanel;
+
+#[derive(Component)]
+pub struct InteractionMenuText;
+
+fn in_reach(player: &Position, pos: &Position, kind: InteractionKind) -> bool {
+    (pos.x - player.x).abs().max((pos.y - player.y).abs()) <= kind.reach()
+}
+
+pub fn setup_interaction_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(80.0),
+                bottom: Val::Px(140.0), // Where the conversation panel goes; the two never show together
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            },
+            background_color: BackgroundColor(Color::rgba(0.0, 0.0, 0.0, 0.85)),
+            visibility: Visibility::Hidden,
+            z_index: ZIndex::Global(110),
+            ..default()
+        },
+        InteractionMenuPanel,
+    ))
+    .with_children(|parent| {
+        parent.spawn((
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/FiraSans-Light.ttf"),
+                    font_size: 16.0,
+                    color: Color::WHITE,
+                },
+            ),
+            InteractionMenuText,
+        ));
+    });
+}
+
+// System to work out what E is being used on, asking when more than one thing is in reach,
+// and hand it to whichever system handles that kind of interaction
+pub fn dispatch_interactions(
+    mut keyboard: ResMut<Input<KeyCode>>,
+    conversation: Res<Conversation>,
+    mut menu: ResMut<InteractionMenu>,
+    mut interactions: EventWriter<InteractedWith>,
+    player_query: Query<&Position, With<Player>>,
+    interactable_query: Query<(Entity, &Position, &Interactable)>,
+) {
+    let player_pos = if let Ok(pos) = player_query.get_single() { *pos } else { return; };
+
+    if menu.is_open() {
+        // Anything that has gone (or been used up) drops off the list
+        menu.options.retain(|(entity, _)| {
+            interactable_query.get(*entity).map_or(false, |(_, pos, interactable)| in_reach(&player_pos, pos, interactable.kind))
+        });
+        if !menu.is_open() || keyboard.just_pressed(KeyCode::Back) {
+            menu.close();
+            return;
+        }
+
+        let count = menu.options.len();
+        if keyboard.just_pressed(KeyCode::Up) || keyboard.just_pressed(KeyCode::W) {
+            menu.selected = (menu.selected + count - 1) % count;
+        }
+        if keyboard.just_pressed(KeyCode::Down) || keyboard.just_pressed(KeyCode::S) {
+            menu.selected = (menu.selected + 1) % count;
+        }
+        menu.selected = menu.selected.min(count - 1);
+
+        let number_keys = [
+            KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4, KeyCode::Key5,
+            KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9,
+        ];
+        let confirm_keys = [KeyCode::E, KeyCode::Space, KeyCode::Return];
+        let picked = if let Some(index) = number_keys.iter().take(count).position(|key| keyboard.just_pressed(*key)) {
+            keyboard.clear_just_pressed(number_keys[index]);
+            Some(index)
+        } else if let Some(&key) = confirm_keys.iter().find(|key| keyboard.just_pressed(**key)) {
+            keyboard.clear_just_pressed(key);
+            Some(menu.selected)
+        } else {
+            None
+        };
+
+        if let Some(index) = picked {
+            let (entity, label) = menu.options[index].clone();
+            if let Ok((_, _, interactable)) = interactable_query.get(entity) {
+                println!("Interacting with {}", label);
+                interactions.send(InteractedWith { target: entity, kind: interactable.kind });
+            }
+            menu.close();
+        }
+        return;
+    }
+
+    // Mid-conversation E moves the conversation along instead
+    if conversation.is_active() || !keyboard.just_pressed(KeyCode::E) {
+        return;
+    }
+
+    // Straight up, down, left and right come before the diagonals
+    let mut options: Vec<(i32, Entity, InteractionKind, String)> = interactable_query.iter()
+        .filter(|(_, pos, interactable)| in_reach(&player_pos, pos, interactable.kind))
+        .map(|(entity, pos, interactable)| {
+            let distance = (pos.x - player_pos.x).abs() + (pos.y - player_pos.y).abs();
+            (distance, entity, interactable.kind, interactable.label.clone())
+        })
+        .collect();
+    options.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.3.cmp(&b.3)));
+
+    match options.len() {
+        0 => {}
+        1 => interactions.send(InteractedWith { target: options[0].1, kind: options[0].2 }),
+        _ => {
+            // The key that opened the menu shouldn't also pick from it
+            keyboard.clear_just_pressed(KeyCode::E);
+            menu.options = options.into_iter().map(|(_, entity, _, label)| (entity, label)).collect();
+            menu.selected = 0;
+        }
+    }
+}
+
+// Keep the menu panel in step with the menu
+pub fn update_interaction_menu(
+    menu: Res<InteractionMenu>,
+    mut panel_query: Query<&mut Visibility, With<InteractionMenuPanel>>,
+    mut text_query: Query<&mut Text, With<InteractionMenuText>>,
+) {
+    if !menu.is_changed() {
+        return;
+    }
+
+    for mut visibility in panel_query.iter_mut() {
+        *visibility = if menu.is_open() { Visibility::Visible } else { Visibility::Hidden };
+    }
+
+    let mut lines = vec!["Interact with:".to_string()];
+    for (index, (_, label)) in menu.options.iter().enumerate() {
+        let marker = if index == menu.selected { ">" } else { " " };
+        lines.push(format!("{} {}. {}", marker, index + 1, label));
+    }
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = lines.join("\n");
+    }
+}
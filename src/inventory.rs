@@ -0,0 +1,130 @@
+use bevy::prelude::*;
+
+/// The kinds of items that can be carried
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ItemKind {
+    Ration,
+    HealingPotion,
+    ManaPotion,
+    Antidote,
+    PoisonPotion,
+    ScrollOfIdentify,
+    ScrollOfBlessing,
+    ScrollOfCursing,
+    Dagger,
+    Key,
+    Lockpick,
+    Meat,
+    Hide,
+    Fang,
+    Claw,
+    Trophy,
+    Gold, // Only in drop tables; spills as a treasure pile rather than being carried
+}
+
+/// Items that look alike until identified
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemClass {
+    Potion,
+    Scroll,
+}
+
+impl ItemKind {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            ItemKind::Ration => "ration",
+            ItemKind::HealingPotion => "healing potion",
+            ItemKind::ManaPotion => "mana potion",
+            ItemKind::Antidote => "antidote",
+            ItemKind::PoisonPotion => "poison potion",
+            ItemKind::ScrollOfIdentify => "scroll of identify",
+            ItemKind::ScrollOfBlessing => "scroll of blessing",
+            ItemKind::ScrollOfCursing => "scroll of cursing",
+            ItemKind::Dagger => "dagger",
+            ItemKind::Key => "key",
+            ItemKind::Lockpick => "lockpick",
+            ItemKind::Meat => "raw meat",
+            ItemKind::Hide => "hide",
+            ItemKind::Fang => "fang",
+            ItemKind::Claw => "claw",
+            ItemKind::Trophy => "trophy",
+            ItemKind::Gold => "gold coin",
+        }
+    }
+
+    // Potions and scrolls go by their looks until the player learns what they are
+    pub fn class(&self) -> Option<ItemClass> {
+        match self {
+            ItemKind::HealingPotion | ItemKind::ManaPotion | ItemKind::Antidote | ItemKind::PoisonPotion => Some(ItemClass::Potion),
+            ItemKind::ScrollOfIdentify | ItemKind::ScrollOfBlessing | ItemKind::ScrollOfCursing => Some(ItemClass::Scroll),
+            _ => None,
+        }
+    }
+
+    // Daggers fly true and potions shatter where they land
+    pub fn is_throwable(&self) -> bool {
+        matches!(self, ItemKind::Dagger) || self.class() == Some(ItemClass::Potion)
+    }
+
+    // Whether the item can be eaten (or fed to an animal)
+    pub fn is_food(&self) -> bool {
+        matches!(self, ItemKind::Ration | ItemKind::Meat)
+    }
+
+    // Parts taken from kills, which are only worth anything to a trader
+    pub fn is_valuable(&self) -> bool {
+        matches!(self, ItemKind::Hide | ItemKind::Fang | ItemKind::Claw | ItemKind::Trophy)
+    }
+}
+
+/// Items carried by an entity
+#[derive(Component, Debug, Default)]
+pub struct Inventory {
+    pub items: Vec<ItemKind>,
+}
+
+impl Inventory {
+    pub fn add(&mut self, item: ItemKind) {
+        self.items.push(item);
+    }
+
+    pub fn count(&self, item: ItemKind) -> usize {
+        self.items.iter().filter(|&&carried| carried == item).count()
+    }
+
+    // Remove one item of the given kind, returning whether one was carried
+    pub fn remove(&mut self, item: ItemKind) -> bool {
+        if let Some(index) = self.items.iter().position(|&carried| carried == item) {
+            self.items.remove(index);
+            true
+        } else {
+            false
+        }
+    }
+
+    // Each kind carried once, with how many, in the order first picked up
+    pub fn stacks(&self) -> Vec<(ItemKind, usize)> {
+        let mut stacks: Vec<(ItemKind, usize)> = Vec::new();
+        for &item in &self.items {
+            if let Some(stack) = stacks.iter_mut().find(|(kind, _)| *kind == item) {
+                stack.1 += 1;
+            } else {
+                stacks.push((item, 1));
+            }
+        }
+        stacks
+    }
+
+    // Remove and return the first food item carried, if any
+    pub fn take_food(&mut self) -> Option<ItemKind> {
+        let index = self.items.iter().position(|item| item.is_food())?;
+        Some(self.items.remove(index))
+    }
+
+    // Starting kit for a new run
+    pub fn starting_kit() -> Self {
+        Self {
+            items: vec![ItemKind::Ration, ItemKind::Ration, ItemKind::Ration],
+        }
+    }
+}
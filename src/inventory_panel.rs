@@ -0,0 +1,237 @@
+use bevy::prelude::*;
+
+use crate::combat::Health;
+use crate::components::{GameTurn, Player, Position};
+use crate::conversation::Conversation;
+use crate::identify::{with_article, ItemAppearances};
+use crate::inventory::{Inventory, ItemKind};
+use crate::spells::Mana;
+use crate::status::{ApplyStatusEffect, StatusEffect, StatusEffects, StatusKind};
+use crate::throwing::ThrowTargeting;
+use crate::ui::MessageLog;
+
+const RATION_HEAL: i32 = 2;
+const MEAT_HEAL: i32 = 3;
+pub const HEALING_POTION_HEAL: i32 = 10;
+const MANA_POTION_RESTORE: i32 = 10;
+const POISON_TURNS: u32 = 5;
+const BLESSING_TURNS: u32 = 40;
+const CURSE_TURNS: u32 = 30;
+
+/// Whether the inventory is open, and which line is picked
+#[derive(Resource, Default)]
+pub struct InventoryMenu {
+    pub open: bool,
+    pub selected: usize,
+}
+
+#[derive(Component)]
+pub struct InventoryPanel;
+
+#[derive(Component)]
+pub struct InventoryText;
+
+pub fn setup_inventory_panel(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                right: Val::Px(10.0),
+                top: Val::Px(40.0), // Below the status line
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            },
+            background_color: BackgroundColor(Color::rgba(0.0, 0.0, 0.0, 0.85)),
+            visibility: Visibility::Hidden,
+            z_index: ZIndex::Global(110),
+            ..default()
+        },
+        InventoryPanel,
+    ))
+    .with_children(|parent| {
+        parent.spawn((
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/FiraSans-Light.ttf"),
+                    font_size: 16.0,
+                    color: Color::WHITE,
+                },
+            ),
+            InventoryText,
+        ));
+    });
+}
+
+// System to open the inventory with I, use what's picked with E and throw it with X
+pub fn inventory_input_system(
+    mut keyboard: ResMut<Input<KeyCode>>,
+    conversation: Res<Conversation>,
+    mut menu: ResMut<InventoryMenu>,
+    mut appearances: ResMut<ItemAppearances>,
+    mut targeting: ResMut<ThrowTargeting>,
+    mut player_query: Query<(Entity, &Position, &mut Inventory, Option<&mut Health>, Option<&mut Mana>, Option<&mut StatusEffects>), With<Player>>,
+    mut game_turn: ResMut<GameTurn>,
+    mut message_log: ResMut<MessageLog>,
+    mut status_events: EventWriter<ApplyStatusEffect>,
+) {
+    if !menu.open {
+        if keyboard.just_pressed(KeyCode::I) && !conversation.is_active() && !targeting.is_aiming() {
+            menu.open = true;
+            menu.selected = 0;
+        }
+        return;
+    }
+    if keyboard.just_pressed(KeyCode::I) || keyboard.just_pressed(KeyCode::Back) {
+        menu.open = false;
+        return;
+    }
+
+    let (player, player_pos, mut inventory, mut health, mana, status) = if let Ok(player) = player_query.get_single_mut() { player } else { return; };
+    let stacks = inventory.stacks();
+    if stacks.is_empty() {
+        return;
+    }
+
+    let count = stacks.len();
+    if keyboard.just_pressed(KeyCode::Up) || keyboard.just_pressed(KeyCode::W) {
+        menu.selected = (menu.selected + count - 1) % count;
+    }
+    if keyboard.just_pressed(KeyCode::Down) || keyboard.just_pressed(KeyCode::S) {
+        menu.selected = (menu.selected + 1) % count;
+    }
+    menu.selected = menu.selected.min(count - 1);
+
+    let item = stacks[menu.selected].0;
+    let name = appearances.display_name(item);
+    if keyboard.just_pressed(KeyCode::X) {
+        if item.is_throwable() {
+            menu.open = false;
+            targeting.begin(item, (player_pos.x, player_pos.y));
+            message_log.add_message(format!("Throw the {} where? (E throw, Backspace cancel)", name));
+        } else {
+            message_log.add_message(format!("The {} is no good for throwing", name));
+        }
+        return;
+    }
+
+    let confirm_keys = [KeyCode::E, KeyCode::Space, KeyCode::Return];
+    let key = if let Some(&key) = confirm_keys.iter().find(|key| keyboard.just_pressed(**key)) { key } else { return; };
+    // The key is spent here rather than also talking to or opening something
+    keyboard.clear_just_pressed(key);
+
+    match item {
+        ItemKind::Key | ItemKind::Lockpick => {
+            message_log.add_message(format!("The {} is used on locked chests", name));
+            return;
+        }
+        ItemKind::Dagger => {
+            message_log.add_message(format!("The {} is for throwing (X)", name));
+            return;
+        }
+        _ if item.is_valuable() => {
+            message_log.add_message(format!("The {} is worth something to a trader", name));
+            return;
+        }
+        _ => {}
+    }
+    inventory.remove(item);
+    game_turn.increment();
+
+    let verb = match item {
+        ItemKind::Ration | ItemKind::Meat => "eat",
+        ItemKind::ScrollOfIdentify | ItemKind::ScrollOfBlessing | ItemKind::ScrollOfCursing => "read",
+        _ => "drink",
+    };
+    message_log.add_message(format!("You {} the {}", verb, name));
+
+    let mut status_effect = |kind, turns, potency| {
+        status_events.send(ApplyStatusEffect { target: player, effect: StatusEffect::new(kind, turns, potency) });
+    };
+    match item {
+        ItemKind::Ration => {
+            if let Some(health) = health.as_deref_mut() {
+                health.heal(RATION_HEAL);
+            }
+        }
+        ItemKind::Meat => {
+            if let Some(health) = health.as_deref_mut() {
+                health.heal(MEAT_HEAL);
+            }
+        }
+        ItemKind::HealingPotion => {
+            if let Some(health) = health.as_deref_mut() {
+                health.heal(HEALING_POTION_HEAL);
+            }
+            message_log.add_message("Your wounds knit closed".to_string());
+        }
+        ItemKind::ManaPotion => {
+            if let Some(mut mana) = mana {
+                mana.restore(MANA_POTION_RESTORE);
+            }
+            message_log.add_message("Your mind clears and your mana returns".to_string());
+        }
+        ItemKind::Antidote => {
+            if let Some(mut status) = status {
+                status.effects.retain(|effect| effect.kind != StatusKind::Poison);
+            }
+            message_log.add_message("A bitter taste, and the poison is gone".to_string());
+        }
+        ItemKind::PoisonPotion => status_effect(StatusKind::Poison, POISON_TURNS, 1),
+        ItemKind::ScrollOfBlessing => status_effect(StatusKind::Blessed, BLESSING_TURNS, 1),
+        ItemKind::ScrollOfCursing => status_effect(StatusKind::Cursed, CURSE_TURNS, 1),
+        ItemKind::ScrollOfIdentify => {
+            // Reveals the first unknown thing carried
+            let unknown = inventory.stacks().into_iter().map(|(kind, _)| kind).find(|&kind| kind != item && !appearances.is_known(kind));
+            match unknown {
+                Some(kind) => {
+                    let before = appearances.display_name(kind);
+                    appearances.identify(kind);
+                    message_log.add_message(format!("The {} is {}", before, with_article(kind.get_name())));
+                }
+                None => message_log.add_message("The words fade, with nothing to tell you about".to_string()),
+            }
+        }
+        ItemKind::Key | ItemKind::Lockpick | ItemKind::Dagger => {}
+        ItemKind::Hide | ItemKind::Fang | ItemKind::Claw | ItemKind::Trophy | ItemKind::Gold => {}
+    }
+
+    // Using something is how you learn what it was
+    if appearances.identify(item) {
+        message_log.add_message(format!("That was {}!", with_article(item.get_name())));
+    }
+    if inventory.items.is_empty() {
+        menu.open = false;
+    }
+}
+
+// Keep the inventory panel in step with what's carried
+pub fn update_inventory_panel(
+    menu: Res<InventoryMenu>,
+    appearances: Res<ItemAppearances>,
+    player_query: Query<&Inventory, With<Player>>,
+    mut panel_query: Query<&mut Visibility, With<InventoryPanel>>,
+    mut text_query: Query<&mut Text, With<InventoryText>>,
+) {
+    for mut visibility in panel_query.iter_mut() {
+        *visibility = if menu.open { Visibility::Visible } else { Visibility::Hidden };
+    }
+    if !menu.open {
+        return;
+    }
+
+    let inventory = if let Ok(inventory) = player_query.get_single() { inventory } else { return; };
+    let mut lines = vec!["Inventory (E use, X throw, I close):".to_string()];
+    let stacks = inventory.stacks();
+    if stacks.is_empty() {
+        lines.push("  Nothing carried".to_string());
+    }
+    for (index, (item, count)) in stacks.into_iter().enumerate() {
+        let marker = if index == menu.selected { ">" } else { " " };
+        let count = if count > 1 { format!(" x{}", count) } else { String::new() };
+        lines.push(format!("{} {}{}", marker, appearances.display_name(item), count));
+    }
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = lines.join("\n");
+    }
+}
@@ -0,0 +1,1028 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::sprite::{TextureAtlas, TextureAtlasSprite};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::HashMap;
+
+use crate::animals::{AnimalManager, spawn_animals, place_companions_near};
+use crate::assets::{SpriteAssets, TextureAtlases, load_sprite_assets};
+use crate::biome::BiomeManager;
+use crate::bookmarks::Bookmark;
+use crate::boss::Boss;
+use crate::chests::{Chest, spawn_chests};
+use crate::combat::{Health, CombatStats, RangedAttack};
+use crate::components::{self, Animal, AnimalAnimation, Companion, GameTurn, Npc, Player, Position, Skills, Tile};
+use crate::events::{ItemPickedUp, LevelChanged, SecretDoorFound};
+use crate::faction::Faction;
+use crate::input::{InputState, TILE_SIZE};
+use crate::inspect::InspectTooltip;
+use crate::interaction::{Interactable, InteractedWith, InteractionKind};
+use crate::inventory::Inventory;
+use crate::level_snapshots::LevelSnapshots;
+use crate::map::{self, GridLine, TileIndex, TileMap, TileType, generate_map_visuals};
+use crate::npc::{spawn_npc, spawn_marked_npcs};
+use crate::rng::GameRng;
+use crate::run_modifiers::{RunModifier, RunModifiers};
+use crate::spells::{Mana, Spellbook};
+use crate::visibility::{Vision, VisibilityMap};
+use crate::{GameState, GameplaySet};
+
+/// The dungeon itself: making levels and moving between them, what sits in
+/// the tiles (chests, traps, lights), and the record kept of the run
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<RegenerateMapEvent>()
+            .add_event::<LevelChanged>()
+            .add_event::<ItemPickedUp>()
+            .add_event::<SecretDoorFound>()
+            .add_event::<crate::gold::GoldCollected>()
+            .add_event::<crate::run_summary::RunEnded>()
+            .add_event::<crate::achievements::AchievementUnlocked>()
+            .init_resource::<TileIndex>()
+            .init_resource::<BiomeManager>()
+            .init_resource::<crate::lighting::LightMap>()
+            .init_resource::<VisibilityMap>()
+            .init_resource::<crate::run_log::RunLog>()
+            .init_resource::<crate::run_summary::RunStats>()
+            .init_resource::<crate::altars::DeityFavor>()
+            .init_resource::<crate::gold::Purse>()
+            .init_resource::<crate::gold::CollectedTreasure>()
+            .init_resource::<crate::level_generation::LevelGeneration>()
+            .init_resource::<LevelSnapshots>()
+            .init_resource::<crate::graves::Graves>()
+            .init_resource::<RunModifiers>()
+            .insert_resource(crate::graves::DeathMode::from_args())
+            .insert_resource(crate::daily::DailyChallenge::from_args())
+            .add_systems(OnExit(GameState::MainMenu), crate::daily::start_daily_run.run_if(crate::daily::daily_active))
+            .insert_resource(crate::run_log::RunReplay::from_args())
+            .insert_resource(crate::achievements::Achievements::load())
+            .add_systems(Startup, setup)
+            .add_systems(OnEnter(GameState::InGame), (
+                initialize_biome_manager,
+                spawn_game_world.after(initialize_biome_manager).after(crate::npc::initialize_animal_manager),
+                crate::achievements::reset_run_achievements,
+                crate::altars::reset_deity_favor,
+                crate::gold::reset_purse,
+                crate::level_generation::reset_level_generation,
+                crate::level_snapshots::reset_level_snapshots,
+                crate::graves::reset_graves,
+                crate::run_modifiers::apply_starting_modifiers
+                    .after(spawn_game_world)
+                    .after(crate::gold::reset_purse),
+                crate::visibility::setup_visibility_map.after(spawn_game_world),
+                crate::run_log::record_run_modifiers,
+            ).run_if(crate::loading_screen::starting_run))
+            .add_systems(Update, crate::level_generation::poll_level_generation.run_if(in_state(GameState::LoadingLevel)))
+            .add_systems(
+                Update,
+                (
+                    regenerate_map_system.in_set(GameplaySet::World),
+                    handle_map_regeneration
+                        .after(regenerate_map_system)
+                        .in_set(GameplaySet::World)
+                        .run_if(resource_exists::<TileMap>())
+                        .run_if(on_event::<RegenerateMapEvent>()),
+                    sync_stair_interactables.run_if(crate::map::layout_changed),
+                    handle_stairs_system
+                        .after(crate::npc::handle_npc_interaction)
+                        .in_set(GameplaySet::World),
+                    // update_fade_effects, // Temporarily disabled fade effects
+                    crate::chests::open_chest_system,
+                    crate::lore::read_readables_system,
+                    crate::corpses::spawn_corpses_system
+                        .after(crate::combat::animate_projectiles)
+                        .after(crate::animals::move_companions_system)
+                        .after(crate::infighting::creature_infighting_system)
+                        .after(crate::throwing::animate_thrown_items)
+                        .before(crate::combat::despawn_dead_entities),
+                    crate::corpses::loot_corpses_system,
+                    crate::graves::record_looted_graves.after(crate::corpses::loot_corpses_system),
+                    crate::traps::trigger_traps_system,
+                )
+                .run_if(in_state(GameState::InGame))
+            )
+            .add_systems(
+                Update,
+                (
+                    crate::lighting::sync_light_fixtures.run_if(crate::map::layout_changed),
+                    crate::lighting::update_light_map.after(crate::lighting::sync_light_fixtures),
+                )
+                .run_if(in_state(GameState::InGame))
+                .run_if(resource_exists::<TextureAtlases>())
+            )
+            .add_systems(
+                Update,
+                (
+                    crate::portals::sync_portals.run_if(crate::map::layout_changed),
+                    crate::portals::spin_portals.after(crate::portals::sync_portals),
+                    crate::portals::use_portals_system.after(crate::player::animate_player_movement),
+                    crate::portals::animate_portal_swirls,
+                )
+                .run_if(in_state(GameState::InGame))
+                .run_if(resource_exists::<TileMap>())
+            )
+            .add_systems(
+                Update,
+                (
+                    crate::altars::sync_altars.run_if(crate::map::layout_changed),
+                    crate::altars::pray_at_altar_system,
+                    crate::gold::sync_treasure_piles.run_if(crate::map::layout_changed),
+                    crate::gold::pick_up_treasure_system
+                        .after(crate::gold::sync_treasure_piles)
+                        .after(crate::input::move_player),
+                    crate::gold::collect_gold_system
+                        .after(crate::gold::pick_up_treasure_system)
+                        .after(crate::chests::open_chest_system),
+                    crate::graves::sync_graves.run_if(crate::map::layout_changed),
+                    crate::graves::soft_death_system
+                        .after(crate::run_summary::record_damage_source_system)
+                        .before(crate::run_summary::detect_player_death_system),
+                )
+                .run_if(in_state(GameState::InGame))
+                .run_if(resource_exists::<TileMap>())
+                .run_if(resource_exists::<TextureAtlases>())
+            )
+            .add_systems(Update, crate::tile_animation::animate_tiles.run_if(in_state(GameState::InGame)))
+            .add_systems(
+                Update,
+                crate::hot_reload::hot_reload_sprite_manifest
+                    .before(crate::tile_animation::animate_tiles)
+                    .run_if(in_state(GameState::InGame))
+                    .run_if(resource_exists::<TextureAtlases>())
+            )
+            .add_systems(
+                Update,
+                (
+                    crate::ambient::ambient_events_system,
+                    crate::ambient::camera_shake_system.before(crate::camera::follow_camera),
+                    crate::ambient::flicker_lights_system.before(crate::lighting::update_light_map),
+                    crate::ambient::move_scurriers_system,
+                )
+                    .run_if(in_state(GameState::InGame))
+                    .run_if(resource_exists::<TileMap>())
+                    .run_if(resource_exists::<TextureAtlases>())
+            )
+            .add_systems(
+                Update,
+                (
+                    crate::capture::take_screenshot_system,
+                    crate::capture::export_map_image_system,
+                    crate::map::export_map_text_system,
+                    crate::map::announce_room_system,
+                )
+                .run_if(in_state(GameState::InGame))
+                .run_if(resource_exists::<TextureAtlases>())
+            )
+            .add_systems(
+                PreUpdate,
+                (
+                    crate::input::read_tile_click.after(bevy::input::InputSystem),
+                    crate::run_log::replay_run_system.after(crate::input::read_tile_click),
+                )
+                .run_if(in_state(GameState::InGame))
+            )
+            .add_systems(
+                Update,
+                (
+                    crate::run_log::record_run_system,
+                    crate::run_log::export_run_log_system,
+                )
+                .run_if(in_state(GameState::InGame))
+                .run_if(resource_exists::<TileMap>())
+            )
+            .add_systems(
+                Update,
+                (
+                    crate::run_summary::track_depth_system.after(handle_stairs_system),
+                    crate::run_summary::record_damage_source_system
+                        .after(crate::animals::animal_attack_system)
+                        .after(crate::traps::trigger_traps_system)
+                        .after(crate::status::tick_status_effects_system)
+                        .after(crate::boss::boss_ai_system),
+                    crate::run_summary::detect_player_death_system
+                        .after(crate::run_summary::record_damage_source_system),
+                    crate::run_summary::finish_run_system
+                        .after(handle_stairs_system)
+                        .after(crate::run_summary::detect_player_death_system),
+                )
+                .run_if(in_state(GameState::InGame))
+            )
+            .add_systems(
+                Update,
+                crate::achievements::evaluate_achievements_system
+                    .after(handle_stairs_system)
+                    .after(crate::run_summary::detect_player_death_system)
+                    .before(crate::run_summary::finish_run_system)
+                    .run_if(in_state(GameState::InGame))
+                    .run_if(resource_exists::<TileMap>())
+            );
+    }
+}
+
+// Add a resource to track dungeon levels
+#[derive(Resource)]
+pub struct DungeonState {
+    pub levels: Vec<TileMap>,
+    pub current_level_index: usize,
+    pub explored: HashMap<usize, VisibilityMap>, // What was seen of each level the player has left
+    pub bookmarks: HashMap<usize, Vec<Bookmark>>, // The player's own notes, by level
+}
+
+impl Default for DungeonState {
+    fn default() -> Self {
+        let initial_map = TileMap::new();
+        Self {
+            levels: vec![initial_map],
+            current_level_index: 0,
+            explored: HashMap::new(),
+            bookmarks: HashMap::new(),
+        }
+    }
+}
+
+impl DungeonState {
+    // The bookmarks on the level the player is on
+    pub fn current_bookmarks(&self) -> &[Bookmark] {
+        self.bookmarks.get(&self.current_level_index).map_or(&[][..], |bookmarks| bookmarks.as_slice())
+    }
+
+    // Store the fog of war of the level being left and bring back what was explored of the one being entered,
+    // so dimmed tiles come back as they were
+    pub fn swap_visibility(&mut self, from: usize, to: usize, visibility_map: &mut VisibilityMap, new_map: &TileMap) {
+        let mut left = std::mem::replace(visibility_map, self.explored.remove(&to).unwrap_or_default());
+        left.forget_current_sight();
+        self.explored.insert(from, left);
+        // A level never visited starts out unexplored
+        visibility_map.fit_to(new_map);
+    }
+}
+
+// Add a component for the fade effect
+#[derive(Component)]
+struct FadeEffect {
+    timer: Timer,
+    fade_in: bool,
+    target_level: Option<usize>,
+}
+
+// Add a component for UI prompts
+#[derive(Component)]
+struct StairPrompt;
+
+#[derive(Event)]
+struct RegenerateMapEvent;
+
+pub fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    texture_atlases: ResMut<Assets<TextureAtlas>>,
+) {
+    // Load all sprite assets
+    // Nothing can be drawn without the sprites, so a broken manifest stops the game here
+    if let Err(e) = load_sprite_assets(&mut commands, asset_server, texture_atlases) {
+        panic!("Error loading sprite assets: {}", e);
+    }
+
+    // Create initial TileMap
+    let map = TileMap::new();
+
+    // The first level's seed is the run seed every other roll follows from
+    commands.insert_resource(GameRng::new(map.seed));
+
+    commands.insert_resource(map.clone());
+    
+    // Create DungeonState with the same map
+    commands.insert_resource(DungeonState {
+        levels: vec![map],
+        ..default()
+    });
+    
+    // Initialize BiomeManager as a resource
+    commands.init_resource::<BiomeManager>();
+}
+
+// Update the spawn_game_world function to add PlayerAnimation component
+pub fn spawn_game_world(
+    mut commands: Commands,
+    texture_atlases: Res<TextureAtlases>,
+    sprite_assets: Res<SpriteAssets>,
+    map: Res<TileMap>,
+    biome_manager: Res<BiomeManager>,
+    animal_manager: Res<AnimalManager>,
+    existing_entities: Query<Entity, Or<(With<Tile>, With<Player>, With<Npc>, With<GridLine>, With<Animal>, With<InspectTooltip>, With<Chest>, With<crate::props::Prop>)>>,
+    mut tile_index: ResMut<TileIndex>,
+    mut game_rng: ResMut<GameRng>,
+    modifiers: Res<RunModifiers>,
+) {
+    // First, clean up any existing entities
+    for entity in existing_entities.iter() {
+        commands.entity(entity).despawn();
+    }
+    
+    // Then spawn new tiles and player
+    let tile_entities = map::spawn_tiles(&mut commands, &map, &texture_atlases, &sprite_assets, Some(&biome_manager), &[]);
+    tile_index.rebuild(&map, &tile_entities);
+    
+    // Spawn grid lines
+    let grid_lines = map::spawn_grid_lines(&mut commands, &map);
+    tile_index.set_grid_lines(&map, grid_lines);
+
+    // Spawn animals
+    spawn_animals(&mut commands, &map, &texture_atlases, &animal_manager, &modifiers, &mut game_rng.spawns);
+    
+    // Spawn chests
+    spawn_chests(&mut commands, &map, &texture_atlases, &sprite_assets);
+    crate::props::spawn_props(&mut commands, &map, &texture_atlases, &sprite_assets);
+
+    // Find valid floor tiles for NPC spawn
+    let floor_tiles: Vec<(i32, i32)> = (0..map.width * map.height)
+        .filter(|&i| {
+            let row = i / map.width; 
+            let col = i % map.width;
+            map.tiles[row][col] == TileType::Floor
+        })
+        .map(|i| (
+            (i % map.width) as i32,
+            (i / map.width) as i32
+        ))
+        .collect();
+        
+    println!("Found {} floor tiles for NPC spawning", floor_tiles.len());
+
+    // Choose random position away from player spawn
+    let spawn_pos = map.get_spawn_position();
+    let npc_pos = floor_tiles.into_iter()
+        .filter(|pos| {
+            let dx = (pos.0 - spawn_pos.0 as i32).abs();
+            let dy = (pos.1 - spawn_pos.1 as i32).abs();
+            dx + dy > 5 // Minimum Manhattan distance from player
+        })
+        .collect::<Vec<_>>();
+        
+    println!("Found {} valid positions for NPC (minimum 5 tiles from player)", npc_pos.len());
+
+    // 10% chance to spawn an NPC
+    if !modifiers.has(RunModifier::NoNpcs) && !npc_pos.is_empty() && game_rng.spawns.gen_bool(0.1) {
+        let npc_pos = npc_pos
+            .choose(&mut game_rng.spawns)
+            .copied()
+            .unwrap_or((5, 5));
+            
+        spawn_npc(&mut commands, &texture_atlases, &sprite_assets, npc_pos, &map.get_biome_at(npc_pos.0 as usize, npc_pos.1 as usize), &mut game_rng);
+    }
+    if !modifiers.has(RunModifier::NoNpcs) {
+        spawn_marked_npcs(&mut commands, &texture_atlases, &sprite_assets, &map, &mut game_rng);
+    }
+
+    // Spawn player
+    let spawn_pos = map.get_spawn_position();
+    let player_pos = Vec3::new(
+        spawn_pos.0 as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+        spawn_pos.1 as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+        10.0  // Increased z-index to ensure player is always on top
+    );
+    
+    commands.spawn((
+        SpriteSheetBundle {
+            texture_atlas: texture_atlases.characters.clone(),
+            sprite: TextureAtlasSprite {
+                index: crate::assets::get_character_sprite(&sprite_assets, "male wizard"),
+                ..default()
+            },
+            transform: Transform::from_translation(player_pos).with_scale(Vec3::splat(1.0)),
+            ..default()
+        },
+        Player,
+        Position::new(spawn_pos.0 as i32, spawn_pos.1 as i32),
+        Vision::default(),
+        components::PlayerAnimation::default(),
+        Inventory::starting_kit(),
+        Health::new(20),
+        CombatStats { attack: 3 },
+        // The player is a wizard and starts out able to cast bolts
+        RangedAttack { damage: 4, range: 8 },
+        Mana::new(12),
+        Spellbook::default(),
+        Skills::default(),
+        crate::lighting::LightSource::torch(),
+    ));
+}
+
+/// Which way a staircase leads
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StairDirection {
+    Down,
+    Up,
+}
+
+/// Everything a trip to another level touches: the level being left is stored away and the one
+/// arrived at is loaded, populated and fogged as it was left
+#[derive(SystemParam)]
+pub struct LevelTransition<'w, 's> {
+    commands: Commands<'w, 's>,
+    dungeon_state: ResMut<'w, DungeonState>,
+    player_query: Query<'w, 's, (&'static mut Transform, &'static mut Position), With<Player>>,
+    companion_query: Query<'w, 's, (Entity, &'static mut Transform, &'static mut AnimalAnimation), (With<Companion>, Without<Player>)>,
+    existing_entities: Query<'w, 's, Entity, (Or<(With<Npc>, With<Animal>, With<InspectTooltip>, With<Chest>, With<crate::props::Prop>)>, Without<Companion>)>,
+    npc_query: Query<'w, 's, (&'static Npc, &'static Position, &'static TextureAtlasSprite, &'static Faction, Option<&'static crate::npc_registry::UniqueNpc>), (Without<Animal>, Without<Boss>, Without<Player>, Without<Companion>, Without<crate::warden::Warden>)>,
+    texture_atlases: Res<'w, TextureAtlases>,
+    sprite_assets: Res<'w, SpriteAssets>,
+    asset_server: Res<'w, AssetServer>,
+    tile_index: ResMut<'w, TileIndex>,
+    biome_manager: Res<'w, BiomeManager>,
+    animal_manager: Res<'w, AnimalManager>,
+    modifiers: Res<'w, RunModifiers>,
+    game_turn: ResMut<'w, GameTurn>,
+    game_rng: ResMut<'w, GameRng>,
+    level_snapshots: ResMut<'w, LevelSnapshots>,
+    visibility_map: ResMut<'w, VisibilityMap>,
+    level_changed: EventWriter<'w, LevelChanged>,
+}
+
+impl LevelTransition<'_, '_> {
+    // Take the player to a level already generated, arriving on the stairs that lead back the way they came
+    fn travel(&mut self, target_level: usize, direction: StairDirection) {
+        // Using the stairs takes a turn
+        self.game_turn.increment();
+
+        let from = self.dungeon_state.current_level_index;
+        let new_map = self.dungeon_state.levels[target_level].clone();
+
+        // Remember who was on the level being left before they're cleaned up, and what of it was explored
+        self.level_snapshots.save(from, self.npc_query.iter());
+        self.dungeon_state.swap_visibility(from, target_level, &mut self.visibility_map, &new_map);
+
+        self.level_changed.send(LevelChanged { from, to: target_level });
+        self.dungeon_state.current_level_index = target_level;
+        self.commands.insert_resource(new_map.clone());
+
+        // Clean up existing entities (the player and companions travel with us)
+        for entity in self.existing_entities.iter() {
+            self.commands.entity(entity).despawn_recursive();
+        }
+
+        generate_map_visuals(
+            &mut self.commands,
+            &new_map,
+            &self.asset_server,
+            &self.sprite_assets,
+            &self.texture_atlases,
+            &self.biome_manager,
+            &mut self.tile_index,
+        );
+        spawn_animals(&mut self.commands, &new_map, &self.texture_atlases, &self.animal_manager, &self.modifiers, &mut self.game_rng.spawns);
+        spawn_chests(&mut self.commands, &new_map, &self.texture_atlases, &self.sprite_assets);
+        crate::props::spawn_props(&mut self.commands, &new_map, &self.texture_atlases, &self.sprite_assets);
+        self.level_snapshots.restore(&mut self.commands, &self.texture_atlases, target_level);
+
+        let arrival_stairs = match direction {
+            StairDirection::Down => new_map.up_stairs_pos,
+            StairDirection::Up => new_map.down_stairs_pos,
+        };
+        let arrival_pos = arrival_stairs.unwrap_or_else(|| {
+            eprintln!("Level {} has no stairs back {:?}; arriving at its spawn point", target_level, direction);
+            new_map.get_spawn_position()
+        });
+
+        let (mut player_transform, mut player_position) = self.player_query.single_mut();
+        move_player_to(&mut player_transform, &mut player_position, arrival_pos);
+
+        // Bring any companions along
+        place_companions_near(&mut self.commands, &mut self.companion_query, &new_map, (arrival_pos.0 as i32, arrival_pos.1 as i32));
+    }
+}
+
+// System to take the stairs the player uses (E on them), or carry on down once a new level has finished generating
+pub fn handle_stairs_system(
+    mut interactions: EventReader<InteractedWith>,
+    map: Res<TileMap>,
+    mut message_log: ResMut<crate::ui::MessageLog>,
+    mut level_generation: ResMut<crate::level_generation::LevelGeneration>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut run_ended: EventWriter<crate::run_summary::RunEnded>,
+    mut transition: LevelTransition,
+) {
+    let use_stairs = interactions.read().any(|interaction| interaction.kind == InteractionKind::UseStairs);
+    let level_ready = level_generation.take_ready();
+
+    let player_tile = if let Ok((_, pos)) = transition.player_query.get_single() { (pos.x as usize, pos.y as usize) } else { return; };
+    let direction = if map.down_stairs_pos == Some(player_tile) {
+        StairDirection::Down
+    } else if map.up_stairs_pos == Some(player_tile) {
+        StairDirection::Up
+    } else {
+        return;
+    };
+    if !use_stairs && !(level_ready && direction == StairDirection::Down) {
+        return;
+    }
+
+    let current_level = transition.dungeon_state.current_level_index;
+    match direction {
+        StairDirection::Down => {
+            // Boss floors keep the down stairs sealed until the boss is dead
+            if map.down_stairs_locked {
+                message_log.add_message("The stairs are sealed. Defeat the guardian of this floor first.".to_string());
+                return;
+            }
+            // A level not seen yet is generated in the background behind the loading screen;
+            // the descent carries on once it's ready
+            let target_level = current_level + 1;
+            if target_level >= transition.dungeon_state.levels.len() {
+                if !level_generation.is_generating() {
+                    level_generation.start(map.clone(), target_level, transition.game_rng.mapgen.clone());
+                    next_state.set(GameState::LoadingLevel);
+                }
+                return;
+            }
+            transition.travel(target_level, direction);
+        }
+        // The up stairs on the first floor lead out to the surface, ending the run
+        StairDirection::Up if current_level == 0 => {
+            message_log.add_message("You climb toward the daylight...".to_string());
+            run_ended.send(crate::run_summary::RunEnded { outcome: crate::run_summary::RunOutcome::Escaped });
+        }
+        StairDirection::Up => transition.travel(current_level - 1, direction),
+    }
+}
+
+/// Stands on a staircase so E can be used on it
+#[derive(Component)]
+pub struct StairsInteractable;
+
+// System to put an interactable on each staircase whenever a different level is loaded
+pub fn sync_stair_interactables(
+    mut commands: Commands,
+    map: Res<TileMap>,
+    stairs_query: Query<Entity, With<StairsInteractable>>,
+) {
+    for entity in stairs_query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let stairs = [(map.down_stairs_pos, "Go down the stairs"), (map.up_stairs_pos, "Go up the stairs")];
+    for (pos, label) in stairs {
+        if let Some((x, y)) = pos {
+            commands.spawn((
+                Interactable::new(InteractionKind::UseStairs, label),
+                Position::new(x as i32, y as i32),
+                StairsInteractable,
+            ));
+        }
+    }
+}
+
+// Move the existing player entity to a tile, e.g. after changing levels
+pub fn move_player_to(transform: &mut Transform, position: &mut Position, tile: (usize, usize)) {
+    transform.translation = Vec3::new(
+        tile.0 as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+        tile.1 as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+        10.0  // Increased z-index to ensure player is always on top
+    );
+    transform.rotation = Quat::IDENTITY;
+    position.x = tile.0 as i32;
+    position.y = tile.1 as i32;
+}
+
+// Modify regenerate_map_system to directly handle map regeneration without fade effects
+pub fn regenerate_map_system(
+    mut commands: Commands,
+    input_state: Res<InputState>,
+    mut dungeon_state: ResMut<DungeonState>,
+    mut player_query: Query<(&mut Transform, &mut Position), With<Player>>,
+    mut companion_query: Query<(Entity, &mut Transform, &mut AnimalAnimation), (With<Companion>, Without<Player>)>,
+    texture_atlases: Res<TextureAtlases>,
+    sprite_assets: Res<SpriteAssets>,
+    asset_server: Res<AssetServer>,
+    existing_entities: Query<Entity, (Or<(With<Npc>, With<Animal>, With<InspectTooltip>, With<Chest>, With<crate::props::Prop>)>, Without<Companion>)>,
+    mut tile_index: ResMut<TileIndex>,
+    mut ev_regenerate: EventWriter<RegenerateMapEvent>,
+    biome_manager: Res<BiomeManager>,
+    animal_manager: Res<AnimalManager>,
+    mut game_rng: ResMut<GameRng>,
+    mut level_snapshots: ResMut<LevelSnapshots>,
+    (daily, mut message_log, modifiers, mut visibility_map): (Res<crate::daily::DailyChallenge>, ResMut<crate::ui::MessageLog>, Res<RunModifiers>, ResMut<VisibilityMap>),
+) {
+    // Only proceed if SHIFT+R (or F10 for the custom map) was pressed
+    if !input_state.regenerate_map && !input_state.load_custom_map {
+        return;
+    }
+
+    // Everyone plays the same Daily Chasm, so its levels can't be rerolled
+    if daily.active {
+        message_log.add_message("The Daily Chasm can't be regenerated.".to_string());
+        return;
+    }
+    
+    // First check if we have a player entity
+    if player_query.is_empty() {
+        return;
+    }
+    
+    println!("Map regeneration triggered with SHIFT+R");
+    
+    // Get the current level index
+    let current_index = dungeon_state.current_level_index;
+    
+    // DIRECT REGENERATION WITHOUT FADE
+    // Generate a new map with the same level index, or load the hand-authored one in its place
+    let new_map = if input_state.load_custom_map {
+        match crate::map::load_custom_map() {
+            Ok(map) => {
+                println!("Loaded custom map in place of level {}", current_index);
+                map
+            }
+            Err(e) => {
+                eprintln!("Could not load custom map: {}", e);
+                return;
+            }
+        }
+    } else {
+        println!("Regenerating map for level {}", current_index);
+        TileMap::new_level(current_index, None, &mut game_rng.mapgen)
+    };
+    
+    // Update the map in dungeon state
+    if let Some(level) = dungeon_state.levels.get_mut(current_index) {
+        *level = new_map.clone();
+    }
+    level_snapshots.forget(current_index); // Whoever lived there went with the old layout
+    // Nothing of the new layout has been seen yet, and bookmarks pinned to the old one no longer point anywhere
+    visibility_map.reset_to(&new_map);
+    dungeon_state.explored.remove(&current_index);
+    dungeon_state.bookmarks.remove(&current_index);
+    
+    // Update the map resource
+    commands.insert_resource(new_map.clone());
+    
+    // Send an event to notify other systems
+    ev_regenerate.send(RegenerateMapEvent);
+    
+    // Clean up existing entities (the player and companions are kept)
+    for entity in existing_entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    
+    // Generate new map visuals
+    generate_map_visuals(
+        &mut commands,
+        &new_map,
+        &asset_server,
+        &sprite_assets,
+        &texture_atlases,
+        &biome_manager,
+        &mut tile_index
+    );
+    
+    // Spawn animals and chests on the new map
+    spawn_animals(&mut commands, &new_map, &texture_atlases, &animal_manager, &modifiers, &mut game_rng.spawns);
+    spawn_chests(&mut commands, &new_map, &texture_atlases, &sprite_assets);
+    crate::props::spawn_props(&mut commands, &new_map, &texture_atlases, &sprite_assets);
+    
+    // Move the player to the spawn position
+    let spawn_pos = new_map.get_spawn_position();
+    {
+        let (mut player_transform, mut player_position) = player_query.single_mut();
+        move_player_to(&mut player_transform, &mut player_position, spawn_pos);
+    }
+    
+    println!("Player moved to position: {:?}", spawn_pos);
+    
+    // Bring any companions along
+    place_companions_near(&mut commands, &mut companion_query, &new_map, (spawn_pos.0 as i32, spawn_pos.1 as i32));
+    
+    // Find valid floor tiles for NPC spawn
+    let mut npc_pos = Vec::new();
+    
+    // Define map outside the loop so it's available later
+    let map = if current_index == dungeon_state.current_level_index {
+        // Use the newly generated map for regeneration
+        dungeon_state.levels[current_index].clone()
+    } else {
+        // Use the map from the target level
+        dungeon_state.levels[current_index].clone()
+    };
+    
+    for y in 0..map.height {
+        for x in 0..map.width {
+            if map.tiles[y][x] == TileType::Floor {
+                // Get player position
+                let (_, player_position) = player_query.single();
+                
+                // Don't spawn NPCs at player position or stairs
+                let is_player_pos = player_position.x == x as i32 && player_position.y == y as i32;
+                let is_stairs = map.down_stairs_pos.map_or(false, |pos| pos.0 == x && pos.1 == y) ||
+                               map.up_stairs_pos.map_or(false, |pos| pos.0 == x && pos.1 == y);
+                
+                if !is_player_pos && !is_stairs {
+                    npc_pos.push((x as i32, y as i32));
+                }
+            }
+        }
+    }
+    
+    // Spawn NPC if we found valid positions with 10% chance
+    if !modifiers.has(RunModifier::NoNpcs) && !npc_pos.is_empty() && game_rng.spawns.gen_bool(0.1) {
+        let npc_pos = npc_pos
+            .choose(&mut game_rng.spawns)
+            .copied()
+            .unwrap_or((5, 5));
+        
+        println!("Spawning NPC at position: ({}, {})", npc_pos.0, npc_pos.1);
+        
+        // Spawn NPC
+        spawn_npc(&mut commands, &texture_atlases, &sprite_assets, npc_pos, &map.get_biome_at(npc_pos.0 as usize, npc_pos.1 as usize), &mut game_rng);
+    }
+    if !modifiers.has(RunModifier::NoNpcs) {
+        spawn_marked_npcs(&mut commands, &texture_atlases, &sprite_assets, &map, &mut game_rng);
+    }
+}
+
+// System to initialize the BiomeManager with tile mappings
+pub fn initialize_biome_manager(
+    mut biome_manager: ResMut<BiomeManager>,
+    sprite_assets: Res<SpriteAssets>,
+) {
+    biome_manager.initialize_default_tiles(&sprite_assets.tile_sprites);
+    println!("Initialized BiomeManager with tile mappings");
+}
+
+// System to update fade effects
+pub fn update_fade_effects(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut fade_query: Query<(Entity, &mut FadeEffect, &mut BackgroundColor)>,
+    mut dungeon_state: ResMut<DungeonState>,
+    texture_atlases: Res<TextureAtlases>,
+    sprite_assets: Res<SpriteAssets>,
+    asset_server: Res<AssetServer>,
+    mut player_query: Query<(&mut Transform, &mut Position), With<Player>>,
+    existing_entities: Query<Entity, With<Npc>>,
+    mut tile_index: ResMut<TileIndex>,
+    biome_manager: Res<BiomeManager>,
+    mut events: EventWriter<RegenerateMapEvent>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    // Debug: Print the number of fade effects
+    if !fade_query.is_empty() {
+        println!("Processing {} fade effects", fade_query.iter().count());
+    }
+
+    for (entity, mut fade, mut background) in fade_query.iter_mut() {
+        // Update fade timer
+        fade.timer.tick(time.delta());
+        
+        // Calculate alpha based on fade direction and progress
+        let progress = fade.timer.percent();
+        let alpha = if fade.fade_in {
+            progress // Fade in: 0.0 -> 1.0
+        } else {
+            1.0 - progress // Fade out: 1.0 -> 0.0
+        };
+        
+        // Update background alpha
+        background.0.set_a(alpha);
+        
+        // Debug: Print fade progress
+        println!("Fade progress: {:.2}, Alpha: {:.2}, Fade in: {}, Target level: {:?}", 
+                 progress, alpha, fade.fade_in, fade.target_level);
+        
+        // Check if fade is complete
+        if fade.timer.finished() {
+            println!("Fade effect completed!");
+            
+            // If this was a fade out, handle the transition
+            if !fade.fade_in && fade.target_level.is_some() {
+                let target_level = fade.target_level.unwrap();
+                println!("Transitioning to level {}", target_level);
+                
+                // Get the current level index
+                let current_level = dungeon_state.current_level_index;
+                
+                // Check if this is a map regeneration (same level)
+                let is_regeneration = target_level == current_level;
+                
+                if is_regeneration {
+                    // Generate a new map with the same level index
+                    println!("Regenerating map for level {}", target_level);
+                    let new_map = TileMap::new_level(target_level, None, &mut game_rng.mapgen);
+                    
+                    // Update the map in dungeon state
+                    if let Some(level) = dungeon_state.levels.get_mut(target_level) {
+                        *level = new_map.clone();
+                    }
+                    
+                    // Update the map resource
+                    commands.insert_resource(new_map.clone());
+                    
+                    // Send an event to notify other systems
+                    events.send(RegenerateMapEvent);
+                    
+                    // Clean up existing entities
+                    for entity in existing_entities.iter() {
+                        commands.entity(entity).despawn_recursive();
+                    }
+                    
+                    // Generate new map visuals
+                    generate_map_visuals(
+                        &mut commands,
+                        &new_map,
+                        &asset_server,
+                        &sprite_assets,
+                        &texture_atlases,
+                        &biome_manager,
+                        &mut tile_index
+                    );
+                    
+                    // Move player to spawn position
+                    let spawn_pos = new_map.get_spawn_position();
+                    let (mut player_transform, mut player_position) = player_query.single_mut();
+                    player_transform.translation.x = (spawn_pos.0 as f32) * TILE_SIZE + (TILE_SIZE / 2.0);
+                    player_transform.translation.y = (spawn_pos.1 as f32) * TILE_SIZE + (TILE_SIZE / 2.0);
+                    player_position.x = spawn_pos.0 as i32;
+                    player_position.y = spawn_pos.1 as i32;
+                    
+                    println!("Player moved to spawn position: {:?}", spawn_pos);
+                } else {
+                    // Only proceed if the target level is valid
+                    if target_level >= dungeon_state.levels.len() {
+                        // Generate a new level if needed
+                        println!("Generating new level {}", target_level);
+                        let new_map = TileMap::new_level(target_level, None, &mut game_rng.mapgen);
+                        dungeon_state.levels.push(new_map);
+                    }
+                    
+                    // Clone the map before borrowing dungeon_state as mutable
+                    let new_map = dungeon_state.levels[target_level].clone();
+                    
+                    // Update the current level index
+                    dungeon_state.current_level_index = target_level;
+                    println!("Updated current level index to {}", target_level);
+                    
+                    // Update the map resource
+                    commands.insert_resource(new_map.clone());
+                    
+                    // Clean up existing entities
+                    for entity in existing_entities.iter() {
+                        commands.entity(entity).despawn_recursive();
+                    }
+                    
+                    // Generate new map visuals
+                    generate_map_visuals(
+                        &mut commands,
+                        &new_map,
+                        &asset_server,
+                        &sprite_assets,
+                        &texture_atlases,
+                        &biome_manager,
+                        &mut tile_index
+                    );
+                    
+                    // Move player to appropriate stairs position and update both Transform and Position
+                    let (mut player_transform, mut player_position) = player_query.single_mut();
+                    if target_level > current_level {
+                        // Going down, so place at up stairs
+                        if let Some(up_pos) = new_map.up_stairs_pos {
+                            println!("Moving player to up stairs at {:?}", up_pos);
+                            player_transform.translation.x = (up_pos.0 as f32) * TILE_SIZE + (TILE_SIZE / 2.0);
+                            player_transform.translation.y = (up_pos.1 as f32) * TILE_SIZE + (TILE_SIZE / 2.0);
+                            // Update Position component to match
+                            player_position.x = up_pos.0 as i32;
+                            player_position.y = up_pos.1 as i32;
+                        } else {
+                            println!("WARNING: No up stairs found in the new map!");
+                        }
+                    } else {
+                        // Going up, so place at down stairs
+                        if let Some(down_pos) = new_map.down_stairs_pos {
+                            println!("Moving player to down stairs at {:?}", down_pos);
+                            player_transform.translation.x = (down_pos.0 as f32) * TILE_SIZE + (TILE_SIZE / 2.0);
+                            player_transform.translation.y = (down_pos.1 as f32) * TILE_SIZE + (TILE_SIZE / 2.0);
+                            // Update Position component to match
+                            player_position.x = down_pos.0 as i32;
+                            player_position.y = down_pos.1 as i32;
+                        } else {
+                            println!("WARNING: No down stairs found in the new map!");
+                        }
+                    }
+                }
+                
+                // Find valid floor tiles for NPC spawn (similar to handle_map_regeneration)
+                let mut npc_pos = Vec::new();
+                let (map_width, map_height) = (dungeon_state.levels[target_level].width, dungeon_state.levels[target_level].height);
+                for y in 0..map_height {
+                    for x in 0..map_width {
+                        let map = if is_regeneration {
+                            // Use the newly generated map for regeneration
+                            dungeon_state.levels[target_level].clone()
+                        } else {
+                            // Use the map from the target level
+                            dungeon_state.levels[target_level].clone()
+                        };
+                        
+                        if map.tiles[y][x] == TileType::Floor {
+                            // Get player position
+                            let (_, player_position) = player_query.single();
+                            
+                            // Don't spawn NPCs at player position or stairs
+                            let is_player_pos = player_position.x == x as i32 && player_position.y == y as i32;
+                            let is_stairs = map.down_stairs_pos.map_or(false, |pos| pos.0 == x && pos.1 == y) ||
+                                           map.up_stairs_pos.map_or(false, |pos| pos.0 == x && pos.1 == y);
+                            
+                            if !is_player_pos && !is_stairs {
+                                npc_pos.push((x as i32, y as i32));
+                            }
+                        }
+                    }
+                }
+                
+                // Spawn NPC if we found valid positions with 10% chance
+                if !npc_pos.is_empty() && game_rng.spawns.gen_bool(0.1) {
+                    let npc_pos = npc_pos
+                        .choose(&mut game_rng.spawns)
+                        .copied()
+                        .unwrap_or((5, 5));
+                    
+                    // Get the map for biome information
+                    let map = dungeon_state.levels[target_level].clone();
+                    
+                    println!("Spawning NPC at position: ({}, {})", npc_pos.0, npc_pos.1);
+                    
+                    // Spawn NPC
+                    spawn_npc(&mut commands, &texture_atlases, &sprite_assets, npc_pos, &map.get_biome_at(npc_pos.0 as usize, npc_pos.1 as usize), &mut game_rng);
+                }
+                spawn_marked_npcs(&mut commands, &texture_atlases, &sprite_assets, &dungeon_state.levels[target_level], &mut game_rng);
+                
+                // Start fade in
+                spawn_fade_effect(&mut commands, true, None);
+            } else {
+                // Remove the fade effect entity
+                commands.entity(entity).despawn();
+                println!("Removed fade effect entity");
+            }
+        }
+    }
+}
+
+// Helper function to spawn a fade effect
+fn spawn_fade_effect(
+    commands: &mut Commands,
+    fade_in: bool,
+    target_level: Option<usize>,
+) {
+    let initial_alpha = if fade_in { 1.0 } else { 0.0 };
+    
+    // First, ensure we're creating a proper UI element with a background color
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            z_index: ZIndex::Global(100),
+            background_color: BackgroundColor(Color::rgba(0.0, 0.0, 0.0, initial_alpha)),
+            ..default()
+        },
+        FadeEffect {
+            timer: Timer::from_seconds(0.5, TimerMode::Once),
+            fade_in,
+            target_level,
+        },
+    ));
+    
+    // Log the fade effect creation for debugging
+    if fade_in {
+        println!("Created fade IN effect");
+    } else {
+        println!("Created fade OUT effect with target level: {:?}", target_level);
+    }
+}
+
+// Update the handle_map_regeneration function to include animals
+pub fn handle_map_regeneration(
+    mut commands: Commands,
+    texture_atlases: Res<TextureAtlases>,
+    sprite_assets: Res<SpriteAssets>,
+    asset_server: Res<AssetServer>,
+    map: Res<TileMap>,
+    biome_manager: Res<BiomeManager>,
+    animal_manager: Res<AnimalManager>,
+    existing_entities: Query<Entity, Or<(With<Tile>, With<Player>, With<Npc>, With<GridLine>, With<Animal>, With<InspectTooltip>, With<Chest>, With<crate::props::Prop>)>>,
+    mut tile_index: ResMut<TileIndex>,
+    mut ev_regenerate: EventReader<RegenerateMapEvent>,
+) {
+    // Only proceed if we received a regenerate map event
+    if ev_regenerate.read().next().is_none() {
+        return;
+    }
+    
+    println!("Handling map regeneration event");
+    
+    // The actual regeneration logic is now handled in regenerate_map_system
+    // This function is kept for compatibility with the existing event system
+}
@@ -0,0 +1,75 @@
+use bevy::prelude::*;
+use bevy::tasks::{block_on, AsyncComputeTaskPool, Task};
+use rand::rngs::StdRng;
+
+use crate::level::DungeonState;
+use crate::map::TileMap;
+use crate::rng::GameRng;
+
+/// A new level being generated off the main thread, so taking the stairs doesn't freeze the frame.
+/// The map generator stream travels with the task and comes back advanced, keeping seeded runs identical
+#[derive(Resource, Default)]
+pub struct LevelGeneration {
+    task: Option<Task<(TileMap, StdRng)>>,
+    target_level: usize,
+    ready: bool, // The level is in the dungeon and the stairs can be taken
+}
+
+impl LevelGeneration {
+    pub fn is_generating(&self) -> bool {
+        self.task.is_some()
+    }
+
+    // Start generating a level below the current one on the async compute pool
+    pub fn start(&mut self, previous: TileMap, target_level: usize, mut rng: StdRng) {
+        let task = AsyncComputeTaskPool::get().spawn(async move {
+            let map = TileMap::new_level(target_level, Some(&previous), &mut rng);
+            (map, rng)
+        });
+        self.task = Some(task);
+        self.target_level = target_level;
+        self.ready = false;
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.ready
+    }
+
+    pub fn target_level(&self) -> usize {
+        self.target_level
+    }
+
+    // Whether a finished level is waiting to be entered; only answers yes once
+    pub fn take_ready(&mut self) -> bool {
+        std::mem::take(&mut self.ready)
+    }
+}
+
+// System to drop any half-made level when a run starts
+pub fn reset_level_generation(mut level_generation: ResMut<LevelGeneration>) {
+    *level_generation = LevelGeneration::default();
+}
+
+// System to pick up a finished level and add it to the dungeon
+pub fn poll_level_generation(
+    mut level_generation: ResMut<LevelGeneration>,
+    mut dungeon_state: ResMut<DungeonState>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    let finished = level_generation.task.as_ref().map_or(false, |task| task.is_finished());
+    if !finished {
+        return;
+    }
+    let task = if let Some(task) = level_generation.task.take() { task } else { return; };
+    let (map, rng) = block_on(task);
+    game_rng.mapgen = rng;
+
+    // Only the next level down is ever generated, so it always goes on the end
+    if dungeon_state.levels.len() == level_generation.target_level {
+        dungeon_state.levels.push(map);
+        level_generation.ready = true;
+        println!("Level {} finished generating", level_generation.target_level);
+    } else {
+        eprintln!("Discarding generated level {}: the dungeon has {} levels", level_generation.target_level, dungeon_state.levels.len());
+    }
+}
@@ -0,0 +1,78 @@
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+use crate::assets::TextureAtlases;
+use crate::components::{Npc, Position};
+use crate::faction::Faction;
+use crate::npc::spawn_npc_entity;
+use crate::npc_registry::UniqueNpc;
+
+/// An NPC as it was when the player left its level
+#[derive(Debug, Clone)]
+pub struct NpcSnapshot {
+    pub npc: Npc,
+    pub position: (i32, i32),
+    pub sprite_index: usize,
+    pub faction: Faction,
+    pub unique: Option<UniqueNpc>,
+}
+
+/// What each level looked like when the player last left it, so coming back finds the same
+/// people who remember the same conversations, rather than strangers rolled afresh
+#[derive(Resource, Debug, Default)]
+pub struct LevelSnapshots {
+    npcs: HashMap<usize, Vec<NpcSnapshot>>,
+}
+
+impl LevelSnapshots {
+    // Remember the NPCs on a level that's being left
+    pub fn save<'a>(&mut self, level: usize, npcs: impl Iterator<Item = (&'a Npc, &'a Position, &'a TextureAtlasSprite, &'a Faction, Option<&'a UniqueNpc>)>) {
+        let saved: Vec<NpcSnapshot> = npcs
+            .map(|(npc, position, sprite, faction, unique)| {
+                let mut npc = npc.clone();
+                npc.speaking = false; // Any conversation ends with the visit
+                NpcSnapshot {
+                    npc,
+                    position: (position.x, position.y),
+                    sprite_index: sprite.index,
+                    faction: *faction,
+                    unique: unique.copied(),
+                }
+            })
+            .collect();
+        println!("Saved {} NPCs on level {}", saved.len(), level);
+        self.npcs.insert(level, saved);
+    }
+
+    // Bring back the NPCs saved for a level, if it has been visited before
+    pub fn restore(&self, commands: &mut Commands, texture_atlases: &TextureAtlases, level: usize) {
+        for snapshot in self.npcs.get(&level).into_iter().flatten() {
+            let entity = spawn_npc_entity(commands, texture_atlases, snapshot.sprite_index, snapshot.position, snapshot.npc.clone(), snapshot.faction);
+            if let Some(unique) = snapshot.unique {
+                commands.entity(entity).insert(unique);
+            }
+        }
+    }
+
+    // Whether the player has left this level before
+    pub fn has(&self, level: usize) -> bool {
+        self.npcs.contains_key(&level)
+    }
+
+    // Take a roster member off a level they've moved on from
+    pub fn forget_unique(&mut self, level: usize, id: usize) {
+        if let Some(npcs) = self.npcs.get_mut(&level) {
+            npcs.retain(|snapshot| snapshot.unique.map_or(true, |unique| unique.id != id));
+        }
+    }
+
+    // Drop a level's snapshot, e.g. when it has been regenerated
+    pub fn forget(&mut self, level: usize) {
+        self.npcs.remove(&level);
+    }
+}
+
+// System to start each run without any remembered levels
+pub fn reset_level_snapshots(mut snapshots: ResMut<LevelSnapshots>) {
+    *snapshots = LevelSnapshots::default();
+}
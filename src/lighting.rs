@@ -0,0 +1,259 @@
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use crate::assets::{get_tile_sprite, SpriteAssets, TextureAtlases};
+use crate::biome::BiomeType;
+use crate::components::{Position, Tile};
+use crate::escalation::{Escalation, EscalationSettings};
+use crate::input::TILE_SIZE;
+use crate::map::{TileMap, TilePos, TileType, MAP_HEIGHT, MAP_WIDTH};
+use crate::visibility::{line_of_sight, VisibilityMap};
+
+// Brightness of tiles no light reaches, before any map is loaded
+const AMBIENT_LIGHT: f32 = 0.3;
+
+/// Something that casts colored light around itself
+#[derive(Component, Debug, Clone)]
+pub struct LightSource {
+    pub color: Color,
+    pub radius: i32,
+    pub intensity: f32,
+}
+
+impl LightSource {
+    // The warm light of the player's torch
+    pub fn torch() -> Self {
+        Self { color: Color::rgb(1.0, 0.85, 0.6), radius: 6, intensity: 1.0 }
+    }
+}
+
+/// Light-emitting fixtures placed on the map
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LightFixture {
+    Brazier,
+    LavaVent,
+    GlowingFungus,
+}
+
+impl LightFixture {
+    fn light(&self) -> LightSource {
+        match self {
+            LightFixture::Brazier => LightSource { color: Color::rgb(1.0, 0.6, 0.25), radius: 5, intensity: 0.9 },
+            LightFixture::LavaVent => LightSource { color: Color::rgb(1.0, 0.3, 0.1), radius: 3, intensity: 1.2 },
+            LightFixture::GlowingFungus => LightSource { color: Color::rgb(0.3, 1.0, 0.6), radius: 3, intensity: 0.7 },
+        }
+    }
+}
+
+/// Cached per-tile light levels
+#[derive(Resource)]
+pub struct LightMap {
+    pub light: Vec<Vec<Color>>,
+}
+
+impl Default for LightMap {
+    fn default() -> Self {
+        Self {
+            light: vec![vec![Color::rgb(AMBIENT_LIGHT, AMBIENT_LIGHT, AMBIENT_LIGHT); MAP_WIDTH]; MAP_HEIGHT],
+        }
+    }
+}
+
+impl LightMap {
+    pub fn get(&self, x: i32, y: i32) -> Color {
+        if x < 0 || y < 0 {
+            return Color::BLACK;
+        }
+        self.light.get(y as usize)
+            .and_then(|row| row.get(x as usize))
+            .copied()
+            .unwrap_or(Color::BLACK)
+    }
+
+    // Recompute every tile from scratch, with only `dim` of the usual light
+    fn recalculate(&mut self, map: &TileMap, sources: &[(Position, LightSource)], dim: f32) {
+        // Deeper levels are darker (see the depth progression table)
+        let ambient = map.depth_tier.ambient_light * dim;
+        let mut light = vec![vec![[ambient; 3]; map.width]; map.height];
+
+        for (position, source) in sources {
+            for y in (position.y - source.radius)..=(position.y + source.radius) {
+                for x in (position.x - source.radius)..=(position.x + source.radius) {
+                    if !map.in_bounds(x, y) {
+                        continue;
+                    }
+
+                    let distance = (((x - position.x).pow(2) + (y - position.y).pow(2)) as f32).sqrt();
+                    if distance > source.radius as f32 {
+                        continue;
+                    }
+                    if !line_of_sight(map, (position.x, position.y), (x, y)) {
+                        continue;
+                    }
+
+                    // Linear falloff toward the edge of the radius
+                    let strength = source.intensity * dim * (1.0 - distance / (source.radius as f32 + 1.0));
+                    let tile = &mut light[y as usize][x as usize];
+                    tile[0] += source.color.r() * strength;
+                    tile[1] += source.color.g() * strength;
+                    tile[2] += source.color.b() * strength;
+                }
+            }
+        }
+
+        self.light = light
+            .into_iter()
+            .map(|row| row.into_iter().map(|[r, g, b]| Color::rgb(r.min(1.0), g.min(1.0), b.min(1.0))).collect())
+            .collect();
+    }
+}
+
+// Pick spots for light fixtures; seeded from the layout so a revisited level looks the same
+fn place_fixtures(map: &TileMap) -> Vec<(LightFixture, (usize, usize))> {
+    let seed = (map.current_level as u64) << 32
+        ^ (map.spawn_position.0 as u64) << 16
+        ^ map.spawn_position.1 as u64
+        ^ (map.rooms.len() as u64) << 40;
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let biome = map.get_biome_at(0, 0); // All maps currently use a single biome
+    let mut fixtures = Vec::new();
+
+    let mut floor_tiles = Vec::new();
+    let mut wall_hugging_tiles = Vec::new();
+    for y in 1..map.height - 1 {
+        for x in 1..map.width - 1 {
+            if map.tiles[y][x] != TileType::Floor {
+                continue;
+            }
+            floor_tiles.push((x, y));
+            let touches_wall = [(0i32, 1i32), (1, 0), (0, -1), (-1, 0)].iter().any(|(dx, dy)| {
+                map.tiles[(y as i32 + dy) as usize][(x as i32 + dx) as usize] == TileType::Wall
+            });
+            if touches_wall {
+                wall_hugging_tiles.push((x, y));
+            }
+        }
+    }
+
+    match biome {
+        BiomeType::Groves => {
+            // Fungi glow along the walls
+            wall_hugging_tiles.shuffle(&mut rng);
+            for &tile in wall_hugging_tiles.iter().take(8) {
+                fixtures.push((LightFixture::GlowingFungus, tile));
+            }
+        }
+        BiomeType::Caves => {
+            floor_tiles.shuffle(&mut rng);
+            for &tile in floor_tiles.iter().take(3) {
+                fixtures.push((LightFixture::LavaVent, tile));
+            }
+        }
+        _ => {}
+    }
+
+    // Braziers burn in the middle of some rooms (the groves are lit well enough)
+    if biome != BiomeType::Groves {
+        let mut rooms = map.rooms.clone();
+        rooms.shuffle(&mut rng);
+        for room in rooms.iter().take(4) {
+            let center = (room.x + room.width / 2, room.y + room.height / 2);
+            if center.0 < map.width && center.1 < map.height
+                && map.tiles[center.1][center.0] == TileType::Floor
+                && center != map.spawn_position
+            {
+                fixtures.push((LightFixture::Brazier, center));
+            }
+        }
+    }
+
+    fixtures
+}
+
+// System to replace the light fixtures whenever a different map is loaded
+pub fn sync_light_fixtures(
+    mut commands: Commands,
+    map: Res<TileMap>,
+    fixture_query: Query<Entity, With<LightFixture>>,
+    texture_atlases: Res<TextureAtlases>,
+    sprite_assets: Res<SpriteAssets>,
+) {
+    for entity in fixture_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    for (fixture, (x, y)) in place_fixtures(&map) {
+        let translation = Vec3::new(
+            x as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+            y as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+            0.5, // Just above the floor
+        );
+
+        let mut entity = match fixture {
+            LightFixture::GlowingFungus => commands.spawn(SpriteSheetBundle {
+                texture_atlas: texture_atlases.tiles.clone(),
+                sprite: TextureAtlasSprite {
+                    index: get_tile_sprite(&sprite_assets, "small mushrooms"),
+                    color: Color::rgb(0.6, 1.0, 0.8),
+                    ..default()
+                },
+                transform: Transform::from_translation(translation),
+                ..default()
+            }),
+            LightFixture::LavaVent => commands.spawn(SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgba(1.0, 0.35, 0.05, 0.8),
+                    custom_size: Some(Vec2::splat(TILE_SIZE)),
+                    ..default()
+                },
+                transform: Transform::from_translation(translation),
+                ..default()
+            }),
+            LightFixture::Brazier => commands.spawn(SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgb(1.0, 0.55, 0.1),
+                    custom_size: Some(Vec2::splat(TILE_SIZE / 3.0)),
+                    ..default()
+                },
+                transform: Transform::from_translation(translation),
+                ..default()
+            }),
+        };
+
+        entity.insert((fixture, fixture.light(), Position::new(x as i32, y as i32)));
+    }
+}
+
+// System to rebuild the light map, only when a light or what can be seen has changed
+pub fn update_light_map(
+    mut light_map: ResMut<LightMap>,
+    map: Res<TileMap>,
+    visibility_map: Option<Res<VisibilityMap>>,
+    sources: Query<(&Position, &LightSource)>,
+    changed_sources: Query<(), (With<LightSource>, Or<(Changed<LightSource>, Changed<Position>)>)>,
+    mut removed_sources: RemovedComponents<LightSource>,
+    changed_tiles: Query<(), Changed<Tile>>, // New tiles, or ones rewritten for another level
+    mut tile_query: Query<(&TilePos, &mut TextureAtlasSprite), With<Tile>>,
+    escalation: Res<Escalation>,
+    escalation_settings: Res<EscalationSettings>,
+) {
+    let sources_changed = !changed_sources.is_empty() || removed_sources.read().count() > 0;
+    let fov_changed = visibility_map.map_or(false, |visibility| visibility.is_changed());
+
+    if !sources_changed && !fov_changed && !map.is_changed() && changed_tiles.is_empty() && !escalation.is_changed() {
+        return;
+    }
+
+    let sources: Vec<(Position, LightSource)> = sources.iter().map(|(position, source)| (*position, source.clone())).collect();
+    light_map.recalculate(&map, &sources, escalation.light_factor(&escalation_settings));
+
+    // Tint the tiles; alpha is left alone since update_tile_visibility uses it for fog of war
+    for (pos, mut sprite) in tile_query.iter_mut() {
+        let light = light_map.get(pos.x, pos.y);
+        let alpha = sprite.color.a();
+        sprite.color = Color::rgba(light.r(), light.g(), light.b(), alpha);
+    }
+}
@@ -0,0 +1,111 @@
+use bevy::prelude::*;
+
+use crate::dialogue::generate_cryptic_dialogue;
+use crate::level::DungeonState;
+use crate::level_generation::LevelGeneration;
+use crate::menu::screen_root;
+use crate::rng::GameRng;
+use crate::GameState;
+
+// Keep the screen up at least this long, so a quick level doesn't just flash it
+const MIN_LOADING_SECONDS: f32 = 1.0;
+// Frames of the spinner, cycled while the level is being dug out
+const SPINNER_FRAMES: [&str; 4] = ["|", "/", "-", "\\"];
+const SPINNER_FRAME_SECONDS: f32 = 0.1;
+
+/// Between-floors bookkeeping. `resuming` is set while coming back from the loading screen or a cutscene,
+/// so the run setup on entering the game doesn't start the run over
+#[derive(Resource, Debug, Default)]
+pub struct LoadingScreen {
+    resuming: bool,
+    shown_for: f32,
+}
+
+impl LoadingScreen {
+    // Come back into the game without starting the run over, e.g. after a cutscene
+    pub fn resume_run(&mut self) {
+        self.resuming = true;
+    }
+}
+
+/// Marker for everything on the loading screen
+#[derive(Component)]
+pub struct LoadingScreenRoot;
+
+#[derive(Component)]
+pub struct LoadingSpinner;
+
+#[derive(Component)]
+pub struct LoadingBiomeText;
+
+// Run condition for the systems that set up a fresh run on entering the game
+pub fn starting_run(loading_screen: Res<LoadingScreen>) -> bool {
+    !loading_screen.resuming
+}
+
+// System to clear the resuming flag once the game is running again
+pub fn finish_resuming(mut loading_screen: ResMut<LoadingScreen>) {
+    if loading_screen.resuming {
+        loading_screen.resuming = false;
+    }
+}
+
+// Show the depth being descended to and a line from the deep; the biome is filled in once it's known
+pub fn setup_loading_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    level_generation: Res<LevelGeneration>,
+    mut loading_screen: ResMut<LoadingScreen>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    loading_screen.shown_for = 0.0;
+    let font = asset_server.load("fonts/FiraSans-Light.ttf");
+    let style = |size: f32, color: Color| TextStyle { font: font.clone(), font_size: size, color };
+    let flavor = generate_cryptic_dialogue(&mut game_rng.dialogue).into_iter().next().unwrap_or_default();
+
+    commands.spawn((screen_root(), LoadingScreenRoot)).with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            format!("Depth {}", level_generation.target_level() + 1),
+            style(48.0, Color::GOLD),
+        ));
+        parent.spawn((TextBundle::from_section("", style(24.0, Color::WHITE)), LoadingBiomeText));
+        parent.spawn(TextBundle::from_section(flavor, style(20.0, Color::rgb(0.6, 0.6, 0.7))));
+        parent.spawn((TextBundle::from_section("", style(24.0, Color::WHITE)), LoadingSpinner));
+    });
+}
+
+// System to spin the spinner, name the biome once generated, and go back to the game when done
+pub fn update_loading_screen(
+    time: Res<Time>,
+    level_generation: Res<LevelGeneration>,
+    dungeon_state: Res<DungeonState>,
+    mut loading_screen: ResMut<LoadingScreen>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut spinner_query: Query<&mut Text, (With<LoadingSpinner>, Without<LoadingBiomeText>)>,
+    mut biome_query: Query<&mut Text, (With<LoadingBiomeText>, Without<LoadingSpinner>)>,
+) {
+    loading_screen.shown_for += time.delta_seconds();
+
+    let frame = (time.elapsed_seconds() / SPINNER_FRAME_SECONDS) as usize % SPINNER_FRAMES.len();
+    for mut text in spinner_query.iter_mut() {
+        text.sections[0].value = if level_generation.is_ready() { String::new() } else { SPINNER_FRAMES[frame].to_string() };
+    }
+
+    if !level_generation.is_ready() {
+        return;
+    }
+    // The new level is the one the player arrives on, at its up stairs
+    if let Some(map) = dungeon_state.levels.get(level_generation.target_level()) {
+        let (x, y) = map.up_stairs_pos.unwrap_or(map.spawn_position);
+        for mut text in biome_query.iter_mut() {
+            if text.sections[0].value.is_empty() {
+                text.sections[0].value = map.get_biome_at(x, y).get_name().to_string();
+            }
+        }
+    }
+
+    if loading_screen.shown_for >= MIN_LOADING_SECONDS {
+        loading_screen.resume_run();
+        next_state.set(GameState::InGame);
+    }
+}
@@ -0,0 +1,109 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::biome::BiomeType;
+use crate::inventory::ItemKind;
+
+// Most items a single roll can produce
+const MAX_LOOT_ITEMS: usize = 4;
+
+// One possible drop in a loot table
+pub struct LootEntry {
+    pub item: ItemKind,
+    pub weight: u32,      // Base chance relative to the other entries
+    pub depth_bonus: u32, // Extra weight per dungeon level
+    pub min_depth: usize, // Never drops above this level
+}
+
+impl LootEntry {
+    fn new(item: ItemKind, weight: u32, depth_bonus: u32, min_depth: usize) -> Self {
+        Self { item, weight, depth_bonus, min_depth }
+    }
+
+    fn weight_at(&self, depth: usize) -> u32 {
+        if depth < self.min_depth {
+            0
+        } else {
+            self.weight + self.depth_bonus * depth as u32
+        }
+    }
+}
+
+// The loot table for a biome
+pub fn loot_table(biome: BiomeType) -> Vec<LootEntry> {
+    let mut table = vec![
+        LootEntry::new(ItemKind::Ration, 10, 0, 0),
+        LootEntry::new(ItemKind::HealingPotion, 5, 1, 0),
+        LootEntry::new(ItemKind::ManaPotion, 3, 1, 1),
+        LootEntry::new(ItemKind::Key, 2, 0, 0),
+        LootEntry::new(ItemKind::Lockpick, 3, 0, 0),
+        LootEntry::new(ItemKind::PoisonPotion, 2, 1, 1),
+        LootEntry::new(ItemKind::ScrollOfIdentify, 3, 0, 0),
+        LootEntry::new(ItemKind::ScrollOfBlessing, 2, 0, 1),
+        LootEntry::new(ItemKind::ScrollOfCursing, 2, 0, 1),
+        LootEntry::new(ItemKind::Dagger, 3, 0, 0),
+    ];
+
+    // Each biome leans toward what it has plenty of
+    match biome {
+        BiomeType::Groves => {
+            table.push(LootEntry::new(ItemKind::Ration, 8, 0, 0));
+            table.push(LootEntry::new(ItemKind::Antidote, 6, 0, 0));
+        }
+        BiomeType::Caves => {
+            table.push(LootEntry::new(ItemKind::Lockpick, 3, 0, 0));
+            table.push(LootEntry::new(ItemKind::Antidote, 2, 0, 0));
+        }
+        BiomeType::Labyrinth => {
+            table.push(LootEntry::new(ItemKind::Key, 5, 0, 0));
+        }
+        BiomeType::Catacombs => {
+            table.push(LootEntry::new(ItemKind::ManaPotion, 4, 2, 0));
+            table.push(LootEntry::new(ItemKind::HealingPotion, 2, 1, 0));
+        }
+    }
+
+    table
+}
+
+// Roll the contents of a container found at the given depth
+pub fn roll_loot(biome: BiomeType, depth: usize, rng: &mut impl Rng) -> Vec<ItemKind> {
+    let table = loot_table(biome);
+
+    // Deeper containers hold more
+    let count = (1 + depth / 4 + rng.gen_range(0..=1)).min(MAX_LOOT_ITEMS);
+
+    let mut items = Vec::new();
+    for _ in 0..count {
+        if let Ok(entry) = table.choose_weighted(rng, |entry| entry.weight_at(depth)) {
+            items.push(entry.item);
+        }
+    }
+    items
+}
+
+/// Something a creature may leave on its body when it dies
+pub struct CreatureDrop {
+    pub item: ItemKind,
+    pub chance: f64,      // Chance (0.0 - 1.0) that any turns up at all
+    pub max_count: usize, // How many at most, when it does
+}
+
+impl CreatureDrop {
+    pub fn new(item: ItemKind, chance: f64, max_count: usize) -> Self {
+        Self { item, chance, max_count }
+    }
+}
+
+// Roll what a dead creature leaves behind from its drop table
+pub fn roll_drops(drops: &[CreatureDrop], rng: &mut impl Rng) -> Vec<ItemKind> {
+    let mut items = Vec::new();
+    for drop in drops {
+        if drop.max_count == 0 || !rng.gen_bool(drop.chance.clamp(0.0, 1.0)) {
+            continue;
+        }
+        let count = rng.gen_range(1..=drop.max_count);
+        items.extend(std::iter::repeat(drop.item).take(count));
+    }
+    items
+}
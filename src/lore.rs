@@ -0,0 +1,66 @@
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::biome::BiomeType;
+use crate::codex::Codex;
+use crate::dialogue::{generate_biome_cryptic_dialogue, generate_cryptic_dialogue, CharacterType};
+use crate::interaction::{InteractedWith, InteractionKind};
+use crate::props::ReadableKind;
+use crate::ui::MessageLog;
+
+// Whose graves these are: the same kinds of folk met alive further up
+const BURIED: [CharacterType; 7] = [
+    CharacterType::Dwarf,
+    CharacterType::Elf,
+    CharacterType::Knight,
+    CharacterType::Priest,
+    CharacterType::Wizard,
+    CharacterType::Ranger,
+    CharacterType::Generic,
+];
+
+/// What is written on a sign, gravestone or tablet
+#[derive(Component, Debug, Clone)]
+pub struct Readable {
+    pub kind: ReadableKind,
+    pub title: String, // Names it in the codex
+    pub text: String,
+}
+
+// Write the words for a readable prop, from the same cryptic lines the strangers speak
+pub fn generate_readable(kind: ReadableKind, biome: BiomeType, rng: &mut impl Rng) -> Readable {
+    let biome_line = generate_biome_cryptic_dialogue(&biome, rng);
+    let lines = generate_cryptic_dialogue(rng).join(" ");
+
+    let (title, text) = match kind {
+        ReadableKind::Gravestone => {
+            let name = BURIED.choose(rng).unwrap_or(&CharacterType::Generic).generate_name(rng);
+            (format!("Grave of {}", name), format!("Here lies {}. \"{}\"", name, biome_line))
+        }
+        ReadableKind::Tablet => {
+            (format!("Tablet: {}", biome_line.trim_end_matches('.')), format!("The tablet reads: {} {}", biome_line, lines))
+        }
+        ReadableKind::Sign => {
+            (format!("Sign: {}", lines.trim_end_matches('.')), format!("Scratched into the sign: \"{}\"", lines))
+        }
+    };
+    Readable { kind, title, text }
+}
+
+// System to show what E was used to read, noting it in the codex the first time
+pub fn read_readables_system(
+    mut interactions: EventReader<InteractedWith>,
+    readable_query: Query<&Readable>,
+    mut codex: ResMut<Codex>,
+    mut message_log: ResMut<MessageLog>,
+) {
+    for interaction in interactions.read().filter(|interaction| interaction.kind == InteractionKind::Read) {
+        let readable = if let Ok(readable) = readable_query.get(interaction.target) { readable } else { continue; };
+        message_log.add_message(readable.text.clone());
+        if codex.record_reading(&readable.title, &readable.text) {
+            message_log.add_message(format!("Codex: {} added", readable.title));
+        }
+        println!("Read a {}: {}", readable.kind.get_name(), readable.text);
+    }
+}
@@ -0,0 +1,147 @@
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Where the manifest lives, relative to the assets folder
+pub const MANIFEST_PATH: &str = "sprites/manifest.json";
+
+// Atlases the game can't run without
+const REQUIRED_ATLASES: [&str; 5] = ["tiles", "characters", "monsters", "items", "animals"];
+
+/// Describes every sprite atlas the game loads
+#[derive(Debug, Clone, Deserialize)]
+pub struct AssetManifest {
+    pub atlases: Vec<AtlasManifest>,
+}
+
+/// One sprite sheet: its image, grid layout and named sprites
+#[derive(Debug, Clone, Deserialize)]
+pub struct AtlasManifest {
+    pub name: String,
+    pub image: String,   // Relative to the assets folder
+    pub tile_size: f32,  // Sprites are square
+    pub columns: usize,
+    pub rows: usize,
+    pub sprites: Vec<SpriteEntry>,
+}
+
+/// A named cell in an atlas grid (row and col are zero-based)
+#[derive(Debug, Clone, Deserialize)]
+pub struct SpriteEntry {
+    pub name: String,
+    pub row: usize,
+    pub col: usize,
+    #[serde(default)]
+    pub tags: Vec<String>, // e.g. wall, floor, door, stairs
+}
+
+/// Everything that can go wrong loading the manifest
+#[derive(Debug)]
+pub enum ManifestError {
+    Io(String, std::io::Error),
+    Parse(serde_json::Error),
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ManifestError::Io(path, e) => write!(f, "could not read asset manifest {}: {}", path, e),
+            ManifestError::Parse(e) => write!(f, "asset manifest is not valid JSON: {}", e),
+            ManifestError::Invalid(problems) => {
+                writeln!(f, "asset manifest has {} problem(s):", problems.len())?;
+                for problem in problems {
+                    writeln!(f, "  - {}", problem)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ManifestError {}
+
+impl AtlasManifest {
+    pub fn index_of(&self, sprite: &SpriteEntry) -> usize {
+        sprite.row * self.columns + sprite.col
+    }
+
+    /// Name -> atlas index for every sprite in the atlas
+    pub fn sprite_indices(&self) -> HashMap<String, usize> {
+        self.sprites.iter().map(|sprite| (sprite.name.clone(), self.index_of(sprite))).collect()
+    }
+
+    /// Name -> tags for every sprite in the atlas
+    pub fn sprite_tags(&self) -> HashMap<String, Vec<String>> {
+        self.sprites.iter().map(|sprite| (sprite.name.clone(), sprite.tags.clone())).collect()
+    }
+}
+
+impl AssetManifest {
+    /// Read, parse and validate the manifest under the given assets folder
+    pub fn load(assets_dir: &Path) -> Result<Self, ManifestError> {
+        let path = assets_dir.join(MANIFEST_PATH);
+        let contents = fs::read_to_string(&path)
+            .map_err(|e| ManifestError::Io(path.display().to_string(), e))?;
+        Self::from_json(&contents, Some(assets_dir))
+    }
+
+    /// Parse and validate manifest JSON; image files are only checked when an assets folder is given
+    pub fn from_json(contents: &str, assets_dir: Option<&Path>) -> Result<Self, ManifestError> {
+        let manifest: AssetManifest = serde_json::from_str(contents).map_err(ManifestError::Parse)?;
+
+        let problems = manifest.validate(assets_dir);
+        if problems.is_empty() {
+            Ok(manifest)
+        } else {
+            Err(ManifestError::Invalid(problems))
+        }
+    }
+
+    pub fn atlas(&self, name: &str) -> Option<&AtlasManifest> {
+        self.atlases.iter().find(|atlas| atlas.name == name)
+    }
+
+    // Collect every problem rather than stopping at the first, so they can all be fixed at once
+    fn validate(&self, assets_dir: Option<&Path>) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        for required in REQUIRED_ATLASES {
+            if self.atlas(required).is_none() {
+                problems.push(format!("missing required atlas '{}'", required));
+            }
+        }
+
+        let mut atlas_names = HashSet::new();
+        for atlas in &self.atlases {
+            if !atlas_names.insert(atlas.name.as_str()) {
+                problems.push(format!("atlas '{}' is defined more than once", atlas.name));
+            }
+            if atlas.columns == 0 || atlas.rows == 0 || atlas.tile_size <= 0.0 {
+                problems.push(format!("atlas '{}' has an empty grid", atlas.name));
+            }
+            if let Some(dir) = assets_dir {
+                if !dir.join(&atlas.image).exists() {
+                    problems.push(format!("atlas '{}' image {} does not exist", atlas.name, atlas.image));
+                }
+            }
+
+            let mut sprite_names = HashSet::new();
+            for sprite in &atlas.sprites {
+                if !sprite_names.insert(sprite.name.as_str()) {
+                    problems.push(format!("atlas '{}' has more than one sprite named '{}'", atlas.name, sprite.name));
+                }
+                if sprite.row >= atlas.rows || sprite.col >= atlas.columns {
+                    problems.push(format!(
+                        "sprite '{}' in atlas '{}' is at row {} col {}, outside the {}x{} grid",
+                        sprite.name, atlas.name, sprite.row, sprite.col, atlas.columns, atlas.rows
+                    ));
+                }
+            }
+        }
+
+        problems
+    }
+}
@@ -1,5 +1,6 @@
 use bevy::prelude::*;
 use rand::Rng;
+use std::collections::HashMap;
 use rand::seq::SliceRandom;
 use rand::rngs::StdRng;
 use rand::SeedableRng;
@@ -8,9 +9,39 @@ use crate::assets::{SpriteAssets, TextureAtlases};
 use crate::visibility::{VisibilityMap, TileVisibility};
 use crate::biome::{BiomeManager, TileWalkability};
 use crate::input::TILE_SIZE;
+use crate::progression::{DepthProgression, DepthTier};
+use crate::vault::{self, PlacedVault};
+use crate::props::{place_props, PropPlacement};
 
+// Size of the first level; deeper levels grow from here (see map_size_for_level)
 pub const MAP_WIDTH: usize = 45;
 pub const MAP_HEIGHT: usize = 25;
+// Tiles shown across the window at zoom 1, independent of the map size
+pub const VIEWPORT_WIDTH: usize = 45;
+pub const VIEWPORT_HEIGHT: usize = 25;
+// Largest a level can grow
+pub const MAX_MAP_WIDTH: usize = 90;
+pub const MAX_MAP_HEIGHT: usize = 50;
+// Extra tiles added each level
+const MAP_GROWTH_PER_LEVEL: (usize, usize) = (3, 2);
+
+// The dimensions of the map for a (zero-based) level: deeper is bigger
+pub fn map_size_for_level(level: usize) -> (usize, usize) {
+    (
+        (MAP_WIDTH + MAP_GROWTH_PER_LEVEL.0 * level).min(MAX_MAP_WIDTH),
+        (MAP_HEIGHT + MAP_GROWTH_PER_LEVEL.1 * level).min(MAX_MAP_HEIGHT),
+    )
+}
+
+pub type TileGrid = Vec<Vec<TileType>>;
+pub type BiomeGrid = Vec<Vec<BiomeType>>;
+/// Tells one level's layout from another: which level, where it starts and where its stairs down are
+pub type LayoutKey = (usize, (usize, usize), Option<(usize, usize)>);
+
+// Width and height of a row-major grid
+pub fn grid_size<T>(grid: &[Vec<T>]) -> (usize, usize) {
+    (grid.first().map_or(0, |row| row.len()), grid.len())
+}
 
 // Rendering components
 #[derive(Component)]
@@ -23,21 +54,102 @@ pub struct TilePos {
 #[derive(Component)]
 pub struct GridLine;
 
-// Resource to track tile entities
+// Resource for O(1) lookup of the tile entity at a map position
+// Tiles stay one sprite entity each: lighting, fog of war and tile animation all write to
+// individual tile sprites, which a chunked tilemap would have to re-batch on every change.
 #[derive(Resource, Default)]
-pub struct TileEntities {
-    pub entities: Vec<Entity>,
+pub struct TileIndex {
+    entities: Vec<Option<Entity>>, // Row-major, `width` per row
+    width: usize,
+    height: usize,
+    grid_lines: Vec<Entity>,
+    grid_size: Option<(usize, usize)>, // The map size the grid lines were laid out for
+}
+
+impl TileIndex {
+    // The tile entity at a position, if one has been spawned there
+    pub fn get(&self, x: i32, y: i32) -> Option<Entity> {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return None;
+        }
+        self.entities[y as usize * self.width + x as usize]
+    }
+
+    // Replace the index with freshly spawned tiles (in spawn_tiles order: row by row)
+    pub fn rebuild(&mut self, map: &TileMap, tile_entities: &[Entity]) {
+        self.width = map.width;
+        self.height = map.height;
+        self.entities = vec![None; map.width * map.height];
+        for (i, &entity) in tile_entities.iter().enumerate().take(map.width * map.height) {
+            self.entities[i] = Some(entity);
+        }
+    }
+
+    // Every tile entity, row by row, for reusing on the next level
+    pub fn entities(&self) -> Vec<Entity> {
+        self.entities.iter().flatten().copied().collect()
+    }
+
+    // Start over from newly spawned grid lines (e.g. after everything was despawned for a new run)
+    pub fn set_grid_lines(&mut self, map: &TileMap, grid_lines: Vec<Entity>) {
+        self.grid_lines = grid_lines;
+        self.grid_size = Some((map.width, map.height));
+    }
+
+    pub fn clear(&mut self) {
+        self.entities.iter_mut().for_each(|entity| *entity = None);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.iter().filter(|entity| entity.is_some()).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.iter().all(|entity| entity.is_none())
+    }
 }
 
 #[derive(Component, Resource, Clone)]
 pub struct TileMap {
-    pub tiles: [[TileType; MAP_WIDTH]; MAP_HEIGHT],
+    pub width: usize,
+    pub height: usize,
+    pub tiles: TileGrid,  // Indexed [y][x]
     pub rooms: Vec<Room>,
-    pub biomes: [[BiomeType; MAP_WIDTH]; MAP_HEIGHT],
+    pub biomes: BiomeGrid,
     pub spawn_position: (usize, usize),
     pub down_stairs_pos: Option<(usize, usize)>,
     pub up_stairs_pos: Option<(usize, usize)>,
     pub current_level: usize,
+    pub is_boss_level: bool,
+    pub down_stairs_locked: bool, // Boss floors keep the way down sealed until the boss dies
+    pub chest_positions: Vec<(usize, usize)>, // Unopened chests
+    pub trap_positions: Vec<(usize, usize)>,  // Hidden traps that haven't been sprung
+    pub depth_tier: DepthTier,                // Difficulty and look for this depth
+    pub vaults: Vec<PlacedVault>,             // Prefab rooms stamped in
+    pub npc_spawns: Vec<(usize, usize)>,      // Where NPCs are placed on arrival, from vaults and room purposes
+    pub monster_spawns: Vec<(usize, usize)>,  // Creatures that must appear here, on top of the random ones
+    pub props: Vec<PropPlacement>,            // Decoration drawn above the floor; some block movement
+    pub portal_pairs: Vec<((usize, usize), (usize, usize))>, // Linked tiles that send the player to each other
+    pub altar_positions: Vec<(usize, usize)>, // One in every shrine room, to pray at
+    pub seed: u64,                            // Regenerates this exact layout via generate_level
+}
+
+// Chance for an ordinary room to hold a chest (secret rooms always do)
+const ROOM_CHEST_CHANCE: f64 = 0.15;
+const MAX_ROOM_CHESTS: usize = 3;
+
+// Chance a level gets a pair of portals; Labyrinth levels always get them, and more
+const PORTAL_CHANCE: f64 = 0.25;
+const LABYRINTH_PORTAL_PAIRS: usize = 2;
+// Portals only link rooms at least this far apart (in tiles, walking distance ignored)
+const PORTAL_MIN_DISTANCE: usize = 20;
+
+// Every Nth floor is a boss floor
+pub const BOSS_LEVEL_INTERVAL: usize = 5;
+
+// Whether a (zero-based) level index is a boss floor, i.e. floors 5, 10, 15...
+pub fn is_boss_level(level: usize) -> bool {
+    (level + 1) % BOSS_LEVEL_INTERVAL == 0
 }
 
 impl FromWorld for TileMap {
@@ -56,6 +168,43 @@ pub enum TileType {
     StairsUp,
 }
 
+// Characters used for each tile type in map text files
+pub fn tile_char(tile: TileType) -> char {
+    match tile {
+        TileType::Floor => '.',
+        TileType::Wall => '#',
+        TileType::Door => '+',
+        TileType::SecretDoor => '*',
+        TileType::StairsDown => '>',
+        TileType::StairsUp => '<',
+    }
+}
+
+pub fn tile_from_char(c: char) -> Option<TileType> {
+    match c {
+        '.' => Some(TileType::Floor),
+        '#' => Some(TileType::Wall),
+        '+' => Some(TileType::Door),
+        '*' => Some(TileType::SecretDoor),
+        '>' => Some(TileType::StairsDown),
+        '<' => Some(TileType::StairsUp),
+        _ => None,
+    }
+}
+
+impl TileType {
+    // What the player sees this tile as; secret doors pass for wall until found
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            TileType::Floor => "Floor",
+            TileType::Wall | TileType::SecretDoor => "Wall",
+            TileType::Door => "Door",
+            TileType::StairsDown => "Stairs down",
+            TileType::StairsUp => "Stairs up",
+        }
+    }
+}
+
 // Represents a rectangular room or section of the map
 #[derive(Debug, Clone)]
 pub struct Room {
@@ -64,6 +213,7 @@ pub struct Room {
     pub width: usize,
     pub height: usize,
     pub room_type: RoomType,
+    pub purpose: Option<RoomPurpose>, // Special rooms; most have none
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -77,6 +227,63 @@ pub enum RoomType {
     LargeHall,
 }
 
+// What a special room is for, which decides its floor, its inhabitants and its loot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoomPurpose {
+    Shrine,
+    Library,
+    Armory,
+    Nest,
+    Shop,
+}
+
+// Chance for an ordinary room to be given a purpose
+const ROOM_PURPOSE_CHANCE: f64 = 0.2;
+
+impl RoomPurpose {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            RoomPurpose::Shrine => "Shrine",
+            RoomPurpose::Library => "Library",
+            RoomPurpose::Armory => "Armory",
+            RoomPurpose::Nest => "Nest",
+            RoomPurpose::Shop => "Shop",
+        }
+    }
+
+    // Floor sprites laid over the biome's own floor in these rooms
+    pub fn floor_tiles(&self) -> &'static [&'static str] {
+        match self {
+            RoomPurpose::Shrine => &["blue stone floor 1 (blue bg)", "blue stone floor 2 (blue bg)", "blue stone floor 3 (blue bg)"],
+            RoomPurpose::Library => &["stone floor 1", "stone floor 2", "stone floor 3"],
+            RoomPurpose::Armory => &["red stone floor 1 (red bg)", "red stone floor 2 (red bg)", "red stone floor 3 (red bg)"],
+            RoomPurpose::Nest => &["bones 1 (dark brown bg)", "bones 2 (dark brown bg)", "bones 3 (dark brown bg)"],
+            RoomPurpose::Shop => &["floor stone 1", "floor stone 2", "floor stone 3"],
+        }
+    }
+
+    // How many NPCs keep the room, how many creatures lair in it, and how many chests it holds
+    fn spawn_table(&self) -> (usize, usize, usize) {
+        match self {
+            RoomPurpose::Shrine => (1, 0, 0),
+            RoomPurpose::Library => (1, 0, 1),
+            RoomPurpose::Armory => (0, 1, 2),
+            RoomPurpose::Nest => (0, 3, 0),
+            RoomPurpose::Shop => (1, 0, 0),
+        }
+    }
+
+    // Pick a purpose that suits a room of this size
+    fn choose_for(room: &Room, rng: &mut impl Rng) -> Self {
+        let options: &[RoomPurpose] = match room.size() {
+            RoomSize::Small => &[RoomPurpose::Shrine, RoomPurpose::Shop, RoomPurpose::Nest],
+            RoomSize::Medium => &[RoomPurpose::Shrine, RoomPurpose::Library, RoomPurpose::Armory, RoomPurpose::Nest, RoomPurpose::Shop],
+            RoomSize::Large => &[RoomPurpose::Library, RoomPurpose::Armory, RoomPurpose::Nest],
+        };
+        *options.choose(rng).unwrap_or(&RoomPurpose::Nest)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 enum RoomSize {
     Small,
@@ -86,7 +293,12 @@ enum RoomSize {
 
 impl Room {
     fn new(x: usize, y: usize, width: usize, height: usize, room_type: RoomType) -> Self {
-        Room { x, y, width, height, room_type }
+        Room { x, y, width, height, room_type, purpose: None }
+    }
+
+    // Whether a tile lies within the room's bounds
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
     }
 
     fn size(&self) -> RoomSize {
@@ -117,7 +329,7 @@ impl Room {
     }
 
     // Carve a room into the map based on its type
-    fn carve(&self, tiles: &mut [[TileType; MAP_WIDTH]; MAP_HEIGHT], rng: &mut impl Rng) {
+    fn carve(&self, tiles: &mut [Vec<TileType>], rng: &mut impl Rng) {
         match self.room_type {
             RoomType::Rectangular => self.carve_rectangular(tiles),
             RoomType::Circular => self.carve_circular(tiles),
@@ -130,10 +342,11 @@ impl Room {
     }
 
     // Carve a basic rectangular room
-    fn carve_rectangular(&self, tiles: &mut [[TileType; MAP_WIDTH]; MAP_HEIGHT]) {
+    fn carve_rectangular(&self, tiles: &mut [Vec<TileType>]) {
+        let (map_width, map_height) = grid_size(tiles);
         for y in self.y..self.y + self.height {
             for x in self.x..self.x + self.width {
-                if y > 0 && y < MAP_HEIGHT - 1 && x > 0 && x < MAP_WIDTH - 1 {
+                if y > 0 && y < map_height - 1 && x > 0 && x < map_width - 1 {
                     tiles[y][x] = TileType::Floor;
                 }
             }
@@ -141,7 +354,8 @@ impl Room {
     }
 
     // Carve a circular room
-    fn carve_circular(&self, tiles: &mut [[TileType; MAP_WIDTH]; MAP_HEIGHT]) {
+    fn carve_circular(&self, tiles: &mut [Vec<TileType>]) {
+        let (map_width, map_height) = grid_size(tiles);
         let center_x = self.x + self.width / 2;
         let center_y = self.y + self.height / 2;
         let radius_x = self.width as f32 / 2.0;
@@ -149,7 +363,7 @@ impl Room {
 
         for y in self.y..self.y + self.height {
             for x in self.x..self.x + self.width {
-                if y > 0 && y < MAP_HEIGHT - 1 && x > 0 && x < MAP_WIDTH - 1 {
+                if y > 0 && y < map_height - 1 && x > 0 && x < map_width - 1 {
                     // Calculate normalized distance from center
                     let dx = (x as f32 - center_x as f32) / radius_x;
                     let dy = (y as f32 - center_y as f32) / radius_y;
@@ -165,14 +379,15 @@ impl Room {
     }
 
     // Carve a cross-shaped room
-    fn carve_cross_shaped(&self, tiles: &mut [[TileType; MAP_WIDTH]; MAP_HEIGHT]) {
+    fn carve_cross_shaped(&self, tiles: &mut [Vec<TileType>]) {
+        let (map_width, map_height) = grid_size(tiles);
         let third_width = self.width / 3;
         let third_height = self.height / 3;
 
         // Carve the horizontal bar of the cross
         for y in self.y + third_height..self.y + 2 * third_height {
             for x in self.x..self.x + self.width {
-                if y > 0 && y < MAP_HEIGHT - 1 && x > 0 && x < MAP_WIDTH - 1 {
+                if y > 0 && y < map_height - 1 && x > 0 && x < map_width - 1 {
                     tiles[y][x] = TileType::Floor;
                 }
             }
@@ -181,7 +396,7 @@ impl Room {
         // Carve the vertical bar of the cross
         for y in self.y..self.y + self.height {
             for x in self.x + third_width..self.x + 2 * third_width {
-                if y > 0 && y < MAP_HEIGHT - 1 && x > 0 && x < MAP_WIDTH - 1 {
+                if y > 0 && y < map_height - 1 && x > 0 && x < map_width - 1 {
                     tiles[y][x] = TileType::Floor;
                 }
             }
@@ -189,14 +404,15 @@ impl Room {
     }
 
     // Carve an L-shaped room
-    fn carve_l_shaped(&self, tiles: &mut [[TileType; MAP_WIDTH]; MAP_HEIGHT]) {
+    fn carve_l_shaped(&self, tiles: &mut [Vec<TileType>]) {
+        let (map_width, map_height) = grid_size(tiles);
         let half_width = self.width / 2;
         let half_height = self.height / 2;
 
         // Carve the horizontal part of the L
         for y in self.y..self.y + half_height {
             for x in self.x..self.x + self.width {
-                if y > 0 && y < MAP_HEIGHT - 1 && x > 0 && x < MAP_WIDTH - 1 {
+                if y > 0 && y < map_height - 1 && x > 0 && x < map_width - 1 {
                     tiles[y][x] = TileType::Floor;
                 }
             }
@@ -205,7 +421,7 @@ impl Room {
         // Carve the vertical part of the L
         for y in self.y + half_height..self.y + self.height {
             for x in self.x..self.x + half_width {
-                if y > 0 && y < MAP_HEIGHT - 1 && x > 0 && x < MAP_WIDTH - 1 {
+                if y > 0 && y < map_height - 1 && x > 0 && x < map_width - 1 {
                     tiles[y][x] = TileType::Floor;
                 }
             }
@@ -213,7 +429,8 @@ impl Room {
     }
 
     // Carve a room with pillars
-    fn carve_pillared(&self, tiles: &mut [[TileType; MAP_WIDTH]; MAP_HEIGHT], rng: &mut impl Rng) {
+    fn carve_pillared(&self, tiles: &mut [Vec<TileType>], rng: &mut impl Rng) {
+        let (map_width, map_height) = grid_size(tiles);
         // First carve the basic rectangular room
         self.carve_rectangular(tiles);
 
@@ -233,7 +450,7 @@ impl Room {
             // Create a 2x2 pillar
             for py in pillar_y..pillar_y + 2 {
                 for px in pillar_x..pillar_x + 2 {
-                    if py < MAP_HEIGHT && px < MAP_WIDTH {
+                    if py < map_height && px < map_width {
                         tiles[py][px] = TileType::Wall;
                     }
                 }
@@ -242,7 +459,8 @@ impl Room {
     }
 
     // Carve a small chamber (simple, possibly irregular shape)
-    fn carve_small_chamber(&self, tiles: &mut [[TileType; MAP_WIDTH]; MAP_HEIGHT]) {
+    fn carve_small_chamber(&self, tiles: &mut [Vec<TileType>]) {
+        let (map_width, map_height) = grid_size(tiles);
         // Basic rectangular shape for small chambers
         self.carve_rectangular(tiles);
         
@@ -253,14 +471,14 @@ impl Room {
             let corner_y = self.y;
             
             // Make the corner a wall again
-            if corner_x < MAP_WIDTH && corner_y < MAP_HEIGHT {
+            if corner_x < map_width && corner_y < map_height {
                 tiles[corner_y][corner_x] = TileType::Wall;
             }
         }
     }
     
     // Carve a large hall with possible features
-    fn carve_large_hall(&self, tiles: &mut [[TileType; MAP_WIDTH]; MAP_HEIGHT], rng: &mut impl Rng) {
+    fn carve_large_hall(&self, tiles: &mut [Vec<TileType>], rng: &mut impl Rng) {
         // First carve the basic rectangular room
         self.carve_rectangular(tiles);
         
@@ -279,7 +497,8 @@ impl Room {
     }
     
     // Add a central feature to a large hall
-    fn add_central_feature(&self, tiles: &mut [[TileType; MAP_WIDTH]; MAP_HEIGHT], rng: &mut impl Rng) {
+    fn add_central_feature(&self, tiles: &mut [Vec<TileType>], rng: &mut impl Rng) {
+        let (map_width, map_height) = grid_size(tiles);
         let center_x = self.x + self.width / 2;
         let center_y = self.y + self.height / 2;
         
@@ -288,7 +507,7 @@ impl Room {
         
         for y in center_y - feature_size / 2..=center_y + feature_size / 2 {
             for x in center_x - feature_size / 2..=center_x + feature_size / 2 {
-                if x > 0 && x < MAP_WIDTH - 1 && y > 0 && y < MAP_HEIGHT - 1 {
+                if x > 0 && x < map_width - 1 && y > 0 && y < map_height - 1 {
                     tiles[y][x] = TileType::Wall;
                 }
             }
@@ -296,7 +515,8 @@ impl Room {
     }
     
     // Add columns to a large hall
-    fn add_columns(&self, tiles: &mut [[TileType; MAP_WIDTH]; MAP_HEIGHT], rng: &mut impl Rng) {
+    fn add_columns(&self, tiles: &mut [Vec<TileType>], rng: &mut impl Rng) {
+        let (map_width, map_height) = grid_size(tiles);
         // Calculate column positions
         let columns_per_row = (self.width / 4).max(2);
         let columns_per_col = (self.height / 4).max(2);
@@ -321,7 +541,7 @@ impl Room {
                 let column_y = (column_y_i32 + random_offset_y) as usize;
                 
                 // Ensure we're within bounds
-                if column_x > 0 && column_x < MAP_WIDTH - 1 && column_y > 0 && column_y < MAP_HEIGHT - 1 {
+                if column_x > 0 && column_x < map_width - 1 && column_y > 0 && column_y < map_height - 1 {
                     tiles[column_y][column_x] = TileType::Wall;
                 }
             }
@@ -329,7 +549,8 @@ impl Room {
     }
     
     // Add a divider to create a more complex room
-    fn add_divider(&self, tiles: &mut [[TileType; MAP_WIDTH]; MAP_HEIGHT], rng: &mut impl Rng) {
+    fn add_divider(&self, tiles: &mut [Vec<TileType>], rng: &mut impl Rng) {
+        let (map_width, map_height) = grid_size(tiles);
         // Decide whether to add a horizontal or vertical divider
         let is_horizontal = self.width > self.height || (self.width == self.height && rng.gen_bool(0.5));
         
@@ -341,7 +562,7 @@ impl Room {
             
             for x in self.x + 1..self.x + self.width - 1 {
                 if x < gap_start || x > gap_end {
-                    if divider_y < MAP_HEIGHT {
+                    if divider_y < map_height {
                         tiles[divider_y][x] = TileType::Wall;
                     }
                 }
@@ -354,7 +575,7 @@ impl Room {
             
             for y in self.y + 1..self.y + self.height - 1 {
                 if y < gap_start || y > gap_end {
-                    if divider_x < MAP_WIDTH {
+                    if divider_x < map_width {
                         tiles[y][divider_x] = TileType::Wall;
                     }
                 }
@@ -365,69 +586,324 @@ impl Room {
 
 impl TileMap {
     pub fn new() -> Self {
-        let mut rng = rand::thread_rng();
-        let (tiles, rooms, biomes, spawn_position) = Self::generate_map(&mut rng);
+        // A replay starts from the recorded first level instead of a fresh one
+        let seed = crate::run_log::replay_initial_seed().unwrap_or_else(rand::random);
+        Self::generate_level(0, seed)
+    }
+    
+    // Create a new map for a specific level, its seed drawn from the run's map generation stream
+    pub fn new_level(level: usize, previous_map: Option<&TileMap>, rng: &mut impl Rng) -> Self {
+        let seed = crate::run_log::next_replay_seed().unwrap_or_else(|| rng.gen());
+
+        if let Some(_prev_map) = previous_map {
+            // TODO: Use previous map to influence generation
+        }
+        
+        let map = Self::generate_level(level, seed);
+        println!("Generated new map for level {} with seed: {}", level, seed);
+        map
+    }
+    
+    // Build a level from a seed; the same level and seed always give the same map
+    pub fn generate_level(level: usize, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        
+        // Boss floors get their own layout
+        let (width, height) = map_size_for_level(level);
+        let depth_tier = DepthProgression::load().tier_for_level(level);
+        if is_boss_level(level) {
+            return Self::new_boss_level(level, seed, width, height, depth_tier, &mut rng);
+        }
+        
+        let (tiles, rooms, biomes, spawn_position, secret_rooms, vaults) = Self::generate_map(level, width, height, &depth_tier, &mut rng);
         
         let mut map = Self {
+            width,
+            height,
             tiles,
             rooms,
             biomes,
             spawn_position,
             down_stairs_pos: None,
             up_stairs_pos: None,
-            current_level: 0,
+            current_level: level,
+            is_boss_level: false,
+            down_stairs_locked: false,
+            chest_positions: Vec::new(),
+            trap_positions: Vec::new(),
+            depth_tier,
+            vaults,
+            npc_spawns: Vec::new(),
+            monster_spawns: Vec::new(),
+            props: Vec::new(),
+            portal_pairs: Vec::new(),
+            altar_positions: Vec::new(),
+            seed,
         };
-        
+
         // Add stairs to the map (only once)
         map.add_stairs(&mut rng);
+        map.place_chests(&secret_rooms, &mut rng);
+        map.place_room_spawns(&mut rng);
+        map.place_altars(&mut rng);
+        map.place_portals(&mut rng);
+        map.place_traps(&mut rng);
+        map.props = place_props(&map, &mut rng);
         
         map
     }
     
-    // Create a new map for a specific level
-    pub fn new_level(level: usize, previous_map: Option<&TileMap>) -> Self {
-        // Create a new RNG with a seed based on time to ensure different maps
-        // Use bitwise XOR instead of addition to avoid overflow
-        let time_component = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs() as u64;
-        
-        let random_component = rand::random::<u64>();
-        let seed = time_component ^ random_component;
-        
-        let mut rng = StdRng::seed_from_u64(seed);
-        
-        let (tiles, rooms, biomes, spawn_position) = Self::generate_map(&mut rng);
+    // Create a boss floor: one large columned hall with the stairs at opposite ends
+    fn new_boss_level(level: usize, seed: u64, map_width: usize, map_height: usize, depth_tier: DepthTier, rng: &mut impl Rng) -> Self {
+        let mut tiles = vec![vec![TileType::Wall; map_width]; map_height];
+        let mut biomes = vec![vec![BiomeType::Caves; map_width]; map_height];
+        
+        let hall = Room::new(2, 2, map_width - 4, map_height - 4, RoomType::LargeHall);
+        hall.carve_rectangular(&mut tiles);
+        hall.add_columns(&mut tiles, rng);
+        
+        let rooms = vec![hall];
+        assign_biomes(&mut biomes, &rooms, depth_tier.choose_biome(rng));
+        
+        // Up stairs on the left, sealed down stairs on the right
+        let (_, center_y) = rooms[0].center();
+        let up_pos = (rooms[0].x + 1, center_y);
+        let down_pos = (rooms[0].x + rooms[0].width - 2, center_y);
+        tiles[up_pos.1][up_pos.0] = TileType::StairsUp;
+        tiles[down_pos.1][down_pos.0] = TileType::StairsDown;
+        
+        // Keep the tiles next to the stairs clear of columns
+        for (x, y) in [up_pos, down_pos] {
+            for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+                let nx = (x as isize + dx) as usize;
+                let ny = (y as isize + dy) as usize;
+                if tiles[ny][nx] == TileType::Wall && rooms[0].x <= nx && nx < rooms[0].x + rooms[0].width {
+                    tiles[ny][nx] = TileType::Floor;
+                }
+            }
+        }
         
-        let mut map = Self {
+        Self {
+            width: map_width,
+            height: map_height,
             tiles,
             rooms,
             biomes,
-            spawn_position,
-            down_stairs_pos: None,
-            up_stairs_pos: None,
+            spawn_position: up_pos,
+            down_stairs_pos: Some(down_pos),
+            up_stairs_pos: Some(up_pos),
             current_level: level,
+            is_boss_level: true,
+            down_stairs_locked: true,
+            chest_positions: Vec::new(),
+            trap_positions: Vec::new(),
+            depth_tier,
+            vaults: Vec::new(),
+            npc_spawns: Vec::new(),
+            monster_spawns: Vec::new(),
+            props: Vec::new(),
+            portal_pairs: Vec::new(),
+            altar_positions: Vec::new(),
+            seed,
+        }
+    }
+    
+    // Write the map as text: a header of `key: value` lines, a `---` line, then one row of
+    // characters per tile row with the top of the map first (see tile_char for the legend)
+    pub fn to_string_grid(&self) -> String {
+        let format_pos = |pos: (usize, usize)| format!("{},{}", pos.0, pos.1);
+        let format_list = |positions: &[(usize, usize)]| positions.iter().map(|&pos| format_pos(pos)).collect::<Vec<_>>().join(" ");
+
+        // The header biome is whichever covers the most floor
+        let mut biome_counts: HashMap<BiomeType, usize> = HashMap::new();
+        for (y, row) in self.tiles.iter().enumerate() {
+            for (x, tile) in row.iter().enumerate() {
+                if *tile != TileType::Wall {
+                    *biome_counts.entry(self.biomes[y][x]).or_insert(0) += 1;
+                }
+            }
+        }
+        let biome = biome_counts.into_iter().max_by_key(|(_, count)| *count).map_or(BiomeType::Caves, |(biome, _)| biome);
+
+        let mut lines = vec![
+            format!("level: {}", self.current_level),
+            format!("size: {}x{}", self.width, self.height),
+            format!("biome: {}", biome.get_name()),
+            format!("spawn: {}", format_pos(self.spawn_position)),
+        ];
+        if let Some(pos) = self.down_stairs_pos {
+            lines.push(format!("stairs_down: {}", format_pos(pos)));
+        }
+        if let Some(pos) = self.up_stairs_pos {
+            lines.push(format!("stairs_up: {}", format_pos(pos)));
+        }
+        if !self.chest_positions.is_empty() {
+            lines.push(format!("chests: {}", format_list(&self.chest_positions)));
+        }
+        if !self.trap_positions.is_empty() {
+            lines.push(format!("traps: {}", format_list(&self.trap_positions)));
+        }
+        if !self.portal_pairs.is_empty() {
+            let pairs: Vec<String> = self.portal_pairs.iter().map(|&(a, b)| format!("{}>{}", format_pos(a), format_pos(b))).collect();
+            lines.push(format!("portals: {}", pairs.join(" ")));
+        }
+        if !self.altar_positions.is_empty() {
+            lines.push(format!("altars: {}", format_list(&self.altar_positions)));
+        }
+        lines.push("---".to_string());
+        for row in self.tiles.iter().rev() {
+            lines.push(row.iter().map(|&tile| tile_char(tile)).collect());
+        }
+
+        lines.join("\n") + "\n"
+    }
+
+    // Build a map from the to_string_grid format; stairs come from the header or, failing that, the grid
+    pub fn from_string_grid(text: &str) -> Result<Self, String> {
+        let parse_pos = |value: &str| -> Result<(usize, usize), String> {
+            let (x, y) = value.trim().split_once(',').ok_or_else(|| format!("'{}' is not an x,y position", value))?;
+            let x = x.trim().parse().map_err(|_| format!("bad x coordinate in '{}'", value))?;
+            let y = y.trim().parse().map_err(|_| format!("bad y coordinate in '{}'", value))?;
+            Ok((x, y))
         };
 
-        if let Some(_prev_map) = previous_map {
-            // TODO: Use previous map to influence generation
+        let (header, grid) = text.split_once("\n---").ok_or("map text has no '---' line between header and grid")?;
+
+        let mut level = 0;
+        let mut biome = BiomeType::Caves;
+        let mut spawn = None;
+        let mut down_stairs_pos = None;
+        let mut up_stairs_pos = None;
+        let mut chest_positions = Vec::new();
+        let mut trap_positions = Vec::new();
+        let mut portal_pairs = Vec::new();
+        let mut altar_positions = Vec::new();
+        for line in header.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            let (key, value) = line.split_once(':').ok_or_else(|| format!("header line '{}' is not 'key: value'", line))?;
+            let value = value.trim();
+            match key.trim() {
+                "level" => level = value.parse().map_err(|_| format!("bad level '{}'", value))?,
+                "size" => {} // Taken from the grid itself
+                "biome" => biome = BiomeType::from_name(value).ok_or_else(|| format!("unknown biome '{}'", value))?,
+                "spawn" => spawn = Some(parse_pos(value)?),
+                "stairs_down" => down_stairs_pos = Some(parse_pos(value)?),
+                "stairs_up" => up_stairs_pos = Some(parse_pos(value)?),
+                "chests" => chest_positions = value.split_whitespace().map(parse_pos).collect::<Result<_, _>>()?,
+                "traps" => trap_positions = value.split_whitespace().map(parse_pos).collect::<Result<_, _>>()?,
+                "portals" => portal_pairs = value.split_whitespace()
+                    .map(|pair| {
+                        let (a, b) = pair.split_once('>').ok_or_else(|| format!("'{}' is not a portal pair x,y>x,y", pair))?;
+                        Ok((parse_pos(a)?, parse_pos(b)?))
+                    })
+                    .collect::<Result<_, String>>()?,
+                "altars" => altar_positions = value.split_whitespace().map(parse_pos).collect::<Result<_, _>>()?,
+                other => return Err(format!("unknown header key '{}'", other)),
+            }
         }
 
-        // Add stairs to the map (only once)
-        map.add_stairs(&mut rng);
-        
-        println!("Generated new map with seed: {}", seed);
-        
-        map
+        // Rows are written top first, so flip them back to y-up
+        let mut tiles: TileGrid = Vec::new();
+        for (row_number, row) in grid.lines().skip(1).filter(|row| !row.trim().is_empty()).enumerate() {
+            let row: Vec<TileType> = row.trim_end().chars()
+                .map(|c| tile_from_char(c).ok_or_else(|| format!("unknown tile '{}' on grid row {}", c, row_number + 1)))
+                .collect::<Result<_, _>>()?;
+            tiles.push(row);
+        }
+        tiles.reverse();
+
+        let (width, height) = grid_size(&tiles);
+        if width == 0 || height == 0 {
+            return Err("map grid is empty".to_string());
+        }
+        if let Some(row) = tiles.iter().position(|row| row.len() != width) {
+            return Err(format!("grid row {} is {} tiles wide, expected {}", height - row, tiles[row].len(), width));
+        }
+
+        let find = |tiles: &TileGrid, wanted: TileType| {
+            (0..height).flat_map(|y| (0..width).map(move |x| (x, y))).find(|&(x, y)| tiles[y][x] == wanted)
+        };
+        let down_stairs_pos = down_stairs_pos.or_else(|| find(&tiles, TileType::StairsDown));
+        let up_stairs_pos = up_stairs_pos.or_else(|| find(&tiles, TileType::StairsUp));
+        let spawn_position = spawn.or(up_stairs_pos).or_else(|| find(&tiles, TileType::Floor)).ok_or("map has no floor to spawn on")?;
+
+        let positions = [Some(spawn_position), down_stairs_pos, up_stairs_pos].into_iter().flatten()
+            .chain(chest_positions.iter().copied())
+            .chain(trap_positions.iter().copied())
+            .chain(portal_pairs.iter().flat_map(|&(a, b)| [a, b]))
+            .chain(altar_positions.iter().copied());
+        for (x, y) in positions {
+            if x >= width || y >= height {
+                return Err(format!("position {},{} is outside the {}x{} grid", x, y, width, height));
+            }
+        }
+
+        // Make sure the stairs in the header are on the grid too
+        if let Some((x, y)) = down_stairs_pos {
+            tiles[y][x] = TileType::StairsDown;
+        }
+        if let Some((x, y)) = up_stairs_pos {
+            tiles[y][x] = TileType::StairsUp;
+        }
+
+        let boss = is_boss_level(level);
+        Ok(Self {
+            width,
+            height,
+            tiles,
+            rooms: Vec::new(),
+            biomes: vec![vec![biome; width]; height],
+            spawn_position,
+            down_stairs_pos,
+            up_stairs_pos,
+            current_level: level,
+            is_boss_level: boss,
+            down_stairs_locked: boss,
+            chest_positions,
+            trap_positions,
+            depth_tier: DepthProgression::load().tier_for_level(level),
+            vaults: Vec::new(),
+            npc_spawns: Vec::new(),
+            monster_spawns: Vec::new(),
+            props: Vec::new(),
+            portal_pairs,
+            altar_positions,
+            seed: 0,
+        })
+    }
+    
+    // Whether a tile coordinate lies on this map
+    pub fn in_bounds(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && x < self.width as i32 && y < self.height as i32
     }
     
-    fn generate_map(rng: &mut impl Rng) -> ([[TileType; MAP_WIDTH]; MAP_HEIGHT], Vec<Room>, [[BiomeType; MAP_WIDTH]; MAP_HEIGHT], (usize, usize)) {
-        let mut tiles = [[TileType::Wall; MAP_WIDTH]; MAP_HEIGHT];
-        let mut biomes = [[BiomeType::Caves; MAP_WIDTH]; MAP_HEIGHT]; // Default biome
+    // Where a boss floor's boss stands when the player arrives
+    pub fn boss_spawn_position(&self) -> Option<(usize, usize)> {
+        if !self.is_boss_level {
+            return None;
+        }
+        let (x, y) = self.rooms.first()?.center();
         
-        // Generate rooms
-        let rooms = Self::generate_rooms(rng);
+        // Step off any column that landed in the middle of the hall
+        for (dx, dy) in [(0, 0), (1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (-1, -1)] {
+            let (nx, ny) = ((x as i32 + dx) as usize, (y as i32 + dy) as usize);
+            if self.tiles[ny][nx] == TileType::Floor {
+                return Some((nx, ny));
+            }
+        }
+        Some((x, y))
+    }
+    
+    fn generate_map(level: usize, map_width: usize, map_height: usize, depth_tier: &DepthTier, rng: &mut impl Rng) -> (TileGrid, Vec<Room>, BiomeGrid, (usize, usize), Vec<Room>, Vec<PlacedVault>) {
+        let mut tiles = vec![vec![TileType::Wall; map_width]; map_height];
+        let mut biomes = vec![vec![BiomeType::Caves; map_width]; map_height]; // Default biome
+        
+        // Generate rooms, giving a few of them a purpose
+        let mut rooms = Self::generate_rooms(map_width, map_height, depth_tier.room_multiplier, rng);
+        for room in rooms.iter_mut() {
+            if rng.gen_bool(ROOM_PURPOSE_CHANCE) {
+                room.purpose = Some(RoomPurpose::choose_for(room, rng));
+            }
+        }
         
         // Carve out rooms
         for room in &rooms {
@@ -438,33 +914,37 @@ impl TileMap {
         Self::connect_rooms(&mut tiles, &rooms, rng);
         
         // Add secret rooms
-        Self::add_secret_rooms(&mut tiles, &rooms, rng);
+        let secret_rooms = Self::add_secret_rooms(&mut tiles, &rooms, rng);
         
         // Add extra corridors for more connectivity
         Self::add_extra_corridors(&mut tiles, &rooms, rng);
         
+        // Sometimes stamp a hand-authored vault into the rock left over
+        let vaults: Vec<PlacedVault> = vault::place_vault(&mut tiles, &vault::load_vaults(), level, rng).into_iter().collect();
+        
         // Add doors between rooms and corridors
         // Commented out to prevent door generation until ready to implement
         // Self::add_doors(&mut tiles, &rooms, rng);
         
         // Assign biomes to different regions of the map
-        assign_biomes(&mut biomes, &rooms, rng);
+        assign_biomes(&mut biomes, &rooms, depth_tier.choose_biome(rng));
         
         // Find a valid spawn position (a floor tile)
-        let spawn_position = Self::find_spawn_position(&tiles);
+        let spawn_position = Self::find_spawn_position(&tiles, rng);
         
-        (tiles, rooms, biomes, spawn_position)
+        (tiles, rooms, biomes, spawn_position, secret_rooms, vaults)
     }
     
-    fn generate_rooms(rng: &mut impl Rng) -> Vec<Room> {
+    fn generate_rooms(map_width: usize, map_height: usize, room_multiplier: f32, rng: &mut impl Rng) -> Vec<Room> {
         let mut rooms = Vec::new();
         
-        // Create a larger number of rooms with various sizes
-        let num_rooms = rng.gen_range(20..30);
+        // Create a larger number of rooms with various sizes, more on bigger and deeper maps
+        let area_scale = (map_width * map_height) as f32 / (MAP_WIDTH * MAP_HEIGHT) as f32;
+        let num_rooms = (rng.gen_range(20..30) as f32 * area_scale * room_multiplier) as usize;
         
         // Track attempts to avoid infinite loops
         let mut attempts = 0;
-        let max_attempts = 100;
+        let max_attempts = 100 + num_rooms * 2;
 
         while rooms.len() < num_rooms && attempts < max_attempts {
             attempts += 1;
@@ -493,8 +973,8 @@ impl TileMap {
             };
             
             // Generate random room position
-            let room_x = rng.gen_range(1..MAP_WIDTH - room_width - 1);
-            let room_y = rng.gen_range(1..MAP_HEIGHT - room_height - 1);
+            let room_x = rng.gen_range(1..map_width - room_width - 1);
+            let room_y = rng.gen_range(1..map_height - room_height - 1);
             
             // Choose a room type based on size
             let room_type = match size_category {
@@ -538,7 +1018,7 @@ impl TileMap {
         rooms
     }
     
-    fn connect_rooms(tiles: &mut [[TileType; MAP_WIDTH]; MAP_HEIGHT], rooms: &[Room], rng: &mut impl Rng) {
+    fn connect_rooms(tiles: &mut [Vec<TileType>], rooms: &[Room], rng: &mut impl Rng) {
         if rooms.len() <= 1 {
             return;
         }
@@ -617,7 +1097,8 @@ impl TileMap {
         Self::add_extra_corridors(tiles, rooms, rng);
     }
     
-    fn find_door_position(tiles: &[[TileType; MAP_WIDTH]; MAP_HEIGHT], x: usize, y: usize) -> Option<(usize, usize)> {
+    fn find_door_position(tiles: &[Vec<TileType>], x: usize, y: usize) -> Option<(usize, usize)> {
+        let (map_width, map_height) = grid_size(tiles);
         // Check all four adjacent tiles to find a suitable door position
         let directions = [(0, 1), (1, 0), (0, -1), (-1, 0)];
         
@@ -626,14 +1107,14 @@ impl TileMap {
             let ny = (y as i32 + dy) as usize;
             
             // Ensure we're within bounds
-            if nx > 0 && nx < MAP_WIDTH - 1 && ny > 0 && ny < MAP_HEIGHT - 1 {
+            if nx > 0 && nx < map_width - 1 && ny > 0 && ny < map_height - 1 {
                 // Check if this position has a wall with floor on both sides
                 if tiles[ny][nx] == TileType::Wall {
                     let opposite_x = (nx as i32 + dx) as usize;
                     let opposite_y = (ny as i32 + dy) as usize;
                     
-                    if opposite_x > 0 && opposite_x < MAP_WIDTH - 1 && 
-                       opposite_y > 0 && opposite_y < MAP_HEIGHT - 1 &&
+                    if opposite_x > 0 && opposite_x < map_width - 1 && 
+                       opposite_y > 0 && opposite_y < map_height - 1 &&
                        tiles[opposite_y][opposite_x] == TileType::Floor {
                         return Some((nx, ny));
                     }
@@ -645,7 +1126,7 @@ impl TileMap {
     }
     
     fn create_corridor(
-        tiles: &mut [[TileType; MAP_WIDTH]; MAP_HEIGHT],
+        tiles: &mut [Vec<TileType>],
         start_x: usize, start_y: usize,
         end_x: usize, end_y: usize
     ) {
@@ -656,11 +1137,12 @@ impl TileMap {
     }
     
     fn create_z_corridor(
-        tiles: &mut [[TileType; MAP_WIDTH]; MAP_HEIGHT],
+        tiles: &mut [Vec<TileType>],
         start_x: usize, start_y: usize,
         end_x: usize, end_y: usize,
         rng: &mut impl Rng
     ) {
+        let (map_width, _) = grid_size(tiles);
         // Create a Z-shaped corridor with a middle segment
         let mid_x = if start_x < end_x {
             start_x + (end_x - start_x) / 2
@@ -669,7 +1151,7 @@ impl TileMap {
         };
         
         // Add some randomness to the middle point
-        let mid_x = if mid_x > 5 && mid_x < MAP_WIDTH - 5 {
+        let mid_x = if mid_x > 5 && mid_x < map_width - 5 {
             // Convert to i32 for the calculation, then back to usize
             let mid_x_i32 = mid_x as i32;
             let random_offset = rng.gen_range(-3..=3);
@@ -685,11 +1167,12 @@ impl TileMap {
     }
     
     fn create_winding_corridor(
-        tiles: &mut [[TileType; MAP_WIDTH]; MAP_HEIGHT],
+        tiles: &mut [Vec<TileType>],
         start_x: usize, start_y: usize,
         end_x: usize, end_y: usize,
         rng: &mut impl Rng
     ) {
+        let (map_width, map_height) = grid_size(tiles);
         // Create a winding corridor with multiple segments
         let mut current_x = start_x;
         let mut current_y = start_y;
@@ -709,7 +1192,7 @@ impl TileMap {
                 };
                 
                 // Add some randomness
-                let target_x = if target_x > 5 && target_x < MAP_WIDTH - 5 {
+                let target_x = if target_x > 5 && target_x < map_width - 5 {
                     // Convert to i32 for the calculation, then back to usize
                     let target_x_i32 = target_x as i32;
                     let random_offset = rng.gen_range(-2..=2);
@@ -729,7 +1212,7 @@ impl TileMap {
                 };
                 
                 // Add some randomness
-                let target_y = if target_y > 5 && target_y < MAP_HEIGHT - 5 {
+                let target_y = if target_y > 5 && target_y < map_height - 5 {
                     // Convert to i32 for the calculation, then back to usize
                     let target_y_i32 = target_y as i32;
                     let random_offset = rng.gen_range(-2..=2);
@@ -749,34 +1232,39 @@ impl TileMap {
     }
     
     fn create_horizontal_corridor(
-        tiles: &mut [[TileType; MAP_WIDTH]; MAP_HEIGHT],
+        tiles: &mut [Vec<TileType>],
         x1: usize, x2: usize, y: usize
     ) {
+        let (map_width, map_height) = grid_size(tiles);
         let start = x1.min(x2);
         let end = x1.max(x2);
         
         for x in start..=end {
-            if x > 0 && x < MAP_WIDTH - 1 && y > 0 && y < MAP_HEIGHT - 1 {
+            if x > 0 && x < map_width - 1 && y > 0 && y < map_height - 1 {
                 tiles[y][x] = TileType::Floor;
             }
         }
     }
     
     fn create_vertical_corridor(
-        tiles: &mut [[TileType; MAP_WIDTH]; MAP_HEIGHT],
+        tiles: &mut [Vec<TileType>],
         y1: usize, y2: usize, x: usize
     ) {
+        let (map_width, map_height) = grid_size(tiles);
         let start = y1.min(y2);
         let end = y1.max(y2);
         
         for y in start..=end {
-            if x > 0 && x < MAP_WIDTH - 1 && y > 0 && y < MAP_HEIGHT - 1 {
+            if x > 0 && x < map_width - 1 && y > 0 && y < map_height - 1 {
                 tiles[y][x] = TileType::Floor;
             }
         }
     }
     
-    fn add_secret_rooms(tiles: &mut [[TileType; MAP_WIDTH]; MAP_HEIGHT], _rooms: &[Room], rng: &mut impl Rng) {
+    fn add_secret_rooms(tiles: &mut [Vec<TileType>], _rooms: &[Room], rng: &mut impl Rng) -> Vec<Room> {
+        let (map_width, map_height) = grid_size(tiles);
+        let mut secret_rooms = Vec::new();
+        
         // Try to add 1-3 secret rooms
         let num_secret_rooms = rng.gen_range(1..=3);
         
@@ -787,8 +1275,8 @@ impl TileMap {
             
             while attempts < max_attempts {
                 // Choose a random position on the map
-                let x = rng.gen_range(3..MAP_WIDTH - 6);
-                let y = rng.gen_range(3..MAP_HEIGHT - 6);
+                let x = rng.gen_range(3..map_width - 6);
+                let y = rng.gen_range(3..map_height - 6);
                 
                 // Check if this is a wall with at least one adjacent floor tile
                 if tiles[y][x] == TileType::Wall && Self::has_adjacent_floor(tiles, x, y) {
@@ -800,7 +1288,7 @@ impl TileMap {
                     let mut can_place = true;
                     for ry in y..y + room_height {
                         for rx in x..x + room_width {
-                            if rx >= MAP_WIDTH || ry >= MAP_HEIGHT || tiles[ry][rx] == TileType::Floor {
+                            if rx >= map_width || ry >= map_height || tiles[ry][rx] == TileType::Floor {
                                 can_place = false;
                                 break;
                             }
@@ -814,7 +1302,7 @@ impl TileMap {
                         // Carve the secret room
                         for ry in y..y + room_height {
                             for rx in x..x + room_width {
-                                if rx < MAP_WIDTH && ry < MAP_HEIGHT {
+                                if rx < map_width && ry < map_height {
                                     tiles[ry][rx] = TileType::Floor;
                                 }
                             }
@@ -828,12 +1316,13 @@ impl TileMap {
                             let feature_x = x + room_width / 2;
                             let feature_y = y + room_height / 2;
                             
-                            if feature_x < MAP_WIDTH && feature_y < MAP_HEIGHT {
+                            if feature_x < map_width && feature_y < map_height {
                                 // For now, just add a pillar as a placeholder for a special feature
                                 tiles[feature_y][feature_x] = TileType::Wall;
                             }
                         }
                         
+                        secret_rooms.push(Room::new(x, y, room_width, room_height, RoomType::SmallChamber));
                         break;
                     }
                 }
@@ -841,16 +1330,19 @@ impl TileMap {
                 attempts += 1;
             }
         }
+        
+        secret_rooms
     }
     
-    fn has_adjacent_floor(tiles: &[[TileType; MAP_WIDTH]; MAP_HEIGHT], x: usize, y: usize) -> bool {
+    fn has_adjacent_floor(tiles: &[Vec<TileType>], x: usize, y: usize) -> bool {
+        let (map_width, map_height) = grid_size(tiles);
         let directions = [(0, 1), (1, 0), (0, -1), (-1, 0)];
         
         for (dx, dy) in directions {
             let nx = (x as i32 + dx) as usize;
             let ny = (y as i32 + dy) as usize;
             
-            if nx < MAP_WIDTH && ny < MAP_HEIGHT && tiles[ny][nx] == TileType::Floor {
+            if nx < map_width && ny < map_height && tiles[ny][nx] == TileType::Floor {
                 return true;
             }
         }
@@ -858,12 +1350,13 @@ impl TileMap {
         false
     }
     
-    fn find_spawn_position(tiles: &[[TileType; MAP_WIDTH]; MAP_HEIGHT]) -> (usize, usize) {
+    fn find_spawn_position(tiles: &[Vec<TileType>], rng: &mut impl Rng) -> (usize, usize) {
+        let (map_width, map_height) = grid_size(tiles);
         // Find a valid floor tile to spawn the player
         let mut floor_tiles = Vec::new();
         
-        for y in 0..MAP_HEIGHT {
-            for x in 0..MAP_WIDTH {
+        for y in 0..map_height {
+            for x in 0..map_width {
                 if tiles[y][x] == TileType::Floor {
                     floor_tiles.push((x, y));
                 }
@@ -872,12 +1365,11 @@ impl TileMap {
         
         if !floor_tiles.is_empty() {
             // Choose a random floor tile
-            let mut rng = rand::thread_rng();
             let index = rng.gen_range(0..floor_tiles.len());
             floor_tiles[index]
         } else {
             // Fallback to center of map if no floor tiles
-            (MAP_WIDTH / 2, MAP_HEIGHT / 2)
+            (map_width / 2, map_height / 2)
         }
     }
 
@@ -885,7 +1377,13 @@ impl TileMap {
         self.spawn_position
     }
 
-    fn add_extra_corridors(tiles: &mut [[TileType; MAP_WIDTH]; MAP_HEIGHT], _rooms: &[Room], rng: &mut impl Rng) {
+    // Stays the same through small edits like opened chests, and changes when a different layout is loaded
+    pub fn layout_key(&self) -> LayoutKey {
+        (self.current_level, self.spawn_position, self.down_stairs_pos)
+    }
+
+    fn add_extra_corridors(tiles: &mut [Vec<TileType>], _rooms: &[Room], rng: &mut impl Rng) {
+        let (map_width, map_height) = grid_size(tiles);
         // Add 2-4 extra corridors that aren't directly connecting rooms
         let num_extra_corridors = rng.gen_range(2..=4);
         
@@ -893,8 +1391,8 @@ impl TileMap {
             // Choose a random starting point from an existing floor tile
             let mut floor_tiles = Vec::new();
             
-            for y in 1..MAP_HEIGHT-1 {
-                for x in 1..MAP_WIDTH-1 {
+            for y in 1..map_height-1 {
+                for x in 1..map_width-1 {
                     if tiles[y][x] == TileType::Floor {
                         // Check if this is near a wall (corridor or room edge)
                         let has_adjacent_wall = 
@@ -936,8 +1434,8 @@ impl TileMap {
                 current_y += direction.1;
                 
                 // Ensure we're within bounds
-                if current_x <= 0 || current_x >= MAP_WIDTH as i32 - 1 || 
-                   current_y <= 0 || current_y >= MAP_HEIGHT as i32 - 1 {
+                if current_x <= 0 || current_x >= map_width as i32 - 1 || 
+                   current_y <= 0 || current_y >= map_height as i32 - 1 {
                     break;
                 }
                 
@@ -960,8 +1458,8 @@ impl TileMap {
                         branch_y += branch_direction.1;
                         
                         // Ensure we're within bounds
-                        if branch_x <= 0 || branch_x >= MAP_WIDTH as i32 - 1 || 
-                           branch_y <= 0 || branch_y >= MAP_HEIGHT as i32 - 1 {
+                        if branch_x <= 0 || branch_x >= map_width as i32 - 1 || 
+                           branch_y <= 0 || branch_y >= map_height as i32 - 1 {
                             break;
                         }
                         
@@ -974,11 +1472,12 @@ impl TileMap {
     }
     
     fn create_branching_corridor(
-        tiles: &mut [[TileType; MAP_WIDTH]; MAP_HEIGHT],
+        tiles: &mut [Vec<TileType>],
         start_x: usize, start_y: usize,
         end_x: usize, end_y: usize,
         rng: &mut impl Rng
     ) {
+        let (map_width, map_height) = grid_size(tiles);
         // Create a winding corridor with branches
         let mut current_x = start_x;
         let mut current_y = start_y;
@@ -1002,7 +1501,7 @@ impl TileMap {
                 };
                 
                 // Add some randomness
-                let target_x = if target_x > 5 && target_x < MAP_WIDTH - 5 {
+                let target_x = if target_x > 5 && target_x < map_width - 5 {
                     // Convert to i32 for the calculation, then back to usize
                     let target_x_i32 = target_x as i32;
                     let random_offset = rng.gen_range(-3..=3);
@@ -1022,7 +1521,7 @@ impl TileMap {
                 };
                 
                 // Add some randomness
-                let target_y = if target_y > 5 && target_y < MAP_HEIGHT - 5 {
+                let target_y = if target_y > 5 && target_y < map_height - 5 {
                     // Convert to i32 for the calculation, then back to usize
                     let target_y_i32 = target_y as i32;
                     let random_offset = rng.gen_range(-3..=3);
@@ -1076,8 +1575,8 @@ impl TileMap {
                 current_y += direction.1;
                 
                 // Ensure we're within bounds
-                if current_x <= 0 || current_x >= MAP_WIDTH as i32 - 1 || 
-                   current_y <= 0 || current_y >= MAP_HEIGHT as i32 - 1 {
+                if current_x <= 0 || current_x >= map_width as i32 - 1 || 
+                   current_y <= 0 || current_y >= map_height as i32 - 1 {
                     break;
                 }
                 
@@ -1088,7 +1587,7 @@ impl TileMap {
     }
     
     fn add_corridor_feature(
-        tiles: &mut [[TileType; MAP_WIDTH]; MAP_HEIGHT],
+        tiles: &mut [Vec<TileType>],
         x: usize, y: usize,
         rng: &mut impl Rng
     ) {
@@ -1101,10 +1600,11 @@ impl TileMap {
     }
     
     fn add_corridor_alcove(
-        tiles: &mut [[TileType; MAP_WIDTH]; MAP_HEIGHT],
+        tiles: &mut [Vec<TileType>],
         x: usize, y: usize,
         rng: &mut impl Rng
     ) {
+        let (map_width, map_height) = grid_size(tiles);
         // Create a small alcove off the corridor
         let direction = match rng.gen_range(0..4) {
             0 => (1, 0),   // Right
@@ -1120,7 +1620,7 @@ impl TileMap {
             let ny = (y as i32 + direction.1 * i) as usize;
             
             // Ensure we're within bounds
-            if nx <= 0 || nx >= MAP_WIDTH - 1 || ny <= 0 || ny >= MAP_HEIGHT - 1 {
+            if nx <= 0 || nx >= map_width - 1 || ny <= 0 || ny >= map_height - 1 {
                 break;
             }
             
@@ -1140,7 +1640,7 @@ impl TileMap {
                     let sy = (ny as i32 + side_dir.1 * j) as usize;
                     
                     // Ensure we're within bounds
-                    if sx <= 0 || sx >= MAP_WIDTH - 1 || sy <= 0 || sy >= MAP_HEIGHT - 1 {
+                    if sx <= 0 || sx >= map_width - 1 || sy <= 0 || sy >= map_height - 1 {
                         continue;
                     }
                     
@@ -1152,11 +1652,12 @@ impl TileMap {
     }
     
     fn add_corridor_pillar(
-        tiles: &mut [[TileType; MAP_WIDTH]; MAP_HEIGHT],
+        tiles: &mut [Vec<TileType>],
         x: usize, y: usize
     ) {
+        let (map_width, map_height) = grid_size(tiles);
         // Check if there's enough space for a pillar
-        if x <= 1 || x >= MAP_WIDTH - 2 || y <= 1 || y >= MAP_HEIGHT - 2 {
+        if x <= 1 || x >= map_width - 2 || y <= 1 || y >= map_height - 2 {
             return;
         }
         
@@ -1174,10 +1675,11 @@ impl TileMap {
     }
     
     fn add_corridor_widening(
-        tiles: &mut [[TileType; MAP_WIDTH]; MAP_HEIGHT],
+        tiles: &mut [Vec<TileType>],
         x: usize, y: usize,
         rng: &mut impl Rng
     ) {
+        let (map_width, map_height) = grid_size(tiles);
         // Widen the corridor in all directions
         for dy in -1..=1 {
             for dx in -1..=1 {
@@ -1189,7 +1691,7 @@ impl TileMap {
                 let ny = (y as i32 + dy) as usize;
                 
                 // Ensure we're within bounds
-                if nx <= 0 || nx >= MAP_WIDTH - 1 || ny <= 0 || ny >= MAP_HEIGHT - 1 {
+                if nx <= 0 || nx >= map_width - 1 || ny <= 0 || ny >= map_height - 1 {
                     continue;
                 }
                 
@@ -1203,20 +1705,21 @@ impl TileMap {
 
     // Get the biome at a specific position
     pub fn get_biome_at(&self, x: usize, y: usize) -> BiomeType {
-        if x < MAP_WIDTH && y < MAP_HEIGHT {
+        if x < self.width && y < self.height {
             self.biomes[y][x]
         } else {
             BiomeType::Caves // Default biome
         }
     }
 
-    fn add_doors(tiles: &mut [[TileType; MAP_WIDTH]; MAP_HEIGHT], _rooms: &[Room], rng: &mut impl Rng) {
+    fn add_doors(tiles: &mut [Vec<TileType>], _rooms: &[Room], rng: &mut impl Rng) {
+        let (map_width, map_height) = grid_size(tiles);
         // Add doors between rooms and corridors
         for room in _rooms {
             // Try to add doors on each side of the room
             // Top side
             for x in room.x + 1..room.x + room.width - 1 {
-                if x < MAP_WIDTH - 1 && room.y > 0 {
+                if x < map_width - 1 && room.y > 0 {
                     // Check if there's a wall with floor on both sides
                     if tiles[room.y][x] == TileType::Wall &&
                        tiles[room.y - 1][x] == TileType::Floor &&
@@ -1231,7 +1734,7 @@ impl TileMap {
             
             // Bottom side
             for x in room.x + 1..room.x + room.width - 1 {
-                if x < MAP_WIDTH - 1 && room.y + room.height < MAP_HEIGHT - 1 {
+                if x < map_width - 1 && room.y + room.height < map_height - 1 {
                     // Check if there's a wall with floor on both sides
                     if tiles[room.y + room.height - 1][x] == TileType::Wall &&
                        tiles[room.y + room.height - 2][x] == TileType::Floor &&
@@ -1246,7 +1749,7 @@ impl TileMap {
             
             // Left side
             for y in room.y + 1..room.y + room.height - 1 {
-                if y < MAP_HEIGHT - 1 && room.x > 0 {
+                if y < map_height - 1 && room.x > 0 {
                     // Check if there's a wall with floor on both sides
                     if tiles[y][room.x] == TileType::Wall &&
                        tiles[y][room.x - 1] == TileType::Floor &&
@@ -1261,7 +1764,7 @@ impl TileMap {
             
             // Right side
             for y in room.y + 1..room.y + room.height - 1 {
-                if y < MAP_HEIGHT - 1 && room.x + room.width < MAP_WIDTH - 1 {
+                if y < map_height - 1 && room.x + room.width < map_width - 1 {
                     // Check if there's a wall with floor on both sides
                     if tiles[y][room.x + room.width - 1] == TileType::Wall &&
                        tiles[y][room.x + room.width - 2] == TileType::Floor &&
@@ -1279,8 +1782,8 @@ impl TileMap {
     // Add stairs to the map
     fn add_stairs(&mut self, rng: &mut impl Rng) {
         // Clear any existing stairs first
-        for y in 0..MAP_HEIGHT {
-            for x in 0..MAP_WIDTH {
+        for y in 0..self.height {
+            for x in 0..self.width {
                 if self.tiles[y][x] == TileType::StairsDown || self.tiles[y][x] == TileType::StairsUp {
                     self.tiles[y][x] = TileType::Floor;
                 }
@@ -1300,51 +1803,268 @@ impl TileMap {
         self.down_stairs_pos = Some((down_x, down_y));
         println!("Placed DOWN stairs at position: ({}, {})", down_x, down_y);
         
-        // If this is not the first level, place up stairs
-        if self.current_level > 0 {
-            // Place up stairs in a different room if possible
-            let mut up_stairs_room_idx;
-            let rooms_len = self.rooms.len();
-            
-            if rooms_len > 1 {
-                // Try to find a different room for up stairs
-                loop {
-                    up_stairs_room_idx = rng.gen_range(0..rooms_len);
-                    if &self.rooms[up_stairs_room_idx] as *const _ != down_stairs_room as *const _ {
-                        break;
-                    }
+        // Place up stairs in a different room if possible; on the first floor they lead out to the surface
+        let mut up_stairs_room_idx;
+        let rooms_len = self.rooms.len();
+        
+        if rooms_len > 1 {
+            // Try to find a different room for up stairs
+            loop {
+                up_stairs_room_idx = rng.gen_range(0..rooms_len);
+                if &self.rooms[up_stairs_room_idx] as *const _ != down_stairs_room as *const _ {
+                    break;
+                }
+            }
+        } else {
+            // Only one room, use it but ensure stairs are not too close
+            up_stairs_room_idx = 0;
+        }
+        
+        let up_stairs_room = &self.rooms[up_stairs_room_idx];
+        let (up_x, up_y) = self.find_valid_position_in_room(up_stairs_room, rng);
+        
+        // Ensure up and down stairs are not at the same position
+        if up_x == down_x && up_y == down_y {
+            // Adjust position slightly
+            let offsets = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+            for (dx, dy) in offsets.iter() {
+                let new_x = (up_x as isize + dx) as usize;
+                let new_y = (up_y as isize + dy) as usize;
+                
+                if new_x > 0 && new_x < self.width - 1 && 
+                   new_y > 0 && new_y < self.height - 1 &&
+                   self.tiles[new_y][new_x] == TileType::Floor {
+                    self.tiles[new_y][new_x] = TileType::StairsUp;
+                    self.up_stairs_pos = Some((new_x, new_y));
+                    println!("Placed UP stairs at position: ({}, {})", new_x, new_y);
+                    return;
+                }
+            }
+        }
+        
+        self.tiles[up_y][up_x] = TileType::StairsUp;
+        self.up_stairs_pos = Some((up_x, up_y));
+        println!("Placed UP stairs at position: ({}, {})", up_x, up_y);
+    }
+    
+    // Place chests: one in every secret room, one on each vault chest marker, and a few scattered through ordinary rooms
+    fn place_chests(&mut self, secret_rooms: &[Room], rng: &mut impl Rng) {
+        self.chest_positions.clear();
+        
+        for vault in &self.vaults {
+            self.chest_positions.extend(vault.chests.iter().copied());
+        }
+        
+        for room in secret_rooms {
+            if let Some(pos) = self.find_free_floor_in_room(room, rng) {
+                self.chest_positions.push(pos);
+            }
+        }
+        
+        for room in self.rooms.clone() {
+            let chests = room.purpose.map_or(0, |purpose| purpose.spawn_table().2);
+            for _ in 0..chests {
+                if let Some(pos) = self.find_free_floor_in_room(&room, rng) {
+                    self.chest_positions.push(pos);
+                }
+            }
+        }
+        
+        let mut room_chests = 0;
+        for room in self.rooms.clone() {
+            if room_chests >= MAX_ROOM_CHESTS {
+                break;
+            }
+            if rng.gen_bool(ROOM_CHEST_CHANCE) {
+                if let Some(pos) = self.find_free_floor_in_room(&room, rng) {
+                    self.chest_positions.push(pos);
+                    room_chests += 1;
                 }
+            }
+        }
+        
+        println!("Placed {} chests", self.chest_positions.len());
+    }
+    
+    // Gather where NPCs and creatures must be spawned: vault markers, then the keepers and lairs of special rooms
+    fn place_room_spawns(&mut self, rng: &mut impl Rng) {
+        self.npc_spawns = self.vaults.iter().flat_map(|vault| vault.npcs.iter().copied()).collect();
+        self.monster_spawns = self.vaults.iter().flat_map(|vault| vault.monsters.iter().copied()).collect();
+        
+        for room in self.rooms.clone() {
+            let (npcs, monsters, _) = if let Some(purpose) = room.purpose { purpose.spawn_table() } else { continue };
+            for index in 0..npcs + monsters {
+                let pos = if let Some(pos) = self.find_free_floor_in_room(&room, rng) { pos } else { continue };
+                if Some(pos) == self.down_stairs_pos || Some(pos) == self.up_stairs_pos
+                    || self.npc_spawns.contains(&pos) || self.monster_spawns.contains(&pos)
+                {
+                    continue;
+                }
+                if index < npcs {
+                    self.npc_spawns.push(pos);
+                } else {
+                    self.monster_spawns.push(pos);
+                }
+            }
+        }
+        
+        println!("Marked {} NPC and {} creature spawns", self.npc_spawns.len(), self.monster_spawns.len());
+    }
+    
+    // Stand an altar in the middle of every shrine, or somewhere else in it if the middle is taken
+    fn place_altars(&mut self, rng: &mut impl Rng) {
+        self.altar_positions.clear();
+
+        for room in self.rooms.clone() {
+            if room.purpose != Some(RoomPurpose::Shrine) {
+                continue;
+            }
+            let center = room.center();
+            let free = |map: &Self, pos: (usize, usize)| {
+                map.tiles[pos.1][pos.0] == TileType::Floor
+                    && pos != map.spawn_position
+                    && Some(pos) != map.down_stairs_pos
+                    && Some(pos) != map.up_stairs_pos
+                    && !map.chest_positions.contains(&pos)
+                    && !map.npc_spawns.contains(&pos)
+                    && !map.monster_spawns.contains(&pos)
+            };
+            let pos = if center.0 < self.width && center.1 < self.height && free(self, center) {
+                Some(center)
             } else {
-                // Only one room, use it but ensure stairs are not too close
-                up_stairs_room_idx = 0;
+                (0..10).filter_map(|_| self.find_free_floor_in_room(&room, rng)).find(|&pos| free(self, pos))
+            };
+            if let Some(pos) = pos {
+                self.altar_positions.push(pos);
             }
-            
-            let up_stairs_room = &self.rooms[up_stairs_room_idx];
-            let (up_x, up_y) = self.find_valid_position_in_room(up_stairs_room, rng);
-            
-            // Ensure up and down stairs are not at the same position
-            if up_x == down_x && up_y == down_y {
-                // Adjust position slightly
-                let offsets = [(1, 0), (-1, 0), (0, 1), (0, -1)];
-                for (dx, dy) in offsets.iter() {
-                    let new_x = (up_x as isize + dx) as usize;
-                    let new_y = (up_y as isize + dy) as usize;
-                    
-                    if new_x > 0 && new_x < MAP_WIDTH - 1 && 
-                       new_y > 0 && new_y < MAP_HEIGHT - 1 &&
-                       self.tiles[new_y][new_x] == TileType::Floor {
-                        self.tiles[new_y][new_x] = TileType::StairsUp;
-                        self.up_stairs_pos = Some((new_x, new_y));
-                        println!("Placed UP stairs at position: ({}, {})", new_x, new_y);
-                        return;
+        }
+
+        println!("Placed {} altars", self.altar_positions.len());
+    }
+
+    // Link pairs of distant rooms with portals; twisting Labyrinth levels get the most use out of them
+    fn place_portals(&mut self, rng: &mut impl Rng) {
+        self.portal_pairs.clear();
+
+        let labyrinth = self.get_biome_at(self.spawn_position.0, self.spawn_position.1) == BiomeType::Labyrinth;
+        let pairs = if labyrinth {
+            LABYRINTH_PORTAL_PAIRS
+        } else if rng.gen_bool(PORTAL_CHANCE) {
+            1
+        } else {
+            0
+        };
+
+        let mut rooms: Vec<Room> = self.rooms.iter()
+            .filter(|room| !self.vaults.iter().any(|vault| vault.contains(room.x + room.width / 2, room.y + room.height / 2)))
+            .cloned()
+            .collect();
+        rooms.shuffle(rng);
+
+        for _ in 0..pairs {
+            let mut linked = None;
+            'search: for (i, first) in rooms.iter().enumerate() {
+                for (j, second) in rooms.iter().enumerate().skip(i + 1) {
+                    let (ax, ay) = first.center();
+                    let (bx, by) = second.center();
+                    if ax.abs_diff(bx) + ay.abs_diff(by) < PORTAL_MIN_DISTANCE {
+                        continue;
                     }
+                    let a = if let Some(pos) = self.find_portal_tile(first, rng) { pos } else { continue };
+                    let b = if let Some(pos) = self.find_portal_tile(second, rng) { pos } else { continue };
+                    linked = Some((i, j, a, b));
+                    break 'search;
                 }
             }
-            
-            self.tiles[up_y][up_x] = TileType::StairsUp;
-            self.up_stairs_pos = Some((up_x, up_y));
-            println!("Placed UP stairs at position: ({}, {})", up_x, up_y);
+            let (i, j, a, b) = if let Some(link) = linked { link } else { break };
+            self.portal_pairs.push((a, b));
+            // Each room holds one portal at most
+            rooms.remove(j);
+            rooms.remove(i);
+        }
+
+        println!("Placed {} portal pairs", self.portal_pairs.len());
+    }
+
+    // A free floor tile for a portal, off the stairs and anything already marked
+    fn find_portal_tile(&self, room: &Room, rng: &mut impl Rng) -> Option<(usize, usize)> {
+        let pos = self.find_free_floor_in_room(room, rng)?;
+        let taken = Some(pos) == self.down_stairs_pos
+            || Some(pos) == self.up_stairs_pos
+            || self.npc_spawns.contains(&pos)
+            || self.monster_spawns.contains(&pos)
+            || self.portal_destination(pos).is_some()
+            || self.altar_positions.contains(&pos);
+        if taken { None } else { Some(pos) }
+    }
+
+    // Where stepping on a tile sends the player, if it's a portal
+    pub fn portal_destination(&self, pos: (usize, usize)) -> Option<(usize, usize)> {
+        self.portal_pairs.iter().find_map(|&(a, b)| {
+            if a == pos {
+                Some(b)
+            } else if b == pos {
+                Some(a)
+            } else {
+                None
+            }
+        })
+    }
+    
+    // Whether a blocking prop stands on a tile
+    pub fn prop_blocks(&self, x: i32, y: i32) -> bool {
+        self.props.iter().any(|prop| prop.def.blocking && prop.x as i32 == x && prop.y as i32 == y)
+    }
+    
+    // The room containing a tile, if any; corridors and vaults aren't rooms
+    pub fn room_at(&self, x: usize, y: usize) -> Option<&Room> {
+        self.rooms.iter().find(|room| room.contains(x, y))
+    }
+    
+    // Hide the depth tier's traps in corridors and rooms, away from the stairs and spawn
+    fn place_traps(&mut self, rng: &mut impl Rng) {
+        self.trap_positions.clear();
+        
+        let mut candidates = Vec::new();
+        for y in 1..self.height - 1 {
+            for x in 1..self.width - 1 {
+                let pos = (x, y);
+                let near_spawn = (x as i32 - self.spawn_position.0 as i32).abs() + (y as i32 - self.spawn_position.1 as i32).abs() < 4;
+                if self.tiles[y][x] == TileType::Floor
+                    && !near_spawn
+                    && Some(pos) != self.down_stairs_pos
+                    && Some(pos) != self.up_stairs_pos
+                    && !self.chest_positions.contains(&pos)
+                    && !self.vaults.iter().any(|vault| vault.contains(x, y))
+                    && !self.npc_spawns.contains(&pos)
+                    && !self.monster_spawns.contains(&pos)
+                    && self.portal_destination(pos).is_none()
+                    && !self.altar_positions.contains(&pos)
+                {
+                    candidates.push(pos);
+                }
+            }
+        }
+        
+        candidates.shuffle(rng);
+        self.trap_positions = candidates.into_iter().take(self.depth_tier.traps).collect();
+        
+        println!("Placed {} traps", self.trap_positions.len());
+    }
+    
+    // Find a floor tile in a room that isn't stairs, the spawn point or already holding a chest
+    fn find_free_floor_in_room(&self, room: &Room, rng: &mut impl Rng) -> Option<(usize, usize)> {
+        for _ in 0..10 {
+            let (x, y) = self.find_valid_position_in_room(room, rng);
+            if x < self.width && y < self.height
+                && self.tiles[y][x] == TileType::Floor
+                && (x, y) != self.spawn_position
+                && !self.chest_positions.contains(&(x, y))
+            {
+                return Some((x, y));
+            }
         }
+        None
     }
     
     // Find a valid position in a room for placing stairs
@@ -1370,20 +2090,69 @@ impl TileMap {
     }
 }
 
+// The generator's stages one at a time and at any size, for the benchmarks in bench.rs
+#[cfg(feature = "bench")]
+impl TileMap {
+    // Rooms placed and carved into solid rock, not yet joined up
+    pub fn bench_rooms(map_width: usize, map_height: usize, rng: &mut impl Rng) -> (TileGrid, Vec<Room>) {
+        let mut tiles = vec![vec![TileType::Wall; map_width]; map_height];
+        let rooms = Self::generate_rooms(map_width, map_height, 1.0, rng);
+        for room in &rooms {
+            room.carve(&mut tiles, rng);
+        }
+        (tiles, rooms)
+    }
+
+    pub fn bench_connect_rooms(tiles: &mut [Vec<TileType>], rooms: &[Room], rng: &mut impl Rng) {
+        Self::connect_rooms(tiles, rooms, rng);
+    }
+
+    // The whole layout pass (rooms, corridors, vaults, biomes) at the given size, with the level's depth tier
+    pub fn bench_layout(level: usize, map_width: usize, map_height: usize, rng: &mut impl Rng) -> TileGrid {
+        let depth_tier = DepthProgression::load().tier_for_level(level);
+        Self::generate_map(level, map_width, map_height, &depth_tier, rng).0
+    }
+}
+
+#[cfg(test)]
+impl TileMap {
+    // Open floor with nothing placed on it, walled only where the test says; built directly
+    // so unit tests don't run the generator or read its data files
+    pub fn test_floor(width: usize, height: usize, walls: &[(usize, usize)]) -> Self {
+        let mut tiles = vec![vec![TileType::Floor; width]; height];
+        for &(x, y) in walls {
+            tiles[y][x] = TileType::Wall;
+        }
+        Self {
+            width,
+            height,
+            tiles,
+            rooms: Vec::new(),
+            biomes: vec![vec![BiomeType::Caves; width]; height],
+            spawn_position: (0, 0),
+            down_stairs_pos: None,
+            up_stairs_pos: None,
+            current_level: 0,
+            is_boss_level: false,
+            down_stairs_locked: false,
+            chest_positions: Vec::new(),
+            trap_positions: Vec::new(),
+            depth_tier: DepthTier::default(),
+            vaults: Vec::new(),
+            npc_spawns: Vec::new(),
+            monster_spawns: Vec::new(),
+            props: Vec::new(),
+            portal_pairs: Vec::new(),
+            altar_positions: Vec::new(),
+            seed: 0,
+        }
+    }
+}
+
 // Assign biomes to different regions of the map
-fn assign_biomes(biomes: &mut [[BiomeType; MAP_WIDTH]; MAP_HEIGHT], rooms: &[Room], rng: &mut impl Rng) {
-    // Select a single biome for the entire map based on the level
-    // We'll use a deterministic approach based on the current level
-    let available_biomes = [
-        BiomeType::Caves,
-        BiomeType::Groves,
-        BiomeType::Labyrinth,
-        BiomeType::Catacombs,
-    ];
-    
-    // Select a single biome for the entire map
-    let map_biome = available_biomes[rng.gen_range(0..available_biomes.len())];
-    
+fn assign_biomes(biomes: &mut [Vec<BiomeType>], rooms: &[Room], map_biome: BiomeType) {
+    let (map_width, map_height) = grid_size(biomes);
+    // The biome is picked from the depth progression table for the level
     println!("Map generated with biome: {:?}", map_biome);
     
     // Assign the same biome to all rooms
@@ -1391,7 +2160,7 @@ fn assign_biomes(biomes: &mut [[BiomeType; MAP_WIDTH]; MAP_HEIGHT], rooms: &[Roo
         // Apply the biome to the room area
         for y in room.y..(room.y + room.height) {
             for x in room.x..(room.x + room.width) {
-                if y < MAP_HEIGHT && x < MAP_WIDTH {
+                if y < map_height && x < map_width {
                     biomes[y][x] = map_biome;
                 }
             }
@@ -1399,8 +2168,8 @@ fn assign_biomes(biomes: &mut [[BiomeType; MAP_WIDTH]; MAP_HEIGHT], rooms: &[Roo
     }
     
     // Also assign the biome to corridors and other areas
-    for y in 0..MAP_HEIGHT {
-        for x in 0..MAP_WIDTH {
+    for y in 0..map_height {
+        for x in 0..map_width {
             biomes[y][x] = map_biome;
         }
     }
@@ -1413,12 +2182,14 @@ pub fn spawn_tiles(
     texture_atlases: &Res<TextureAtlases>,
     sprite_assets: &Res<SpriteAssets>,
     biome_manager: Option<&Res<BiomeManager>>,
+    reuse: &[Entity], // Existing tile entities, in the same row-by-row order, to rewrite rather than respawn
 ) -> Vec<Entity> {
-    let mut rng = rand::thread_rng();
+    // Seeded from the level so a revisited floor gets the same tile variants
+    let mut rng = crate::rng::RngStream::MapGen.seeded(map.seed);
     let mut tile_entities = Vec::new();
     
-    for y in 0..MAP_HEIGHT {
-        for x in 0..MAP_WIDTH {
+    for y in 0..map.height {
+        for x in 0..map.width {
             let (x_pos, y_pos) = (
                 x as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
                 y as f32 * TILE_SIZE + (TILE_SIZE / 2.0)
@@ -1438,6 +2209,9 @@ pub fn spawn_tiles(
                 TileType::StairsUp => TileWalkability::Walkable,
             };
             
+            // Set for tiles registered with an animation (water, embers...)
+            let mut animation = None;
+
             // Determine sprite index based on tile type and biome
             // IMPORTANT: We must ensure the sprite matches the actual tile type
             let (sprite_index, z_pos) = if let Some(biome_mgr) = biome_manager {
@@ -1456,7 +2230,31 @@ pub fn spawn_tiles(
                         }
                     }
                     TileType::Floor => {
-                        if let Some(tile_info) = biome_mgr.get_varied_floor_tile(biome, x, y, &mut rng) {
+                        let pool_tile = if biome_mgr.is_in_animated_pool(x, y) {
+                            biome_mgr.get_animated_floor_tile(biome, x, y)
+                        } else {
+                            None
+                        };
+
+                        // Special rooms lay their own floor over the biome's
+                        let purpose_tile = map.room_at(x, y)
+                            .and_then(|room| room.purpose)
+                            .and_then(|purpose| purpose.floor_tiles().choose(&mut rng))
+                            .and_then(|name| sprite_assets.tile_sprites.get(*name).copied());
+
+                        if let Some(tile_info) = pool_tile {
+                            animation = tile_info.animation.as_ref()
+                                .map(|def| crate::tile_animation::TileAnimation::from_def(def, x + y));
+                            (animation.as_ref().map_or(tile_info.sprite_index, |anim| anim.current_index()), 0.0)
+                        } else if let Some(sprite_index) = purpose_tile {
+                            (sprite_index, 0.0)
+                        } else if let Some(tile_info) = biome_mgr.is_on_biome_path(biome, x, y)
+                            .then(|| biome_mgr.get_path_tile(biome, x, y))
+                            .flatten()
+                        {
+                            // Winding dirt and stone paths through rooms and corridors
+                            (tile_info.sprite_index, 0.0)
+                        } else if let Some(tile_info) = biome_mgr.get_varied_floor_tile(biome, x, y, &mut rng) {
                             // Verify the walkability matches
                             if tile_info.walkability == TileWalkability::Walkable {
                                 (tile_info.sprite_index, 0.0)
@@ -1518,29 +2316,45 @@ pub fn spawn_tiles(
                 }
             };
 
-            // Spawn the tile entity with the correct components
-            let entity = commands.spawn((
-                SpriteSheetBundle {
-                    texture_atlas: texture_atlases.tiles.clone(),
-                    sprite: bevy::sprite::TextureAtlasSprite {
-                        index: sprite_index,
-                        color: Color::rgba(1.0, 1.0, 1.0, 1.0), // Always fully visible for debugging
-                        ..default()
-                    },
-                    transform: Transform::from_translation(Vec3::new(x_pos, y_pos, z_pos)),
-                    ..default()
-                },
+            let sprite = bevy::sprite::TextureAtlasSprite {
+                index: sprite_index,
+                color: Color::rgba(1.0, 1.0, 1.0, 0.0), // Hidden until seen; see update_tile_visibility
+                ..default()
+            };
+            let transform = Transform::from_translation(Vec3::new(x_pos, y_pos, z_pos));
+            let tile = (
                 TilePos { x: x as i32, y: y as i32 },
-                TileVisibility {
-                    visible: true, // Always visible for debugging
-                    previously_seen: true, // Always previously seen for debugging
-                },
+                TileVisibility::default(),
                 crate::components::Tile {
                     tile_type: map.tiles[y][x],
                     walkability,
                     biome,
                 },
-            )).id();
+            );
+
+            // Reuse a tile entity from the last level if there is one, rewriting it in place;
+            // otherwise spawn the tile entity with the correct components
+            let entity = if let Some(&entity) = reuse.get(tile_entities.len()) {
+                commands.entity(entity).insert((sprite, transform, tile));
+                if animation.is_none() {
+                    commands.entity(entity).remove::<crate::tile_animation::TileAnimation>();
+                }
+                entity
+            } else {
+                commands.spawn((
+                    SpriteSheetBundle {
+                        texture_atlas: texture_atlases.tiles.clone(),
+                        sprite,
+                        transform,
+                        ..default()
+                    },
+                    tile,
+                )).id()
+            };
+
+            if let Some(animation) = animation {
+                commands.entity(entity).insert(animation);
+            }
             
             // Store the entity ID
             tile_entities.push(entity);
@@ -1551,49 +2365,113 @@ pub fn spawn_tiles(
     tile_entities
 }
 
-pub fn spawn_grid_lines(commands: &mut Commands) {
+pub fn spawn_grid_lines(commands: &mut Commands, map: &TileMap) -> Vec<Entity> {
+    let mut grid_lines = Vec::new();
+
     // Spawn horizontal grid lines
-    for y in 0..=MAP_HEIGHT {
+    for y in 0..=map.height {
         let y_pos = y as f32 * TILE_SIZE;
-        commands.spawn((
+        grid_lines.push(commands.spawn((
             SpriteBundle {
                 sprite: Sprite {
                     color: Color::rgba(0.5, 0.5, 0.5, 0.2),
-                    custom_size: Some(Vec2::new(MAP_WIDTH as f32 * TILE_SIZE, 1.0)),
+                    custom_size: Some(Vec2::new(map.width as f32 * TILE_SIZE, 1.0)),
                     ..default()
                 },
-                transform: Transform::from_xyz(MAP_WIDTH as f32 * TILE_SIZE / 2.0, y_pos, 2.0),
+                transform: Transform::from_xyz(map.width as f32 * TILE_SIZE / 2.0, y_pos, 2.0),
                 visibility: Visibility::Hidden,
                 ..default()
             },
             GridLine,
-        ));
+        )).id());
     }
     
     // Spawn vertical grid lines
-    for x in 0..=MAP_WIDTH {
+    for x in 0..=map.width {
         let x_pos = x as f32 * TILE_SIZE;
-        commands.spawn((
+        grid_lines.push(commands.spawn((
             SpriteBundle {
                 sprite: Sprite {
                     color: Color::rgba(0.5, 0.5, 0.5, 0.2),
-                    custom_size: Some(Vec2::new(1.0, MAP_HEIGHT as f32 * TILE_SIZE)),
+                    custom_size: Some(Vec2::new(1.0, map.height as f32 * TILE_SIZE)),
                     ..default()
                 },
-                transform: Transform::from_xyz(x_pos, MAP_HEIGHT as f32 * TILE_SIZE / 2.0, 2.0),
+                transform: Transform::from_xyz(x_pos, map.height as f32 * TILE_SIZE / 2.0, 2.0),
                 visibility: Visibility::Hidden,
                 ..default()
             },
             GridLine,
-        ));
+        )).id());
     }
+    grid_lines
+}
+
+// Folder map text files are exported to, and the file F10 loads
+pub const MAP_TEXT_DIR: &str = "maps";
+pub const CUSTOM_MAP_PATH: &str = "maps/custom.txt";
+
+// Read a hand-authored map from CUSTOM_MAP_PATH
+pub fn load_custom_map() -> Result<TileMap, String> {
+    let text = std::fs::read_to_string(CUSTOM_MAP_PATH).map_err(|e| format!("could not read {}: {}", CUSTOM_MAP_PATH, e))?;
+    TileMap::from_string_grid(&text).map_err(|e| format!("{}: {}", CUSTOM_MAP_PATH, e))
 }
 
-pub fn toggle_grid_visibility(
-    _grid_query: Query<&mut Visibility, With<GridLine>>,
-    _keyboard_input: Res<Input<KeyCode>>,
+// System to write the current map as text (F9)
+pub fn export_map_text_system(
+    keyboard: Res<Input<KeyCode>>,
+    map: Res<TileMap>,
+    mut message_log: ResMut<crate::ui::MessageLog>,
 ) {
-    // Grid visibility toggle is currently disabled
+    if !keyboard.just_pressed(KeyCode::F9) {
+        return;
+    }
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let path = std::path::Path::new(MAP_TEXT_DIR).join(format!("level{}_{}.txt", map.current_level + 1, timestamp));
+
+    let result = std::fs::create_dir_all(MAP_TEXT_DIR).and_then(|_| std::fs::write(&path, map.to_string_grid()));
+    match result {
+        Ok(()) => {
+            println!("Exported map text to {}", path.display());
+            message_log.add_message(format!("Map text saved to {}", path.display()));
+        }
+        Err(e) => eprintln!("Could not export map text to {}: {}", path.display(), e),
+    }
+}
+
+// System to mention special rooms as the player walks into them
+pub fn announce_room_system(
+    map: Res<TileMap>,
+    player_query: Query<&crate::components::Position, With<crate::components::Player>>,
+    mut last_room: Local<Option<(usize, usize)>>,
+    mut message_log: ResMut<crate::ui::MessageLog>,
+) {
+    let position = if let Ok(position) = player_query.get_single() { position } else { return; };
+    if !map.in_bounds(position.x, position.y) {
+        return;
+    }
+
+    // Rooms are told apart by their corner, which stays put while the level does
+    let room = map.room_at(position.x as usize, position.y as usize);
+    let current = room.map(|room| (room.x, room.y));
+    if current == *last_room {
+        return;
+    }
+    *last_room = current;
+
+    if let Some(purpose) = room.and_then(|room| room.purpose) {
+        let description = match purpose {
+            RoomPurpose::Shrine => "You step into a quiet shrine.",
+            RoomPurpose::Library => "Dusty shelves line this library.",
+            RoomPurpose::Armory => "Racks of old weapons fill this armory.",
+            RoomPurpose::Nest => "Bones crunch underfoot. Something nests here.",
+            RoomPurpose::Shop => "A trader has set up shop here.",
+        };
+        message_log.add_message(description.to_string());
+    }
 }
 
 pub fn generate_map_visuals(
@@ -1603,29 +2481,54 @@ pub fn generate_map_visuals(
     sprite_assets: &Res<SpriteAssets>,
     texture_atlases: &Res<TextureAtlases>,
     biome_manager: &Res<BiomeManager>,
-    tile_entities: &mut TileEntities,
+    tile_index: &mut TileIndex,
 ) {
-    // Clear existing tile entities - but don't try to despawn them
-    // They might have already been despawned by handle_map_regeneration
-    tile_entities.entities.clear();
-    
-    // Spawn new tiles and store the entity IDs
-    let new_entities = spawn_tiles(commands, map, texture_atlases, sprite_assets, Some(biome_manager));
-    tile_entities.entities = new_entities;
+    // Level changes keep the tile and grid line entities alive and rewrite them in place;
+    // spawning ~1100 fresh entities every time was most of the cost of taking the stairs
+    let old_entities = tile_index.entities();
+    let reused = old_entities.len().min(map.width * map.height);
+    for &entity in &old_entities[reused..] {
+        commands.entity(entity).despawn_recursive();
+    }
+    tile_index.clear();
     
-    // Spawn grid lines
-    spawn_grid_lines(commands);
+    let new_entities = spawn_tiles(commands, map, texture_atlases, sprite_assets, Some(biome_manager), &old_entities[..reused]);
+    tile_index.rebuild(map, &new_entities);
     
-    // Log for debugging
-    println!("Map visuals regenerated with {} tile entities", tile_entities.entities.len());
+    // Grid lines only depend on the map's size
+    if tile_index.grid_size != Some((map.width, map.height)) {
+        for entity in tile_index.grid_lines.drain(..) {
+            commands.entity(entity).despawn();
+        }
+        let grid_lines = spawn_grid_lines(commands, map);
+        tile_index.set_grid_lines(map, grid_lines);
+    }
 }
 
+// Run condition for systems that rebuild their entities from the layout: true the first time
+// it runs and whenever a different layout has been loaded since. The map resource also changes
+// for small edits (e.g. opened chests), so change detection alone would rebuild too often
+pub fn layout_changed(map: Res<TileMap>, mut current_layout: Local<Option<LayoutKey>>) -> bool {
+    let layout = map.layout_key();
+    if *current_layout == Some(layout) {
+        return false;
+    }
+    *current_layout = Some(layout);
+    true
+}
+
+// System to draw the fog: tiles in sight at full strength, ones seen before dimmed, the rest hidden
 pub fn update_tile_visibility(
     visibility_map: Res<VisibilityMap>,
     mut query: Query<(&TilePos, &mut bevy::sprite::TextureAtlasSprite, &mut TileVisibility)>,
 ) {
     for (pos, mut sprite, mut tile_vis) in query.iter_mut() {
-        if visibility_map.visible_tiles[pos.y as usize][pos.x as usize] {
+        // The visibility map may still be sized for the previous level for a frame
+        let (x, y) = (pos.x as usize, pos.y as usize);
+        if y >= visibility_map.visible_tiles.len() || x >= visibility_map.visible_tiles[y].len() {
+            continue;
+        }
+        if visibility_map.visible_tiles[y][x] {
             sprite.color.set_a(1.0);
             tile_vis.previously_seen = true;
             tile_vis.visible = true;
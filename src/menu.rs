@@ -0,0 +1,163 @@
+use bevy::prelude::*;
+
+use crate::app_info::build_info;
+use crate::daily::{load_daily_history, runs_on, DailyChallenge};
+use crate::run_log::RunReplay;
+use crate::run_modifiers::{modifier_menu_sections, ModifierMenuText, RunModifier, RunModifiers};
+use crate::run_summary::{high_scores, load_run_history, RunOutcome};
+use crate::GameState;
+
+// How many of the most recent runs the Hall of Records lists under the high scores
+const RECENT_RUNS: usize = 8;
+
+/// Marker for everything on the main menu
+#[derive(Component)]
+pub struct MainMenuScreen;
+
+/// Marker for everything on the Hall of Records screen
+#[derive(Component)]
+pub struct HallOfRecordsScreen;
+
+// A full-screen column to hang a menu screen's text off
+pub fn screen_root() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(24.0),
+            ..default()
+        },
+        background_color: BackgroundColor(Color::rgb(0.05, 0.05, 0.07)),
+        z_index: ZIndex::Global(200),
+        ..default()
+    }
+}
+
+pub fn setup_main_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    daily: Res<DailyChallenge>,
+    modifiers: Res<RunModifiers>,
+) {
+    let font = asset_server.load("fonts/FiraSans-Light.ttf");
+
+    commands.spawn((screen_root(), MainMenuScreen)).with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            "CHASM",
+            TextStyle { font: font.clone(), font_size: 64.0, color: Color::GOLD },
+        ));
+        parent.spawn(TextBundle::from_section(
+            format!("Enter - Descend\nD - Daily Chasm ({})\nH - Hall of Records\nC - Codex\nEsc - Quit", daily.date),
+            TextStyle { font: font.clone(), font_size: 22.0, color: Color::WHITE },
+        ).with_text_alignment(TextAlignment::Center));
+        parent.spawn((TextBundle::from_sections(modifier_menu_sections(&modifiers, &font)), ModifierMenuText));
+        parent.spawn(TextBundle::from_section(
+            build_info(),
+            TextStyle { font: font.clone(), font_size: 14.0, color: Color::GRAY },
+        ));
+    });
+}
+
+// System to pick a menu entry; replays skip straight into the run
+pub fn main_menu_system(
+    keyboard: Res<Input<KeyCode>>,
+    replay: Res<RunReplay>,
+    mut daily: ResMut<DailyChallenge>,
+    mut modifiers: ResMut<RunModifiers>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for modifier in RunModifier::ALL {
+        if keyboard.just_pressed(modifier.key()) {
+            modifiers.toggle(modifier);
+        }
+    }
+
+    if replay.active {
+        // Played under the same rules as the recorded run
+        modifiers.active = replay.modifiers.clone();
+        next_state.set(GameState::InGame);
+    } else if keyboard.any_just_pressed([KeyCode::Return, KeyCode::Space]) {
+        next_state.set(GameState::InGame);
+    } else if keyboard.just_pressed(KeyCode::D) {
+        // Everyone plays the daily dungeon under the same rules
+        daily.active = true;
+        modifiers.active.clear();
+        next_state.set(GameState::InGame);
+    } else if keyboard.just_pressed(KeyCode::H) {
+        next_state.set(GameState::HallOfRecords);
+    } else if keyboard.just_pressed(KeyCode::C) {
+        next_state.set(GameState::Codex);
+    }
+}
+
+pub fn setup_hall_of_records(mut commands: Commands, asset_server: Res<AssetServer>, daily: Res<DailyChallenge>) {
+    let font = asset_server.load("fonts/FiraSans-Light.ttf");
+    let style = |size: f32, color: Color| TextStyle { font: font.clone(), font_size: size, color };
+
+    let history = load_run_history();
+    let runs = history.len();
+    let escapes = history.iter().filter(|record| record.outcome == RunOutcome::Escaped).count();
+
+    let mut best = vec![TextSection::new("High Scores\n", style(24.0, Color::GOLD))];
+    for (index, record) in high_scores(&history).iter().enumerate() {
+        best.push(TextSection::new(
+            format!("{:>2}. {:>6}   floor {:>2}   {:>5} turns   {}\n", index + 1, record.score, record.depth, record.turns, record.outcome.describe()),
+            style(16.0, Color::WHITE),
+        ));
+    }
+
+    let mut recent = vec![TextSection::new("Recent Runs\n", style(24.0, Color::GOLD))];
+    for record in history.iter().rev().take(RECENT_RUNS) {
+        recent.push(TextSection::new(
+            format!("seed {:<20}   floor {:>2}   {:>6} pts   {}\n", record.seed, record.depth, record.score, record.outcome.describe()),
+            style(16.0, Color::rgb(0.8, 0.8, 0.8)),
+        ));
+    }
+    if runs == 0 {
+        recent.push(TextSection::new("No runs yet.\n", style(16.0, Color::GRAY)));
+    }
+
+    let mut today = vec![TextSection::new(format!("Daily Chasm {}\n", daily.date), style(24.0, Color::GOLD))];
+    let daily_table = high_scores(&runs_on(&load_daily_history(), &daily.date));
+    for (index, record) in daily_table.iter().enumerate() {
+        today.push(TextSection::new(
+            format!("{:>2}. {:>6}   floor {:>2}   {:>5} turns   {}\n", index + 1, record.score, record.depth, record.turns, record.outcome.describe()),
+            style(16.0, Color::WHITE),
+        ));
+    }
+    if daily_table.is_empty() {
+        today.push(TextSection::new("Not attempted today.\n", style(16.0, Color::GRAY)));
+    }
+
+    commands.spawn((screen_root(), HallOfRecordsScreen)).with_children(|parent| {
+        parent.spawn(TextBundle::from_section("Hall of Records", style(40.0, Color::WHITE)));
+        parent.spawn(TextBundle::from_section(
+            format!("{} runs, {} escapes", runs, escapes),
+            style(18.0, Color::GRAY),
+        ));
+        parent.spawn(TextBundle::from_sections(best));
+        parent.spawn(TextBundle::from_sections(today));
+        parent.spawn(TextBundle::from_sections(recent));
+        parent.spawn(TextBundle::from_section("Backspace - Back", style(16.0, Color::GRAY)));
+    });
+}
+
+// System to go back to the main menu
+pub fn hall_of_records_system(
+    keyboard: Res<Input<KeyCode>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard.any_just_pressed([KeyCode::Back, KeyCode::H, KeyCode::Return]) {
+        next_state.set(GameState::MainMenu);
+    }
+}
+
+// Tear a screen down when its state is left
+pub fn despawn_screen<T: Component>(mut commands: Commands, query: Query<Entity, With<T>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,92 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::assets::{SpriteAssets, TextureAtlases};
+use crate::combat::Health;
+use crate::components::{Animal, Companion, GameTurn, Position};
+use crate::emotes::{EmoteKind, ShowEmote};
+use crate::faction::Hostile;
+use crate::gold::spawn_treasure_pile;
+use crate::map::TileMap;
+use crate::rng::GameRng;
+use crate::ui::MessageLog;
+
+// Each monster close by lowers the share of health another will run at by this much
+const ALLY_STEADYING: f32 = 0.1;
+// How close (in steps either way) another monster has to be to count as backup
+const ALLY_RANGE: i32 = 4;
+// Chance a monster that breaks drops what it's been hoarding as it runs
+const PANIC_DROP_CHANCE: f64 = 0.35;
+
+/// How readily a monster breaks and runs once it's badly hurt
+#[derive(Component, Debug, Clone)]
+pub struct Morale {
+    pub courage: f32,  // Share of its health left at which it runs when alone; 0.0 fights to the death
+    pub fleeing: bool, // Running for open ground instead of fighting
+}
+
+impl Morale {
+    pub fn new(courage: f32) -> Self {
+        Self { courage, fleeing: false }
+    }
+
+    // Whether a monster this hurt, with this many friends about, holds its nerve
+    fn breaks(&self, health: &Health, allies: usize) -> bool {
+        let threshold = self.courage - ALLY_STEADYING * allies as f32;
+        threshold > 0.0 && (health.current as f32) <= health.max as f32 * threshold
+    }
+}
+
+// System to have each monster weigh up its wounds and its friends every turn: badly hurt and alone,
+// it turns and runs (maybe scattering its hoard), and rallies again if enough help arrives
+pub fn update_morale_system(
+    mut commands: Commands,
+    mut monster_query: Query<(Entity, &Animal, &Position, &Health, &mut Morale), (With<Hostile>, Without<Companion>)>,
+    texture_atlases: Res<TextureAtlases>,
+    sprite_assets: Res<SpriteAssets>,
+    map: Res<TileMap>,
+    game_turn: Res<GameTurn>,
+    mut game_rng: ResMut<GameRng>,
+    mut message_log: ResMut<MessageLog>,
+    mut emote_events: EventWriter<ShowEmote>,
+    mut local: Local<u32>,
+) {
+    // Only weigh things up once per turn
+    if game_turn.current_turn == 0 || game_turn.current_turn == *local {
+        return;
+    }
+    *local = game_turn.current_turn;
+
+    let monsters: Vec<(Entity, (i32, i32))> = monster_query.iter()
+        .filter(|(_, _, _, health, _)| !health.is_dead())
+        .map(|(entity, _, position, ..)| (entity, (position.x, position.y)))
+        .collect();
+
+    for (entity, animal, position, health, mut morale) in monster_query.iter_mut() {
+        if health.is_dead() {
+            continue;
+        }
+        let allies = monsters.iter()
+            .filter(|(other, tile)| *other != entity && (tile.0 - position.x).abs().max((tile.1 - position.y).abs()) <= ALLY_RANGE)
+            .count();
+        let breaks = morale.breaks(health, allies);
+        if breaks == morale.fleeing {
+            continue;
+        }
+        morale.fleeing = breaks;
+
+        let name = animal.animal_type.get_name().to_lowercase();
+        if !breaks {
+            message_log.add_message(format!("The {} finds its courage again.", name));
+            continue;
+        }
+        emote_events.send(ShowEmote::icon(entity, EmoteKind::Alert));
+        if game_rng.combat.gen_bool(PANIC_DROP_CHANCE) {
+            let coins = game_rng.combat.gen_range(1..=3) + map.current_level as u32;
+            spawn_treasure_pile(&mut commands, &texture_atlases, &sprite_assets, (position.x, position.y), coins, true);
+            message_log.add_message(format!("The {} turns tail, dropping something that glints!", name));
+        } else {
+            message_log.add_message(format!("The {} turns tail and runs!", name));
+        }
+    }
+}
@@ -0,0 +1,660 @@
+use bevy::prelude::*;
+use bevy::sprite::{TextureAtlas, TextureAtlasSprite};
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::animals::AnimalManager;
+use crate::assets::{SpriteAssets, TextureAtlases};
+use crate::biome::BiomeType;
+use crate::camera::CameraControl;
+use crate::combat::{CombatStats, Health};
+use crate::components::{Npc, Player, Position};
+use crate::conversation::{Conversation, DialogueChoiceMade};
+use crate::dialogue::{CharacterType, ResponseKind, generate_biome_dialogue, generate_greeting, generate_idle_remark, generate_quest_hint, generate_responses};
+use crate::events::AnimalTamed;
+use crate::faction::{Faction, Hostile, Reputation, ReputationChange, Standing};
+use crate::gold::Purse;
+use crate::identify::{with_article, ItemAppearances};
+use crate::infighting::CreatureFaction;
+use crate::input::TILE_SIZE;
+use crate::interaction::{Interactable, InteractedWith, InteractionKind};
+use crate::inventory::Inventory;
+use crate::map::TileMap;
+use crate::party::{party_responses, PartyChange, PartyMember};
+use crate::rng::GameRng;
+use crate::shop::{buy_price, sell_price, shop_responses, wares};
+use crate::ui::MessageLog;
+use crate::world_facts::WorldFacts;
+use crate::{GameState, GameplaySet};
+
+// How hard a cultist hits the creatures it turns on
+const CULTIST_ATTACK: i32 = 2;
+// How often asking about the depths turns up a hint rather than small talk
+const QUEST_HINT_CHANCE: f64 = 0.3;
+
+/// Everyone else in the dungeon: NPCs and their conversations, factions,
+/// animals and companions, bosses, and the creatures that wander in later
+pub struct NpcPlugin;
+
+impl Plugin for NpcPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ReputationChange>()
+            .add_event::<DialogueChoiceMade>()
+            .add_event::<crate::hearing::NoiseEvent>()
+            .add_event::<AnimalTamed>()
+            .add_event::<PartyChange>()
+            .init_resource::<AnimalManager>()
+            .init_resource::<Reputation>()
+            .init_resource::<Conversation>()
+            .init_resource::<crate::pathmaps::PathMaps>()
+            .init_resource::<crate::npc_registry::NpcRegistry>()
+            .init_resource::<WorldFacts>()
+            .insert_resource(crate::codex::Codex::load())
+            .insert_resource(crate::abilities::MonsterAbilities::load())
+            .insert_resource(crate::escalation::EscalationSettings::load())
+            .init_resource::<crate::escalation::Escalation>()
+            .init_resource::<crate::warden::WardenState>()
+            .add_systems(OnEnter(GameState::InGame), (
+                initialize_animal_manager,
+                crate::conversation::setup_conversation_panel,
+                crate::npc_registry::generate_npc_registry.after(crate::level::spawn_game_world),
+                crate::world_facts::reset_world_facts,
+                crate::escalation::reset_escalation,
+                crate::warden::reset_warden,
+            ).run_if(crate::loading_screen::starting_run))
+            .add_systems(
+                Update,
+                (
+                    crate::morale::update_morale_system.after(crate::player::process_turn_effects),
+                    crate::animals::move_animals_system
+                        .after(crate::morale::update_morale_system)
+                        .in_set(GameplaySet::World),
+                    crate::animals::animate_animal_movement
+                        .after(crate::animals::move_animals_system)
+                        .in_set(GameplaySet::World),
+                    crate::systems::check_dialog_distance.in_set(GameplaySet::World),
+                    crate::map::update_tile_visibility
+                        .after(crate::visibility::update_visibility)
+                        .in_set(GameplaySet::Visibility),
+                    handle_npc_interaction
+                        .after(crate::systems::check_dialog_distance)
+                        .in_set(GameplaySet::World),
+                    animate_speaking_npcs.in_set(GameplaySet::Presentation),
+                    crate::dialog_box::render_dialog_boxes.in_set(GameplaySet::Presentation),
+                )
+                .run_if(in_state(GameState::InGame))
+            )
+            .add_systems(
+                Update,
+                (
+                    crate::conversation::choose_dialogue_response
+                        .after(handle_npc_interaction)
+                        .before(crate::dialog_box::render_dialog_boxes),
+                    apply_dialogue_choices.after(crate::conversation::choose_dialogue_response),
+                    crate::systems::enforce_single_conversation
+                        .after(apply_dialogue_choices)
+                        .before(crate::conversation::update_conversation_panel),
+                    crate::conversation::scroll_conversation_history,
+                    crate::conversation::update_conversation_panel
+                        .after(crate::conversation::scroll_conversation_history)
+                        .after(crate::dialog_box::render_dialog_boxes),
+                )
+                .run_if(in_state(GameState::InGame))
+            )
+            .add_systems(
+                Update,
+                (
+                    crate::faction::apply_reputation_changes,
+                    crate::faction::update_npc_hostility.after(crate::faction::apply_reputation_changes),
+                )
+                .run_if(in_state(GameState::InGame))
+            )
+            .add_systems(
+                Update,
+                (
+                    crate::animals::feed_animal_system,
+                    crate::animals::move_companions_system,
+                    crate::party::apply_party_changes.after(apply_dialogue_choices),
+                    crate::party::move_party_system
+                        .after(crate::animals::move_companions_system)
+                        .after(crate::pathmaps::update_path_maps)
+                        .before(crate::combat::despawn_dead_entities),
+                    crate::animals::animal_attack_system,
+                    crate::infighting::creature_infighting_system
+                        .after(crate::animals::move_animals_system)
+                        .before(crate::combat::despawn_dead_entities),
+                    crate::abilities::use_monster_abilities
+                        .after(crate::animals::move_animals_system)
+                        .after(crate::animals::animal_attack_system)
+                        .before(crate::combat::despawn_dead_entities),
+                )
+                .run_if(in_state(GameState::InGame))
+            )
+            .add_systems(
+                Update,
+                (
+                    crate::boss::setup_boss_floor_system,
+                    crate::boss::update_stairs_seal,
+                    crate::boss::boss_ai_system,
+                    crate::boss::boss_defeated_system
+                        .after(crate::boss::boss_ai_system)
+                        .after(crate::combat::animate_projectiles)
+                        .before(crate::combat::despawn_dead_entities),
+                    crate::warden::warden_stalk_system
+                        .after(crate::pathmaps::update_path_maps)
+                        .after(crate::scent::update_scent_system),
+                    crate::warden::banish_warden_system,
+                    crate::faction::hostile_npc_system
+                        .after(crate::faction::update_npc_hostility)
+                        .after(crate::pathmaps::update_path_maps)
+                        .before(crate::combat::despawn_dead_entities),
+                )
+                .run_if(in_state(GameState::InGame))
+            )
+            .add_systems(
+                Update,
+                (
+                    crate::hearing::hear_noises_system
+                        .after(crate::hearing::footstep_noise_system)
+                        .before(crate::animals::move_animals_system),
+                    crate::codex::record_encounters_system,
+                    crate::dialogue_content::hot_reload_dialogue,
+                    crate::world_facts::update_world_facts.before(handle_npc_interaction),
+                    crate::world_facts::record_world_events
+                        .after(crate::combat::despawn_dead_entities)
+                        .after(crate::level::handle_stairs_system),
+                    crate::npc_registry::place_unique_npcs.after(crate::level::handle_stairs_system),
+                    crate::npc_registry::record_unique_npc_progress.after(handle_npc_interaction),
+                    crate::pathmaps::update_path_maps
+                        .after(crate::input::move_player)
+                        .before(crate::animals::move_animals_system)
+                        .before(crate::animals::move_companions_system)
+                        .before(crate::boss::boss_ai_system),
+                )
+                .run_if(in_state(GameState::InGame))
+                .run_if(resource_exists::<TileMap>())
+            )
+            .add_systems(
+                Update,
+                (
+                    crate::escalation::escalation_system,
+                    crate::warden::warden_arrival_system,
+                    crate::spawn_director::respawn_creatures_system.after(crate::escalation::escalation_system),
+                )
+                    .run_if(in_state(GameState::InGame))
+                    .run_if(resource_exists::<TileMap>())
+                    .run_if(resource_exists::<TextureAtlases>())
+            );
+    }
+}
+
+// Function to spawn an NPC at a given position with random character type
+pub fn spawn_npc(
+    commands: &mut Commands,
+    texture_atlases: &TextureAtlases,
+    sprite_assets: &SpriteAssets,
+    npc_pos: (i32, i32),
+    biome: &BiomeType,
+    rng: &mut GameRng,
+) {
+    // Get all available character sprites
+    let available_sprites = crate::dialogue::get_available_character_sprites();
+    
+    // Choose a random sprite
+    let sprite_name = available_sprites.choose(&mut rng.spawns).unwrap_or(&"dwarf".to_string()).clone();
+    
+    // Get the sprite index
+    let sprite_index = crate::assets::get_character_sprite(sprite_assets, &sprite_name);
+    
+    // Determine character type from sprite name
+    let character_type = CharacterType::from_sprite_name(&sprite_name);
+    
+    // Determine which faction the NPC belongs to
+    let faction = Faction::from_character_type(&character_type);
+    
+    // Generate a name based on character type
+    let npc_name = character_type.generate_name(&mut rng.dialogue);
+    
+    // Generate cryptic dialogue instead of regular dialogue
+    let mut dialog = crate::dialogue::generate_cryptic_dialogue(&mut rng.dialogue);
+    
+    // Add biome-specific cryptic dialogue
+    let biome_dialog = crate::dialogue::generate_biome_cryptic_dialogue(biome, &mut rng.dialogue);
+    dialog.push(biome_dialog);
+    
+    // Get the first dialogue line as the initial text
+    let dialog_text = dialog.first().cloned().unwrap_or_else(|| "The void watches.".to_string());
+    
+    println!("Spawning NPC '{}' ({:?}, {}) at position: ({}, {})", npc_name, character_type, faction.get_name(), npc_pos.0, npc_pos.1);
+    
+    let npc = Npc {
+        name: npc_name,
+        dialog,
+        current_dialog_index: 0,
+        met: false,
+        lines_heard: 0,
+        speaking: false,
+        dialog_text,
+        character_type,
+        animation_timer: Timer::from_seconds(0.15, TimerMode::Repeating), // Faster animation
+        original_scale: Vec3::splat(1.0),
+        wiggle_direction: 1.0,
+        wiggle_amount: 0.1, // Increased wiggle amount
+        is_animal: false,
+        animal_type: None,
+    };
+    spawn_npc_entity(commands, texture_atlases, sprite_index, npc_pos, npc, faction);
+}
+
+// Spawn an NPC that has already been rolled, fresh or brought back from an earlier visit to the level
+pub fn spawn_npc_entity(
+    commands: &mut Commands,
+    texture_atlases: &TextureAtlases,
+    sprite_index: usize,
+    npc_pos: (i32, i32),
+    npc: Npc,
+    faction: Faction,
+) -> Entity {
+    let label = format!("Talk to {}", npc.name);
+    let npc_entity = commands.spawn((
+        SpriteSheetBundle {
+            texture_atlas: texture_atlases.characters.clone(),
+            sprite: TextureAtlasSprite {
+                index: sprite_index,
+                ..default()
+            },
+            transform: Transform::from_xyz(
+                npc_pos.0 as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+                npc_pos.1 as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+                5.0  // Increased z-index to ensure NPCs render on top of floor and wall assets
+            ).with_scale(Vec3::splat(1.0)),
+            ..default()
+        },
+        Interactable::new(InteractionKind::Talk, label),
+        npc,
+        faction,
+        Health::new(10),
+        Position::new(npc_pos.0, npc_pos.1),
+    )).id();
+    
+    // Cultists lash out at any creature that wanders too close
+    if faction == Faction::Cultists {
+        commands.entity(npc_entity).insert((CreatureFaction::Cultists, CombatStats { attack: CULTIST_ATTACK }));
+    }
+    npc_entity
+}
+
+// Put an NPC on every spot the map marked for one (vault markers, shrine keepers, shopkeepers...)
+pub fn spawn_marked_npcs(
+    commands: &mut Commands,
+    texture_atlases: &TextureAtlases,
+    sprite_assets: &SpriteAssets,
+    map: &TileMap,
+    rng: &mut GameRng,
+) {
+    for &(x, y) in &map.npc_spawns {
+        spawn_npc(commands, texture_atlases, sprite_assets, (x as i32, y as i32), &map.get_biome_at(x, y), rng);
+    }
+}
+
+pub fn handle_npc_interaction(
+    keyboard: Res<Input<KeyCode>>,
+    reputation: Res<Reputation>,
+    facts: Res<WorldFacts>,
+    mut interactions: EventReader<InteractedWith>,
+    mut conversation: ResMut<Conversation>,
+    mut game_rng: ResMut<GameRng>,
+    party_query: Query<(), With<PartyMember>>,
+    mut message_log: ResMut<MessageLog>,
+    mut params: ParamSet<(
+        Query<(Entity, &Position, &mut Npc, &Transform, Option<&Faction>, Option<&Handle<TextureAtlas>>, Option<&TextureAtlasSprite>, Option<&Hostile>)>,
+        Query<(&Position, &Transform), With<Player>>,
+        Query<(&mut CameraControl, &mut Transform), Without<Player>>
+    )>,
+) {
+    // E on an NPC starts a conversation; E or Space moves it along once the current line has finished typing
+    let advancing = conversation.is_active() && (keyboard.just_pressed(KeyCode::E) || keyboard.just_pressed(KeyCode::Space));
+    let chosen = interactions.read().find(|interaction| interaction.kind == InteractionKind::Talk).map(|interaction| interaction.target);
+    if chosen.is_none() && !advancing {
+        return;
+    }
+    if advancing && (!conversation.line_finished || conversation.awaiting_choice()) {
+        // The dialog box skips to the end of the line, or the key picks a response instead
+        return;
+    }
+
+    // First, collect all the data we need
+    let player_data = if let Ok(pos) = params.p1().get_single() {
+        Some((Position { x: pos.0.x, y: pos.0.y }, pos.1.translation))
+    } else {
+        None
+    };
+    
+    if player_data.is_none() {
+        return;
+    }
+    
+    let (player_pos, player_transform_translation) = player_data.unwrap();
+    
+    // Find NPCs that are close to the player
+    let mut npc_to_interact = None;
+    
+    for (entity_id, npc_pos, npc, npc_transform, faction, atlas, sprite, hostile) in params.p0().iter() {
+        let dx = (npc_pos.x - player_pos.x).abs();
+        let dy = (npc_pos.y - player_pos.y).abs();
+        
+        // Keep talking to the same NPC even if another one is also adjacent
+        let wanted = if advancing { conversation.speaker } else { chosen };
+        if wanted != Some(entity_id) {
+            continue;
+        }
+        
+        if dx <= 1 && dy <= 1 && faction.is_some() && hostile.is_some() {
+            // Their faction has turned on the player; there's nothing left to talk about
+            message_log.add_message(format!("{} has nothing to say to you", npc.name));
+            return;
+        }
+
+        if dx <= 1 && dy <= 1 {
+            // Found an NPC to interact with. Strangers open with a greeting; acquaintances go
+            // straight on to what they haven't said yet, and once that's all been heard they only pass the time
+            let exhausted = npc.lines_heard >= npc.dialog.len();
+            let greeting = !npc.speaking && !npc.met;
+            let from_pool = !greeting && !exhausted;
+            let next_dialog = if greeting {
+                generate_greeting(&facts, &mut game_rng.dialogue)
+            } else if exhausted {
+                generate_idle_remark(&facts, &mut game_rng.dialogue)
+            } else {
+                // Factions that feel strongly about the player say so instead of their usual line
+                faction
+                    .and_then(|faction| crate::dialogue::generate_reputation_dialogue(faction, reputation.standing(*faction), &facts, &mut game_rng.dialogue))
+                    .unwrap_or_else(|| npc.dialog[npc.lines_heard].clone())
+            };
+            
+            // The conversation wraps up once there's nothing new left to say
+            let finished = npc.speaking && exhausted;
+            let portrait = atlas.zip(sprite).map(|(atlas, sprite)| (atlas.clone(), sprite.index));
+            
+            npc_to_interact = Some((
+                entity_id,
+                npc.speaking,
+                finished,
+                next_dialog,
+                npc_transform.translation,
+                npc_transform.scale,
+                from_pool,
+                npc.name.clone(),
+                portrait,
+            ));
+            break;
+        }
+    }
+    
+    // If we found an NPC to interact with, update it and the camera
+    if let Some((entity_id, is_speaking, finished, next_dialog, npc_translation, npc_scale, from_pool, name, portrait)) = npc_to_interact {
+        // Only one NPC talks at a time: turning to someone else cuts off whoever was speaking,
+        // and the camera goes back out before closing in on the new pair
+        if let Some(previous) = conversation.speaker.filter(|&speaker| speaker != entity_id) {
+            if let Ok((_, _, mut npc, _, _, _, _, _)) = params.p0().get_mut(previous) {
+                npc.speaking = false;
+            }
+            conversation.end();
+            if let Ok((mut camera_control, mut camera_transform)) = params.p2().get_single_mut() {
+                camera_control.end_dialog(previous, &mut camera_transform);
+            }
+        }
+
+        // Then update the camera
+        {
+            let mut camera_query = params.p2();
+            let (mut camera_control, mut camera_transform) = camera_query.single_mut();
+            
+            // Calculate midpoint between player and NPC for camera focus
+            let midpoint = Vec3::new(
+                (player_transform_translation.x + npc_translation.x) / 2.0,
+                (player_transform_translation.y + npc_translation.y) / 2.0,
+                camera_transform.translation.z
+            );
+            
+            if !is_speaking {
+                // Close in on the conversation
+                camera_control.begin_dialog(entity_id, &mut camera_transform, midpoint);
+            } else if finished {
+                // Reset camera zoom and position to where they were
+                camera_control.end_dialog(entity_id, &mut camera_transform);
+            }
+        }
+        
+        // Then update the NPC
+        {
+            let mut npc_query = params.p0();
+            if let Ok((_, _, mut npc, _, _, _, _, _)) = npc_query.get_mut(entity_id) {
+                if !is_speaking || !finished {
+                    // Start speaking, or move on to the next line
+                    npc.speaking = true;
+                    npc.met = true;
+                    
+                    // Remember how far through their lines they've got
+                    if from_pool {
+                        npc.current_dialog_index = npc.lines_heard;
+                        npc.lines_heard += 1;
+                    }
+                    npc.dialog_text = next_dialog.clone();
+                    
+                    if is_speaking {
+                        conversation.say(next_dialog);
+                    } else {
+                        // Store original scale for animation
+                        npc.original_scale = npc_scale;
+                        conversation.begin(entity_id, name, portrait, next_dialog);
+                    }
+                    // Party members are asked for help rather than news
+                    if party_query.contains(entity_id) {
+                        conversation.offer(party_responses());
+                    } else {
+                        conversation.offer(generate_responses(&npc.character_type));
+                    }
+                } else {
+                    // Stop speaking
+                    npc.speaking = false;
+                    conversation.end();
+                }
+            }
+        }
+    }
+}
+
+// React to the response the player picked in a conversation
+pub fn apply_dialogue_choices(
+    mut choice_events: EventReader<DialogueChoiceMade>,
+    mut conversation: ResMut<Conversation>,
+    map: Res<TileMap>,
+    mut game_rng: ResMut<GameRng>,
+    mut npc_query: Query<(&Position, &mut Npc)>,
+    mut camera_query: Query<(&mut CameraControl, &mut Transform), Without<Player>>,
+    mut player_query: Query<&mut Inventory, With<Player>>,
+    mut appearances: ResMut<ItemAppearances>,
+    mut purse: ResMut<Purse>,
+    reputation: Res<Reputation>,
+    facts: Res<WorldFacts>,
+    mut message_log: ResMut<MessageLog>,
+    mut party_query: Query<&mut Inventory, (With<PartyMember>, Without<Player>)>,
+    mut party_events: EventWriter<PartyChange>,
+) {
+    // Joins only land once the event is read, so count them here to keep to the party size
+    let mut party_size = party_query.iter().count();
+    for event in choice_events.read() {
+        let (npc_pos, mut npc) = if let Ok(npc) = npc_query.get_mut(event.speaker) {
+            npc
+        } else {
+            continue;
+        };
+        // Whether they're travelling with the player once this response is dealt with
+        let mut member = party_query.contains(event.speaker);
+
+        let reply = match event.response.kind {
+            ResponseKind::Continue => {
+                npc.current_dialog_index = (npc.current_dialog_index + 1) % npc.dialog.len();
+                npc.dialog[npc.current_dialog_index].clone()
+            }
+            ResponseKind::AskAboutDepths => {
+                // Now and then they point the player at something worth finding instead
+                let hint = if game_rng.dialogue.gen_bool(QUEST_HINT_CHANCE) {
+                    generate_quest_hint(&npc.character_type, &facts, &mut game_rng.dialogue)
+                } else {
+                    None
+                };
+                let biome = map.get_biome_at(npc_pos.x as usize, npc_pos.y as usize);
+                hint.unwrap_or_else(|| generate_biome_dialogue(&npc.character_type, &biome, &facts, &mut game_rng.dialogue))
+            }
+            ResponseKind::Trade => {
+                let multiplier = reputation.price_multiplier(Faction::from_character_type(&npc.character_type));
+                let prices: Vec<String> = wares(&npc.character_type)
+                    .into_iter()
+                    .map(|item| format!("{} for {} gold", with_article(item.get_name()), buy_price(item, multiplier)))
+                    .collect();
+                if prices.is_empty() {
+                    "I've nothing to sell, but I'll buy what you've got.".to_string()
+                } else {
+                    format!("I have {}. And I'll buy hides, fangs and the like.", prices.join(", "))
+                }
+            }
+            ResponseKind::Buy(item) => {
+                let multiplier = reputation.price_multiplier(Faction::from_character_type(&npc.character_type));
+                let price = buy_price(item, multiplier);
+                let mut inventory = if let Ok(inventory) = player_query.get_single_mut() { inventory } else { continue; };
+                if purse.spend(price) {
+                    inventory.add(item);
+                    // Bought by name, so there's no mystery about what it is
+                    appearances.identify(item);
+                    message_log.add_message(format!("You buy {} for {} gold", with_article(item.get_name()), price));
+                    "A fine choice. Anything else?".to_string()
+                } else {
+                    format!("That's {} gold, and you've only {}.", price, purse.gold)
+                }
+            }
+            ResponseKind::Sell => {
+                let multiplier = reputation.price_multiplier(Faction::from_character_type(&npc.character_type));
+                let mut inventory = if let Ok(inventory) = player_query.get_single_mut() { inventory } else { continue; };
+                let mut earned = 0;
+                inventory.items.retain(|&item| match sell_price(item, multiplier) {
+                    Some(price) => {
+                        earned += price;
+                        false
+                    }
+                    None => true,
+                });
+                if earned == 0 {
+                    "You've nothing I'd pay for.".to_string()
+                } else {
+                    purse.add(earned);
+                    message_log.add_message(format!("You sell your spoils for {} gold", earned));
+                    format!("Here's {} gold for the lot.", earned)
+                }
+            }
+            ResponseKind::Identify => {
+                // Sages name anything the player hasn't learned yet, free and without the risk of trying it
+                let carried = player_query.get_single().map_or(Vec::new(), |inventory| inventory.stacks());
+                let mut named = Vec::new();
+                for (item, _) in carried {
+                    let looks = appearances.display_name(item);
+                    if appearances.identify(item) {
+                        named.push(format!("the {} is {}", looks, with_article(item.get_name())));
+                    }
+                }
+                if named.is_empty() {
+                    "You carry nothing I can tell you more about.".to_string()
+                } else {
+                    let reply = format!("Let me see... {}.", named.join(", "));
+                    message_log.add_message(reply.clone());
+                    reply
+                }
+            }
+            ResponseKind::Recruit => {
+                if reputation.standing(Faction::from_character_type(&npc.character_type)) == Standing::Hostile {
+                    "Walk with you? I'd sooner walk into the Chasm.".to_string()
+                } else if !crate::party::has_room(party_size) {
+                    "You've company enough already.".to_string()
+                } else {
+                    party_size += 1;
+                    member = true;
+                    party_events.send(PartyChange::Join(event.speaker));
+                    "Lead the way. I'll watch your back.".to_string()
+                }
+            }
+            ResponseKind::Give(item) => {
+                let mut inventory = if let Ok(inventory) = player_query.get_single_mut() { inventory } else { continue; };
+                let mut member_inventory = if let Ok(inventory) = party_query.get_mut(event.speaker) { inventory } else { continue; };
+                if inventory.remove(item) {
+                    member_inventory.add(item);
+                    message_log.add_message(format!("You give {} {}", npc.name, with_article(item.get_name())));
+                    "My thanks. I'll put it to good use.".to_string()
+                } else {
+                    format!("You don't have {} to give.", with_article(item.get_name()))
+                }
+            }
+            ResponseKind::Dismiss => {
+                party_size = party_size.saturating_sub(1);
+                member = false;
+                party_events.send(PartyChange::Leave(event.speaker));
+                "As you wish. Good luck down there.".to_string()
+            }
+            ResponseKind::Farewell => {
+                npc.speaking = false;
+                conversation.end();
+
+                // Put the camera back where it was before the conversation
+                if let Ok((mut camera_control, mut camera_transform)) = camera_query.get_single_mut() {
+                    camera_control.end_dialog(event.speaker, &mut camera_transform);
+                }
+                continue;
+            }
+        };
+
+        npc.dialog_text = reply.clone();
+        conversation.say(reply);
+        // Haggling carries on until the player is done with the wares
+        let responses = match event.response.kind {
+            ResponseKind::Trade | ResponseKind::Buy(_) | ResponseKind::Sell => shop_responses(&npc.character_type),
+            _ if member => party_responses(),
+            _ => generate_responses(&npc.character_type),
+        };
+        conversation.offer(responses);
+    }
+}
+
+// Add a system to animate speaking NPCs with side-to-side wiggle
+pub fn animate_speaking_npcs(
+    time: Res<Time>,
+    mut query: Query<(&mut Npc, &mut Transform)>,
+) {
+    for (mut npc, mut transform) in query.iter_mut() {
+        if npc.speaking {
+            // Update the animation timer
+            npc.animation_timer.tick(time.delta());
+            
+            // Wiggle the sprite with a more pronounced rotation when the timer finishes
+            if npc.animation_timer.just_finished() {
+                // Change wiggle direction
+                npc.wiggle_direction *= -1.0;
+                
+                // Apply wiggle as a more pronounced rotation (convert to radians)
+                let wiggle_angle = npc.wiggle_amount * npc.wiggle_direction * 0.4; // Increased amount for rotation
+                transform.rotation = Quat::from_rotation_z(wiggle_angle);
+            }
+        } else if transform.rotation != Quat::IDENTITY {
+            // Reset rotation when not speaking
+            transform.rotation = Quat::IDENTITY;
+        }
+    }
+}
+
+// Initialize the animal manager
+pub fn initialize_animal_manager(
+    mut animal_manager: ResMut<AnimalManager>,
+    sprite_assets: Res<SpriteAssets>,
+) {
+    animal_manager.initialize(&sprite_assets.animal_sprites);
+    println!("Animal manager initialized with {} biomes", animal_manager.biome_animals.len());
+}
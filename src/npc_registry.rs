@@ -0,0 +1,208 @@
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::assets::{get_character_sprite, SpriteAssets, TextureAtlases};
+use crate::components::{Npc, Player, Position};
+use crate::dialogue::{get_available_character_sprites, CharacterType};
+use crate::events::LevelChanged;
+use crate::faction::Faction;
+use crate::level::DungeonState;
+use crate::level_snapshots::LevelSnapshots;
+use crate::map::TileType;
+use crate::npc::spawn_npc_entity;
+use crate::rng::GameRng;
+use crate::run_modifiers::{RunModifier, RunModifiers};
+
+// How many unique NPCs each run rolls, and how likely one turns up on a fresh floor
+const ROSTER_SIZE: usize = 3;
+const APPEARANCE_CHANCE: f64 = 0.35;
+// How far from the arrival stairs they wait for the player
+const MIN_ARRIVAL_DISTANCE: i32 = 2;
+const MAX_ARRIVAL_DISTANCE: i32 = 4;
+
+// Each storyline is told one chapter per meeting, deeper and deeper into the Chasm
+const STORYLINES: &[&[&str]] = &[
+    &[
+        "My brother went down before me. He left marks on the walls so I could follow. Have you seen them?",
+        "You again! The marks are fresher here. He can't be more than a day ahead.",
+        "I found his pack by an underground stream. Empty. He'd never leave it behind.",
+        "The marks stop here. Whatever he found down there, he chose to stay. I'll go up and tell our mother.",
+    ],
+    &[
+        "I owe money to people who don't forgive debts. Down here, at least, they won't follow.",
+        "They followed. I heard their voices two floors up. Don't tell them you saw me.",
+        "I've stopped running. There's gold this deep, enough to pay them twice over.",
+        "Funny thing: down here the debt doesn't seem to matter. Nothing up there does.",
+    ],
+    &[
+        "The scholars say the Chasm has a bottom. I mean to be the one who measures it.",
+        "My rope ran out three floors ago. I'm counting steps now. You'd be amazed how many.",
+        "The numbers stopped making sense. The floors below are deeper than the Chasm is wide.",
+        "I've thrown away my notes. Some depths aren't meant to be measured, only reached.",
+    ],
+];
+
+/// One of the run's recurring characters and how far their story has got
+#[derive(Debug, Clone)]
+pub struct RosterEntry {
+    pub name: String,
+    pub character_type: CharacterType,
+    pub sprite_name: String,
+    pub storyline: usize,
+    pub chapter: usize, // The next chapter they'll tell
+    pub met: bool,
+    pub level: Option<usize>, // The floor they're currently waiting on
+}
+
+impl RosterEntry {
+    // Whether they still have something to tell
+    fn has_story_left(&self) -> bool {
+        self.chapter < STORYLINES[self.storyline].len()
+    }
+}
+
+/// The named NPCs who follow the player down through a run, rolled once when it starts
+#[derive(Resource, Debug, Default)]
+pub struct NpcRegistry {
+    pub roster: Vec<RosterEntry>,
+}
+
+impl NpcRegistry {
+    // Roll a fresh cast of characters, each with their own storyline
+    pub fn generate(rng: &mut impl Rng) -> Self {
+        let mut sprites = get_available_character_sprites();
+        sprites.shuffle(rng);
+        let mut storylines: Vec<usize> = (0..STORYLINES.len()).collect();
+        storylines.shuffle(rng);
+
+        let roster = sprites
+            .into_iter()
+            .zip(storylines)
+            .take(ROSTER_SIZE)
+            .map(|(sprite_name, storyline)| {
+                let character_type = CharacterType::from_sprite_name(&sprite_name);
+                RosterEntry {
+                    name: character_type.generate_name(rng),
+                    character_type,
+                    sprite_name,
+                    storyline,
+                    chapter: 0,
+                    met: false,
+                    level: None,
+                }
+            })
+            .collect();
+        NpcRegistry { roster }
+    }
+}
+
+/// Marks an NPC as a roster member, telling the given chapter of their story
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UniqueNpc {
+    pub id: usize,
+    pub chapter: usize,
+}
+
+// System to roll the roster at the start of each run
+pub fn generate_npc_registry(mut commands: Commands, mut game_rng: ResMut<GameRng>) {
+    let registry = NpcRegistry::generate(&mut game_rng.dialogue);
+    for entry in &registry.roster {
+        println!("Roster: {} ({:?})", entry.name, entry.character_type);
+    }
+    commands.insert_resource(registry);
+}
+
+// System to sometimes have a roster member waiting near the stairs on a floor reached for the first time
+pub fn place_unique_npcs(
+    mut commands: Commands,
+    mut level_events: EventReader<LevelChanged>,
+    mut registry: ResMut<NpcRegistry>,
+    mut level_snapshots: ResMut<LevelSnapshots>,
+    mut game_rng: ResMut<GameRng>,
+    dungeon_state: Res<DungeonState>,
+    texture_atlases: Res<TextureAtlases>,
+    sprite_assets: Res<SpriteAssets>,
+    modifiers: Res<RunModifiers>,
+    player_query: Query<&Position, With<Player>>,
+) {
+    if modifiers.has(RunModifier::NoNpcs) {
+        return;
+    }
+    for event in level_events.read() {
+        // Only descents onto floors nobody has been left on yet
+        if event.to <= event.from || level_snapshots.has(event.to) {
+            continue;
+        }
+        let candidates: Vec<usize> = registry.roster.iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.has_story_left())
+            .map(|(id, _)| id)
+            .collect();
+        if candidates.is_empty() || !game_rng.spawns.gen_bool(APPEARANCE_CHANCE) {
+            continue;
+        }
+        let id = if let Some(&id) = candidates.choose(&mut game_rng.spawns) { id } else { continue; };
+
+        let player = if let Ok(position) = player_query.get_single() { *position } else { continue; };
+        let map = if let Some(map) = dungeon_state.levels.get(event.to) { map } else { continue; };
+        let spots: Vec<(i32, i32)> = (-MAX_ARRIVAL_DISTANCE..=MAX_ARRIVAL_DISTANCE)
+            .flat_map(|dy| (-MAX_ARRIVAL_DISTANCE..=MAX_ARRIVAL_DISTANCE).map(move |dx| (player.x + dx, player.y + dy)))
+            .filter(|&(x, y)| {
+                let distance = (x - player.x).abs().max((y - player.y).abs());
+                distance >= MIN_ARRIVAL_DISTANCE
+                    && map.in_bounds(x, y)
+                    && map.tiles[y as usize][x as usize] == TileType::Floor
+            })
+            .collect();
+        let spot = if let Some(&spot) = spots.choose(&mut game_rng.spawns) { spot } else { continue; };
+
+        // They've moved on from wherever the player last left them
+        if let Some(previous) = registry.roster[id].level {
+            level_snapshots.forget_unique(previous, id);
+        }
+
+        let entry = &mut registry.roster[id];
+        entry.level = Some(event.to);
+        let line = STORYLINES[entry.storyline][entry.chapter].to_string();
+        let npc = Npc {
+            name: entry.name.clone(),
+            dialog: vec![line.clone()],
+            current_dialog_index: 0,
+            met: entry.met,
+            lines_heard: 0,
+            speaking: false,
+            dialog_text: line,
+            character_type: entry.character_type.clone(),
+            animation_timer: Timer::from_seconds(0.15, TimerMode::Repeating),
+            original_scale: Vec3::splat(1.0),
+            wiggle_direction: 1.0,
+            wiggle_amount: 0.1,
+            is_animal: false,
+            animal_type: None,
+        };
+        let faction = Faction::from_character_type(&entry.character_type);
+        let sprite_index = get_character_sprite(&sprite_assets, &entry.sprite_name);
+        println!("{} is waiting on level {} at ({}, {})", entry.name, event.to, spot.0, spot.1);
+
+        let npc_entity = spawn_npc_entity(&mut commands, &texture_atlases, sprite_index, spot, npc, faction);
+        commands.entity(npc_entity).insert(UniqueNpc { id, chapter: entry.chapter });
+    }
+}
+
+// System to carry what roster members have told the player back into the registry
+pub fn record_unique_npc_progress(
+    mut registry: ResMut<NpcRegistry>,
+    npc_query: Query<(&Npc, &UniqueNpc), Changed<Npc>>,
+) {
+    for (npc, unique) in npc_query.iter() {
+        let entry = if let Some(entry) = registry.roster.get_mut(unique.id) { entry } else { continue; };
+        entry.met |= npc.met;
+
+        // Hearing the whole chapter moves their story on for the next meeting
+        if npc.lines_heard >= npc.dialog.len() && entry.chapter == unique.chapter {
+            entry.chapter += 1;
+            println!("{}'s story moves on to chapter {}", entry.name, entry.chapter);
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use bevy::prelude::*;
+
+use crate::combat::{CombatStats, Health};
+use crate::components::{AnimalAnimation, Companion, GameTurn, Npc, Player, Position};
+use crate::dialogue::{CharacterType, DialogueResponse, ResponseKind};
+use crate::events::EntityDamaged;
+use crate::faction::Hostile;
+use crate::inventory::{Inventory, ItemKind};
+use crate::inventory_panel::HEALING_POTION_HEAL;
+use crate::map::TileMap;
+use crate::pathmaps::{DistanceMap, PathMaps};
+use crate::ui::MessageLog;
+
+// How many NPCs can travel with the player at once (tamed animals don't count)
+const PARTY_SIZE: usize = 1;
+// How far a party member will go out of its way to fight, and how far it'll stray from the player to do it
+const ENGAGE_RANGE: i32 = 3;
+const LEASH_RANGE: i32 = 4;
+// Extra damage a party member does while carrying a dagger it was given
+const DAGGER_ATTACK_BONUS: i32 = 1;
+
+/// An NPC travelling with the player. Also a `Companion`, so it follows them down the stairs and through portals
+#[derive(Component, Debug)]
+pub struct PartyMember;
+
+/// Sent to add an NPC to the party (they agreed to come along, or a quest was done for them) or let one go
+#[derive(Event, Debug, Clone, Copy)]
+pub enum PartyChange {
+    Join(Entity),
+    Leave(Entity),
+}
+
+// Fighters and wanderers will come along if asked; traders, scholars and the like have their own business
+pub fn can_join(character_type: &CharacterType) -> bool {
+    matches!(
+        character_type,
+        CharacterType::Dwarf
+            | CharacterType::Elf
+            | CharacterType::Ranger
+            | CharacterType::Rogue
+            | CharacterType::Knight
+            | CharacterType::Fighter
+            | CharacterType::FemaleKnight
+            | CharacterType::ShieldKnight
+            | CharacterType::WarCleric
+            | CharacterType::Templar
+            | CharacterType::Barbarian
+            | CharacterType::Swordsman
+            | CharacterType::Fencer
+    )
+}
+
+// Whether there's room for another NPC in the party
+pub fn has_room(members: usize) -> bool {
+    members < PARTY_SIZE
+}
+
+// Responses offered to a party member (at most four, to fit the numbered responses)
+pub fn party_responses() -> Vec<DialogueResponse> {
+    vec![
+        DialogueResponse::new("Take this healing potion.", ResponseKind::Give(ItemKind::HealingPotion)),
+        DialogueResponse::new("Take this dagger.", ResponseKind::Give(ItemKind::Dagger)),
+        DialogueResponse::new("Go your own way.", ResponseKind::Dismiss),
+        DialogueResponse::new("Let's keep moving.", ResponseKind::Farewell),
+    ]
+}
+
+// System to add NPCs to the party and let them go again
+pub fn apply_party_changes(
+    mut commands: Commands,
+    mut party_events: EventReader<PartyChange>,
+    npc_query: Query<(&Npc, Option<&CombatStats>, Option<&PartyMember>)>,
+    mut message_log: ResMut<MessageLog>,
+) {
+    let mut members = npc_query.iter().filter(|(_, _, member)| member.is_some()).count();
+    for event in party_events.read() {
+        match *event {
+            PartyChange::Join(entity) => {
+                let (npc, stats, member) = if let Ok(npc) = npc_query.get(entity) { npc } else { continue; };
+                if member.is_some() || !has_room(members) {
+                    continue;
+                }
+                members += 1;
+                // Walks, fights and changes levels the way a tamed animal does
+                commands
+                    .entity(entity)
+                    .insert((PartyMember, Companion, AnimalAnimation::default(), Inventory::default()))
+                    .remove::<Hostile>();
+                if stats.is_none() {
+                    commands.entity(entity).insert(CombatStats::default());
+                }
+                message_log.add_message(format!("{} joins you.", npc.name));
+            }
+            PartyChange::Leave(entity) => {
+                let (npc, _, member) = if let Ok(npc) = npc_query.get(entity) { npc } else { continue; };
+                if member.is_none() {
+                    continue;
+                }
+                members -= 1;
+                commands.entity(entity).remove::<(PartyMember, Companion, AnimalAnimation)>();
+                message_log.add_message(format!("{} goes their own way.", npc.name));
+            }
+        }
+    }
+}
+
+// System to give each party member its turn: drink a potion if badly hurt, attack a hostile next to it,
+// go after one nearby, or else keep up with the player
+pub fn move_party_system(
+    mut commands: Commands,
+    mut party_query: Query<(Entity, &Npc, &Position, &CombatStats, &mut Health, &mut Inventory, &mut AnimalAnimation, &mut TextureAtlasSprite), With<PartyMember>>,
+    mut hostile_query: Query<(Entity, &Position, &mut Health, Option<&Npc>), (With<Hostile>, Without<PartyMember>)>,
+    companion_query: Query<&Position, (With<Companion>, Without<PartyMember>)>,
+    player_query: Query<&Position, With<Player>>,
+    path_maps: Res<PathMaps>,
+    map: Res<TileMap>,
+    game_turn: Res<GameTurn>,
+    mut damage_events: EventWriter<EntityDamaged>,
+    mut message_log: ResMut<MessageLog>,
+    mut local: Local<u32>,
+) {
+    // Only act once per turn
+    if game_turn.current_turn == 0 || game_turn.current_turn == *local {
+        return;
+    }
+    *local = game_turn.current_turn;
+
+    let player_pos = if let Ok(pos) = player_query.get_single() { *pos } else { return; };
+
+    // Tiles already taken, so the party doesn't stack up on itself or the animals
+    let mut occupied: Vec<(i32, i32)> = companion_query.iter().map(|position| (position.x, position.y)).collect();
+    occupied.extend(party_query.iter().map(|(_, _, position, ..)| (position.x, position.y)));
+    occupied.extend(hostile_query.iter().map(|(_, position, ..)| (position.x, position.y)));
+
+    for (entity, npc, position, stats, mut health, mut inventory, mut animation, mut sprite) in party_query.iter_mut() {
+        // A potion they were given is saved for when it's needed
+        if health.current * 2 <= health.max && inventory.remove(ItemKind::HealingPotion) {
+            health.heal(HEALING_POTION_HEAL);
+            message_log.add_message(format!("{} drinks a healing potion.", npc.name));
+            continue;
+        }
+
+        // Attack the first hostile standing next to them
+        let attack = stats.attack + if inventory.count(ItemKind::Dagger) > 0 { DAGGER_ATTACK_BONUS } else { 0 };
+        let adjacent = hostile_query.iter_mut().find(|(_, hostile_pos, health, _)| {
+            !health.is_dead() && (hostile_pos.x - position.x).abs() + (hostile_pos.y - position.y).abs() == 1
+        });
+        if let Some((hostile, _, mut hostile_health, hostile_npc)) = adjacent {
+            let killed = hostile_health.take_damage(attack);
+            damage_events.send(EntityDamaged { target: hostile, amount: attack, source: npc.name.clone() });
+            let target_name = hostile_npc.map_or("enemy".to_string(), |hostile_npc| hostile_npc.name.clone());
+            println!("{} attacks {} for {} damage", npc.name, target_name, attack);
+            if killed {
+                message_log.add_message(format!("{} has slain {}.", npc.name, target_name));
+            }
+            continue;
+        }
+
+        // Close on a hostile nearby, as long as it doesn't take them too far from the player
+        let near_player = |tile: (i32, i32)| (tile.0 - player_pos.x).abs().max((tile.1 - player_pos.y).abs()) <= LEASH_RANGE;
+        let quarry = hostile_query
+            .iter()
+            .filter(|(_, hostile_pos, health, _)| {
+                !health.is_dead()
+                    && (hostile_pos.x - position.x).abs().max((hostile_pos.y - position.y).abs()) <= ENGAGE_RANGE
+                    && near_player((hostile_pos.x, hostile_pos.y))
+            })
+            .min_by_key(|(_, hostile_pos, ..)| (hostile_pos.x - position.x).abs() + (hostile_pos.y - position.y).abs())
+            .map(|(_, hostile_pos, ..)| (hostile_pos.x, hostile_pos.y));
+
+        let next = if let Some(quarry) = quarry {
+            DistanceMap::new(&map, &[quarry]).downhill_from(position.x, position.y)
+        } else if (player_pos.x - position.x).abs() + (player_pos.y - position.y).abs() > 1 {
+            path_maps.to_player.downhill_from(position.x, position.y)
+        } else {
+            // Already next to the player, nothing to do
+            None
+        };
+
+        // Never step onto the player or anyone else
+        let next = if let Some(next) = next { next } else { continue; };
+        if next == (player_pos.x, player_pos.y) || occupied.contains(&next) {
+            continue;
+        }
+        occupied.retain(|&tile| tile != (position.x, position.y));
+        occupied.push(next);
+
+        let target_pos = Position::new(next.0, next.1);
+        crate::animals::start_animal_hop(&mut animation, &mut sprite, *position, target_pos);
+        commands.entity(entity).insert(target_pos);
+    }
+}
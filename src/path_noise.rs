@@ -0,0 +1,37 @@
+use noise::{Fbm, MultiFractal, NoiseFn, Perlin};
+
+/// A smooth 2D noise field that winding paths are traced along
+pub trait PathNoise: Send + Sync {
+    /// Noise at a point, roughly in -1..1; nearby points give nearby values
+    fn sample(&self, x: f64, y: f64) -> f64;
+}
+
+/// Fractal Perlin noise: broad curves with a little wobble on top
+pub struct PerlinPathNoise {
+    fbm: Fbm<Perlin>,
+}
+
+impl PerlinPathNoise {
+    pub fn new(seed: u32) -> Self {
+        Self {
+            fbm: Fbm::<Perlin>::new(seed).set_octaves(3),
+        }
+    }
+}
+
+impl Default for PerlinPathNoise {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl PathNoise for PerlinPathNoise {
+    fn sample(&self, x: f64, y: f64) -> f64 {
+        self.fbm.get([x, y])
+    }
+}
+
+/// Whether a point lies on a ridge of the noise field, i.e. along one of its zero lines
+pub fn on_ridge(noise: &dyn PathNoise, x: f64, y: f64, width: f64) -> bool {
+    noise.sample(x, y).abs() < width
+}
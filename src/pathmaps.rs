@@ -0,0 +1,233 @@
+use bevy::prelude::*;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::components::{Player, Position};
+use crate::map::{LayoutKey, TileMap};
+use crate::visibility::line_of_sight;
+
+// Tiles within this distance and in view count as explored
+const EXPLORE_SIGHT_RANGE: i32 = 6;
+// Fleeing scales distances to the player by -1.2 (as tenths) before re-flooding,
+// so cornered creatures slip past rather than into dead ends
+const FLEE_SCALE_TENTHS: i32 = -12;
+const STEPS: [(i32, i32); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+
+/// How many steps every walkable tile is from the nearest goal, flooded out
+/// from the goals once rather than searched for per creature
+#[derive(Debug, Clone, Default)]
+pub struct DistanceMap {
+    width: usize,
+    height: usize,
+    distances: Vec<Option<i32>>, // None where no goal can be reached
+}
+
+impl DistanceMap {
+    // Flood out from goal tiles, each starting at zero
+    pub fn new(map: &TileMap, goals: &[(i32, i32)]) -> Self {
+        let seeds: Vec<((i32, i32), i32)> = goals.iter().map(|&goal| (goal, 0)).collect();
+        Self::from_seeds(map, &seeds)
+    }
+
+    // Dijkstra from tiles with given starting values; every step costs one.
+    // Seeds may be unwalkable (e.g. occupied by the player) but nothing is flooded through walls
+    pub fn from_seeds(map: &TileMap, seeds: &[((i32, i32), i32)]) -> Self {
+        let mut distance_map = Self { width: map.width, height: map.height, distances: vec![None; map.width * map.height] };
+        let mut frontier = BinaryHeap::new();
+        for &((x, y), value) in seeds {
+            if let Some(index) = distance_map.index(x, y) {
+                if distance_map.distances[index].map_or(true, |best| value < best) {
+                    distance_map.distances[index] = Some(value);
+                    frontier.push(Reverse((value, x, y)));
+                }
+            }
+        }
+
+        while let Some(Reverse((value, x, y))) = frontier.pop() {
+            if distance_map.get(x, y).map_or(false, |best| best < value) {
+                continue;
+            }
+            for (dx, dy) in STEPS {
+                let (nx, ny) = (x + dx, y + dy);
+                let index = if let Some(index) = distance_map.index(nx, ny) { index } else { continue; };
+                if !map.is_position_walkable(nx, ny) || distance_map.distances[index].map_or(false, |best| best <= value + 1) {
+                    continue;
+                }
+                distance_map.distances[index] = Some(value + 1);
+                frontier.push(Reverse((value + 1, nx, ny)));
+            }
+        }
+        distance_map
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return None;
+        }
+        Some(y as usize * self.width + x as usize)
+    }
+
+    pub fn get(&self, x: i32, y: i32) -> Option<i32> {
+        self.index(x, y).and_then(|index| self.distances[index])
+    }
+
+    // The neighbouring tile that gets closest to a goal, if any is closer than here
+    pub fn downhill_from(&self, x: i32, y: i32) -> Option<(i32, i32)> {
+        let here = self.get(x, y)?;
+        STEPS.iter()
+            .map(|(dx, dy)| (x + dx, y + dy))
+            .filter_map(|(nx, ny)| self.get(nx, ny).map(|distance| (distance, (nx, ny))))
+            .filter(|&(distance, _)| distance < here)
+            .min_by_key(|&(distance, _)| distance)
+            .map(|(_, tile)| tile)
+    }
+
+    // A map that leads away from the goals: following it downhill runs off
+    // towards open space instead of into the nearest corner
+    pub fn flee_map(&self, map: &TileMap) -> Self {
+        let mut seeds = Vec::new();
+        for y in 0..self.height as i32 {
+            for x in 0..self.width as i32 {
+                if let Some(distance) = self.get(x, y) {
+                    seeds.push(((x, y), distance * FLEE_SCALE_TENTHS / 10));
+                }
+            }
+        }
+        Self::from_seeds(map, &seeds)
+    }
+}
+
+/// The shared distance maps creatures and auto-explore walk along, each
+/// rebuilt only when what it measures from has changed
+#[derive(Resource, Default)]
+pub struct PathMaps {
+    pub to_player: DistanceMap,
+    pub to_stairs: DistanceMap,
+    pub to_frontier: DistanceMap, // The nearest tile not yet explored
+    pub flee: DistanceMap,        // Away from the player
+    explored: Vec<bool>,
+    player_tile: Option<(i32, i32)>,
+    layout: Option<LayoutKey>,
+}
+
+impl PathMaps {
+    pub fn is_explored(&self, map: &TileMap, x: i32, y: i32) -> bool {
+        map.in_bounds(x, y) && self.explored.get(y as usize * map.width + x as usize).copied().unwrap_or(false)
+    }
+
+    // Mark what can be seen from a tile as explored; returns whether anything new was
+    fn explore_from(&mut self, map: &TileMap, from: (i32, i32)) -> bool {
+        let mut discovered = false;
+        for y in from.1 - EXPLORE_SIGHT_RANGE..=from.1 + EXPLORE_SIGHT_RANGE {
+            for x in from.0 - EXPLORE_SIGHT_RANGE..=from.0 + EXPLORE_SIGHT_RANGE {
+                if !map.in_bounds(x, y) || self.is_explored(map, x, y) || !line_of_sight(map, from, (x, y)) {
+                    continue;
+                }
+                self.explored[y as usize * map.width + x as usize] = true;
+                discovered = true;
+            }
+        }
+        discovered
+    }
+}
+
+// System to bring the distance maps up to date: stairs when the level changes,
+// the player and flee maps when the player moves, the frontier when more is explored
+pub fn update_path_maps(
+    map: Res<TileMap>,
+    player_query: Query<&Position, With<Player>>,
+    mut path_maps: ResMut<PathMaps>,
+) {
+    let player = if let Ok(position) = player_query.get_single() { (position.x, position.y) } else { return; };
+
+    let layout = map.layout_key();
+    let new_level = path_maps.layout != Some(layout);
+    if new_level {
+        path_maps.layout = Some(layout);
+        path_maps.explored = vec![false; map.width * map.height];
+        path_maps.player_tile = None;
+        let stairs: Vec<(i32, i32)> = map.down_stairs_pos.iter().map(|&(x, y)| (x as i32, y as i32)).collect();
+        path_maps.to_stairs = DistanceMap::new(&map, &stairs);
+    }
+    if path_maps.player_tile == Some(player) {
+        return;
+    }
+    path_maps.player_tile = Some(player);
+
+    path_maps.to_player = DistanceMap::new(&map, &[player]);
+    path_maps.flee = path_maps.to_player.flee_map(&map);
+
+    if path_maps.explore_from(&map, player) || new_level {
+        let mut unexplored = Vec::new();
+        for y in 0..map.height as i32 {
+            for x in 0..map.width as i32 {
+                if !path_maps.is_explored(&map, x, y) && map.is_position_walkable(x, y) {
+                    unexplored.push((x, y));
+                }
+            }
+        }
+        path_maps.to_frontier = DistanceMap::new(&map, &unexplored);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distances_count_steps_from_the_goal() {
+        let map = TileMap::test_floor(6, 4, &[]);
+        let distances = DistanceMap::new(&map, &[(0, 0)]);
+        assert_eq!(distances.get(0, 0), Some(0));
+        assert_eq!(distances.get(3, 0), Some(3));
+        assert_eq!(distances.get(5, 3), Some(8));
+        assert_eq!(distances.get(6, 0), None);
+    }
+
+    #[test]
+    fn seeds_keep_their_starting_values() {
+        let map = TileMap::test_floor(6, 1, &[]);
+        let distances = DistanceMap::from_seeds(&map, &[((0, 0), 5), ((5, 0), 0)]);
+        assert_eq!(distances.get(0, 0), Some(5));
+        assert_eq!(distances.get(1, 0), Some(4));
+        assert_eq!(distances.get(4, 0), Some(1));
+    }
+
+    #[test]
+    fn walls_are_flooded_around_not_through() {
+        // A wall down the middle with a gap at the bottom
+        let walls: Vec<(usize, usize)> = (1..5).map(|y| (2, y)).collect();
+        let map = TileMap::test_floor(5, 5, &walls);
+        let distances = DistanceMap::new(&map, &[(0, 4)]);
+        assert_eq!(distances.get(2, 2), None);
+        assert_eq!(distances.get(4, 4), Some(12));
+    }
+
+    #[test]
+    fn sealed_off_tiles_are_unreachable() {
+        let walls = [(1, 0), (1, 1), (0, 1)];
+        let map = TileMap::test_floor(4, 4, &walls);
+        let distances = DistanceMap::new(&map, &[(3, 3)]);
+        assert_eq!(distances.get(0, 0), None);
+        assert_eq!(distances.downhill_from(0, 0), None);
+    }
+
+    #[test]
+    fn downhill_steps_toward_the_goal() {
+        let map = TileMap::test_floor(5, 5, &[]);
+        let distances = DistanceMap::new(&map, &[(4, 2)]);
+        assert_eq!(distances.downhill_from(0, 2), Some((1, 2)));
+        // Nowhere is closer than the goal itself
+        assert_eq!(distances.downhill_from(4, 2), None);
+    }
+
+    #[test]
+    fn flee_map_leads_away_from_the_goal() {
+        let map = TileMap::test_floor(9, 1, &[]);
+        let to_player = DistanceMap::new(&map, &[(2, 0)]);
+        let flee = to_player.flee_map(&map);
+        // Running from the player heads for the far end of the corridor
+        assert_eq!(flee.downhill_from(3, 0), Some((4, 0)));
+        assert_eq!(flee.downhill_from(1, 0), Some((0, 0)));
+    }
+}
@@ -0,0 +1,530 @@
+use bevy::prelude::*;
+use bevy::sprite::TextureAtlasSprite;
+
+use crate::components::{self, GameTurn, Npc, Player, Position};
+use crate::events::{CreatureKilled, EntityDamaged, MovementBlocked, PlayerAttacked, PlayerMoved, TileEntered};
+use crate::input::{InputState, TILE_SIZE};
+use crate::map::{TileMap, TileType};
+use crate::status::{StatusEffects, StatusKind};
+use crate::{GameState, GameplaySet};
+
+/// The player's input, movement and turn, and everything they can do with a turn:
+/// fighting, casting, resting, and the status effects that follow
+pub struct PlayerPlugin;
+
+impl Plugin for PlayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<crate::status::ApplyStatusEffect>()
+            .add_event::<PlayerMoved>()
+            .add_event::<TileEntered>()
+            .add_event::<MovementBlocked>()
+            .add_event::<EntityDamaged>()
+            .add_event::<CreatureKilled>()
+            .add_event::<PlayerAttacked>()
+            .add_event::<crate::interaction::InteractedWith>()
+            .init_resource::<InputState>()
+            .init_resource::<crate::input::TileClick>()
+            .init_resource::<AnimationState>()
+            .init_resource::<GameTurn>()
+            .init_resource::<crate::rest::RestState>()
+            .init_resource::<crate::scent::ScentMap>()
+            .init_resource::<crate::running::RunState>()
+            .init_resource::<crate::interaction::InteractionMenu>()
+            .init_resource::<crate::bookmarks::BookmarkNaming>()
+            .init_resource::<crate::inventory_panel::InventoryMenu>()
+            .init_resource::<crate::identify::ItemAppearances>()
+            .init_resource::<crate::throwing::ThrowTargeting>()
+            .init_resource::<crate::aoe::AreaPreview>()
+            .init_resource::<crate::stealth::Sneaking>()
+            .init_resource::<crate::virtual_cursor::VirtualCursor>()
+            .insert_resource(crate::animation_settings::AnimationSettings::load())
+            .add_systems(OnEnter(GameState::InGame), (
+                crate::spells::setup_spell_bar,
+                crate::status::setup_status_hud,
+                crate::interaction::setup_interaction_menu,
+                crate::inventory_panel::setup_inventory_panel,
+                crate::identify::setup_item_appearances,
+                crate::throwing::setup_throw_cursor,
+                crate::virtual_cursor::setup_look_cursor,
+                crate::stealth::reset_sneaking,
+            ).run_if(crate::loading_screen::starting_run))
+            .add_systems(
+                Update,
+                (
+                    crate::input::handle_input.in_set(GameplaySet::Input),
+                    crate::input::queue_next_movement
+                        .after(crate::input::handle_input)
+                        .in_set(GameplaySet::Input),
+                    update_sprite_positions.in_set(GameplaySet::Presentation),
+                    crate::visibility::update_visibility.in_set(GameplaySet::Visibility),
+                    crate::running::run_system
+                        .after(crate::input::handle_input)
+                        .before(crate::input::move_player),
+                    crate::input::move_player.in_set(GameplaySet::Movement),
+                    crate::animation_settings::apply_animation_settings
+                        .before(animate_player_movement)
+                        .before(crate::animals::animate_animal_movement),
+                    animate_player_movement
+                        .after(crate::input::move_player)
+                        .in_set(GameplaySet::Movement),
+                    process_turn_effects
+                        .after(animate_player_movement)
+                        .in_set(GameplaySet::Movement),
+                )
+                .run_if(in_state(GameState::InGame))
+            )
+            .add_systems(
+                Update,
+                (
+                    crate::interaction::dispatch_interactions
+                        .before(crate::input::handle_input)
+                        .before(crate::spells::select_spell_system)
+                        .before(crate::npc::handle_npc_interaction)
+                        .before(crate::chests::open_chest_system)
+                        .before(crate::corpses::loot_corpses_system)
+                        .before(crate::level::handle_stairs_system),
+                    crate::interaction::update_interaction_menu.after(crate::interaction::dispatch_interactions),
+                    crate::inventory_panel::inventory_input_system
+                        .before(crate::interaction::dispatch_interactions)
+                        .before(crate::npc::handle_npc_interaction),
+                    crate::inventory_panel::update_inventory_panel.after(crate::inventory_panel::inventory_input_system),
+                    crate::throwing::throw_targeting_system
+                        .after(crate::inventory_panel::inventory_input_system)
+                        .before(crate::interaction::dispatch_interactions),
+                    crate::throwing::update_throw_cursor.after(crate::throwing::throw_targeting_system),
+                    crate::aoe::update_area_preview.after(crate::throwing::update_throw_cursor),
+                    crate::throwing::animate_thrown_items,
+                    crate::throwing::fade_splashes,
+                    crate::stealth::toggle_sneak_system.before(crate::input::handle_input),
+                )
+                .run_if(in_state(GameState::InGame))
+            )
+            .add_systems(
+                Update,
+                (
+                    crate::virtual_cursor::look_mode_system
+                        .after(crate::inventory_panel::inventory_input_system)
+                        .before(crate::input::handle_input),
+                    crate::virtual_cursor::stick_cursor_system
+                        .after(crate::virtual_cursor::look_mode_system)
+                        .before(crate::throwing::throw_targeting_system),
+                    crate::virtual_cursor::update_look_cursor.after(crate::virtual_cursor::stick_cursor_system),
+                )
+                .run_if(in_state(GameState::InGame))
+            )
+            .add_systems(
+                PreUpdate,
+                crate::bookmarks::bookmark_name_entry_system
+                    .after(crate::run_log::replay_run_system)
+                    .run_if(in_state(GameState::InGame))
+            )
+            .add_systems(
+                Update,
+                (
+                    crate::bookmarks::place_bookmark_system
+                        .after(crate::inventory_panel::inventory_input_system)
+                        .after(crate::virtual_cursor::look_mode_system),
+                    crate::bookmarks::sync_bookmark_markers
+                        .after(crate::bookmarks::place_bookmark_system)
+                        .after(crate::level::handle_stairs_system),
+                )
+                .run_if(in_state(GameState::InGame))
+                .run_if(resource_exists::<crate::map::TileMap>())
+            )
+            .add_systems(
+                Update,
+                (
+                    crate::combat::fire_ranged_attack.after(crate::input::handle_input),
+                    crate::combat::animate_projectiles.after(crate::combat::fire_ranged_attack),
+                    crate::combat::despawn_dead_entities
+                        .after(crate::animals::move_companions_system)
+                        .after(crate::combat::animate_projectiles),
+                )
+                .run_if(in_state(GameState::InGame))
+            )
+            .add_systems(
+                Update,
+                (
+                    crate::spells::select_spell_system.after(crate::conversation::choose_dialogue_response),
+                    crate::spells::cast_spell_system.after(crate::spells::select_spell_system),
+                    crate::spells::tick_spells_system.after(crate::spells::cast_spell_system),
+                    crate::spells::update_spell_bar.after(crate::spells::tick_spells_system),
+                )
+                .run_if(in_state(GameState::InGame))
+            )
+            .add_systems(
+                Update,
+                (
+                    crate::status::apply_status_effects_system
+                        .after(crate::animals::animal_attack_system)
+                        .after(crate::traps::trigger_traps_system)
+                        .after(crate::spells::cast_spell_system),
+                    crate::status::tick_status_effects_system.after(crate::status::apply_status_effects_system),
+                    crate::status::update_status_hud.after(crate::status::tick_status_effects_system),
+                )
+                .run_if(in_state(GameState::InGame))
+            )
+            .add_systems(
+                Update,
+                (
+                    crate::rest::wait_and_rest_input_system.after(crate::input::handle_input),
+                    crate::rest::search_system.after(crate::rest::wait_and_rest_input_system),
+                    crate::hearing::footstep_noise_system.after(crate::input::move_player),
+                    crate::scent::update_scent_system
+                        .after(crate::input::move_player)
+                        .before(crate::animals::move_animals_system),
+                )
+                .run_if(in_state(GameState::InGame))
+                .run_if(resource_exists::<TileMap>())
+            )
+            // Resting passes turns on the fixed simulation tick rather than once a frame
+            .add_systems(
+                FixedUpdate,
+                crate::rest::rest_system
+                    .run_if(in_state(GameState::InGame))
+                    .run_if(resource_exists::<TileMap>()),
+            );
+    }
+}
+
+// Add a new resource to track animation state
+#[derive(Resource, Default)]
+pub struct AnimationState {
+    pub animation_in_progress: bool,
+}
+
+pub fn update_sprite_positions(
+    mut query: Query<(&Position, &mut Transform, Option<&components::PlayerAnimation>), With<Player>>,
+) {
+    for (pos, mut transform, animation_opt) in &mut query {
+        // Only update position directly if not currently animating
+        if let Some(anim) = animation_opt {
+            if anim.is_moving {
+                // Skip position update if animation is in progress
+                continue;
+            }
+        }
+        
+        // Update position directly if no animation is in progress
+        transform.translation.x = pos.x as f32 * TILE_SIZE + (TILE_SIZE / 2.0);
+        transform.translation.y = pos.y as f32 * TILE_SIZE + (TILE_SIZE / 2.0); 
+        transform.translation.z = 10.0;  // Keep player above all tiles with higher z-index
+    }
+}
+
+/// Height above the straight line at `progress` (0.0 to 1.0) through a hop;
+/// a sine curve, so it peaks halfway. Thrown items arc the same way
+pub fn hop_offset(progress: f32, height: f32) -> f32 {
+    (progress * std::f32::consts::PI).sin() * height
+}
+
+// Add a new system to animate player movement with hop and wobble
+pub fn animate_player_movement(
+    time: Res<Time>,
+    input_state: Res<InputState>,
+    mut player_query: Query<(Entity, &Position, &mut Transform, &mut components::PlayerAnimation, &mut TextureAtlasSprite, Option<&StatusEffects>), With<Player>>,
+    mut commands: Commands,
+    map: Res<TileMap>,
+    mut animation_state: ResMut<AnimationState>,
+    mut game_turn: ResMut<GameTurn>,
+    mut moved_events: EventWriter<PlayerMoved>,
+    mut tile_events: EventWriter<TileEntered>,
+    sneaking: Res<crate::stealth::Sneaking>,
+    animation_settings: Res<crate::animation_settings::AnimationSettings>,
+) {
+    let step_seconds = animation_settings.player_motion().duration;
+    for (entity, position, mut transform, mut animation, mut sprite, status) in player_query.iter_mut() {
+        // Slowed or sneaking players spend two turns on every step
+        let slowed = sneaking.0 || status.map_or(false, |status| status.has(StatusKind::Slow));
+
+        // If currently animating, continue the animation
+        if animation.is_moving {
+            // Ensure animation state is marked as in progress for player movement only
+            animation_state.animation_in_progress = true;
+            
+            // Update the timer
+            animation.animation_timer.tick(time.delta());
+            
+            // Calculate progress (0.0 to 1.0)
+            let progress = animation.animation_timer.percent();
+            
+            // Calculate the current position with a hop
+            let hop = hop_offset(progress, animation.hop_height);
+            
+            // Interpolate between start and target positions, along the configured curve
+            let current_pos = animation.start_pos.lerp(animation.target_pos, animation_settings.eased(progress));
+            
+            // Apply the hop offset to the y coordinate
+            transform.translation = Vec3::new(
+                current_pos.x,
+                current_pos.y + hop,
+                current_pos.z
+            );
+            
+            // Apply wobble (rotation) based on progress
+            // Maximum wobble at the middle of the animation
+            let wobble_factor = (progress * std::f32::consts::PI).sin();
+            let wobble_angle = animation.wobble_direction * animation.wobble_amount * wobble_factor;
+            transform.rotation = Quat::from_rotation_z(wobble_angle);
+            
+            // Check if animation is complete
+            if animation.animation_timer.finished() {
+                // Reset animation state
+                animation.is_moving = false;
+                
+                // Reset the global animation state when player animation is complete
+                animation_state.animation_in_progress = false;
+                
+                // Ensure the sprite is at exactly the target position with no rotation
+                transform.translation = animation.target_pos;
+                transform.rotation = Quat::IDENTITY;
+                
+                println!("Animation complete, final position: {:?}", transform.translation);
+                moved_events.send(PlayerMoved { x: position.x, y: position.y });
+                tile_events.send(TileEntered { entity, x: position.x, y: position.y });
+                
+                // Take the next buffered move that isn't walking into a wall; blocked ones are dropped
+                let now = time.elapsed_seconds_f64();
+                let mut next_step = None;
+                while let Some(direction) = animation.move_buffer.pop(now) {
+                    let mut new_pos_x = position.x;
+                    let mut new_pos_y = position.y;
+                    
+                    // Calculate new position based on queued direction
+                    match direction {
+                        components::MovementDirection::Up => new_pos_y += 1,
+                        components::MovementDirection::Down => new_pos_y -= 1,
+                        components::MovementDirection::Left => new_pos_x -= 1,
+                        components::MovementDirection::Right => new_pos_x += 1,
+                    }
+                    
+                    // Check if the new position is valid
+                    if map.in_bounds(new_pos_x, new_pos_y) {
+                        let tile_type = map.tiles[new_pos_y as usize][new_pos_x as usize];
+                        if tile_type != TileType::Wall && !map.prop_blocks(new_pos_x, new_pos_y) {
+                            next_step = Some((direction, new_pos_x, new_pos_y));
+                            break;
+                        }
+                    }
+                }
+                
+                if let Some((direction, new_pos_x, new_pos_y)) = next_step {
+                    // Create a new Position component
+                    let new_pos = Position::new(new_pos_x, new_pos_y);
+                    
+                    // Update the player's position component
+                    commands.entity(entity).insert(new_pos);
+                    
+                    // Start a new animation immediately
+                    let target_pos = Vec3::new(
+                        new_pos_x as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+                        new_pos_y as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+                        10.0  // Keep z-coordinate at 10.0 to ensure player is always on top
+                    );
+                    
+                    animation.start_pos = transform.translation;
+                    animation.target_pos = target_pos;
+                    animation.is_moving = true;
+                    
+                    // Set the global animation state for player movement
+                    animation_state.animation_in_progress = true;
+                    
+                    // Store the movement direction
+                    animation.last_movement_direction = Some(direction);
+                    
+                    // Update facing direction for left/right movement
+                    if direction == components::MovementDirection::Left || direction == components::MovementDirection::Right {
+                        let facing_right = direction == components::MovementDirection::Right;
+                        if animation.facing_right != facing_right {
+                            animation.facing_right = facing_right;
+                            sprite.flip_x = facing_right;
+                            println!("Flipping sprite to face {}", if facing_right { "right" } else { "left" });
+                        }
+                    }
+                    
+                    // Use consistent animation duration
+                    let animation_duration = step_seconds;
+                    animation.animation_timer = Timer::from_seconds(animation_duration, TimerMode::Once);
+                    
+                    // Flip the wobble direction for alternating effect
+                    animation.wobble_direction *= -1.0;
+                    
+                    // Increment the turn counter for queued movement
+                    game_turn.increment();
+                    if slowed {
+                        game_turn.increment();
+                    }
+                    
+                    println!("Processing queued movement in direction {:?}, animation speed: {:.2}s, {} still buffered", 
+                             direction, animation_duration, animation.move_buffer.len());
+                    
+                    // Skip the rest of the processing since we've started a new animation
+                    continue;
+                }
+                
+                // Handle continuous movement - start a new movement in the same direction if key is still held
+                if input_state.continuous_movement && animation.last_movement_direction.is_some() {
+                    let direction = animation.last_movement_direction.unwrap();
+                    let mut new_pos_x = position.x;
+                    let mut new_pos_y = position.y;
+                    
+                    // Calculate new position based on direction
+                    match direction {
+                        components::MovementDirection::Up => new_pos_y += 1,
+                        components::MovementDirection::Down => new_pos_y -= 1,
+                        components::MovementDirection::Left => new_pos_x -= 1,
+                        components::MovementDirection::Right => new_pos_x += 1,
+                    }
+                    
+                    // Check if the new position is valid
+                    if map.in_bounds(new_pos_x, new_pos_y) {
+                        let tile_type = map.tiles[new_pos_y as usize][new_pos_x as usize];
+                        if tile_type != TileType::Wall && !map.prop_blocks(new_pos_x, new_pos_y) {
+                            // Create a new Position component
+                            let new_pos = Position::new(new_pos_x, new_pos_y);
+                            
+                            // Update the player's position component
+                            commands.entity(entity).insert(new_pos);
+                            
+                            // Start a new animation immediately
+                            let target_pos = Vec3::new(
+                                new_pos_x as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+                                new_pos_y as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+                                10.0  // Keep z-coordinate at 10.0 to ensure player is always on top
+                            );
+                            
+                            animation.start_pos = transform.translation;
+                            animation.target_pos = target_pos;
+                            animation.is_moving = true;
+                            animation_state.animation_in_progress = true;
+                            
+                            // Use consistent animation duration for continuous movement
+                            let animation_duration = step_seconds;
+                            animation.animation_timer = Timer::from_seconds(animation_duration, TimerMode::Once);
+                            
+                            // Flip the wobble direction for alternating effect
+                            animation.wobble_direction *= -1.0;
+                            
+                            // Increment the turn counter for continuous movement
+                            game_turn.increment();
+                            if slowed {
+                                game_turn.increment();
+                            }
+                            
+                            println!("Continuing movement in direction {:?}, animation speed: {:.2}s", 
+                                     direction, animation_duration);
+                        }
+                    }
+                } else {
+                    // Reset rapid press count when not continuing movement
+                    animation.rapid_press_count = 0;
+                }
+            }
+        }
+        // Only start a new animation if not currently animating
+        else if (input_state.up || input_state.down || input_state.left || input_state.right) && !animation_state.animation_in_progress {
+            // Calculate the target position based on the Position component
+            let target_pos = Vec3::new(
+                position.x as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+                position.y as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+                10.0  // Keep z-coordinate at 10.0 to ensure player is always on top
+            );
+            
+            // Only start animation if the position actually changed
+            if transform.translation != target_pos {
+                // Store the starting position
+                animation.start_pos = transform.translation;
+                animation.target_pos = target_pos;
+                animation.is_moving = true;
+                
+                // Set the global animation state for player movement
+                animation_state.animation_in_progress = true;
+                
+                // Store the movement direction and update sprite facing
+                let mut direction = None;
+                
+                if input_state.up {
+                    direction = Some(components::MovementDirection::Up);
+                } else if input_state.down {
+                    direction = Some(components::MovementDirection::Down);
+                } else if input_state.left {
+                    direction = Some(components::MovementDirection::Left);
+                    if animation.facing_right {
+                        animation.facing_right = false;
+                        sprite.flip_x = false;
+                        println!("Flipping sprite to face left");
+                    }
+                } else if input_state.right {
+                    direction = Some(components::MovementDirection::Right);
+                    if !animation.facing_right {
+                        animation.facing_right = true;
+                        sprite.flip_x = true;
+                        println!("Flipping sprite to face right");
+                    }
+                }
+                
+                animation.last_movement_direction = direction;
+                
+                // Check for rapid key presses (within 0.3 seconds)
+                let current_time = time.elapsed_seconds_f64();
+                
+                if current_time - input_state.last_key_press_time < 0.3 {
+                    // Increment rapid press count (max 5) - we still track this but don't use it for speed
+                    animation.rapid_press_count = (animation.rapid_press_count + 1).min(5);
+                } else {
+                    // Reset rapid press count
+                    animation.rapid_press_count = 0;
+                }
+                
+                // Use consistent animation duration regardless of rapid press count
+                let animation_duration = step_seconds;
+                animation.animation_timer = Timer::from_seconds(animation_duration, TimerMode::Once);
+                
+                // Flip the wobble direction for alternating effect
+                animation.wobble_direction *= -1.0;
+                
+                // Print debug info
+                println!("Starting animation, direction: {:?}, animation speed: {:.2}s", 
+                         animation.last_movement_direction, animation_duration);
+            }
+        }
+    }
+}
+
+// Add a new system to process turn-based effects
+pub fn process_turn_effects(
+    game_turn: Res<GameTurn>,
+    player_query: Query<&Position, With<Player>>,
+    npc_query: Query<(&Position, &Npc)>,
+    map: Res<TileMap>,
+) {
+    // Skip if there's no player
+    if player_query.is_empty() {
+        return;
+    }
+
+    // Get the player position
+    let player_pos = player_query.single();
+    
+    // Log turn milestones
+    if game_turn.current_turn > 0 && game_turn.current_turn % 10 == 0 {
+        println!("Turn milestone: {} turns have passed", game_turn.current_turn);
+        println!("Player is at position: ({}, {})", player_pos.x, player_pos.y);
+        
+        // Count nearby NPCs (within 5 tiles) - this could be used for future combat awareness
+        let mut nearby_npcs = 0;
+        for (npc_pos, _) in npc_query.iter() {
+            let distance = ((npc_pos.x - player_pos.x).pow(2) + (npc_pos.y - player_pos.y).pow(2)) as f32;
+            if distance.sqrt() <= 5.0 {
+                nearby_npcs += 1;
+            }
+        }
+        
+        if nearby_npcs > 0 {
+            println!("There are {} NPCs within 5 tiles of the player", nearby_npcs);
+        }
+    }
+    
+    // Note: Animal movements are now handled by the move_animals_system
+    // which is called after this system and checks the game_turn
+}
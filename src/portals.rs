@@ -0,0 +1,154 @@
+use bevy::prelude::*;
+use std::path::Path;
+
+use crate::animals::place_companions_near;
+use crate::components::{AnimalAnimation, Companion, Player, PlayerAnimation, Position};
+use crate::events::{PlayerMoved, TileEntered};
+use crate::input::TILE_SIZE;
+use crate::level::move_player_to;
+use crate::lighting::LightSource;
+use crate::map::TileMap;
+use crate::ui::MessageLog;
+
+const PORTAL_SOUND_PATH: &str = "audio/portal.ogg";
+// How fast an idle portal turns, in radians per second
+const PORTAL_SPIN_SPEED: f32 = 1.5;
+const SWIRL_SECONDS: f32 = 0.6;
+
+/// The visible half of a portal pair, standing on its tile
+#[derive(Component)]
+pub struct Portal;
+
+/// A burst of swirling light where the player left or arrived
+#[derive(Component)]
+pub struct PortalSwirl {
+    timer: Timer,
+}
+
+fn portal_color(alpha: f32) -> Color {
+    Color::rgba(0.6, 0.3, 1.0, alpha)
+}
+
+fn tile_center(tile: (usize, usize), z: f32) -> Vec3 {
+    Vec3::new(tile.0 as f32 * TILE_SIZE + TILE_SIZE / 2.0, tile.1 as f32 * TILE_SIZE + TILE_SIZE / 2.0, z)
+}
+
+fn spawn_swirl(commands: &mut Commands, tile: (usize, usize)) {
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: portal_color(0.9),
+                custom_size: Some(Vec2::splat(TILE_SIZE * 0.6)),
+                ..default()
+            },
+            transform: Transform::from_translation(tile_center(tile, 11.0)),
+            ..default()
+        },
+        PortalSwirl {
+            timer: Timer::from_seconds(SWIRL_SECONDS, TimerMode::Once),
+        },
+    ));
+}
+
+// System to put a portal on each end of every pair whenever a different level is loaded
+pub fn sync_portals(
+    mut commands: Commands,
+    map: Res<TileMap>,
+    portal_query: Query<Entity, With<Portal>>,
+) {
+    for entity in portal_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    for &(a, b) in &map.portal_pairs {
+        for tile in [a, b] {
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: portal_color(0.75),
+                        custom_size: Some(Vec2::splat(TILE_SIZE * 0.7)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(tile_center(tile, 0.6)),
+                    ..default()
+                },
+                Position::new(tile.0 as i32, tile.1 as i32),
+                LightSource { color: portal_color(1.0), radius: 2, intensity: 0.8 },
+                Portal,
+            ));
+        }
+    }
+}
+
+// System to keep idle portals turning
+pub fn spin_portals(time: Res<Time>, mut portal_query: Query<&mut Transform, With<Portal>>) {
+    for mut transform in portal_query.iter_mut() {
+        transform.rotate_z(PORTAL_SPIN_SPEED * time.delta_seconds());
+    }
+}
+
+// System to send the player to the other end of a portal they step onto
+pub fn use_portals_system(
+    mut commands: Commands,
+    mut tile_events: EventReader<TileEntered>,
+    map: Res<TileMap>,
+    asset_server: Res<AssetServer>,
+    mut player_query: Query<(&mut Transform, &mut Position, &mut PlayerAnimation), With<Player>>,
+    mut companion_query: Query<(Entity, &mut Transform, &mut AnimalAnimation), (With<Companion>, Without<Player>)>,
+    mut moved_events: EventWriter<PlayerMoved>,
+    mut message_log: ResMut<MessageLog>,
+) {
+    for entered in tile_events.read() {
+        let (mut transform, mut position, mut animation) = if let Ok(player) = player_query.get_mut(entered.entity) {
+            player
+        } else {
+            continue;
+        };
+        if entered.x < 0 || entered.y < 0 {
+            continue;
+        }
+        let from = (entered.x as usize, entered.y as usize);
+        let to = if let Some(to) = map.portal_destination(from) { to } else { continue };
+
+        // A step queued behind this one would start from the wrong end of the portal
+        animation.is_moving = false;
+        animation.move_buffer.clear();
+        move_player_to(&mut transform, &mut position, to);
+        // Overrides the Position a queued step may have just inserted
+        commands.entity(entered.entity).insert(*position);
+
+        place_companions_near(&mut commands, &mut companion_query, &map, (to.0 as i32, to.1 as i32));
+        spawn_swirl(&mut commands, from);
+        spawn_swirl(&mut commands, to);
+        if Path::new("assets").join(PORTAL_SOUND_PATH).exists() {
+            commands.spawn(AudioBundle {
+                source: asset_server.load(PORTAL_SOUND_PATH),
+                settings: PlaybackSettings::DESPAWN,
+            });
+        }
+
+        // Lets sight, scent and room announcements catch up with the jump
+        moved_events.send(PlayerMoved { x: position.x, y: position.y });
+        message_log.add_message("The portal twists around you and you step out somewhere else.".to_string());
+        println!("Portal jump from {:?} to {:?}", from, to);
+    }
+}
+
+// System to spin swirls out and fade them away
+pub fn animate_portal_swirls(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut swirl_query: Query<(Entity, &mut Transform, &mut Sprite, &mut PortalSwirl)>,
+) {
+    for (entity, mut transform, mut sprite, mut swirl) in swirl_query.iter_mut() {
+        swirl.timer.tick(time.delta());
+        if swirl.timer.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let progress = swirl.timer.percent();
+        transform.rotation = Quat::from_rotation_z(progress * std::f32::consts::TAU * 2.0);
+        transform.scale = Vec3::splat(1.0 + progress * 1.5);
+        sprite.color = portal_color(0.9 * (1.0 - progress));
+    }
+}
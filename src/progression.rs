@@ -0,0 +1,100 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+use crate::biome::BiomeType;
+
+/// Where the depth progression table lives, relative to the assets folder
+pub const PROGRESSION_PATH: &str = "data/progression.json";
+
+/// A biome and how likely it is to be picked
+#[derive(Debug, Clone, Deserialize)]
+pub struct BiomeWeight {
+    pub biome: BiomeType,
+    pub weight: u32,
+}
+
+/// How levels look and play from a given depth onward
+#[derive(Debug, Clone, Deserialize)]
+pub struct DepthTier {
+    pub min_level: usize,                // First (zero-based) level this tier applies to
+    pub biomes: Vec<BiomeWeight>,
+    pub room_multiplier: f32,            // Scales the number of rooms generated
+    pub traps: usize,                    // Hidden traps placed on the level
+    pub monster_health_multiplier: f32,
+    pub monster_attack_bonus: i32,
+    pub ambient_light: f32,              // Brightness of unlit tiles
+}
+
+impl Default for DepthTier {
+    fn default() -> Self {
+        Self {
+            min_level: 0,
+            biomes: vec![
+                BiomeWeight { biome: BiomeType::Caves, weight: 1 },
+                BiomeWeight { biome: BiomeType::Groves, weight: 1 },
+                BiomeWeight { biome: BiomeType::Labyrinth, weight: 1 },
+                BiomeWeight { biome: BiomeType::Catacombs, weight: 1 },
+            ],
+            room_multiplier: 1.0,
+            traps: 0,
+            monster_health_multiplier: 1.0,
+            monster_attack_bonus: 0,
+            ambient_light: 0.3,
+        }
+    }
+}
+
+impl DepthTier {
+    // Pick this level's biome from the tier's weights
+    pub fn choose_biome(&self, rng: &mut impl Rng) -> BiomeType {
+        self.biomes
+            .choose_weighted(rng, |entry| entry.weight)
+            .map(|entry| entry.biome)
+            .unwrap_or(BiomeType::Caves)
+    }
+
+    pub fn scale_monster_health(&self, health: i32) -> i32 {
+        ((health as f32 * self.monster_health_multiplier).round() as i32).max(1)
+    }
+}
+
+/// The full depth progression table
+#[derive(Debug, Clone, Deserialize)]
+pub struct DepthProgression {
+    pub tiers: Vec<DepthTier>,
+}
+
+impl DepthProgression {
+    // Read the table from disk; it's re-read for every new level so edits apply without a restart
+    pub fn load() -> Self {
+        let path = Path::new("assets").join(PROGRESSION_PATH);
+        let parsed = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| serde_json::from_str::<DepthProgression>(&contents).map_err(|e| e.to_string()));
+
+        match parsed {
+            Ok(progression) if !progression.tiers.is_empty() => progression,
+            Ok(_) => {
+                eprintln!("Depth progression table {} has no tiers, using defaults", path.display());
+                Self { tiers: vec![DepthTier::default()] }
+            }
+            Err(e) => {
+                eprintln!("Could not load depth progression table {}: {}", path.display(), e);
+                Self { tiers: vec![DepthTier::default()] }
+            }
+        }
+    }
+
+    // The deepest tier that has started by this level
+    pub fn tier_for_level(&self, level: usize) -> DepthTier {
+        self.tiers
+            .iter()
+            .filter(|tier| tier.min_level <= level)
+            .max_by_key(|tier| tier.min_level)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
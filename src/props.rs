@@ -0,0 +1,304 @@
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::assets::{get_item_sprite, get_tile_sprite, SpriteAssets, TextureAtlases};
+use crate::biome::BiomeType;
+use crate::components::Position;
+use crate::input::TILE_SIZE;
+use crate::interaction::{Interactable, InteractionKind};
+use crate::lore::generate_readable;
+use crate::map::{RoomPurpose, TileMap, TileType};
+use crate::rng::RngStream;
+
+// Props sit above the floor and below chests and creatures
+const PROP_Z: f32 = 2.0;
+
+// Share of open floor that gets a prop, in plain areas and in special rooms
+const SCATTER_DENSITY: f64 = 0.03;
+const ROOM_DENSITY: f64 = 0.15;
+
+// Chance a room gets something to read, by what's usual in its biome
+const GRAVESTONE_CHANCE: f64 = 0.5;
+const TABLET_CHANCE: f64 = 0.35;
+const SIGN_CHANCE: f64 = 0.08;
+// Most gravestones put in one Catacombs room
+const MAX_GRAVESTONES_PER_ROOM: usize = 3;
+
+/// Which sprite sheet a prop is drawn from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PropAtlas {
+    Tiles,
+    Items,
+}
+
+/// One kind of prop: its sprite and whether it stands in the way
+#[derive(Debug, Clone, Copy)]
+pub struct PropDef {
+    pub sprite: &'static str,
+    pub atlas: PropAtlas,
+    pub blocking: bool,
+}
+
+const fn prop(sprite: &'static str, atlas: PropAtlas, blocking: bool) -> PropDef {
+    PropDef { sprite, atlas, blocking }
+}
+
+/// Props with something written on them, read with E
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadableKind {
+    Sign,
+    Gravestone,
+    Tablet,
+}
+
+impl ReadableKind {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            ReadableKind::Sign => "sign",
+            ReadableKind::Gravestone => "gravestone",
+            ReadableKind::Tablet => "tablet",
+        }
+    }
+
+    // There are no sprites made for these, so they borrow one and are tinted to stand apart
+    fn def(&self) -> PropDef {
+        match self {
+            ReadableKind::Sign => prop("scroll", PropAtlas::Items, false),
+            ReadableKind::Gravestone => prop("large rock 1", PropAtlas::Tiles, false),
+            ReadableKind::Tablet => prop("large rock 2", PropAtlas::Tiles, false),
+        }
+    }
+
+    fn tint(&self) -> Color {
+        match self {
+            ReadableKind::Sign => Color::rgb(0.9, 0.8, 0.6),
+            ReadableKind::Gravestone => Color::rgb(0.85, 0.85, 0.95),
+            ReadableKind::Tablet => Color::rgb(0.6, 0.75, 1.0),
+        }
+    }
+}
+
+/// A prop placed on the map during generation
+#[derive(Debug, Clone)]
+pub struct PropPlacement {
+    pub x: usize,
+    pub y: usize,
+    pub def: PropDef,
+    pub readable: Option<ReadableKind>,
+}
+
+/// Marker for a prop entity
+#[derive(Component, Debug)]
+pub struct Prop {
+    pub blocking: bool,
+}
+
+// Litter scattered through each biome
+fn biome_props(biome: BiomeType) -> Vec<PropDef> {
+    match biome {
+        BiomeType::Caves => vec![
+            prop("large rock 1", PropAtlas::Tiles, true),
+            prop("large rock 2", PropAtlas::Tiles, true),
+            prop("small mushrooms", PropAtlas::Tiles, false),
+            prop("corpse (bones) 1", PropAtlas::Tiles, false),
+        ],
+        BiomeType::Groves => vec![
+            prop("small mushrooms", PropAtlas::Tiles, false),
+            prop("large mushroom", PropAtlas::Tiles, true),
+            prop("log pile", PropAtlas::Tiles, true),
+            prop("flax", PropAtlas::Tiles, false),
+        ],
+        BiomeType::Labyrinth => vec![
+            prop("large rock 2", PropAtlas::Tiles, true),
+            prop("corpse (bones) 2", PropAtlas::Tiles, false),
+            prop("blood spatter 1", PropAtlas::Tiles, false),
+        ],
+        BiomeType::Catacombs => vec![
+            prop("corpse (bones) 1", PropAtlas::Tiles, false),
+            prop("corpse (bones) 2", PropAtlas::Tiles, false),
+            prop("blood spatter 2", PropAtlas::Tiles, false),
+            prop("jar (closed)", PropAtlas::Tiles, true),
+        ],
+    }
+}
+
+// Furniture and clutter that give special rooms their look
+fn purpose_props(purpose: RoomPurpose) -> Vec<PropDef> {
+    match purpose {
+        RoomPurpose::Shrine => vec![
+            prop("jar (closed)", PropAtlas::Tiles, true),
+            prop("jar (open)", PropAtlas::Tiles, false),
+            prop("holy staff", PropAtlas::Items, false),
+        ],
+        RoomPurpose::Library => vec![
+            prop("book", PropAtlas::Items, false),
+            prop("scroll", PropAtlas::Items, false),
+            prop("barrel", PropAtlas::Tiles, true),
+        ],
+        RoomPurpose::Armory => vec![
+            prop("kite shield", PropAtlas::Items, false),
+            prop("halberd", PropAtlas::Items, false),
+            prop("spear", PropAtlas::Items, false),
+            prop("barrel", PropAtlas::Tiles, true),
+        ],
+        RoomPurpose::Nest => vec![
+            prop("corpse (bones) 1", PropAtlas::Tiles, false),
+            prop("corpse (bones) 2", PropAtlas::Tiles, false),
+            prop("blood spatter 1", PropAtlas::Tiles, false),
+            prop("blood spatter 2", PropAtlas::Tiles, false),
+        ],
+        RoomPurpose::Shop => vec![
+            prop("barrel", PropAtlas::Tiles, true),
+            prop("ore sack", PropAtlas::Tiles, true),
+            prop("jar (closed)", PropAtlas::Tiles, false),
+        ],
+    }
+}
+
+// Whether a blocking prop here would leave its open neighbours still joined up around it
+fn safe_to_block(map: &TileMap, x: usize, y: usize) -> bool {
+    // The eight neighbours in order around the tile
+    const RING: [(i32, i32); 8] = [(0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1), (-1, 0), (-1, 1)];
+    let open: Vec<bool> = RING.iter()
+        .map(|(dx, dy)| {
+            let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+            map.in_bounds(nx, ny) && map.tiles[ny as usize][nx as usize] != TileType::Wall
+        })
+        .collect();
+
+    // More than one run of open tiles around the ring means this tile is a chokepoint
+    let runs = (0..RING.len()).filter(|&i| open[i] && !open[(i + RING.len() - 1) % RING.len()]).count();
+    runs <= 1 && open.iter().any(|&o| o)
+}
+
+// Pick props for a freshly generated level, keeping clear of anything else on the floor
+pub fn place_props(map: &TileMap, rng: &mut impl Rng) -> Vec<PropPlacement> {
+    let mut props: Vec<PropPlacement> = Vec::new();
+
+    for y in 1..map.height - 1 {
+        for x in 1..map.width - 1 {
+            let pos = (x, y);
+            let occupied = map.tiles[y][x] != TileType::Floor
+                || pos == map.spawn_position
+                || map.chest_positions.contains(&pos)
+                || map.trap_positions.contains(&pos)
+                || map.npc_spawns.contains(&pos)
+                || map.monster_spawns.contains(&pos)
+                || map.portal_destination(pos).is_some()
+                || map.altar_positions.contains(&pos)
+                || map.vaults.iter().any(|vault| vault.contains(x, y));
+            if occupied {
+                continue;
+            }
+
+            let purpose = map.room_at(x, y).and_then(|room| room.purpose);
+            let (choices, density) = match purpose {
+                Some(purpose) => (purpose_props(purpose), ROOM_DENSITY),
+                None => (biome_props(map.get_biome_at(x, y)), SCATTER_DENSITY),
+            };
+            if !rng.gen_bool(density) {
+                continue;
+            }
+
+            let def = if let Some(def) = choices.choose(rng) { *def } else { continue };
+            // Props that would cut off part of the level don't block after all
+            let next_to_prop = props.iter().any(|other| other.def.blocking && other.x.abs_diff(x) <= 1 && other.y.abs_diff(y) <= 1);
+            let blocking = def.blocking && !next_to_prop && safe_to_block(map, x, y);
+            props.push(PropPlacement { x, y, def: PropDef { blocking, ..def }, readable: None });
+        }
+    }
+
+    let readables = place_readables(map, &props, rng);
+    println!("Placed {} props, {} of them readable", props.len() + readables.len(), readables.len());
+    props.extend(readables);
+    props
+}
+
+// Gravestones in the Catacombs, tablets in the Labyrinth, and the odd sign anywhere else
+fn place_readables(map: &TileMap, props: &[PropPlacement], rng: &mut impl Rng) -> Vec<PropPlacement> {
+    let mut readables: Vec<PropPlacement> = Vec::new();
+
+    for room in &map.rooms {
+        let biome = map.get_biome_at(room.x + room.width / 2, room.y + room.height / 2);
+        let (kind, chance, count) = match biome {
+            BiomeType::Catacombs => (ReadableKind::Gravestone, GRAVESTONE_CHANCE, rng.gen_range(1..=MAX_GRAVESTONES_PER_ROOM)),
+            BiomeType::Labyrinth => (ReadableKind::Tablet, TABLET_CHANCE, 1),
+            BiomeType::Caves | BiomeType::Groves => (ReadableKind::Sign, SIGN_CHANCE, 1),
+        };
+        if !rng.gen_bool(chance) {
+            continue;
+        }
+
+        // Open floor in the room with nothing else on it
+        let mut spots: Vec<(usize, usize)> = (room.y..room.y + room.height)
+            .flat_map(|y| (room.x..room.x + room.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| {
+                let pos = (x, y);
+                map.in_bounds(x as i32, y as i32)
+                    && map.tiles[y][x] == TileType::Floor
+                    && pos != map.spawn_position
+                    && Some(pos) != map.down_stairs_pos
+                    && Some(pos) != map.up_stairs_pos
+                    && !map.chest_positions.contains(&pos)
+                    && !map.trap_positions.contains(&pos)
+                    && !map.npc_spawns.contains(&pos)
+                    && !map.monster_spawns.contains(&pos)
+                    && map.portal_destination(pos).is_none()
+                    && !map.altar_positions.contains(&pos)
+                    && !props.iter().chain(readables.iter()).any(|prop| prop.x == x && prop.y == y)
+            })
+            .collect();
+        spots.shuffle(rng);
+
+        for (x, y) in spots.into_iter().take(count) {
+            readables.push(PropPlacement { x, y, def: kind.def(), readable: Some(kind) });
+        }
+    }
+    readables
+}
+
+// Spawn a sprite for every prop on the map
+pub fn spawn_props(
+    commands: &mut Commands,
+    map: &TileMap,
+    texture_atlases: &TextureAtlases,
+    sprite_assets: &SpriteAssets,
+) {
+    for placement in &map.props {
+        let (texture_atlas, index) = match placement.def.atlas {
+            PropAtlas::Tiles => (texture_atlases.tiles.clone(), get_tile_sprite(sprite_assets, placement.def.sprite)),
+            PropAtlas::Items => (texture_atlases.items.clone(), get_item_sprite(sprite_assets, placement.def.sprite)),
+        };
+
+        let color = placement.readable.map_or(Color::WHITE, |kind| kind.tint());
+        let mut prop = commands.spawn((
+            SpriteSheetBundle {
+                texture_atlas,
+                sprite: TextureAtlasSprite {
+                    index,
+                    color,
+                    ..default()
+                },
+                transform: Transform::from_xyz(
+                    placement.x as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+                    placement.y as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+                    PROP_Z,
+                ),
+                ..default()
+            },
+            Prop { blocking: placement.def.blocking },
+            Position::new(placement.x as i32, placement.y as i32),
+        ));
+
+        if let Some(kind) = placement.readable {
+            // Seeded from the level and the spot, so the same stone always says the same thing
+            let mut rng = RngStream::Dialogue.seeded(map.seed ^ ((placement.x as u64) << 32 | placement.y as u64));
+            let biome = map.get_biome_at(placement.x, placement.y);
+            prop.insert((
+                Interactable::new(InteractionKind::Read, format!("Read the {}", kind.get_name())),
+                generate_readable(kind, biome, &mut rng),
+            ));
+        }
+    }
+}
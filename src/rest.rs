@@ -0,0 +1,206 @@
+use bevy::core::FrameCount;
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::combat::Health;
+use crate::components::{GameTurn, Player, Position};
+use crate::conversation::Conversation;
+use crate::emotes::ShowEmote;
+use crate::events::SecretDoorFound;
+use crate::faction::Hostile;
+use crate::frame_timing::first_tick_this_frame;
+use crate::map::{TileMap, TileType};
+use crate::ui::MessageLog;
+use crate::level::DungeonState;
+use crate::player::AnimationState;
+use crate::rng::GameRng;
+
+// Resting heals one hit point every this many turns
+const REST_TURNS_PER_HEAL: u32 = 3;
+// A hostile this many tiles away or closer stops (or refuses) a rest
+const REST_ALERT_RANGE: i32 = 6;
+// Resting gives up after this many turns even if not fully healed.
+// There's no hunger clock yet, so this is what stops a rest going on forever
+const MAX_REST_TURNS: u32 = 200;
+// Chance for each hidden thing next to the player to be found by one search
+const SEARCH_CHANCE: f64 = 0.5;
+
+/// An ongoing rest, started with R
+#[derive(Resource, Default, Debug)]
+pub struct RestState {
+    pub active: bool,
+    pub turns: u32,
+    last_health: i32, // Health at the last rest turn, so any damage interrupts the rest
+}
+
+impl RestState {
+    fn stop(&mut self, message_log: &mut MessageLog, reason: &str) {
+        message_log.add_message(format!("You stop resting after {} turns: {}", self.turns, reason));
+        self.active = false;
+    }
+}
+
+// The closest hostile within alert range, as a tile distance
+fn nearest_hostile(player: &Position, hostiles: &Query<&Position, (With<Hostile>, Without<Player>)>) -> Option<i32> {
+    hostiles.iter()
+        .map(|pos| (pos.x - player.x).abs().max((pos.y - player.y).abs()))
+        .filter(|&distance| distance <= REST_ALERT_RANGE)
+        .min()
+}
+
+// System to wait a turn (period), or start and stop resting (R)
+pub fn wait_and_rest_input_system(
+    keyboard: Res<Input<KeyCode>>,
+    conversation: Res<Conversation>,
+    animation_state: Res<AnimationState>,
+    mut game_turn: ResMut<GameTurn>,
+    mut rest_state: ResMut<RestState>,
+    mut message_log: ResMut<MessageLog>,
+    player_query: Query<(&Position, &Health), With<Player>>,
+    hostile_query: Query<&Position, (With<Hostile>, Without<Player>)>,
+) {
+    if conversation.awaiting_choice() || animation_state.animation_in_progress {
+        return;
+    }
+
+    // Any other key interrupts a rest in progress
+    if rest_state.active {
+        if keyboard.get_just_pressed().next().is_some() {
+            rest_state.stop(&mut message_log, "interrupted.");
+        }
+        return;
+    }
+
+    let (position, health) = if let Ok(player) = player_query.get_single() { player } else { return; };
+
+    if keyboard.just_pressed(KeyCode::Period) {
+        game_turn.increment();
+        message_log.add_message("You wait.".to_string());
+        return;
+    }
+
+    // Shift+R regenerates the map, so only a bare R rests
+    if keyboard.just_pressed(KeyCode::R) && !keyboard.pressed(KeyCode::ShiftLeft) {
+        if health.current >= health.max {
+            message_log.add_message("You are already at full health.".to_string());
+        } else if nearest_hostile(position, &hostile_query).is_some() {
+            message_log.add_message("You can't rest with enemies nearby!".to_string());
+        } else {
+            message_log.add_message("You settle down to rest...".to_string());
+            *rest_state = RestState {
+                active: true,
+                turns: 0,
+                last_health: health.current,
+            };
+        }
+    }
+}
+
+// System to pass turns while resting, until healed or something interesting happens. Runs on the fixed
+// simulation tick, one turn a tick, so monsters can be seen moving and a rest takes as long on any machine
+pub fn rest_system(
+    frame: Res<FrameCount>,
+    mut game_turn: ResMut<GameTurn>,
+    mut rest_state: ResMut<RestState>,
+    mut message_log: ResMut<MessageLog>,
+    mut player_query: Query<(Entity, &Position, &mut Health), With<Player>>,
+    hostile_query: Query<&Position, (With<Hostile>, Without<Player>)>,
+    mut emote_events: EventWriter<ShowEmote>,
+    mut last_frame: Local<Option<u32>>,
+) {
+    if !rest_state.active || !first_tick_this_frame(&frame, &mut last_frame) {
+        return;
+    }
+
+    let (entity, position, mut health) = if let Ok(player) = player_query.get_single_mut() {
+        player
+    } else {
+        rest_state.active = false;
+        return;
+    };
+
+    // Check what happened on the last turn before passing another one
+    if health.current < rest_state.last_health {
+        rest_state.stop(&mut message_log, "something hurt you!");
+        return;
+    }
+    if let Some(distance) = nearest_hostile(position, &hostile_query) {
+        rest_state.stop(&mut message_log, &format!("an enemy is {} tiles away!", distance));
+        return;
+    }
+    if health.current >= health.max {
+        rest_state.stop(&mut message_log, "you feel fully rested.");
+        return;
+    }
+    if rest_state.turns >= MAX_REST_TURNS {
+        rest_state.stop(&mut message_log, "you can't rest any longer.");
+        return;
+    }
+
+    game_turn.increment();
+    rest_state.turns += 1;
+    if rest_state.turns % REST_TURNS_PER_HEAL == 0 {
+        health.heal(1);
+        emote_events.send(ShowEmote::text(entity, "z"));
+    }
+    rest_state.last_health = health.current;
+}
+
+// System to search the tiles around the player (Z) for hidden traps and secret doors
+pub fn search_system(
+    keyboard: Res<Input<KeyCode>>,
+    conversation: Res<Conversation>,
+    animation_state: Res<AnimationState>,
+    rest_state: Res<RestState>,
+    mut game_turn: ResMut<GameTurn>,
+    mut map: ResMut<TileMap>,
+    mut dungeon_state: ResMut<DungeonState>,
+    mut message_log: ResMut<MessageLog>,
+    mut door_events: EventWriter<SecretDoorFound>,
+    player_query: Query<&Position, With<Player>>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    if !keyboard.just_pressed(KeyCode::Z)
+        || conversation.awaiting_choice()
+        || animation_state.animation_in_progress
+        || rest_state.active
+    {
+        return;
+    }
+    let position = if let Ok(pos) = player_query.get_single() { *pos } else { return; };
+
+    game_turn.increment();
+    let rng = &mut game_rng.combat;
+    let mut found = false;
+
+    for dy in -1..=1 {
+        for dx in -1..=1 {
+            let (x, y) = (position.x + dx, position.y + dy);
+            if (dx == 0 && dy == 0) || !map.in_bounds(x, y) || !rng.gen_bool(SEARCH_CHANCE) {
+                continue;
+            }
+            let tile = (x as usize, y as usize);
+
+            // A found trap is disarmed, the same way a sprung one is used up
+            if map.trap_positions.contains(&tile) {
+                map.trap_positions.retain(|&pos| pos != tile);
+                let current_level = dungeon_state.current_level_index;
+                if let Some(level) = dungeon_state.levels.get_mut(current_level) {
+                    level.trap_positions.retain(|&pos| pos != tile);
+                }
+                message_log.add_message("You find a hidden trap and disarm it.".to_string());
+                found = true;
+            }
+
+            if map.tiles[tile.1][tile.0] == TileType::SecretDoor {
+                message_log.add_message("You feel a draft - there's a secret door in this wall.".to_string());
+                door_events.send(SecretDoorFound { x, y });
+                found = true;
+            }
+        }
+    }
+
+    if !found {
+        message_log.add_message("You search around but find nothing.".to_string());
+    }
+}
@@ -0,0 +1,71 @@
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+/// The independent random streams a run draws from. Keeping them apart means that,
+/// say, an extra line of dialogue doesn't change which monsters spawn next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RngStream {
+    MapGen,
+    Spawns,
+    Dialogue,
+    Combat,
+    Ambient,
+}
+
+impl RngStream {
+    // Mixed into the run seed so each stream starts somewhere different
+    fn salt(&self) -> u64 {
+        match self {
+            RngStream::MapGen => 0x6d61_7067_656e_0001,
+            RngStream::Spawns => 0x7370_6177_6e73_0002,
+            RngStream::Dialogue => 0x6469_616c_6f67_0003,
+            RngStream::Combat => 0x636f_6d62_6174_0004,
+            RngStream::Ambient => 0x616d_6269_656e_0005,
+        }
+    }
+
+    /// A generator for this stream from any seed, e.g. a level's, for work that must repeat exactly
+    pub fn seeded(&self, seed: u64) -> StdRng {
+        StdRng::seed_from_u64(seed ^ self.salt())
+    }
+}
+
+/// Every random roll in a run comes from here, so the same run seed plays out the same way
+#[derive(Resource)]
+pub struct GameRng {
+    seed: u64,
+    pub mapgen: StdRng,   // Seeds for each new level
+    pub spawns: StdRng,   // What is placed where, and who turns up
+    pub dialogue: StdRng, // Names and lines
+    pub combat: StdRng,   // Hits, taming and anything else decided in play
+    pub ambient: StdRng,  // Rumbles, flickering lights and other background events
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            mapgen: RngStream::MapGen.seeded(seed),
+            spawns: RngStream::Spawns.seeded(seed),
+            dialogue: RngStream::Dialogue.seeded(seed),
+            combat: RngStream::Combat.seeded(seed),
+            ambient: RngStream::Ambient.seeded(seed),
+        }
+    }
+
+    /// The run seed everything was derived from
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn stream(&mut self, stream: RngStream) -> &mut StdRng {
+        match stream {
+            RngStream::MapGen => &mut self.mapgen,
+            RngStream::Spawns => &mut self.spawns,
+            RngStream::Dialogue => &mut self.dialogue,
+            RngStream::Combat => &mut self.combat,
+            RngStream::Ambient => &mut self.ambient,
+        }
+    }
+}
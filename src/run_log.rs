@@ -0,0 +1,325 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::components::{GameTurn, Player, Position};
+use crate::input::TileClick;
+use crate::map::TileMap;
+use crate::ui::MessageLog;
+use crate::player::AnimationState;
+use crate::run_modifiers::{RunModifier, RunModifiers};
+
+/// Folder exported run logs are written to
+pub const RUN_LOG_DIR: &str = "runs";
+
+// Keys that change the game state, with the names they're saved under.
+// Camera and UI keys are left out so a log only holds what matters for a replay,
+// apart from the ones that also confirm, cancel or aim
+const RECORDED_KEYS: [(KeyCode, &str); 31] = [
+    (KeyCode::W, "W"),
+    (KeyCode::A, "A"),
+    (KeyCode::S, "S"),
+    (KeyCode::D, "D"),
+    (KeyCode::Up, "W"),
+    (KeyCode::Left, "A"),
+    (KeyCode::Down, "S"),
+    (KeyCode::Right, "D"),
+    (KeyCode::E, "E"),
+    (KeyCode::C, "C"),
+    (KeyCode::F, "F"),
+    (KeyCode::T, "T"),
+    (KeyCode::R, "R"),
+    (KeyCode::Z, "Z"),
+    (KeyCode::Period, "Period"),
+    (KeyCode::I, "I"),
+    (KeyCode::X, "X"),
+    (KeyCode::Q, "Q"),
+    (KeyCode::O, "O"),
+    (KeyCode::V, "V"),
+    (KeyCode::B, "B"),
+    (KeyCode::Space, "Space"),
+    (KeyCode::Return, "Return"),
+    (KeyCode::Back, "Back"),
+    (KeyCode::Key1, "1"),
+    (KeyCode::Key2, "2"),
+    (KeyCode::Key3, "3"),
+    (KeyCode::Key4, "4"),
+    (KeyCode::Key5, "5"),
+    (KeyCode::ShiftLeft, "Shift"),
+    (KeyCode::ControlLeft, "Ctrl"),
+];
+
+// Modifiers are saved with a chord but never start one on their own
+const MODIFIER_KEYS: [KeyCode; 2] = [KeyCode::ShiftLeft, KeyCode::ControlLeft];
+
+fn key_name(key: KeyCode) -> Option<&'static str> {
+    RECORDED_KEYS.iter().find(|(code, _)| *code == key).map(|(_, name)| *name)
+}
+
+fn key_from_name(name: &str) -> Option<KeyCode> {
+    RECORDED_KEYS.iter().find(|(_, key_name)| *key_name == name).map(|(code, _)| *code)
+}
+
+/// A level that was generated during the run and the seed that built it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LevelSeed {
+    pub turn: u32,
+    pub level: usize,
+    pub seed: u64,
+}
+
+/// Keys pressed together on one turn, e.g. ["Ctrl", "S"] to take the stairs down, and the tile clicked, if any
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunInput {
+    pub turn: u32,
+    pub keys: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub click: Option<(i32, i32)>,
+}
+
+/// Everything needed to re-simulate a run: the modifiers it was played with, level seeds plus every action in order
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RunLog {
+    pub initial_seed: Option<u64>,
+    #[serde(default)]
+    pub modifiers: Vec<RunModifier>,
+    pub levels: Vec<LevelSeed>,
+    pub inputs: Vec<RunInput>,
+}
+
+impl RunLog {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+        serde_json::from_str(&contents).map_err(|e| format!("{} is not a valid run log: {}", path.display(), e))
+    }
+
+    // Write the log as compact JSON under RUN_LOG_DIR, returning where it went
+    pub fn export(&self) -> Result<PathBuf, String> {
+        fs::create_dir_all(RUN_LOG_DIR).map_err(|e| format!("could not create {}: {}", RUN_LOG_DIR, e))?;
+
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let path = Path::new(RUN_LOG_DIR).join(format!("run_{}.json", timestamp));
+
+        let contents = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        fs::write(&path, contents).map_err(|e| format!("could not write {}: {}", path.display(), e))?;
+        Ok(path)
+    }
+}
+
+// Seeds for a replay, read by map generation before the ECS world can be reached
+struct ReplaySeeds {
+    initial: Option<u64>,
+    levels: VecDeque<u64>,
+}
+
+static REPLAY_SEEDS: Mutex<Option<ReplaySeeds>> = Mutex::new(None);
+
+/// The first level's seed when replaying a run
+pub fn replay_initial_seed() -> Option<u64> {
+    REPLAY_SEEDS.lock().ok()?.as_ref()?.initial
+}
+
+/// The seed for the next generated level when replaying, in the order they were first generated
+pub fn next_replay_seed() -> Option<u64> {
+    REPLAY_SEEDS.lock().ok()?.as_mut()?.levels.pop_front()
+}
+
+/// A recorded run being played back.
+/// Level layouts, player inputs and every roll drawn from GameRng replay exactly. Only cosmetic effects
+/// (particles, camera shake) still use thread_rng, and they never feed back into play.
+#[derive(Resource, Default)]
+pub struct RunReplay {
+    inputs: VecDeque<RunInput>,
+    held: Vec<KeyCode>,
+    pub modifiers: Vec<RunModifier>, // Chosen for the run instead of whatever is ticked on the menu
+    pub active: bool,
+}
+
+impl RunReplay {
+    // Start a replay when launched with `--replay <run log>`
+    pub fn from_args() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+        let path = if let Some(index) = args.iter().position(|arg| arg == "--replay") {
+            if let Some(path) = args.get(index + 1) {
+                path
+            } else {
+                eprintln!("--replay needs the path of a run log");
+                return Self::default();
+            }
+        } else {
+            return Self::default();
+        };
+
+        let log = match RunLog::load(Path::new(path)) {
+            Ok(log) => log,
+            Err(e) => {
+                eprintln!("Not replaying: {}", e);
+                return Self::default();
+            }
+        };
+        println!("Replaying {} ({} levels, {} inputs)", path, log.levels.len() + 1, log.inputs.len());
+
+        if let Ok(mut seeds) = REPLAY_SEEDS.lock() {
+            *seeds = Some(ReplaySeeds {
+                initial: log.initial_seed,
+                levels: log.levels.iter().map(|level| level.seed).collect(),
+            });
+        }
+
+        Self {
+            inputs: log.inputs.into_iter().collect(),
+            held: Vec::new(),
+            modifiers: log.modifiers,
+            active: true,
+        }
+    }
+}
+
+// System to note the run's modifiers in the log as it starts; a replay skips the menu they're picked on
+pub fn record_run_modifiers(modifiers: Res<RunModifiers>, mut run_log: ResMut<RunLog>) {
+    run_log.modifiers = modifiers.active.clone();
+}
+
+/// Per-system bookkeeping for the recorder
+#[derive(Default)]
+pub struct RecorderState {
+    seen_seeds: HashSet<u64>,
+    last_turn: u32,
+    turn_start_position: Option<(i32, i32)>,
+    pressed_since_turn: bool,
+}
+
+// System to record generated levels and every action key into the run log
+pub fn record_run_system(
+    keyboard: Res<Input<KeyCode>>,
+    tile_click: Res<TileClick>,
+    game_turn: Res<GameTurn>,
+    map: Res<TileMap>,
+    player_query: Query<&Position, With<Player>>,
+    mut run_log: ResMut<RunLog>,
+    mut state: Local<RecorderState>,
+) {
+    // Revisited levels are restored rather than regenerated, so only new seeds are logged
+    if state.seen_seeds.insert(map.seed) {
+        if run_log.initial_seed.is_none() {
+            run_log.initial_seed = Some(map.seed);
+        } else {
+            run_log.levels.push(LevelSeed {
+                turn: game_turn.current_turn,
+                level: map.current_level,
+                seed: map.seed,
+            });
+        }
+    }
+
+    let pressed: Vec<KeyCode> = RECORDED_KEYS.iter()
+        .map(|(key, _)| *key)
+        .filter(|key| !MODIFIER_KEYS.contains(key) && keyboard.just_pressed(*key))
+        .collect();
+    if !pressed.is_empty() || tile_click.tile.is_some() {
+        let mut keys: Vec<String> = MODIFIER_KEYS.iter()
+            .filter(|key| !pressed.is_empty() && keyboard.pressed(**key))
+            .chain(pressed.iter())
+            .filter_map(|key| key_name(*key))
+            .map(String::from)
+            .collect();
+        keys.dedup();
+        run_log.inputs.push(RunInput { turn: game_turn.current_turn, keys, click: tile_click.tile });
+        state.pressed_since_turn = true;
+    }
+
+    let position = player_query.get_single().ok().map(|position| (position.x, position.y));
+    if game_turn.current_turn != state.last_turn {
+        // Holding a movement key walks on without new presses; log those steps as presses too
+        if !state.pressed_since_turn {
+            if let (Some((x, y)), Some((last_x, last_y))) = (position, state.turn_start_position) {
+                let key = match (x - last_x, y - last_y) {
+                    (0, 1) => Some("W"),
+                    (0, -1) => Some("S"),
+                    (-1, 0) => Some("A"),
+                    (1, 0) => Some("D"),
+                    _ => None,
+                };
+                if let Some(key) = key {
+                    run_log.inputs.push(RunInput { turn: state.last_turn, keys: vec![key.to_string()], click: None });
+                }
+            }
+        }
+        state.last_turn = game_turn.current_turn;
+        state.pressed_since_turn = false;
+        state.turn_start_position = position;
+    } else if state.turn_start_position.is_none() {
+        state.turn_start_position = position;
+    }
+}
+
+// System to write the run log to disk (Ctrl+L)
+pub fn export_run_log_system(
+    keyboard: Res<Input<KeyCode>>,
+    run_log: Res<RunLog>,
+    mut message_log: ResMut<MessageLog>,
+) {
+    if !(keyboard.pressed(KeyCode::ControlLeft) && keyboard.just_pressed(KeyCode::L)) {
+        return;
+    }
+
+    match run_log.export() {
+        Ok(path) => {
+            println!("Exported run log to {}", path.display());
+            message_log.add_message(format!("Run log saved to {}", path.display()));
+        }
+        Err(e) => {
+            eprintln!("Could not export run log: {}", e);
+            message_log.add_message("Could not save the run log.".to_string());
+        }
+    }
+}
+
+// System to feed a recorded run back in as key presses and clicks, one action at a time.
+// Runs right after Bevy's input update so the game sees them like real presses.
+pub fn replay_run_system(
+    mut replay: ResMut<RunReplay>,
+    mut keyboard: ResMut<Input<KeyCode>>,
+    mut tile_click: ResMut<TileClick>,
+    game_turn: Res<GameTurn>,
+    animation_state: Res<AnimationState>,
+) {
+    if !replay.active {
+        return;
+    }
+    // Only recorded clicks count while replaying
+    tile_click.tile = None;
+
+    // Let go of the last chord before pressing the next so it registers as a fresh press
+    if !replay.held.is_empty() {
+        for key in std::mem::take(&mut replay.held) {
+            keyboard.release(key);
+        }
+        return;
+    }
+
+    // Wait for the game to reach the recorded turn and finish moving
+    let ready = replay.inputs.front()
+        .map_or(false, |input| input.turn <= game_turn.current_turn && !animation_state.animation_in_progress);
+    if !ready {
+        if replay.inputs.is_empty() {
+            println!("Replay finished on turn {}", game_turn.current_turn);
+            replay.active = false;
+        }
+        return;
+    }
+
+    if let Some(input) = replay.inputs.pop_front() {
+        for key in input.keys.iter().filter_map(|name| key_from_name(name)) {
+            keyboard.press(key);
+            replay.held.push(key);
+        }
+        tile_click.tile = input.click;
+    }
+}
@@ -0,0 +1,189 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::combat::Health;
+use crate::components::Player;
+use crate::gold::Purse;
+use crate::inventory::{Inventory, ItemKind};
+use crate::lighting::LightSource;
+use crate::visibility::Vision;
+
+// What a rich start adds to the pack and purse
+const RICH_START_ITEMS: [ItemKind; 4] = [ItemKind::HealingPotion, ItemKind::HealingPotion, ItemKind::ManaPotion, ItemKind::Dagger];
+const RICH_START_GOLD: u32 = 100;
+
+/// A rule that changes how a run plays, picked on the main menu before descending
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RunModifier {
+    NoNpcs,         // Nobody to talk to, trade with or learn from
+    DoubleMonsters, // Twice as many creatures on every floor
+    Darkness,       // The torch lights half as far, and the player sees half as far
+    Fragile,        // A single hit point
+    RichStart,      // Potions, a dagger and a purse of gold from the start
+}
+
+impl RunModifier {
+    pub const ALL: [RunModifier; 5] = [
+        RunModifier::NoNpcs,
+        RunModifier::DoubleMonsters,
+        RunModifier::Darkness,
+        RunModifier::Fragile,
+        RunModifier::RichStart,
+    ];
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            RunModifier::NoNpcs => "No NPCs",
+            RunModifier::DoubleMonsters => "Double Monsters",
+            RunModifier::Darkness => "Darkness",
+            RunModifier::Fragile => "Fragile",
+            RunModifier::RichStart => "Rich Start",
+        }
+    }
+
+    // Percentage added to (or taken off) the score for playing with it
+    pub fn score_bonus(&self) -> i32 {
+        match self {
+            RunModifier::NoNpcs => 10,
+            RunModifier::DoubleMonsters => 50,
+            RunModifier::Darkness => 30,
+            RunModifier::Fragile => 100,
+            RunModifier::RichStart => -25,
+        }
+    }
+
+    // The number key that toggles it on the main menu
+    pub fn key(&self) -> KeyCode {
+        match self {
+            RunModifier::NoNpcs => KeyCode::Key1,
+            RunModifier::DoubleMonsters => KeyCode::Key2,
+            RunModifier::Darkness => KeyCode::Key3,
+            RunModifier::Fragile => KeyCode::Key4,
+            RunModifier::RichStart => KeyCode::Key5,
+        }
+    }
+}
+
+/// The modifiers chosen for this run; spawning, lighting, the player's starting state and the score all check it
+#[derive(Resource, Debug, Clone, Default)]
+pub struct RunModifiers {
+    pub active: Vec<RunModifier>,
+}
+
+impl RunModifiers {
+    pub fn has(&self, modifier: RunModifier) -> bool {
+        self.active.contains(&modifier)
+    }
+
+    pub fn toggle(&mut self, modifier: RunModifier) {
+        if let Some(index) = self.active.iter().position(|&active| active == modifier) {
+            self.active.remove(index);
+        } else {
+            self.active.push(modifier);
+        }
+    }
+
+    // How many creatures each floor gets for every one it would normally have
+    pub fn creature_multiplier(&self) -> usize {
+        if self.has(RunModifier::DoubleMonsters) { 2 } else { 1 }
+    }
+
+    // The score as a percentage of normal; never below zero
+    pub fn score_percent(&self) -> u32 {
+        (100 + self.active.iter().map(|modifier| modifier.score_bonus()).sum::<i32>()).max(0) as u32
+    }
+
+    // Names of the active modifiers, in the order they were chosen
+    pub fn names(&self) -> Vec<String> {
+        self.active.iter().map(|modifier| modifier.get_name().to_string()).collect()
+    }
+}
+
+/// Marker for the list of modifiers on the main menu
+#[derive(Component)]
+pub struct ModifierMenuText;
+
+/// Marker for the HUD line naming the active modifiers
+#[derive(Component)]
+pub struct ModifierHudText;
+
+// The main menu's list of modifiers, each marked if chosen
+pub fn modifier_menu_sections(modifiers: &RunModifiers, font: &Handle<Font>) -> Vec<TextSection> {
+    let mut sections = vec![TextSection::new(
+        "Modifiers\n",
+        TextStyle { font: font.clone(), font_size: 20.0, color: Color::GOLD },
+    )];
+    for (index, modifier) in RunModifier::ALL.iter().enumerate() {
+        let chosen = modifiers.has(*modifier);
+        sections.push(TextSection::new(
+            format!("{} [{}] {} ({:+}%)\n", index + 1, if chosen { "x" } else { " " }, modifier.get_name(), modifier.score_bonus()),
+            TextStyle { font: font.clone(), font_size: 18.0, color: if chosen { Color::WHITE } else { Color::GRAY } },
+        ));
+    }
+    sections
+}
+
+// System to redraw the main menu's list when a modifier is toggled
+pub fn update_modifier_menu(
+    modifiers: Res<RunModifiers>,
+    asset_server: Res<AssetServer>,
+    mut text_query: Query<&mut Text, With<ModifierMenuText>>,
+) {
+    if !modifiers.is_changed() {
+        return;
+    }
+    let font = asset_server.load("fonts/FiraSans-Light.ttf");
+    for mut text in text_query.iter_mut() {
+        text.sections = modifier_menu_sections(&modifiers, &font);
+    }
+}
+
+// System to set the player up as the modifiers ask, once the run's world has been spawned
+pub fn apply_starting_modifiers(
+    modifiers: Res<RunModifiers>,
+    mut purse: ResMut<Purse>,
+    mut player_query: Query<(&mut Health, &mut Inventory, &mut LightSource, &mut Vision), With<Player>>,
+) {
+    let (mut health, mut inventory, mut light, mut vision) = if let Ok(player) = player_query.get_single_mut() { player } else { return; };
+    if modifiers.has(RunModifier::Fragile) {
+        health.max = 1;
+        health.current = 1;
+    }
+    if modifiers.has(RunModifier::Darkness) {
+        light.radius = (light.radius / 2).max(1);
+        vision.radius = (vision.radius / 2.0).max(1.0); // Sight decides what's revealed, so the fog closes in too
+    }
+    if modifiers.has(RunModifier::RichStart) {
+        for item in RICH_START_ITEMS {
+            inventory.add(item);
+        }
+        purse.add(RICH_START_GOLD);
+    }
+    if !modifiers.active.is_empty() {
+        println!("Run modifiers: {}", modifiers.names().join(", "));
+    }
+}
+
+// Name the active modifiers under the purse
+pub fn setup_modifier_hud(mut commands: Commands, asset_server: Res<AssetServer>, modifiers: Res<RunModifiers>) {
+    if modifiers.active.is_empty() {
+        return;
+    }
+    commands.spawn((
+        TextBundle::from_section(
+            modifiers.names().join(" | "),
+            TextStyle {
+                font: asset_server.load("fonts/FiraSans-Light.ttf"),
+                font_size: 16.0,
+                color: Color::rgb(1.0, 0.5, 0.4),
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(58.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+        ModifierHudText,
+    ));
+}
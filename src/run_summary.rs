@@ -0,0 +1,238 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+use crate::combat::Health;
+use crate::components::{GameTurn, Player};
+use crate::daily::{load_daily_history, runs_on, save_daily_history, DailyChallenge, DailyRecord};
+use crate::events::{EntityDamaged, LevelChanged};
+use crate::run_modifiers::RunModifiers;
+use crate::scoring::{score_run, ScoreInput};
+use crate::level::DungeonState;
+use crate::GameState;
+
+/// File every finished run, won or lost, is recorded in
+pub const RUN_HISTORY_PATH: &str = "run_history.json";
+// How many runs the high-score table shows
+pub const MAX_HIGH_SCORES: usize = 10;
+
+/// Running totals for the current run
+#[derive(Resource, Default, Debug, Clone)]
+pub struct RunStats {
+    pub deepest_level: usize,        // Zero-based, like TileMap::current_level
+    pub kills: u32,
+    pub gold: u32,
+    pub last_hit_by: Option<String>, // Whatever last hurt the player, for the cause of death
+}
+
+/// How a run came to an end
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RunOutcome {
+    Escaped,               // Climbed back out to the surface
+    Died { cause: String }, // e.g. "a Wolf" or "poison"
+}
+
+impl RunOutcome {
+    pub fn describe(&self) -> String {
+        match self {
+            RunOutcome::Escaped => "Escaped".to_string(),
+            RunOutcome::Died { cause } => format!("Killed by {}", cause),
+        }
+    }
+}
+
+/// Sent when the run is over
+#[derive(Event, Debug, Clone)]
+pub struct RunEnded {
+    pub outcome: RunOutcome,
+}
+
+/// One finished run in the history file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub seed: u64,
+    pub outcome: RunOutcome,
+    pub depth: usize, // Deepest floor reached, counting from 1
+    pub turns: u32,
+    pub kills: u32,
+    pub gold: u32,
+    pub score: u32,
+    pub timestamp: u64,
+    #[serde(default)]
+    pub modifiers: Vec<String>, // Names of the run modifiers it was played with
+}
+
+/// The finished run, as shown on the summary screen
+#[derive(Resource, Debug, Clone)]
+pub struct RunSummary {
+    pub record: RunRecord,
+    pub rank: Option<usize>, // Place in the high-score table, if it made it in
+    pub table: Vec<RunRecord>,
+}
+
+// Read every recorded run, oldest first, treating a missing or unreadable file as empty
+pub fn load_run_history() -> Vec<RunRecord> {
+    match fs::read_to_string(RUN_HISTORY_PATH) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+            eprintln!("Ignoring unreadable {}: {}", RUN_HISTORY_PATH, e);
+            Vec::new()
+        }),
+        Err(_) => Vec::new(),
+    }
+}
+
+fn save_run_history(history: &[RunRecord]) -> Result<(), String> {
+    let contents = serde_json::to_string_pretty(history).map_err(|e| e.to_string())?;
+    fs::write(RUN_HISTORY_PATH, contents).map_err(|e| format!("could not write {}: {}", RUN_HISTORY_PATH, e))
+}
+
+// The best runs, highest score first; ties go to the earlier run
+pub fn high_scores(history: &[RunRecord]) -> Vec<RunRecord> {
+    let mut table = history.to_vec();
+    table.sort_by(|a, b| b.score.cmp(&a.score).then(a.timestamp.cmp(&b.timestamp)));
+    table.truncate(MAX_HIGH_SCORES);
+    table
+}
+
+// System to keep track of the deepest floor reached
+pub fn track_depth_system(mut level_events: EventReader<LevelChanged>, mut run_stats: ResMut<RunStats>) {
+    for event in level_events.read() {
+        run_stats.deepest_level = run_stats.deepest_level.max(event.to);
+    }
+}
+
+// System to remember whatever last hurt the player, for the cause of death
+pub fn record_damage_source_system(
+    mut damage_events: EventReader<EntityDamaged>,
+    player_query: Query<Entity, With<Player>>,
+    mut run_stats: ResMut<RunStats>,
+) {
+    let player = if let Ok(player) = player_query.get_single() { player } else { return; };
+    for event in damage_events.read().filter(|event| event.target == player) {
+        run_stats.last_hit_by = Some(event.source.clone());
+    }
+}
+
+// System to end the run once the player's health runs out
+pub fn detect_player_death_system(
+    player_query: Query<&Health, With<Player>>,
+    run_stats: Res<RunStats>,
+    mut run_ended: EventWriter<RunEnded>,
+) {
+    if player_query.get_single().map_or(false, |health| health.is_dead()) {
+        let cause = run_stats.last_hit_by.clone().unwrap_or_else(|| "unknown causes".to_string());
+        run_ended.send(RunEnded { outcome: RunOutcome::Died { cause } });
+    }
+}
+
+// System to wrap the run up: score it, add it to the history and show the summary
+pub fn finish_run_system(
+    mut commands: Commands,
+    mut run_ended: EventReader<RunEnded>,
+    run_stats: Res<RunStats>,
+    game_turn: Res<GameTurn>,
+    dungeon_state: Res<DungeonState>,
+    daily: Res<DailyChallenge>,
+    modifiers: Res<RunModifiers>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let outcome = if let Some(event) = run_ended.read().last() { event.outcome.clone() } else { return; };
+
+    let depth = run_stats.deepest_level + 1;
+    let turns = game_turn.current_turn;
+    let score = score_run(ScoreInput {
+        depth,
+        turns,
+        kills: run_stats.kills,
+        gold: run_stats.gold,
+        escaped: outcome == RunOutcome::Escaped,
+        modifier_percent: modifiers.score_percent(),
+    });
+    let record = RunRecord {
+        seed: dungeon_state.levels.first().map_or(0, |level| level.seed),
+        outcome,
+        depth,
+        turns,
+        kills: run_stats.kills,
+        gold: run_stats.gold,
+        score,
+        timestamp: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        modifiers: modifiers.names(),
+    };
+
+    // Daily runs are only ranked against others on the same dungeon
+    let table = if daily.active {
+        let mut history = load_daily_history();
+        history.push(DailyRecord { date: daily.date.clone(), record: record.clone() });
+        if let Err(e) = save_daily_history(&history) {
+            eprintln!("Could not save daily run history: {}", e);
+        }
+        high_scores(&runs_on(&history, &daily.date))
+    } else {
+        let mut history = load_run_history();
+        history.push(record.clone());
+        if let Err(e) = save_run_history(&history) {
+            eprintln!("Could not save run history: {}", e);
+        }
+        high_scores(&history)
+    };
+    let rank = table.iter().position(|other| *other == record);
+
+    println!("Run over ({}) with a score of {}", record.outcome.describe(), record.score);
+    commands.insert_resource(RunSummary { record, rank, table });
+    next_state.set(GameState::RunOver);
+}
+
+// Show the end-of-run screen
+pub fn setup_run_summary_screen(
+    mut commands: Commands,
+    summary: Res<RunSummary>,
+    asset_server: Res<AssetServer>,
+) {
+    let font = asset_server.load("fonts/FiraSans-Light.ttf");
+    let style = |size: f32, color: Color| TextStyle { font: font.clone(), font_size: size, color };
+
+    let record = &summary.record;
+    let title = match &record.outcome {
+        RunOutcome::Escaped => "You climb out of the chasm into daylight".to_string(),
+        RunOutcome::Died { cause } => format!("You were killed by {}", cause),
+    };
+    let modifiers = if record.modifiers.is_empty() { String::new() } else { format!("\nModifiers: {}", record.modifiers.join(", ")) };
+    let stats = format!(
+        "Deepest floor: {}\nTurns taken: {}\nKills: {}\nGold: {}{}\n\nScore: {}",
+        record.depth, record.turns, record.kills, record.gold, modifiers, record.score
+    );
+
+    let mut table_sections = vec![TextSection::new("High Scores\n", style(22.0, Color::GOLD))];
+    for (index, other) in summary.table.iter().enumerate() {
+        let color = if summary.rank == Some(index) { Color::GOLD } else { Color::rgb(0.8, 0.8, 0.8) };
+        table_sections.push(TextSection::new(
+            format!("{:>2}. {:>6}   floor {:>2}   {}\n", index + 1, other.score, other.depth, other.outcome.describe()),
+            style(16.0, color),
+        ));
+    }
+
+    commands.spawn(NodeBundle {
+        style: Style {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(24.0),
+            ..default()
+        },
+        background_color: BackgroundColor(Color::rgba(0.0, 0.0, 0.0, 0.92)),
+        z_index: ZIndex::Global(200),
+        ..default()
+    })
+    .with_children(|parent| {
+        parent.spawn(TextBundle::from_section(title, style(32.0, Color::WHITE)));
+        parent.spawn(TextBundle::from_section(stats, style(20.0, Color::WHITE)).with_text_alignment(TextAlignment::Center));
+        parent.spawn(TextBundle::from_sections(table_sections));
+        parent.spawn(TextBundle::from_section("Press Esc to quit", style(16.0, Color::GRAY)));
+    });
+}
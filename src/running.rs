@@ -0,0 +1,297 @@
+use bevy::prelude::*;
+
+use crate::components::{Animal, Companion, MovementDirection, Npc, Player, Position};
+use crate::conversation::Conversation;
+use crate::events::EntityDamaged;
+use crate::input::InputState;
+use crate::level::DungeonState;
+use crate::map::{TileMap, TileType};
+use crate::pathmaps::{DistanceMap, PathMaps};
+use crate::player::AnimationState;
+use crate::ui::MessageLog;
+use crate::visibility::line_of_sight;
+
+// Creatures further away than this don't interrupt a run
+const RUN_SIGHT_RANGE: i32 = 8;
+// Give up eventually, even down the longest corridor
+const RUN_MAX_STEPS: u32 = 100;
+
+/// A run in progress: the player keeps stepping one way until something worth stopping for,
+/// or, when exploring, keeps heading for the nearest unexplored ground, or, when travelling, for a bookmark
+#[derive(Resource, Default)]
+pub struct RunState {
+    pub direction: Option<MovementDirection>,
+    pub exploring: bool,
+    travel: Option<DistanceMap>,           // The way to the bookmark being walked to
+    next_bookmark: usize,                  // Which of this floor's bookmarks B heads for next
+    steps: u32,
+    sides: Option<(bool, bool)>, // Whether the tiles to the left and right were open on the last step
+    seen: Vec<Entity>,           // Creatures already in view when the run started
+}
+
+impl RunState {
+    pub fn is_running(&self) -> bool {
+        self.direction.is_some() || self.exploring || self.travel.is_some()
+    }
+
+    fn stop(&mut self) {
+        self.direction = None;
+        self.exploring = false;
+        self.travel = None;
+        self.steps = 0;
+        self.sides = None;
+        self.seen.clear();
+    }
+}
+
+fn offset(direction: MovementDirection) -> (i32, i32) {
+    match direction {
+        MovementDirection::Up => (0, 1),
+        MovementDirection::Down => (0, -1),
+        MovementDirection::Left => (-1, 0),
+        MovementDirection::Right => (1, 0),
+    }
+}
+
+// The direction that steps from one tile to a neighbouring one
+fn direction_between(from: (i32, i32), to: (i32, i32)) -> Option<MovementDirection> {
+    [MovementDirection::Up, MovementDirection::Down, MovementDirection::Left, MovementDirection::Right]
+        .into_iter()
+        .find(|&direction| offset(direction) == (to.0 - from.0, to.1 - from.1))
+}
+
+fn pressed_direction(keyboard: &Input<KeyCode>) -> Option<MovementDirection> {
+    if keyboard.just_pressed(KeyCode::W) || keyboard.just_pressed(KeyCode::Up) {
+        Some(MovementDirection::Up)
+    } else if keyboard.just_pressed(KeyCode::S) || keyboard.just_pressed(KeyCode::Down) {
+        Some(MovementDirection::Down)
+    } else if keyboard.just_pressed(KeyCode::A) || keyboard.just_pressed(KeyCode::Left) {
+        Some(MovementDirection::Left)
+    } else if keyboard.just_pressed(KeyCode::D) || keyboard.just_pressed(KeyCode::Right) {
+        Some(MovementDirection::Right)
+    } else {
+        None
+    }
+}
+
+fn is_open(map: &TileMap, x: i32, y: i32) -> bool {
+    map.in_bounds(x, y) && map.tiles[y as usize][x as usize] != TileType::Wall && !map.prop_blocks(x, y)
+}
+
+// Creatures the player can see from a tile
+fn creatures_in_view<'a>(
+    map: &TileMap,
+    from: &Position,
+    creatures: impl Iterator<Item = (Entity, &'a Position)>,
+) -> Vec<Entity> {
+    creatures
+        .filter(|(_, pos)| (pos.x - from.x).abs().max((pos.y - from.y).abs()) <= RUN_SIGHT_RANGE)
+        .filter(|(_, pos)| line_of_sight(map, (from.x, from.y), (pos.x, pos.y)))
+        .map(|(entity, _)| entity)
+        .collect()
+}
+
+// Why a run should end on this tile, if it should
+fn stop_reason(map: &TileMap, pos: &Position, direction: MovementDirection, run: &RunState, sides: (bool, bool)) -> Option<&'static str> {
+    let (dx, dy) = offset(direction);
+    let (ahead_x, ahead_y) = (pos.x + dx, pos.y + dy);
+    let tile = (pos.x as usize, pos.y as usize);
+
+    if matches!(map.tiles[tile.1][tile.0], TileType::StairsDown | TileType::StairsUp) {
+        return Some("stairs");
+    }
+    if (map.in_bounds(ahead_x, ahead_y) && map.tiles[ahead_y as usize][ahead_x as usize] == TileType::Door)
+        || map.tiles[tile.1][tile.0] == TileType::Door
+    {
+        return Some("a doorway");
+    }
+    let near_chest = map.chest_positions.iter().any(|&(x, y)| (x as i32 - pos.x).abs() <= 1 && (y as i32 - pos.y).abs() <= 1);
+    if near_chest {
+        return Some("a chest");
+    }
+    if !is_open(map, ahead_x, ahead_y) {
+        return Some("the way ahead is blocked");
+    }
+    // Side passages opening or closing mean a junction, a room's edge, or the end of a wall
+    if run.sides.map_or(false, |last| last != sides) {
+        return Some("a junction");
+    }
+    None
+}
+
+// System to start, continue and interrupt runs, feeding the normal one-step movement each turn.
+// O starts auto-explore, which follows the frontier distance map until something interrupts it;
+// B travels to this floor's bookmarks, each press heading for the next one
+pub fn run_system(
+    keyboard: Res<Input<KeyCode>>,
+    mut run: ResMut<RunState>,
+    mut input_state: ResMut<InputState>,
+    animation_state: Res<AnimationState>,
+    conversation: Res<Conversation>,
+    map: Res<TileMap>,
+    path_maps: Res<PathMaps>,
+    player_query: Query<(Entity, &Position), With<Player>>,
+    creature_query: Query<(Entity, &Position), (Or<(With<Npc>, With<Animal>)>, Without<Companion>, Without<Player>)>,
+    mut damage_events: EventReader<EntityDamaged>,
+    dungeon_state: Res<DungeonState>,
+    mut message_log: ResMut<MessageLog>,
+) {
+    let (player, pos) = if let Ok(player) = player_query.get_single() { player } else { return; };
+    let hurt = damage_events.read().any(|event| event.target == player);
+    let shift = keyboard.pressed(KeyCode::ShiftLeft);
+
+    // Shift+direction starts a run; the ordinary step this frame is its first
+    if let Some(direction) = pressed_direction(&keyboard) {
+        run.stop();
+        if shift && !conversation.awaiting_choice() && !input_state.aiming {
+            run.direction = Some(direction);
+            run.seen = creatures_in_view(&map, pos, creature_query.iter());
+        }
+        return;
+    }
+    if keyboard.just_pressed(KeyCode::O) && !conversation.awaiting_choice() && !input_state.aiming {
+        run.stop();
+        run.exploring = true;
+        run.seen = creatures_in_view(&map, pos, creature_query.iter());
+    }
+    if keyboard.just_pressed(KeyCode::B) && !conversation.awaiting_choice() && !input_state.aiming {
+        run.stop();
+        let bookmarks = dungeon_state.current_bookmarks();
+        if bookmarks.is_empty() {
+            message_log.add_message("No bookmarks on this floor to travel to (X to add one).".to_string());
+        } else {
+            let bookmark = &bookmarks[run.next_bookmark % bookmarks.len()];
+            run.next_bookmark = (run.next_bookmark + 1) % bookmarks.len();
+            message_log.add_message(format!("Travelling to: {}", bookmark.name));
+            run.travel = Some(DistanceMap::new(&map, &[bookmark.tile]));
+            run.seen = creatures_in_view(&map, pos, creature_query.iter());
+        }
+    }
+
+    if !run.is_running() {
+        return;
+    }
+    if hurt || conversation.awaiting_choice() || input_state.aiming {
+        run.stop();
+        return;
+    }
+    // Wait for the current step to land
+    if animation_state.animation_in_progress || !map.in_bounds(pos.x, pos.y) {
+        return;
+    }
+
+    if run.exploring {
+        explore_step(&mut run, &mut input_state, &map, &path_maps, pos, creature_query.iter(), &mut message_log);
+        return;
+    }
+    if run.travel.is_some() {
+        travel_step(&mut run, &mut input_state, &map, pos, creature_query.iter(), &mut message_log);
+        return;
+    }
+    let direction = if let Some(direction) = run.direction { direction } else { return; };
+
+    let (dx, dy) = offset(direction);
+    let sides = (is_open(&map, pos.x - dy, pos.y + dx), is_open(&map, pos.x + dy, pos.y - dx));
+    let newly_seen = creatures_in_view(&map, pos, creature_query.iter()).into_iter().any(|entity| !run.seen.contains(&entity));
+
+    let reason = if newly_seen {
+        Some("something comes into view")
+    } else if run.steps >= RUN_MAX_STEPS {
+        Some("you're out of breath")
+    } else {
+        stop_reason(&map, pos, direction, &run, sides)
+    };
+    if let Some(reason) = reason {
+        if run.steps > 0 && newly_seen {
+            message_log.add_message("You stop running: something comes into view.".to_string());
+        }
+        println!("Run stopped after {} steps: {}", run.steps, reason);
+        run.stop();
+        return;
+    }
+
+    run.sides = Some(sides);
+    run.steps += 1;
+    press(&mut input_state, direction);
+}
+
+fn press(input_state: &mut InputState, direction: MovementDirection) {
+    match direction {
+        MovementDirection::Up => input_state.up = true,
+        MovementDirection::Down => input_state.down = true,
+        MovementDirection::Left => input_state.left = true,
+        MovementDirection::Right => input_state.right = true,
+    }
+}
+
+// Take one auto-explore step downhill towards the nearest unexplored tile, or say why not
+fn explore_step<'a>(
+    run: &mut RunState,
+    input_state: &mut InputState,
+    map: &TileMap,
+    path_maps: &PathMaps,
+    pos: &Position,
+    creatures: impl Iterator<Item = (Entity, &'a Position)>,
+    message_log: &mut MessageLog,
+) {
+    let newly_seen = creatures_in_view(map, pos, creatures).into_iter().any(|entity| !run.seen.contains(&entity));
+    let next = path_maps.to_frontier.downhill_from(pos.x, pos.y).and_then(|next| direction_between((pos.x, pos.y), next));
+
+    let reason = if newly_seen {
+        Some("something comes into view")
+    } else if run.steps >= RUN_MAX_STEPS {
+        Some("you're out of breath")
+    } else if next.is_none() {
+        Some("there's nothing left to explore")
+    } else {
+        None
+    };
+    if let Some(reason) = reason {
+        message_log.add_message(format!("You stop exploring: {}.", reason));
+        println!("Auto-explore stopped after {} steps: {}", run.steps, reason);
+        run.stop();
+        return;
+    }
+
+    run.steps += 1;
+    if let Some(direction) = next {
+        press(input_state, direction);
+    }
+}
+
+// Take one step downhill towards the bookmark being travelled to, or say why not
+fn travel_step<'a>(
+    run: &mut RunState,
+    input_state: &mut InputState,
+    map: &TileMap,
+    pos: &Position,
+    creatures: impl Iterator<Item = (Entity, &'a Position)>,
+    message_log: &mut MessageLog,
+) {
+    let way = if let Some(way) = &run.travel { way } else { return; };
+    let newly_seen = creatures_in_view(map, pos, creatures).into_iter().any(|entity| !run.seen.contains(&entity));
+    let next = way.downhill_from(pos.x, pos.y).and_then(|next| direction_between((pos.x, pos.y), next));
+
+    let reason = if way.get(pos.x, pos.y) == Some(0) {
+        Some("you've arrived")
+    } else if newly_seen {
+        Some("something comes into view")
+    } else if run.steps >= RUN_MAX_STEPS {
+        Some("you're out of breath")
+    } else if next.is_none() {
+        Some("there's no way there from here")
+    } else {
+        None
+    };
+    if let Some(reason) = reason {
+        message_log.add_message(format!("You stop travelling: {}.", reason));
+        println!("Travel stopped after {} steps: {}", run.steps, reason);
+        run.stop();
+        return;
+    }
+
+    run.steps += 1;
+    if let Some(direction) = next {
+        press(input_state, direction);
+    }
+}
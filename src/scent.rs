@@ -0,0 +1,74 @@
+use bevy::prelude::*;
+
+use crate::components::{GameTurn, Player, Position};
+use crate::map::TileMap;
+
+// Scent the player leaves on their tile each turn
+const FRESH_SCENT: f32 = 1.0;
+// Fraction of the scent left on a tile after each turn
+const SCENT_DECAY: f32 = 0.9;
+// Scent fainter than this can't be followed
+pub const MIN_SCENT: f32 = 0.05;
+
+/// How strongly the player's scent lingers on each tile
+#[derive(Resource, Default)]
+pub struct ScentMap {
+    pub strength: Vec<Vec<f32>>,
+    seed: u64, // The level the trail was laid on, so changing floors starts a fresh one
+}
+
+impl ScentMap {
+    // Forget the trail when a different level is loaded
+    pub fn fit_to(&mut self, map: &TileMap) {
+        if self.seed != map.seed || self.strength.len() != map.height || self.strength.first().map_or(0, |row| row.len()) != map.width {
+            self.strength = vec![vec![0.0; map.width]; map.height];
+            self.seed = map.seed;
+        }
+    }
+
+    pub fn get(&self, x: i32, y: i32) -> f32 {
+        if x < 0 || y < 0 {
+            return 0.0;
+        }
+        self.strength.get(y as usize).and_then(|row| row.get(x as usize)).copied().unwrap_or(0.0)
+    }
+
+    // The neighbouring tile with the strongest scent, if it's stronger than here
+    pub fn uphill_from(&self, x: i32, y: i32) -> Option<(i32, i32)> {
+        let here = self.get(x, y);
+        [(0, 1), (1, 0), (0, -1), (-1, 0)].iter()
+            .map(|(dx, dy)| (x + dx, y + dy))
+            .filter(|&(nx, ny)| self.get(nx, ny) >= MIN_SCENT && self.get(nx, ny) > here)
+            .max_by(|a, b| self.get(a.0, a.1).total_cmp(&self.get(b.0, b.1)))
+    }
+}
+
+// System to fade the trail each turn and lay fresh scent where the player stands
+pub fn update_scent_system(
+    game_turn: Res<GameTurn>,
+    map: Res<TileMap>,
+    player_query: Query<&Position, With<Player>>,
+    mut scent_map: ResMut<ScentMap>,
+    mut local: Local<u32>,
+) {
+    scent_map.fit_to(&map);
+
+    if game_turn.current_turn == *local {
+        return;
+    }
+    let turns_passed = game_turn.current_turn.saturating_sub(*local).max(1);
+    *local = game_turn.current_turn;
+
+    let decay = SCENT_DECAY.powi(turns_passed as i32);
+    for row in scent_map.strength.iter_mut() {
+        for strength in row.iter_mut() {
+            *strength *= decay;
+        }
+    }
+
+    if let Ok(position) = player_query.get_single() {
+        if map.in_bounds(position.x, position.y) {
+            scent_map.strength[position.y as usize][position.x as usize] = FRESH_SCENT;
+        }
+    }
+}
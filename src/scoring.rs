@@ -0,0 +1,33 @@
+// How a run's score is worked out from how it went
+
+// Points for each floor reached, counting from 1
+const POINTS_PER_FLOOR: u32 = 100;
+const POINTS_PER_KILL: u32 = 25;
+// Each gold piece is worth a point
+const POINTS_PER_GOLD: u32 = 1;
+// Making it back to the surface alive
+const ESCAPE_BONUS: u32 = 500;
+// One point is lost for every this many turns, so quicker runs score higher
+const TURNS_PER_PENALTY_POINT: u32 = 10;
+
+/// Everything a run's score depends on
+#[derive(Debug, Clone, Copy)]
+pub struct ScoreInput {
+    pub depth: usize, // Deepest floor reached, counting from 1
+    pub turns: u32,
+    pub kills: u32,
+    pub gold: u32,
+    pub escaped: bool,
+    pub modifier_percent: u32, // The run modifiers' scaling, 100 for none
+}
+
+/// Score a run; never negative
+pub fn score_run(input: ScoreInput) -> u32 {
+    let escape_bonus = if input.escaped { ESCAPE_BONUS } else { 0 };
+    let earned = input.depth as u32 * POINTS_PER_FLOOR
+        + input.kills * POINTS_PER_KILL
+        + input.gold * POINTS_PER_GOLD
+        + escape_bonus;
+    let base = earned.saturating_sub(input.turns / TURNS_PER_PENALTY_POINT);
+    (base as u64 * input.modifier_percent as u64 / 100) as u32
+}
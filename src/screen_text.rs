@@ -0,0 +1,125 @@
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+use crate::camera::CameraControl;
+use crate::components::DialogBox;
+use crate::dialog_box::DialogText;
+use crate::display_settings::DisplaySettings;
+use crate::inspect::InspectTooltip;
+
+// Box each label is centred along the bottom of, in screen pixels; wide and tall enough for any speech box
+const LABEL_AREA: Vec2 = Vec2::new(480.0, 400.0);
+const DIALOG_FONT_SIZE: f32 = 16.0;
+const TOOLTIP_FONT_SIZE: f32 = 14.0;
+const DIALOG_BACKGROUND: Color = Color::rgba(0.2, 0.2, 0.2, 0.85);
+const TOOLTIP_BACKGROUND: Color = Color::rgba(0.0, 0.0, 0.0, 0.6);
+
+/// A UI copy of a world-space dialog box or tooltip, drawn at the same size however far the camera is zoomed out
+#[derive(Component, Debug)]
+pub struct ScreenText {
+    source: Entity,
+}
+
+/// The text inside a `ScreenText`
+#[derive(Component)]
+pub struct ScreenTextLabel;
+
+// Place a label's area so its bottom centre sits on the projected point
+fn label_position(style: &mut Style, point: Vec2) {
+    let left = Val::Px(point.x - LABEL_AREA.x / 2.0);
+    let top = Val::Px(point.y - LABEL_AREA.y);
+    if style.left != left || style.top != top {
+        style.left = left;
+        style.top = top;
+    }
+}
+
+fn spawn_label(commands: &mut Commands, source: Entity, text: &str, point: Vec2, font: Handle<Font>, dialog: bool) {
+    let mut style = Style {
+        position_type: PositionType::Absolute,
+        width: Val::Px(LABEL_AREA.x),
+        height: Val::Px(LABEL_AREA.y),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::FlexEnd,
+        ..default()
+    };
+    label_position(&mut style, point);
+    let (font_size, background) = if dialog { (DIALOG_FONT_SIZE, DIALOG_BACKGROUND) } else { (TOOLTIP_FONT_SIZE, TOOLTIP_BACKGROUND) };
+    let alignment = if dialog { TextAlignment::Left } else { TextAlignment::Center };
+
+    commands
+        .spawn((NodeBundle { style, z_index: ZIndex::Global(50), ..default() }, ScreenText { source }))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(text, TextStyle { font, font_size, color: Color::WHITE })
+                    .with_text_alignment(alignment)
+                    .with_style(Style { padding: UiRect::all(Val::Px(6.0)), ..default() })
+                    .with_background_color(background),
+                ScreenTextLabel,
+            ));
+        });
+}
+
+// System to mirror speech boxes and the inspect tooltip into screen-space UI while screen-space text is on,
+// anchored where the world copies would be drawn
+pub fn update_screen_text(
+    mut commands: Commands,
+    display: Res<DisplaySettings>,
+    asset_server: Res<AssetServer>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<CameraControl>>,
+    dialog_query: Query<(Entity, &DialogBox, &GlobalTransform, &Children)>,
+    dialog_text_query: Query<&Text, (With<DialogText>, Without<ScreenTextLabel>)>,
+    tooltip_query: Query<(Entity, &InspectTooltip, &Text, &GlobalTransform), Without<ScreenTextLabel>>,
+    mut node_query: Query<(Entity, &ScreenText, &mut Style, &Children)>,
+    mut label_query: Query<&mut Text, (With<ScreenTextLabel>, Without<DialogText>, Without<InspectTooltip>)>,
+) {
+    if !display.screen_space_text {
+        for (entity, ..) in node_query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+    let (camera, camera_transform) = if let Ok(camera) = camera_query.get_single() { camera } else { return; };
+
+    // Everything that should have a label: what it says, where it's anchored in the world, and whether it's speech
+    let mut sources: HashMap<Entity, (String, Vec3, bool)> = HashMap::new();
+    for (entity, dialog, transform, children) in dialog_query.iter() {
+        if dialog.speaker.is_none() {
+            continue;
+        }
+        let text = children.iter().find_map(|&child| dialog_text_query.get(child).ok());
+        if let Some(text) = text.filter(|text| !text.sections[0].value.is_empty()) {
+            sources.insert(entity, (text.sections[0].value.clone(), transform.translation(), true));
+        }
+    }
+    for (entity, tooltip, text, transform) in tooltip_query.iter() {
+        if tooltip.shown {
+            sources.insert(entity, (text.sections[0].value.clone(), transform.translation(), false));
+        }
+    }
+
+    // Update the labels already up, dropping those whose source has gone quiet
+    for (entity, screen_text, mut style, children) in node_query.iter_mut() {
+        let (text, anchor, _) = if let Some(source) = sources.remove(&screen_text.source) { source } else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+        if let Some(point) = camera.world_to_viewport(camera_transform, anchor) {
+            label_position(&mut style, point);
+        }
+        for &child in children.iter() {
+            if let Ok(mut label) = label_query.get_mut(child) {
+                if label.sections[0].value != text {
+                    label.sections[0].value = text.clone();
+                }
+            }
+        }
+    }
+
+    // And put up labels for anything new
+    for (source, (text, anchor, dialog)) in sources {
+        if let Some(point) = camera.world_to_viewport(camera_transform, anchor) {
+            spawn_label(&mut commands, source, &text, point, asset_server.load("fonts/FiraSans-Light.ttf"), dialog);
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use crate::dialogue::{CharacterType, DialogueResponse, ResponseKind};
+use crate::identify::with_article;
+use crate::inventory::ItemKind;
+
+// Traders pay this fraction of an item's worth for it
+const RESALE_FRACTION: f32 = 0.5;
+
+// What each kind of trader keeps in stock (at most two, to fit the numbered responses)
+pub fn wares(character_type: &CharacterType) -> Vec<ItemKind> {
+    match character_type {
+        CharacterType::Shopkeeper => vec![ItemKind::HealingPotion, ItemKind::Antidote],
+        CharacterType::Blacksmith => vec![ItemKind::Dagger, ItemKind::Lockpick],
+        CharacterType::Baker => vec![ItemKind::Ration],
+        _ => Vec::new(),
+    }
+}
+
+// What an item is worth before the trader's mood is taken into account
+fn base_price(item: ItemKind) -> u32 {
+    match item {
+        ItemKind::Ration | ItemKind::Meat => 5,
+        ItemKind::Lockpick | ItemKind::Key | ItemKind::Hide => 10,
+        ItemKind::Dagger | ItemKind::Antidote | ItemKind::Fang | ItemKind::Claw => 12,
+        ItemKind::HealingPotion | ItemKind::ManaPotion => 20,
+        ItemKind::PoisonPotion | ItemKind::ScrollOfIdentify | ItemKind::ScrollOfBlessing | ItemKind::ScrollOfCursing => 15,
+        ItemKind::Trophy => 80,
+        ItemKind::Gold => 1,
+    }
+}
+
+// What buying an item costs, given the trader's price multiplier
+pub fn buy_price(item: ItemKind, multiplier: f32) -> u32 {
+    ((base_price(item) as f32 * multiplier).round() as u32).max(1)
+}
+
+// What a trader pays for an item, if it's the kind of thing they buy
+pub fn sell_price(item: ItemKind, multiplier: f32) -> Option<u32> {
+    if !item.is_valuable() {
+        return None;
+    }
+    // A trader who likes you pays more, just as they charge less
+    Some(((base_price(item) as f32 * RESALE_FRACTION / multiplier).round() as u32).max(1))
+}
+
+// Responses offered while looking over a trader's wares
+pub fn shop_responses(character_type: &CharacterType) -> Vec<DialogueResponse> {
+    let mut responses: Vec<DialogueResponse> = wares(character_type)
+        .into_iter()
+        .map(|item| DialogueResponse::new(&format!("I'll take {}.", with_article(item.get_name())), ResponseKind::Buy(item)))
+        .collect();
+    responses.push(DialogueResponse::new("I have things to sell.", ResponseKind::Sell));
+    responses.push(DialogueResponse::new("That's all.", ResponseKind::Continue));
+    responses
+}
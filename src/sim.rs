@@ -0,0 +1,206 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::{HashMap, VecDeque};
+
+use crate::biome::BiomeType;
+use crate::map::{is_boss_level, TileMap, TileType};
+
+// Maps generated when --maps isn't given
+const DEFAULT_MAPS: usize = 100;
+// Levels cycled through when --depth isn't given; enough to include a boss floor
+const DEFAULT_DEPTH: usize = 10;
+
+/// What a headless run should generate
+#[derive(Debug, Clone)]
+pub struct SimOptions {
+    pub maps: usize,
+    pub seed: u64,    // Seeds for every map are drawn from this, so a report can be reproduced
+    pub depth: usize, // Maps cycle through levels 0..depth
+}
+
+impl SimOptions {
+    // Headless mode when launched with `--headless [--maps N] [--seed S] [--depth D]`
+    pub fn from_args() -> Option<Self> {
+        let args: Vec<String> = std::env::args().collect();
+        if !args.iter().any(|arg| arg == "--headless") {
+            return None;
+        }
+        let value = |flag: &str| -> Option<u64> {
+            let index = args.iter().position(|arg| arg == flag)?;
+            match args.get(index + 1).map(|value| value.parse::<u64>()) {
+                Some(Ok(value)) => Some(value),
+                _ => {
+                    eprintln!("{} needs a number, using the default", flag);
+                    None
+                }
+            }
+        };
+        Some(Self {
+            maps: value("--maps").map_or(DEFAULT_MAPS, |maps| maps as usize),
+            seed: value("--seed").unwrap_or_else(rand::random),
+            depth: value("--depth").map_or(DEFAULT_DEPTH, |depth| (depth as usize).max(1)),
+        })
+    }
+}
+
+/// A problem found with one generated map
+#[derive(Debug, Clone)]
+pub struct SimFailure {
+    pub level: usize,
+    pub seed: u64,
+    pub problem: String,
+}
+
+/// Totals over every generated map
+#[derive(Debug, Default)]
+pub struct SimReport {
+    pub maps: usize,
+    pub rooms: usize,
+    pub min_rooms: Option<usize>,
+    pub max_rooms: usize,
+    pub floor_coverage: f64, // Summed share of each map that is walkable, averaged when printed
+    pub biome_tiles: HashMap<BiomeType, usize>,
+    pub chests: usize,
+    pub traps: usize,
+    pub failures: Vec<SimFailure>,
+}
+
+impl SimReport {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    pub fn print(&self) {
+        let maps = self.maps.max(1) as f64;
+        println!("Generated {} maps", self.maps);
+        println!("  rooms: {:.1} average, {} fewest, {} most", self.rooms as f64 / maps, self.min_rooms.unwrap_or(0), self.max_rooms);
+        println!("  floor coverage: {:.1}% average", self.floor_coverage / maps * 100.0);
+        println!("  chests: {:.1} per map, traps: {:.1} per map", self.chests as f64 / maps, self.traps as f64 / maps);
+
+        let total_biome_tiles: usize = self.biome_tiles.values().sum::<usize>().max(1);
+        let mut biomes: Vec<_> = self.biome_tiles.iter().collect();
+        biomes.sort_by(|a, b| b.1.cmp(a.1));
+        for (biome, tiles) in biomes {
+            println!("  {:<10} {:.1}%", biome.get_name(), *tiles as f64 / total_biome_tiles as f64 * 100.0);
+        }
+
+        if self.passed() {
+            println!("All maps passed validation");
+        } else {
+            println!("{} problems found:", self.failures.len());
+            for failure in &self.failures {
+                println!("  level {} seed {}: {}", failure.level, failure.seed, failure.problem);
+            }
+        }
+    }
+}
+
+fn walkable(tile: TileType) -> bool {
+    // Secret doors count: they're there to be found, not to cut a level in two
+    tile != TileType::Wall
+}
+
+// Every tile reachable on foot from the spawn point, ignoring blocking props
+fn reachable_from_spawn(map: &TileMap) -> Vec<Vec<bool>> {
+    let mut reached = vec![vec![false; map.width]; map.height];
+    let blocked: Vec<(usize, usize)> = map.props.iter().filter(|prop| prop.def.blocking).map(|prop| (prop.x, prop.y)).collect();
+    let (sx, sy) = map.spawn_position;
+    if sx >= map.width || sy >= map.height {
+        return reached;
+    }
+
+    let mut queue = VecDeque::from([(sx, sy)]);
+    reached[sy][sx] = true;
+    while let Some((x, y)) = queue.pop_front() {
+        for (dx, dy) in [(0i32, 1i32), (1, 0), (0, -1), (-1, 0)] {
+            let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+            if !map.in_bounds(nx, ny) {
+                continue;
+            }
+            let (nx, ny) = (nx as usize, ny as usize);
+            if reached[ny][nx] || !walkable(map.tiles[ny][nx]) || blocked.contains(&(nx, ny)) {
+                continue;
+            }
+            reached[ny][nx] = true;
+            queue.push_back((nx, ny));
+        }
+    }
+    reached
+}
+
+/// Everything wrong with a map: unreachable floor, missing or cut-off stairs, spawns in walls
+pub fn validate_map(map: &TileMap) -> Vec<String> {
+    let mut problems = Vec::new();
+    let (sx, sy) = map.spawn_position;
+    if sx >= map.width || sy >= map.height || !walkable(map.tiles[sy][sx]) {
+        problems.push(format!("player spawn {:?} is not on open ground", map.spawn_position));
+        return problems;
+    }
+
+    let reached = reachable_from_spawn(map);
+    let mut check = |what: &str, pos: Option<(usize, usize)>| match pos {
+        None => problems.push(format!("no {}", what)),
+        Some((x, y)) if x >= map.width || y >= map.height => problems.push(format!("{} {:?} is off the map", what, (x, y))),
+        Some((x, y)) if !reached[y][x] => problems.push(format!("{} at {:?} can't be reached", what, (x, y))),
+        Some(_) => {}
+    };
+    check("down stairs", map.down_stairs_pos);
+    check("up stairs", map.up_stairs_pos);
+    for &chest in &map.chest_positions {
+        check("chest", Some(chest));
+    }
+    for &(a, b) in &map.portal_pairs {
+        check("portal", Some(a));
+        check("portal", Some(b));
+    }
+    for &altar in &map.altar_positions {
+        check("altar", Some(altar));
+    }
+    for &spawn in map.npc_spawns.iter().chain(map.monster_spawns.iter()) {
+        check("creature spawn", Some(spawn));
+    }
+
+    let unreachable_floor = (0..map.height)
+        .flat_map(|y| (0..map.width).map(move |x| (x, y)))
+        .filter(|&(x, y)| map.tiles[y][x] == TileType::Floor && !reached[y][x])
+        .count();
+    if unreachable_floor > 0 {
+        problems.push(format!("{} floor tiles can't be reached from the spawn", unreachable_floor));
+    }
+    problems
+}
+
+/// Generate maps and tally them up, without Bevy
+pub fn simulate(options: &SimOptions) -> SimReport {
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let mut report = SimReport::default();
+
+    for i in 0..options.maps {
+        let level = i % options.depth;
+        let seed: u64 = rng.gen();
+        let map = TileMap::generate_level(level, seed);
+
+        report.maps += 1;
+        report.rooms += map.rooms.len();
+        report.min_rooms = Some(report.min_rooms.map_or(map.rooms.len(), |min| min.min(map.rooms.len())));
+        report.max_rooms = report.max_rooms.max(map.rooms.len());
+        report.chests += map.chest_positions.len();
+        report.traps += map.trap_positions.len();
+
+        let open = map.tiles.iter().flatten().filter(|&&tile| walkable(tile)).count();
+        report.floor_coverage += open as f64 / (map.width * map.height) as f64;
+        for y in 0..map.height {
+            for x in 0..map.width {
+                if walkable(map.tiles[y][x]) {
+                    *report.biome_tiles.entry(map.get_biome_at(x, y)).or_insert(0) += 1;
+                }
+            }
+        }
+
+        for problem in validate_map(&map) {
+            let problem = if is_boss_level(level) { format!("{} (boss floor)", problem) } else { problem };
+            report.failures.push(SimFailure { level, seed, problem });
+        }
+    }
+    report
+}
@@ -0,0 +1,91 @@
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+
+use crate::animals::{spawn_animal, AnimalManager};
+use crate::assets::TextureAtlases;
+use crate::components::{AnimalNpc, Companion, GameTurn, Player, Position};
+use crate::escalation::{Escalation, EscalationSettings};
+use crate::map::{TileMap, TileType};
+use crate::rng::GameRng;
+use crate::run_modifiers::RunModifiers;
+use crate::visibility::{line_of_sight, VisibilityMap};
+
+// One creature for every this many floor tiles
+const FLOOR_TILES_PER_CREATURE: usize = 150;
+// One more creature every this many levels down
+const LEVELS_PER_EXTRA_CREATURE: usize = 2;
+const MIN_CREATURES: usize = 2;
+const MAX_CREATURES: usize = 12;
+// A new creature may wander in every this many turns while the level is below its budget
+const RESPAWN_INTERVAL_TURNS: u32 = 50;
+// Without a visibility map, "unexplored" means this far from the player and out of sight
+const RESPAWN_MIN_DISTANCE: i32 = 12;
+
+/// How many wild creatures a level should hold, from its size and depth and the run's modifiers
+pub fn creature_budget(map: &TileMap, modifiers: &RunModifiers) -> usize {
+    let floor_tiles = map.tiles.iter().flatten().filter(|&&tile| tile == TileType::Floor).count();
+    let budget = floor_tiles / FLOOR_TILES_PER_CREATURE + map.current_level / LEVELS_PER_EXTRA_CREATURE;
+    budget.clamp(MIN_CREATURES, MAX_CREATURES) * modifiers.creature_multiplier()
+}
+
+// Floor tiles a creature could wander in on without the player watching it appear
+pub fn respawn_candidates(map: &TileMap, player: &Position, visibility_map: Option<&VisibilityMap>) -> Vec<(i32, i32)> {
+    let mut candidates = Vec::new();
+    for y in 0..map.height {
+        for x in 0..map.width {
+            if map.tiles[y][x] != TileType::Floor || map.vaults.iter().any(|vault| vault.contains(x, y)) {
+                continue;
+            }
+            let (tx, ty) = (x as i32, y as i32);
+            let unexplored = match visibility_map.and_then(|vis| vis.previously_seen.get(y).and_then(|row| row.get(x))) {
+                Some(&seen) => !seen,
+                None => (tx - player.x).abs() + (ty - player.y).abs() >= RESPAWN_MIN_DISTANCE
+                    && !line_of_sight(map, (player.x, player.y), (tx, ty)),
+            };
+            if unexplored {
+                candidates.push((tx, ty));
+            }
+        }
+    }
+    candidates
+}
+
+// System to trickle new creatures into unexplored parts of a level that has been thinned out
+pub fn respawn_creatures_system(
+    mut commands: Commands,
+    game_turn: Res<GameTurn>,
+    map: Res<TileMap>,
+    texture_atlases: Res<TextureAtlases>,
+    animal_manager: Res<AnimalManager>,
+    visibility_map: Option<Res<VisibilityMap>>,
+    modifiers: Res<RunModifiers>,
+    escalation: Res<Escalation>,
+    escalation_settings: Res<EscalationSettings>,
+    mut game_rng: ResMut<GameRng>,
+    player_query: Query<&Position, With<Player>>,
+    creature_query: Query<(), (With<AnimalNpc>, Without<Companion>)>,
+    mut local: Local<u32>, // The last respawn window that was checked
+) {
+    // Creatures come faster, and more of them, once the Chasm stirs
+    let window = game_turn.current_turn / escalation.respawn_interval(&escalation_settings, RESPAWN_INTERVAL_TURNS);
+    if window == *local {
+        return;
+    }
+    *local = window;
+
+    // Boss floors keep to their set piece
+    if window == 0 || map.is_boss_level || creature_query.iter().count() >= creature_budget(&map, &modifiers) + escalation.extra_creatures(&escalation_settings) {
+        return;
+    }
+
+    let player = if let Ok(pos) = player_query.get_single() { *pos } else { return; };
+    let rng = &mut game_rng.spawns;
+    let candidates = respawn_candidates(&map, &player, visibility_map.as_deref());
+    let pos = if let Some(&pos) = candidates.choose(rng) { pos } else { return; };
+
+    let biome = map.get_biome_at(pos.0 as usize, pos.1 as usize);
+    if let Some(animal_data) = animal_manager.get_random_animal(biome, rng) {
+        println!("A {} wanders in on turn {}", animal_data.animal_type.get_name(), game_turn.current_turn);
+        spawn_animal(&mut commands, &map, &texture_atlases, animal_data, pos);
+    }
+}
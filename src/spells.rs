@@ -0,0 +1,421 @@
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+
+use crate::aoe::{area_tiles, AreaShape};
+use crate::biome::BiomeType;
+use crate::combat::{spawn_projectile, trace_projectile_path, Health};
+use crate::components::{GameTurn, Player, PlayerAnimation, Position};
+use crate::conversation::Conversation;
+use crate::events::{PlayerAttacked, PlayerMoved};
+use crate::faction::Hostile;
+use crate::hearing::{NoiseEvent, NoiseKind};
+use crate::level::move_player_to;
+use crate::map::TileMap;
+use crate::rng::GameRng;
+use crate::status::{ApplyStatusEffect, StatusEffect, StatusKind};
+use crate::visibility::{line_of_sight, Vision, VisibilityMap};
+use crate::player::AnimationState;
+
+// Mana regained every turn
+const MANA_REGEN_PER_TURN: i32 = 1;
+// How many turns a Light spell lasts before biome modifiers
+const LIGHT_DURATION: u32 = 20;
+
+/// The spells the player can cast
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SpellKind {
+    Firebolt,   // Hurls a bolt of fire at the nearest hostile creature in sight
+    Blink,      // Teleports a short distance to a random visible tile
+    RevealMap,  // Reveals the map around the caster
+    Light,      // Extends the caster's sight for a while
+    Mend,       // Slowly regenerates the caster's health
+}
+
+impl SpellKind {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            SpellKind::Firebolt => "Firebolt",
+            SpellKind::Blink => "Blink",
+            SpellKind::RevealMap => "Reveal Map",
+            SpellKind::Light => "Light",
+            SpellKind::Mend => "Mend",
+        }
+    }
+
+    // Mana cost before biome modifiers
+    pub fn base_cost(&self) -> i32 {
+        match self {
+            SpellKind::Firebolt => 3,
+            SpellKind::Blink => 4,
+            SpellKind::RevealMap => 8,
+            SpellKind::Light => 2,
+            SpellKind::Mend => 5,
+        }
+    }
+
+    // Damage, distance, radius or sight bonus depending on the spell
+    pub fn base_power(&self) -> i32 {
+        match self {
+            SpellKind::Firebolt => 5,
+            SpellKind::Blink => 5,
+            SpellKind::RevealMap => 12,
+            SpellKind::Light => 4,
+            SpellKind::Mend => 1,
+        }
+    }
+}
+
+/// How a biome bends a spell
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SpellModifier {
+    pub cost: i32,
+    pub power: i32,
+}
+
+// Per-biome spell modifiers
+pub fn biome_modifier(spell: SpellKind, biome: BiomeType) -> SpellModifier {
+    match (spell, biome) {
+        // Dry undergrowth feeds the flames
+        (SpellKind::Firebolt, BiomeType::Groves) => SpellModifier { cost: 0, power: 2 },
+        // Damp cave air smothers fire but open caverns give room to blink
+        (SpellKind::Firebolt, BiomeType::Caves) => SpellModifier { cost: 0, power: -1 },
+        (SpellKind::Blink, BiomeType::Caves) => SpellModifier { cost: 0, power: 2 },
+        // The maze resists being shortcut, but wants to be known
+        (SpellKind::Blink, BiomeType::Labyrinth) => SpellModifier { cost: 2, power: 0 },
+        (SpellKind::RevealMap, BiomeType::Labyrinth) => SpellModifier { cost: -3, power: 6 },
+        // Light comes cheap among the dead, who have no use for it
+        (SpellKind::Light, BiomeType::Catacombs) => SpellModifier { cost: -1, power: 2 },
+        // Life grows easily in the groves
+        (SpellKind::Mend, BiomeType::Groves) => SpellModifier { cost: -1, power: 1 },
+        _ => SpellModifier::default(),
+    }
+}
+
+// Final cost of a spell in a biome
+pub fn spell_cost(spell: SpellKind, biome: BiomeType) -> i32 {
+    (spell.base_cost() + biome_modifier(spell, biome).cost).max(1)
+}
+
+// Final power of a spell in a biome
+pub fn spell_power(spell: SpellKind, biome: BiomeType) -> i32 {
+    (spell.base_power() + biome_modifier(spell, biome).power).max(1)
+}
+
+/// Mana pool for spellcasters
+#[derive(Component, Debug, Clone)]
+pub struct Mana {
+    pub current: i32,
+    pub max: i32,
+}
+
+impl Mana {
+    pub fn new(max: i32) -> Self {
+        Self { current: max, max }
+    }
+
+    // Spend mana, returning false if there isn't enough
+    pub fn spend(&mut self, amount: i32) -> bool {
+        if self.current < amount {
+            return false;
+        }
+        self.current -= amount;
+        true
+    }
+
+    pub fn restore(&mut self, amount: i32) {
+        self.current = (self.current + amount.max(0)).min(self.max);
+    }
+}
+
+/// The spells an entity knows and which one is readied
+#[derive(Component, Debug, Clone)]
+pub struct Spellbook {
+    pub spells: Vec<SpellKind>,
+    pub selected: usize,
+}
+
+impl Default for Spellbook {
+    fn default() -> Self {
+        Self {
+            spells: vec![SpellKind::Firebolt, SpellKind::Blink, SpellKind::RevealMap, SpellKind::Light, SpellKind::Mend],
+            selected: 0,
+        }
+    }
+}
+
+impl Spellbook {
+    pub fn selected_spell(&self) -> Option<SpellKind> {
+        self.spells.get(self.selected).copied()
+    }
+}
+
+/// An active Light spell
+#[derive(Component, Debug)]
+pub struct LightSpell {
+    pub turns_left: u32,
+    pub bonus: f32,
+}
+
+/// Marker for the spell bar UI text
+#[derive(Component)]
+pub struct SpellBarText;
+
+// System to pick the readied spell with the number keys
+pub fn select_spell_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut player_query: Query<&mut Spellbook, With<Player>>,
+    conversation: Res<Conversation>,
+) {
+    // Number keys answer the conversation instead
+    if conversation.awaiting_choice() {
+        return;
+    }
+
+    let keys = [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4, KeyCode::Key5];
+
+    if let Ok(mut spellbook) = player_query.get_single_mut() {
+        for (index, key) in keys.iter().enumerate() {
+            if keyboard_input.just_pressed(*key) && index < spellbook.spells.len() {
+                spellbook.selected = index;
+                println!("Readied {}", spellbook.spells[index].get_name());
+            }
+        }
+    }
+}
+
+// System to cast the readied spell (C key)
+pub fn cast_spell_system(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    animation_state: Res<AnimationState>,
+    mut player_query: Query<(Entity, &mut Position, &mut Transform, &mut PlayerAnimation, &mut Mana, &Spellbook, &mut Vision, Option<&mut LightSpell>), With<Player>>,
+    creature_query: Query<(Entity, &Position, Option<&Hostile>), (With<Health>, Without<Player>)>,
+    map: Res<TileMap>,
+    visibility_map: Option<ResMut<VisibilityMap>>,
+    mut status_events: EventWriter<ApplyStatusEffect>,
+    mut noise_events: EventWriter<NoiseEvent>,
+    mut attack_events: EventWriter<PlayerAttacked>,
+    mut game_turn: ResMut<GameTurn>,
+    mut game_rng: ResMut<GameRng>,
+    mut moved_events: EventWriter<PlayerMoved>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::C) {
+        return;
+    }
+
+    // Don't teleport or fire out from under a moving player
+    if animation_state.animation_in_progress {
+        return;
+    }
+
+    let (player_entity, mut player_pos, mut transform, mut animation, mut mana, spellbook, mut vision, light) =
+        if let Ok(player) = player_query.get_single_mut() {
+            player
+        } else {
+            return;
+        };
+
+    let spell = if let Some(spell) = spellbook.selected_spell() {
+        spell
+    } else {
+        return;
+    };
+
+    let start = (player_pos.x, player_pos.y);
+    let biome = map.get_biome_at(start.0 as usize, start.1 as usize);
+    let cost = spell_cost(spell, biome);
+    let power = spell_power(spell, biome);
+
+    if mana.current < cost {
+        println!("Not enough mana to cast {} ({} needed, {} left)", spell.get_name(), cost, mana.current);
+        return;
+    }
+
+    let cast = match spell {
+        SpellKind::Firebolt => {
+            // Aim at the closest hostile creature we can see
+            let range = 8;
+            let creatures: Vec<(Entity, (i32, i32))> = creature_query
+                .iter()
+                .map(|(entity, position, _)| (entity, (position.x, position.y)))
+                .collect();
+            let target = creature_query
+                .iter()
+                .filter(|(_, position, hostile)| {
+                    hostile.is_some()
+                        && (position.x - start.0).abs().max((position.y - start.1).abs()) <= range
+                        && line_of_sight(&map, start, (position.x, position.y))
+                })
+                .min_by_key(|(_, position, _)| (position.x - start.0).abs() + (position.y - start.1).abs());
+
+            if let Some((_, target_pos, _)) = target {
+                let (path, hit) = trace_projectile_path(&map, start, (target_pos.x, target_pos.y), range, &creatures);
+                println!("You hurl a firebolt!");
+                spawn_projectile(&mut commands, start, path, power, hit, Color::rgb(1.0, 0.5, 0.1));
+                attack_events.send(PlayerAttacked);
+                true
+            } else {
+                println!("There is nothing hostile in sight to burn");
+                false
+            }
+        }
+        SpellKind::Blink => {
+            // Pick a random open tile within range that we can see
+            let spots: Vec<(i32, i32)> = area_tiles(&map, start, AreaShape::Circle { radius: power })
+                .into_iter()
+                .filter(|&(x, y)| {
+                    (x, y) != start
+                        && map.is_position_walkable(x, y)
+                        && !creature_query.iter().any(|(_, position, _)| position.x == x && position.y == y)
+                })
+                .collect();
+
+            if let Some(&(x, y)) = spots.choose(&mut game_rng.combat) {
+                // Land the sprite, camera and light with the player, as a portal does
+                animation.is_moving = false;
+                animation.move_buffer.clear();
+                move_player_to(&mut transform, &mut player_pos, (x as usize, y as usize));
+                moved_events.send(PlayerMoved { x, y });
+                println!("You blink to ({}, {})", x, y);
+                true
+            } else {
+                println!("There is nowhere to blink to");
+                false
+            }
+        }
+        SpellKind::RevealMap => {
+            if let Some(mut visibility_map) = visibility_map {
+                visibility_map.fit_to(&map);
+                for y in 0..map.height as i32 {
+                    for x in 0..map.width as i32 {
+                        if (x - start.0).abs() + (y - start.1).abs() <= power {
+                            visibility_map.previously_seen[y as usize][x as usize] = true;
+                        }
+                    }
+                }
+            }
+
+            // Always reveal where the stairs are
+            if let Some(down) = map.down_stairs_pos {
+                println!("You sense stairs leading down at ({}, {})", down.0, down.1);
+            }
+            println!("The surrounding passages unfold in your mind");
+            true
+        }
+        SpellKind::Light => {
+            let turns = LIGHT_DURATION + if biome == BiomeType::Catacombs { 10 } else { 0 };
+            if let Some(mut light) = light {
+                // Recasting just refreshes the duration
+                light.turns_left = turns;
+            } else {
+                vision.radius += power as f32;
+                commands.entity(player_entity).insert(LightSpell { turns_left: turns, bonus: power as f32 });
+            }
+            println!("A soft light surrounds you");
+            true
+        }
+        SpellKind::Mend => {
+            status_events.send(ApplyStatusEffect {
+                target: player_entity,
+                effect: StatusEffect::new(StatusKind::Regeneration, 10, power),
+            });
+            true
+        }
+    };
+
+    if cast {
+        mana.spend(cost);
+        game_turn.increment();
+        noise_events.send(NoiseEvent { x: start.0, y: start.1, kind: NoiseKind::Combat });
+    }
+}
+
+// System to regenerate mana and wear down lasting spell effects as turns pass
+pub fn tick_spells_system(
+    mut commands: Commands,
+    game_turn: Res<GameTurn>,
+    mut mana_query: Query<&mut Mana>,
+    mut light_query: Query<(Entity, &mut LightSpell, &mut Vision)>,
+    mut local: Local<u32>,
+) {
+    if game_turn.current_turn <= *local {
+        *local = game_turn.current_turn;
+        return;
+    }
+
+    let turns_passed = game_turn.current_turn - *local;
+    *local = game_turn.current_turn;
+
+    for mut mana in mana_query.iter_mut() {
+        mana.restore(MANA_REGEN_PER_TURN * turns_passed as i32);
+    }
+
+    for (entity, mut light, mut vision) in light_query.iter_mut() {
+        light.turns_left = light.turns_left.saturating_sub(turns_passed);
+        if light.turns_left == 0 {
+            vision.radius = (vision.radius - light.bonus).max(0.0);
+            commands.entity(entity).remove::<LightSpell>();
+            println!("Your light fades");
+        }
+    }
+}
+
+// Create the spell bar in the top left corner (the message log sits at the bottom)
+pub fn setup_spell_bar(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let style = TextStyle {
+        font: asset_server.load("fonts/FiraSans-Light.ttf"),
+        font_size: 18.0,
+        color: Color::WHITE,
+    };
+
+    commands.spawn((
+        TextBundle::from_sections([
+            TextSection::new("", style.clone()), // Mana
+            TextSection::new("", style.clone()), // Spells
+        ])
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+        SpellBarText,
+    ));
+}
+
+// Keep the spell bar in sync with the player's mana and readied spell
+pub fn update_spell_bar(
+    player_query: Query<(&Mana, &Spellbook, &Position), (With<Player>, Or<(Changed<Mana>, Changed<Spellbook>, Changed<Position>)>)>,
+    map: Res<TileMap>,
+    mut text_query: Query<&mut Text, With<SpellBarText>>,
+) {
+    let (mana, spellbook, position) = if let Ok(player) = player_query.get_single() {
+        player
+    } else {
+        return;
+    };
+
+    let biome = map.get_biome_at(position.x as usize, position.y as usize);
+
+    let spells = spellbook
+        .spells
+        .iter()
+        .enumerate()
+        .map(|(index, spell)| {
+            let marker = if index == spellbook.selected { ">" } else { " " };
+            format!("{}{} {} ({})", marker, index + 1, spell.get_name(), spell_cost(*spell, biome))
+        })
+        .collect::<Vec<_>>()
+        .join("   ");
+
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = format!("Mana: {}/{}    ", mana.current, mana.max);
+        text.sections[1].value = spells.clone();
+
+        // Dim the spell list when the readied spell can't be afforded
+        let affordable = spellbook
+            .selected_spell()
+            .map_or(false, |spell| mana.current >= spell_cost(spell, biome));
+        text.sections[1].style.color = if affordable { Color::WHITE } else { Color::GRAY };
+    }
+}
@@ -0,0 +1,286 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::combat::Health;
+use crate::components::{GameTurn, Npc, Player};
+use crate::display_settings::{DisplaySettings, Palette};
+use crate::events::EntityDamaged;
+use crate::ui::MessageLog;
+
+/// The kinds of lingering effects an entity can suffer or enjoy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum StatusKind {
+    Poison,       // Loses health every turn
+    Slow,         // Acts every other turn
+    Regeneration, // Regains health every turn
+    Blessed,      // Bolts hit harder
+    Cursed,       // Bolts hit softer
+}
+
+impl StatusKind {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            StatusKind::Poison => "poisoned",
+            StatusKind::Slow => "slowed",
+            StatusKind::Regeneration => "regenerating",
+            StatusKind::Blessed => "blessed",
+            StatusKind::Cursed => "cursed",
+        }
+    }
+
+    // Short label shown in the HUD
+    pub fn icon(&self) -> &'static str {
+        match self {
+            StatusKind::Poison => "PSN",
+            StatusKind::Slow => "SLW",
+            StatusKind::Regeneration => "RGN",
+            StatusKind::Blessed => "BLS",
+            StatusKind::Cursed => "CRS",
+        }
+    }
+
+    // Poison and Regeneration are green and red in the standard palette, which many players can't tell apart
+    pub fn icon_color(&self, palette: Palette) -> Color {
+        match (palette, self) {
+            (Palette::Standard, StatusKind::Poison) => Color::rgb(0.4, 0.9, 0.3),
+            (Palette::Standard, StatusKind::Slow) => Color::rgb(0.5, 0.6, 1.0),
+            (Palette::Standard, StatusKind::Regeneration) => Color::rgb(1.0, 0.4, 0.5),
+            (Palette::Standard, StatusKind::Blessed) => Color::rgb(1.0, 0.85, 0.4),
+            (Palette::Standard, StatusKind::Cursed) => Color::rgb(0.7, 0.3, 0.8),
+            (Palette::Colorblind, StatusKind::Poison) => Color::rgb(0.9, 0.6, 0.0),
+            (Palette::Colorblind, StatusKind::Slow) => Color::rgb(0.0, 0.45, 0.7),
+            (Palette::Colorblind, StatusKind::Regeneration) => Color::rgb(0.35, 0.7, 0.9),
+            (Palette::Colorblind, StatusKind::Blessed) => Color::rgb(0.95, 0.9, 0.25),
+            (Palette::Colorblind, StatusKind::Cursed) => Color::rgb(0.8, 0.6, 0.7),
+        }
+    }
+}
+
+/// A single effect with a duration and a strength
+#[derive(Debug, Clone)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub turns_left: u32,
+    pub potency: i32,
+}
+
+impl StatusEffect {
+    pub fn new(kind: StatusKind, turns: u32, potency: i32) -> Self {
+        Self { kind, turns_left: turns, potency }
+    }
+
+    // Per-turn hook; returns a message describing what happened, if anything
+    pub fn on_tick(&self, name: &str, health: Option<&mut Health>) -> Option<String> {
+        match self.kind {
+            StatusKind::Poison => {
+                let health = health?;
+                health.take_damage(self.potency);
+                Some(format!("{} takes {} poison damage", name, self.potency))
+            }
+            StatusKind::Regeneration => {
+                let health = health?;
+                if health.current >= health.max {
+                    return None;
+                }
+                health.heal(self.potency);
+                Some(format!("{} regenerates {} health", name, self.potency))
+            }
+            // These don't do anything on their own: movement and combat check for them
+            StatusKind::Slow | StatusKind::Blessed | StatusKind::Cursed => None,
+        }
+    }
+}
+
+/// All status effects currently on an entity
+#[derive(Component, Debug, Default)]
+pub struct StatusEffects {
+    pub effects: Vec<StatusEffect>,
+}
+
+impl StatusEffects {
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.effects.iter().any(|effect| effect.kind == kind)
+    }
+
+    // Add an effect; reapplying an effect keeps the longer duration and stronger potency
+    pub fn add(&mut self, effect: StatusEffect) {
+        if let Some(existing) = self.effects.iter_mut().find(|existing| existing.kind == effect.kind) {
+            existing.turns_left = existing.turns_left.max(effect.turns_left);
+            existing.potency = existing.potency.max(effect.potency);
+        } else {
+            self.effects.push(effect);
+        }
+    }
+
+    // Extra damage from blessings, less from curses
+    pub fn damage_modifier(&self) -> i32 {
+        self.effects.iter().map(|effect| match effect.kind {
+            StatusKind::Blessed => effect.potency,
+            StatusKind::Cursed => -effect.potency,
+            _ => 0,
+        }).sum()
+    }
+
+    // Slowed entities only get to act on even turns
+    pub fn skips_turn(&self, turn: u32) -> bool {
+        self.has(StatusKind::Slow) && turn % 2 == 1
+    }
+}
+
+/// Event sent by monsters, traps and spells to put an effect on something
+#[derive(Event, Debug)]
+pub struct ApplyStatusEffect {
+    pub target: Entity,
+    pub effect: StatusEffect,
+}
+
+/// Marker for the HUD text listing the player's active effects
+#[derive(Component)]
+pub struct StatusHudText;
+
+// Display name used in log messages
+fn entity_name(npc: Option<&Npc>, is_player: bool) -> String {
+    if is_player {
+        "You".to_string()
+    } else {
+        npc.map_or("Something".to_string(), |npc| npc.name.clone())
+    }
+}
+
+// System to attach newly applied effects to their targets
+pub fn apply_status_effects_system(
+    mut commands: Commands,
+    mut events: EventReader<ApplyStatusEffect>,
+    mut target_query: Query<(Option<&mut StatusEffects>, Option<&Npc>, Option<&Player>)>,
+    mut message_log: ResMut<MessageLog>,
+) {
+    // Effects for entities that don't have a StatusEffects component yet
+    let mut new_effects: HashMap<Entity, StatusEffects> = HashMap::new();
+
+    for event in events.read() {
+        let (effects, npc, player) = if let Ok(target) = target_query.get_mut(event.target) {
+            target
+        } else {
+            continue;
+        };
+
+        let name = entity_name(npc, player.is_some());
+        let verb = if player.is_some() { "are" } else { "is" };
+        message_log.add_message(format!("{} {} {}", name, verb, event.effect.kind.get_name()));
+
+        if let Some(mut effects) = effects {
+            effects.add(event.effect.clone());
+        } else {
+            new_effects.entry(event.target).or_default().add(event.effect.clone());
+        }
+    }
+
+    for (entity, effects) in new_effects {
+        commands.entity(entity).insert(effects);
+    }
+}
+
+// System to run each effect's per-turn hook and expire finished effects
+pub fn tick_status_effects_system(
+    mut commands: Commands,
+    game_turn: Res<GameTurn>,
+    mut query: Query<(Entity, &mut StatusEffects, Option<&mut Health>, Option<&Npc>, Option<&Player>)>,
+    mut message_log: ResMut<MessageLog>,
+    mut damage_events: EventWriter<EntityDamaged>,
+    mut local: Local<u32>,
+) {
+    if game_turn.current_turn <= *local {
+        *local = game_turn.current_turn;
+        return;
+    }
+
+    let turns_passed = game_turn.current_turn - *local;
+    *local = game_turn.current_turn;
+
+    for (entity, mut status, mut health, npc, player) in query.iter_mut() {
+        let name = entity_name(npc, player.is_some());
+
+        for _ in 0..turns_passed {
+            for effect in status.effects.iter_mut() {
+                if effect.turns_left == 0 {
+                    continue;
+                }
+                if let Some(message) = effect.on_tick(&name, health.as_deref_mut()) {
+                    message_log.add_message(message);
+                    if matches!(effect.kind, StatusKind::Poison) {
+                        damage_events.send(EntityDamaged { target: entity, amount: effect.potency, source: "poison".to_string() });
+                    }
+                }
+                effect.turns_left -= 1;
+            }
+        }
+
+        // Report and drop anything that has run its course
+        for effect in status.effects.iter().filter(|effect| effect.turns_left == 0) {
+            let verb = if player.is_some() { "are" } else { "is" };
+            message_log.add_message(format!("{} {} no longer {}", name, verb, effect.kind.get_name()));
+        }
+        status.effects.retain(|effect| effect.turns_left > 0);
+
+        if status.effects.is_empty() {
+            commands.entity(entity).remove::<StatusEffects>();
+        }
+    }
+}
+
+// Create the HUD line for the player's effects in the top right corner
+pub fn setup_status_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/FiraSans-Light.ttf"),
+                font_size: 18.0,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            right: Val::Px(10.0),
+            ..default()
+        }),
+        StatusHudText,
+    ));
+}
+
+// Show one colored icon per active effect on the player
+pub fn update_status_hud(
+    asset_server: Res<AssetServer>,
+    display_settings: Res<DisplaySettings>,
+    player_query: Query<Option<&StatusEffects>, With<Player>>,
+    mut hud_query: Query<&mut Text, With<StatusHudText>>,
+) {
+    let effects = if let Ok(effects) = player_query.get_single() {
+        effects
+    } else {
+        return;
+    };
+
+    for mut text in hud_query.iter_mut() {
+        text.sections = effects
+            .map(|effects| {
+                effects
+                    .effects
+                    .iter()
+                    .map(|effect| {
+                        TextSection::new(
+                            format!("[{} {}] ", effect.kind.icon(), effect.turns_left),
+                            TextStyle {
+                                font: asset_server.load("fonts/FiraSans-Light.ttf"),
+                                font_size: 18.0,
+                                color: effect.kind.icon_color(display_settings.palette),
+                            },
+                        )
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+    }
+}
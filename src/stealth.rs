@@ -0,0 +1,118 @@
+use bevy::prelude::*;
+
+use crate::components::Player;
+use crate::conversation::Conversation;
+use crate::inventory_panel::InventoryMenu;
+use crate::throwing::ThrowTargeting;
+use crate::ui::MessageLog;
+
+// A creature sees 60 degrees either side of where it faces (the cosine of that angle)
+pub const VISION_CONE_COS: f32 = 0.5;
+// Turns a creature keeps hunting after losing sight of the player, then stays uneasy for
+const ALERT_MEMORY_TURNS: u32 = 6;
+const SUSPICION_TURNS: u32 = 4;
+// The player fades a little while sneaking
+const SNEAKING_ALPHA: f32 = 0.6;
+
+/// Whether the player is creeping along: footsteps make half the noise, but each step takes two turns
+#[derive(Resource, Debug, Default)]
+pub struct Sneaking(pub bool);
+
+/// Which way a creature is looking, as a step direction
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Facing(pub (i32, i32));
+
+impl Default for Facing {
+    fn default() -> Self {
+        Self((0, -1))
+    }
+}
+
+impl Facing {
+    // Look along whichever axis the target is further off on
+    pub fn turn_toward(&mut self, from: (i32, i32), to: (i32, i32)) {
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        if dx == 0 && dy == 0 {
+            return;
+        }
+        self.0 = if dx.abs() > dy.abs() { (dx.signum(), 0) } else { (0, dy.signum()) };
+    }
+}
+
+/// How much a creature knows about the player
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertState {
+    Unaware,    // Going about its business
+    Suspicious, // Glimpsed or heard something and is looking into it
+    Alert,      // Knows where the player is and is hunting them
+}
+
+#[derive(Component, Debug, Clone)]
+pub struct Awareness {
+    pub state: AlertState,
+    pub turns_left: u32, // Until it calms down a step
+}
+
+impl Default for Awareness {
+    fn default() -> Self {
+        Self { state: AlertState::Unaware, turns_left: 0 }
+    }
+}
+
+impl Awareness {
+    // Move the state on a turn, from whether the player is in view and whether
+    // something was heard; returns true on the turn it becomes alert
+    pub fn observe(&mut self, sees_player: bool, heard_noise: bool) -> bool {
+        let before = self.state;
+        if sees_player {
+            // A first glimpse only makes it wary; a second look confirms it
+            if before == AlertState::Unaware {
+                self.state = AlertState::Suspicious;
+                self.turns_left = SUSPICION_TURNS;
+            } else {
+                self.state = AlertState::Alert;
+                self.turns_left = ALERT_MEMORY_TURNS;
+            }
+        } else if heard_noise && before == AlertState::Unaware {
+            self.state = AlertState::Suspicious;
+            self.turns_left = SUSPICION_TURNS;
+        } else if self.turns_left > 0 {
+            self.turns_left -= 1;
+        } else if before == AlertState::Alert {
+            self.state = AlertState::Suspicious;
+            self.turns_left = SUSPICION_TURNS;
+        } else {
+            self.state = AlertState::Unaware;
+        }
+        self.state == AlertState::Alert && before != AlertState::Alert
+    }
+}
+
+// System to start each run on foot
+pub fn reset_sneaking(mut sneaking: ResMut<Sneaking>) {
+    sneaking.0 = false;
+}
+
+// System to toggle sneaking with Q
+pub fn toggle_sneak_system(
+    keyboard: Res<Input<KeyCode>>,
+    conversation: Res<Conversation>,
+    inventory_menu: Res<InventoryMenu>,
+    throw_targeting: Res<ThrowTargeting>,
+    mut sneaking: ResMut<Sneaking>,
+    mut player_query: Query<&mut TextureAtlasSprite, With<Player>>,
+    mut message_log: ResMut<MessageLog>,
+) {
+    if !keyboard.just_pressed(KeyCode::Q) || conversation.is_active() || inventory_menu.open || throw_targeting.is_aiming() {
+        return;
+    }
+    sneaking.0 = !sneaking.0;
+    if let Ok(mut sprite) = player_query.get_single_mut() {
+        sprite.color.set_a(if sneaking.0 { SNEAKING_ALPHA } else { 1.0 });
+    }
+    message_log.add_message(if sneaking.0 {
+        "You start to sneak. Your steps are quiet, but slow.".to_string()
+    } else {
+        "You stop sneaking.".to_string()
+    });
+}
@@ -1,25 +1,66 @@
 use bevy::prelude::*;
 use crate::input::InputState;
 use crate::components::{Position, Player, Npc, DialogBox};
+use crate::conversation::Conversation;
+use crate::events::PlayerMoved;
+use crate::camera::{CameraControl, FocusTarget};
 
+// Walking away from an NPC mid-conversation ends it and puts the camera back
 pub fn check_dialog_distance(
-    mut npc_query: Query<(&Position, &mut Npc)>,
-    player_query: Query<&Position, With<Player>>,
+    mut moved_events: EventReader<PlayerMoved>,
+    mut npc_query: Query<(Entity, &Position, &mut Npc)>,
+    mut conversation: ResMut<Conversation>,
+    mut camera_query: Query<(&mut CameraControl, &mut Transform), Without<Player>>,
 ) {
-    let player_pos = if let Ok(pos) = player_query.get_single() {
-        pos
+    let player_pos = if let Some(moved) = moved_events.read().last() {
+        *moved
     } else {
-        return; 
+        return;
     };
 
-    for (npc_pos, mut npc) in npc_query.iter_mut() {
+    for (entity, npc_pos, mut npc) in npc_query.iter_mut() {
         let dx = (npc_pos.x - player_pos.x).abs();
         let dy = (npc_pos.y - player_pos.y).abs();
 
         // If NPC is speaking and player moves too far away, stop dialog
         if npc.speaking && (dx > 1 || dy > 1) {
             npc.speaking = false;
+
+            if conversation.speaker == Some(entity) {
+                conversation.end();
+                if let Ok((mut camera_control, mut camera_transform)) = camera_query.get_single_mut() {
+                    camera_control.end_dialog(entity, &mut camera_transform);
+                }
+            }
+        }
+    }
+}
+
+// The conversation has one speaker: any other NPC still talking is cut off, and the camera lets go of
+// conversations that ended without a goodbye (the speaker turned hostile, or is gone)
+pub fn enforce_single_conversation(
+    mut conversation: ResMut<Conversation>,
+    mut npc_query: Query<(Entity, &mut Npc)>,
+    mut camera_query: Query<(&mut CameraControl, &mut Transform), Without<Player>>,
+) {
+    if let Some(speaker) = conversation.speaker {
+        if !npc_query.get(speaker).map_or(false, |(_, npc)| npc.speaking) {
+            conversation.end();
+        }
+    }
+
+    for (entity, mut npc) in npc_query.iter_mut() {
+        if npc.speaking && conversation.speaker != Some(entity) {
+            npc.speaking = false;
+        }
+    }
+
+    let (mut camera_control, mut camera_transform) = if let Ok(camera) = camera_query.get_single_mut() { camera } else { return; };
+    while let Some(FocusTarget::Conversation(speaker)) = camera_control.current_focus() {
+        if conversation.speaker == Some(speaker) {
+            break;
         }
+        camera_control.end_dialog(speaker, &mut camera_transform);
     }
 }
 
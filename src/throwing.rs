@@ -0,0 +1,355 @@
+use bevy::prelude::*;
+
+use crate::aoe::{area_tiles, AreaPreview, AreaShape};
+use crate::assets::{get_item_sprite, SpriteAssets, TextureAtlases};
+use crate::combat::{trace_projectile_path, Health, ATTACK_REPUTATION_PENALTY};
+use crate::components::{GameTurn, Npc, Player, Position};
+use crate::events::{EntityDamaged, PlayerAttacked};
+use crate::faction::{Faction, ReputationChange};
+use crate::hearing::{NoiseEvent, NoiseKind};
+use crate::identify::{with_article, ItemAppearances};
+use crate::input::{TileClick, TILE_SIZE};
+use crate::inventory::{Inventory, ItemKind};
+use crate::map::TileMap;
+use crate::player::hop_offset;
+use crate::status::{ApplyStatusEffect, StatusEffect, StatusEffects, StatusKind};
+use crate::ui::MessageLog;
+use crate::visibility::line_of_sight;
+
+// Furthest an item can be thrown, in tiles
+const THROW_RANGE: i32 = 6;
+const DAGGER_DAMAGE: i32 = 4;
+// Seconds in the air per tile travelled
+const THROW_SECONDS_PER_TILE: f32 = 0.07;
+// How high the arc rises for each tile travelled
+const ARC_HEIGHT_PER_TILE: f32 = 6.0;
+const SPIN_SPEED: f32 = 12.0;
+// Tiles around where a potion lands that it splashes
+const SPLASH_RADIUS: i32 = 1;
+const SPLASH_SECONDS: f32 = 0.4;
+const SPLASH_HEAL: i32 = 5;
+const SPLASH_POISON_TURNS: u32 = 4;
+
+/// The item being aimed, and the tile it's aimed at, while choosing where to throw
+#[derive(Resource, Default)]
+pub struct ThrowTargeting {
+    pub item: Option<ItemKind>,
+    pub cursor: (i32, i32),
+}
+
+impl ThrowTargeting {
+    // Movement keys move the cursor instead of the player
+    pub fn is_aiming(&self) -> bool {
+        self.item.is_some()
+    }
+
+    pub fn begin(&mut self, item: ItemKind, from: (i32, i32)) {
+        self.item = Some(item);
+        self.cursor = from;
+    }
+}
+
+/// The highlight on the tile being aimed at
+#[derive(Component)]
+pub struct ThrowCursor;
+
+/// An item in flight
+#[derive(Component)]
+pub struct Thrown {
+    item: ItemKind,
+    from: Vec3,
+    to: Vec3,
+    landing: (i32, i32),
+    target: Option<Entity>,
+    arc_height: f32,
+    damage: i32,
+    timer: Timer,
+}
+
+/// A potion's splash fading out
+#[derive(Component)]
+pub struct Splash {
+    timer: Timer,
+}
+
+fn tile_center(tile: (i32, i32), z: f32) -> Vec3 {
+    Vec3::new(tile.0 as f32 * TILE_SIZE + TILE_SIZE / 2.0, tile.1 as f32 * TILE_SIZE + TILE_SIZE / 2.0, z)
+}
+
+fn potion_color(item: ItemKind) -> Color {
+    match item {
+        ItemKind::HealingPotion => Color::rgba(1.0, 0.3, 0.4, 0.6),
+        ItemKind::ManaPotion => Color::rgba(0.3, 0.5, 1.0, 0.6),
+        ItemKind::PoisonPotion => Color::rgba(0.4, 0.9, 0.3, 0.6),
+        _ => Color::rgba(0.9, 0.9, 0.9, 0.6),
+    }
+}
+
+pub fn setup_throw_cursor(mut commands: Commands) {
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(1.0, 0.9, 0.3, 0.35),
+                custom_size: Some(Vec2::splat(TILE_SIZE)),
+                ..default()
+            },
+            transform: Transform::from_xyz(0.0, 0.0, 11.0),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        ThrowCursor,
+    ));
+}
+
+// System to aim a throw: movement keys or the mouse pick the tile, E throws, Backspace puts it away
+pub fn throw_targeting_system(
+    mut commands: Commands,
+    mut keyboard: ResMut<Input<KeyCode>>,
+    tile_click: Res<TileClick>,
+    mut targeting: ResMut<ThrowTargeting>,
+    mut player_query: Query<(&Position, &mut Inventory, Option<&StatusEffects>), With<Player>>,
+    creature_query: Query<(Entity, &Position), (With<Health>, Without<Player>)>,
+    map: Res<TileMap>,
+    texture_atlases: Res<TextureAtlases>,
+    sprite_assets: Res<SpriteAssets>,
+    appearances: Res<ItemAppearances>,
+    mut game_turn: ResMut<GameTurn>,
+    mut message_log: ResMut<MessageLog>,
+    mut noise_events: EventWriter<NoiseEvent>,
+) {
+    let item = if let Some(item) = targeting.item { item } else { return; };
+    let (player_pos, mut inventory, status) = if let Ok(player) = player_query.get_single_mut() { player } else { return; };
+
+    if keyboard.just_pressed(KeyCode::Back) {
+        targeting.item = None;
+        return;
+    }
+
+    let (mut x, mut y) = targeting.cursor;
+    if keyboard.just_pressed(KeyCode::W) || keyboard.just_pressed(KeyCode::Up) {
+        y += 1;
+    }
+    if keyboard.just_pressed(KeyCode::S) || keyboard.just_pressed(KeyCode::Down) {
+        y -= 1;
+    }
+    if keyboard.just_pressed(KeyCode::A) || keyboard.just_pressed(KeyCode::Left) {
+        x -= 1;
+    }
+    if keyboard.just_pressed(KeyCode::D) || keyboard.just_pressed(KeyCode::Right) {
+        x += 1;
+    }
+    // The cursor can't wander further than anything could be thrown
+    targeting.cursor = (
+        x.clamp(player_pos.x - THROW_RANGE, player_pos.x + THROW_RANGE),
+        y.clamp(player_pos.y - THROW_RANGE, player_pos.y + THROW_RANGE),
+    );
+
+    let clicked = tile_click.tile;
+    let confirm_keys = [KeyCode::E, KeyCode::Space, KeyCode::Return];
+    let target_tile = if let Some(tile) = clicked {
+        tile
+    } else if let Some(&key) = confirm_keys.iter().find(|key| keyboard.just_pressed(**key)) {
+        // Thrown, not also used on whatever is next to the player
+        keyboard.clear_just_pressed(key);
+        targeting.cursor
+    } else {
+        return;
+    };
+
+    let from = (player_pos.x, player_pos.y);
+    if target_tile == from {
+        return;
+    }
+    if (target_tile.0 - from.0).abs().max((target_tile.1 - from.1).abs()) > THROW_RANGE {
+        message_log.add_message("That's too far to throw".to_string());
+        return;
+    }
+    if !map.in_bounds(target_tile.0, target_tile.1) || !line_of_sight(&map, from, target_tile) {
+        message_log.add_message("You can't see a clear way to throw there".to_string());
+        return;
+    }
+
+    // Flies until it hits a wall or a creature
+    let creatures: Vec<(Entity, (i32, i32))> = creature_query.iter().map(|(entity, position)| (entity, (position.x, position.y))).collect();
+    let (path, target) = trace_projectile_path(&map, from, target_tile, THROW_RANGE, &creatures);
+    let landing = path.last().copied().unwrap_or(from);
+
+    inventory.remove(item);
+    targeting.item = None;
+    game_turn.increment();
+    noise_events.send(NoiseEvent { x: player_pos.x, y: player_pos.y, kind: NoiseKind::Combat });
+    message_log.add_message(format!("You throw the {}", appearances.display_name(item)));
+
+    let tiles = path.len().max(1) as f32;
+    let sprite = if item == ItemKind::Dagger { "dagger" } else { "purple potion" };
+    commands.spawn((
+        SpriteSheetBundle {
+            texture_atlas: texture_atlases.items.clone(),
+            sprite: TextureAtlasSprite {
+                index: get_item_sprite(&sprite_assets, sprite),
+                ..default()
+            },
+            transform: Transform::from_translation(tile_center(from, 12.0)).with_scale(Vec3::splat(0.6)),
+            ..default()
+        },
+        Thrown {
+            item,
+            from: tile_center(from, 12.0),
+            to: tile_center(landing, 12.0),
+            landing,
+            target,
+            arc_height: ARC_HEIGHT_PER_TILE * tiles,
+            // Blessings and curses reach a thrown dagger as they do bolts
+            damage: (DAGGER_DAMAGE + status.map_or(0, |status| status.damage_modifier())).max(1),
+            timer: Timer::from_seconds(THROW_SECONDS_PER_TILE * tiles, TimerMode::Once),
+        },
+    ));
+}
+
+// Keep the cursor highlight on the tile being aimed at, and preview where a potion would splash
+pub fn update_throw_cursor(
+    targeting: Res<ThrowTargeting>,
+    mut cursor_query: Query<(&mut Transform, &mut Visibility), With<ThrowCursor>>,
+    player_query: Query<&Position, With<Player>>,
+    creature_query: Query<(Entity, &Position), (With<Health>, Without<Player>)>,
+    map: Res<TileMap>,
+    mut preview: ResMut<AreaPreview>,
+) {
+    for (mut transform, mut visibility) in cursor_query.iter_mut() {
+        *visibility = if targeting.is_aiming() { Visibility::Visible } else { Visibility::Hidden };
+        transform.translation = tile_center(targeting.cursor, 11.0);
+    }
+
+    let player_pos = player_query.get_single().ok();
+    let tiles = match (targeting.item, player_pos) {
+        (Some(item), Some(player_pos)) if item != ItemKind::Dagger && targeting.cursor != (player_pos.x, player_pos.y) => {
+            // Lands where the throw would, short of whatever's in the way
+            let from = (player_pos.x, player_pos.y);
+            let creatures: Vec<(Entity, (i32, i32))> = creature_query.iter().map(|(entity, position)| (entity, (position.x, position.y))).collect();
+            let (path, _) = trace_projectile_path(&map, from, targeting.cursor, THROW_RANGE, &creatures);
+            let landing = path.last().copied().unwrap_or(from);
+            area_tiles(&map, landing, AreaShape::Circle { radius: SPLASH_RADIUS })
+        }
+        _ => Vec::new(),
+    };
+    preview.set_if_neq(AreaPreview { tiles });
+}
+
+// System to fly thrown items along their arc, and see what happens where they come down
+pub fn animate_thrown_items(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut thrown_query: Query<(Entity, &mut Thrown, &mut Transform)>,
+    mut creature_query: Query<(Entity, &Position, &mut Health, Option<&Npc>, Option<&Faction>, Option<&mut StatusEffects>, Option<&Player>)>,
+    map: Res<TileMap>,
+    mut appearances: ResMut<ItemAppearances>,
+    mut message_log: ResMut<MessageLog>,
+    mut damage_events: EventWriter<EntityDamaged>,
+    mut attack_events: EventWriter<PlayerAttacked>,
+    mut reputation_events: EventWriter<ReputationChange>,
+    mut status_events: EventWriter<ApplyStatusEffect>,
+) {
+    for (entity, mut thrown, mut transform) in thrown_query.iter_mut() {
+        thrown.timer.tick(time.delta());
+        let progress = thrown.timer.percent();
+        let position = thrown.from.lerp(thrown.to, progress);
+        transform.translation = Vec3::new(position.x, position.y + hop_offset(progress, thrown.arc_height), position.z);
+        transform.rotate_z(SPIN_SPEED * time.delta_seconds());
+
+        if !thrown.timer.finished() {
+            continue;
+        }
+        commands.entity(entity).despawn();
+
+        if thrown.item == ItemKind::Dagger {
+            let hit = thrown.target.and_then(|target| creature_query.get_mut(target).ok());
+            if let Some((target, _, mut health, npc, faction, _, _)) = hit {
+                let name = npc.map_or("the creature".to_string(), |npc| npc.name.clone());
+                health.take_damage(thrown.damage);
+                damage_events.send(EntityDamaged { target, amount: thrown.damage, source: "your dagger".to_string() });
+                attack_events.send(PlayerAttacked);
+                message_log.add_message(format!("Your dagger hits {} for {} damage", name, thrown.damage));
+                if let Some(faction) = faction {
+                    reputation_events.send(ReputationChange {
+                        faction: *faction,
+                        amount: ATTACK_REPUTATION_PENALTY,
+                        reason: format!("threw a dagger at {}", name),
+                    });
+                }
+            } else {
+                message_log.add_message("Your dagger clatters away into the dark".to_string());
+            }
+            continue;
+        }
+
+        // Potions shatter and splash everything close by, the player included
+        let item = thrown.item;
+        let looks = appearances.display_name(item);
+        message_log.add_message(format!("The {} shatters", looks));
+        // Walls keep the splash off whatever is behind them
+        let splash_tiles = area_tiles(&map, thrown.landing, AreaShape::Circle { radius: SPLASH_RADIUS });
+        for &tile in splash_tiles.iter() {
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: potion_color(item),
+                        custom_size: Some(Vec2::splat(TILE_SIZE)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(tile_center(tile, 11.0)),
+                    ..default()
+                },
+                Splash { timer: Timer::from_seconds(SPLASH_SECONDS, TimerMode::Once) },
+            ));
+        }
+
+        let mut splashed = 0;
+        for (target, pos, mut health, _, faction, status, player) in creature_query.iter_mut() {
+            if !splash_tiles.contains(&(pos.x, pos.y)) {
+                continue;
+            }
+            splashed += 1;
+            match item {
+                ItemKind::HealingPotion => health.heal(SPLASH_HEAL),
+                ItemKind::PoisonPotion => {
+                    status_events.send(ApplyStatusEffect { target, effect: StatusEffect::new(StatusKind::Poison, SPLASH_POISON_TURNS, 1) });
+                    if let (Some(faction), None) = (faction, player) {
+                        reputation_events.send(ReputationChange {
+                            faction: *faction,
+                            amount: ATTACK_REPUTATION_PENALTY,
+                            reason: "splashed with poison".to_string(),
+                        });
+                    }
+                }
+                ItemKind::Antidote => {
+                    if let Some(mut status) = status {
+                        status.effects.retain(|effect| effect.kind != StatusKind::Poison);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Seeing what the splash did is as good as drinking it
+        if splashed > 0 && appearances.identify(item) {
+            message_log.add_message(format!("That was {}!", with_article(item.get_name())));
+        }
+    }
+}
+
+// System to fade out potion splashes
+pub fn fade_splashes(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut splash_query: Query<(Entity, &mut Sprite, &mut Splash)>,
+) {
+    for (entity, mut sprite, mut splash) in splash_query.iter_mut() {
+        splash.timer.tick(time.delta());
+        if splash.timer.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let alpha = 0.6 * splash.timer.percent_left();
+        sprite.color.set_a(alpha);
+    }
+}
@@ -0,0 +1,43 @@
+use bevy::prelude::*;
+
+use crate::biome::TileAnimationDef;
+
+/// Cycles a tile sprite through a list of atlas indices
+#[derive(Component, Debug, Clone)]
+pub struct TileAnimation {
+    pub frames: Vec<usize>,
+    pub current: usize,
+    pub timer: Timer,
+}
+
+impl TileAnimation {
+    // Build the animation for a registered tile, starting at a given frame so
+    // neighbouring tiles don't all pulse in lockstep
+    pub fn from_def(def: &TileAnimationDef, start_frame: usize) -> Self {
+        Self {
+            frames: def.frames.clone(),
+            current: start_frame % def.frames.len().max(1),
+            timer: Timer::from_seconds(def.frame_time, TimerMode::Repeating),
+        }
+    }
+
+    pub fn current_index(&self) -> usize {
+        self.frames[self.current]
+    }
+}
+
+// System to advance every animated tile
+pub fn animate_tiles(
+    time: Res<Time>,
+    mut query: Query<(&mut TileAnimation, &mut TextureAtlasSprite)>,
+) {
+    for (mut animation, mut sprite) in query.iter_mut() {
+        animation.timer.tick(time.delta());
+        if !animation.timer.just_finished() || animation.frames.is_empty() {
+            continue;
+        }
+
+        animation.current = (animation.current + 1) % animation.frames.len();
+        sprite.index = animation.current_index();
+    }
+}
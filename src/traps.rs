@@ -0,0 +1,79 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::aoe::{area_tiles, AreaShape};
+use crate::combat::Health;
+use crate::components::{Player, Position};
+use crate::events::{EntityDamaged, TileEntered};
+use crate::map::TileMap;
+use crate::rng::GameRng;
+use crate::status::{ApplyStatusEffect, StatusEffect, StatusKind};
+use crate::ui::MessageLog;
+use crate::level::DungeonState;
+
+// Base damage of a sprung trap, plus a bit more every few levels
+const TRAP_DAMAGE: i32 = 2;
+const TRAP_DAMAGE_LEVELS: usize = 3;
+// Chance a trap is a gas vent rather than a plain spike
+const POISON_TRAP_CHANCE: f64 = 0.3;
+// How far the gas spreads from the vent, poisoning whoever breathes it
+const GAS_RADIUS: i32 = 1;
+const GAS_POISON_TURNS: u32 = 4;
+
+// System to spring hidden traps the player steps on
+pub fn trigger_traps_system(
+    mut tile_events: EventReader<TileEntered>,
+    mut player_query: Query<&mut Health, With<Player>>,
+    creature_query: Query<(Entity, &Position), (With<Health>, Without<Player>)>,
+    mut map: ResMut<TileMap>,
+    mut dungeon_state: ResMut<DungeonState>,
+    mut status_events: EventWriter<ApplyStatusEffect>,
+    mut damage_events: EventWriter<EntityDamaged>,
+    mut message_log: ResMut<MessageLog>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    for entered in tile_events.read() {
+        let mut health = if let Ok(health) = player_query.get_mut(entered.entity) {
+            health
+        } else {
+            continue;
+        };
+        if entered.x < 0 || entered.y < 0 {
+            continue;
+        }
+        let tile = (entered.x as usize, entered.y as usize);
+        if !map.trap_positions.contains(&tile) {
+            continue;
+        }
+
+        let damage = TRAP_DAMAGE + (map.current_level / TRAP_DAMAGE_LEVELS) as i32;
+        health.take_damage(damage);
+
+        let source = if game_rng.combat.gen_bool(POISON_TRAP_CHANCE) {
+            message_log.add_message(format!("A vent bursts open, scalding you for {} damage! Poison gas billows out", damage));
+            // The gas catches anything standing close by on this side of a wall, not just the player
+            let cloud = area_tiles(&map, (entered.x, entered.y), AreaShape::Circle { radius: GAS_RADIUS });
+            let breathers = creature_query.iter()
+                .filter(|(_, position)| cloud.contains(&(position.x, position.y)))
+                .map(|(entity, _)| entity);
+            for target in std::iter::once(entered.entity).chain(breathers) {
+                status_events.send(ApplyStatusEffect {
+                    target,
+                    effect: StatusEffect::new(StatusKind::Poison, GAS_POISON_TURNS, 1),
+                });
+            }
+            "a gas vent"
+        } else {
+            message_log.add_message(format!("Spikes spring from the floor for {} damage!", damage));
+            "a spike trap"
+        };
+        damage_events.send(EntityDamaged { target: entered.entity, amount: damage, source: source.to_string() });
+
+        // A trap only fires once, even if the level is revisited
+        map.trap_positions.retain(|&pos| pos != tile);
+        let current_level = dungeon_state.current_level_index;
+        if let Some(level) = dungeon_state.levels.get_mut(current_level) {
+            level.trap_positions.retain(|&pos| pos != tile);
+        }
+    }
+}
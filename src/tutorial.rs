@@ -0,0 +1,177 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+use crate::chests::Chest;
+use crate::components::{Npc, Player, Position};
+use crate::map::{TileMap, TileType};
+
+/// File the tips already shown are kept in, so each only ever appears once
+pub const TUTORIAL_PATH: &str = "tutorial.json";
+// How close, in tiles, something has to be before its tip is shown
+const NOTICE_DISTANCE: i32 = 2;
+
+/// Things the game explains the first time the player comes across them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TutorialTopic {
+    Movement,
+    Npc,
+    Stairs,
+    Door,
+    Chest,
+}
+
+impl TutorialTopic {
+    fn tip(&self) -> &'static str {
+        match self {
+            TutorialTopic::Movement => "Move with WASD or the arrow keys. Hold Shift to run, or press O to explore on your own.",
+            TutorialTopic::Npc => "Someone is nearby. Walk up to them and press E to talk; number keys pick your replies.",
+            TutorialTopic::Stairs => "Stairs! Stand on them and press E, or use Ctrl+S to go down and Ctrl+W to go up.",
+            TutorialTopic::Door => "A door. Walk into it or press E beside it to open it.",
+            TutorialTopic::Chest => "A chest. Press E next to it to open it; locked ones need a lockpick.",
+        }
+    }
+}
+
+/// Which tips the player has seen, across every run
+#[derive(Resource, Debug, Default, Serialize, Deserialize)]
+pub struct TutorialProgress {
+    pub seen: Vec<TutorialTopic>,
+    #[serde(skip)]
+    pending: Vec<TutorialTopic>, // Noticed, waiting for the tip on screen to be dismissed
+}
+
+impl TutorialProgress {
+    // Read saved progress, starting fresh if there's none or it can't be read
+    pub fn load() -> Self {
+        match fs::read_to_string(TUTORIAL_PATH) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                eprintln!("Ignoring unreadable {}: {}", TUTORIAL_PATH, e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let contents = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(TUTORIAL_PATH, contents).map_err(|e| format!("could not write {}: {}", TUTORIAL_PATH, e))
+    }
+
+    // Queue a tip unless it's been seen or is already waiting
+    fn notice(&mut self, topic: TutorialTopic) {
+        if !self.seen.contains(&topic) && !self.pending.contains(&topic) {
+            self.pending.push(topic);
+        }
+    }
+}
+
+/// The tip currently on screen
+#[derive(Component, Debug)]
+pub struct TutorialTip {
+    topic: TutorialTopic,
+}
+
+// Whether any tile within NOTICE_DISTANCE of the player is one of these
+fn tile_nearby(map: &TileMap, player: &Position, wanted: &[TileType]) -> bool {
+    (-NOTICE_DISTANCE..=NOTICE_DISTANCE).any(|dy| {
+        (-NOTICE_DISTANCE..=NOTICE_DISTANCE).any(|dx| {
+            let (x, y) = (player.x + dx, player.y + dy);
+            map.in_bounds(x, y) && wanted.contains(&map.tiles[y as usize][x as usize])
+        })
+    })
+}
+
+fn near(player: &Position, other: &Position) -> bool {
+    (other.x - player.x).abs().max((other.y - player.y).abs()) <= NOTICE_DISTANCE
+}
+
+// System to notice the things a new player should be told about
+pub fn notice_tutorial_topics(
+    mut progress: ResMut<TutorialProgress>,
+    map: Res<TileMap>,
+    player_query: Query<&Position, With<Player>>,
+    npc_query: Query<&Position, (With<Npc>, Without<Player>)>,
+    chest_query: Query<(&Position, &Chest), Without<Player>>,
+) {
+    let player = if let Ok(player) = player_query.get_single() { player } else { return; };
+
+    // Everyone needs to know how to walk before anything else
+    progress.notice(TutorialTopic::Movement);
+    if npc_query.iter().any(|position| near(player, position)) {
+        progress.notice(TutorialTopic::Npc);
+    }
+    if tile_nearby(&map, player, &[TileType::StairsDown, TileType::StairsUp]) {
+        progress.notice(TutorialTopic::Stairs);
+    }
+    if tile_nearby(&map, player, &[TileType::Door]) {
+        progress.notice(TutorialTopic::Door);
+    }
+    if chest_query.iter().any(|(position, chest)| !chest.opened && near(player, position)) {
+        progress.notice(TutorialTopic::Chest);
+    }
+}
+
+// System to show the next waiting tip once nothing else is on screen
+pub fn show_tutorial_tip(
+    mut commands: Commands,
+    mut progress: ResMut<TutorialProgress>,
+    asset_server: Res<AssetServer>,
+    tip_query: Query<(), With<TutorialTip>>,
+) {
+    if !tip_query.is_empty() || progress.pending.is_empty() {
+        return;
+    }
+    let topic = progress.pending.remove(0);
+    let font = asset_server.load("fonts/FiraSans-Light.ttf");
+
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(120.0),
+                left: Val::Percent(20.0),
+                width: Val::Percent(60.0),
+                padding: UiRect::all(Val::Px(12.0)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.0),
+                ..default()
+            },
+            background_color: BackgroundColor(Color::rgba(0.05, 0.05, 0.1, 0.9)),
+            z_index: ZIndex::Global(150),
+            ..default()
+        },
+        TutorialTip { topic },
+    ))
+    .with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            topic.tip(),
+            TextStyle { font: font.clone(), font_size: 18.0, color: Color::WHITE },
+        ));
+        parent.spawn(TextBundle::from_section(
+            "Tab - Got it",
+            TextStyle { font, font_size: 14.0, color: Color::GRAY },
+        ));
+    });
+}
+
+// System to close the tip on Tab and remember it was read
+pub fn dismiss_tutorial_tip(
+    mut commands: Commands,
+    keyboard: Res<Input<KeyCode>>,
+    mut progress: ResMut<TutorialProgress>,
+    tip_query: Query<(Entity, &TutorialTip)>,
+) {
+    if !keyboard.just_pressed(KeyCode::Tab) {
+        return;
+    }
+    for (entity, tip) in tip_query.iter() {
+        commands.entity(entity).despawn_recursive();
+        if !progress.seen.contains(&tip.topic) {
+            progress.seen.push(tip.topic);
+        }
+    }
+    if let Err(e) = progress.save() {
+        eprintln!("Could not save tutorial progress: {}", e);
+    }
+}
@@ -1,17 +1,207 @@
 use bevy::prelude::*;
+use bevy::text::{Text2dBundle, Text, TextStyle, TextAlignment};
+
+use crate::components::{GameTurn, TurnCounter, TurnCounterVisibility};
+use crate::input::{InputState, TILE_SIZE};
+use crate::map::{VIEWPORT_HEIGHT, VIEWPORT_WIDTH};
+use crate::debug_grid::toggle_grid_visibility;
+use crate::{GameState, GameplaySet};
+
+/// Everything drawn over the world: the HUD and message log, tooltips, toasts,
+/// and the screens outside a run (menus, codex, run summary)
+pub struct UiPlugin;
+
+impl Plugin for UiPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TurnCounterVisibility>()
+            .init_resource::<MessageLog>()
+            .init_resource::<crate::debug_grid::DebugGrid>()
+            .init_resource::<crate::debug_overlay::DebugOverlay>()
+            .insert_resource(crate::accessibility::AccessibilityLog::from_args())
+            .insert_resource(crate::display_settings::DisplaySettings::from_args())
+            .insert_resource(crate::tutorial::TutorialProgress::load())
+            .init_resource::<crate::help::KeyBindings>()
+            .add_event::<crate::emotes::ShowEmote>()
+            .add_systems(OnEnter(GameState::InGame), (
+                setup_turn_counter,
+                setup_ui,
+                crate::gold::setup_gold_hud,
+                crate::run_modifiers::setup_modifier_hud,
+                crate::cutscene::reset_cutscene,
+            ).run_if(crate::loading_screen::starting_run))
+            .add_systems(
+                Update,
+                (
+                    toggle_grid_visibility.in_set(GameplaySet::Presentation),
+                    crate::debug_grid::sync_debug_grid_layers.after(toggle_grid_visibility),
+                    toggle_turn_counter_visibility.in_set(GameplaySet::Presentation),
+                    update_turn_counter
+                        .after(toggle_turn_counter_visibility)
+                        .in_set(GameplaySet::Presentation),
+                    update_message_log.after(crate::status::tick_status_effects_system),
+                    crate::inspect::inspect_hover_system.in_set(GameplaySet::Presentation),
+                    crate::gold::update_gold_hud,
+                    crate::loading_screen::finish_resuming,
+                )
+                .run_if(in_state(GameState::InGame))
+            )
+            .add_systems(
+                Update,
+                (
+                    crate::debug_overlay::record_debug_events,
+                    crate::debug_overlay::record_entity_churn,
+                    crate::debug_overlay::toggle_debug_overlay,
+                    crate::debug_overlay::update_debug_overlay.after(crate::debug_overlay::toggle_debug_overlay),
+                    crate::debug_overlay::debug_click_system,
+                    crate::debug_overlay::print_inspected_components.after(crate::debug_overlay::debug_click_system),
+                )
+                .run_if(in_state(GameState::InGame))
+                .run_if(resource_exists::<crate::map::TileMap>())
+            )
+            .add_systems(
+                Update,
+                (
+                    crate::accessibility::announce_game_events
+                        .after(crate::input::move_player)
+                        .after(crate::combat::despawn_dead_entities)
+                        .run_if(resource_exists::<crate::map::TileMap>()),
+                    crate::accessibility::announce_messages.after(update_message_log),
+                    crate::accessibility::announce_conversation.after(crate::dialog_box::render_dialog_boxes),
+                    crate::accessibility::announce_menus
+                        .after(crate::interaction::dispatch_interactions)
+                        .after(crate::inventory_panel::inventory_input_system),
+                )
+                .run_if(in_state(GameState::InGame))
+                .run_if(crate::accessibility::accessibility_enabled)
+            )
+            .add_systems(Update, crate::accessibility::announce_screens.run_if(crate::accessibility::accessibility_enabled))
+            .add_systems(
+                Update,
+                (
+                    crate::display_settings::toggle_display_settings,
+                    crate::display_settings::sync_contrast_outlines.after(crate::display_settings::toggle_display_settings),
+                    crate::display_settings::hide_unseen_stairs_outlines.after(crate::display_settings::sync_contrast_outlines),
+                    crate::screen_text::update_screen_text
+                        .after(crate::display_settings::toggle_display_settings)
+                        .after(crate::dialog_box::render_dialog_boxes)
+                        .after(crate::inspect::inspect_hover_system)
+                        .after(crate::camera::follow_camera),
+                    crate::zoom_markers::attach_zoom_markers,
+                    crate::zoom_markers::update_zoom_markers
+                        .after(crate::zoom_markers::attach_zoom_markers)
+                        .after(crate::camera::update_camera_zoom),
+                )
+                .run_if(in_state(GameState::InGame))
+            )
+            .add_systems(
+                Update,
+                (
+                    crate::tutorial::notice_tutorial_topics,
+                    crate::tutorial::dismiss_tutorial_tip,
+                    crate::tutorial::show_tutorial_tip
+                        .after(crate::tutorial::notice_tutorial_topics)
+                        .after(crate::tutorial::dismiss_tutorial_tip),
+                )
+                .run_if(in_state(GameState::InGame))
+                .run_if(resource_exists::<crate::map::TileMap>())
+            )
+            // An unread tip goes with the run; it comes back the next time its topic does
+            .add_systems(OnExit(GameState::InGame), (
+                crate::menu::despawn_screen::<crate::tutorial::TutorialTip>,
+                crate::menu::despawn_screen::<crate::screen_text::ScreenText>,
+                crate::menu::despawn_screen::<crate::help::HelpOverlay>,
+                crate::menu::despawn_screen::<crate::depth_map::DepthMapOverlay>,
+            ))
+            .add_systems(
+                Update,
+                (
+                    crate::emotes::queue_emotes
+                        .after(crate::animals::move_animals_system)
+                        .after(crate::animals::feed_animal_system)
+                        .after(crate::faction::update_npc_hostility),
+                    crate::emotes::update_emotes.after(crate::emotes::queue_emotes),
+                )
+                .run_if(in_state(GameState::InGame))
+            )
+            .add_systems(
+                Update,
+                (
+                    crate::help::sync_key_bindings,
+                    crate::help::toggle_help_overlay.after(crate::help::sync_key_bindings),
+                    crate::depth_map::toggle_depth_map.before(crate::tutorial::dismiss_tutorial_tip),
+                )
+                .run_if(in_state(GameState::InGame))
+            )
+            // Toasts outlive the run so a last-moment unlock still shows on the summary screen
+            .add_systems(
+                Update,
+                (
+                    crate::achievements::show_achievement_toasts,
+                    crate::achievements::update_achievement_toasts,
+                )
+            )
+            // Cutscenes pause the run under a letterbox, then hand it back where it was
+            .init_resource::<crate::cutscene::Cutscene>()
+            .add_systems(
+                Update,
+                (
+                    crate::cutscene::queue_intro_cutscene,
+                    crate::cutscene::discover_biomes_system.after(crate::cutscene::queue_intro_cutscene),
+                    crate::cutscene::queue_boss_intro.after(crate::input::move_player),
+                    crate::cutscene::start_cutscene
+                        .after(crate::cutscene::queue_intro_cutscene)
+                        .after(crate::cutscene::discover_biomes_system)
+                        .after(crate::cutscene::queue_boss_intro)
+                        .after(crate::level::handle_stairs_system)
+                        .after(crate::run_summary::finish_run_system),
+                )
+                .run_if(in_state(GameState::InGame))
+                .run_if(resource_exists::<crate::map::TileMap>())
+            )
+            .add_systems(OnEnter(GameState::Cutscene), crate::cutscene::setup_cutscene_screen)
+            .add_systems(Update, crate::cutscene::play_cutscene.run_if(in_state(GameState::Cutscene)))
+            .add_systems(OnExit(GameState::Cutscene), crate::cutscene::finish_cutscene)
+            .init_resource::<crate::loading_screen::LoadingScreen>()
+            .add_systems(OnEnter(GameState::LoadingLevel), crate::loading_screen::setup_loading_screen)
+            .add_systems(Update, crate::loading_screen::update_loading_screen.run_if(in_state(GameState::LoadingLevel)))
+            .add_systems(OnExit(GameState::LoadingLevel), crate::menu::despawn_screen::<crate::loading_screen::LoadingScreenRoot>)
+            .add_systems(OnEnter(GameState::RunOver), crate::run_summary::setup_run_summary_screen)
+            .add_systems(OnEnter(GameState::MainMenu), crate::menu::setup_main_menu)
+            .add_systems(
+                Update,
+                (
+                    crate::menu::main_menu_system,
+                    crate::run_modifiers::update_modifier_menu.after(crate::menu::main_menu_system),
+                )
+                .run_if(in_state(GameState::MainMenu))
+            )
+            .add_systems(OnExit(GameState::MainMenu), crate::menu::despawn_screen::<crate::menu::MainMenuScreen>)
+            .add_systems(OnEnter(GameState::HallOfRecords), crate::menu::setup_hall_of_records)
+            .add_systems(Update, crate::menu::hall_of_records_system.run_if(in_state(GameState::HallOfRecords)))
+            .add_systems(OnExit(GameState::HallOfRecords), crate::menu::despawn_screen::<crate::menu::HallOfRecordsScreen>)
+            .add_systems(OnEnter(GameState::Codex), crate::codex::setup_codex_screen)
+            .add_systems(Update, crate::codex::codex_screen_system.run_if(in_state(GameState::Codex)))
+            .add_systems(OnExit(GameState::Codex), crate::menu::despawn_screen::<crate::codex::CodexScreen>);
+    }
+}
 
 // Maximum number of messages to keep in history
 const MAX_MESSAGES: usize = 50;
 
+// Number of messages shown in the log panel
+const VISIBLE_MESSAGES: usize = 3;
+
 #[derive(Resource)]
 pub struct MessageLog {
     messages: Vec<String>,
+    total: usize, // Every message ever added, including those dropped off the end
 }
 
 impl Default for MessageLog {
     fn default() -> Self {
         let mut log = MessageLog {
             messages: Vec::new(),
+            total: 0,
         };
         log.add_message("Welcome to Chasm!".to_string());
         log
@@ -21,12 +211,27 @@ impl Default for MessageLog {
 impl MessageLog {
     pub fn add_message(&mut self, message: String) {
         self.messages.push(message);
+        self.total += 1;
         if self.messages.len() > MAX_MESSAGES {
             self.messages.remove(0);
         }
     }
+
+    // The most recent messages, oldest first
+    pub fn recent(&self, count: usize) -> &[String] {
+        let start = self.messages.len().saturating_sub(count);
+        &self.messages[start..]
+    }
+
+    pub fn total(&self) -> usize {
+        self.total
+    }
 }
 
+// Marker for the text entity showing the latest messages
+#[derive(Component)]
+pub struct MessageLogText;
+
 pub fn setup_ui(mut commands: Commands) {
     let text_style = TextStyle {
         font_size: 16.0,
@@ -96,18 +301,21 @@ pub fn setup_ui(mut commands: Commands) {
                 });
 
                 // Message text centered
-                parent.spawn(TextBundle {
-                    text: Text::from_section(
-                        "Welcome to Chasm!",
-                        text_style.clone(),
-                    ),
-                    style: Style {
-                        position_type: PositionType::Absolute,
-                        left: Val::Px(20.0),
+                parent.spawn((
+                    TextBundle {
+                        text: Text::from_section(
+                            "Welcome to Chasm!",
+                            text_style.clone(),
+                        ),
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            left: Val::Px(20.0),
+                            ..default()
+                        },
                         ..default()
                     },
-                    ..default()
-                });
+                    MessageLogText,
+                ));
             });
 
             // Message border - bottom
@@ -127,11 +335,80 @@ pub fn setup_ui(mut commands: Commands) {
 
 pub fn update_message_log(
     message_log: Res<MessageLog>,
-    mut query: Query<&mut Text>,
+    mut query: Query<&mut Text, With<MessageLogText>>,
 ) {
+    if !message_log.is_changed() {
+        return;
+    }
+
     if let Ok(mut text) = query.get_single_mut() {
-        let messages = message_log.messages.join("\n");
+        let messages = message_log.recent(VISIBLE_MESSAGES).join("\n");
         text.sections[0].value = messages;
     }
 }
 
+// Add a system to setup the turn counter UI
+pub fn setup_turn_counter(mut commands: Commands, asset_server: Res<AssetServer>) {
+    // Create the turn counter text at the top of the screen
+    commands.spawn((
+        // Use a Text2dBundle for in-world rendering
+        Text2dBundle {
+            text: Text::from_section(
+                "Turn: 0",
+                TextStyle {
+                    font: asset_server.load("fonts/FiraSans-Light.ttf"),
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                },
+            )
+            .with_alignment(TextAlignment::Center),
+            // Position at the top center of the screen
+            transform: Transform::from_xyz(
+                (VIEWPORT_WIDTH as f32 * TILE_SIZE) / 2.0,
+                (VIEWPORT_HEIGHT as f32 * TILE_SIZE) - 20.0,
+                100.0, // High z-index to ensure it's on top
+            ),
+            // Initially hidden
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        TurnCounter,
+    ));
+}
+
+// Add a system to toggle the turn counter visibility
+pub fn toggle_turn_counter_visibility(
+    input_state: Res<InputState>,
+    mut turn_counter_visibility: ResMut<TurnCounterVisibility>,
+    mut turn_counter_query: Query<&mut Visibility, With<TurnCounter>>,
+) {
+    // Toggle visibility when SHIFT+T is pressed
+    if input_state.toggle_turn_counter {
+        turn_counter_visibility.visible = !turn_counter_visibility.visible;
+        
+        // Update the visibility of the turn counter UI
+        for mut visibility in turn_counter_query.iter_mut() {
+            *visibility = if turn_counter_visibility.visible {
+                Visibility::Visible
+            } else {
+                Visibility::Hidden
+            };
+        }
+        
+        println!("Turn counter visibility toggled: {}", turn_counter_visibility.visible);
+    }
+}
+
+// Add a system to update the turn counter text
+pub fn update_turn_counter(
+    game_turn: Res<GameTurn>,
+    turn_counter_visibility: Res<TurnCounterVisibility>,
+    mut turn_counter_query: Query<&mut Text, With<TurnCounter>>,
+) {
+    // Only update if the turn counter is visible
+    if turn_counter_visibility.visible {
+        for mut text in turn_counter_query.iter_mut() {
+            text.sections[0].value = format!("Turn: {}", game_turn.current_turn);
+        }
+    }
+}
@@ -0,0 +1,299 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+
+use crate::map::{grid_size, tile_from_char, TileType};
+
+/// Folder vault templates live in, relative to the assets folder
+pub const VAULT_DIR: &str = "vaults";
+
+// Chance a level gets a vault at all, and how hard to look for room to fit one
+const VAULT_CHANCE: f64 = 0.35;
+const PLACEMENT_ATTEMPTS: usize = 80;
+
+/// What a template cell turns into when stamped
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum VaultCell {
+    Keep,            // ' ' leaves the map as it was
+    Tile(TileType),  // The same characters as map text files
+    Chest,           // 'C' floor with a chest
+    Npc,             // 'N' floor where an NPC stands
+    Monster,         // 'M' floor where a monster waits
+}
+
+/// A hand-authored room from assets/vaults
+#[derive(Debug, Clone)]
+pub struct VaultTemplate {
+    pub name: String,
+    pub weight: u32,
+    pub min_level: usize,
+    cells: Vec<Vec<VaultCell>>, // Indexed [y][x], y up like the map
+}
+
+/// A vault stamped into a map, with the spawn points it marked
+#[derive(Debug, Clone)]
+pub struct PlacedVault {
+    pub name: String,
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    pub chests: Vec<(usize, usize)>,
+    pub npcs: Vec<(usize, usize)>,
+    pub monsters: Vec<(usize, usize)>,
+}
+
+impl PlacedVault {
+    // Whether a tile lies inside the stamped template
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+impl VaultTemplate {
+    // Parse a template: `key: value` header lines, a `---` line, then the rows top first
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (header, grid) = text.split_once("\n---").ok_or("vault has no '---' line between header and grid")?;
+
+        let mut name = None;
+        let mut weight = 1;
+        let mut min_level = 0;
+        for line in header.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            let (key, value) = line.split_once(':').ok_or_else(|| format!("header line '{}' is not 'key: value'", line))?;
+            let value = value.trim();
+            match key.trim() {
+                "name" => name = Some(value.to_string()),
+                "weight" => weight = value.parse().map_err(|_| format!("bad weight '{}'", value))?,
+                "min_level" => min_level = value.parse().map_err(|_| format!("bad min_level '{}'", value))?,
+                other => return Err(format!("unknown header key '{}'", other)),
+            }
+        }
+
+        let rows: Vec<&str> = grid.lines().skip(1).filter(|row| !row.trim().is_empty()).collect();
+        let width = rows.iter().map(|row| row.trim_end().chars().count()).max().unwrap_or(0);
+        if width == 0 {
+            return Err("vault grid is empty".to_string());
+        }
+
+        let mut cells = Vec::new();
+        for row in rows.iter().rev() {
+            let mut cell_row = Vec::new();
+            for c in row.trim_end().chars() {
+                let cell = match c {
+                    ' ' => VaultCell::Keep,
+                    'C' => VaultCell::Chest,
+                    'N' => VaultCell::Npc,
+                    'M' => VaultCell::Monster,
+                    other => VaultCell::Tile(tile_from_char(other).ok_or_else(|| format!("unknown vault cell '{}'", other))?),
+                };
+                cell_row.push(cell);
+            }
+            // Short rows are padded with cells that leave the map alone
+            cell_row.resize(width, VaultCell::Keep);
+            cells.push(cell_row);
+        }
+
+        Ok(Self {
+            name: name.ok_or("vault has no name")?,
+            weight,
+            min_level,
+            cells,
+        })
+    }
+
+    fn width(&self) -> usize {
+        self.cells[0].len()
+    }
+
+    fn height(&self) -> usize {
+        self.cells.len()
+    }
+
+    // Floor and door cells on the outer edge, where corridors can join the vault
+    fn entrances(&self) -> Vec<(usize, usize, i32, i32)> {
+        let (width, height) = (self.width(), self.height());
+        let mut entrances = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                let walkable = matches!(self.cells[y][x], VaultCell::Tile(TileType::Floor | TileType::Door | TileType::SecretDoor));
+                if !walkable {
+                    continue;
+                }
+                // The direction pointing out of the vault
+                let outward = if x == 0 {
+                    Some((-1, 0))
+                } else if x == width - 1 {
+                    Some((1, 0))
+                } else if y == 0 {
+                    Some((0, -1))
+                } else if y == height - 1 {
+                    Some((0, 1))
+                } else {
+                    None
+                };
+                if let Some((dx, dy)) = outward {
+                    entrances.push((x, y, dx, dy));
+                }
+            }
+        }
+        entrances
+    }
+}
+
+// Read every template under assets/vaults; broken files are reported and skipped
+pub fn load_vaults() -> Vec<VaultTemplate> {
+    let dir = Path::new("assets").join(VAULT_DIR);
+    let mut paths: Vec<_> = match fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().map_or(false, |ext| ext == "txt"))
+            .collect(),
+        Err(e) => {
+            eprintln!("Could not read vault folder {}: {}", dir.display(), e);
+            return Vec::new();
+        }
+    };
+    // Sorted so the same seed always sees the templates in the same order
+    paths.sort();
+
+    paths.iter()
+        .filter_map(|path| {
+            let parsed = fs::read_to_string(path)
+                .map_err(|e| e.to_string())
+                .and_then(|text| VaultTemplate::parse(&text));
+            match parsed {
+                Ok(template) => Some(template),
+                Err(e) => {
+                    eprintln!("Skipping vault {}: {}", path.display(), e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+// Carve the shortest path from a tile outside the vault to the nearest open floor, never crossing the vault
+fn connect_to_floor(tiles: &mut [Vec<TileType>], start: (usize, usize), vault: &PlacedVault) -> bool {
+    let (map_width, map_height) = grid_size(tiles);
+
+    let mut came_from = vec![vec![None; map_width]; map_height];
+    let mut queue = VecDeque::from([start]);
+    came_from[start.1][start.0] = Some(start);
+
+    while let Some((x, y)) = queue.pop_front() {
+        if tiles[y][x] == TileType::Floor && (x, y) != start {
+            // Walk back to the start, opening up the way
+            let mut current = (x, y);
+            while current != start {
+                current = came_from[current.1][current.0].unwrap_or(start);
+                if tiles[current.1][current.0] == TileType::Wall {
+                    tiles[current.1][current.0] = TileType::Floor;
+                }
+            }
+            return true;
+        }
+
+        for (dx, dy) in [(0, 1), (1, 0), (0, -1), (-1, 0)] {
+            let nx = x as i32 + dx;
+            let ny = y as i32 + dy;
+            // Stay off the outermost ring so the map keeps its border
+            if nx < 1 || ny < 1 || nx >= map_width as i32 - 1 || ny >= map_height as i32 - 1 {
+                continue;
+            }
+            let (nx, ny) = (nx as usize, ny as usize);
+            if came_from[ny][nx].is_none() && !vault.contains(nx, ny) {
+                came_from[ny][nx] = Some((x, y));
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+    false
+}
+
+// Maybe stamp one of the templates, intact, into solid rock and join it to the rest of the level
+pub fn place_vault(tiles: &mut [Vec<TileType>], templates: &[VaultTemplate], level: usize, rng: &mut impl Rng) -> Option<PlacedVault> {
+    if !rng.gen_bool(VAULT_CHANCE) {
+        return None;
+    }
+
+    let eligible: Vec<&VaultTemplate> = templates.iter().filter(|template| template.min_level <= level).collect();
+    let template = *eligible.choose_weighted(rng, |template| template.weight).ok()?;
+    let (map_width, map_height) = grid_size(tiles);
+    let (width, height) = (template.width(), template.height());
+    if width + 4 > map_width || height + 4 > map_height {
+        return None;
+    }
+
+    for _ in 0..PLACEMENT_ATTEMPTS {
+        let x0 = rng.gen_range(2..=map_width - width - 2);
+        let y0 = rng.gen_range(2..=map_height - height - 2);
+
+        // Only solid rock (with a one-tile margin) so no existing room or corridor is cut
+        let clear = (y0 - 1..y0 + height + 1).all(|y| (x0 - 1..x0 + width + 1).all(|x| tiles[y][x] == TileType::Wall));
+        if !clear {
+            continue;
+        }
+
+        let mut stamped = tiles.to_vec();
+        let mut placed = PlacedVault {
+            name: template.name.clone(),
+            x: x0,
+            y: y0,
+            width,
+            height,
+            chests: Vec::new(),
+            npcs: Vec::new(),
+            monsters: Vec::new(),
+        };
+        for (vy, row) in template.cells.iter().enumerate() {
+            for (vx, cell) in row.iter().enumerate() {
+                let (x, y) = (x0 + vx, y0 + vy);
+                match cell {
+                    VaultCell::Keep => {}
+                    VaultCell::Tile(tile) => stamped[y][x] = *tile,
+                    VaultCell::Chest => {
+                        stamped[y][x] = TileType::Floor;
+                        placed.chests.push((x, y));
+                    }
+                    VaultCell::Npc => {
+                        stamped[y][x] = TileType::Floor;
+                        placed.npcs.push((x, y));
+                    }
+                    VaultCell::Monster => {
+                        stamped[y][x] = TileType::Floor;
+                        placed.monsters.push((x, y));
+                    }
+                }
+            }
+        }
+
+        // Every entrance gets a way in; give up on this spot if none can be reached
+        let mut connected = false;
+        for (vx, vy, dx, dy) in template.entrances() {
+            let outside = ((x0 + vx) as i32 + dx, (y0 + vy) as i32 + dy);
+            if outside.0 < 1 || outside.1 < 1 || outside.0 >= map_width as i32 - 1 || outside.1 >= map_height as i32 - 1 {
+                continue;
+            }
+            let outside = (outside.0 as usize, outside.1 as usize);
+            let mut trial = stamped.clone();
+            trial[outside.1][outside.0] = TileType::Floor;
+            if connect_to_floor(&mut trial, outside, &placed) {
+                stamped = trial;
+                connected = true;
+            }
+        }
+        if !connected {
+            continue;
+        }
+
+        for (row, stamped_row) in tiles.iter_mut().zip(stamped) {
+            *row = stamped_row;
+        }
+        println!("Placed vault '{}' at ({}, {})", placed.name, x0, y0);
+        return Some(placed);
+    }
+
+    None
+}
@@ -0,0 +1,164 @@
+use bevy::prelude::*;
+
+use crate::components::{Player, Position};
+use crate::conversation::Conversation;
+use crate::input::TILE_SIZE;
+use crate::inventory_panel::InventoryMenu;
+use crate::map::{TileMap, VIEWPORT_HEIGHT, VIEWPORT_WIDTH};
+use crate::throwing::ThrowTargeting;
+
+// Toggles look mode from the keyboard
+const LOOK_KEY: KeyCode = KeyCode::V;
+// How far the right stick has to be pushed before the cursor moves
+const STICK_DEADZONE: f32 = 0.5;
+// Seconds between cursor steps while the stick is held over
+const STICK_REPEAT_SECONDS: f32 = 0.15;
+
+/// A tile cursor that stands in for the mouse, so tiles can be inspected (and throws aimed) from the
+/// keyboard or a gamepad. V or the right stick starts looking; Backspace, V or clicking the right stick stops
+#[derive(Resource, Debug, Default)]
+pub struct VirtualCursor {
+    pub tile: Option<(i32, i32)>, // Set while looking
+}
+
+impl VirtualCursor {
+    // Movement keys move the cursor instead of the player
+    pub fn is_looking(&self) -> bool {
+        self.tile.is_some()
+    }
+}
+
+/// The highlight on the tile being looked at
+#[derive(Component)]
+pub struct LookCursor;
+
+pub fn setup_look_cursor(mut commands: Commands, mut cursor: ResMut<VirtualCursor>) {
+    cursor.tile = None;
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(0.4, 0.9, 1.0, 0.3),
+                custom_size: Some(Vec2::splat(TILE_SIZE)),
+                ..default()
+            },
+            transform: Transform::from_xyz(0.0, 0.0, 11.0),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        LookCursor,
+    ));
+}
+
+// Keep a cursor on the map and within the screen around the player
+fn clamp_to_view(tile: (i32, i32), player: &Position, map: &TileMap) -> (i32, i32) {
+    let (half_width, half_height) = (VIEWPORT_WIDTH as i32 / 2, VIEWPORT_HEIGHT as i32 / 2);
+    (
+        tile.0.clamp(player.x - half_width, player.x + half_width).clamp(0, map.width as i32 - 1),
+        tile.1.clamp(player.y - half_height, player.y + half_height).clamp(0, map.height as i32 - 1),
+    )
+}
+
+// System to start and stop looking, and step the cursor with the movement keys
+pub fn look_mode_system(
+    keyboard: Res<Input<KeyCode>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    gamepads: Res<Gamepads>,
+    mut cursor: ResMut<VirtualCursor>,
+    conversation: Res<Conversation>,
+    inventory_menu: Res<InventoryMenu>,
+    throw_targeting: Res<ThrowTargeting>,
+    map: Res<TileMap>,
+    player_query: Query<&Position, With<Player>>,
+) {
+    let player = if let Ok(player) = player_query.get_single() { player } else { return; };
+    let stick_clicked = gamepads
+        .iter()
+        .any(|gamepad| gamepad_buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::RightThumb)));
+
+    if cursor.is_looking() {
+        if keyboard.just_pressed(LOOK_KEY) || keyboard.just_pressed(KeyCode::Back) || stick_clicked {
+            cursor.tile = None;
+        }
+    } else if (keyboard.just_pressed(LOOK_KEY) || stick_clicked)
+        && !conversation.is_active()
+        && !inventory_menu.open
+        && !throw_targeting.is_aiming()
+    {
+        cursor.tile = Some((player.x, player.y));
+    }
+
+    let (mut x, mut y) = if let Some(tile) = cursor.tile { tile } else { return; };
+    if keyboard.just_pressed(KeyCode::W) || keyboard.just_pressed(KeyCode::Up) {
+        y += 1;
+    }
+    if keyboard.just_pressed(KeyCode::S) || keyboard.just_pressed(KeyCode::Down) {
+        y -= 1;
+    }
+    if keyboard.just_pressed(KeyCode::A) || keyboard.just_pressed(KeyCode::Left) {
+        x -= 1;
+    }
+    if keyboard.just_pressed(KeyCode::D) || keyboard.just_pressed(KeyCode::Right) {
+        x += 1;
+    }
+    cursor.tile = Some(clamp_to_view((x, y), player, &map));
+}
+
+// System to move a cursor with the right stick: the throw cursor while aiming, otherwise the look cursor,
+// starting to look if nothing was
+pub fn stick_cursor_system(
+    time: Res<Time>,
+    gamepads: Res<Gamepads>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut cursor: ResMut<VirtualCursor>,
+    mut throw_targeting: ResMut<ThrowTargeting>,
+    conversation: Res<Conversation>,
+    inventory_menu: Res<InventoryMenu>,
+    map: Res<TileMap>,
+    player_query: Query<&Position, With<Player>>,
+    mut cooldown: Local<f32>,
+) {
+    let player = if let Ok(player) = player_query.get_single() { player } else { return; };
+    let stick = gamepads
+        .iter()
+        .map(|gamepad| {
+            let x = axes.get(GamepadAxis::new(gamepad, GamepadAxisType::RightStickX)).unwrap_or(0.0);
+            let y = axes.get(GamepadAxis::new(gamepad, GamepadAxisType::RightStickY)).unwrap_or(0.0);
+            Vec2::new(x, y)
+        })
+        .find(|stick| stick.x.abs() > STICK_DEADZONE || stick.y.abs() > STICK_DEADZONE);
+
+    // Letting go lets the next push step straight away
+    let stick = if let Some(stick) = stick { stick } else {
+        *cooldown = 0.0;
+        return;
+    };
+    *cooldown -= time.delta_seconds();
+    if *cooldown > 0.0 {
+        return;
+    }
+    *cooldown = STICK_REPEAT_SECONDS;
+
+    let step = |value: f32| if value > STICK_DEADZONE { 1 } else if value < -STICK_DEADZONE { -1 } else { 0 };
+    let (dx, dy) = (step(stick.x), step(stick.y));
+    if throw_targeting.is_aiming() {
+        // The throw itself keeps its cursor in range
+        throw_targeting.cursor = (throw_targeting.cursor.0 + dx, throw_targeting.cursor.1 + dy);
+    } else if let Some((x, y)) = cursor.tile {
+        cursor.tile = Some(clamp_to_view((x + dx, y + dy), player, &map));
+    } else if !conversation.is_active() && !inventory_menu.open {
+        cursor.tile = Some(clamp_to_view((player.x + dx, player.y + dy), player, &map));
+    }
+}
+
+// Keep the highlight on the tile being looked at
+pub fn update_look_cursor(
+    cursor: Res<VirtualCursor>,
+    mut cursor_query: Query<(&mut Transform, &mut Visibility), With<LookCursor>>,
+) {
+    for (mut transform, mut visibility) in cursor_query.iter_mut() {
+        *visibility = if cursor.is_looking() { Visibility::Visible } else { Visibility::Hidden };
+        if let Some((x, y)) = cursor.tile {
+            transform.translation = Vec3::new(x as f32 * TILE_SIZE + TILE_SIZE / 2.0, y as f32 * TILE_SIZE + TILE_SIZE / 2.0, 11.0);
+        }
+    }
+}
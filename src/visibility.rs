@@ -1,22 +1,97 @@
 use bevy::prelude::*;
+use crate::biome::BiomeType;
+use crate::components::{Player, Position};
+use crate::lighting::LightMap;
 use crate::map::{TileMap, TileType, MAP_WIDTH, MAP_HEIGHT};
+
+// How much of an entity's sight a pitch-black floor takes away; lit tiles can still be seen out to the full radius
+const DARKNESS_SIGHT_LOSS: f32 = 0.6;
+// Light level (brightest channel) at which a tile counts as lit
+const LIT_THRESHOLD: f32 = 0.5;
 #[derive(Component, Default)]
 pub struct TileVisibility {
     pub visible: bool,
     pub previously_seen: bool,
 }
 
-#[derive(Component, Default)]
-pub struct PlayerVisibility {
-    pub range: f32,
+/// How far an entity can see on a well-lit floor, in tiles. Darker floors cut it down; see `Vision::radius_to`
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Vision {
+    pub radius: f32,
+}
+
+impl Default for Vision {
+    // The player's
+    fn default() -> Self {
+        Self { radius: 8.0 }
+    }
+}
+
+impl Vision {
+    // How far this entity can make things out in the floor's darkness, without a light on them
+    pub fn in_darkness(&self, darkness: f32) -> f32 {
+        self.radius * (1.0 - darkness * DARKNESS_SIGHT_LOSS)
+    }
+
+    // How far away this entity could see `tile` from: the full radius when the tile is lit, less when it's dark
+    pub fn radius_to(&self, darkness: f32, light_map: Option<&LightMap>, tile: (i32, i32)) -> f32 {
+        if light_map.map_or(false, |light_map| is_lit(light_map, tile)) {
+            self.radius
+        } else {
+            self.in_darkness(darkness)
+        }
+    }
+}
+
+// How dark the current floor is, from 0.0 (bright as day) to 1.0: deeper floors have less ambient light,
+// and some biomes are gloomier than others
+pub fn floor_darkness(map: &TileMap) -> f32 {
+    let biome_shade = match map.get_biome_at(0, 0) { // All maps currently use a single biome
+        BiomeType::Groves => -0.1, // Open to what little sky there is
+        BiomeType::Caves => 0.0,
+        BiomeType::Labyrinth => 0.05,
+        BiomeType::Catacombs => 0.15,
+    };
+    (1.0 - map.depth_tier.ambient_light + biome_shade).clamp(0.0, 1.0)
+}
+
+pub fn is_lit(light_map: &LightMap, tile: (i32, i32)) -> bool {
+    let light = light_map.get(tile.0, tile.1);
+    light.r().max(light.g()).max(light.b()) >= LIT_THRESHOLD
 }
 
-#[derive(Resource, Default)]
+#[derive(Resource, Default, Clone)]
 pub struct VisibilityMap {
     pub visible_tiles: Vec<Vec<bool>>,
     pub previously_seen: Vec<Vec<bool>>,
 }
 
+impl VisibilityMap {
+    // Start over with nothing seen when a map of a different size is loaded
+    pub fn fit_to(&mut self, map: &TileMap) {
+        if self.visible_tiles.len() != map.height || self.visible_tiles.first().map_or(0, |row| row.len()) != map.width {
+            self.reset_to(map);
+        }
+    }
+
+    // Forget everything seen, e.g. when the level is replaced by a fresh layout
+    pub fn reset_to(&mut self, map: &TileMap) {
+        self.visible_tiles = vec![vec![false; map.width]; map.height];
+        self.previously_seen = vec![vec![false; map.width]; map.height];
+    }
+
+    // Fold what's in sight now into what's been seen, e.g. before the level is stored away
+    pub fn forget_current_sight(&mut self) {
+        for (visible_row, seen_row) in self.visible_tiles.iter_mut().zip(self.previously_seen.iter_mut()) {
+            for (visible, seen) in visible_row.iter_mut().zip(seen_row.iter_mut()) {
+                *seen |= *visible;
+                *visible = false;
+            }
+        }
+    }
+}
+
+// System to start a run with nothing seen
 pub fn setup_visibility_map(mut commands: Commands) {
     let visibility_map = VisibilityMap {
         visible_tiles: vec![vec![false; MAP_WIDTH]; MAP_HEIGHT],
@@ -25,35 +100,19 @@ pub fn setup_visibility_map(mut commands: Commands) {
     commands.insert_resource(visibility_map);
 }
 
-pub fn update_tile_visibility(
-    visibility_map: Res<VisibilityMap>,
-    mut query: Query<&mut TileVisibility>,
-) {
-    for (i, mut tile_vis) in query.iter_mut().enumerate() {
-        let x = i % MAP_WIDTH;
-        let y = i / MAP_WIDTH;
-        
-        // Add bounds checking to prevent index out of bounds errors
-        if y < MAP_HEIGHT && x < MAP_WIDTH {
-            tile_vis.visible = visibility_map.visible_tiles[y][x];
-            if tile_vis.visible {
-                tile_vis.previously_seen = true;
-            }
-        } else {
-            // Default to not visible if out of bounds
-            tile_vis.visible = false;
-        }
-    }
-}
-
+// System to work out what the player can see from their tile: everything in their field of view
+// out to their vision radius when it's lit, and less of it on darker floors
 pub fn update_visibility(
     mut visibility_map: ResMut<VisibilityMap>,
-    query: Query<(&Transform, &PlayerVisibility)>,
+    query: Query<(&Position, &Vision), With<Player>>,
     map: Res<TileMap>,
+    light_map: Option<Res<LightMap>>,
 ) {
+    visibility_map.fit_to(&map);
+
     // Store current visible tiles in previously_seen
-    for y in 0..MAP_HEIGHT {
-        for x in 0..MAP_WIDTH {
+    for y in 0..map.height {
+        for x in 0..map.width {
             if visibility_map.visible_tiles[y][x] {
                 visibility_map.previously_seen[y][x] = true;
             }
@@ -61,57 +120,89 @@ pub fn update_visibility(
         }
     }
 
-    for (transform, visibility) in query.iter() {
-        let pos = transform.translation;
-        let player_pos = (
-            (pos.x / 32.0).round() as i32,
-            (pos.y / 32.0).round() as i32
-        );
-        
-        // Cast rays in a 360-degree arc
-        for angle in 0..360 {
-            let rad = angle as f32 * 0.0174533;
-            let end_x = player_pos.0 + (visibility.range * rad.cos()) as i32;
-            let end_y = player_pos.1 + (visibility.range * rad.sin()) as i32;
-            cast_ray(player_pos.0, player_pos.1, end_x, end_y, &mut visibility_map, &map);
+    let darkness = floor_darkness(&map);
+    for (position, vision) in query.iter() {
+        let player_pos = (position.x, position.y);
+        // Out past what can be made out in the dark, only lit tiles show up
+        for (x, y) in visible_tiles_from(&map, player_pos, vision.radius) {
+            let distance = (((x - player_pos.0).pow(2) + (y - player_pos.1).pow(2)) as f32).sqrt();
+            if distance <= vision.radius_to(darkness, light_map.as_deref(), (x, y)) {
+                visibility_map.visible_tiles[y as usize][x as usize] = true;
+            }
         }
     }
 }
 
-
-fn cast_ray(
-    start_x: i32,
-    start_y: i32,
-    end_x: i32,
-    end_y: i32,
-    visibility_map: &mut VisibilityMap,
-    map: &TileMap,
-) {
-    let points = bresenham_line(start_x, start_y, end_x, end_y);
-    
-    for point in points {
-        if point.0 >= 0 && point.0 < MAP_WIDTH as i32 && 
-        point.1 >= 0 && point.1 < MAP_HEIGHT as i32 {
-            visibility_map.visible_tiles[point.1 as usize][point.0 as usize] = true;
-            
+// Every tile that can be seen from `origin` out to `radius`: rays cast all the way round, each
+// stopping at the first wall (which is itself seen). The same field of view for the player and AI
+pub fn visible_tiles_from(map: &TileMap, origin: (i32, i32), radius: f32) -> Vec<(i32, i32)> {
+    let mut seen = vec![vec![false; map.width]; map.height];
+    let mut tiles = Vec::new();
+    for angle in 0..360 {
+        let rad = (angle as f32).to_radians();
+        let end_x = origin.0 + (radius * rad.cos()) as i32;
+        let end_y = origin.1 + (radius * rad.sin()) as i32;
+        for (x, y) in bresenham_line(origin.0, origin.1, end_x, end_y) {
+            if !map.in_bounds(x, y) {
+                break;
+            }
+            if !seen[y as usize][x as usize] {
+                seen[y as usize][x as usize] = true;
+                tiles.push((x, y));
+            }
             // Stop if we hit a wall
-            if map.tiles[point.1 as usize][point.0 as usize] == TileType::Wall {
+            if map.tiles[y as usize][x as usize] == TileType::Wall {
                 break;
             }
-        } else {
-            break;
         }
     }
+    tiles
+}
+
+// Whether there's an unobstructed line between two tiles. The one sight check shared by the
+// player's targeting, ranged attacks, monster vision and auto-explore
+pub fn line_of_sight(map: &TileMap, from: (i32, i32), to: (i32, i32)) -> bool {
+    let points = bresenham_line(from.0, from.1, to.0, to.1);
+    
+    // The end points themselves never block (you can see a wall you're looking at)
+    for &(x, y) in points.iter().skip(1) {
+        if (x, y) == to {
+            return true;
+        }
+        if blocks_sight(x, y, map) {
+            return false;
+        }
+    }
+    true
+}
+
+// Whether a creature looking along `facing` can see a tile: FOV the other way
+// round. Within range, inside its vision cone (or close enough to sense) and
+// not behind a wall
+pub fn in_field_of_view(map: &TileMap, from: (i32, i32), facing: (i32, i32), to: (i32, i32), range: i32, cone_cos: f32) -> bool {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    if dx.abs() + dy.abs() > range {
+        return false;
+    }
+    // Right next to something, it's noticed whichever way the creature faces
+    let adjacent = dx.abs().max(dy.abs()) <= 1;
+    if !adjacent {
+        let length = ((dx * dx + dy * dy) as f32).sqrt() * ((facing.0 * facing.0 + facing.1 * facing.1) as f32).sqrt();
+        if length == 0.0 || ((dx * facing.0 + dy * facing.1) as f32) / length < cone_cos {
+            return false;
+        }
+    }
+    line_of_sight(map, from, to)
 }
 
-fn blocks_sight(x: i32, y: i32, map: &TileMap) -> bool {
-    if x < 0 || x >= MAP_WIDTH as i32 || y < 0 || y >= MAP_HEIGHT as i32 {
+pub fn blocks_sight(x: i32, y: i32, map: &TileMap) -> bool {
+    if !map.in_bounds(x, y) {
         return true;
     }
     map.tiles[y as usize][x as usize] == TileType::Wall
 }
 
-fn bresenham_line(x0: i32, y0: i32, x1: i32, y1: i32) -> Vec<(i32, i32)> {
+pub fn bresenham_line(x0: i32, y0: i32, x1: i32, y1: i32) -> Vec<(i32, i32)> {
     let mut points = Vec::new();
     let dx = (x1 - x0).abs();
     let dy = -(y1 - y0).abs();
@@ -139,3 +230,42 @@ fn bresenham_line(x0: i32, y0: i32, x1: i32, y1: i32) -> Vec<(i32, i32)> {
     points
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clear_line_is_seen() {
+        let map = TileMap::test_floor(10, 5, &[]);
+        assert!(line_of_sight(&map, (1, 1), (8, 3)));
+    }
+
+    #[test]
+    fn wall_in_the_way_blocks_sight() {
+        let map = TileMap::test_floor(10, 5, &[(4, 1)]);
+        assert!(!line_of_sight(&map, (1, 1), (8, 1)));
+        // The wall itself can still be seen
+        assert!(line_of_sight(&map, (1, 1), (4, 1)));
+    }
+
+    #[test]
+    fn ray_stops_at_the_first_wall() {
+        let walls: Vec<(usize, usize)> = (0..10).map(|y| (5, y)).collect();
+        let map = TileMap::test_floor(10, 10, &walls);
+        let seen = visible_tiles_from(&map, (2, 4), 8.0);
+        assert!(seen.contains(&(4, 4)));
+        assert!(seen.contains(&(5, 4)));
+        assert!(!seen.iter().any(|&(x, _)| x > 5));
+    }
+
+    #[test]
+    fn out_of_bounds_endpoints_are_handled() {
+        let map = TileMap::test_floor(10, 5, &[]);
+        assert!(!line_of_sight(&map, (1, 1), (15, 1)));
+        assert!(!line_of_sight(&map, (1, 1), (-3, 1)));
+        let seen = visible_tiles_from(&map, (0, 0), 6.0);
+        assert!(!seen.is_empty());
+        assert!(seen.iter().all(|&(x, y)| map.in_bounds(x, y)));
+    }
+}
@@ -0,0 +1,233 @@
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::assets::{get_monster_sprite, SpriteAssets, TextureAtlases};
+use crate::combat::{CombatStats, Health};
+use crate::components::{GameTurn, Npc, Player, Position};
+use crate::events::{EntityDamaged, LevelChanged};
+use crate::faction::Hostile;
+use crate::input::TILE_SIZE;
+use crate::interaction::{Interactable, InteractionKind};
+use crate::map::TileMap;
+use crate::pathmaps::PathMaps;
+use crate::rng::GameRng;
+use crate::scent::{ScentMap, MIN_SCENT};
+use crate::spawn_director::respawn_candidates;
+use crate::ui::MessageLog;
+use crate::visibility::{line_of_sight, VisibilityMap};
+use crate::world_facts::WorldFacts;
+
+pub const WARDEN_NAME: &str = "The Warden of the Chasm";
+const WARDEN_SPRITE: &str = "reaper";
+// First (zero-based) level the Warden can turn up on
+const WARDEN_MIN_LEVEL: usize = 2;
+// Chance it comes looking on a floor the player arrives on
+const WARDEN_APPEAR_CHANCE: f64 = 0.25;
+// Turns after the player arrives before it shows itself
+const WARDEN_ARRIVAL_TURNS: std::ops::RangeInclusive<u32> = 30..=120;
+// Turns it takes to follow the player down (or up) the stairs
+const WARDEN_FOLLOW_TURNS: u32 = 15;
+// Floors it stays away once beaten down
+const WARDEN_BANISHED_FLOORS: usize = 3;
+// How far it can see the player to make straight for them; beyond that it goes by scent
+const WARDEN_SIGHT_RANGE: i32 = 8;
+
+/// The one Warden of the Chasm. It can't be killed, only banished for a few floors
+#[derive(Component, Debug)]
+pub struct Warden;
+
+/// What the Warden is up to across the whole run
+#[derive(Resource, Debug, Default)]
+pub struct WardenState {
+    pub present: bool,           // Stalking the current floor
+    pub health: Option<i32>,     // Carried from floor to floor; None means it comes back whole
+    pub arrives_on: Option<u32>, // The turn it will show itself on this floor
+    pub banished_until: usize,   // First level it can come back on
+}
+
+impl WardenState {
+    fn max_health(level: usize) -> i32 {
+        30 + level as i32 * 4
+    }
+
+    fn attack(level: usize) -> i32 {
+        3 + level as i32 / 3
+    }
+}
+
+// System to forget the Warden when a run starts
+pub fn reset_warden(mut state: ResMut<WardenState>) {
+    *state = WardenState::default();
+}
+
+// System to decide when the Warden shows up: it follows a player who fled it down the stairs, and otherwise
+// sometimes comes looking once they've been on a floor a while. Spawns it somewhere out of sight when it's time
+pub fn warden_arrival_system(
+    mut commands: Commands,
+    mut state: ResMut<WardenState>,
+    mut level_events: EventReader<LevelChanged>,
+    warden_query: Query<&Health, With<Warden>>,
+    player_query: Query<&Position, With<Player>>,
+    map: Res<TileMap>,
+    visibility_map: Option<Res<VisibilityMap>>,
+    texture_atlases: Res<TextureAtlases>,
+    sprite_assets: Res<SpriteAssets>,
+    game_turn: Res<GameTurn>,
+    mut game_rng: ResMut<GameRng>,
+    mut message_log: ResMut<MessageLog>,
+    mut facts: ResMut<WorldFacts>,
+    mut local: Local<u32>,
+) {
+    if let Some(event) = level_events.read().last() {
+        let followed = std::mem::take(&mut state.present);
+        state.arrives_on = if followed {
+            Some(game_turn.current_turn + WARDEN_FOLLOW_TURNS)
+        } else if event.to >= WARDEN_MIN_LEVEL && event.to >= state.banished_until && game_rng.spawns.gen_bool(WARDEN_APPEAR_CHANCE) {
+            Some(game_turn.current_turn + game_rng.spawns.gen_range(WARDEN_ARRIVAL_TURNS))
+        } else {
+            None
+        };
+    }
+    facts.warden_stalking = state.present;
+
+    // Only check once per turn
+    if game_turn.current_turn == 0 || game_turn.current_turn == *local {
+        return;
+    }
+    *local = game_turn.current_turn;
+
+    // Its wounds go with it to the next floor
+    if let Ok(health) = warden_query.get_single() {
+        state.health = Some(health.current);
+        return;
+    }
+    let arrives_on = if let Some(turn) = state.arrives_on { turn } else { return; };
+    if game_turn.current_turn < arrives_on || state.present {
+        return;
+    }
+    state.arrives_on = None;
+    // Boss floors belong to their guardians
+    if map.is_boss_level {
+        return;
+    }
+
+    let player = if let Ok(pos) = player_query.get_single() { *pos } else { return; };
+    let candidates = respawn_candidates(&map, &player, visibility_map.as_deref());
+    let (x, y) = if let Some(&pos) = candidates.choose(&mut game_rng.spawns) { pos } else { return; };
+
+    let max_health = WardenState::max_health(map.current_level);
+    let mut health = Health::new(max_health);
+    health.current = state.health.unwrap_or(max_health).clamp(1, max_health);
+    let lines = vec![
+        "You have gone deep enough.".to_string(),
+        "The Chasm keeps what falls into it.".to_string(),
+    ];
+
+    commands.spawn((
+        SpriteSheetBundle {
+            texture_atlas: texture_atlases.monsters.clone(),
+            sprite: TextureAtlasSprite {
+                index: get_monster_sprite(&sprite_assets, WARDEN_SPRITE),
+                color: Color::rgb(0.7, 0.75, 1.0),
+                ..default()
+            },
+            transform: Transform::from_xyz(
+                x as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+                y as f32 * TILE_SIZE + (TILE_SIZE / 2.0),
+                8.0 // Above animals, below the player
+            ).with_scale(Vec3::splat(1.3)),
+            ..default()
+        },
+        Warden,
+        Interactable::new(InteractionKind::Talk, format!("Talk to {}", WARDEN_NAME)),
+        Npc {
+            name: WARDEN_NAME.to_string(),
+            dialog_text: lines[0].clone(),
+            dialog: lines,
+            original_scale: Vec3::splat(1.3),
+            ..default()
+        },
+        Hostile,
+        health,
+        CombatStats { attack: WardenState::attack(map.current_level) },
+        Position::new(x, y),
+    ));
+    state.present = true;
+    facts.warden_stalking = true;
+
+    message_log.add_message("The air turns cold. The Warden of the Chasm is hunting you.".to_string());
+    println!("The Warden arrived at ({}, {}) on level {}", x, y, map.current_level);
+}
+
+// System to move the Warden: straight at the player when it can see them, along their scent when it can't,
+// and by the shortest path when the trail has gone cold. It strikes when next to them
+pub fn warden_stalk_system(
+    mut warden_query: Query<(&mut Position, &mut Transform, &Health, &CombatStats), (With<Warden>, Without<Player>)>,
+    mut player_query: Query<(Entity, &Position, &mut Health), (With<Player>, Without<Warden>)>,
+    path_maps: Res<PathMaps>,
+    scent_map: Res<ScentMap>,
+    map: Res<TileMap>,
+    game_turn: Res<GameTurn>,
+    mut damage_events: EventWriter<EntityDamaged>,
+    mut message_log: ResMut<MessageLog>,
+    mut local: Local<u32>,
+) {
+    // Only act once per turn
+    if game_turn.current_turn == 0 || game_turn.current_turn == *local {
+        return;
+    }
+    *local = game_turn.current_turn;
+
+    let (player_entity, player_pos, mut player_health) = if let Ok(player) = player_query.get_single_mut() { player } else { return; };
+    let (mut position, mut transform, health, stats) = if let Ok(warden) = warden_query.get_single_mut() { warden } else { return; };
+    if health.is_dead() {
+        return;
+    }
+
+    let here = (position.x, position.y);
+    let player_tile = (player_pos.x, player_pos.y);
+    if (here.0 - player_tile.0).abs() + (here.1 - player_tile.1).abs() <= 1 {
+        player_health.take_damage(stats.attack);
+        damage_events.send(EntityDamaged { target: player_entity, amount: stats.attack, source: WARDEN_NAME.to_string() });
+        message_log.add_message(format!("The Warden's scythe bites for {} damage", stats.attack));
+        return;
+    }
+
+    let sees_player = (here.0 - player_tile.0).abs().max((here.1 - player_tile.1).abs()) <= WARDEN_SIGHT_RANGE
+        && line_of_sight(&map, here, player_tile);
+    let next = if sees_player || scent_map.get(here.0, here.1) < MIN_SCENT {
+        path_maps.to_player.downhill_from(here.0, here.1)
+    } else {
+        scent_map.uphill_from(here.0, here.1).or_else(|| path_maps.to_player.downhill_from(here.0, here.1))
+    };
+
+    if let Some((x, y)) = next.filter(|&tile| tile != player_tile) {
+        position.x = x;
+        position.y = y;
+        transform.translation.x = x as f32 * TILE_SIZE + (TILE_SIZE / 2.0);
+        transform.translation.y = y as f32 * TILE_SIZE + (TILE_SIZE / 2.0);
+    }
+}
+
+// System to banish the Warden instead of letting it die: beaten down, it comes apart and stays away
+// for a few floors, coming back whole. despawn_dead_entities leaves it alone, so it's never counted as a kill
+pub fn banish_warden_system(
+    mut commands: Commands,
+    mut warden_query: Query<(Entity, &mut Health), With<Warden>>,
+    mut state: ResMut<WardenState>,
+    map: Res<TileMap>,
+    mut message_log: ResMut<MessageLog>,
+) {
+    for (entity, mut health) in warden_query.iter_mut() {
+        if !health.is_dead() {
+            continue;
+        }
+        health.current = health.max;
+        commands.entity(entity).despawn_recursive();
+        state.present = false;
+        state.health = None;
+        state.banished_until = map.current_level + 1 + WARDEN_BANISHED_FLOORS;
+        message_log.add_message("The Warden comes apart into cold smoke. It is not dead. It is never dead.".to_string());
+    }
+}
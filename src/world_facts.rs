@@ -0,0 +1,193 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::biome::BiomeType;
+use crate::combat::Health;
+use crate::components::{Player, Position};
+use crate::events::{CreatureKilled, LevelChanged};
+use crate::inventory::{Inventory, ItemKind};
+use crate::level::DungeonState;
+use crate::map::TileMap;
+
+/// What dialogue conditions can ask about the world, refreshed every frame
+#[derive(Resource, Debug, Default)]
+pub struct WorldFacts {
+    pub depth: usize, // One-based, as shown to the player
+    pub hp: i32,
+    pub max_hp: i32,
+    pub biome: Option<BiomeType>, // Where the player is standing
+    pub items: Vec<ItemKind>,
+    pub killed_this_floor: Vec<String>, // Lowercased names of what the player has killed since arriving
+    pub warden_stalking: bool,          // The Warden of the Chasm is on this floor
+}
+
+/// How a number in a condition is compared
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Comparison {
+    fn parse(op: &str) -> Result<Self, String> {
+        match op {
+            "<" => Ok(Comparison::Less),
+            "<=" => Ok(Comparison::LessOrEqual),
+            ">" => Ok(Comparison::Greater),
+            ">=" => Ok(Comparison::GreaterOrEqual),
+            "==" => Ok(Comparison::Equal),
+            "!=" => Ok(Comparison::NotEqual),
+            _ => Err(format!("unknown comparison '{}'", op)),
+        }
+    }
+
+    fn holds(self, left: i64, right: i64) -> bool {
+        match self {
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Greater => left > right,
+            Comparison::GreaterOrEqual => left >= right,
+            Comparison::Equal => left == right,
+            Comparison::NotEqual => left != right,
+        }
+    }
+}
+
+/// One test in a condition
+#[derive(Debug, Clone, PartialEq)]
+enum Test {
+    Depth(Comparison, i64),
+    Hp(Comparison, i64),
+    HpPercent(Comparison, i64),
+    Biome(BiomeType),
+    Has(String),    // Carries an item with this name
+    Killed(String), // Killed something with this in its name on the current floor
+    Warden,         // The Warden is hunting the player on this floor
+}
+
+impl Test {
+    fn holds(&self, facts: &WorldFacts) -> bool {
+        match self {
+            Test::Depth(comparison, value) => comparison.holds(facts.depth as i64, *value),
+            Test::Hp(comparison, value) => comparison.holds(facts.hp as i64, *value),
+            // Compared as hp * 100 against value * max to stay in whole numbers
+            Test::HpPercent(comparison, value) => comparison.holds(facts.hp as i64 * 100, value * facts.max_hp.max(1) as i64),
+            Test::Biome(biome) => facts.biome == Some(*biome),
+            Test::Has(name) => facts.items.iter().any(|item| item.get_name() == name),
+            Test::Killed(name) => facts.killed_this_floor.iter().any(|killed| killed.contains(name.as_str())),
+            Test::Warden => facts.warden_stalking,
+        }
+    }
+}
+
+/// When a line can be said, written in the dialogue file as clauses joined by `&&`, each optionally negated with `!`:
+/// `depth >= 3`, `hp < 50%`, `hp <= 5`, `biome == Catacombs`, `has:healing potion`, `killed:bear` or `warden`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Condition {
+    clauses: Vec<(bool, Test)>, // (negated, test)
+}
+
+impl Condition {
+    pub fn holds(&self, facts: &WorldFacts) -> bool {
+        self.clauses.iter().all(|(negated, test)| test.holds(facts) != *negated)
+    }
+}
+
+impl TryFrom<String> for Condition {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, String> {
+        let clauses = text
+            .split("&&")
+            .map(|clause| {
+                let clause = clause.trim();
+                let (negated, clause) = match clause.strip_prefix('!') {
+                    Some(rest) => (true, rest.trim()),
+                    None => (false, clause),
+                };
+                parse_test(clause).map(|test| (negated, test)).map_err(|e| format!("in condition '{}': {}", text, e))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Condition { clauses })
+    }
+}
+
+fn parse_test(clause: &str) -> Result<Test, String> {
+    if let Some(item) = clause.strip_prefix("has:") {
+        return Ok(Test::Has(item.trim().to_lowercase()));
+    }
+    if let Some(creature) = clause.strip_prefix("killed:") {
+        return Ok(Test::Killed(creature.trim().to_lowercase()));
+    }
+    if clause == "warden" {
+        return Ok(Test::Warden);
+    }
+
+    let parts: Vec<&str> = clause.split_whitespace().collect();
+    let (subject, op, value) = if let [subject, op, value] = parts.as_slice() {
+        (*subject, *op, *value)
+    } else {
+        return Err(format!("expected '<fact> <comparison> <value>', got '{}'", clause));
+    };
+    let comparison = Comparison::parse(op)?;
+    let number = |value: &str| value.parse::<i64>().map_err(|_| format!("'{}' is not a number", value));
+
+    match subject {
+        "depth" => Ok(Test::Depth(comparison, number(value)?)),
+        "hp" => match value.strip_suffix('%') {
+            Some(percent) => Ok(Test::HpPercent(comparison, number(percent)?)),
+            None => Ok(Test::Hp(comparison, number(value)?)),
+        },
+        "biome" => {
+            // Biomes are named the same way as everywhere else in the data files
+            let biome = serde_json::from_value::<BiomeType>(serde_json::Value::String(value.to_string()))
+                .map_err(|_| format!("unknown biome '{}'", value))?;
+            match comparison {
+                Comparison::Equal => Ok(Test::Biome(biome)),
+                _ => Err("biomes can only be compared with ==; use ! to negate".to_string()),
+            }
+        }
+        _ => Err(format!("unknown fact '{}'", subject)),
+    }
+}
+
+// System to start each run knowing nothing
+pub fn reset_world_facts(mut facts: ResMut<WorldFacts>) {
+    *facts = WorldFacts::default();
+}
+
+// System to keep the facts in step with the player and their surroundings
+pub fn update_world_facts(
+    mut facts: ResMut<WorldFacts>,
+    dungeon_state: Res<DungeonState>,
+    map: Res<TileMap>,
+    player_query: Query<(&Position, &Health, &Inventory), With<Player>>,
+) {
+    let (position, health, inventory) = if let Ok(player) = player_query.get_single() { player } else { return; };
+    facts.depth = dungeon_state.current_level_index + 1;
+    facts.hp = health.current;
+    facts.max_hp = health.max;
+    facts.biome = map
+        .in_bounds(position.x, position.y)
+        .then(|| map.get_biome_at(position.x as usize, position.y as usize));
+    facts.items = inventory.items.clone();
+}
+
+// System to note what the player kills, forgetting it when they leave the floor
+pub fn record_world_events(
+    mut facts: ResMut<WorldFacts>,
+    mut level_events: EventReader<LevelChanged>,
+    mut kill_events: EventReader<CreatureKilled>,
+) {
+    if level_events.read().count() > 0 {
+        facts.killed_this_floor.clear();
+    }
+    for event in kill_events.read() {
+        facts.killed_this_floor.push(event.name.to_lowercase());
+    }
+}
@@ -0,0 +1,168 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::boss::Boss;
+use crate::camera::CameraControl;
+use crate::components::{Animal, Npc, Tile};
+use crate::faction::Hostile;
+use crate::infighting::CreatureFaction;
+use crate::input::TILE_SIZE;
+use crate::map::{TileType, VIEWPORT_WIDTH};
+use crate::visibility::TileVisibility;
+
+// Markers start to show once a tile is drawn smaller than this many screen pixels...
+const MARKER_FADE_START: f32 = 20.0;
+// ...and are fully opaque once it's this small
+const MARKER_FADE_END: f32 = 12.0;
+// Size of a marker on screen, in pixels, however far out the camera is
+const MARKER_SIZE: f32 = 10.0;
+// Above everything else in the world
+const MARKER_Z: f32 = 40.0;
+
+/// What a marker stands for; each has its own color and shape so they can be told apart without color
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkerKind {
+    Npc,     // Blue square
+    Animal,  // Small yellow square
+    Monster, // Red diamond
+    Stairs,  // Large white diamond
+}
+
+impl MarkerKind {
+    fn color(&self) -> Color {
+        match self {
+            MarkerKind::Npc => Color::rgb(0.3, 0.6, 1.0),
+            MarkerKind::Animal => Color::rgb(1.0, 0.85, 0.2),
+            MarkerKind::Monster => Color::rgb(1.0, 0.25, 0.2),
+            MarkerKind::Stairs => Color::rgb(1.0, 1.0, 1.0),
+        }
+    }
+
+    // Relative to MARKER_SIZE
+    fn scale(&self) -> f32 {
+        match self {
+            MarkerKind::Animal => 0.7,
+            MarkerKind::Stairs => 1.3,
+            _ => 1.0,
+        }
+    }
+
+    fn is_diamond(&self) -> bool {
+        matches!(self, MarkerKind::Monster | MarkerKind::Stairs)
+    }
+}
+
+/// A dot drawn over something so it can be found when the camera is zoomed far out
+#[derive(Component, Debug)]
+pub struct ZoomMarker {
+    kind: MarkerKind,
+}
+
+// What marker a creature should carry
+fn creature_marker(boss: bool, hostile: bool, animal: bool, faction: Option<&CreatureFaction>) -> MarkerKind {
+    if boss || hostile || faction == Some(&CreatureFaction::Cultists) {
+        MarkerKind::Monster
+    } else if animal {
+        MarkerKind::Animal
+    } else {
+        MarkerKind::Npc
+    }
+}
+
+fn spawn_marker(commands: &mut Commands, entity: Entity, kind: MarkerKind) {
+    let rotation = if kind.is_diamond() { Quat::from_rotation_z(std::f32::consts::FRAC_PI_4) } else { Quat::IDENTITY };
+    commands.entity(entity).with_children(|parent| {
+        parent.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: kind.color().with_a(0.0),
+                    custom_size: Some(Vec2::splat(MARKER_SIZE * kind.scale())),
+                    ..default()
+                },
+                transform: Transform::from_xyz(0.0, 0.0, MARKER_Z).with_rotation(rotation),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            ZoomMarker { kind },
+        ));
+    });
+}
+
+// System to give every NPC, animal, monster and staircase a marker, swapping it when what it marks changes
+pub fn attach_zoom_markers(
+    mut commands: Commands,
+    creatures: Query<(Entity, Option<&Children>, Option<&Boss>, Option<&Hostile>, Option<&Animal>, Option<&CreatureFaction>), Or<(With<Npc>, With<Animal>, With<Boss>)>>,
+    tiles: Query<(Entity, Option<&Children>, &Tile)>,
+    markers: Query<(Entity, &ZoomMarker, &Parent)>,
+) {
+    // Markers whose owner is gone, e.g. despawned without its children
+    for (marker, _, parent) in markers.iter() {
+        if !creatures.contains(parent.get()) && !tiles.contains(parent.get()) {
+            commands.entity(marker).despawn_recursive();
+        }
+    }
+
+    // Keep exactly the wanted marker under an entity
+    let mut mark = |entity: Entity, children: Option<&Children>, wanted: Option<MarkerKind>| {
+        let mut has_wanted = false;
+        for &child in children.map_or(&[][..], |children| &children[..]) {
+            if let Ok((marker, zoom_marker, _)) = markers.get(child) {
+                if Some(zoom_marker.kind) == wanted && !has_wanted {
+                    has_wanted = true;
+                } else {
+                    commands.entity(marker).despawn_recursive();
+                }
+            }
+        }
+        if let (Some(kind), false) = (wanted, has_wanted) {
+            spawn_marker(&mut commands, entity, kind);
+        }
+    };
+
+    for (entity, children, boss, hostile, animal, faction) in creatures.iter() {
+        let wanted = creature_marker(boss.is_some(), hostile.is_some(), animal.is_some(), faction);
+        mark(entity, children, Some(wanted));
+    }
+    // Tile entities are reused across levels, so a tile can stop being stairs
+    for (entity, children, tile) in tiles.iter() {
+        let wanted = matches!(tile.tile_type, TileType::StairsDown | TileType::StairsUp).then_some(MarkerKind::Stairs);
+        if wanted.is_some() || children.is_some() {
+            mark(entity, children, wanted);
+        }
+    }
+}
+
+// System to fade markers in as the camera zooms out past where sprites can be made out, keeping them a fixed size on screen
+pub fn update_zoom_markers(
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<&OrthographicProjection, With<CameraControl>>,
+    parents: Query<(Option<&TileVisibility>, &GlobalTransform)>,
+    mut markers: Query<(&Parent, &mut Sprite, &mut Transform, &mut Visibility), With<ZoomMarker>>,
+) {
+    let window = if let Ok(window) = window_query.get_single() { window } else { return; };
+    let projection = if let Ok(projection) = camera_query.get_single() { projection } else { return; };
+
+    // How big one tile is on screen right now
+    let tile_pixels = window.width() / (VIEWPORT_WIDTH as f32 * projection.scale.max(0.001));
+    let opacity = ((MARKER_FADE_START - tile_pixels) / (MARKER_FADE_START - MARKER_FADE_END)).clamp(0.0, 1.0);
+    // World units per screen pixel, so the marker stays MARKER_SIZE pixels across
+    let pixel = TILE_SIZE / tile_pixels;
+
+    for (parent, mut sprite, mut transform, mut visibility) in markers.iter_mut() {
+        let (tile_visibility, parent_transform) = if let Ok(parent) = parents.get(parent.get()) { parent } else { continue; };
+        // Stairs stay secret until they've been seen
+        let seen = tile_visibility.map_or(true, |tile| tile.previously_seen);
+        let shown = opacity > 0.0 && seen;
+        let wanted = if shown { Visibility::Inherited } else { Visibility::Hidden };
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+        if !shown {
+            continue;
+        }
+        sprite.color.set_a(opacity);
+        // Undo the parent's own scale (NPCs wiggle) so every marker is the same size
+        let parent_scale = parent_transform.compute_transform().scale.truncate().max(Vec2::splat(0.001));
+        transform.scale = (Vec2::splat(pixel) / parent_scale).extend(1.0);
+    }
+}